@@ -110,6 +110,18 @@ fn main() {
     writeln!(out, "}}").unwrap();
     writeln!(out).unwrap();
 
+    // block_name_to_id
+    writeln!(out, "/// Map block name to its block registry ID (used by Block Action packets).").unwrap();
+    writeln!(out, "pub fn block_name_to_id(name: &str) -> Option<i32> {{").unwrap();
+    writeln!(out, "    match name {{").unwrap();
+    for b in &blocks {
+        writeln!(out, "        \"{}\" => Some({}),", b.name, b.id).unwrap();
+    }
+    writeln!(out, "        _ => None,").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+    writeln!(out).unwrap();
+
     // item_name_to_id
     writeln!(out, "/// Map item name to item registry ID.").unwrap();
     writeln!(
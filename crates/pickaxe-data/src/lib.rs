@@ -1,5 +1,7 @@
 include!(concat!(env!("OUT_DIR"), "/generated.rs"));
 
+pub mod registries;
+
 /// Returns the fuel burn time in ticks for the given item, or None if it is not a fuel.
 pub fn fuel_burn_time(item_id: i32) -> Option<i16> {
     let name = item_id_to_name(item_id)?;
@@ -109,6 +111,23 @@ pub fn smelting_result(item_id: i32) -> Option<(i32, i16)> {
     Some((result_id, cook_time))
 }
 
+/// Campfire recipe: (cooked item ID, cook ticks). Campfires only cook food, slowly.
+pub fn campfire_cooking_result(item_id: i32) -> Option<(i32, i32)> {
+    let result_name = match item_id_to_name(item_id)? {
+        "beef" => "cooked_beef",
+        "porkchop" => "cooked_porkchop",
+        "chicken" => "cooked_chicken",
+        "mutton" => "cooked_mutton",
+        "rabbit" => "cooked_rabbit",
+        "cod" => "cooked_cod",
+        "salmon" => "cooked_salmon",
+        "potato" => "baked_potato",
+        "kelp" => "dried_kelp",
+        _ => return None,
+    };
+    Some((item_name_to_id(result_name)?, 600))
+}
+
 /// Food properties for edible items.
 pub struct FoodProperties {
     pub nutrition: i32,
@@ -1423,6 +1442,11 @@ pub fn fire_age(state_id: i32) -> i32 {
     }
 }
 
+/// Blocks fire burns on forever, regardless of age, fuel or rain (overworld `infiniburn` tag).
+pub fn is_infiniburn(name: &str) -> bool {
+    matches!(name, "netherrack" | "magma_block")
+}
+
 /// Get flammability data for a block: (ignite_odds, burn_odds).
 /// ignite_odds = how easily fire can spread TO this block (0-60).
 /// burn_odds = how quickly fire consumes this block (0-100).
@@ -1600,6 +1624,11 @@ pub fn is_lever_powered(state_id: i32) -> bool {
     (state_id - 5626) % 2 == 0
 }
 
+/// Check if a block state is a lectern emitting its page-turn pulse.
+pub fn is_lectern_powered(state_id: i32) -> bool {
+    block_state_to_name(state_id) == Some("lectern") && block_property(state_id, "powered") == Some("true")
+}
+
 /// Check if a block state is any powered button.
 pub fn is_button_powered(state_id: i32) -> bool {
     let name = block_state_to_name(state_id).unwrap_or("");
@@ -1626,6 +1655,8 @@ pub fn block_power_output(state_id: i32) -> i32 {
     if is_lever_powered(state_id) { return 15; }
     // Powered button
     if is_button_powered(state_id) { return 15; }
+    // Lectern during a page turn
+    if is_lectern_powered(state_id) { return 15; }
     // Lit redstone torch
     if is_redstone_torch(state_id) && redstone_torch_is_lit(state_id) { return 15; }
     // Redstone block (always outputs 15)
@@ -1973,7 +2004,45 @@ pub fn is_piston_destroyable(state_id: i32) -> bool {
 
 // === Mob Data ===
 
+/// Entity type registry for MC 1.21.1, indexed by protocol entity type ID.
+pub const ENTITY_TYPES: [&str; 130] = [
+    "allay", "area_effect_cloud", "armadillo", "armor_stand", "arrow", "axolotl", "bat", "bee",
+    "blaze", "block_display", "boat", "bogged", "breeze", "breeze_wind_charge", "camel", "cat",
+    "cave_spider", "chest_boat", "chest_minecart", "chicken", "cod", "command_block_minecart",
+    "cow", "creeper", "dolphin", "donkey", "dragon_fireball", "drowned", "egg", "elder_guardian",
+    "end_crystal", "ender_dragon", "ender_pearl", "enderman", "endermite", "evoker", "evoker_fangs",
+    "experience_bottle", "experience_orb", "eye_of_ender", "falling_block", "firework_rocket",
+    "fox", "frog", "furnace_minecart", "ghast", "giant", "glow_item_frame", "glow_squid", "goat",
+    "guardian", "hoglin", "hopper_minecart", "horse", "husk", "illusioner", "interaction",
+    "iron_golem", "item", "item_display", "item_frame", "ominous_item_spawner", "fireball",
+    "leash_knot", "lightning_bolt", "llama", "llama_spit", "magma_cube", "marker", "minecart",
+    "mooshroom", "mule", "ocelot", "painting", "panda", "parrot", "phantom", "pig", "piglin",
+    "piglin_brute", "pillager", "polar_bear", "potion", "pufferfish", "rabbit", "ravager", "salmon",
+    "sheep", "shulker", "shulker_bullet", "silverfish", "skeleton", "skeleton_horse", "slime",
+    "small_fireball", "sniffer", "snowball", "snow_golem", "spawner_minecart", "spectral_arrow",
+    "spider", "squid", "stray", "strider", "tadpole", "text_display", "tnt", "tnt_minecart",
+    "trader_llama", "trident", "tropical_fish", "turtle", "vex", "villager", "vindicator",
+    "wandering_trader", "warden", "wind_charge", "witch", "wither", "wither_skeleton",
+    "wither_skull", "wolf", "zoglin", "zombie", "zombie_horse", "zombie_villager",
+    "zombified_piglin", "player", "fishing_bobber",
+];
+
+/// Returns the entity type name for a protocol entity type ID.
+pub fn entity_type_name(type_id: i32) -> Option<&'static str> {
+    usize::try_from(type_id).ok().and_then(|i| ENTITY_TYPES.get(i)).copied()
+}
+
+/// Reverse lookup: entity type name → protocol entity type ID.
+pub fn entity_name_to_type(name: &str) -> Option<i32> {
+    ENTITY_TYPES.iter().position(|n| *n == name).map(|i| i as i32)
+}
+
 /// Mob type constants (protocol entity type IDs for MC 1.21.1).
+pub const ENTITY_ARMOR_STAND: i32 = 3;
+pub const ENTITY_BLOCK_DISPLAY: i32 = 9;
+pub const ENTITY_ITEM: i32 = 58;
+pub const ENTITY_ITEM_DISPLAY: i32 = 59;
+pub const ENTITY_TEXT_DISPLAY: i32 = 105;
 pub const ENTITY_TNT: i32 = 106;
 
 pub const MOB_BAT: i32 = 6;
@@ -2331,6 +2400,20 @@ mod tests {
         assert_eq!(block_name_to_default_state("nonexistent"), None);
     }
 
+    #[test]
+    fn test_enchantment_supports_item() {
+        let sharpness = enchantment_name_to_id("sharpness").unwrap();
+        assert!(enchantment_supports_item(sharpness, "diamond_sword"));
+        assert!(enchantment_supports_item(sharpness, "iron_axe"));
+        assert!(!enchantment_supports_item(sharpness, "bow"));
+        let feather_falling = enchantment_name_to_id("feather_falling").unwrap();
+        assert!(enchantment_supports_item(feather_falling, "leather_boots"));
+        assert!(!enchantment_supports_item(feather_falling, "iron_helmet"));
+        let mending = enchantment_name_to_id("mending").unwrap();
+        assert!(enchantment_supports_item(mending, "elytra"));
+        assert!(!enchantment_supports_item(mending, "stone"));
+    }
+
     #[test]
     fn test_item_lookups() {
         assert_eq!(item_name_to_id("stone"), Some(1));
@@ -2358,6 +2441,15 @@ mod tests {
         assert_eq!(item_id_to_name(0), Some("air"));
     }
 
+    #[test]
+    fn test_entity_types() {
+        assert_eq!(entity_type_name(ENTITY_ARMOR_STAND), Some("armor_stand"));
+        assert_eq!(entity_type_name(ENTITY_BLOCK_DISPLAY), Some("block_display"));
+        assert_eq!(entity_type_name(ENTITY_ITEM_DISPLAY), Some("item_display"));
+        assert_eq!(entity_type_name(ENTITY_TEXT_DISPLAY), Some("text_display"));
+        assert_eq!(entity_name_to_type("tnt"), Some(ENTITY_TNT));
+    }
+
     #[test]
     fn test_stack_size() {
         assert_eq!(item_id_to_stack_size(1), Some(64));
@@ -2598,6 +2690,111 @@ mod tests {
         assert!(!mob_is_ranged(MOB_ZOMBIE));
         assert!(mob_is_explosive(MOB_CREEPER));
     }
+
+    #[test]
+    fn test_block_support() {
+        assert!(needs_support("torch"));
+        assert!(needs_support("white_carpet"));
+        assert!(needs_support("oak_wall_sign"));
+        assert!(!needs_support("stone"));
+
+        // Standing torch rests on the block below
+        assert_eq!(support_offset(2355), Some((0, -1, 0)));
+        // wall_torch facing=north (2356) hangs off the block to the south
+        assert_eq!(support_offset(2356), Some((0, 0, 1)));
+        // ladder facing=east (4660) hangs off the block to the west
+        assert_eq!(support_offset(4660), Some((-1, 0, 0)));
+        assert_eq!(support_offset(1), None);
+
+        assert!(can_support(1, "torch"));
+        assert!(!can_support(0, "torch"));
+        assert!(!can_support(WATER_SOURCE, "ladder"));
+        assert!(!can_support(2355, "torch"));
+        // Carpets sit on anything, including other carpets
+        assert!(can_support(10728, "white_carpet"));
+
+        assert!(has_collision("stone"));
+        assert!(!has_collision("torch"));
+        assert!(!has_collision("oak_sign"));
+    }
+
+    #[test]
+    fn test_waterlogging() {
+        let slab = block_name_with_properties_to_state("oak_slab", &[("type", "bottom"), ("waterlogged", "false")]).unwrap();
+        assert!(!is_waterlogged(slab));
+        let wet_slab = set_waterlogged(slab, true).unwrap();
+        assert_ne!(wet_slab, slab);
+        assert!(is_waterlogged(wet_slab));
+        assert_eq!(set_waterlogged(wet_slab, false), Some(slab));
+
+        assert!(is_waterlogged(block_name_to_default_state("seagrass").unwrap()));
+        assert!(!is_waterlogged(1));
+        assert!(!is_waterlogged(WATER_SOURCE));
+        assert_eq!(set_waterlogged(1, true), None);
+    }
+
+    #[test]
+    fn test_cauldron_states() {
+        let empty = block_name_to_default_state("cauldron").unwrap();
+        assert_eq!(cauldron_contents(empty), Some((CauldronContents::Empty, 0)));
+        assert_eq!(cauldron_contents(1), None);
+
+        let water2 = cauldron_state(CauldronContents::Water, 2);
+        assert_eq!(cauldron_contents(water2), Some((CauldronContents::Water, 2)));
+        assert_eq!(cauldron_state(CauldronContents::Water, 0), empty);
+        assert_eq!(cauldron_state(CauldronContents::PowderSnow, 5), cauldron_state(CauldronContents::PowderSnow, 3));
+
+        let lava = cauldron_state(CauldronContents::Lava, 3);
+        assert_eq!(block_state_to_name(lava).unwrap(), "lava_cauldron");
+        assert_eq!(cauldron_contents(lava), Some((CauldronContents::Lava, 3)));
+
+        assert_eq!(cauldron_washed_item("red_shulker_box"), Some(CauldronWash::Into("shulker_box")));
+        assert_eq!(cauldron_washed_item("shulker_box"), None);
+        assert_eq!(cauldron_washed_item("light_blue_banner"), Some(CauldronWash::BannerLayer));
+        assert_eq!(cauldron_washed_item("loom"), None);
+        assert_eq!(dye_color_id("light_blue"), Some(3));
+        assert_eq!(dye_color_name(15), Some("black"));
+        assert!(is_dyeable_item("leather_boots"));
+    }
+
+    #[test]
+    fn test_block_property() {
+        let bell = block_name_to_default_state("bell").unwrap();
+        assert_eq!(block_property(bell, "powered"), Some("false"));
+        let powered = set_block_property(bell, "powered", "true").unwrap();
+        assert_eq!(block_property(powered, "powered"), Some("true"));
+        assert_eq!(block_property(powered, "attachment"), block_property(bell, "attachment"));
+        assert_eq!(set_block_property(block_name_to_default_state("stone").unwrap(), "powered", "true"), None);
+        assert!(block_name_to_id("bell").is_some());
+    }
+
+    #[test]
+    fn test_campfire_cooking() {
+        let beef = item_name_to_id("beef").unwrap();
+        assert_eq!(campfire_cooking_result(beef), Some((item_name_to_id("cooked_beef").unwrap(), 600)));
+        assert_eq!(campfire_cooking_result(item_name_to_id("raw_iron").unwrap()), None);
+    }
+
+    #[test]
+    fn test_dead_coral() {
+        assert!(is_live_coral("brain_coral_wall_fan"));
+        assert!(!is_live_coral("dead_brain_coral_wall_fan"));
+        assert!(is_coral_block("tube_coral_block"));
+        assert!(!is_coral_block("tube_coral"));
+
+        let block = block_name_to_default_state("fire_coral_block").unwrap();
+        assert_eq!(block_state_to_name(dead_coral_state(block).unwrap()), Some("dead_fire_coral_block"));
+
+        let fan = block_name_with_properties_to_state(
+            "horn_coral_wall_fan",
+            &[("facing", "east"), ("waterlogged", "true")],
+        ).unwrap();
+        let (name, props) = block_state_to_properties(dead_coral_state(fan).unwrap()).unwrap();
+        assert_eq!(name, "dead_horn_coral_wall_fan");
+        assert!(props.contains(&("facing", "east")));
+        assert!(props.contains(&("waterlogged", "false")));
+        assert_eq!(dead_coral_state(1), None);
+    }
 }
 
 // ── Status Effects ───────────────────────────────────────────────────
@@ -2682,8 +2879,90 @@ pub fn effect_id_to_name(id: i32) -> Option<&'static str> {
     }
 }
 
+// ── Attributes ───────────────────────────────────────────────────────
+
+/// Attribute names in MC 1.21.1 registry order; the index is the registry ID.
+const ATTRIBUTES: [&str; 31] = [
+    "generic.armor",
+    "generic.armor_toughness",
+    "generic.attack_damage",
+    "generic.attack_knockback",
+    "generic.attack_speed",
+    "player.block_break_speed",
+    "player.block_interaction_range",
+    "generic.burning_time",
+    "generic.explosion_knockback_resistance",
+    "player.entity_interaction_range",
+    "generic.fall_damage_multiplier",
+    "generic.flying_speed",
+    "generic.follow_range",
+    "generic.gravity",
+    "generic.jump_strength",
+    "generic.knockback_resistance",
+    "generic.luck",
+    "generic.max_absorption",
+    "generic.max_health",
+    "player.mining_efficiency",
+    "generic.movement_efficiency",
+    "generic.movement_speed",
+    "generic.oxygen_bonus",
+    "generic.safe_fall_distance",
+    "generic.scale",
+    "player.sneaking_speed",
+    "zombie.spawn_reinforcements",
+    "generic.step_height",
+    "player.submerged_mining_speed",
+    "player.sweeping_damage_ratio",
+    "generic.water_movement_efficiency",
+];
+
+/// Returns the registry ID for an attribute name like `generic.attack_damage`.
+pub fn attribute_name_to_id(name: &str) -> Option<i32> {
+    ATTRIBUTES.iter().position(|a| *a == name).map(|i| i as i32)
+}
+
+/// Returns the attribute name for a registry ID.
+pub fn attribute_id_to_name(id: i32) -> Option<&'static str> {
+    usize::try_from(id).ok().and_then(|i| ATTRIBUTES.get(i)).copied()
+}
+
 // ── Potions ──────────────────────────────────────────────────────────
 
+/// Potion names in MC 1.21.1 registry order, as the `potion_contents`
+/// component sends them. This differs from the potion type index below,
+/// which has no turtle master or 1.21 potions.
+const POTION_REGISTRY: [&str; 46] = [
+    "water", "mundane", "thick", "awkward",
+    "night_vision", "long_night_vision",
+    "invisibility", "long_invisibility",
+    "leaping", "long_leaping", "strong_leaping",
+    "fire_resistance", "long_fire_resistance",
+    "swiftness", "long_swiftness", "strong_swiftness",
+    "slowness", "long_slowness", "strong_slowness",
+    "turtle_master", "long_turtle_master", "strong_turtle_master",
+    "water_breathing", "long_water_breathing",
+    "healing", "strong_healing",
+    "harming", "strong_harming",
+    "poison", "long_poison", "strong_poison",
+    "regeneration", "long_regeneration", "strong_regeneration",
+    "strength", "long_strength", "strong_strength",
+    "weakness", "long_weakness",
+    "luck",
+    "slow_falling", "long_slow_falling",
+    "wind_charged", "weaving", "oozing", "infested",
+];
+
+/// Returns the potion registry ID for a potion name.
+pub fn potion_registry_id(name: &str) -> Option<i32> {
+    POTION_REGISTRY.iter().position(|p| *p == name).map(|i| i as i32)
+}
+
+/// Returns the potion name for a potion registry ID.
+pub fn potion_registry_name(id: i32) -> Option<&'static str> {
+    usize::try_from(id).ok().and_then(|i| POTION_REGISTRY.get(i)).copied()
+}
+
+
 /// A potion effect entry: (effect_id, duration_ticks, amplifier).
 pub struct PotionEffect {
     pub effect_id: i32,
@@ -3069,6 +3348,43 @@ pub fn enchantment_max_level(id: i32) -> i32 {
     }
 }
 
+/// Returns true if the enchantment can be applied to the item (vanilla 1.21
+/// `supported_items`, as checked by /enchant).
+pub fn enchantment_supports_item(id: i32, item_name: &str) -> bool {
+    let head = item_name.ends_with("_helmet");
+    let chest = item_name.ends_with("_chestplate");
+    let legs = item_name.ends_with("_leggings");
+    let feet = item_name.ends_with("_boots");
+    let armor = head || chest || legs || feet;
+    let sword = item_name.ends_with("_sword");
+    let axe = is_axe(item_name);
+    let mining = item_name.ends_with("_pickaxe") || item_name.ends_with("_shovel") || axe || is_hoe(item_name);
+    let durability = item_max_durability(item_name) > 0
+        || matches!(item_name, "elytra" | "mace" | "brush" | "carrot_on_a_stick" | "warped_fungus_on_a_stick");
+    let head_slot = item_name == "carved_pumpkin" || item_name.ends_with("_skull") || item_name.ends_with("_head");
+    match id {
+        0 | 1 | 3 | 4 | 7 => armor,       // protection types, thorns
+        2 | 8 | 9 | 11 => feet,           // feather_falling, depth_strider, frost_walker, soul_speed
+        5 | 6 => head,                    // respiration, aqua_affinity
+        10 => armor || head_slot || item_name == "elytra", // binding_curse
+        12 => legs,                       // swift_sneak
+        13 => sword || axe,               // sharpness
+        14 | 15 => sword || axe || item_name == "mace", // smite, bane_of_arthropods
+        16 | 18 | 19 => sword,            // knockback, looting, sweeping_edge
+        17 => sword || item_name == "mace", // fire_aspect
+        20 => mining || item_name == "shears", // efficiency
+        21 | 23 => mining,                // silk_touch, fortune
+        22 | 37 => durability,            // unbreaking, mending
+        24..=27 => item_name == "bow",
+        28 | 29 => item_name == "fishing_rod",
+        30..=33 => item_name == "trident",
+        34..=36 => item_name == "crossbow",
+        38 => durability || head_slot || item_name == "compass", // vanishing_curse
+        39..=41 => item_name == "mace",   // density, breach, wind_burst
+        _ => false,
+    }
+}
+
 /// Returns the anvil XP cost multiplier per level for the given enchantment.
 /// Returns true if the given block name is a standing sign (any wood type).
 pub fn is_standing_sign(name: &str) -> bool {
@@ -3219,3 +3535,258 @@ pub fn enchantment_anvil_cost(id: i32) -> i32 {
         _ => 1,
     }
 }
+
+// === Block Support ===
+
+/// Returns true if the block can't stand on its own and pops off when the
+/// block it is attached to goes away (torches, ladders, carpets, signs).
+pub fn needs_support(name: &str) -> bool {
+    matches!(name,
+        "torch" | "wall_torch" | "soul_torch" | "soul_wall_torch"
+        | "redstone_torch" | "redstone_wall_torch" | "ladder"
+    ) || name.ends_with("_carpet") || is_any_sign(name)
+}
+
+/// Returns true if the block hangs off the side of another block rather than sitting on top.
+pub fn is_wall_attachment(name: &str) -> bool {
+    matches!(name, "wall_torch" | "soul_wall_torch" | "redstone_wall_torch" | "ladder")
+        || is_wall_sign(name)
+}
+
+/// Offset from an attachment block to the block holding it up.
+/// Wall attachments hang off the block behind their `facing`; everything else
+/// rests on the block below. Returns None for blocks that don't need support.
+pub fn support_offset(state_id: i32) -> Option<(i32, i32, i32)> {
+    let (name, props) = block_state_to_properties(state_id)?;
+    if !needs_support(name) {
+        return None;
+    }
+    if !is_wall_attachment(name) {
+        return Some((0, -1, 0));
+    }
+    let facing = props.iter().find(|(k, _)| *k == "facing").map(|(_, v)| *v)?;
+    match facing {
+        "north" => Some((0, 0, 1)),
+        "south" => Some((0, 0, -1)),
+        "west" => Some((1, 0, 0)),
+        "east" => Some((-1, 0, 0)),
+        _ => None,
+    }
+}
+
+/// Check if `support_state` can hold up an attachment named `attachment_name`.
+/// Carpets only need something non-empty underneath; torches, ladders and signs
+/// need a solid face (not air, fluid, fire, plants, or another attachment).
+pub fn can_support(support_state: i32, attachment_name: &str) -> bool {
+    let name = match block_state_to_name(support_state) {
+        Some(n) => n,
+        None => return false,
+    };
+    if matches!(name, "air" | "cave_air" | "void_air") || is_fluid(support_state) || is_fire(support_state) {
+        return false;
+    }
+    if attachment_name.ends_with("_carpet") {
+        return true;
+    }
+    !needs_support(name) && is_solid_for_fluid(name)
+}
+
+/// Check if a block occupies space that entities collide with, so it can't be
+/// placed inside a player or mob.
+pub fn has_collision(name: &str) -> bool {
+    is_solid_for_fluid(name)
+        && !is_any_sign(name)
+        && !name.ends_with("_button")
+        && !name.ends_with("_pressure_plate")
+        && name != "lever"
+}
+
+// === Waterlogging ===
+
+/// Blocks that are always filled with water and have no `waterlogged` property.
+fn is_always_waterlogged(name: &str) -> bool {
+    matches!(name, "kelp" | "kelp_plant" | "seagrass" | "tall_seagrass" | "bubble_column")
+}
+
+/// Lookup table of waterlogged states, built once on first use.
+/// The fluid tick checks every loaded block, so per-call property parsing is too slow.
+fn waterlogged_table() -> &'static [bool] {
+    static TABLE: std::sync::OnceLock<Vec<bool>> = std::sync::OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = Vec::new();
+        let mut state = 0;
+        while let Some((name, props)) = block_state_to_properties(state) {
+            table.push(
+                is_always_waterlogged(name)
+                    || props.iter().any(|(k, v)| *k == "waterlogged" && *v == "true"),
+            );
+            state += 1;
+        }
+        table
+    })
+}
+
+/// Check if a block state holds water without being a water block itself
+/// (waterlogged slabs, stairs, fences, etc., plus kelp and seagrass).
+/// Waterlogged blocks behave like water sources for flow.
+pub fn is_waterlogged(state_id: i32) -> bool {
+    usize::try_from(state_id)
+        .ok()
+        .and_then(|i| waterlogged_table().get(i).copied())
+        .unwrap_or(false)
+}
+
+/// Return the same block with its `waterlogged` property changed.
+/// Returns None if the block can't be waterlogged.
+pub fn set_waterlogged(state_id: i32, waterlogged: bool) -> Option<i32> {
+    set_block_property(state_id, "waterlogged", if waterlogged { "true" } else { "false" })
+}
+
+/// Get the value of one property of a block state.
+pub fn block_property(state_id: i32, key: &str) -> Option<&'static str> {
+    let (_, props) = block_state_to_properties(state_id)?;
+    props.into_iter().find(|(k, _)| *k == key).map(|(_, v)| v)
+}
+
+/// Return the same block with one property changed, keeping the others.
+/// Returns None if the block doesn't have that property.
+pub fn set_block_property(state_id: i32, key: &str, value: &str) -> Option<i32> {
+    let (name, props) = block_state_to_properties(state_id)?;
+    if !props.iter().any(|(k, _)| *k == key) {
+        return None;
+    }
+    let props: Vec<(&str, &str)> = props
+        .into_iter()
+        .map(|(k, v)| if k == key { (k, value) } else { (k, v) })
+        .collect();
+    block_name_with_properties_to_state(name, &props)
+}
+
+// === Cauldrons ===
+
+/// What a cauldron is filled with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CauldronContents {
+    Empty,
+    Water,
+    Lava,
+    PowderSnow,
+}
+
+/// Get a cauldron's contents and fill level (1-3; lava is always full).
+/// Returns None if the block is not a cauldron.
+pub fn cauldron_contents(state_id: i32) -> Option<(CauldronContents, i32)> {
+    let (name, props) = block_state_to_properties(state_id)?;
+    let level = || {
+        props.iter()
+            .find(|(k, _)| *k == "level")
+            .and_then(|(_, v)| v.parse().ok())
+            .unwrap_or(3)
+    };
+    match name {
+        "cauldron" => Some((CauldronContents::Empty, 0)),
+        "water_cauldron" => Some((CauldronContents::Water, level())),
+        "lava_cauldron" => Some((CauldronContents::Lava, 3)),
+        "powder_snow_cauldron" => Some((CauldronContents::PowderSnow, level())),
+        _ => None,
+    }
+}
+
+/// Get the cauldron block state for the given contents and level.
+/// A level of 0 (or less) gives an empty cauldron; levels above 3 are clamped.
+pub fn cauldron_state(contents: CauldronContents, level: i32) -> i32 {
+    let level = level.min(3);
+    let state = match contents {
+        _ if level <= 0 => block_name_to_default_state("cauldron"),
+        CauldronContents::Empty => block_name_to_default_state("cauldron"),
+        CauldronContents::Lava => block_name_to_default_state("lava_cauldron"),
+        CauldronContents::Water => {
+            block_name_with_properties_to_state("water_cauldron", &[("level", &level.to_string())])
+        }
+        CauldronContents::PowderSnow => {
+            block_name_with_properties_to_state("powder_snow_cauldron", &[("level", &level.to_string())])
+        }
+    };
+    state.unwrap_or(0)
+}
+
+/// Items that can be dyed and are washed clean in a water cauldron.
+pub fn is_dyeable_item(item_name: &str) -> bool {
+    matches!(
+        item_name,
+        "leather_helmet" | "leather_chestplate" | "leather_leggings" | "leather_boots"
+            | "leather_horse_armor" | "wolf_armor"
+    )
+}
+
+/// Dye colors, in network ID order.
+const DYE_COLORS: [&str; 16] = [
+    "white", "orange", "magenta", "light_blue", "yellow", "lime", "pink", "gray", "light_gray", "cyan",
+    "purple", "blue", "brown", "green", "red", "black",
+];
+
+/// Dye color name (like `light_blue`) to its network ID.
+pub fn dye_color_id(name: &str) -> Option<i32> {
+    DYE_COLORS.iter().position(|&c| c == name).map(|i| i as i32)
+}
+
+/// Dye color network ID to its name.
+pub fn dye_color_name(id: i32) -> Option<&'static str> {
+    DYE_COLORS.get(usize::try_from(id).ok()?).copied()
+}
+
+/// What washing an item in a water cauldron does to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CauldronWash {
+    /// The item becomes this one, for items whose color is part of the item
+    /// itself (dyed shulker boxes become a plain shulker box).
+    Into(&'static str),
+    /// A banner loses its last pattern layer. Banners without patterns
+    /// aren't washed.
+    BannerLayer,
+}
+
+/// How a water cauldron washes this item, besides removing dye. Each wash
+/// costs one level of water.
+pub fn cauldron_washed_item(item_name: &str) -> Option<CauldronWash> {
+    if item_name.ends_with("_shulker_box") {
+        Some(CauldronWash::Into("shulker_box"))
+    } else if item_name.ends_with("_banner") {
+        Some(CauldronWash::BannerLayer)
+    } else {
+        None
+    }
+}
+
+// === Coral ===
+
+const CORAL_TYPES: [&str; 5] = ["tube", "brain", "bubble", "fire", "horn"];
+
+/// Check if a block is living coral (block, plant, fan or wall fan).
+pub fn is_live_coral(name: &str) -> bool {
+    CORAL_TYPES.iter().any(|t| {
+        name.strip_prefix(t).is_some_and(|rest| {
+            matches!(rest, "_coral_block" | "_coral" | "_coral_fan" | "_coral_wall_fan")
+        })
+    })
+}
+
+/// Check if a living coral is a full block (which only needs water next to it)
+/// rather than a plant or fan (which also count being waterlogged).
+pub fn is_coral_block(name: &str) -> bool {
+    is_live_coral(name) && name.ends_with("_coral_block")
+}
+
+/// The dead state for a living coral state: same shape and facing, no longer waterlogged.
+pub fn dead_coral_state(state_id: i32) -> Option<i32> {
+    let (name, props) = block_state_to_properties(state_id)?;
+    if !is_live_coral(name) {
+        return None;
+    }
+    let dead_name = format!("dead_{}", name);
+    let props: Vec<(&str, &str)> = props
+        .into_iter()
+        .map(|(k, v)| if k == "waterlogged" { (k, "false") } else { (k, v) })
+        .collect();
+    block_name_with_properties_to_state(&dead_name, &props)
+}
@@ -0,0 +1,335 @@
+use serde::Deserialize;
+
+/// An entry of `minecraft:dimension_type`. Missing fields in config take the
+/// overworld's values.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct DimensionType {
+    pub name: String,
+    pub has_skylight: bool,
+    pub has_ceiling: bool,
+    pub ultrawarm: bool,
+    pub natural: bool,
+    pub coordinate_scale: f64,
+    pub bed_works: bool,
+    pub respawn_anchor_works: bool,
+    pub min_y: i32,
+    pub height: i32,
+    pub logical_height: i32,
+    pub infiniburn: String,
+    pub effects: String,
+    pub ambient_light: f32,
+    pub piglin_safe: bool,
+    pub has_raids: bool,
+    pub monster_spawn_light_level: i32,
+    pub monster_spawn_block_light_limit: i32,
+}
+
+impl Default for DimensionType {
+    fn default() -> Self {
+        Self {
+            name: "minecraft:overworld".into(),
+            has_skylight: true,
+            has_ceiling: false,
+            ultrawarm: false,
+            natural: true,
+            coordinate_scale: 1.0,
+            bed_works: true,
+            respawn_anchor_works: false,
+            min_y: -64,
+            height: 384,
+            logical_height: 384,
+            infiniburn: "#minecraft:infiniburn_overworld".into(),
+            effects: "minecraft:overworld".into(),
+            ambient_light: 0.0,
+            piglin_safe: false,
+            has_raids: true,
+            monster_spawn_light_level: 0,
+            monster_spawn_block_light_limit: 0,
+        }
+    }
+}
+
+/// An entry of `minecraft:worldgen/biome`. Missing fields in config take
+/// plains' values.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Biome {
+    pub name: String,
+    pub has_precipitation: bool,
+    pub temperature: f32,
+    pub downfall: f32,
+    pub fog_color: i32,
+    pub water_color: i32,
+    pub water_fog_color: i32,
+    pub sky_color: i32,
+    pub grass_color: Option<i32>,
+    pub foliage_color: Option<i32>,
+}
+
+impl Default for Biome {
+    fn default() -> Self {
+        Self {
+            name: "minecraft:plains".into(),
+            has_precipitation: true,
+            temperature: 0.8,
+            downfall: 0.4,
+            fog_color: 12638463,
+            water_color: 4159204,
+            water_fog_color: 329011,
+            sky_color: 7907327,
+            grass_color: None,
+            foliage_color: None,
+        }
+    }
+}
+
+/// An entry of `minecraft:damage_type`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DamageType {
+    pub name: String,
+    pub message_id: String,
+    pub scaling: String,
+    pub exhaustion: f32,
+    pub effects: Option<String>,
+    pub death_message_type: Option<String>,
+}
+
+/// An entry of `minecraft:wolf_variant`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WolfVariant {
+    pub name: String,
+    pub wild_texture: String,
+    pub tame_texture: String,
+    pub angry_texture: String,
+    /// Biomes the variant spawns in.
+    pub biomes: Vec<String>,
+}
+
+/// An entry of `minecraft:banner_pattern`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BannerPattern {
+    pub name: String,
+    pub asset_id: String,
+    pub translation_key: String,
+}
+
+/// The data-driven registries sent to clients during configuration. Entries
+/// are sent in order, so an entry's index is its network ID.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Registries {
+    pub dimension_types: Vec<DimensionType>,
+    pub biomes: Vec<Biome>,
+    pub damage_types: Vec<DamageType>,
+    pub wolf_variants: Vec<WolfVariant>,
+    pub banner_patterns: Vec<BannerPattern>,
+}
+
+impl Default for Registries {
+    fn default() -> Self {
+        Self {
+            dimension_types: vec![DimensionType::default()],
+            biomes: vec![Biome::default()],
+            damage_types: vanilla_damage_types(),
+            wolf_variants: vanilla_wolf_variants(),
+            banner_patterns: vanilla_banner_patterns(),
+        }
+    }
+}
+
+impl Registries {
+    /// Add a dimension type, replacing one with the same name. Returns its ID.
+    pub fn add_dimension_type(&mut self, dimension_type: DimensionType) -> i32 {
+        upsert(&mut self.dimension_types, dimension_type, |d| &d.name)
+    }
+
+    /// Add a biome, replacing one with the same name. Returns its ID.
+    pub fn add_biome(&mut self, biome: Biome) -> i32 {
+        upsert(&mut self.biomes, biome, |b| &b.name)
+    }
+
+    pub fn dimension_type_id(&self, name: &str) -> Option<i32> {
+        self.dimension_types.iter().position(|d| d.name == name).map(|i| i as i32)
+    }
+
+    pub fn biome_id(&self, name: &str) -> Option<i32> {
+        self.biomes.iter().position(|b| b.name == name).map(|i| i as i32)
+    }
+}
+
+fn upsert<T>(entries: &mut Vec<T>, entry: T, name: impl Fn(&T) -> &String) -> i32 {
+    match entries.iter().position(|e| name(e) == name(&entry)) {
+        Some(index) => {
+            entries[index] = entry;
+            index as i32
+        }
+        None => {
+            entries.push(entry);
+            entries.len() as i32 - 1
+        }
+    }
+}
+
+fn damage(
+    name: &str,
+    message_id: &str,
+    scaling: &str,
+    exhaustion: f32,
+    effects: Option<&str>,
+    death_message_type: Option<&str>,
+) -> DamageType {
+    DamageType {
+        name: format!("minecraft:{}", name),
+        message_id: message_id.into(),
+        scaling: scaling.into(),
+        exhaustion,
+        effects: effects.map(Into::into),
+        death_message_type: death_message_type.map(Into::into),
+    }
+}
+
+/// Every vanilla damage type. The client refers to these by name, so none
+/// can be left out.
+pub fn vanilla_damage_types() -> Vec<DamageType> {
+    let s = "when_caused_by_living_non_player";
+    vec![
+        damage("arrow", "arrow", s, 0.1, None, None),
+        damage("bad_respawn_point", "badRespawnPoint", "always", 0.1, None, Some("intentional_game_design")),
+        damage("cactus", "cactus", s, 0.1, None, None),
+        damage("campfire", "inFire", s, 0.1, Some("burning"), None),
+        damage("cramming", "cramming", s, 0.0, None, None),
+        damage("dragon_breath", "dragonBreath", s, 0.0, None, None),
+        damage("drown", "drown", s, 0.0, Some("drowning"), None),
+        damage("dry_out", "dryout", s, 0.1, None, None),
+        damage("explosion", "explosion", "always", 0.1, None, None),
+        damage("fall", "fall", s, 0.0, None, Some("fall_variants")),
+        damage("falling_anvil", "anvil", s, 0.1, None, None),
+        damage("falling_block", "fallingBlock", s, 0.1, None, None),
+        damage("falling_stalactite", "fallingStalactite", s, 0.1, None, None),
+        damage("fireball", "fireball", s, 0.1, Some("burning"), None),
+        damage("fireworks", "fireworks", s, 0.1, None, None),
+        damage("fly_into_wall", "flyIntoWall", s, 0.0, None, None),
+        damage("freeze", "freeze", s, 0.0, Some("freezing"), None),
+        damage("generic", "generic", s, 0.0, None, None),
+        damage("generic_kill", "genericKill", s, 0.0, None, None),
+        damage("hot_floor", "hotFloor", s, 0.1, Some("burning"), None),
+        damage("in_fire", "inFire", s, 0.1, Some("burning"), None),
+        damage("in_wall", "inWall", s, 0.0, None, None),
+        damage("indirect_magic", "indirectMagic", s, 0.0, None, None),
+        damage("lava", "lava", s, 0.1, Some("burning"), None),
+        damage("lightning_bolt", "lightningBolt", s, 0.1, None, None),
+        damage("magic", "magic", s, 0.0, None, None),
+        damage("mob_attack", "mob", s, 0.1, None, None),
+        damage("mob_attack_no_aggro", "mob", s, 0.1, None, None),
+        damage("mob_projectile", "mob", s, 0.1, None, None),
+        damage("on_fire", "onFire", s, 0.0, Some("burning"), None),
+        damage("out_of_world", "outOfWorld", s, 0.0, None, None),
+        damage("outside_border", "outsideBorder", s, 0.0, None, None),
+        damage("player_attack", "player", s, 0.1, None, None),
+        damage("player_explosion", "explosion.player", "always", 0.1, None, None),
+        damage("sonic_boom", "sonic_boom", "always", 0.0, None, None),
+        damage("spit", "mob", s, 0.1, None, None),
+        damage("stalagmite", "stalagmite", s, 0.0, None, None),
+        damage("starve", "starve", s, 0.0, None, None),
+        damage("sting", "sting", s, 0.1, None, None),
+        damage("sweet_berry_bush", "sweetBerryBush", s, 0.1, Some("poking"), None),
+        damage("thorns", "thorns", s, 0.1, Some("thorns"), None),
+        damage("thrown", "thrown", s, 0.1, None, None),
+        damage("trident", "trident", s, 0.1, None, None),
+        damage("unattributed_fireball", "onFire", s, 0.1, Some("burning"), None),
+        damage("wind_charge", "mob", s, 0.1, None, None),
+        damage("wither", "wither", s, 0.0, None, None),
+        damage("wither_skull", "witherSkull", s, 0.1, None, None),
+    ]
+}
+
+/// The vanilla wolf variants with the biome each spawns in.
+pub fn vanilla_wolf_variants() -> Vec<WolfVariant> {
+    [
+        ("pale", "", "plains"),
+        ("ashen", "_ashen", "snowy_taiga"),
+        ("black", "_black", "old_growth_pine_taiga"),
+        ("chestnut", "_chestnut", "old_growth_spruce_taiga"),
+        ("rusty", "_rusty", "sparse_jungle"),
+        ("snowy", "_snowy", "grove"),
+        ("spotted", "_spotted", "savanna_plateau"),
+        ("striped", "_striped", "wooded_badlands"),
+        ("woods", "_woods", "forest"),
+    ]
+    .into_iter()
+    .map(|(name, suffix, biome)| WolfVariant {
+        name: format!("minecraft:{}", name),
+        wild_texture: format!("minecraft:textures/entity/wolf/wolf{}.png", suffix),
+        tame_texture: format!("minecraft:textures/entity/wolf/wolf{}_tame.png", suffix),
+        angry_texture: format!("minecraft:textures/entity/wolf/wolf{}_angry.png", suffix),
+        biomes: vec![format!("minecraft:{}", biome)],
+    })
+    .collect()
+}
+
+/// Names of the vanilla banner patterns, in registry order.
+const BANNER_PATTERNS: [&str; 43] = [
+    "base", "border", "bricks", "circle", "creeper", "cross", "curly_border", "diagonal_left",
+    "diagonal_right", "diagonal_up_left", "diagonal_up_right", "flow", "flower", "globe", "gradient",
+    "gradient_up", "guster", "half_horizontal", "half_horizontal_bottom", "half_vertical",
+    "half_vertical_right", "mojang", "piglin", "rhombus", "skull", "small_stripes", "square_bottom_left",
+    "square_bottom_right", "square_top_left", "square_top_right", "straight_cross", "stripe_bottom",
+    "stripe_center", "stripe_downleft", "stripe_downright", "stripe_left", "stripe_middle", "stripe_right",
+    "stripe_top", "triangle_bottom", "triangle_top", "triangles_bottom", "triangles_top",
+];
+
+/// The vanilla banner patterns, in registry order.
+pub fn vanilla_banner_patterns() -> Vec<BannerPattern> {
+    BANNER_PATTERNS
+        .into_iter()
+        .map(|name| BannerPattern {
+            name: format!("minecraft:{}", name),
+            asset_id: format!("minecraft:{}", name),
+            translation_key: format!("block.minecraft.banner.{}", name),
+        })
+        .collect()
+}
+
+/// Vanilla banner pattern name (without `minecraft:`) to its registry ID.
+pub fn banner_pattern_id(name: &str) -> Option<i32> {
+    BANNER_PATTERNS.iter().position(|&p| p == name).map(|i| i as i32)
+}
+
+/// Vanilla banner pattern registry ID to its name (without `minecraft:`).
+pub fn banner_pattern_name(id: i32) -> Option<&'static str> {
+    BANNER_PATTERNS.get(usize::try_from(id).ok()?).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_entries() {
+        let mut registries = Registries::default();
+        assert_eq!(registries.dimension_type_id("minecraft:overworld"), Some(0));
+
+        let void = DimensionType {
+            name: "pickaxe:void".into(),
+            has_skylight: false,
+            ..Default::default()
+        };
+        assert_eq!(registries.add_dimension_type(void.clone()), 1);
+        assert_eq!(registries.add_dimension_type(DimensionType { min_y: 0, ..void }), 1);
+        assert_eq!(registries.dimension_types[1].min_y, 0);
+
+        let desert = Biome {
+            name: "minecraft:desert".into(),
+            has_precipitation: false,
+            temperature: 2.0,
+            ..Default::default()
+        };
+        assert_eq!(registries.add_biome(desert), 1);
+        assert_eq!(registries.biome_id("minecraft:desert"), Some(1));
+        assert_eq!(registries.biome_id("minecraft:swamp"), None);
+
+        let flow = banner_pattern_id("flow").unwrap();
+        assert_eq!(registries.banner_patterns[flow as usize].name, "minecraft:flow");
+        assert_eq!(banner_pattern_name(flow), Some("flow"));
+    }
+}
@@ -1,3 +1,7 @@
 mod nbt;
+mod reader;
+mod snbt;
 
 pub use nbt::*;
+pub use reader::NbtLimits;
+pub use snbt::parse_snbt;
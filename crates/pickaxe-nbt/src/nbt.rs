@@ -1,5 +1,6 @@
+use crate::reader::{NbtLimits, NbtReader};
 use bytes::{BufMut, BytesMut};
-use std::io::{self, Cursor, Read};
+use std::io::{self, Read};
 
 /// NBT tag type IDs.
 pub const TAG_END: u8 = 0;
@@ -120,30 +121,37 @@ impl NbtValue {
 
     /// Read a named root tag from bytes. Returns (name, value).
     pub fn read_root_named(data: &[u8]) -> io::Result<(String, NbtValue)> {
-        let mut cursor = Cursor::new(data);
-        let tag_type = read_u8(&mut cursor)?;
-        if tag_type != TAG_COMPOUND {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Root must be compound",
-            ));
+        Self::read_root_named_from(data, NbtLimits::FILE)
+    }
+
+    /// Read a named root tag from a stream, e.g. straight out of a
+    /// `GzDecoder`, failing once `limits` are exceeded.
+    pub fn read_root_named_from(r: impl Read, limits: NbtLimits) -> io::Result<(String, NbtValue)> {
+        let mut reader = NbtReader::new(r, limits);
+        if reader.read_u8()? != TAG_COMPOUND {
+            return Err(reader.error("Root must be compound"));
         }
-        let name = read_nbt_string_r(&mut cursor)?;
-        let value = read_payload(&mut cursor, TAG_COMPOUND)?;
+        let name = reader.read_string()?;
+        let value = reader.read_payload(TAG_COMPOUND)?;
         Ok((name, value))
     }
 
     /// Read an unnamed root tag (network format).
     pub fn read_root_network(data: &[u8]) -> io::Result<NbtValue> {
-        let mut cursor = Cursor::new(data);
-        let tag_type = read_u8(&mut cursor)?;
-        if tag_type != TAG_COMPOUND {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Root must be compound",
-            ));
+        let mut reader = NbtReader::new(data, NbtLimits::NETWORK);
+        if reader.read_u8()? != TAG_COMPOUND {
+            return Err(reader.error("Root must be compound"));
         }
-        read_payload(&mut cursor, TAG_COMPOUND)
+        reader.read_payload(TAG_COMPOUND)
+    }
+
+    /// Read an unnamed root tag of any type (network format, e.g. a text
+    /// component that is a bare string). Returns the value and bytes consumed.
+    pub fn read_network_any(data: &[u8]) -> io::Result<(NbtValue, usize)> {
+        let mut reader = NbtReader::new(data, NbtLimits::NETWORK);
+        let tag_type = reader.read_u8()?;
+        let value = reader.read_payload(tag_type)?;
+        Ok((value, reader.position() as usize))
     }
 
     /// Get a named field from a compound tag.
@@ -154,6 +162,28 @@ impl NbtValue {
         }
     }
 
+    /// Set a named field in a compound tag, replacing any existing value.
+    /// Does nothing if this is not a compound.
+    pub fn set(&mut self, key: &str, value: NbtValue) {
+        if let NbtValue::Compound(entries) = self {
+            match entries.iter_mut().find(|(k, _)| k == key) {
+                Some(entry) => entry.1 = value,
+                None => entries.push((key.to_string(), value)),
+            }
+        }
+    }
+
+    /// Remove a named field from a compound tag, returning its value.
+    pub fn remove(&mut self, key: &str) -> Option<NbtValue> {
+        match self {
+            NbtValue::Compound(entries) => {
+                let index = entries.iter().position(|(k, _)| k == key)?;
+                Some(entries.remove(index).1)
+            }
+            _ => None,
+        }
+    }
+
     /// Get as i8.
     pub fn as_byte(&self) -> Option<i8> {
         match self {
@@ -202,6 +232,19 @@ impl NbtValue {
         }
     }
 
+    /// Get any numeric tag widened to f64 (SNBT input is loosely typed).
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            NbtValue::Byte(v) => Some(*v as f64),
+            NbtValue::Short(v) => Some(*v as f64),
+            NbtValue::Int(v) => Some(*v as f64),
+            NbtValue::Long(v) => Some(*v as f64),
+            NbtValue::Float(v) => Some(*v as f64),
+            NbtValue::Double(v) => Some(*v),
+            _ => None,
+        }
+    }
+
     /// Get as string slice.
     pub fn as_str(&self) -> Option<&str> {
         match self {
@@ -249,131 +292,6 @@ fn write_nbt_string(s: &str, buf: &mut BytesMut) {
     buf.put_slice(bytes);
 }
 
-// --- NBT Reader helpers ---
-
-fn read_u8(r: &mut impl Read) -> io::Result<u8> {
-    let mut buf = [0u8; 1];
-    r.read_exact(&mut buf)?;
-    Ok(buf[0])
-}
-
-fn read_i8(r: &mut impl Read) -> io::Result<i8> {
-    Ok(read_u8(r)? as i8)
-}
-
-fn read_i16(r: &mut impl Read) -> io::Result<i16> {
-    let mut buf = [0u8; 2];
-    r.read_exact(&mut buf)?;
-    Ok(i16::from_be_bytes(buf))
-}
-
-fn read_u16(r: &mut impl Read) -> io::Result<u16> {
-    let mut buf = [0u8; 2];
-    r.read_exact(&mut buf)?;
-    Ok(u16::from_be_bytes(buf))
-}
-
-fn read_i32(r: &mut impl Read) -> io::Result<i32> {
-    let mut buf = [0u8; 4];
-    r.read_exact(&mut buf)?;
-    Ok(i32::from_be_bytes(buf))
-}
-
-fn read_i64(r: &mut impl Read) -> io::Result<i64> {
-    let mut buf = [0u8; 8];
-    r.read_exact(&mut buf)?;
-    Ok(i64::from_be_bytes(buf))
-}
-
-fn read_f32(r: &mut impl Read) -> io::Result<f32> {
-    let mut buf = [0u8; 4];
-    r.read_exact(&mut buf)?;
-    Ok(f32::from_be_bytes(buf))
-}
-
-fn read_f64(r: &mut impl Read) -> io::Result<f64> {
-    let mut buf = [0u8; 8];
-    r.read_exact(&mut buf)?;
-    Ok(f64::from_be_bytes(buf))
-}
-
-fn read_nbt_string_r(r: &mut impl Read) -> io::Result<String> {
-    let len = read_u16(r)? as usize;
-    let mut buf = vec![0u8; len];
-    r.read_exact(&mut buf)?;
-    String::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
-}
-
-fn read_length(r: &mut impl Read) -> io::Result<usize> {
-    let raw = read_i32(r)?;
-    if raw < 0 {
-        return Err(io::Error::new(io::ErrorKind::InvalidData, "Negative length"));
-    }
-    Ok(raw as usize)
-}
-
-fn read_payload(r: &mut impl Read, tag_type: u8) -> io::Result<NbtValue> {
-    match tag_type {
-        TAG_BYTE => Ok(NbtValue::Byte(read_i8(r)?)),
-        TAG_SHORT => Ok(NbtValue::Short(read_i16(r)?)),
-        TAG_INT => Ok(NbtValue::Int(read_i32(r)?)),
-        TAG_LONG => Ok(NbtValue::Long(read_i64(r)?)),
-        TAG_FLOAT => Ok(NbtValue::Float(read_f32(r)?)),
-        TAG_DOUBLE => Ok(NbtValue::Double(read_f64(r)?)),
-        TAG_BYTE_ARRAY => {
-            let len = read_length(r)?;
-            let mut data = vec![0i8; len];
-            for v in &mut data {
-                *v = read_i8(r)?;
-            }
-            Ok(NbtValue::ByteArray(data))
-        }
-        TAG_STRING => Ok(NbtValue::String(read_nbt_string_r(r)?)),
-        TAG_LIST => {
-            let elem_type = read_u8(r)?;
-            let len = read_length(r)?;
-            let mut items = Vec::with_capacity(len);
-            for _ in 0..len {
-                items.push(read_payload(r, elem_type)?);
-            }
-            Ok(NbtValue::List(items))
-        }
-        TAG_COMPOUND => {
-            let mut entries = Vec::new();
-            loop {
-                let child_type = read_u8(r)?;
-                if child_type == TAG_END {
-                    break;
-                }
-                let name = read_nbt_string_r(r)?;
-                let value = read_payload(r, child_type)?;
-                entries.push((name, value));
-            }
-            Ok(NbtValue::Compound(entries))
-        }
-        TAG_INT_ARRAY => {
-            let len = read_length(r)?;
-            let mut data = Vec::with_capacity(len);
-            for _ in 0..len {
-                data.push(read_i32(r)?);
-            }
-            Ok(NbtValue::IntArray(data))
-        }
-        TAG_LONG_ARRAY => {
-            let len = read_length(r)?;
-            let mut data = Vec::with_capacity(len);
-            for _ in 0..len {
-                data.push(read_i64(r)?);
-            }
-            Ok(NbtValue::LongArray(data))
-        }
-        _ => Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            format!("Unknown tag type {}", tag_type),
-        )),
-    }
-}
-
 /// Helper macro for building compound tags.
 #[macro_export]
 macro_rules! nbt_compound {
@@ -453,6 +371,19 @@ mod tests {
         assert_eq!(parsed, nbt);
     }
 
+    #[test]
+    fn test_set_replaces_or_appends() {
+        let mut nbt = NbtValue::Compound(vec![("value".into(), NbtValue::Int(1))]);
+        nbt.set("value", NbtValue::Int(2));
+        nbt.set("extra", NbtValue::Byte(1));
+        assert_eq!(nbt.get("value"), Some(&NbtValue::Int(2)));
+        assert_eq!(nbt.get("extra"), Some(&NbtValue::Byte(1)));
+        assert_eq!(nbt, NbtValue::Compound(vec![
+            ("value".into(), NbtValue::Int(2)),
+            ("extra".into(), NbtValue::Byte(1)),
+        ]));
+    }
+
     #[test]
     fn test_roundtrip_empty_list() {
         let nbt = NbtValue::Compound(vec![("empty".into(), NbtValue::List(vec![]))]);
@@ -461,4 +392,14 @@ mod tests {
         let (_, parsed) = NbtValue::read_root_named(&buf).unwrap();
         assert_eq!(parsed, nbt);
     }
+
+    #[test]
+    fn test_read_network_any_string_root() {
+        let mut buf = BytesMut::new();
+        NbtValue::String("Hi".into()).write_root_network(&mut buf);
+        buf.extend_from_slice(&[0xAB]);
+        let (value, consumed) = NbtValue::read_network_any(&buf).unwrap();
+        assert_eq!(value, NbtValue::String("Hi".into()));
+        assert_eq!(consumed, buf.len() - 1);
+    }
 }
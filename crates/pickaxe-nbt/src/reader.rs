@@ -0,0 +1,224 @@
+use crate::nbt::*;
+use std::fmt::Display;
+use std::io::{self, Read};
+use std::mem::size_of;
+
+/// Bounds on NBT read from files or the network, so a corrupt or hostile
+/// input fails with an error instead of exhausting the stack or memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NbtLimits {
+    /// Deepest nesting of lists and compounds.
+    pub max_depth: usize,
+    /// Rough cap on the memory the decoded value may take, in bytes.
+    pub max_bytes: usize,
+}
+
+impl NbtLimits {
+    /// Vanilla's limits for region, player and level files.
+    pub const FILE: NbtLimits = NbtLimits {
+        max_depth: 512,
+        max_bytes: 100 * 1024 * 1024,
+    };
+    /// Vanilla's limits for NBT inside packets.
+    pub const NETWORK: NbtLimits = NbtLimits {
+        max_depth: 512,
+        max_bytes: 2 * 1024 * 1024,
+    };
+}
+
+/// Reads tags straight from a stream, charging every allocation against the
+/// limits before making it. Errors carry the byte offset they happened at.
+pub(crate) struct NbtReader<R> {
+    inner: R,
+    limits: NbtLimits,
+    depth: usize,
+    position: u64,
+    used: usize,
+}
+
+impl<R: Read> NbtReader<R> {
+    pub(crate) fn new(inner: R, limits: NbtLimits) -> Self {
+        Self {
+            inner,
+            limits,
+            depth: 0,
+            position: 0,
+            used: 0,
+        }
+    }
+
+    /// Bytes consumed so far.
+    pub(crate) fn position(&self) -> u64 {
+        self.position
+    }
+
+    pub(crate) fn error(&self, msg: impl Display) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, format!("{} at byte {}", msg, self.position))
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.inner.read_exact(buf).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("NBT ends early at byte {}", self.position),
+            ),
+            _ => e,
+        })?;
+        self.position += buf.len() as u64;
+        Ok(())
+    }
+
+    fn read_bytes<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut buf = [0u8; N];
+        self.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    pub(crate) fn read_u8(&mut self) -> io::Result<u8> {
+        Ok(self.read_bytes::<1>()?[0])
+    }
+
+    fn read_i16(&mut self) -> io::Result<i16> {
+        Ok(i16::from_be_bytes(self.read_bytes()?))
+    }
+
+    fn read_i32(&mut self) -> io::Result<i32> {
+        Ok(i32::from_be_bytes(self.read_bytes()?))
+    }
+
+    fn read_i64(&mut self) -> io::Result<i64> {
+        Ok(i64::from_be_bytes(self.read_bytes()?))
+    }
+
+    fn account(&mut self, bytes: usize) -> io::Result<()> {
+        self.used = self.used.saturating_add(bytes);
+        if self.used > self.limits.max_bytes {
+            return Err(self.error(format!("NBT is bigger than {} bytes", self.limits.max_bytes)));
+        }
+        Ok(())
+    }
+
+    /// An array or list length, charged at `element_size` bytes per element.
+    fn read_length(&mut self, element_size: usize) -> io::Result<usize> {
+        let raw = self.read_i32()?;
+        if raw < 0 {
+            return Err(self.error(format!("Negative length {}", raw)));
+        }
+        let len = raw as usize;
+        self.account(len.saturating_mul(element_size))?;
+        Ok(len)
+    }
+
+    pub(crate) fn read_string(&mut self) -> io::Result<String> {
+        let len = u16::from_be_bytes(self.read_bytes()?) as usize;
+        self.account(len)?;
+        let mut buf = vec![0u8; len];
+        self.read_exact(&mut buf)?;
+        String::from_utf8(buf).map_err(|_| self.error("Invalid UTF-8 in string"))
+    }
+
+    fn enter(&mut self) -> io::Result<()> {
+        self.depth += 1;
+        if self.depth > self.limits.max_depth {
+            return Err(self.error(format!("NBT nested deeper than {}", self.limits.max_depth)));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn read_payload(&mut self, tag_type: u8) -> io::Result<NbtValue> {
+        self.account(size_of::<NbtValue>())?;
+        match tag_type {
+            TAG_BYTE => Ok(NbtValue::Byte(self.read_u8()? as i8)),
+            TAG_SHORT => Ok(NbtValue::Short(self.read_i16()?)),
+            TAG_INT => Ok(NbtValue::Int(self.read_i32()?)),
+            TAG_LONG => Ok(NbtValue::Long(self.read_i64()?)),
+            TAG_FLOAT => Ok(NbtValue::Float(f32::from_be_bytes(self.read_bytes()?))),
+            TAG_DOUBLE => Ok(NbtValue::Double(f64::from_be_bytes(self.read_bytes()?))),
+            TAG_BYTE_ARRAY => {
+                let len = self.read_length(1)?;
+                let mut data = vec![0u8; len];
+                self.read_exact(&mut data)?;
+                Ok(NbtValue::ByteArray(data.into_iter().map(|b| b as i8).collect()))
+            }
+            TAG_STRING => Ok(NbtValue::String(self.read_string()?)),
+            TAG_LIST => {
+                let elem_type = self.read_u8()?;
+                let len = self.read_length(size_of::<NbtValue>())?;
+                if elem_type == TAG_END && len > 0 {
+                    return Err(self.error("List of end tags"));
+                }
+                self.enter()?;
+                let mut items = Vec::with_capacity(len);
+                for _ in 0..len {
+                    items.push(self.read_payload(elem_type)?);
+                }
+                self.depth -= 1;
+                Ok(NbtValue::List(items))
+            }
+            TAG_COMPOUND => {
+                self.enter()?;
+                let mut entries = Vec::new();
+                loop {
+                    let child_type = self.read_u8()?;
+                    if child_type == TAG_END {
+                        break;
+                    }
+                    if child_type > TAG_LONG_ARRAY {
+                        return Err(self.error(format!("Unknown tag type {}", child_type)));
+                    }
+                    let name = self.read_string()?;
+                    let value = self.read_payload(child_type)?;
+                    entries.push((name, value));
+                }
+                self.depth -= 1;
+                Ok(NbtValue::Compound(entries))
+            }
+            TAG_INT_ARRAY => {
+                let len = self.read_length(size_of::<i32>())?;
+                let mut data = Vec::with_capacity(len);
+                for _ in 0..len {
+                    data.push(self.read_i32()?);
+                }
+                Ok(NbtValue::IntArray(data))
+            }
+            TAG_LONG_ARRAY => {
+                let len = self.read_length(size_of::<i64>())?;
+                let mut data = Vec::with_capacity(len);
+                for _ in 0..len {
+                    data.push(self.read_i64()?);
+                }
+                Ok(NbtValue::LongArray(data))
+            }
+            _ => Err(self.error(format!("Unknown tag type {}", tag_type))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits() {
+        // A list claiming two billion compounds fails before allocating
+        let mut huge = vec![TAG_COMPOUND, 0, 0, TAG_LIST, 0, 1, b'a', TAG_COMPOUND];
+        huge.extend_from_slice(&i32::MAX.to_be_bytes());
+        let err = NbtValue::read_root_named(&huge).unwrap_err();
+        assert!(err.to_string().contains("bigger than"), "{}", err);
+
+        // Lists nested past the depth limit
+        let mut deep = vec![TAG_COMPOUND, 0, 0, TAG_LIST, 0, 1, b'a'];
+        for _ in 0..600 {
+            deep.push(TAG_LIST);
+            deep.extend_from_slice(&1i32.to_be_bytes());
+        }
+        let err = NbtValue::read_root_named(&deep).unwrap_err();
+        assert!(err.to_string().contains("nested deeper than 512"), "{}", err);
+
+        let err = NbtValue::read_root_named(&[TAG_COMPOUND, 0, 0, 13]).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown tag type 13 at byte 4");
+        let err = NbtValue::read_root_named(&[TAG_COMPOUND, 0, 0, TAG_INT, 0, 1, b'x', 0]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(err.to_string(), "NBT ends early at byte 7");
+    }
+}
@@ -0,0 +1,338 @@
+use crate::NbtValue;
+use std::fmt;
+
+/// Parse stringified NBT (the `{Key:value,...}` syntax used in commands).
+///
+/// Supports compounds, lists, typed arrays (`[B;..]`, `[I;..]`, `[L;..]`),
+/// quoted and unquoted strings, numeric suffixes (`b`, `s`, `l`, `f`, `d`)
+/// and `true`/`false` as bytes.
+pub fn parse_snbt(input: &str) -> Result<NbtValue, String> {
+    let mut parser = SnbtParser { input: input.as_bytes(), pos: 0 };
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+    if parser.pos != parser.input.len() {
+        return Err(format!("Trailing data at position {}", parser.pos));
+    }
+    Ok(value)
+}
+
+struct SnbtParser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl SnbtParser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' at position {}", c as char, self.pos))
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<NbtValue, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.parse_compound(),
+            Some(b'[') => self.parse_list(),
+            Some(b'"') | Some(b'\'') => Ok(NbtValue::String(self.parse_quoted()?)),
+            Some(_) => {
+                let token = self.parse_unquoted();
+                if token.is_empty() {
+                    return Err(format!("Expected value at position {}", self.pos));
+                }
+                Ok(parse_scalar(&token))
+            }
+            None => Err("Unexpected end of input".to_string()),
+        }
+    }
+
+    fn parse_compound(&mut self) -> Result<NbtValue, String> {
+        self.expect(b'{')?;
+        let mut entries: Vec<(String, NbtValue)> = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(NbtValue::Compound(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            // Quoted keys may be empty, unquoted ones can't
+            let key = match self.peek() {
+                Some(b'"') | Some(b'\'') => self.parse_quoted()?,
+                _ => match self.parse_unquoted() {
+                    key if key.is_empty() => return Err(format!("Expected key at position {}", self.pos)),
+                    key => key,
+                },
+            };
+            self.expect(b':')?;
+            let value = self.parse_value()?;
+            match entries.iter_mut().find(|(k, _)| *k == key) {
+                Some(entry) => entry.1 = value,
+                None => entries.push((key, value)),
+            }
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(NbtValue::Compound(entries));
+                }
+                _ => return Err(format!("Expected ',' or '}}' at position {}", self.pos)),
+            }
+        }
+    }
+
+    fn parse_list(&mut self) -> Result<NbtValue, String> {
+        self.expect(b'[')?;
+        // Typed arrays: [B;1b,2b], [I;1,2], [L;1l,2l]
+        let prefix = self.input.get(self.pos..self.pos + 2);
+        if let Some(&[kind @ (b'B' | b'I' | b'L'), b';']) = prefix {
+            self.pos += 2;
+            let items = self.parse_elements()?;
+            let numbers = items
+                .iter()
+                .map(|v| v.as_number().map(|n| n as i64))
+                .collect::<Option<Vec<i64>>>()
+                .ok_or_else(|| "Typed arrays may only contain numbers".to_string())?;
+            return Ok(match kind {
+                b'B' => NbtValue::ByteArray(numbers.into_iter().map(|n| n as i8).collect()),
+                b'I' => NbtValue::IntArray(numbers.into_iter().map(|n| n as i32).collect()),
+                _ => NbtValue::LongArray(numbers),
+            });
+        }
+        let items = self.parse_elements()?;
+        if let Some(first) = items.first() {
+            if items.iter().any(|v| v.tag_id() != first.tag_id()) {
+                return Err("List elements must all be the same type".to_string());
+            }
+        }
+        Ok(NbtValue::List(items))
+    }
+
+    /// Parse comma-separated values up to and including the closing `]`.
+    fn parse_elements(&mut self) -> Result<Vec<NbtValue>, String> {
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(items);
+        }
+        loop {
+            items.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(items);
+                }
+                _ => return Err(format!("Expected ',' or ']' at position {}", self.pos)),
+            }
+        }
+    }
+
+    fn parse_quoted(&mut self) -> Result<String, String> {
+        let quote = self.input[self.pos];
+        self.pos += 1;
+        let mut out = Vec::new();
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                b'\\' => match self.peek() {
+                    Some(escaped) => {
+                        out.push(escaped);
+                        self.pos += 1;
+                    }
+                    None => break,
+                },
+                _ if c == quote => {
+                    return String::from_utf8(out).map_err(|_| "Invalid UTF-8 in string".to_string());
+                }
+                _ => out.push(c),
+            }
+        }
+        Err("Unterminated string".to_string())
+    }
+
+    fn parse_unquoted(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(is_unquoted_char) {
+            self.pos += 1;
+        }
+        String::from_utf8_lossy(&self.input[start..self.pos]).into_owned()
+    }
+}
+
+/// Interpret an unquoted token as a number or boolean, falling back to a string.
+fn parse_scalar(token: &str) -> NbtValue {
+    match token {
+        "true" => return NbtValue::Byte(1),
+        "false" => return NbtValue::Byte(0),
+        _ => {}
+    }
+    let (body, suffix) = match token.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&token[..i], Some(c.to_ascii_lowercase())),
+        _ => (token, None),
+    };
+    let parsed = match suffix {
+        Some('b') => body.parse().ok().map(NbtValue::Byte),
+        Some('s') => body.parse().ok().map(NbtValue::Short),
+        Some('l') => body.parse().ok().map(NbtValue::Long),
+        Some('f') => body.parse().ok().map(NbtValue::Float),
+        Some('d') => body.parse().ok().map(NbtValue::Double),
+        Some(_) => None,
+        None => body
+            .parse()
+            .ok()
+            .map(NbtValue::Int)
+            .or_else(|| body.contains('.').then(|| body.parse().ok().map(NbtValue::Double)).flatten()),
+    };
+    parsed.unwrap_or_else(|| NbtValue::String(token.to_string()))
+}
+
+/// Prints the value as SNBT the way vanilla does, e.g.
+/// `{Count:1b,id:"minecraft:stone"}`. Compound keys keep their order, and
+/// the output parses back to the same value with [`parse_snbt`].
+impl fmt::Display for NbtValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NbtValue::Byte(v) => write!(f, "{}b", v),
+            NbtValue::Short(v) => write!(f, "{}s", v),
+            NbtValue::Int(v) => write!(f, "{}", v),
+            NbtValue::Long(v) => write!(f, "{}L", v),
+            // Debug keeps the decimal point on whole numbers
+            NbtValue::Float(v) => write!(f, "{:?}f", v),
+            NbtValue::Double(v) => write!(f, "{:?}d", v),
+            NbtValue::String(v) => write_quoted(f, v),
+            NbtValue::ByteArray(v) => write_array(f, "B", v.iter().map(|n| format!("{}B", n))),
+            NbtValue::IntArray(v) => write_array(f, "I", v.iter().map(|n| n.to_string())),
+            NbtValue::LongArray(v) => write_array(f, "L", v.iter().map(|n| format!("{}L", n))),
+            NbtValue::List(items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_str("]")
+            }
+            NbtValue::Compound(entries) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    if !key.is_empty() && key.bytes().all(is_unquoted_char) {
+                        f.write_str(key)?;
+                    } else {
+                        write_quoted(f, key)?;
+                    }
+                    write!(f, ":{}", value)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+fn is_unquoted_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'_' | b'-' | b'.' | b'+')
+}
+
+/// Double quotes unless the string contains one, as vanilla does.
+fn write_quoted(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    let quote = if s.contains('"') && !s.contains('\'') { '\'' } else { '"' };
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push(quote);
+    for c in s.chars() {
+        if c == quote || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push(quote);
+    f.write_str(&out)
+}
+
+fn write_array(f: &mut fmt::Formatter<'_>, kind: &str, items: impl Iterator<Item = String>) -> fmt::Result {
+    write!(f, "[{};", kind)?;
+    for (i, item) in items.enumerate() {
+        if i > 0 {
+            f.write_str(",")?;
+        }
+        f.write_str(&item)?;
+    }
+    f.write_str("]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_compound() {
+        let nbt = parse_snbt(r#"{Health:10.5f, CustomName:"Bob", NoAI:1b, Fuse:40}"#).unwrap();
+        assert_eq!(nbt.get("Health"), Some(&NbtValue::Float(10.5)));
+        assert_eq!(nbt.get("CustomName").and_then(|v| v.as_str()), Some("Bob"));
+        assert_eq!(nbt.get("NoAI"), Some(&NbtValue::Byte(1)));
+        assert_eq!(nbt.get("Fuse"), Some(&NbtValue::Int(40)));
+    }
+
+    #[test]
+    fn test_parse_nested_and_lists() {
+        let nbt = parse_snbt("{Item:{id:'minecraft:diamond',count:3},Pos:[1.0d,2.5,3d],Ids:[I;1,2]}").unwrap();
+        let item = nbt.get("Item").unwrap();
+        assert_eq!(item.get("id").and_then(|v| v.as_str()), Some("minecraft:diamond"));
+        assert_eq!(item.get("count"), Some(&NbtValue::Int(3)));
+        assert_eq!(
+            nbt.get("Pos"),
+            Some(&NbtValue::List(vec![
+                NbtValue::Double(1.0),
+                NbtValue::Double(2.5),
+                NbtValue::Double(3.0)
+            ]))
+        );
+        assert_eq!(nbt.get("Ids"), Some(&NbtValue::IntArray(vec![1, 2])));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_snbt("{a:1").is_err());
+        assert!(parse_snbt("{a:1} extra").is_err());
+        assert!(parse_snbt("[1,\"x\"]").is_err());
+        assert!(parse_snbt("{:1}").is_err());
+    }
+
+    #[test]
+    fn test_print_roundtrip() {
+        let item = parse_snbt(r#"{id:"minecraft:stone",Count:1b}"#).unwrap();
+        assert_eq!(item.to_string(), r#"{id:"minecraft:stone",Count:1b}"#);
+
+        let inputs = [
+            r#"{Pos:[0.5d,64.0d,-12.25d],Rotation:[90.0f,0.0f],UUID:[I;1,-2,3,4],Seeds:[L;5L,-6L],Flags:[B;1B,0B]}"#,
+            r#"{"":1s,"has space":'say "hi"',"tab\name":"back\\slash",Time:9000000000L,Items:[]}"#,
+            r#"{Tags:["a","b"],Passengers:[{id:"minecraft:pig",Health:1.0E10f}],Nested:[[1,2],[3]]}"#,
+        ];
+        for input in inputs {
+            let parsed = parse_snbt(input).unwrap();
+            assert_eq!(parse_snbt(&parsed.to_string()).unwrap(), parsed, "{}", input);
+        }
+        // Vanilla-style spacing and suffix case
+        let spaced = parse_snbt("{ CustomName : 'Bob' , Age : 5S , Ids : [I; 1 , 2 ] }").unwrap();
+        assert_eq!(spaced.to_string(), r#"{CustomName:"Bob",Age:5s,Ids:[I;1,2]}"#);
+    }
+}
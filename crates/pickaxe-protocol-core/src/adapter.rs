@@ -1,6 +1,7 @@
 use crate::{ConnectionState, InternalPacket};
 use anyhow::Result;
 use bytes::BytesMut;
+use pickaxe_data::registries::Registries;
 
 /// Trait for version-specific protocol adapters.
 /// Each supported MC version implements this trait.
@@ -8,6 +9,9 @@ pub trait ProtocolAdapter: Send + Sync {
     /// The protocol version number this adapter handles.
     fn protocol_version(&self) -> i32;
 
+    /// The game version shown in the server list, e.g. "1.21.1".
+    fn version_name(&self) -> &'static str;
+
     /// Decode a raw packet from wire format into an InternalPacket.
     fn decode_packet(
         &self,
@@ -23,6 +27,7 @@ pub trait ProtocolAdapter: Send + Sync {
         packet: &InternalPacket,
     ) -> Result<BytesMut>;
 
-    /// Get the registry data packets for the Configuration state.
-    fn registry_data(&self) -> Vec<InternalPacket>;
+    /// Get the registry data packets for the Configuration state, with the
+    /// data-driven entries taken from `registries`.
+    fn registry_data(&self, registries: &Registries) -> Vec<InternalPacket>;
 }
@@ -0,0 +1,214 @@
+use crate::{ConnectionState, InternalPacket, ProtocolAdapter};
+use bytes::BytesMut;
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Start of every capture file; the last byte is the format version.
+const MAGIC: &[u8; 8] = b"PXCAP\0\0\x01";
+
+/// Longest decoded form stored with a packet. Chunk data would otherwise
+/// print every byte.
+const MAX_DECODED_LEN: usize = 512;
+
+/// Which way a captured packet went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Serverbound,
+    Clientbound,
+}
+
+/// One frame read back from a capture.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapturedPacket {
+    /// Time since the capture started.
+    pub elapsed: Duration,
+    pub direction: Direction,
+    pub state: ConnectionState,
+    pub packet_id: i32,
+    /// The uncompressed, unencrypted payload after the packet ID.
+    pub payload: Vec<u8>,
+    /// `Debug` form of the packet as the server saw it, shortened; empty if
+    /// it couldn't be decoded.
+    pub decoded: String,
+}
+
+impl CapturedPacket {
+    /// Decode a serverbound packet again, e.g. to check a changed adapter
+    /// still reads what a client sent.
+    pub fn decode(&self, adapter: &dyn ProtocolAdapter) -> anyhow::Result<InternalPacket> {
+        let mut data = BytesMut::from(&self.payload[..]);
+        adapter.decode_packet(self.state, self.packet_id, &mut data)
+    }
+}
+
+/// Writes a packet capture: every frame of a connection with a timestamp,
+/// in a file `Replay` reads back to debug the protocol.
+pub struct CaptureWriter<W: Write> {
+    out: W,
+    started: Instant,
+}
+
+impl<W: Write> CaptureWriter<W> {
+    pub fn new(mut out: W) -> io::Result<Self> {
+        out.write_all(MAGIC)?;
+        Ok(Self {
+            out,
+            started: Instant::now(),
+        })
+    }
+
+    /// Record a frame, with the packet it was decoded from or into if known.
+    pub fn record(
+        &mut self,
+        direction: Direction,
+        state: ConnectionState,
+        packet_id: i32,
+        payload: &[u8],
+        packet: Option<&InternalPacket>,
+    ) -> io::Result<()> {
+        let decoded = packet.map(describe).unwrap_or_default();
+        let elapsed = self.started.elapsed().as_micros() as u64;
+        let mut record = Vec::with_capacity(22 + payload.len() + decoded.len());
+        record.extend_from_slice(&elapsed.to_be_bytes());
+        record.push(direction as u8);
+        record.push(state_id(state));
+        record.extend_from_slice(&packet_id.to_be_bytes());
+        record.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        record.extend_from_slice(payload);
+        record.extend_from_slice(&(decoded.len() as u32).to_be_bytes());
+        record.extend_from_slice(decoded.as_bytes());
+        self.out.write_all(&record)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// A capture shared by a connection's reader and writer.
+pub type SharedCapture = Arc<Mutex<CaptureWriter<Box<dyn Write + Send>>>>;
+
+/// Reads a capture back, one packet at a time.
+pub struct Replay<R: Read> {
+    input: R,
+}
+
+impl<R: Read> Replay<R> {
+    pub fn new(mut input: R) -> anyhow::Result<Self> {
+        let mut magic = [0u8; 8];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            anyhow::bail!("Not a packet capture");
+        }
+        Ok(Self { input })
+    }
+
+    fn read_record(&mut self) -> anyhow::Result<Option<CapturedPacket>> {
+        let mut elapsed = [0u8; 8];
+        match self.input.read_exact(&mut elapsed) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        let mut header = [0u8; 6];
+        self.input.read_exact(&mut header)?;
+        let direction = match header[0] {
+            0 => Direction::Serverbound,
+            1 => Direction::Clientbound,
+            other => anyhow::bail!("Unknown direction {}", other),
+        };
+        let state = state_from_id(header[1]).ok_or_else(|| anyhow::anyhow!("Unknown state {}", header[1]))?;
+        let packet_id = i32::from_be_bytes([header[2], header[3], header[4], header[5]]);
+        let payload = self.read_bytes()?;
+        let decoded = String::from_utf8(self.read_bytes()?)?;
+        Ok(Some(CapturedPacket {
+            elapsed: Duration::from_micros(u64::from_be_bytes(elapsed)),
+            direction,
+            state,
+            packet_id,
+            payload,
+            decoded,
+        }))
+    }
+
+    fn read_bytes(&mut self) -> io::Result<Vec<u8>> {
+        let mut len = [0u8; 4];
+        self.input.read_exact(&mut len)?;
+        let mut bytes = vec![0u8; u32::from_be_bytes(len) as usize];
+        self.input.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl<R: Read> Iterator for Replay<R> {
+    type Item = anyhow::Result<CapturedPacket>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+/// A packet's `Debug` form, cut to `MAX_DECODED_LEN` bytes.
+pub fn describe(packet: &InternalPacket) -> String {
+    let mut text = format!("{:?}", packet);
+    if text.len() > MAX_DECODED_LEN {
+        let mut end = MAX_DECODED_LEN;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push_str("...");
+    }
+    text
+}
+
+fn state_id(state: ConnectionState) -> u8 {
+    match state {
+        ConnectionState::Handshaking => 0,
+        ConnectionState::Status => 1,
+        ConnectionState::Login => 2,
+        ConnectionState::Configuration => 3,
+        ConnectionState::Play => 4,
+    }
+}
+
+fn state_from_id(id: u8) -> Option<ConnectionState> {
+    Some(match id {
+        0 => ConnectionState::Handshaking,
+        1 => ConnectionState::Status,
+        2 => ConnectionState::Login,
+        3 => ConnectionState::Configuration,
+        4 => ConnectionState::Play,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_roundtrip() {
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        let packet = InternalPacket::KeepAliveClientbound { id: 7 };
+        writer
+            .record(Direction::Clientbound, ConnectionState::Play, 0x26, &7i64.to_be_bytes(), Some(&packet))
+            .unwrap();
+        writer.record(Direction::Serverbound, ConnectionState::Login, 0x03, &[], None).unwrap();
+
+        let packets: Vec<CapturedPacket> = Replay::new(&writer.into_inner()[..]).unwrap().map(Result::unwrap).collect();
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].direction, Direction::Clientbound);
+        assert_eq!(packets[0].payload, 7i64.to_be_bytes());
+        assert_eq!(packets[0].decoded, "KeepAliveClientbound { id: 7 }");
+        assert_eq!(packets[1].state, ConnectionState::Login);
+        assert!(packets[1].decoded.is_empty());
+
+        assert!(Replay::new(&b"not a capture"[..]).is_err());
+    }
+}
@@ -1,5 +1,9 @@
 use bytes::{Buf, BufMut, BytesMut};
-use pickaxe_types::ItemStack;
+use pickaxe_nbt::NbtValue;
+use pickaxe_types::{
+    AttributeModifier, AttributeOperation, BannerLayer, ClickEvent, EquipmentSlotGroup, HoverEvent, ItemStack, PotionContents,
+    PotionContentsEffect, TextComponent,
+};
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -205,10 +209,199 @@ pub fn write_byte_array(buf: &mut BytesMut, data: &[u8]) {
     buf.put_slice(data);
 }
 
+/// Convert a text component to its network NBT form (1.20.3+).
+pub fn text_component_to_nbt(text: &TextComponent) -> NbtValue {
+    let mut fields = vec![("text".into(), NbtValue::String(text.text.clone()))];
+    if let Some(key) = &text.translate {
+        fields.push(("translate".into(), NbtValue::String(key.clone())));
+    }
+    if !text.with.is_empty() {
+        let with = text.with.iter().map(text_component_to_nbt).collect();
+        fields.push(("with".into(), NbtValue::List(with)));
+    }
+    if let Some(color) = &text.color {
+        fields.push(("color".into(), NbtValue::String(color.clone())));
+    }
+    let flags = [
+        ("bold", text.bold),
+        ("italic", text.italic),
+        ("underlined", text.underlined),
+        ("strikethrough", text.strikethrough),
+        ("obfuscated", text.obfuscated),
+    ];
+    for (key, value) in flags {
+        if let Some(value) = value {
+            fields.push((key.into(), NbtValue::Byte(value as i8)));
+        }
+    }
+    if let Some(click) = &text.click_event {
+        fields.push((
+            "clickEvent".into(),
+            NbtValue::Compound(vec![
+                ("action".into(), NbtValue::String(click.action().into())),
+                ("value".into(), NbtValue::String(click.value().into())),
+            ]),
+        ));
+    }
+    if let Some(hover) = &text.hover_event {
+        let (action, contents) = match hover.as_ref() {
+            HoverEvent::ShowText(text) => ("show_text", text_component_to_nbt(text)),
+            HoverEvent::ShowItem { id, count } => (
+                "show_item",
+                NbtValue::Compound(vec![
+                    ("id".into(), NbtValue::String(id.clone())),
+                    ("count".into(), NbtValue::Int(*count)),
+                ]),
+            ),
+        };
+        fields.push((
+            "hoverEvent".into(),
+            NbtValue::Compound(vec![
+                ("action".into(), NbtValue::String(action.into())),
+                ("contents".into(), contents),
+            ]),
+        ));
+    }
+    if !text.extra.is_empty() {
+        let extra = text.extra.iter().map(text_component_to_nbt).collect();
+        fields.push(("extra".into(), NbtValue::List(extra)));
+    }
+    NbtValue::Compound(fields)
+}
+
+/// Convert network NBT (a bare string or a compound) back to a text component.
+pub fn text_component_from_nbt(nbt: &NbtValue) -> TextComponent {
+    if let Some(text) = nbt.as_str() {
+        return TextComponent::plain(text);
+    }
+    let flag = |key: &str| nbt.get(key).and_then(|v| v.as_byte()).map(|b| b != 0);
+    let mut text = TextComponent::plain(nbt.get("text").and_then(|v| v.as_str()).unwrap_or(""));
+    text.translate = nbt.get("translate").and_then(|v| v.as_str()).map(String::from);
+    if let Some(with) = nbt.get("with").and_then(|v| v.as_list()) {
+        text.with = with.iter().map(text_component_from_nbt).collect();
+    }
+    text.color = nbt.get("color").and_then(|v| v.as_str()).map(String::from);
+    text.bold = flag("bold");
+    text.italic = flag("italic");
+    text.underlined = flag("underlined");
+    text.strikethrough = flag("strikethrough");
+    text.obfuscated = flag("obfuscated");
+    text.click_event = nbt.get("clickEvent").and_then(|click| {
+        let action = click.get("action")?.as_str()?;
+        let value = click.get("value")?.as_str()?;
+        ClickEvent::from_action(action, value).map(Box::new)
+    });
+    text.hover_event = nbt.get("hoverEvent").and_then(|hover| {
+        let contents = hover.get("contents")?;
+        match hover.get("action")?.as_str()? {
+            "show_text" => Some(HoverEvent::ShowText(text_component_from_nbt(contents))),
+            "show_item" => Some(HoverEvent::ShowItem {
+                id: contents.get("id")?.as_str()?.to_string(),
+                count: contents.get("count").and_then(|v| v.as_number()).map_or(1, |n| n as i32),
+            }),
+            _ => None,
+        }
+        .map(Box::new)
+    });
+    if let Some(extra) = nbt.get("extra").and_then(|v| v.as_list()) {
+        text.extra = extra.iter().map(text_component_from_nbt).collect();
+    }
+    text
+}
+
 // Data component type IDs (MC 1.21.1 registry order from DataComponents.java)
+const COMPONENT_CUSTOM_DATA: i32 = 0;
 const COMPONENT_MAX_DAMAGE: i32 = 2;
 const COMPONENT_DAMAGE: i32 = 3;
+const COMPONENT_UNBREAKABLE: i32 = 4;
+const COMPONENT_CUSTOM_NAME: i32 = 5;
+const COMPONENT_LORE: i32 = 7;
 const COMPONENT_ENCHANTMENTS: i32 = 9;
+const COMPONENT_ATTRIBUTE_MODIFIERS: i32 = 12;
+const COMPONENT_DYED_COLOR: i32 = 24;
+const COMPONENT_POTION_CONTENTS: i32 = 31;
+const COMPONENT_BANNER_PATTERNS: i32 = 49;
+
+/// Key of the custom item id within the `custom_data` component.
+pub const CUSTOM_ID_KEY: &str = "pickaxe:id";
+
+/// Split a `custom_data` compound into the custom item id and the other entries.
+pub fn split_custom_data(nbt: &NbtValue) -> (Option<String>, Vec<(String, NbtValue)>) {
+    let NbtValue::Compound(entries) = nbt else {
+        return (None, Vec::new());
+    };
+    let mut custom_id = None;
+    let mut rest = Vec::new();
+    for (key, value) in entries {
+        match value.as_str() {
+            Some(id) if key == CUSTOM_ID_KEY => custom_id = Some(id.to_string()),
+            _ => rest.push((key.clone(), value.clone())),
+        }
+    }
+    (custom_id, rest)
+}
+
+/// The `custom_data` compound for an item, or None if it has no custom data.
+pub fn custom_data_nbt(item: &ItemStack) -> Option<NbtValue> {
+    if item.custom_id.is_none() && item.custom_data.is_empty() {
+        return None;
+    }
+    let mut entries = item.custom_data.clone();
+    if let Some(id) = &item.custom_id {
+        entries.insert(0, (CUSTOM_ID_KEY.into(), NbtValue::String(id.clone())));
+    }
+    Some(NbtValue::Compound(entries))
+}
+
+fn read_bool(buf: &mut BytesMut) -> CodecResult<bool> {
+    Ok(read_u8(buf)? != 0)
+}
+
+fn read_network_nbt(buf: &mut BytesMut) -> CodecResult<NbtValue> {
+    let (nbt, consumed) = NbtValue::read_network_any(&buf[..])?;
+    buf.advance(consumed);
+    Ok(nbt)
+}
+
+fn read_attribute_modifier(buf: &mut BytesMut) -> CodecResult<Option<AttributeModifier>> {
+    let attribute = read_varint(buf)?;
+    let id = read_string(buf, 32767)?;
+    let amount = read_f64(buf)?;
+    let operation = read_varint(buf)?;
+    let slot = read_varint(buf)?;
+    Ok(AttributeOperation::from_id(operation).zip(EquipmentSlotGroup::from_id(slot)).map(|(operation, slot)| {
+        AttributeModifier { attribute, id, amount, operation, slot }
+    }))
+}
+
+fn read_potion_contents(buf: &mut BytesMut) -> CodecResult<PotionContents> {
+    let potion = if read_bool(buf)? { Some(read_varint(buf)?) } else { None };
+    let custom_color = if read_bool(buf)? { Some(read_i32_raw(buf)?) } else { None };
+    let mut custom_effects = Vec::new();
+    for _ in 0..read_varint(buf)? {
+        let effect_id = read_varint(buf)?;
+        let effect = read_effect_details(buf, effect_id)?;
+        custom_effects.push(effect);
+    }
+    Ok(PotionContents { potion, custom_color, custom_effects })
+}
+
+/// An effect's amplifier, duration and flags, followed by an optional
+/// hidden effect of the same type, which is read and dropped.
+fn read_effect_details(buf: &mut BytesMut, effect_id: i32) -> CodecResult<PotionContentsEffect> {
+    let effect = PotionContentsEffect {
+        effect_id,
+        amplifier: read_varint(buf)?,
+        duration: read_varint(buf)?,
+        ambient: read_bool(buf)?,
+        show_particles: read_bool(buf)?,
+        show_icon: read_bool(buf)?,
+    };
+    if read_bool(buf)? {
+        read_effect_details(buf, effect_id)?;
+    }
+    Ok(effect)
+}
 
 /// Read a Slot from the wire (1.21.1 component-based format).
 /// Returns None for empty slots (item_count == 0).
@@ -220,37 +413,79 @@ pub fn read_slot(buf: &mut BytesMut) -> CodecResult<Option<ItemStack>> {
     let item_id = read_varint(buf)?;
     let add_count = read_varint(buf)?;
     let remove_count = read_varint(buf)?;
-    let mut max_damage = 0i32;
-    let mut damage = 0i32;
-    let mut enchantments = Vec::new();
-    // Parse added components — we handle MAX_DAMAGE, DAMAGE, ENCHANTMENTS, skip others
+    let mut item = ItemStack::new(item_id, item_count as i8);
+    // Parse the added components we model, stop at the first we don't
     for _ in 0..add_count {
         let comp_type = read_varint(buf)?;
         match comp_type {
-            COMPONENT_MAX_DAMAGE => { max_damage = read_varint(buf)?; }
-            COMPONENT_DAMAGE => { damage = read_varint(buf)?; }
+            COMPONENT_CUSTOM_DATA => {
+                (item.custom_id, item.custom_data) = split_custom_data(&read_network_nbt(buf)?);
+            }
+            COMPONENT_MAX_DAMAGE => { item.max_damage = read_varint(buf)?; }
+            COMPONENT_DAMAGE => { item.damage = read_varint(buf)?; }
+            COMPONENT_UNBREAKABLE => {
+                read_bool(buf)?; // show_in_tooltip
+                item.unbreakable = true;
+            }
+            COMPONENT_CUSTOM_NAME => {
+                item.custom_name = Some(text_component_from_nbt(&read_network_nbt(buf)?));
+            }
+            COMPONENT_LORE => {
+                let lines = read_varint(buf)?;
+                for _ in 0..lines {
+                    item.lore.push(text_component_from_nbt(&read_network_nbt(buf)?));
+                }
+            }
             COMPONENT_ENCHANTMENTS => {
                 let map_size = read_varint(buf)?;
                 for _ in 0..map_size {
                     let ench_holder = read_varint(buf)?; // holder id + 1
                     let level = read_varint(buf)?;
                     if ench_holder > 0 {
-                        enchantments.push((ench_holder - 1, level));
+                        item.enchantments.push((ench_holder - 1, level));
+                    }
+                }
+                // show_in_tooltip boolean
+                if buf.remaining() > 0 {
+                    buf.advance(1);
+                }
+            }
+            COMPONENT_ATTRIBUTE_MODIFIERS => {
+                let modifiers = read_varint(buf)?;
+                for _ in 0..modifiers {
+                    if let Some(modifier) = read_attribute_modifier(buf)? {
+                        item.attribute_modifiers.push(modifier);
                     }
                 }
+                read_bool(buf)?; // show_in_tooltip
+            }
+            COMPONENT_DYED_COLOR => {
+                item.dyed_color = Some(read_i32_raw(buf)?);
                 // show_in_tooltip boolean
                 if buf.remaining() > 0 {
                     buf.advance(1);
                 }
             }
+            COMPONENT_POTION_CONTENTS => {
+                item.potion_contents = Some(read_potion_contents(buf)?);
+            }
+            COMPONENT_BANNER_PATTERNS => {
+                for _ in 0..read_varint(buf)? {
+                    let holder = read_varint(buf)?; // holder id + 1, 0 = inline pattern
+                    if holder == 0 {
+                        read_string(buf, 32767)?; // asset_id
+                        read_string(buf, 32767)?; // translation_key
+                    }
+                    let color = read_varint(buf)?;
+                    if holder > 0 {
+                        item.banner_patterns.push(BannerLayer { pattern: holder - 1, color });
+                    }
+                }
+            }
             _ => {
                 // Unknown component — consume remaining bytes
                 tracing::debug!("Unknown component type {} — consuming remaining bytes", comp_type);
                 buf.advance(buf.remaining());
-                let mut item = ItemStack::new(item_id, item_count as i8);
-                item.damage = damage;
-                item.max_damage = max_damage;
-                item.enchantments = enchantments;
                 return Ok(Some(item));
             }
         }
@@ -259,10 +494,6 @@ pub fn read_slot(buf: &mut BytesMut) -> CodecResult<Option<ItemStack>> {
     for _ in 0..remove_count {
         let _comp_type = read_varint(buf)?;
     }
-    let mut item = ItemStack::new(item_id, item_count as i8);
-    item.damage = damage;
-    item.max_damage = max_damage;
-    item.enchantments = enchantments;
     Ok(Some(item))
 }
 
@@ -278,38 +509,115 @@ pub fn write_slot(buf: &mut BytesMut, slot: &Option<ItemStack>) {
 
             let has_durability = item.max_damage > 0;
             let has_enchantments = !item.enchantments.is_empty();
-
-            if has_durability || has_enchantments {
-                let mut add_count = 0;
-                if has_durability { add_count += 1; } // MAX_DAMAGE
-                if has_durability && item.damage > 0 { add_count += 1; } // DAMAGE
-                if has_enchantments { add_count += 1; } // ENCHANTMENTS
-                write_varint(buf, add_count);
-                write_varint(buf, 0); // no removed components
-
-                // MAX_DAMAGE component (type 2, VarInt value)
-                if has_durability {
-                    write_varint(buf, COMPONENT_MAX_DAMAGE);
-                    write_varint(buf, item.max_damage);
-                    // DAMAGE component (type 3, VarInt value) — only if damaged
-                    if item.damage > 0 {
-                        write_varint(buf, COMPONENT_DAMAGE);
-                        write_varint(buf, item.damage);
-                    }
+            let custom_data = custom_data_nbt(item);
+
+            let mut add_count = 0;
+            if custom_data.is_some() { add_count += 1; } // CUSTOM_DATA
+            if has_durability { add_count += 1; } // MAX_DAMAGE
+            if has_durability && item.damage > 0 { add_count += 1; } // DAMAGE
+            if item.unbreakable { add_count += 1; } // UNBREAKABLE
+            if item.custom_name.is_some() { add_count += 1; } // CUSTOM_NAME
+            if !item.lore.is_empty() { add_count += 1; } // LORE
+            if has_enchantments { add_count += 1; } // ENCHANTMENTS
+            if !item.attribute_modifiers.is_empty() { add_count += 1; } // ATTRIBUTE_MODIFIERS
+            if item.dyed_color.is_some() { add_count += 1; } // DYED_COLOR
+            if item.potion_contents.is_some() { add_count += 1; } // POTION_CONTENTS
+            if !item.banner_patterns.is_empty() { add_count += 1; } // BANNER_PATTERNS
+            write_varint(buf, add_count);
+            write_varint(buf, 0); // no removed components
+
+            // CUSTOM_DATA component (type 0, NBT compound)
+            if let Some(nbt) = custom_data {
+                write_varint(buf, COMPONENT_CUSTOM_DATA);
+                nbt.write_root_network(buf);
+            }
+            // MAX_DAMAGE component (type 2, VarInt value)
+            if has_durability {
+                write_varint(buf, COMPONENT_MAX_DAMAGE);
+                write_varint(buf, item.max_damage);
+                // DAMAGE component (type 3, VarInt value) — only if damaged
+                if item.damage > 0 {
+                    write_varint(buf, COMPONENT_DAMAGE);
+                    write_varint(buf, item.damage);
                 }
-                // ENCHANTMENTS component (type 9)
-                if has_enchantments {
-                    write_varint(buf, COMPONENT_ENCHANTMENTS);
-                    write_varint(buf, item.enchantments.len() as i32);
-                    for (ench_id, level) in &item.enchantments {
-                        write_varint(buf, ench_id + 1); // Holder encoding: id + 1
-                        write_varint(buf, *level);
-                    }
-                    buf.put_u8(1); // show_in_tooltip = true
+            }
+            // UNBREAKABLE component (type 4, show_in_tooltip)
+            if item.unbreakable {
+                write_varint(buf, COMPONENT_UNBREAKABLE);
+                buf.put_u8(1);
+            }
+            // CUSTOM_NAME component (type 5, NBT text component)
+            if let Some(name) = &item.custom_name {
+                write_varint(buf, COMPONENT_CUSTOM_NAME);
+                text_component_to_nbt(name).write_root_network(buf);
+            }
+            // LORE component (type 7, list of NBT text components)
+            if !item.lore.is_empty() {
+                write_varint(buf, COMPONENT_LORE);
+                write_varint(buf, item.lore.len() as i32);
+                for line in &item.lore {
+                    text_component_to_nbt(line).write_root_network(buf);
+                }
+            }
+            // ENCHANTMENTS component (type 9)
+            if has_enchantments {
+                write_varint(buf, COMPONENT_ENCHANTMENTS);
+                write_varint(buf, item.enchantments.len() as i32);
+                for (ench_id, level) in &item.enchantments {
+                    write_varint(buf, ench_id + 1); // Holder encoding: id + 1
+                    write_varint(buf, *level);
+                }
+                buf.put_u8(1); // show_in_tooltip = true
+            }
+            // ATTRIBUTE_MODIFIERS component (type 12)
+            if !item.attribute_modifiers.is_empty() {
+                write_varint(buf, COMPONENT_ATTRIBUTE_MODIFIERS);
+                write_varint(buf, item.attribute_modifiers.len() as i32);
+                for modifier in &item.attribute_modifiers {
+                    write_varint(buf, modifier.attribute);
+                    write_string(buf, &modifier.id);
+                    buf.put_f64(modifier.amount);
+                    write_varint(buf, modifier.operation.id());
+                    write_varint(buf, modifier.slot.id());
+                }
+                buf.put_u8(1); // show_in_tooltip = true
+            }
+            // DYED_COLOR component (type 24, Int rgb)
+            if let Some(rgb) = item.dyed_color {
+                write_varint(buf, COMPONENT_DYED_COLOR);
+                buf.put_i32(rgb);
+                buf.put_u8(1); // show_in_tooltip = true
+            }
+            // POTION_CONTENTS component (type 31, optional potion and color, custom effects)
+            if let Some(contents) = &item.potion_contents {
+                write_varint(buf, COMPONENT_POTION_CONTENTS);
+                buf.put_u8(contents.potion.is_some() as u8);
+                if let Some(potion) = contents.potion {
+                    write_varint(buf, potion);
+                }
+                buf.put_u8(contents.custom_color.is_some() as u8);
+                if let Some(color) = contents.custom_color {
+                    buf.put_i32(color);
+                }
+                write_varint(buf, contents.custom_effects.len() as i32);
+                for effect in &contents.custom_effects {
+                    write_varint(buf, effect.effect_id);
+                    write_varint(buf, effect.amplifier);
+                    write_varint(buf, effect.duration);
+                    buf.put_u8(effect.ambient as u8);
+                    buf.put_u8(effect.show_particles as u8);
+                    buf.put_u8(effect.show_icon as u8);
+                    buf.put_u8(0); // no hidden effect
+                }
+            }
+            // BANNER_PATTERNS component (type 49, list of pattern holder and dye color)
+            if !item.banner_patterns.is_empty() {
+                write_varint(buf, COMPONENT_BANNER_PATTERNS);
+                write_varint(buf, item.banner_patterns.len() as i32);
+                for layer in &item.banner_patterns {
+                    write_varint(buf, layer.pattern + 1); // Holder encoding: id + 1
+                    write_varint(buf, layer.color);
                 }
-            } else {
-                write_varint(buf, 0); // no added components
-                write_varint(buf, 0); // no removed components
             }
         }
     }
@@ -376,4 +684,60 @@ mod tests {
         let result = read_uuid(&mut buf).unwrap();
         assert_eq!(result, uuid);
     }
+
+    #[test]
+    fn test_slot_roundtrip_with_components() {
+        let mut item = ItemStack::with_durability(805, 1, 80);
+        item.damage = 12;
+        item.dyed_color = Some(0x3C44AA);
+        item.custom_name = Some(TextComponent {
+            color: Some("gold".into()),
+            ..TextComponent::plain("Excalibur")
+        });
+        item.lore = vec![TextComponent::plain("Forged in the lake"), TextComponent::plain("")];
+        item.custom_id = Some("legends:excalibur".into());
+        item.custom_data = vec![("quest".into(), NbtValue::Int(3))];
+        item.unbreakable = true;
+        item.attribute_modifiers = vec![AttributeModifier {
+            attribute: 2,
+            id: "legends:excalibur_damage".into(),
+            amount: 4.5,
+            operation: AttributeOperation::AddValue,
+            slot: EquipmentSlotGroup::MainHand,
+        }];
+        item.potion_contents = Some(PotionContents {
+            potion: Some(13),
+            custom_color: Some(0xFF00FF),
+            custom_effects: vec![PotionContentsEffect {
+                effect_id: 25,
+                amplifier: 1,
+                duration: -1,
+                ambient: false,
+                show_particles: true,
+                show_icon: true,
+            }],
+        });
+        item.banner_patterns = vec![BannerLayer { pattern: 4, color: 15 }, BannerLayer { pattern: 11, color: 3 }];
+        let slot = Some(item.with_enchantment(0, 2));
+        let mut buf = BytesMut::new();
+        write_slot(&mut buf, &slot);
+        let result = read_slot(&mut buf).unwrap();
+        assert_eq!(result, slot);
+        assert_eq!(buf.remaining(), 0);
+    }
+
+    #[test]
+    fn test_text_component_nbt_roundtrip() {
+        let text = TextComponent::plain("[Click]")
+            .color("green")
+            .underlined(true)
+            .click(ClickEvent::RunCommand("/spawn".into()))
+            .hover_text(TextComponent::colored("Teleport to spawn", "gray"))
+            .append(TextComponent::plain(" sword").hover(HoverEvent::ShowItem {
+                id: "minecraft:diamond_sword".into(),
+                count: 1,
+            }))
+            .append(TextComponent::translatable("disconnect.timeout", vec![TextComponent::plain("arg")]));
+        assert_eq!(text_component_from_nbt(&text_component_to_nbt(&text)), text);
+    }
 }
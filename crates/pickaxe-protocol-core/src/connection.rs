@@ -1,3 +1,4 @@
+use crate::capture::SharedCapture;
 use crate::codec::{read_varint, varint_len, write_varint};
 use aes::cipher::{BlockEncrypt, KeyInit};
 use aes::Aes128;
@@ -48,13 +49,18 @@ impl Cfb8Cipher {
     }
 }
 
+/// Largest packet accepted from a client unless `set_max_packet_size` lowers it.
+pub const DEFAULT_MAX_PACKET_SIZE: usize = 8 * 1024 * 1024;
+
 /// A framed Minecraft protocol connection with optional compression and encryption.
 pub struct Connection {
     stream: Option<TcpStream>,
     read_buf: BytesMut,
+    max_packet_size: usize,
     compression_threshold: Option<i32>,
     encryptor: Option<Cfb8Cipher>,
     decryptor: Option<Cfb8Cipher>,
+    capture: Option<SharedCapture>,
 }
 
 impl Connection {
@@ -62,9 +68,11 @@ impl Connection {
         Self {
             stream: Some(stream),
             read_buf: BytesMut::with_capacity(4096),
+            max_packet_size: DEFAULT_MAX_PACKET_SIZE,
             compression_threshold: None,
             encryptor: None,
             decryptor: None,
+            capture: None,
         }
     }
 
@@ -75,9 +83,11 @@ impl Connection {
         Self {
             stream: None,
             read_buf: BytesMut::new(),
+            max_packet_size: DEFAULT_MAX_PACKET_SIZE,
             compression_threshold: None,
             encryptor: None,
             decryptor: None,
+            capture: None,
         }
     }
 
@@ -96,6 +106,23 @@ impl Connection {
         self.compression_threshold = Some(threshold);
     }
 
+    /// Reject inbound packets larger than `bytes`, before or after decompression.
+    /// Carried over to the read half by `into_split`.
+    pub fn set_max_packet_size(&mut self, bytes: usize) {
+        self.max_packet_size = bytes.min(DEFAULT_MAX_PACKET_SIZE);
+    }
+
+    /// Record this connection's packets. The connection only carries the
+    /// capture, through `into_split` too; callers record what they send and
+    /// receive, since they know the state and the decoded packet.
+    pub fn set_capture(&mut self, capture: SharedCapture) {
+        self.capture = Some(capture);
+    }
+
+    pub fn capture(&self) -> Option<&SharedCapture> {
+        self.capture.as_ref()
+    }
+
     /// Read a single packet frame, returning (packet_id, payload).
     pub async fn read_packet(&mut self) -> anyhow::Result<(i32, BytesMut)> {
         let stream = self
@@ -103,8 +130,11 @@ impl Connection {
             .as_mut()
             .ok_or_else(|| anyhow::anyhow!("Connection has been split"))?;
         loop {
-            if let Some(result) = try_parse_packet(&mut self.read_buf, self.compression_threshold)?
-            {
+            if let Some(result) = try_parse_packet(
+                &mut self.read_buf,
+                self.max_packet_size,
+                self.compression_threshold,
+            )? {
                 return Ok(result);
             }
             let mut tmp = [0u8; 4096];
@@ -155,13 +185,16 @@ impl Connection {
             ConnectionReader {
                 stream: read_half,
                 read_buf: self.read_buf,
+                max_packet_size: self.max_packet_size,
                 compression_threshold: self.compression_threshold,
                 decryptor: self.decryptor,
+                capture: self.capture.clone(),
             },
             ConnectionWriter {
                 stream: write_half,
                 compression_threshold: self.compression_threshold,
                 encryptor: self.encryptor,
+                capture: self.capture,
             },
         )
     }
@@ -171,16 +204,24 @@ impl Connection {
 pub struct ConnectionReader {
     stream: OwnedReadHalf,
     read_buf: BytesMut,
+    max_packet_size: usize,
     compression_threshold: Option<i32>,
     decryptor: Option<Cfb8Cipher>,
+    capture: Option<SharedCapture>,
 }
 
 impl ConnectionReader {
+    pub fn capture(&self) -> Option<&SharedCapture> {
+        self.capture.as_ref()
+    }
+
     pub async fn read_packet(&mut self) -> anyhow::Result<(i32, BytesMut)> {
         loop {
-            if let Some(result) =
-                try_parse_packet(&mut self.read_buf, self.compression_threshold)?
-            {
+            if let Some(result) = try_parse_packet(
+                &mut self.read_buf,
+                self.max_packet_size,
+                self.compression_threshold,
+            )? {
                 return Ok(result);
             }
             let mut tmp = [0u8; 4096];
@@ -202,9 +243,14 @@ pub struct ConnectionWriter {
     stream: OwnedWriteHalf,
     compression_threshold: Option<i32>,
     encryptor: Option<Cfb8Cipher>,
+    capture: Option<SharedCapture>,
 }
 
 impl ConnectionWriter {
+    pub fn capture(&self) -> Option<&SharedCapture> {
+        self.capture.as_ref()
+    }
+
     pub async fn write_packet(&mut self, packet_id: i32, payload: &[u8]) -> anyhow::Result<()> {
         let frame = build_frame(
             packet_id,
@@ -221,6 +267,7 @@ impl ConnectionWriter {
 
 fn try_parse_packet(
     read_buf: &mut BytesMut,
+    max_packet_size: usize,
     compression_threshold: Option<i32>,
 ) -> anyhow::Result<Option<(i32, BytesMut)>> {
     if read_buf.is_empty() {
@@ -234,9 +281,9 @@ fn try_parse_packet(
         Err(_) => return Ok(None),
     };
 
-    // Cap raw packet size at 8 MB
-    if length > 8 * 1024 * 1024 {
-        anyhow::bail!("Packet length {} exceeds 8 MB limit", length);
+    // Reject oversized packets before buffering them
+    if length > max_packet_size {
+        anyhow::bail!("Packet length {} exceeds {} byte limit", length, max_packet_size);
     }
 
     let varint_bytes = read_buf.len() - peek.len();
@@ -251,9 +298,14 @@ fn try_parse_packet(
     if let Some(_threshold) = compression_threshold {
         let data_length = read_varint(&mut packet_data)? as usize;
         if data_length > 0 {
-            // Cap at 8 MB to prevent malicious clients from causing OOM
-            if data_length > 8 * 1024 * 1024 {
-                anyhow::bail!("Decompressed packet size {} exceeds 8 MB limit", data_length);
+            // Checked before allocating, so a small compressed packet can't
+            // claim a huge size and exhaust memory
+            if data_length > max_packet_size {
+                anyhow::bail!(
+                    "Decompressed packet size {} exceeds {} byte limit",
+                    data_length,
+                    max_packet_size
+                );
             }
             let mut decompressed = vec![0u8; data_length];
             let mut decoder = ZlibDecoder::new(&packet_data[..]);
@@ -2,10 +2,13 @@ pub mod codec;
 pub mod state;
 pub mod packets;
 pub mod adapter;
+pub mod capture;
 pub mod connection;
+pub mod shared;
 
 pub use codec::*;
 pub use state::*;
 pub use packets::*;
 pub use adapter::*;
-pub use connection::{Connection, ConnectionReader, ConnectionWriter};
+pub use shared::SharedPacket;
+pub use connection::{Connection, ConnectionReader, ConnectionWriter, DEFAULT_MAX_PACKET_SIZE};
@@ -35,6 +35,11 @@ pub enum InternalPacket {
         shared_secret: Vec<u8>,
         verify_token: Vec<u8>,
     },
+    /// None if the client didn't understand the channel.
+    LoginPluginResponse {
+        message_id: i32,
+        data: Option<Vec<u8>>,
+    },
     LoginAcknowledged,
 
     // === Login (clientbound) ===
@@ -49,6 +54,11 @@ pub enum InternalPacket {
     LoginSuccess {
         profile: GameProfile,
     },
+    LoginPluginRequest {
+        message_id: i32,
+        channel: String,
+        data: Vec<u8>,
+    },
 
     // === Configuration (serverbound) ===
     ClientInformation {
@@ -61,6 +71,7 @@ pub enum InternalPacket {
         text_filtering: bool,
         allow_listing: bool,
     },
+    /// Sent both ways, in Configuration and Play.
     PluginMessage {
         channel: String,
         data: Vec<u8>,
@@ -89,6 +100,28 @@ pub enum InternalPacket {
         overlay: bool,
     },
 
+    /// Player Chat Message (0x39 clientbound, protocol 767) — a player's
+    /// chat, with the signature other clients verify.
+    PlayerChat {
+        sender: Uuid,
+        /// Position in the sender's chain of signed messages this session.
+        index: i32,
+        /// 256-byte RSA signature; None for unsigned chat.
+        signature: Option<Vec<u8>>,
+        message: String,
+        /// Milliseconds since the epoch, as signed.
+        timestamp: i64,
+        salt: i64,
+        /// Signatures of the messages the sender had seen, as signed.
+        previous_messages: Vec<Vec<u8>>,
+        /// Shown instead of `message` when the server changed it.
+        unsigned_content: Option<TextComponent>,
+        /// Index in the `minecraft:chat_type` registry.
+        chat_type: i32,
+        sender_name: TextComponent,
+        target_name: Option<TextComponent>,
+    },
+
     /// Player Info Update (0x3E clientbound, protocol 767)
     /// Bitmask-driven: only sends fields indicated by actions.
     PlayerInfoUpdate {
@@ -132,6 +165,9 @@ pub enum InternalPacket {
         chunk_x: i32,
         chunk_z: i32,
     },
+    SetSimulationDistance {
+        simulation_distance: i32,
+    },
     ChunkDataAndUpdateLight {
         chunk_x: i32,
         chunk_z: i32,
@@ -151,6 +187,13 @@ pub enum InternalPacket {
         event: u8,
         value: f32,
     },
+    /// Player Abilities (clientbound): flags are 0x01 invulnerable, 0x02 flying,
+    /// 0x04 allow flying, 0x08 creative mode (instant break).
+    PlayerAbilities {
+        flags: u8,
+        flying_speed: f32,
+        fov_modifier: f32,
+    },
     SetDefaultSpawnPosition {
         position: pickaxe_types::BlockPos,
         angle: f32,
@@ -163,6 +206,20 @@ pub enum InternalPacket {
     AcknowledgeBlockChange {
         sequence: i32,
     },
+    /// Update Advancements (0x74 clientbound, protocol 767). `reset` clears
+    /// the client's tree first; `progress` lists each advancement's criteria
+    /// with the time they were obtained (milliseconds since the epoch).
+    UpdateAdvancements {
+        reset: bool,
+        added: Vec<AdvancementEntry>,
+        removed: Vec<String>,
+        progress: Vec<(String, Vec<(String, Option<i64>)>)>,
+    },
+    /// Award Statistics (0x04 clientbound, protocol 767) — the reply to a
+    /// Client Command "request stats". Each entry is (category, statistic, value).
+    AwardStatistics {
+        stats: Vec<(i32, i32, i32)>,
+    },
     /// Set Block Destroy Stage (0x06 clientbound, protocol 767)
     SetBlockDestroyStage {
         entity_id: i32,
@@ -177,6 +234,17 @@ pub enum InternalPacket {
         batch_size: i32,
     },
 
+    /// Set Ticking State (0x71 clientbound) — the client runs its own
+    /// entity and animation ticks at this rate, or not at all when frozen.
+    TickingState {
+        tick_rate: f32,
+        is_frozen: bool,
+    },
+    /// Step Tick (0x72 clientbound) — ticks a frozen client should advance.
+    StepTick {
+        steps: i32,
+    },
+
     /// Update Time (0x64 clientbound, protocol 767)
     UpdateTime {
         world_age: i64,
@@ -257,6 +325,15 @@ pub enum InternalPacket {
         root_index: i32,
     },
 
+    /// Command Suggestions Response (0x10 CB) — completions for the text
+    /// from `start` (counted from the leading `/`) for `length` bytes.
+    CommandSuggestionsResponse {
+        transaction_id: i32,
+        start: i32,
+        length: i32,
+        matches: Vec<String>,
+    },
+
     /// Set Container Content (0x13 CB) — sends entire inventory.
     SetContainerContent {
         window_id: u8,
@@ -403,6 +480,57 @@ pub enum InternalPacket {
         text: TextComponent,
     },
 
+    /// Change Difficulty (0x0B CB) — difficulty shown in the client's options screen.
+    ChangeDifficulty {
+        difficulty: u8,
+        locked: bool,
+    },
+
+    /// Clear Titles (0x0F CB) — hide the current title; `reset` also restores
+    /// the default fade times and forgets the pending subtitle.
+    ClearTitles {
+        reset: bool,
+    },
+
+    /// Update Objectives (0x5E CB) — create (0), remove (1) or update (2) a
+    /// scoreboard objective. `render_type` is 0 for integer, 1 for hearts.
+    UpdateObjectives {
+        name: String,
+        mode: u8,
+        display_name: TextComponent,
+        render_type: i32,
+    },
+
+    /// Display Objective (0x57 CB) — show an objective in a display slot
+    /// (0 list, 1 sidebar, 2 below name, 3-18 team sidebars). Empty name clears it.
+    DisplayObjective {
+        position: i32,
+        objective: String,
+    },
+
+    /// Update Score (0x61 CB) — set a score holder's value in an objective.
+    UpdateScore {
+        entity_name: String,
+        objective: String,
+        value: i32,
+    },
+
+    /// Reset Score (0x44 CB) — remove a holder's score from one objective, or all if None.
+    ResetScore {
+        entity_name: String,
+        objective: Option<String>,
+    },
+
+    /// Update Teams (0x60 CB) — mode 0 create, 1 remove, 2 update info,
+    /// 3 add entities, 4 remove entities. `info` is sent for modes 0 and 2,
+    /// `entities` for modes 0, 3 and 4.
+    UpdateTeams {
+        name: String,
+        mode: u8,
+        info: Option<TeamInfo>,
+        entities: Vec<String>,
+    },
+
     /// Level Particles (0x29 CB) — spawn particle effects.
     LevelParticles {
         particle_id: i32,
@@ -429,6 +557,23 @@ pub enum InternalPacket {
         acknowledged: [u8; 3],
     },
 
+    /// Acknowledge Message (0x03 serverbound) — messages seen since the
+    /// last acknowledgement, sent when the client has many pending.
+    ChatAck {
+        offset: i32,
+    },
+
+    /// Player Session (0x07 serverbound) — the client's chat signing key.
+    PlayerSession {
+        session: ChatSession,
+    },
+
+    /// Chunk Batch Received (0x08 serverbound) — the client loaded a batch
+    /// and can take this many chunks per tick.
+    ChunkBatchReceived {
+        chunks_per_tick: f32,
+    },
+
     /// Chat Command (0x04 serverbound, protocol 767)
     ChatCommand {
         command: String,
@@ -445,6 +590,13 @@ pub enum InternalPacket {
         item: Option<ItemStack>,
     },
 
+    /// Command Suggestions Request (0x0B serverbound, protocol 767) — the
+    /// client asks for completions of a command line (starting with `/`).
+    CommandSuggestionsRequest {
+        transaction_id: i32,
+        text: String,
+    },
+
     /// Client Command (0x09 serverbound, protocol 767)
     /// action: 0=respawn, 1=request_stats
     ClientCommand {
@@ -470,6 +622,12 @@ pub enum InternalPacket {
         carried_item: Option<ItemStack>,
     },
 
+    /// Click Container Button (0x0D SB) — e.g. lectern page buttons.
+    ContainerButtonClick {
+        window_id: u8,
+        button_id: u8,
+    },
+
     /// Close Container (0x0F SB) — client closed a container.
     ClientCloseContainer {
         container_id: u8,
@@ -615,6 +773,15 @@ pub enum InternalPacket {
         block_interaction: i32,
     },
 
+    /// Block Action (0x08 CB) — block animations such as bell rings and chest lids.
+    BlockAction {
+        position: BlockPos,
+        action_id: u8,
+        action_param: u8,
+        /// Block registry ID (not a block state)
+        block_type: i32,
+    },
+
     /// World Event / Level Event (0x28 CB) — block break particles, sounds, etc.
     WorldEvent {
         event: i32,
@@ -643,12 +810,58 @@ pub enum InternalPacket {
     Disconnect {
         reason: TextComponent,
     },
+    /// Add Resource Pack (clientbound, Configuration and Play)
+    AddResourcePack {
+        uuid: Uuid,
+        url: String,
+        /// Hex SHA-1 of the pack, or empty to skip the check.
+        hash: String,
+        /// The client disconnects itself if the player declines.
+        forced: bool,
+        prompt: Option<TextComponent>,
+    },
+    /// Remove Resource Pack (clientbound, Configuration and Play); None removes all.
+    RemoveResourcePack {
+        uuid: Option<Uuid>,
+    },
+    /// Cookie Request (clientbound, Login, Configuration and Play)
+    CookieRequest {
+        key: String,
+    },
+    /// Cookie Response (serverbound, Login, Configuration and Play); None if
+    /// the client has no cookie under `key`.
+    CookieResponse {
+        key: String,
+        payload: Option<Vec<u8>>,
+    },
+    /// Store Cookie (clientbound, Configuration and Play). Kept by the client
+    /// across transfers, at most 5 KiB.
+    StoreCookie {
+        key: String,
+        payload: Vec<u8>,
+    },
+    /// Transfer (clientbound, Configuration and Play): reconnect to another server.
+    Transfer {
+        host: String,
+        port: i32,
+    },
+    /// Resource Pack Response (serverbound, Configuration and Play)
+    ResourcePackResponse {
+        uuid: Uuid,
+        /// 0 loaded, 1 declined, 2 download failed, 3 accepted, 4 downloaded,
+        /// 5 invalid URL, 6 reload failed, 7 discarded.
+        result: i32,
+    },
 
     /// Unknown / unhandled packet — raw bytes preserved.
     Unknown {
         packet_id: i32,
         data: Vec<u8>,
     },
+
+    /// A clientbound Play packet broadcast to many players, encoded once per
+    /// protocol by the connection writers. Adapters never see it.
+    Shared(crate::SharedPacket),
 }
 
 /// Player Info Update action bitmask flags.
@@ -677,6 +890,63 @@ pub struct PlayerInfoEntry {
     pub ping: Option<i32>,
     /// Present when UPDATE_DISPLAY_NAME action is set.
     pub display_name: Option<TextComponent>,
+    /// Sent with INITIALIZE_CHAT; None clears the player's session.
+    pub chat_session: Option<ChatSession>,
+}
+
+/// A player's chat session: the Mojang-signed public key their client signs
+/// chat with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatSession {
+    pub session_id: Uuid,
+    /// Key expiry in milliseconds since the epoch.
+    pub expires_at: i64,
+    /// DER-encoded RSA public key.
+    pub public_key: Vec<u8>,
+    /// Mojang's signature over the player's UUID, expiry and key.
+    pub key_signature: Vec<u8>,
+}
+
+/// Team parameters carried by UpdateTeams create/update.
+#[derive(Debug, Clone)]
+pub struct TeamInfo {
+    pub display_name: TextComponent,
+    /// 0x01 = allow friendly fire, 0x02 = see invisible teammates.
+    pub friendly_flags: u8,
+    /// "always", "hideForOtherTeams", "hideForOwnTeam", or "never".
+    pub name_tag_visibility: String,
+    /// "always", "pushOtherTeams", "pushOwnTeam", or "never".
+    pub collision_rule: String,
+    /// Chat formatting ID: 0-15 for colors, 21 for reset.
+    pub color: i32,
+    pub prefix: TextComponent,
+    pub suffix: TextComponent,
+}
+
+/// One advancement in an UpdateAdvancements packet.
+#[derive(Debug, Clone)]
+pub struct AdvancementEntry {
+    pub id: String,
+    pub parent: Option<String>,
+    /// None for advancements that never show in the client's screen.
+    pub display: Option<AdvancementDisplay>,
+    /// Groups of criterion names; one of each group must be obtained.
+    pub requirements: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AdvancementDisplay {
+    pub title: TextComponent,
+    pub description: TextComponent,
+    pub icon: Option<ItemStack>,
+    /// 0 = task, 1 = challenge, 2 = goal.
+    pub frame: i32,
+    /// Tab background texture; set on root advancements.
+    pub background: Option<String>,
+    pub show_toast: bool,
+    pub hidden: bool,
+    pub x: f32,
+    pub y: f32,
 }
 
 /// A single entity metadata entry for SetEntityMetadata.
@@ -724,6 +994,8 @@ pub struct CommandNode {
     pub parser: Option<String>,
     /// Extra parser properties (raw bytes, parser-specific).
     pub parser_properties: Option<Vec<u8>>,
+    /// Suggestions type (e.g. "minecraft:ask_server"); set with flag bit 4.
+    pub suggestions_type: Option<String>,
 }
 
 impl Default for ChunkLightData {
@@ -0,0 +1,122 @@
+use crate::{read_varint, ConnectionState, InternalPacket, ProtocolAdapter};
+use anyhow::Result;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// A packet sent to many players. Cloning it is cheap, and it is encoded at
+/// most once per protocol version, with every writer sharing the bytes.
+/// Compression and encryption still happen per connection.
+#[derive(Clone)]
+pub struct SharedPacket(Arc<Inner>);
+
+struct Inner {
+    packet: InternalPacket,
+    /// (protocol version, packet ID, payload after the ID)
+    frames: Mutex<Vec<(i32, i32, Arc<[u8]>)>>,
+}
+
+impl SharedPacket {
+    pub fn new(packet: InternalPacket) -> Self {
+        Self(Arc::new(Inner {
+            packet,
+            frames: Mutex::new(Vec::new()),
+        }))
+    }
+
+    pub fn packet(&self) -> &InternalPacket {
+        &self.0.packet
+    }
+
+    /// The packet ID and payload for the adapter's protocol, encoding them
+    /// if no writer on that protocol has yet.
+    pub fn encode(&self, adapter: &dyn ProtocolAdapter, state: ConnectionState) -> Result<(i32, Arc<[u8]>)> {
+        let protocol = adapter.protocol_version();
+        // Held while encoding, so writers racing on the same packet wait for
+        // the first one instead of encoding it again
+        let mut frames = self.0.frames.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, id, payload)) = frames.iter().find(|(p, _, _)| *p == protocol) {
+            return Ok((*id, payload.clone()));
+        }
+        let mut data = adapter.encode_packet(state, &self.0.packet)?;
+        let id = read_varint(&mut data)?;
+        let payload: Arc<[u8]> = Arc::from(&data[..]);
+        frames.push((protocol, id, payload.clone()));
+        Ok((id, payload))
+    }
+}
+
+impl fmt::Debug for SharedPacket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.packet.fmt(f)
+    }
+}
+
+impl InternalPacket {
+    /// Wrap the packet to send the same bytes to many players.
+    pub fn shared(self) -> InternalPacket {
+        match self {
+            InternalPacket::Shared(_) => self,
+            packet => InternalPacket::Shared(SharedPacket::new(packet)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+    use pickaxe_data::registries::Registries;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountingAdapter {
+        protocol: i32,
+        encoded: AtomicUsize,
+    }
+
+    impl ProtocolAdapter for CountingAdapter {
+        fn protocol_version(&self) -> i32 {
+            self.protocol
+        }
+
+        fn version_name(&self) -> &'static str {
+            "test"
+        }
+
+        fn decode_packet(&self, _: ConnectionState, _: i32, _: &mut BytesMut) -> Result<InternalPacket> {
+            anyhow::bail!("not decoding")
+        }
+
+        fn encode_packet(&self, _: ConnectionState, packet: &InternalPacket) -> Result<BytesMut> {
+            self.encoded.fetch_add(1, Ordering::Relaxed);
+            let InternalPacket::KeepAliveClientbound { id } = packet else {
+                anyhow::bail!("unexpected packet");
+            };
+            let mut buf = BytesMut::new();
+            crate::write_varint(&mut buf, self.protocol);
+            buf.extend_from_slice(&id.to_be_bytes());
+            Ok(buf)
+        }
+
+        fn registry_data(&self, _: &Registries) -> Vec<InternalPacket> {
+            Vec::new()
+        }
+    }
+
+    #[test]
+    fn test_encodes_once_per_protocol() {
+        let new = CountingAdapter { protocol: 0x27, encoded: AtomicUsize::new(0) };
+        let old = CountingAdapter { protocol: 0x26, encoded: AtomicUsize::new(0) };
+        let packet = InternalPacket::KeepAliveClientbound { id: 7 }.shared();
+        let InternalPacket::Shared(shared) = packet.clone().shared() else {
+            panic!("not shared");
+        };
+
+        for _ in 0..3 {
+            assert_eq!(shared.encode(&new, ConnectionState::Play).unwrap(), (0x27, Arc::from(&7i64.to_be_bytes()[..])));
+        }
+        assert_eq!(shared.encode(&old, ConnectionState::Play).unwrap().0, 0x26);
+        assert_eq!(new.encoded.load(Ordering::Relaxed), 1);
+        assert_eq!(old.encoded.load(Ordering::Relaxed), 1);
+        assert_eq!(format!("{:?}", packet), "Shared(KeepAliveClientbound { id: 7 })");
+    }
+}
@@ -8,11 +8,15 @@ pub enum ConnectionState {
     Play,
 }
 
+/// Handshake intent of a client sent by another server's Transfer packet.
+/// It logs in like any other client.
+pub const HANDSHAKE_TRANSFER: i32 = 3;
+
 impl ConnectionState {
     pub fn from_handshake_next(next: i32) -> Option<Self> {
         match next {
             1 => Some(ConnectionState::Status),
-            2 => Some(ConnectionState::Login),
+            2 | HANDSHAKE_TRANSFER => Some(ConnectionState::Login),
             _ => None,
         }
     }
@@ -0,0 +1,193 @@
+use anyhow::Result;
+use bytes::BytesMut;
+use pickaxe_data::registries::Registries;
+use pickaxe_protocol_core::*;
+use pickaxe_protocol_v1_21::V1_21Adapter;
+use pickaxe_types::ItemStack;
+
+use crate::remap;
+
+/// Registries 1.21 added to the Configuration sync. A 1.20.6 client rejects
+/// registries it doesn't know, so they are left out.
+const NEW_REGISTRIES: [&str; 3] = ["minecraft:enchantment", "minecraft:jukebox_song", "minecraft:painting_variant"];
+
+/// Entity metadata serializer ID for an item slot.
+const METADATA_SLOT: i32 = 7;
+
+/// Adapter for 1.20.5 and 1.20.6 (protocol 766).
+///
+/// Every packet the server uses has the same ID and layout as in 1.21, so
+/// this wraps `V1_21Adapter` and only translates registry contents: the
+/// synced registries, item IDs and enchantment IDs. Particle IDs are passed
+/// through unchanged.
+pub struct V1_20_6Adapter {
+    inner: V1_21Adapter,
+}
+
+impl V1_20_6Adapter {
+    pub fn new() -> Self {
+        Self {
+            inner: V1_21Adapter::new(),
+        }
+    }
+}
+
+impl ProtocolAdapter for V1_20_6Adapter {
+    fn protocol_version(&self) -> i32 {
+        766
+    }
+
+    fn version_name(&self) -> &'static str {
+        "1.20.6"
+    }
+
+    fn decode_packet(
+        &self,
+        state: ConnectionState,
+        id: i32,
+        data: &mut BytesMut,
+    ) -> Result<InternalPacket> {
+        let mut packet = self.inner.decode_packet(state, id, data)?;
+        match &mut packet {
+            InternalPacket::CreativeInventoryAction { item, .. } => stack_from_client(item),
+            InternalPacket::ContainerClick {
+                changed_slots,
+                carried_item,
+                ..
+            } => {
+                for (_, item) in changed_slots {
+                    stack_from_client(item);
+                }
+                stack_from_client(carried_item);
+            }
+            _ => {}
+        }
+        Ok(packet)
+    }
+
+    fn encode_packet(
+        &self,
+        state: ConnectionState,
+        packet: &InternalPacket,
+    ) -> Result<BytesMut> {
+        match to_client(packet) {
+            Some(translated) => self.inner.encode_packet(state, &translated),
+            None => self.inner.encode_packet(state, packet),
+        }
+    }
+
+    fn registry_data(&self, registries: &Registries) -> Vec<InternalPacket> {
+        self.inner
+            .registry_data(registries)
+            .into_iter()
+            .filter(|packet| {
+                !matches!(packet, InternalPacket::RegistryData { registry_id, .. }
+                    if NEW_REGISTRIES.contains(&registry_id.as_str()))
+            })
+            .collect()
+    }
+}
+
+/// A copy of `packet` with its items translated for a 1.20.6 client, or
+/// None if it carries no items.
+fn to_client(packet: &InternalPacket) -> Option<InternalPacket> {
+    let mut packet = match packet {
+        InternalPacket::SetContainerContent { .. }
+        | InternalPacket::SetContainerSlot { .. }
+        | InternalPacket::SetEquipment { .. }
+        | InternalPacket::UpdateAdvancements { .. } => packet.clone(),
+        InternalPacket::SetEntityMetadata { metadata, .. }
+            if metadata.iter().any(|entry| entry.type_id == METADATA_SLOT) =>
+        {
+            packet.clone()
+        }
+        _ => return None,
+    };
+    match &mut packet {
+        InternalPacket::SetContainerContent {
+            slots,
+            carried_item,
+            ..
+        } => {
+            for item in slots {
+                stack_to_client(item);
+            }
+            stack_to_client(carried_item);
+        }
+        InternalPacket::SetContainerSlot { item, .. } => stack_to_client(item),
+        InternalPacket::SetEquipment { equipment, .. } => {
+            for (_, item) in equipment {
+                stack_to_client(item);
+            }
+        }
+        InternalPacket::UpdateAdvancements { added, .. } => {
+            for display in added.iter_mut().filter_map(|entry| entry.display.as_mut()) {
+                stack_to_client(&mut display.icon);
+            }
+        }
+        InternalPacket::SetEntityMetadata { metadata, .. } => {
+            for entry in metadata.iter_mut().filter(|entry| entry.type_id == METADATA_SLOT) {
+                let Ok(mut item) = read_slot(&mut BytesMut::from(&entry.data[..])) else {
+                    continue;
+                };
+                stack_to_client(&mut item);
+                let mut buf = BytesMut::new();
+                write_slot(&mut buf, &item);
+                entry.data = buf.to_vec();
+            }
+        }
+        _ => {}
+    }
+    Some(packet)
+}
+
+fn stack_to_client(item: &mut Option<ItemStack>) {
+    if let Some(item) = item {
+        item.item_id = remap::item_to_client(item.item_id);
+        for (id, _) in &mut item.enchantments {
+            *id = remap::enchantment_to_client(*id);
+        }
+        // 1.20.6 numbers the banner_patterns component one lower
+        item.banner_patterns.clear();
+    }
+}
+
+fn stack_from_client(item: &mut Option<ItemStack>) {
+    if let Some(item) = item {
+        item.item_id = remap::item_from_client(item.item_id);
+        for (id, _) in &mut item.enchantments {
+            *id = remap::enchantment_from_client(*id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translates_items_and_registries() {
+        let adapter = V1_20_6Adapter::new();
+        assert!(adapter.registry_data(&Registries::default()).iter().all(|packet| match packet {
+            InternalPacket::RegistryData { registry_id, .. } => !NEW_REGISTRIES.contains(&registry_id.as_str()),
+            _ => true,
+        }));
+
+        let pigstep = pickaxe_data::item_name_to_id("music_disc_pigstep").unwrap();
+        let mut buf = BytesMut::new();
+        write_slot(&mut buf, &Some(ItemStack::new(pigstep, 1)));
+        let packet = InternalPacket::SetEntityMetadata {
+            entity_id: 1,
+            metadata: vec![EntityMetadataEntry {
+                index: 8,
+                type_id: METADATA_SLOT,
+                data: buf.to_vec(),
+            }],
+        };
+        let Some(InternalPacket::SetEntityMetadata { metadata, .. }) = to_client(&packet) else {
+            panic!("metadata with an item should be translated");
+        };
+        let item = read_slot(&mut BytesMut::from(&metadata[0].data[..])).unwrap().unwrap();
+        assert_eq!(item.item_id, pigstep - 2);
+    }
+}
@@ -0,0 +1,4 @@
+mod adapter;
+mod remap;
+
+pub use adapter::V1_20_6Adapter;
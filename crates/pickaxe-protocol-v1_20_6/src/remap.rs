@@ -0,0 +1,71 @@
+/// Items added in 1.21: music_disc_creator, music_disc_creator_music_box and
+/// music_disc_precipice. Every later item ID is shifted by those before it.
+const NEW_ITEMS: [i32; 3] = [1172, 1173, 1186];
+
+/// Shown to 1.20.6 clients in place of an item they don't have (music_disc_13).
+const FALLBACK_ITEM: i32 = 1168;
+
+/// 1.21.1 item ID → 1.20.6 item ID.
+pub fn item_to_client(id: i32) -> i32 {
+    if NEW_ITEMS.contains(&id) {
+        return FALLBACK_ITEM;
+    }
+    id - NEW_ITEMS.iter().filter(|&&new| new < id).count() as i32
+}
+
+/// 1.20.6 item ID → 1.21.1 item ID.
+pub fn item_from_client(id: i32) -> i32 {
+    NEW_ITEMS.iter().fold(id, |id, &new| if id >= new { id + 1 } else { id })
+}
+
+/// 1.21.1 enchantment ID → 1.20.6 enchantment ID. The server's registry puts
+/// mending and curse of vanishing (37, 38) before the mace enchantments;
+/// 1.20.6's built-in order puts density, breach and wind burst first.
+pub fn enchantment_to_client(id: i32) -> i32 {
+    match id {
+        37 | 38 => id + 3,
+        39..=41 => id - 2,
+        _ => id,
+    }
+}
+
+/// 1.20.6 enchantment ID → 1.21.1 enchantment ID.
+pub fn enchantment_from_client(id: i32) -> i32 {
+    match id {
+        37..=39 => id + 2,
+        40 | 41 => id - 3,
+        _ => id,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_item_remap() {
+        let id = |name| pickaxe_data::item_name_to_id(name).unwrap();
+        assert_eq!(NEW_ITEMS, [id("music_disc_creator"), id("music_disc_creator_music_box"), id("music_disc_precipice")]);
+        assert_eq!(FALLBACK_ITEM, id("music_disc_13"));
+
+        assert_eq!(item_to_client(id("stone")), id("stone"));
+        assert_eq!(item_to_client(id("music_disc_far")), id("music_disc_far") - 2);
+        assert_eq!(item_to_client(id("disc_fragment_5")), id("disc_fragment_5") - 3);
+        assert_eq!(item_to_client(id("music_disc_precipice")), FALLBACK_ITEM);
+        for name in ["stone", "music_disc_chirp", "music_disc_far", "music_disc_pigstep", "disc_fragment_5", "trident"] {
+            assert_eq!(item_from_client(item_to_client(id(name))), id(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_enchantment_remap() {
+        let id = |name| pickaxe_data::enchantment_name_to_id(name).unwrap();
+        assert_eq!(enchantment_to_client(id("sharpness")), id("sharpness"));
+        assert_eq!(enchantment_to_client(id("density")), 37);
+        assert_eq!(enchantment_to_client(id("mending")), 40);
+        assert_eq!(enchantment_to_client(id("vanishing_curse")), 41);
+        for server_id in 0..42 {
+            assert_eq!(enchantment_from_client(enchantment_to_client(server_id)), server_id);
+        }
+    }
+}
@@ -1,8 +1,9 @@
 use anyhow::{bail, Result};
 use bytes::{Buf, BufMut, BytesMut};
-use pickaxe_nbt::NbtValue;
+use pickaxe_data::registries::Registries;
 use pickaxe_protocol_core::*;
-use pickaxe_types::BlockPos;
+use pickaxe_types::{BlockPos, TextComponent};
+use uuid::Uuid;
 
 use crate::registries;
 
@@ -19,6 +20,10 @@ impl ProtocolAdapter for V1_21Adapter {
         767
     }
 
+    fn version_name(&self) -> &'static str {
+        "1.21.1"
+    }
+
     fn decode_packet(
         &self,
         state: ConnectionState,
@@ -48,8 +53,8 @@ impl ProtocolAdapter for V1_21Adapter {
         }
     }
 
-    fn registry_data(&self) -> Vec<InternalPacket> {
-        registries::build_registry_packets()
+    fn registry_data(&self, registries: &Registries) -> Vec<InternalPacket> {
+        registries::build_registry_packets(registries)
     }
 }
 
@@ -64,16 +69,31 @@ const LOGIN_DISCONNECT: i32 = 0x00;
 const ENCRYPTION_REQUEST: i32 = 0x01;
 const LOGIN_SUCCESS: i32 = 0x02;
 const SET_COMPRESSION: i32 = 0x03;
+const LOGIN_PLUGIN_REQUEST: i32 = 0x04;
+const LOGIN_COOKIE_REQUEST: i32 = 0x05;
 
 // Configuration clientbound
+const CONFIG_COOKIE_REQUEST: i32 = 0x00;
+const CONFIG_PLUGIN_MESSAGE: i32 = 0x01;
 const CONFIG_FINISH: i32 = 0x03;
 const CONFIG_REGISTRY_DATA: i32 = 0x07;
 const CONFIG_KNOWN_PACKS: i32 = 0x0E;
+const CONFIG_REMOVE_RESOURCE_PACK: i32 = 0x08;
+const CONFIG_ADD_RESOURCE_PACK: i32 = 0x09;
+const CONFIG_STORE_COOKIE: i32 = 0x0A;
+const CONFIG_TRANSFER: i32 = 0x0B;
 
 // Play clientbound
+const PLAY_AWARD_STATISTICS: i32 = 0x04;
 const PLAY_ACK_BLOCK_CHANGE: i32 = 0x05;
 const PLAY_BLOCK_DESTROY_STAGE: i32 = 0x06;
+const PLAY_BLOCK_EVENT: i32 = 0x08;
 const PLAY_BLOCK_UPDATE: i32 = 0x09;
+const PLAY_COOKIE_REQUEST: i32 = 0x16;
+const PLAY_PLUGIN_MESSAGE: i32 = 0x19;
+const PLAY_STORE_COOKIE: i32 = 0x6B;
+const PLAY_TRANSFER: i32 = 0x73;
+const PLAY_UPDATE_ADVANCEMENTS: i32 = 0x74;
 const PLAY_DISCONNECT: i32 = 0x1D;
 const PLAY_UNLOAD_CHUNK: i32 = 0x21;
 const PLAY_GAME_EVENT: i32 = 0x22;
@@ -84,8 +104,10 @@ const PLAY_PLAYER_REMOVE: i32 = 0x3D;
 const PLAY_PLAYER_INFO: i32 = 0x3E;
 const PLAY_SYNC_PLAYER_POS: i32 = 0x40;
 const PLAY_SET_CENTER_CHUNK: i32 = 0x54;
+const PLAY_SET_SIMULATION_DISTANCE: i32 = 0x62;
 const PLAY_SET_DEFAULT_SPAWN: i32 = 0x56;
 const PLAY_SYSTEM_CHAT: i32 = 0x6C;
+const PLAY_PLAYER_CHAT: i32 = 0x39;
 const PLAY_SPAWN_ENTITY: i32 = 0x01;
 const PLAY_REMOVE_ENTITIES: i32 = 0x42;
 const PLAY_UPDATE_ENTITY_POS: i32 = 0x2E;
@@ -93,6 +115,9 @@ const PLAY_UPDATE_ENTITY_POS_ROT: i32 = 0x2F;
 const PLAY_UPDATE_ENTITY_ROT: i32 = 0x30;
 const PLAY_SET_HEAD_ROTATION: i32 = 0x48;
 const PLAY_TELEPORT_ENTITY: i32 = 0x70;
+const PLAY_TICKING_STATE: i32 = 0x71;
+const PLAY_STEP_TICK: i32 = 0x72;
+const PLAY_COMMAND_SUGGESTIONS: i32 = 0x10;
 const PLAY_DECLARE_COMMANDS: i32 = 0x11;
 const PLAY_SET_CONTAINER_CONTENT: i32 = 0x13;
 const PLAY_SET_CONTAINER_SLOT: i32 = 0x15;
@@ -126,7 +151,17 @@ const PLAY_SET_ACTION_BAR_TEXT: i32 = 0x4C;
 const PLAY_SET_SUBTITLE_TEXT: i32 = 0x63;
 const PLAY_SET_TITLE_TEXT: i32 = 0x65;
 const PLAY_SET_TITLES_ANIMATION: i32 = 0x66;
+const PLAY_CLEAR_TITLES: i32 = 0x0F;
+const PLAY_CHANGE_DIFFICULTY: i32 = 0x0B;
 const PLAY_TAB_LIST: i32 = 0x6D;
+const PLAY_PLAYER_ABILITIES: i32 = 0x38;
+const PLAY_DISPLAY_OBJECTIVE: i32 = 0x57;
+const PLAY_UPDATE_OBJECTIVES: i32 = 0x5E;
+const PLAY_UPDATE_SCORE: i32 = 0x61;
+const PLAY_RESET_SCORE: i32 = 0x44;
+const PLAY_REMOVE_RESOURCE_PACK: i32 = 0x45;
+const PLAY_ADD_RESOURCE_PACK: i32 = 0x46;
+const PLAY_UPDATE_TEAMS: i32 = 0x60;
 
 // === Decode functions ===
 
@@ -134,7 +169,9 @@ fn decode_handshaking(id: i32, data: &mut BytesMut) -> Result<InternalPacket> {
     match id {
         0x00 => {
             let protocol_version = read_varint(data)?;
-            let server_address = read_string(data, 255)?;
+            // BungeeCord forwarding appends the player's IP, UUID and
+            // profile to the address, so allow more than a hostname
+            let server_address = read_string(data, 32767)?;
             let server_port = read_u16(data)?;
             let next_state = read_varint(data)?;
             Ok(InternalPacket::Handshake {
@@ -180,7 +217,14 @@ fn decode_login(id: i32, data: &mut BytesMut) -> Result<InternalPacket> {
                 verify_token,
             })
         }
+        0x02 => {
+            let message_id = read_varint(data)?;
+            let successful = read_u8(data)? != 0;
+            let data = successful.then(|| data.split().to_vec());
+            Ok(InternalPacket::LoginPluginResponse { message_id, data })
+        }
         0x03 => Ok(InternalPacket::LoginAcknowledged),
+        0x04 => decode_cookie_response(data),
         _ => Ok(InternalPacket::Unknown {
             packet_id: id,
             data: data.to_vec(),
@@ -190,36 +234,11 @@ fn decode_login(id: i32, data: &mut BytesMut) -> Result<InternalPacket> {
 
 fn decode_configuration(id: i32, data: &mut BytesMut) -> Result<InternalPacket> {
     match id {
-        0x00 => {
-            let locale = read_string(data, 16)?;
-            let view_distance = read_i8(data)?;
-            let chat_mode = read_varint(data)?;
-            let chat_colors = read_u8(data)? != 0;
-            let skin_parts = read_u8(data)?;
-            let main_hand = read_varint(data)?;
-            let text_filtering = read_u8(data)? != 0;
-            let allow_listing = read_u8(data)? != 0;
-            Ok(InternalPacket::ClientInformation {
-                locale,
-                view_distance,
-                chat_mode,
-                chat_colors,
-                skin_parts,
-                main_hand,
-                text_filtering,
-                allow_listing,
-            })
-        }
-        0x02 => {
-            let channel = read_string(data, 32767)?;
-            let remaining = data.to_vec();
-            data.advance(remaining.len());
-            Ok(InternalPacket::PluginMessage {
-                channel,
-                data: remaining,
-            })
-        }
+        0x00 => decode_client_information(data),
+        0x01 => decode_cookie_response(data),
+        0x02 => decode_plugin_message(data),
         0x03 => Ok(InternalPacket::FinishConfigurationAck),
+        0x06 => decode_resource_pack_response(data),
         0x07 => {
             let count = read_varint(data)? as usize;
             let mut packs = Vec::with_capacity(count);
@@ -242,12 +261,65 @@ fn decode_configuration(id: i32, data: &mut BytesMut) -> Result<InternalPacket>
     }
 }
 
+/// Client Information, sent in Configuration and again in Play when the
+/// player changes their options.
+fn decode_client_information(data: &mut BytesMut) -> Result<InternalPacket> {
+    let locale = read_string(data, 16)?;
+    let view_distance = read_i8(data)?;
+    let chat_mode = read_varint(data)?;
+    let chat_colors = read_u8(data)? != 0;
+    let skin_parts = read_u8(data)?;
+    let main_hand = read_varint(data)?;
+    let text_filtering = read_u8(data)? != 0;
+    let allow_listing = read_u8(data)? != 0;
+    Ok(InternalPacket::ClientInformation {
+        locale,
+        view_distance,
+        chat_mode,
+        chat_colors,
+        skin_parts,
+        main_hand,
+        text_filtering,
+        allow_listing,
+    })
+}
+
+fn decode_cookie_response(data: &mut BytesMut) -> Result<InternalPacket> {
+    let key = read_string(data, 32767)?;
+    let payload = if read_u8(data)? != 0 {
+        Some(read_byte_array(data)?)
+    } else {
+        None
+    };
+    Ok(InternalPacket::CookieResponse { key, payload })
+}
+
+fn decode_plugin_message(data: &mut BytesMut) -> Result<InternalPacket> {
+    let channel = read_string(data, 32767)?;
+    let remaining = data.to_vec();
+    data.advance(remaining.len());
+    Ok(InternalPacket::PluginMessage {
+        channel,
+        data: remaining,
+    })
+}
+
+fn decode_resource_pack_response(data: &mut BytesMut) -> Result<InternalPacket> {
+    let uuid = read_uuid(data)?;
+    let result = read_varint(data)?;
+    Ok(InternalPacket::ResourcePackResponse { uuid, result })
+}
+
 fn decode_play(id: i32, data: &mut BytesMut) -> Result<InternalPacket> {
     match id {
         0x00 => {
             let teleport_id = read_varint(data)?;
             Ok(InternalPacket::ConfirmTeleportation { teleport_id })
         }
+        0x03 => {
+            let offset = read_varint(data)?;
+            Ok(InternalPacket::ChatAck { offset })
+        }
         0x04 => {
             // Chat Command (serverbound)
             let command = read_string(data, 256)?;
@@ -281,19 +353,37 @@ fn decode_play(id: i32, data: &mut BytesMut) -> Result<InternalPacket> {
                 acknowledged,
             })
         }
-        0x08 => {
-            // Chunk Batch Received — just acknowledge, read the chunks_per_tick float
-            let _chunks_per_tick = read_f32(data)?;
-            Ok(InternalPacket::Unknown {
-                packet_id: id,
-                data: vec![],
+        0x07 => {
+            let session_id = read_uuid(data)?;
+            let expires_at = read_i64(data)?;
+            let public_key = read_byte_array(data)?;
+            let key_signature = read_byte_array(data)?;
+            Ok(InternalPacket::PlayerSession {
+                session: ChatSession {
+                    session_id,
+                    expires_at,
+                    public_key,
+                    key_signature,
+                },
             })
         }
+        0x08 => {
+            // Chunk Batch Received
+            let chunks_per_tick = read_f32(data)?;
+            Ok(InternalPacket::ChunkBatchReceived { chunks_per_tick })
+        }
         0x09 => {
             // Client Command (respawn / request stats)
             let action = read_varint(data)?;
             Ok(InternalPacket::ClientCommand { action })
         }
+        0x0A => decode_client_information(data),
+        0x0B => {
+            // Command Suggestions Request
+            let transaction_id = read_varint(data)?;
+            let text = read_string(data, 32500)?;
+            Ok(InternalPacket::CommandSuggestionsRequest { transaction_id, text })
+        }
         0x18 => {
             let id = read_i64(data)?;
             Ok(InternalPacket::KeepAliveServerbound { id })
@@ -388,16 +478,25 @@ fn decode_play(id: i32, data: &mut BytesMut) -> Result<InternalPacket> {
                 window_id, state_id, slot, button, mode, changed_slots, carried_item,
             })
         }
+        0x0D => {
+            // Click Container Button
+            let window_id = read_u8(data)?;
+            let button_id = read_u8(data)?;
+            Ok(InternalPacket::ContainerButtonClick { window_id, button_id })
+        }
         0x0F => {
             // Close Container (serverbound)
             let container_id = read_u8(data)?;
             Ok(InternalPacket::ClientCloseContainer { container_id })
         }
+        0x11 => decode_cookie_response(data),
+        0x12 => decode_plugin_message(data),
         0x2A => {
             // Rename Item (serverbound) — anvil rename field
             let name = read_string(data, 50).map_err(|e| anyhow::anyhow!("{}", e))?;
             Ok(InternalPacket::RenameItem { name })
         }
+        0x2B => decode_resource_pack_response(data),
         0x2F => {
             // SetHeldItem (serverbound)
             let slot_id = read_i16(data)?;
@@ -521,6 +620,20 @@ fn encode_login(packet: &InternalPacket) -> Result<BytesMut> {
             write_varint(&mut buf, SET_COMPRESSION);
             write_varint(&mut buf, *threshold);
         }
+        InternalPacket::CookieRequest { key } => {
+            write_varint(&mut buf, LOGIN_COOKIE_REQUEST);
+            write_string(&mut buf, key);
+        }
+        InternalPacket::LoginPluginRequest {
+            message_id,
+            channel,
+            data,
+        } => {
+            write_varint(&mut buf, LOGIN_PLUGIN_REQUEST);
+            write_varint(&mut buf, *message_id);
+            write_string(&mut buf, channel);
+            buf.put_slice(data);
+        }
         _ => bail!("Cannot encode {:?} in Login state", std::mem::discriminant(packet)),
     }
     Ok(buf)
@@ -560,13 +673,38 @@ fn encode_configuration(packet: &InternalPacket) -> Result<BytesMut> {
         InternalPacket::Disconnect { reason } => {
             write_varint(&mut buf, 0x02); // Disconnect (Configuration)
             // In configuration state, disconnect reason is NBT text component
-            let nbt = NbtValue::Compound(vec![
-                ("text".into(), NbtValue::String(reason.text.clone())),
-            ]);
+            let nbt = text_component_to_nbt(reason);
             let mut nbt_buf = BytesMut::new();
             nbt.write_root_network(&mut nbt_buf);
             buf.extend_from_slice(&nbt_buf);
         }
+        InternalPacket::CookieRequest { key } => {
+            write_varint(&mut buf, CONFIG_COOKIE_REQUEST);
+            write_string(&mut buf, key);
+        }
+        InternalPacket::StoreCookie { key, payload } => {
+            write_varint(&mut buf, CONFIG_STORE_COOKIE);
+            write_string(&mut buf, key);
+            write_byte_array(&mut buf, payload);
+        }
+        InternalPacket::Transfer { host, port } => {
+            write_varint(&mut buf, CONFIG_TRANSFER);
+            write_string(&mut buf, host);
+            write_varint(&mut buf, *port);
+        }
+        InternalPacket::PluginMessage { channel, data } => {
+            write_varint(&mut buf, CONFIG_PLUGIN_MESSAGE);
+            write_string(&mut buf, channel);
+            buf.put_slice(data);
+        }
+        InternalPacket::AddResourcePack { .. } => {
+            write_varint(&mut buf, CONFIG_ADD_RESOURCE_PACK);
+            write_add_resource_pack(&mut buf, packet);
+        }
+        InternalPacket::RemoveResourcePack { uuid } => {
+            write_varint(&mut buf, CONFIG_REMOVE_RESOURCE_PACK);
+            write_optional_uuid(&mut buf, uuid);
+        }
         _ => bail!(
             "Cannot encode {:?} in Configuration state",
             std::mem::discriminant(packet)
@@ -575,6 +713,46 @@ fn encode_configuration(packet: &InternalPacket) -> Result<BytesMut> {
     Ok(buf)
 }
 
+/// Add Resource Pack fields, shared by Configuration and Play.
+fn write_add_resource_pack(buf: &mut BytesMut, packet: &InternalPacket) {
+    if let InternalPacket::AddResourcePack {
+        uuid,
+        url,
+        hash,
+        forced,
+        prompt,
+    } = packet
+    {
+        write_uuid(buf, uuid);
+        write_string(buf, url);
+        write_string(buf, hash);
+        buf.put_u8(*forced as u8);
+        match prompt {
+            Some(prompt) => {
+                buf.put_u8(1);
+                let mut nbt_buf = BytesMut::new();
+                text_component_to_nbt(prompt).write_root_network(&mut nbt_buf);
+                buf.extend_from_slice(&nbt_buf);
+            }
+            None => buf.put_u8(0),
+        }
+    }
+}
+
+/// A text component as anonymous network NBT.
+fn write_text(buf: &mut BytesMut, text: &TextComponent) {
+    let mut nbt_buf = BytesMut::new();
+    text_component_to_nbt(text).write_root_network(&mut nbt_buf);
+    buf.extend_from_slice(&nbt_buf);
+}
+
+fn write_optional_uuid(buf: &mut BytesMut, uuid: &Option<Uuid>) {
+    buf.put_u8(uuid.is_some() as u8);
+    if let Some(uuid) = uuid {
+        write_uuid(buf, uuid);
+    }
+}
+
 fn encode_play(packet: &InternalPacket) -> Result<BytesMut> {
     let mut buf = BytesMut::new();
     match packet {
@@ -644,6 +822,10 @@ fn encode_play(packet: &InternalPacket) -> Result<BytesMut> {
             write_varint(&mut buf, *chunk_x);
             write_varint(&mut buf, *chunk_z);
         }
+        InternalPacket::SetSimulationDistance { simulation_distance } => {
+            write_varint(&mut buf, PLAY_SET_SIMULATION_DISTANCE);
+            write_varint(&mut buf, *simulation_distance);
+        }
         InternalPacket::ChunkDataAndUpdateLight {
             chunk_x,
             chunk_z,
@@ -682,6 +864,12 @@ fn encode_play(packet: &InternalPacket) -> Result<BytesMut> {
             buf.put_u8(*event);
             buf.put_f32(*value);
         }
+        InternalPacket::PlayerAbilities { flags, flying_speed, fov_modifier } => {
+            write_varint(&mut buf, PLAY_PLAYER_ABILITIES);
+            buf.put_u8(*flags);
+            buf.put_f32(*flying_speed);
+            buf.put_f32(*fov_modifier);
+        }
         InternalPacket::SetDefaultSpawnPosition { position, angle } => {
             write_varint(&mut buf, PLAY_SET_DEFAULT_SPAWN);
             buf.put_u64(position.encode());
@@ -696,6 +884,68 @@ fn encode_play(packet: &InternalPacket) -> Result<BytesMut> {
             write_varint(&mut buf, PLAY_ACK_BLOCK_CHANGE);
             write_varint(&mut buf, *sequence);
         }
+        InternalPacket::UpdateAdvancements { reset, added, removed, progress } => {
+            write_varint(&mut buf, PLAY_UPDATE_ADVANCEMENTS);
+            buf.put_u8(*reset as u8);
+            write_varint(&mut buf, added.len() as i32);
+            for entry in added {
+                write_string(&mut buf, &entry.id);
+                buf.put_u8(entry.parent.is_some() as u8);
+                if let Some(parent) = &entry.parent {
+                    write_string(&mut buf, parent);
+                }
+                buf.put_u8(entry.display.is_some() as u8);
+                if let Some(display) = &entry.display {
+                    text_component_to_nbt(&display.title).write_root_network(&mut buf);
+                    text_component_to_nbt(&display.description).write_root_network(&mut buf);
+                    write_slot(&mut buf, &display.icon);
+                    write_varint(&mut buf, display.frame);
+                    let flags = display.background.is_some() as i32
+                        | (display.show_toast as i32) << 1
+                        | (display.hidden as i32) << 2;
+                    buf.put_i32(flags);
+                    if let Some(background) = &display.background {
+                        write_string(&mut buf, background);
+                    }
+                    buf.put_f32(display.x);
+                    buf.put_f32(display.y);
+                }
+                write_varint(&mut buf, entry.requirements.len() as i32);
+                for group in &entry.requirements {
+                    write_varint(&mut buf, group.len() as i32);
+                    for criterion in group {
+                        write_string(&mut buf, criterion);
+                    }
+                }
+                // Sends telemetry data
+                buf.put_u8(0);
+            }
+            write_varint(&mut buf, removed.len() as i32);
+            for id in removed {
+                write_string(&mut buf, id);
+            }
+            write_varint(&mut buf, progress.len() as i32);
+            for (id, criteria) in progress {
+                write_string(&mut buf, id);
+                write_varint(&mut buf, criteria.len() as i32);
+                for (criterion, obtained) in criteria {
+                    write_string(&mut buf, criterion);
+                    buf.put_u8(obtained.is_some() as u8);
+                    if let Some(time) = obtained {
+                        buf.put_i64(*time);
+                    }
+                }
+            }
+        }
+        InternalPacket::AwardStatistics { stats } => {
+            write_varint(&mut buf, PLAY_AWARD_STATISTICS);
+            write_varint(&mut buf, stats.len() as i32);
+            for (category, statistic, value) in stats {
+                write_varint(&mut buf, *category);
+                write_varint(&mut buf, *statistic);
+                write_varint(&mut buf, *value);
+            }
+        }
         InternalPacket::ChunkBatchStart => {
             write_varint(&mut buf, 0x0D);
         }
@@ -703,17 +953,86 @@ fn encode_play(packet: &InternalPacket) -> Result<BytesMut> {
             write_varint(&mut buf, 0x0C);
             write_varint(&mut buf, *batch_size);
         }
+        InternalPacket::CookieRequest { key } => {
+            write_varint(&mut buf, PLAY_COOKIE_REQUEST);
+            write_string(&mut buf, key);
+        }
+        InternalPacket::StoreCookie { key, payload } => {
+            write_varint(&mut buf, PLAY_STORE_COOKIE);
+            write_string(&mut buf, key);
+            write_byte_array(&mut buf, payload);
+        }
+        InternalPacket::Transfer { host, port } => {
+            write_varint(&mut buf, PLAY_TRANSFER);
+            write_string(&mut buf, host);
+            write_varint(&mut buf, *port);
+        }
+        InternalPacket::PluginMessage { channel, data } => {
+            write_varint(&mut buf, PLAY_PLUGIN_MESSAGE);
+            write_string(&mut buf, channel);
+            buf.put_slice(data);
+        }
+        InternalPacket::AddResourcePack { .. } => {
+            write_varint(&mut buf, PLAY_ADD_RESOURCE_PACK);
+            write_add_resource_pack(&mut buf, packet);
+        }
+        InternalPacket::RemoveResourcePack { uuid } => {
+            write_varint(&mut buf, PLAY_REMOVE_RESOURCE_PACK);
+            write_optional_uuid(&mut buf, uuid);
+        }
         InternalPacket::SystemChatMessage { content, overlay } => {
             write_varint(&mut buf, PLAY_SYSTEM_CHAT);
             // Content is an NBT text component (anonymous NBT in 1.20.3+)
-            let nbt = NbtValue::Compound(vec![
-                ("text".into(), NbtValue::String(content.text.clone())),
-            ]);
+            let nbt = text_component_to_nbt(content);
             let mut nbt_buf = BytesMut::new();
             nbt.write_root_network(&mut nbt_buf);
             buf.extend_from_slice(&nbt_buf);
             buf.put_u8(*overlay as u8);
         }
+        InternalPacket::PlayerChat {
+            sender,
+            index,
+            signature,
+            message,
+            timestamp,
+            salt,
+            previous_messages,
+            unsigned_content,
+            chat_type,
+            sender_name,
+            target_name,
+        } => {
+            write_varint(&mut buf, PLAY_PLAYER_CHAT);
+            write_uuid(&mut buf, sender);
+            write_varint(&mut buf, *index);
+            buf.put_u8(signature.is_some() as u8);
+            if let Some(signature) = signature {
+                buf.put_slice(signature);
+            }
+            write_string(&mut buf, message);
+            buf.put_i64(*timestamp);
+            buf.put_i64(*salt);
+            // Previous messages are sent in full (id 0) rather than as
+            // indexes into the client's signature cache
+            write_varint(&mut buf, previous_messages.len() as i32);
+            for previous in previous_messages {
+                write_varint(&mut buf, 0);
+                buf.put_slice(previous);
+            }
+            buf.put_u8(unsigned_content.is_some() as u8);
+            if let Some(content) = unsigned_content {
+                write_text(&mut buf, content);
+            }
+            // Filter type: pass through
+            write_varint(&mut buf, 0);
+            // Chat type holder: registry index + 1
+            write_varint(&mut buf, chat_type + 1);
+            write_text(&mut buf, sender_name);
+            buf.put_u8(target_name.is_some() as u8);
+            if let Some(target) = target_name {
+                write_text(&mut buf, target);
+            }
+        }
         InternalPacket::PlayerInfoUpdate { actions, players } => {
             write_varint(&mut buf, PLAY_PLAYER_INFO);
             buf.put_u8(*actions);
@@ -737,8 +1056,13 @@ fn encode_play(packet: &InternalPacket) -> Result<BytesMut> {
                     }
                 }
                 if actions & player_info_actions::INITIALIZE_CHAT != 0 {
-                    // No chat session — write false
-                    buf.put_u8(0);
+                    buf.put_u8(player.chat_session.is_some() as u8);
+                    if let Some(session) = &player.chat_session {
+                        write_uuid(&mut buf, &session.session_id);
+                        buf.put_i64(session.expires_at);
+                        write_byte_array(&mut buf, &session.public_key);
+                        write_byte_array(&mut buf, &session.key_signature);
+                    }
                 }
                 if actions & player_info_actions::UPDATE_GAME_MODE != 0 {
                     write_varint(&mut buf, player.game_mode.unwrap_or(0));
@@ -752,9 +1076,7 @@ fn encode_play(packet: &InternalPacket) -> Result<BytesMut> {
                 if actions & player_info_actions::UPDATE_DISPLAY_NAME != 0 {
                     if let Some(ref display) = player.display_name {
                         buf.put_u8(1); // has display name
-                        let nbt = NbtValue::Compound(vec![
-                            ("text".into(), NbtValue::String(display.text.clone())),
-                        ]);
+                        let nbt = text_component_to_nbt(display);
                         let mut nbt_buf = BytesMut::new();
                         nbt.write_root_network(&mut nbt_buf);
                         buf.extend_from_slice(&nbt_buf);
@@ -774,9 +1096,7 @@ fn encode_play(packet: &InternalPacket) -> Result<BytesMut> {
         InternalPacket::Disconnect { reason } => {
             write_varint(&mut buf, PLAY_DISCONNECT);
             // Play disconnect uses NBT text component in 1.20.3+
-            let nbt = NbtValue::Compound(vec![
-                ("text".into(), NbtValue::String(reason.text.clone())),
-            ]);
+            let nbt = text_component_to_nbt(reason);
             let mut nbt_buf = BytesMut::new();
             nbt.write_root_network(&mut nbt_buf);
             buf.extend_from_slice(&nbt_buf);
@@ -888,14 +1208,37 @@ fn encode_play(packet: &InternalPacket) -> Result<BytesMut> {
                         buf.extend_from_slice(props);
                     }
                 }
+                if let Some(suggestions) = &node.suggestions_type {
+                    write_string(&mut buf, suggestions);
+                }
             }
             write_varint(&mut buf, *root_index);
         }
+        InternalPacket::CommandSuggestionsResponse { transaction_id, start, length, matches } => {
+            write_varint(&mut buf, PLAY_COMMAND_SUGGESTIONS);
+            write_varint(&mut buf, *transaction_id);
+            write_varint(&mut buf, *start);
+            write_varint(&mut buf, *length);
+            write_varint(&mut buf, matches.len() as i32);
+            for m in matches {
+                write_string(&mut buf, m);
+                buf.put_u8(0); // no tooltip
+            }
+        }
         InternalPacket::UpdateTime { world_age, time_of_day } => {
             write_varint(&mut buf, PLAY_UPDATE_TIME);
             buf.put_i64(*world_age);
             buf.put_i64(*time_of_day);
         }
+        InternalPacket::TickingState { tick_rate, is_frozen } => {
+            write_varint(&mut buf, PLAY_TICKING_STATE);
+            buf.put_f32(*tick_rate);
+            buf.put_u8(*is_frozen as u8);
+        }
+        InternalPacket::StepTick { steps } => {
+            write_varint(&mut buf, PLAY_STEP_TICK);
+            write_varint(&mut buf, *steps);
+        }
         InternalPacket::SetBlockDestroyStage { entity_id, position, destroy_stage } => {
             write_varint(&mut buf, PLAY_BLOCK_DESTROY_STAGE);
             write_varint(&mut buf, *entity_id);
@@ -949,9 +1292,7 @@ fn encode_play(packet: &InternalPacket) -> Result<BytesMut> {
             write_varint(&mut buf, PLAY_PLAYER_COMBAT_KILL);
             write_varint(&mut buf, *player_id);
             // Death message as NBT text component
-            let nbt = NbtValue::Compound(vec![
-                ("text".into(), NbtValue::String(message.text.clone())),
-            ]);
+            let nbt = text_component_to_nbt(message);
             let mut nbt_buf = BytesMut::new();
             nbt.write_root_network(&mut nbt_buf);
             buf.extend_from_slice(&nbt_buf);
@@ -989,9 +1330,7 @@ fn encode_play(packet: &InternalPacket) -> Result<BytesMut> {
             write_varint(&mut buf, *container_id);
             write_varint(&mut buf, *menu_type);
             // Title as NBT text component
-            let nbt = NbtValue::Compound(vec![
-                ("text".into(), NbtValue::String(title.text.clone())),
-            ]);
+            let nbt = text_component_to_nbt(title);
             let mut nbt_buf = BytesMut::new();
             nbt.write_root_network(&mut nbt_buf);
             buf.extend_from_slice(&nbt_buf);
@@ -1033,6 +1372,13 @@ fn encode_play(packet: &InternalPacket) -> Result<BytesMut> {
             buf.put_f32(*pitch);
             buf.put_i64(*seed);
         }
+        InternalPacket::BlockAction { position, action_id, action_param, block_type } => {
+            write_varint(&mut buf, PLAY_BLOCK_EVENT);
+            buf.put_u64(position.encode());
+            buf.put_u8(*action_id);
+            buf.put_u8(*action_param);
+            write_varint(&mut buf, *block_type);
+        }
         InternalPacket::WorldEvent { event, position, data, disable_relative } => {
             write_varint(&mut buf, PLAY_WORLD_EVENT);
             buf.put_i32(*event);
@@ -1117,34 +1463,26 @@ fn encode_play(packet: &InternalPacket) -> Result<BytesMut> {
         InternalPacket::SetTabListHeaderAndFooter { header, footer } => {
             write_varint(&mut buf, PLAY_TAB_LIST);
             // Header component as NBT
-            let header_nbt = NbtValue::Compound(vec![
-                ("text".into(), NbtValue::String(header.text.clone())),
-            ]);
+            let header_nbt = text_component_to_nbt(header);
             let mut nbt_buf = BytesMut::new();
             header_nbt.write_root_network(&mut nbt_buf);
             buf.extend_from_slice(&nbt_buf);
             // Footer component as NBT
-            let footer_nbt = NbtValue::Compound(vec![
-                ("text".into(), NbtValue::String(footer.text.clone())),
-            ]);
+            let footer_nbt = text_component_to_nbt(footer);
             let mut nbt_buf = BytesMut::new();
             footer_nbt.write_root_network(&mut nbt_buf);
             buf.extend_from_slice(&nbt_buf);
         }
         InternalPacket::SetTitleText { text } => {
             write_varint(&mut buf, PLAY_SET_TITLE_TEXT);
-            let nbt = NbtValue::Compound(vec![
-                ("text".into(), NbtValue::String(text.text.clone())),
-            ]);
+            let nbt = text_component_to_nbt(text);
             let mut nbt_buf = BytesMut::new();
             nbt.write_root_network(&mut nbt_buf);
             buf.extend_from_slice(&nbt_buf);
         }
         InternalPacket::SetSubtitleText { text } => {
             write_varint(&mut buf, PLAY_SET_SUBTITLE_TEXT);
-            let nbt = NbtValue::Compound(vec![
-                ("text".into(), NbtValue::String(text.text.clone())),
-            ]);
+            let nbt = text_component_to_nbt(text);
             let mut nbt_buf = BytesMut::new();
             nbt.write_root_network(&mut nbt_buf);
             buf.extend_from_slice(&nbt_buf);
@@ -1155,15 +1493,86 @@ fn encode_play(packet: &InternalPacket) -> Result<BytesMut> {
             buf.put_i32(*stay);
             buf.put_i32(*fade_out);
         }
+        InternalPacket::ChangeDifficulty { difficulty, locked } => {
+            write_varint(&mut buf, PLAY_CHANGE_DIFFICULTY);
+            buf.put_u8(*difficulty);
+            buf.put_u8(*locked as u8);
+        }
+        InternalPacket::ClearTitles { reset } => {
+            write_varint(&mut buf, PLAY_CLEAR_TITLES);
+            buf.put_u8(*reset as u8);
+        }
         InternalPacket::SetActionBarText { text } => {
             write_varint(&mut buf, PLAY_SET_ACTION_BAR_TEXT);
-            let nbt = NbtValue::Compound(vec![
-                ("text".into(), NbtValue::String(text.text.clone())),
-            ]);
+            let nbt = text_component_to_nbt(text);
             let mut nbt_buf = BytesMut::new();
             nbt.write_root_network(&mut nbt_buf);
             buf.extend_from_slice(&nbt_buf);
         }
+        InternalPacket::UpdateObjectives { name, mode, display_name, render_type } => {
+            write_varint(&mut buf, PLAY_UPDATE_OBJECTIVES);
+            write_string(&mut buf, name);
+            buf.put_u8(*mode);
+            if *mode == 0 || *mode == 2 {
+                let nbt = text_component_to_nbt(display_name);
+                let mut nbt_buf = BytesMut::new();
+                nbt.write_root_network(&mut nbt_buf);
+                buf.extend_from_slice(&nbt_buf);
+                write_varint(&mut buf, *render_type);
+                buf.put_u8(0); // no number format
+            }
+        }
+        InternalPacket::DisplayObjective { position, objective } => {
+            write_varint(&mut buf, PLAY_DISPLAY_OBJECTIVE);
+            write_varint(&mut buf, *position);
+            write_string(&mut buf, objective);
+        }
+        InternalPacket::UpdateScore { entity_name, objective, value } => {
+            write_varint(&mut buf, PLAY_UPDATE_SCORE);
+            write_string(&mut buf, entity_name);
+            write_string(&mut buf, objective);
+            write_varint(&mut buf, *value);
+            buf.put_u8(0); // no display name
+            buf.put_u8(0); // no number format
+        }
+        InternalPacket::ResetScore { entity_name, objective } => {
+            write_varint(&mut buf, PLAY_RESET_SCORE);
+            write_string(&mut buf, entity_name);
+            buf.put_u8(objective.is_some() as u8);
+            if let Some(objective) = objective {
+                write_string(&mut buf, objective);
+            }
+        }
+        InternalPacket::UpdateTeams { name, mode, info, entities } => {
+            write_varint(&mut buf, PLAY_UPDATE_TEAMS);
+            write_string(&mut buf, name);
+            buf.put_u8(*mode);
+            if *mode == 0 || *mode == 2 {
+                let Some(info) = info else {
+                    bail!("UpdateTeams mode {} requires team info", mode);
+                };
+                let nbt = text_component_to_nbt(&info.display_name);
+                let mut nbt_buf = BytesMut::new();
+                nbt.write_root_network(&mut nbt_buf);
+                buf.extend_from_slice(&nbt_buf);
+                buf.put_u8(info.friendly_flags);
+                write_string(&mut buf, &info.name_tag_visibility);
+                write_string(&mut buf, &info.collision_rule);
+                write_varint(&mut buf, info.color);
+                for component in [&info.prefix, &info.suffix] {
+                    let nbt = text_component_to_nbt(component);
+                    let mut nbt_buf = BytesMut::new();
+                    nbt.write_root_network(&mut nbt_buf);
+                    buf.extend_from_slice(&nbt_buf);
+                }
+            }
+            if *mode == 0 || *mode == 3 || *mode == 4 {
+                write_varint(&mut buf, entities.len() as i32);
+                for entity in entities {
+                    write_string(&mut buf, entity);
+                }
+            }
+        }
         InternalPacket::LevelParticles { particle_id, long_distance, x, y, z, offset_x, offset_y, offset_z, max_speed, count } => {
             write_varint(&mut buf, PLAY_LEVEL_PARTICLES);
             buf.put_u8(*long_distance as u8);
@@ -1312,6 +1721,166 @@ pub fn build_tnt_metadata(fuse: i32, block_state: i32) -> Vec<EntityMetadataEntr
     vec![fuse_entry, state_entry]
 }
 
+/// Build entity metadata for a custom name.
+/// Index 2: custom name (OptChat, type 6), Index 3: name always visible (Boolean, type 8).
+pub fn build_custom_name_metadata(name: &TextComponent, visible: bool) -> Vec<EntityMetadataEntry> {
+    use pickaxe_protocol_core::EntityMetadataEntry;
+
+    let mut name_buf = BytesMut::new();
+    name_buf.put_u8(1); // present = true
+    text_component_to_nbt(name).write_root_network(&mut name_buf);
+    let name_entry = EntityMetadataEntry {
+        index: 2,
+        type_id: 6,
+        data: name_buf.to_vec(),
+    };
+
+    let visible_entry = EntityMetadataEntry {
+        index: 3,
+        type_id: 8,
+        data: vec![visible as u8],
+    };
+
+    vec![name_entry, visible_entry]
+}
+
+/// Build entity metadata for the shared entity flags byte (index 0, type 0).
+/// 0x40 makes the entity glow.
+pub fn build_entity_flags_metadata(flags: u8) -> Vec<EntityMetadataEntry> {
+    use pickaxe_protocol_core::EntityMetadataEntry;
+
+    vec![EntityMetadataEntry {
+        index: 0,
+        type_id: 0,
+        data: vec![flags],
+    }]
+}
+
+/// Build entity metadata shared by all display entities.
+/// Index 12: scale (Vector3, type 29), Index 15: billboard constraints (Byte:
+/// 0 fixed, 1 vertical, 2 horizontal, 3 center), Index 22: glow color
+/// override (VarInt, -1 uses the team color).
+pub fn build_display_metadata(billboard: u8, scale: f32, glow_color: Option<i32>) -> Vec<EntityMetadataEntry> {
+    use pickaxe_protocol_core::EntityMetadataEntry;
+
+    let mut scale_buf = BytesMut::new();
+    for _ in 0..3 {
+        scale_buf.put_f32(scale);
+    }
+    let scale_entry = EntityMetadataEntry {
+        index: 12,
+        type_id: 29,
+        data: scale_buf.to_vec(),
+    };
+
+    let billboard_entry = EntityMetadataEntry {
+        index: 15,
+        type_id: 0,
+        data: vec![billboard],
+    };
+
+    let mut color_buf = BytesMut::new();
+    write_varint(&mut color_buf, glow_color.unwrap_or(-1));
+    let color_entry = EntityMetadataEntry {
+        index: 22,
+        type_id: 1,
+        data: color_buf.to_vec(),
+    };
+
+    vec![scale_entry, billboard_entry, color_entry]
+}
+
+/// Build entity metadata for a text display's text (index 23, Chat type 5).
+pub fn build_text_display_text_metadata(text: &TextComponent) -> Vec<EntityMetadataEntry> {
+    use pickaxe_protocol_core::EntityMetadataEntry;
+
+    let mut text_buf = BytesMut::new();
+    text_component_to_nbt(text).write_root_network(&mut text_buf);
+    vec![EntityMetadataEntry {
+        index: 23,
+        type_id: 5,
+        data: text_buf.to_vec(),
+    }]
+}
+
+/// Build entity metadata for a text display.
+/// Index 23: text, Index 24: line width (VarInt), Index 25: background color
+/// (VarInt ARGB), Index 27: style flags (Byte: 0x01 shadow, 0x02 see through,
+/// 0x04 default background). A None background uses the client default.
+pub fn build_text_display_metadata(
+    text: &TextComponent,
+    line_width: i32,
+    background: Option<i32>,
+    shadow: bool,
+    see_through: bool,
+) -> Vec<EntityMetadataEntry> {
+    use pickaxe_protocol_core::EntityMetadataEntry;
+
+    let mut entries = build_text_display_text_metadata(text);
+
+    let mut width_buf = BytesMut::new();
+    write_varint(&mut width_buf, line_width);
+    entries.push(EntityMetadataEntry {
+        index: 24,
+        type_id: 1,
+        data: width_buf.to_vec(),
+    });
+
+    if let Some(color) = background {
+        let mut color_buf = BytesMut::new();
+        write_varint(&mut color_buf, color);
+        entries.push(EntityMetadataEntry {
+            index: 25,
+            type_id: 1,
+            data: color_buf.to_vec(),
+        });
+    }
+
+    let mut flags = 0u8;
+    if shadow {
+        flags |= 0x01;
+    }
+    if see_through {
+        flags |= 0x02;
+    }
+    if background.is_none() {
+        flags |= 0x04;
+    }
+    entries.push(EntityMetadataEntry {
+        index: 27,
+        type_id: 0,
+        data: vec![flags],
+    });
+
+    entries
+}
+
+/// Build entity metadata for an item display's item (index 23, Slot type 7).
+pub fn build_item_display_metadata(item: &pickaxe_types::ItemStack) -> Vec<EntityMetadataEntry> {
+    use pickaxe_protocol_core::EntityMetadataEntry;
+
+    let mut slot_buf = BytesMut::new();
+    write_slot(&mut slot_buf, &Some(item.clone()));
+    vec![EntityMetadataEntry {
+        index: 23,
+        type_id: 7,
+        data: slot_buf.to_vec(),
+    }]
+}
+
+/// Build entity metadata for a block display's block (index 23, BlockState type 14).
+pub fn build_block_display_metadata(block_state: i32) -> Vec<EntityMetadataEntry> {
+    use pickaxe_protocol_core::EntityMetadataEntry;
+
+    let mut state_buf = BytesMut::new();
+    write_varint(&mut state_buf, block_state);
+    vec![EntityMetadataEntry {
+        index: 23,
+        type_id: 14,
+        data: state_buf.to_vec(),
+    }]
+}
+
 fn encode_light_data(buf: &mut BytesMut, light: &ChunkLightData) {
     // Sky light mask
     write_varint(buf, light.sky_light_mask.len() as i32);
@@ -1,4 +1,9 @@
 mod adapter;
 mod registries;
 
-pub use adapter::{build_item_metadata, build_sleeping_metadata, build_tnt_metadata, build_wake_metadata, V1_21Adapter};
+pub use adapter::{
+    build_block_display_metadata, build_custom_name_metadata, build_display_metadata, build_entity_flags_metadata,
+    build_item_display_metadata, build_item_metadata, build_sleeping_metadata, build_text_display_metadata,
+    build_text_display_text_metadata, build_tnt_metadata, build_wake_metadata, V1_21Adapter,
+};
+pub use registries::{CHAT_TYPE_CHAT, CHAT_TYPE_RAW};
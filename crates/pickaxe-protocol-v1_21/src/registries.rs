@@ -1,175 +1,159 @@
-use pickaxe_nbt::{nbt_compound, nbt_list, NbtValue};
+use pickaxe_data::registries::{BannerPattern, Biome, DamageType, DimensionType, Registries, WolfVariant};
+use pickaxe_nbt::{nbt_compound, NbtValue};
 use pickaxe_protocol_core::{InternalPacket, RegistryEntry};
 
 /// Build all required registry data packets for MC 1.21 Configuration state.
 /// The client expects specific registries to be sent during configuration.
-pub fn build_registry_packets() -> Vec<InternalPacket> {
+pub fn build_registry_packets(registries: &Registries) -> Vec<InternalPacket> {
     vec![
-        build_dimension_type_registry(),
-        build_biome_registry(),
+        build_dimension_type_registry(&registries.dimension_types),
+        build_biome_registry(&registries.biomes),
         build_chat_type_registry(),
         build_trim_pattern_registry(),
         build_trim_material_registry(),
-        build_wolf_variant_registry(),
+        build_wolf_variant_registry(&registries.wolf_variants, &registries.biomes),
         build_painting_variant_registry(),
-        build_damage_type_registry(),
-        build_banner_pattern_registry(),
+        build_damage_type_registry(&registries.damage_types),
+        build_banner_pattern_registry(&registries.banner_patterns),
         build_enchantment_registry(),
         build_jukebox_song_registry(),
     ]
 }
 
-fn build_dimension_type_registry() -> InternalPacket {
+fn byte(value: bool) -> NbtValue {
+    NbtValue::Byte(value as i8)
+}
+
+fn build_dimension_type_registry(dimension_types: &[DimensionType]) -> InternalPacket {
     InternalPacket::RegistryData {
         registry_id: "minecraft:dimension_type".into(),
-        entries: vec![RegistryEntry {
-            id: "minecraft:overworld".into(),
-            data: Some(nbt_compound! {
-                "has_skylight" => NbtValue::Byte(1),
-                "has_ceiling" => NbtValue::Byte(0),
-                "ultrawarm" => NbtValue::Byte(0),
-                "natural" => NbtValue::Byte(1),
-                "coordinate_scale" => NbtValue::Double(1.0),
-                "bed_works" => NbtValue::Byte(1),
-                "respawn_anchor_works" => NbtValue::Byte(0),
-                "min_y" => NbtValue::Int(-64),
-                "height" => NbtValue::Int(384),
-                "logical_height" => NbtValue::Int(384),
-                "infiniburn" => NbtValue::String("#minecraft:infiniburn_overworld".into()),
-                "effects" => NbtValue::String("minecraft:overworld".into()),
-                "ambient_light" => NbtValue::Float(0.0),
-                "piglin_safe" => NbtValue::Byte(0),
-                "has_raids" => NbtValue::Byte(1),
-                "monster_spawn_light_level" => NbtValue::Int(0),
-                "monster_spawn_block_light_limit" => NbtValue::Int(0)
-            }),
-        }],
+        entries: dimension_types
+            .iter()
+            .map(|d| RegistryEntry {
+                id: d.name.clone(),
+                data: Some(nbt_compound! {
+                    "has_skylight" => byte(d.has_skylight),
+                    "has_ceiling" => byte(d.has_ceiling),
+                    "ultrawarm" => byte(d.ultrawarm),
+                    "natural" => byte(d.natural),
+                    "coordinate_scale" => NbtValue::Double(d.coordinate_scale),
+                    "bed_works" => byte(d.bed_works),
+                    "respawn_anchor_works" => byte(d.respawn_anchor_works),
+                    "min_y" => NbtValue::Int(d.min_y),
+                    "height" => NbtValue::Int(d.height),
+                    "logical_height" => NbtValue::Int(d.logical_height),
+                    "infiniburn" => NbtValue::String(d.infiniburn.clone()),
+                    "effects" => NbtValue::String(d.effects.clone()),
+                    "ambient_light" => NbtValue::Float(d.ambient_light),
+                    "piglin_safe" => byte(d.piglin_safe),
+                    "has_raids" => byte(d.has_raids),
+                    "monster_spawn_light_level" => NbtValue::Int(d.monster_spawn_light_level),
+                    "monster_spawn_block_light_limit" => NbtValue::Int(d.monster_spawn_block_light_limit)
+                }),
+            })
+            .collect(),
     }
 }
 
-fn build_biome_registry() -> InternalPacket {
+fn build_biome_registry(biomes: &[Biome]) -> InternalPacket {
     InternalPacket::RegistryData {
         registry_id: "minecraft:worldgen/biome".into(),
-        entries: vec![RegistryEntry {
-            id: "minecraft:plains".into(),
-            data: Some(nbt_compound! {
-                "has_precipitation" => NbtValue::Byte(1),
-                "temperature" => NbtValue::Float(0.8),
-                "downfall" => NbtValue::Float(0.4),
-                "effects" => NbtValue::Compound(vec![
-                    ("fog_color".into(), NbtValue::Int(12638463)),
-                    ("water_color".into(), NbtValue::Int(4159204)),
-                    ("water_fog_color".into(), NbtValue::Int(329011)),
-                    ("sky_color".into(), NbtValue::Int(7907327)),
-                    ("mood_sound".into(), NbtValue::Compound(vec![
-                        ("sound".into(), NbtValue::String("minecraft:ambient.cave".into())),
-                        ("tick_delay".into(), NbtValue::Int(6000)),
-                        ("offset".into(), NbtValue::Double(2.0)),
-                        ("block_search_extent".into(), NbtValue::Int(8)),
-                    ])),
-                ])
-            }),
-        }],
+        entries: biomes
+            .iter()
+            .map(|b| {
+                let mut effects = vec![
+                    ("fog_color".into(), NbtValue::Int(b.fog_color)),
+                    ("water_color".into(), NbtValue::Int(b.water_color)),
+                    ("water_fog_color".into(), NbtValue::Int(b.water_fog_color)),
+                    ("sky_color".into(), NbtValue::Int(b.sky_color)),
+                ];
+                if let Some(color) = b.grass_color {
+                    effects.push(("grass_color".into(), NbtValue::Int(color)));
+                }
+                if let Some(color) = b.foliage_color {
+                    effects.push(("foliage_color".into(), NbtValue::Int(color)));
+                }
+                effects.push(("mood_sound".into(), NbtValue::Compound(vec![
+                    ("sound".into(), NbtValue::String("minecraft:ambient.cave".into())),
+                    ("tick_delay".into(), NbtValue::Int(6000)),
+                    ("offset".into(), NbtValue::Double(2.0)),
+                    ("block_search_extent".into(), NbtValue::Int(8)),
+                ])));
+                RegistryEntry {
+                    id: b.name.clone(),
+                    data: Some(nbt_compound! {
+                        "has_precipitation" => byte(b.has_precipitation),
+                        "temperature" => NbtValue::Float(b.temperature),
+                        "downfall" => NbtValue::Float(b.downfall),
+                        "effects" => NbtValue::Compound(effects)
+                    }),
+                }
+            })
+            .collect(),
     }
 }
 
+/// `minecraft:chat_type` index of vanilla's `<sender> message` chat.
+pub const CHAT_TYPE_CHAT: i32 = 0;
+/// `minecraft:chat_type` index of chat the server already formatted; only the
+/// content is shown.
+pub const CHAT_TYPE_RAW: i32 = 1;
+
+fn chat_decoration(translation_key: &str, parameters: &[&str]) -> NbtValue {
+    NbtValue::Compound(vec![
+        ("translation_key".into(), NbtValue::String(translation_key.into())),
+        (
+            "parameters".into(),
+            NbtValue::List(parameters.iter().map(|p| NbtValue::String((*p).into())).collect()),
+        ),
+    ])
+}
+
 fn build_chat_type_registry() -> InternalPacket {
     InternalPacket::RegistryData {
         registry_id: "minecraft:chat_type".into(),
-        entries: vec![RegistryEntry {
-            id: "minecraft:chat".into(),
-            data: Some(nbt_compound! {
-                "chat" => NbtValue::Compound(vec![
-                    ("translation_key".into(), NbtValue::String("chat.type.text".into())),
-                    ("parameters".into(), nbt_list![
-                        NbtValue::String("sender".into()),
-                        NbtValue::String("content".into())
-                    ]),
-                ]),
-                "narration" => NbtValue::Compound(vec![
-                    ("translation_key".into(), NbtValue::String("chat.type.text.narrate".into())),
-                    ("parameters".into(), nbt_list![
-                        NbtValue::String("sender".into()),
-                        NbtValue::String("content".into())
-                    ]),
-                ])
-            }),
-        }],
-    }
-}
-
-fn damage_entry(id: &str, message_id: &str, scaling: &str, exhaustion: f32,
-                 effects: Option<&str>, death_message_type: Option<&str>) -> RegistryEntry {
-    let mut fields = vec![
-        ("message_id".into(), NbtValue::String(message_id.into())),
-        ("scaling".into(), NbtValue::String(scaling.into())),
-        ("exhaustion".into(), NbtValue::Float(exhaustion)),
-    ];
-    if let Some(fx) = effects {
-        fields.push(("effects".into(), NbtValue::String(fx.into())));
-    }
-    if let Some(dmt) = death_message_type {
-        fields.push(("death_message_type".into(), NbtValue::String(dmt.into())));
-    }
-    RegistryEntry {
-        id: format!("minecraft:{}", id),
-        data: Some(NbtValue::Compound(fields)),
+        entries: vec![
+            RegistryEntry {
+                id: "minecraft:chat".into(),
+                data: Some(nbt_compound! {
+                    "chat" => chat_decoration("chat.type.text", &["sender", "content"]),
+                    "narration" => chat_decoration("chat.type.text.narrate", &["sender", "content"])
+                }),
+            },
+            RegistryEntry {
+                id: "pickaxe:raw".into(),
+                data: Some(nbt_compound! {
+                    "chat" => chat_decoration("%s", &["content"]),
+                    "narration" => chat_decoration("chat.type.text.narrate", &["sender", "content"])
+                }),
+            },
+        ],
     }
 }
 
-fn build_damage_type_registry() -> InternalPacket {
-    let s = "when_caused_by_living_non_player";
+fn build_damage_type_registry(damage_types: &[DamageType]) -> InternalPacket {
     InternalPacket::RegistryData {
         registry_id: "minecraft:damage_type".into(),
-        entries: vec![
-            damage_entry("arrow", "arrow", s, 0.1, None, None),
-            damage_entry("bad_respawn_point", "badRespawnPoint", "always", 0.1, None, Some("intentional_game_design")),
-            damage_entry("cactus", "cactus", s, 0.1, None, None),
-            damage_entry("campfire", "inFire", s, 0.1, Some("burning"), None),
-            damage_entry("cramming", "cramming", s, 0.0, None, None),
-            damage_entry("dragon_breath", "dragonBreath", s, 0.0, None, None),
-            damage_entry("drown", "drown", s, 0.0, Some("drowning"), None),
-            damage_entry("dry_out", "dryout", s, 0.1, None, None),
-            damage_entry("explosion", "explosion", "always", 0.1, None, None),
-            damage_entry("fall", "fall", s, 0.0, None, Some("fall_variants")),
-            damage_entry("falling_anvil", "anvil", s, 0.1, None, None),
-            damage_entry("falling_block", "fallingBlock", s, 0.1, None, None),
-            damage_entry("falling_stalactite", "fallingStalactite", s, 0.1, None, None),
-            damage_entry("fireball", "fireball", s, 0.1, Some("burning"), None),
-            damage_entry("fireworks", "fireworks", s, 0.1, None, None),
-            damage_entry("fly_into_wall", "flyIntoWall", s, 0.0, None, None),
-            damage_entry("freeze", "freeze", s, 0.0, Some("freezing"), None),
-            damage_entry("generic", "generic", s, 0.0, None, None),
-            damage_entry("generic_kill", "genericKill", s, 0.0, None, None),
-            damage_entry("hot_floor", "hotFloor", s, 0.1, Some("burning"), None),
-            damage_entry("in_fire", "inFire", s, 0.1, Some("burning"), None),
-            damage_entry("in_wall", "inWall", s, 0.0, None, None),
-            damage_entry("indirect_magic", "indirectMagic", s, 0.0, None, None),
-            damage_entry("lava", "lava", s, 0.1, Some("burning"), None),
-            damage_entry("lightning_bolt", "lightningBolt", s, 0.1, None, None),
-            damage_entry("magic", "magic", s, 0.0, None, None),
-            damage_entry("mob_attack", "mob", s, 0.1, None, None),
-            damage_entry("mob_attack_no_aggro", "mob", s, 0.1, None, None),
-            damage_entry("mob_projectile", "mob", s, 0.1, None, None),
-            damage_entry("on_fire", "onFire", s, 0.0, Some("burning"), None),
-            damage_entry("out_of_world", "outOfWorld", s, 0.0, None, None),
-            damage_entry("outside_border", "outsideBorder", s, 0.0, None, None),
-            damage_entry("player_attack", "player", s, 0.1, None, None),
-            damage_entry("player_explosion", "explosion.player", "always", 0.1, None, None),
-            damage_entry("sonic_boom", "sonic_boom", "always", 0.0, None, None),
-            damage_entry("spit", "mob", s, 0.1, None, None),
-            damage_entry("stalagmite", "stalagmite", s, 0.0, None, None),
-            damage_entry("starve", "starve", s, 0.0, None, None),
-            damage_entry("sting", "sting", s, 0.1, None, None),
-            damage_entry("sweet_berry_bush", "sweetBerryBush", s, 0.1, Some("poking"), None),
-            damage_entry("thorns", "thorns", s, 0.1, Some("thorns"), None),
-            damage_entry("thrown", "thrown", s, 0.1, None, None),
-            damage_entry("trident", "trident", s, 0.1, None, None),
-            damage_entry("unattributed_fireball", "onFire", s, 0.1, Some("burning"), None),
-            damage_entry("wind_charge", "mob", s, 0.1, None, None),
-            damage_entry("wither", "wither", s, 0.0, None, None),
-            damage_entry("wither_skull", "witherSkull", s, 0.1, None, None),
-        ],
+        entries: damage_types
+            .iter()
+            .map(|d| {
+                let mut fields = vec![
+                    ("message_id".into(), NbtValue::String(d.message_id.clone())),
+                    ("scaling".into(), NbtValue::String(d.scaling.clone())),
+                    ("exhaustion".into(), NbtValue::Float(d.exhaustion)),
+                ];
+                if let Some(effects) = &d.effects {
+                    fields.push(("effects".into(), NbtValue::String(effects.clone())));
+                }
+                if let Some(death_message_type) = &d.death_message_type {
+                    fields.push(("death_message_type".into(), NbtValue::String(death_message_type.clone())));
+                }
+                RegistryEntry {
+                    id: d.name.clone(),
+                    data: Some(NbtValue::Compound(fields)),
+                }
+            })
+            .collect(),
     }
 }
 
@@ -187,18 +171,31 @@ fn build_trim_material_registry() -> InternalPacket {
     }
 }
 
-fn build_wolf_variant_registry() -> InternalPacket {
+/// Wolf variants, with their spawn biomes cut down to the biomes that are
+/// sent; the client rejects references to biomes it doesn't have.
+fn build_wolf_variant_registry(wolf_variants: &[WolfVariant], biomes: &[Biome]) -> InternalPacket {
     InternalPacket::RegistryData {
         registry_id: "minecraft:wolf_variant".into(),
-        entries: vec![RegistryEntry {
-            id: "minecraft:pale".into(),
-            data: Some(nbt_compound! {
-                "wild_texture" => NbtValue::String("minecraft:textures/entity/wolf/wolf.png".into()),
-                "tame_texture" => NbtValue::String("minecraft:textures/entity/wolf/wolf_tame.png".into()),
-                "angry_texture" => NbtValue::String("minecraft:textures/entity/wolf/wolf_angry.png".into()),
-                "biomes" => NbtValue::String("minecraft:plains".into())
-            }),
-        }],
+        entries: wolf_variants
+            .iter()
+            .map(|w| {
+                let spawn_biomes = w
+                    .biomes
+                    .iter()
+                    .filter(|name| biomes.iter().any(|b| &b.name == *name))
+                    .map(|name| NbtValue::String(name.clone()))
+                    .collect();
+                RegistryEntry {
+                    id: w.name.clone(),
+                    data: Some(nbt_compound! {
+                        "wild_texture" => NbtValue::String(w.wild_texture.clone()),
+                        "tame_texture" => NbtValue::String(w.tame_texture.clone()),
+                        "angry_texture" => NbtValue::String(w.angry_texture.clone()),
+                        "biomes" => NbtValue::List(spawn_biomes)
+                    }),
+                }
+            })
+            .collect(),
     }
 }
 
@@ -216,10 +213,19 @@ fn build_painting_variant_registry() -> InternalPacket {
     }
 }
 
-fn build_banner_pattern_registry() -> InternalPacket {
+fn build_banner_pattern_registry(banner_patterns: &[BannerPattern]) -> InternalPacket {
     InternalPacket::RegistryData {
         registry_id: "minecraft:banner_pattern".into(),
-        entries: vec![],
+        entries: banner_patterns
+            .iter()
+            .map(|p| RegistryEntry {
+                id: p.name.clone(),
+                data: Some(nbt_compound! {
+                    "asset_id" => NbtValue::String(p.asset_id.clone()),
+                    "translation_key" => NbtValue::String(p.translation_key.clone())
+                }),
+            })
+            .collect(),
     }
 }
 
@@ -0,0 +1,55 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Folder in a dimension's directory holding its chunk region files.
+pub const REGION_DIR: &str = "region";
+/// Folder in a dimension's directory holding its entity region files.
+pub const ENTITIES_DIR: &str = "entities";
+/// Folder in a dimension's directory holding its point-of-interest region files.
+pub const POI_DIR: &str = "poi";
+
+/// Where a dimension's files live in the world, following vanilla's layout:
+/// the overworld in the world directory itself, the nether in `DIM-1`, the
+/// end in `DIM1`, and any other dimension in `dimensions/<namespace>/<path>`.
+/// Returns `None` for a name that isn't a valid resource location.
+pub fn dimension_dir(world_dir: &Path, dimension: &str) -> Option<PathBuf> {
+    let (namespace, path) = dimension.split_once(':').unwrap_or(("minecraft", dimension));
+    let valid = |s: &str, extra: &[u8]| {
+        !s.is_empty() && s.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b"_-.".contains(&b) || extra.contains(&b))
+    };
+    if !valid(namespace, b"") || !valid(path, b"/") || path.split('/').any(|p| p.is_empty() || p == "." || p == "..") {
+        return None;
+    }
+    Some(match (namespace, path) {
+        ("minecraft", "overworld") => world_dir.to_path_buf(),
+        ("minecraft", "the_nether") => world_dir.join("DIM-1"),
+        ("minecraft", "the_end") => world_dir.join("DIM1"),
+        _ => world_dir.join("dimensions").join(namespace).join(path),
+    })
+}
+
+/// Create a dimension's region, entities and poi folders, returning its
+/// region folder.
+pub fn create_dimension_dirs(dir: &Path) -> io::Result<PathBuf> {
+    for folder in [ENTITIES_DIR, POI_DIR, REGION_DIR] {
+        fs::create_dir_all(dir.join(folder))?;
+    }
+    Ok(dir.join(REGION_DIR))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dimension_dir() {
+        let world = Path::new("world");
+        assert_eq!(dimension_dir(world, "minecraft:overworld"), Some(PathBuf::from("world")));
+        assert_eq!(dimension_dir(world, "the_nether"), Some(PathBuf::from("world/DIM-1")));
+        assert_eq!(dimension_dir(world, "minecraft:the_end"), Some(PathBuf::from("world/DIM1")));
+        assert_eq!(dimension_dir(world, "pickaxe:lobby"), Some(PathBuf::from("world/dimensions/pickaxe/lobby")));
+        assert_eq!(dimension_dir(world, "pickaxe:../lobby"), None);
+        assert_eq!(dimension_dir(world, "Lobby"), None);
+    }
+}
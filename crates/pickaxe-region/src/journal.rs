@@ -0,0 +1,229 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// File in the world directory holding chunk writes not yet known to be on
+/// disk in their region files.
+pub const JOURNAL_FILE: &str = "chunks.journal";
+
+/// A chunk write recorded in the journal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    /// Dimension the chunk belongs to, like `minecraft:overworld`.
+    pub level: String,
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+    /// Uncompressed chunk NBT.
+    pub data: Vec<u8>,
+}
+
+/// Write-ahead log for region files. Chunks are appended and synced here
+/// before their region file is touched, so a crash in the middle of a region
+/// write is repaired by replaying the journal on the next start. Once the
+/// region files are synced the journal is cleared.
+///
+/// Each record is `[length: u32][crc32: u32][payload]`, big-endian, where the
+/// payload is `[name length: u16][level name][x: i32][z: i32][data]`. A
+/// record cut short by a crash fails its checksum and is dropped with
+/// everything after it.
+pub struct ChunkJournal {
+    path: PathBuf,
+    file: File,
+    len: u64,
+}
+
+impl ChunkJournal {
+    /// Open or create the journal, returning the intact entries left in it
+    /// by a previous run, oldest first.
+    pub fn open(path: &Path) -> io::Result<(Self, Vec<JournalEntry>)> {
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        let mut entries = Vec::new();
+        let mut offset = 0;
+        while let Some((entry, next)) = read_record(&data, offset) {
+            entries.push(entry);
+            offset = next;
+        }
+        if offset < data.len() {
+            warn!(
+                "Dropping {} bytes of incomplete records from the end of {}",
+                data.len() - offset,
+                path.display()
+            );
+        }
+
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+        file.set_len(offset as u64)?;
+        file.seek(SeekFrom::End(0))?;
+        let journal = Self {
+            path: path.to_path_buf(),
+            file,
+            len: offset as u64,
+        };
+        Ok((journal, entries))
+    }
+
+    /// Append chunk writes and sync them to disk.
+    pub fn append(&mut self, entries: &[JournalEntry]) -> io::Result<()> {
+        let mut buf = Vec::new();
+        for entry in entries {
+            let mut payload = Vec::with_capacity(10 + entry.level.len() + entry.data.len());
+            payload.extend_from_slice(&(entry.level.len() as u16).to_be_bytes());
+            payload.extend_from_slice(entry.level.as_bytes());
+            payload.extend_from_slice(&entry.chunk_x.to_be_bytes());
+            payload.extend_from_slice(&entry.chunk_z.to_be_bytes());
+            payload.extend_from_slice(&entry.data);
+            buf.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+            buf.extend_from_slice(&crc32fast::hash(&payload).to_be_bytes());
+            buf.extend_from_slice(&payload);
+        }
+        self.file.write_all(&buf)?;
+        self.file.sync_data()?;
+        self.len += buf.len() as u64;
+        Ok(())
+    }
+
+    /// Cut off whatever a failed `append` left past the last complete
+    /// record, so later appends aren't hidden behind a torn one on replay.
+    pub fn rewind(&mut self) -> io::Result<()> {
+        self.file.set_len(self.len)?;
+        self.file.seek(SeekFrom::Start(self.len))?;
+        self.file.sync_data()
+    }
+
+    /// Empty the journal, once everything in it is synced to region files.
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.file.set_len(0)?;
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.sync_all()?;
+        self.len = 0;
+        Ok(())
+    }
+
+    /// Bytes of records in the journal.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// The record at `offset` and where the next one starts, if it's complete
+/// and its checksum matches.
+fn read_record(data: &[u8], offset: usize) -> Option<(JournalEntry, usize)> {
+    let header = data.get(offset..offset + 8)?;
+    let length = u32::from_be_bytes(header[0..4].try_into().ok()?) as usize;
+    let crc = u32::from_be_bytes(header[4..8].try_into().ok()?);
+    let payload = data.get(offset + 8..(offset + 8).checked_add(length)?)?;
+    if crc32fast::hash(payload) != crc {
+        return None;
+    }
+    let name_len = u16::from_be_bytes(payload.get(0..2)?.try_into().ok()?) as usize;
+    let level = std::str::from_utf8(payload.get(2..2 + name_len)?).ok()?.to_string();
+    let rest = payload.get(2 + name_len..)?;
+    let chunk_x = i32::from_be_bytes(rest.get(0..4)?.try_into().ok()?);
+    let chunk_z = i32::from_be_bytes(rest.get(4..8)?.try_into().ok()?);
+    let entry = JournalEntry {
+        level,
+        chunk_x,
+        chunk_z,
+        data: rest.get(8..)?.to_vec(),
+    };
+    Some((entry, offset + 8 + length))
+}
+
+/// Replace `path` with `data` so that a crash leaves either the old or the
+/// new contents: write a temporary file next to it, sync it, rename it over
+/// the original, then sync the directory so the rename itself is on disk.
+pub fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&tmp_path, path)?;
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_replay_drops_torn_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(JOURNAL_FILE);
+        let entry = |x, data: &[u8]| JournalEntry {
+            level: "minecraft:overworld".into(),
+            chunk_x: x,
+            chunk_z: -3,
+            data: data.to_vec(),
+        };
+
+        let (mut journal, replay) = ChunkJournal::open(&path).unwrap();
+        assert!(replay.is_empty());
+        journal.append(&[entry(1, b"one"), entry(2, b"two")]).unwrap();
+        drop(journal);
+
+        // A crash partway through the next append
+        let mut data = fs::read(&path).unwrap();
+        let intact = data.len();
+        data.extend_from_slice(&[0, 0, 0, 40, 1, 2, 3]);
+        fs::write(&path, &data).unwrap();
+
+        let (mut journal, replay) = ChunkJournal::open(&path).unwrap();
+        assert_eq!(replay, [entry(1, b"one"), entry(2, b"two")]);
+        assert_eq!(journal.len(), intact as u64);
+        journal.clear().unwrap();
+        drop(journal);
+        assert!(ChunkJournal::open(&path).unwrap().1.is_empty());
+    }
+
+    #[test]
+    fn test_rewind_after_failed_append() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(JOURNAL_FILE);
+        let entry = |x, data: &[u8]| JournalEntry {
+            level: "minecraft:the_nether".into(),
+            chunk_x: x,
+            chunk_z: 7,
+            data: data.to_vec(),
+        };
+
+        let (mut journal, _) = ChunkJournal::open(&path).unwrap();
+        journal.append(&[entry(1, b"one")]).unwrap();
+        let intact = journal.len();
+
+        // An append that failed after writing part of its record
+        journal.file.write_all(&[0, 0, 0, 40, 1, 2, 3]).unwrap();
+        journal.rewind().unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().len(), intact);
+
+        journal.append(&[entry(2, b"two")]).unwrap();
+        drop(journal);
+        assert_eq!(ChunkJournal::open(&path).unwrap().1, [entry(1, b"one"), entry(2, b"two")]);
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("level.dat");
+        write_atomic(&path, b"old").unwrap();
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        assert!(!dir.path().join("level.dat.tmp").exists());
+    }
+}
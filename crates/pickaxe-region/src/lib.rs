@@ -1,2 +1,7 @@
+mod dimension;
+mod journal;
+mod lz4;
 mod region_file;
+pub use dimension::*;
+pub use journal::*;
 pub use region_file::*;
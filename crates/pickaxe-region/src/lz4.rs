@@ -0,0 +1,146 @@
+use std::io;
+
+/// The framing vanilla uses for LZ4 chunks: lz4-java's `LZ4BlockOutputStream`.
+/// Each block is `LZ4Block`, a token, little-endian compressed length,
+/// original length and checksum, then the block; an empty block ends it.
+const MAGIC: &[u8; 8] = b"LZ4Block";
+const HEADER_BYTES: usize = MAGIC.len() + 13;
+const METHOD_RAW: u8 = 0x10;
+const METHOD_LZ4: u8 = 0x20;
+/// lz4-java's default block size, 64 KiB.
+const BLOCK_SIZE: usize = 1 << 16;
+/// Token level for `BLOCK_SIZE`: log2 of the block size minus 10.
+const BLOCK_LEVEL: u8 = 6;
+const CHECKSUM_SEED: u32 = 0x9747_b28c;
+
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() / 2 + HEADER_BYTES * 2);
+    for block in data.chunks(BLOCK_SIZE) {
+        let compressed = lz4_flex::block::compress(block);
+        // Incompressible blocks are stored as they are
+        let (method, body) = if compressed.len() < block.len() {
+            (METHOD_LZ4, &compressed[..])
+        } else {
+            (METHOD_RAW, block)
+        };
+        write_header(&mut out, method, body.len(), block.len(), checksum(block));
+        out.extend_from_slice(body);
+    }
+    write_header(&mut out, METHOD_RAW, 0, 0, 0);
+    out
+}
+
+pub fn decompress(mut data: &[u8]) -> io::Result<Vec<u8>> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, format!("LZ4 chunk: {}", msg));
+    let mut out = Vec::new();
+    loop {
+        if data.len() < HEADER_BYTES || &data[..MAGIC.len()] != MAGIC {
+            return Err(invalid("bad block header"));
+        }
+        let method = data[MAGIC.len()] & 0xF0;
+        let field = |at: usize| u32::from_le_bytes(data[at..at + 4].try_into().unwrap());
+        let compressed_len = field(MAGIC.len() + 1) as usize;
+        let original_len = field(MAGIC.len() + 5) as usize;
+        let expected_checksum = field(MAGIC.len() + 9);
+        data = &data[HEADER_BYTES..];
+        if original_len == 0 {
+            return Ok(out);
+        }
+        if compressed_len > data.len() {
+            return Err(invalid("truncated block"));
+        }
+        let (body, rest) = data.split_at(compressed_len);
+        let block = match method {
+            METHOD_RAW => body.to_vec(),
+            METHOD_LZ4 => lz4_flex::block::decompress(body, original_len).map_err(|e| invalid(&e.to_string()))?,
+            _ => return Err(invalid("unknown compression method")),
+        };
+        if block.len() != original_len || checksum(&block) != expected_checksum {
+            return Err(invalid("checksum mismatch"));
+        }
+        out.extend_from_slice(&block);
+        data = rest;
+    }
+}
+
+fn write_header(out: &mut Vec<u8>, method: u8, compressed_len: usize, original_len: usize, checksum: u32) {
+    out.extend_from_slice(MAGIC);
+    out.push(method | BLOCK_LEVEL);
+    out.extend_from_slice(&(compressed_len as u32).to_le_bytes());
+    out.extend_from_slice(&(original_len as u32).to_le_bytes());
+    out.extend_from_slice(&checksum.to_le_bytes());
+}
+
+/// lz4-java's block checksum: XXH32 with its seed, top four bits cleared.
+fn checksum(data: &[u8]) -> u32 {
+    xxh32(data, CHECKSUM_SEED) & 0x0FFF_FFFF
+}
+
+fn xxh32(data: &[u8], seed: u32) -> u32 {
+    const P1: u32 = 2_654_435_761;
+    const P2: u32 = 2_246_822_519;
+    const P3: u32 = 3_266_489_917;
+    const P4: u32 = 668_265_263;
+    const P5: u32 = 374_761_393;
+    let word = |bytes: &[u8]| u32::from_le_bytes(bytes[..4].try_into().unwrap());
+    let round = |acc: u32, lane: u32| acc.wrapping_add(lane.wrapping_mul(P2)).rotate_left(13).wrapping_mul(P1);
+
+    let stripes = data.chunks_exact(16);
+    let tail = stripes.remainder();
+    let mut hash = if data.len() >= 16 {
+        let mut v = [
+            seed.wrapping_add(P1).wrapping_add(P2),
+            seed.wrapping_add(P2),
+            seed,
+            seed.wrapping_sub(P1),
+        ];
+        for stripe in stripes {
+            for (i, acc) in v.iter_mut().enumerate() {
+                *acc = round(*acc, word(&stripe[i * 4..]));
+            }
+        }
+        v[0].rotate_left(1)
+            .wrapping_add(v[1].rotate_left(7))
+            .wrapping_add(v[2].rotate_left(12))
+            .wrapping_add(v[3].rotate_left(18))
+    } else {
+        seed.wrapping_add(P5)
+    };
+    hash = hash.wrapping_add(data.len() as u32);
+
+    let words = tail.chunks_exact(4);
+    let bytes = words.remainder();
+    for w in words {
+        hash = hash.wrapping_add(word(w).wrapping_mul(P3)).rotate_left(17).wrapping_mul(P4);
+    }
+    for &b in bytes {
+        hash = hash.wrapping_add((b as u32).wrapping_mul(P5)).rotate_left(11).wrapping_mul(P1);
+    }
+
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(P2);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(P3);
+    hash ^ (hash >> 16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lz4_roundtrip() {
+        assert_eq!(xxh32(b"", 0), 0x02CC_5D05);
+        assert_eq!(xxh32(b"abc", 0), 0x32D1_53FF);
+        assert_eq!(xxh32(b"Nobody inspects the spammish repetition", 0), 0xE229_3B2F);
+
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8 ^ (i / 1000) as u8).collect();
+        let compressed = compress(&data);
+        assert!(compressed.len() < data.len());
+        assert_eq!(decompress(&compressed).unwrap(), data);
+
+        let mut corrupt = compressed.clone();
+        corrupt[HEADER_BYTES + 10] ^= 0xFF;
+        assert!(decompress(&corrupt).is_err());
+    }
+}
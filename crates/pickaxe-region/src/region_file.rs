@@ -1,18 +1,63 @@
-use flate2::read::DeflateDecoder;
-use flate2::write::DeflateEncoder;
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use flate2::write::ZlibEncoder;
 use flate2::Compression;
+use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs::{self, File, OpenOptions};
 use std::io::{self, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
+use tracing::warn;
 
 const SECTOR_BYTES: usize = 4096;
 const HEADER_SECTORS: usize = 2;
-const COMPRESSION_DEFLATE: u8 = 2;
+const COMPRESSION_GZIP: u8 = 1;
+const COMPRESSION_ZLIB: u8 = 2;
+const COMPRESSION_NONE: u8 = 3;
+const COMPRESSION_LZ4: u8 = 4;
+/// Set on the compression byte when the chunk is too big for the region
+/// file and is stored in its own `c.<x>.<z>.mcc` file instead.
+const EXTERNAL_FLAG: u8 = 0x80;
+/// The sector count is a single byte, so anything bigger goes external.
+const MAX_CHUNK_SECTORS: usize = 255;
+
+/// How newly written chunks are compressed. Reads accept every format
+/// vanilla knows regardless of this setting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChunkCompression {
+    #[default]
+    Zlib,
+    Lz4,
+    None,
+}
+
+impl ChunkCompression {
+    fn id(self) -> u8 {
+        match self {
+            ChunkCompression::Zlib => COMPRESSION_ZLIB,
+            ChunkCompression::Lz4 => COMPRESSION_LZ4,
+            ChunkCompression::None => COMPRESSION_NONE,
+        }
+    }
+
+    fn compress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            ChunkCompression::Zlib => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            ChunkCompression::Lz4 => Ok(crate::lz4::compress(data)),
+            ChunkCompression::None => Ok(data.to_vec()),
+        }
+    }
+}
 
 /// A single .mca region file handle.
 pub struct RegionFile {
+    path: PathBuf,
     file: File,
+    compression: ChunkCompression,
     locations: [u32; 1024],
     timestamps: [u32; 1024],
     used_sectors: Vec<bool>,
@@ -20,117 +65,139 @@ pub struct RegionFile {
 
 impl RegionFile {
     pub fn open(path: &Path) -> io::Result<Self> {
-        let exists = path.exists();
         let mut file = OpenOptions::new()
             .read(true)
             .write(true)
             .create(true)
+            .truncate(false)
             .open(path)?;
 
         let mut locations = [0u32; 1024];
         let mut timestamps = [0u32; 1024];
 
-        if exists {
-            let file_len = file.metadata()?.len();
-            if file_len >= (HEADER_SECTORS * SECTOR_BYTES) as u64 {
-                file.seek(SeekFrom::Start(0))?;
-                let mut loc_buf = [0u8; 4096];
-                file.read_exact(&mut loc_buf)?;
-                for i in 0..1024 {
-                    locations[i] = u32::from_be_bytes([
-                        loc_buf[i * 4],
-                        loc_buf[i * 4 + 1],
-                        loc_buf[i * 4 + 2],
-                        loc_buf[i * 4 + 3],
-                    ]);
-                }
-                let mut ts_buf = [0u8; 4096];
-                file.read_exact(&mut ts_buf)?;
-                for i in 0..1024 {
-                    timestamps[i] = u32::from_be_bytes([
-                        ts_buf[i * 4],
-                        ts_buf[i * 4 + 1],
-                        ts_buf[i * 4 + 2],
-                        ts_buf[i * 4 + 3],
-                    ]);
-                }
+        let file_len = file.metadata()?.len() as usize;
+        if file_len >= HEADER_SECTORS * SECTOR_BYTES {
+            let mut header = vec![0u8; HEADER_SECTORS * SECTOR_BYTES];
+            file.seek(SeekFrom::Start(0))?;
+            file.read_exact(&mut header)?;
+            for i in 0..1024 {
+                locations[i] = u32::from_be_bytes(header[i * 4..i * 4 + 4].try_into().unwrap());
+                timestamps[i] = u32::from_be_bytes(header[SECTOR_BYTES + i * 4..SECTOR_BYTES + i * 4 + 4].try_into().unwrap());
             }
         } else {
-            let zeros = [0u8; SECTOR_BYTES];
-            file.write_all(&zeros)?;
-            file.write_all(&zeros)?;
+            if file_len > 0 {
+                warn!("Region file {} has a truncated header, starting it empty", path.display());
+            }
+            file.set_len(0)?;
+            file.write_all(&[0u8; HEADER_SECTORS * SECTOR_BYTES])?;
             file.flush()?;
         }
 
         let file_len = file.metadata()?.len() as usize;
-        let total_sectors = (file_len + SECTOR_BYTES - 1) / SECTOR_BYTES;
-        let total_sectors = total_sectors.max(HEADER_SECTORS);
+        let total_sectors = file_len.div_ceil(SECTOR_BYTES).max(HEADER_SECTORS);
         let mut used_sectors = vec![false; total_sectors];
         used_sectors[0] = true;
         used_sectors[1] = true;
 
-        for &loc in &locations {
-            if loc != 0 {
-                let sector = (loc >> 8) as usize;
-                let count = (loc & 0xFF) as usize;
-                for s in sector..sector + count {
-                    if s < used_sectors.len() {
-                        used_sectors[s] = true;
-                    }
-                }
+        // Entries pointing into the header, past the end of the file or at
+        // another chunk's sectors can't be trusted; drop them so the rest of
+        // the region stays readable and their sectors are never shared.
+        for index in 0..1024 {
+            let loc = locations[index];
+            if loc == 0 {
+                continue;
             }
+            let (sector, count) = split_location(loc);
+            let valid = sector >= HEADER_SECTORS
+                && count > 0
+                && sector + count <= total_sectors
+                && !used_sectors[sector..sector + count].contains(&true);
+            if !valid {
+                warn!(
+                    "Dropping chunk {},{} from {}: bad location (sector {}, {} sectors)",
+                    index % 32,
+                    index / 32,
+                    path.display(),
+                    sector,
+                    count
+                );
+                locations[index] = 0;
+                timestamps[index] = 0;
+                continue;
+            }
+            used_sectors[sector..sector + count].fill(true);
         }
 
         Ok(Self {
+            path: path.to_path_buf(),
             file,
+            compression: ChunkCompression::default(),
             locations,
             timestamps,
             used_sectors,
         })
     }
 
+    pub fn set_compression(&mut self, compression: ChunkCompression) {
+        self.compression = compression;
+    }
+
+    /// Reads a chunk's NBT bytes. A chunk whose data is damaged is logged
+    /// and treated as missing, so it regenerates instead of failing the
+    /// whole region.
     pub fn read_chunk(&mut self, local_x: usize, local_z: usize) -> io::Result<Option<Vec<u8>>> {
+        match self.read_chunk_data(local_x, local_z) {
+            Err(e) if matches!(e.kind(), io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof) => {
+                warn!("Skipping corrupt chunk {},{} in {}: {}", local_x, local_z, self.path.display(), e);
+                Ok(None)
+            }
+            result => result,
+        }
+    }
+
+    fn read_chunk_data(&mut self, local_x: usize, local_z: usize) -> io::Result<Option<Vec<u8>>> {
         let index = local_x + local_z * 32;
         let loc = self.locations[index];
         if loc == 0 {
             return Ok(None);
         }
 
-        let sector = (loc >> 8) as u64;
-        let _count = (loc & 0xFF) as usize;
-
+        let (sector, count) = split_location(loc);
         self.file
-            .seek(SeekFrom::Start(sector * SECTOR_BYTES as u64))?;
+            .seek(SeekFrom::Start((sector * SECTOR_BYTES) as u64))?;
 
         let mut header = [0u8; 5];
         self.file.read_exact(&mut header)?;
         let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
         let compression = header[4];
 
-        if length <= 1 {
+        let compressed = if compression & EXTERNAL_FLAG != 0 {
+            fs::read(self.external_path(local_x, local_z)?)?
+        } else if length <= 1 {
             return Ok(None);
-        }
-
-        let data_len = length - 1;
-        let mut compressed = vec![0u8; data_len];
-        self.file.read_exact(&mut compressed)?;
-
-        let decompressed = match compression {
-            COMPRESSION_DEFLATE => {
-                let mut decoder = DeflateDecoder::new(&compressed[..]);
-                let mut out = Vec::new();
-                decoder.read_to_end(&mut out)?;
-                out
-            }
-            _ => {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    format!("Unsupported compression type: {}", compression),
-                ));
-            }
+        } else if length + 4 > count * SECTOR_BYTES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("length {} overruns its {} sectors", length, count),
+            ));
+        } else {
+            let mut compressed = vec![0u8; length - 1];
+            self.file.read_exact(&mut compressed)?;
+            compressed
         };
 
-        Ok(Some(decompressed))
+        Ok(Some(decompress(compression & !EXTERNAL_FLAG, &compressed)?))
+    }
+
+    /// Where vanilla keeps a chunk too large for the region file: next to
+    /// it, named by the chunk's absolute coordinates.
+    fn external_path(&self, local_x: usize, local_z: usize) -> io::Result<PathBuf> {
+        let (region_x, region_z) = region_coords(&self.path).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "Region file name has no coordinates")
+        })?;
+        let chunk_x = region_x * 32 + local_x as i32;
+        let chunk_z = region_z * 32 + local_z as i32;
+        Ok(self.path.with_file_name(format!("c.{}.{}.mcc", chunk_x, chunk_z)))
     }
 
     pub fn write_chunk(
@@ -140,41 +207,38 @@ impl RegionFile {
         nbt_bytes: &[u8],
     ) -> io::Result<()> {
         let index = local_x + local_z * 32;
+        let compressed = self.compression.compress(nbt_bytes)?;
+
+        // Oversized chunks go to their own file, leaving a bare header here
+        let external = 5 + compressed.len() > MAX_CHUNK_SECTORS * SECTOR_BYTES;
+        let mut record = Vec::with_capacity(5 + compressed.len());
+        if external {
+            // Replaced whole, like vanilla, but synced so the header never
+            // points at a half-written file
+            crate::write_atomic(&self.external_path(local_x, local_z)?, &compressed)?;
+            record.extend_from_slice(&1u32.to_be_bytes());
+            record.push(self.compression.id() | EXTERNAL_FLAG);
+        } else {
+            record.extend_from_slice(&((compressed.len() + 1) as u32).to_be_bytes());
+            record.push(self.compression.id());
+            record.extend_from_slice(&compressed);
+        }
+        let sectors_needed = record.len().div_ceil(SECTOR_BYTES);
+        record.resize(sectors_needed * SECTOR_BYTES, 0);
 
-        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
-        encoder.write_all(nbt_bytes)?;
-        let compressed = encoder.finish()?;
-
-        let total = 5 + compressed.len();
-        let sectors_needed = (total + SECTOR_BYTES - 1) / SECTOR_BYTES;
-
+        // Free the old sectors first so the chunk can be rewritten in place
         let old_loc = self.locations[index];
         if old_loc != 0 {
-            let old_sector = (old_loc >> 8) as usize;
-            let old_count = (old_loc & 0xFF) as usize;
-            for s in old_sector..old_sector + old_count {
-                if s < self.used_sectors.len() {
-                    self.used_sectors[s] = false;
-                }
-            }
+            let (old_sector, old_count) = split_location(old_loc);
+            self.used_sectors[old_sector..old_sector + old_count].fill(false);
         }
 
         let new_sector = self.allocate_sectors(sectors_needed);
-
         self.file
-            .seek(SeekFrom::Start(new_sector as u64 * SECTOR_BYTES as u64))?;
-        let length = (compressed.len() + 1) as u32;
-        self.file.write_all(&length.to_be_bytes())?;
-        self.file.write_all(&[COMPRESSION_DEFLATE])?;
-        self.file.write_all(&compressed)?;
-
-        let written = 5 + compressed.len();
-        let padding = sectors_needed * SECTOR_BYTES - written;
-        if padding > 0 {
-            self.file.write_all(&vec![0u8; padding])?;
-        }
+            .seek(SeekFrom::Start((new_sector * SECTOR_BYTES) as u64))?;
+        self.file.write_all(&record)?;
 
-        self.locations[index] = ((new_sector as u32) << 8) | (sectors_needed as u32 & 0xFF);
+        self.locations[index] = ((new_sector as u32) << 8) | sectors_needed as u32;
 
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -185,9 +249,56 @@ impl RegionFile {
         self.write_header()?;
         self.file.flush()?;
 
+        if !external {
+            if let Ok(stale) = self.external_path(local_x, local_z) {
+                let _ = fs::remove_file(stale);
+            }
+        }
+
         Ok(())
     }
 
+    /// Rewrites the file with its chunks packed back to back, dropping the
+    /// free sectors left behind by rewrites. Returns the bytes reclaimed.
+    pub fn compact(&mut self) -> io::Result<u64> {
+        let before = self.file.metadata()?.len();
+        let tmp_path = self.path.with_extension("mca.tmp");
+        let mut tmp = File::create(&tmp_path)?;
+
+        let mut locations = [0u32; 1024];
+        let mut next_sector = HEADER_SECTORS;
+        tmp.seek(SeekFrom::Start((HEADER_SECTORS * SECTOR_BYTES) as u64))?;
+        for (index, &loc) in self.locations.iter().enumerate() {
+            if loc == 0 {
+                continue;
+            }
+            let (sector, count) = split_location(loc);
+            let mut record = Vec::with_capacity(count * SECTOR_BYTES);
+            self.file
+                .seek(SeekFrom::Start((sector * SECTOR_BYTES) as u64))?;
+            (&mut self.file).take((count * SECTOR_BYTES) as u64).read_to_end(&mut record)?;
+            record.resize(count * SECTOR_BYTES, 0);
+            tmp.write_all(&record)?;
+            locations[index] = ((next_sector as u32) << 8) | count as u32;
+            next_sector += count;
+        }
+        tmp.seek(SeekFrom::Start(0))?;
+        tmp.write_all(&encode_header(&locations, &self.timestamps))?;
+        tmp.sync_all()?;
+        drop(tmp);
+
+        fs::rename(&tmp_path, &self.path)?;
+        self.file = OpenOptions::new().read(true).write(true).open(&self.path)?;
+        self.locations = locations;
+        self.used_sectors = vec![true; next_sector];
+        Ok(before.saturating_sub((next_sector * SECTOR_BYTES) as u64))
+    }
+
+    /// Flush the region file's data and header to disk.
+    pub fn sync(&self) -> io::Result<()> {
+        self.file.sync_all()
+    }
+
     fn allocate_sectors(&mut self, count: usize) -> usize {
         let mut start = HEADER_SECTORS;
         while start + count <= self.used_sectors.len() {
@@ -214,26 +325,64 @@ impl RegionFile {
 
     fn write_header(&mut self) -> io::Result<()> {
         self.file.seek(SeekFrom::Start(0))?;
-        let mut loc_buf = [0u8; 4096];
-        for i in 0..1024 {
-            let bytes = self.locations[i].to_be_bytes();
-            loc_buf[i * 4..i * 4 + 4].copy_from_slice(&bytes);
-        }
-        self.file.write_all(&loc_buf)?;
+        self.file.write_all(&encode_header(&self.locations, &self.timestamps))
+    }
+}
 
-        let mut ts_buf = [0u8; 4096];
-        for i in 0..1024 {
-            let bytes = self.timestamps[i].to_be_bytes();
-            ts_buf[i * 4..i * 4 + 4].copy_from_slice(&bytes);
+fn encode_header(locations: &[u32; 1024], timestamps: &[u32; 1024]) -> Vec<u8> {
+    let mut header = vec![0u8; HEADER_SECTORS * SECTOR_BYTES];
+    for i in 0..1024 {
+        header[i * 4..i * 4 + 4].copy_from_slice(&locations[i].to_be_bytes());
+        header[SECTOR_BYTES + i * 4..SECTOR_BYTES + i * 4 + 4].copy_from_slice(&timestamps[i].to_be_bytes());
+    }
+    header
+}
+
+fn split_location(loc: u32) -> (usize, usize) {
+    ((loc >> 8) as usize, (loc & 0xFF) as usize)
+}
+
+/// Region coordinates from an `r.<x>.<z>.mca` file name.
+fn region_coords(path: &Path) -> Option<(i32, i32)> {
+    let name = path.file_name()?.to_str()?;
+    let mut parts = name.split('.');
+    if parts.next() != Some("r") {
+        return None;
+    }
+    let region_x = parts.next()?.parse().ok()?;
+    let region_z = parts.next()?.parse().ok()?;
+    Some((region_x, region_z))
+}
+
+fn decompress(compression: u8, data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    match compression {
+        COMPRESSION_GZIP => {
+            GzDecoder::new(data).read_to_end(&mut out)?;
+        }
+        COMPRESSION_ZLIB => {
+            // Older pickaxe builds wrote raw deflate under the zlib type
+            if ZlibDecoder::new(data).read_to_end(&mut out).is_err() {
+                out.clear();
+                DeflateDecoder::new(data).read_to_end(&mut out)?;
+            }
+        }
+        COMPRESSION_NONE => out.extend_from_slice(data),
+        COMPRESSION_LZ4 => out = crate::lz4::decompress(data)?,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unsupported compression type: {}", compression),
+            ));
         }
-        self.file.write_all(&ts_buf)?;
-        Ok(())
     }
+    Ok(out)
 }
 
 /// Manages a directory of region files.
 pub struct RegionStorage {
     dir: PathBuf,
+    compression: ChunkCompression,
     cache: HashMap<(i32, i32), RegionFile>,
 }
 
@@ -242,10 +391,18 @@ impl RegionStorage {
         fs::create_dir_all(&dir)?;
         Ok(Self {
             dir,
+            compression: ChunkCompression::default(),
             cache: HashMap::new(),
         })
     }
 
+    pub fn set_compression(&mut self, compression: ChunkCompression) {
+        self.compression = compression;
+        for region in self.cache.values_mut() {
+            region.set_compression(compression);
+        }
+    }
+
     pub fn read_chunk(&mut self, chunk_x: i32, chunk_z: i32) -> io::Result<Option<Vec<u8>>> {
         let (region_x, region_z, local_x, local_z) = Self::chunk_to_region(chunk_x, chunk_z);
         // Don't create the region file just to read from it
@@ -270,10 +427,40 @@ impl RegionStorage {
         region.write_chunk(local_x, local_z, nbt_bytes)
     }
 
+    /// Compacts every region file in the directory. A file that fails is
+    /// logged and left as it was. Returns the total bytes reclaimed.
+    pub fn compact(&mut self) -> io::Result<u64> {
+        let mut reclaimed = 0;
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("mca") {
+                continue;
+            }
+            let Some((region_x, region_z)) = region_coords(&path) else {
+                continue;
+            };
+            match self.get_or_open(region_x, region_z).and_then(|region| region.compact()) {
+                Ok(bytes) => reclaimed += bytes,
+                Err(e) => warn!("Failed to compact {}: {}", path.display(), e),
+            }
+        }
+        Ok(reclaimed)
+    }
+
+    /// Flush every open region file to disk, e.g. before the chunk journal
+    /// is cleared.
+    pub fn sync(&self) -> io::Result<()> {
+        for region in self.cache.values() {
+            region.sync()?;
+        }
+        Ok(())
+    }
+
     fn get_or_open(&mut self, region_x: i32, region_z: i32) -> io::Result<&mut RegionFile> {
         if !self.cache.contains_key(&(region_x, region_z)) {
             let path = self.dir.join(format!("r.{}.{}.mca", region_x, region_z));
-            let region = RegionFile::open(&path)?;
+            let mut region = RegionFile::open(&path)?;
+            region.set_compression(self.compression);
             self.cache.insert((region_x, region_z), region);
         }
         Ok(self.cache.get_mut(&(region_x, region_z)).unwrap())
@@ -366,6 +553,66 @@ mod tests {
         assert_eq!(files.len(), 2);
     }
 
+    #[test]
+    fn test_vanilla_chunk_formats() {
+        use flate2::write::DeflateEncoder;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("r.-1.0.mca");
+        let mut region = RegionFile::open(&path).unwrap();
+        region.write_chunk(0, 0, b"zlib").unwrap();
+        assert_eq!(region.read_chunk(0, 0).unwrap(), Some(b"zlib".to_vec()));
+
+        // A chunk in its own file, as vanilla stores oversized chunks
+        fs::write(dir.path().join("c.-31.2.mcc"), b"external").unwrap();
+        let mut header = 3u32.to_be_bytes().to_vec();
+        header.push(EXTERNAL_FLAG | COMPRESSION_NONE);
+        let sector = region.allocate_sectors(1);
+        region.file.seek(SeekFrom::Start((sector * SECTOR_BYTES) as u64)).unwrap();
+        region.file.write_all(&header).unwrap();
+        region.locations[1 + 2 * 32] = ((sector as u32) << 8) | 1;
+        assert_eq!(region.read_chunk(1, 2).unwrap(), Some(b"external".to_vec()));
+
+        let mut legacy = DeflateEncoder::new(Vec::new(), Compression::default());
+        legacy.write_all(b"raw deflate").unwrap();
+        assert_eq!(decompress(COMPRESSION_ZLIB, &legacy.finish().unwrap()).unwrap(), b"raw deflate");
+    }
+
+    #[test]
+    fn test_compact_and_recover() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("r.0.0.mca");
+        let mut region = RegionFile::open(&path).unwrap();
+        region.set_compression(ChunkCompression::None);
+        region.write_chunk(0, 0, &[1u8; 5000]).unwrap();
+        region.write_chunk(1, 0, b"small").unwrap();
+        // Growing chunk 0 past its two sectors leaves a hole behind
+        region.set_compression(ChunkCompression::Lz4);
+        let big: Vec<u8> = (0..20_000u32).map(|i| (i * 7 % 256) as u8).collect();
+        region.write_chunk(0, 0, &big).unwrap();
+
+        let before = fs::metadata(&path).unwrap().len();
+        assert!(region.compact().unwrap() > 0);
+        assert!(fs::metadata(&path).unwrap().len() < before);
+        assert_eq!(region.read_chunk(0, 0).unwrap(), Some(big.clone()));
+        assert_eq!(region.read_chunk(1, 0).unwrap(), Some(b"small".to_vec()));
+
+        // Point chunk 2 at chunk 1's sectors and garble chunk 1's data:
+        // chunk 2 is dropped on open, chunk 1 reads as missing
+        let loc = region.locations[1];
+        region.locations[2] = loc;
+        region.write_header().unwrap();
+        let (sector, _) = split_location(loc);
+        region.file.seek(SeekFrom::Start((sector * SECTOR_BYTES + 4) as u64)).unwrap();
+        region.file.write_all(&[9]).unwrap();
+        drop(region);
+
+        let mut region = RegionFile::open(&path).unwrap();
+        assert_eq!(region.locations[2], 0);
+        assert_eq!(region.read_chunk(1, 0).unwrap(), None);
+        assert_eq!(region.read_chunk(0, 0).unwrap(), Some(big));
+    }
+
     #[test]
     fn test_reopen_region() {
         let dir = tempfile::tempdir().unwrap();
@@ -0,0 +1,262 @@
+use mlua::Lua;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Documentation for one Lua API function, recorded by `document` when the
+/// function is registered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiDoc {
+    /// Full name, e.g. `pickaxe.world.get_block`.
+    pub name: String,
+    pub params: Vec<ApiParam>,
+    /// Return types, in order.
+    pub returns: Vec<String>,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiParam {
+    pub name: String,
+    pub ty: String,
+    pub optional: bool,
+}
+
+/// Every `ApiDoc` recorded on a Lua VM, kept in its app data.
+#[derive(Default)]
+pub struct ApiDocs(HashMap<String, ApiDoc>);
+
+/// A function found in the `pickaxe` table, with its documentation if any.
+pub struct ApiEntry {
+    pub name: String,
+    pub doc: Option<ApiDoc>,
+}
+
+impl ApiDoc {
+    /// Parse a signature such as
+    /// `pickaxe.world.get_block(x: integer, y: integer, z: integer) -> integer`.
+    /// A `?` after a parameter name marks it optional; untyped parameters are
+    /// `any`. Several return values are separated by commas.
+    pub fn parse(signature: &str, description: &str) -> Option<Self> {
+        let (name, rest) = signature.split_once('(')?;
+        let close = closing_paren(rest)?;
+        let (params, returns) = (&rest[..close], &rest[close + 1..]);
+        let params = split_top_level(params)
+            .into_iter()
+            .map(|param| {
+                let (name, ty) = param.split_once(':').unwrap_or((param, "any"));
+                let (name, optional) = match name.trim().strip_suffix('?') {
+                    Some(name) => (name, true),
+                    None => (name.trim(), false),
+                };
+                ApiParam { name: name.to_string(), ty: ty.trim().to_string(), optional }
+            })
+            .collect();
+        let returns = match returns.trim().strip_prefix("->") {
+            Some(returns) => split_top_level(returns).into_iter().map(str::to_string).collect(),
+            None => Vec::new(),
+        };
+        Some(Self { name: name.trim().to_string(), params, returns, description: description.to_string() })
+    }
+
+    /// `name(a, b?)`, as shown in the markdown reference.
+    fn call_signature(&self) -> String {
+        let params: Vec<String> =
+            self.params.iter().map(|p| format!("{}{}", p.name, if p.optional { "?" } else { "" })).collect();
+        format!("{}({})", self.name, params.join(", "))
+    }
+}
+
+/// Index of the `)` closing a parameter list that `s` starts inside of.
+fn closing_paren(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(i),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split on commas that aren't inside brackets, dropping empty pieces.
+fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' | '[' | '{' | '<' => depth += 1,
+            ')' | ']' | '}' | '>' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(s[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(s[start..].trim());
+    parts.retain(|p| !p.is_empty());
+    parts
+}
+
+/// Record documentation for an API function, for `--dump-lua-api`. Call it
+/// next to the function's registration; see `ApiDoc::parse` for the
+/// signature syntax.
+pub fn document(lua: &Lua, signature: &str, description: &str) {
+    let Some(doc) = ApiDoc::parse(signature, description) else {
+        tracing::warn!("Malformed API signature: {}", signature);
+        return;
+    };
+    if lua.app_data_ref::<ApiDocs>().is_none() {
+        lua.set_app_data(ApiDocs::default());
+    }
+    if let Some(mut docs) = lua.app_data_mut::<ApiDocs>() {
+        docs.0.insert(doc.name.clone(), doc);
+    }
+}
+
+/// Every function reachable from the `pickaxe` global, sorted by name, with
+/// the documentation recorded for it.
+pub fn collect(lua: &Lua) -> mlua::Result<Vec<ApiEntry>> {
+    let mut names = Vec::new();
+    let pickaxe: mlua::Table = lua.globals().get("pickaxe")?;
+    find_functions(&pickaxe, "pickaxe", &mut names)?;
+    names.sort();
+    let docs = lua.app_data_ref::<ApiDocs>();
+    Ok(names
+        .into_iter()
+        .map(|name| {
+            let doc = docs.as_ref().and_then(|d| d.0.get(&name).cloned());
+            ApiEntry { name, doc }
+        })
+        .collect())
+}
+
+fn find_functions(table: &mlua::Table, path: &str, names: &mut Vec<String>) -> mlua::Result<()> {
+    for pair in table.pairs::<String, mlua::Value>() {
+        let (key, value) = pair?;
+        let name = format!("{}.{}", path, key);
+        match value {
+            mlua::Value::Function(_) => names.push(name),
+            mlua::Value::Table(t) => find_functions(&t, &name, names)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// The tables that hold `entries`, parents first.
+fn namespaces(entries: &[ApiEntry]) -> Vec<String> {
+    let mut namespaces: Vec<String> = Vec::new();
+    for entry in entries {
+        let mut name = entry.name.as_str();
+        while let Some((parent, _)) = name.rsplit_once('.') {
+            if !namespaces.iter().any(|n| n == parent) {
+                namespaces.push(parent.to_string());
+            }
+            name = parent;
+        }
+    }
+    namespaces.sort();
+    namespaces
+}
+
+/// A Lua language server (LuaLS/EmmyLua) definition file for `entries`.
+pub fn lua_stubs(entries: &[ApiEntry]) -> String {
+    let mut out = String::from("---@meta\n-- Generated by `pickaxe-server --dump-lua-api`; do not edit.\n");
+    for namespace in namespaces(entries) {
+        let _ = write!(out, "\n---@class {}\n{} = {{}}\n", namespace, namespace);
+    }
+    for entry in entries {
+        out.push('\n');
+        let Some(doc) = &entry.doc else {
+            let _ = writeln!(out, "---Undocumented.\nfunction {}(...) end", entry.name);
+            continue;
+        };
+        for line in doc.description.lines() {
+            let _ = writeln!(out, "---{}", line);
+        }
+        for p in &doc.params {
+            let _ = writeln!(out, "---@param {}{} {}", p.name, if p.optional { "?" } else { "" }, p.ty);
+        }
+        for ty in &doc.returns {
+            let _ = writeln!(out, "---@return {}", ty);
+        }
+        let params: Vec<&str> = doc.params.iter().map(|p| p.name.as_str()).collect();
+        let _ = writeln!(out, "function {}({}) end", entry.name, params.join(", "));
+    }
+    out
+}
+
+/// A markdown reference for `entries`, one section per table.
+pub fn markdown(entries: &[ApiEntry]) -> String {
+    let mut out = String::from("# Pickaxe Lua API\n\nGenerated by `pickaxe-server --dump-lua-api`.\n");
+    let mut section = "";
+    for entry in entries {
+        let namespace = entry.name.rsplit_once('.').map(|(ns, _)| ns).unwrap_or("");
+        if namespace != section {
+            section = namespace;
+            let _ = write!(out, "\n## `{}`\n", section);
+        }
+        let Some(doc) = &entry.doc else {
+            let _ = write!(out, "\n### `{}(...)`\n\nUndocumented.\n", entry.name);
+            continue;
+        };
+        let _ = write!(out, "\n### `{}`\n\n", doc.call_signature());
+        if !doc.description.is_empty() {
+            let _ = writeln!(out, "{}\n", doc.description);
+        }
+        for p in &doc.params {
+            let _ = writeln!(out, "- `{}`: `{}`{}", p.name, p.ty, if p.optional { " (optional)" } else { "" });
+        }
+        if !doc.returns.is_empty() {
+            let _ = writeln!(out, "- returns `{}`", doc.returns.join(", "));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_signature() {
+        let doc = ApiDoc::parse("pickaxe.sounds.play(x: number, name: string, pitch?: number) -> boolean", "Play a sound.").unwrap();
+        assert_eq!(doc.name, "pickaxe.sounds.play");
+        assert_eq!(doc.params.len(), 3);
+        assert_eq!(doc.params[2], ApiParam { name: "pitch".into(), ty: "number".into(), optional: true });
+        assert_eq!(doc.returns, vec!["boolean"]);
+
+        let doc = ApiDoc::parse("pickaxe.scoreboard.add_score(holder, map: table<string, integer>) -> integer?, string?", "").unwrap();
+        assert_eq!(doc.params[0].ty, "any");
+        assert_eq!(doc.params[1].ty, "table<string, integer>");
+        assert_eq!(doc.returns, vec!["integer?", "string?"]);
+        let doc = ApiDoc::parse("pickaxe.events.on(name: string, callback: fun(event: table): string?)", "").unwrap();
+        assert_eq!(doc.params[1].ty, "fun(event: table): string?");
+        assert_eq!(ApiDoc::parse("pickaxe.log(message: string)", "").unwrap().returns, Vec::<String>::new());
+        assert!(ApiDoc::parse("pickaxe.log", "").is_none());
+    }
+
+    #[test]
+    fn test_stubs_and_markdown() {
+        let entries = vec![
+            ApiEntry {
+                name: "pickaxe.world.get_block".into(),
+                doc: ApiDoc::parse("pickaxe.world.get_block(x: integer, y: integer, z: integer) -> integer", "Block state at a position."),
+            },
+            ApiEntry { name: "pickaxe.world.mystery".into(), doc: None },
+        ];
+        let stubs = lua_stubs(&entries);
+        assert!(stubs.contains("---@class pickaxe\npickaxe = {}\n\n---@class pickaxe.world\npickaxe.world = {}\n"));
+        assert!(stubs.contains("---Block state at a position.\n---@param x integer\n"));
+        assert!(stubs.contains("---@return integer\nfunction pickaxe.world.get_block(x, y, z) end\n"));
+        assert!(stubs.contains("function pickaxe.world.mystery(...) end"));
+
+        let md = markdown(&entries);
+        assert!(md.contains("## `pickaxe.world`\n\n### `pickaxe.world.get_block(x, y, z)`\n"));
+        assert!(md.contains("- returns `integer`"));
+    }
+}
@@ -1,7 +1,10 @@
 pub mod runtime;
 pub mod bridge;
+pub mod docs;
 pub mod mod_loader;
 pub mod sandbox;
+pub mod services;
+pub mod timings;
 
 pub use runtime::ScriptRuntime;
 pub use mod_loader::ModManifest;
@@ -8,6 +8,8 @@ pub struct ModManifest {
     pub mod_info: ModInfo,
     pub entrypoint: PathBuf,
     pub base_dir: PathBuf,
+    /// Mods that must load before this one (`[mod.load_order] after`).
+    pub after: Vec<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -91,18 +93,35 @@ fn parse_manifest(manifest_path: &Path, base_dir: &Path) -> anyhow::Result<ModMa
         },
         entrypoint: base_dir.join(entrypoint_file),
         base_dir: base_dir.to_path_buf(),
+        after: file.mod_section.load_order.map(|l| l.after).unwrap_or_default(),
     })
 }
 
-/// Sort mods topologically based on load_order.after declarations.
-/// For now, a simple sort: "core" first, "vanilla" second, everything else after.
+/// Sort mods so each loads after the mods in its `load_order.after` that are
+/// present. Otherwise "core" goes first, "vanilla" second, everything else
+/// after in discovery order. Mods in a cycle load in that order too.
 pub fn sort_mods(mut mods: Vec<ModManifest>) -> Vec<ModManifest> {
     mods.sort_by(|a, b| {
         let order_a = mod_sort_key(&a.mod_info.id);
         let order_b = mod_sort_key(&b.mod_info.id);
         order_a.cmp(&order_b)
     });
-    mods
+
+    let mut sorted: Vec<ModManifest> = Vec::with_capacity(mods.len());
+    while !mods.is_empty() {
+        let waiting = |m: &ModManifest| {
+            m.after.iter().any(|dep| mods.iter().any(|other| &other.mod_info.id == dep))
+        };
+        let next = match mods.iter().position(|m| !waiting(m)) {
+            Some(i) => i,
+            None => {
+                tracing::warn!("Mod load_order has a cycle; loading '{}' first", mods[0].mod_info.id);
+                0
+            }
+        };
+        sorted.push(mods.remove(next));
+    }
+    sorted
 }
 
 fn mod_sort_key(id: &str) -> u32 {
@@ -112,3 +131,31 @@ fn mod_sort_key(id: &str) -> u32 {
         _ => 2,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(id: &str, after: &[&str]) -> ModManifest {
+        ModManifest {
+            mod_info: ModInfo { id: id.into(), name: id.into(), version: "1.0.0".into() },
+            entrypoint: PathBuf::from("init.lua"),
+            base_dir: PathBuf::new(),
+            after: after.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_sort_mods_load_order() {
+        let mods = vec![
+            manifest("shop", &["economy", "missing"]),
+            manifest("economy", &["pickaxe-vanilla"]),
+            manifest("pickaxe-vanilla", &["pickaxe-core"]),
+            manifest("pickaxe-core", &[]),
+            manifest("a", &["b"]),
+            manifest("b", &["a"]),
+        ];
+        let ids: Vec<String> = sort_mods(mods).into_iter().map(|m| m.mod_info.id).collect();
+        assert_eq!(ids, ["pickaxe-core", "pickaxe-vanilla", "economy", "shop", "a", "b"]);
+    }
+}
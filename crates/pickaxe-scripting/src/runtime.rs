@@ -1,13 +1,18 @@
+use crate::docs::document;
 use crate::mod_loader;
+use crate::sandbox::{self, ModUsage, SandboxLimits, SharedModUsage};
+use crate::services::{self, SharedModRegistry};
+use crate::timings::EventTimings;
 use mlua::{Lua, RegistryKey};
 use pickaxe_events::{EventBus, OverrideRegistry, Priority};
 use std::collections::HashMap;
 use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
 use tracing::{error, info};
 
 /// Convert mlua::Error to anyhow::Error by stringifying it.
-fn lua_err(e: mlua::Error) -> anyhow::Error {
+pub(crate) fn lua_err(e: mlua::Error) -> anyhow::Error {
     anyhow::anyhow!("{}", e)
 }
 
@@ -17,6 +22,9 @@ pub struct ScriptRuntime {
     pub event_bus: Arc<Mutex<EventBus>>,
     pub override_registry: Arc<Mutex<OverrideRegistry>>,
     callbacks: Arc<Mutex<HashMap<u64, RegistryKey>>>,
+    usage: SharedModUsage,
+    mods: SharedModRegistry,
+    timings: Mutex<EventTimings>,
 }
 
 impl ScriptRuntime {
@@ -27,15 +35,45 @@ impl ScriptRuntime {
         let callbacks = Arc::new(Mutex::new(HashMap::new()));
 
         setup_globals(&lua, event_bus.clone(), callbacks.clone())?;
+        let mods = SharedModRegistry::default();
+        services::register_api(&lua, mods.clone())?;
+        // For `sandbox::call`, which callers only reach through the Lua VM
+        let usage: SharedModUsage = Arc::new(Mutex::new(ModUsage::default()));
+        lua.set_app_data(usage.clone());
 
         Ok(Self {
             lua,
             event_bus,
             override_registry,
             callbacks,
+            usage,
+            mods,
+            timings: Mutex::new(EventTimings::default()),
         })
     }
 
+    /// Enforce per-mod resource limits on all Lua code from now on.
+    pub fn set_limits(&self, limits: SandboxLimits) {
+        *self.usage.lock().unwrap() = ModUsage::new(limits);
+        sandbox::install_limits(&self.lua, self.usage.clone());
+    }
+
+    /// Reset per-tick instruction budgets. Called at the start of every tick.
+    pub fn begin_tick(&self) {
+        self.usage.lock().unwrap().begin_tick();
+        self.timings.lock().unwrap().tick();
+    }
+
+    /// Time spent in event handlers per mod, for `/mods timings`.
+    pub fn timings(&self) -> MutexGuard<'_, EventTimings> {
+        self.timings.lock().unwrap()
+    }
+
+    /// Ids of the mods that loaded successfully, in load order.
+    pub fn loaded_mods(&self) -> Vec<String> {
+        self.mods.lock().unwrap().loaded().to_vec()
+    }
+
     /// Discover and load mods from the given directories.
     pub fn load_mods(&self, mod_dirs: &[&Path]) -> anyhow::Result<()> {
         let mut manifests = Vec::new();
@@ -55,10 +93,12 @@ impl ScriptRuntime {
                 "Loading mod: {} v{}",
                 manifest.mod_info.name, manifest.mod_info.version
             );
-            if let Err(e) = crate::sandbox::load_mod(&self.lua, manifest) {
-                error!("Failed to load mod '{}': {}", manifest.mod_info.id, e);
+            match crate::sandbox::load_mod(&self.lua, manifest) {
+                Ok(()) => self.mods.lock().unwrap().mod_loaded(&manifest.mod_info.id),
+                Err(e) => error!("Failed to load mod '{}': {}", manifest.mod_info.id, e),
             }
         }
+        self.mods.lock().unwrap().finish_loading();
 
         let bus = self.event_bus.lock().unwrap();
         info!(
@@ -93,21 +133,62 @@ impl ScriptRuntime {
         result
     }
 
+    /// Like `fire_event_mut`, with game context available to bridge functions.
+    pub fn fire_event_mut_in_context(
+        &self,
+        event_name: &str,
+        data: &mut [(&str, String)],
+        world: *mut (),
+        world_state: *mut (),
+    ) -> bool {
+        self.lua.set_app_data(crate::bridge::LuaGameContext {
+            world_ptr: world,
+            world_state_ptr: world_state,
+        });
+        let result = self.fire_event_mut(event_name, data);
+        self.lua.remove_app_data::<crate::bridge::LuaGameContext>();
+        result
+    }
+
     /// Fire an event with string key-value data. Returns true if cancelled.
     pub fn fire_event(&self, event_name: &str, data: &[(&str, &str)]) -> bool {
+        self.dispatch(event_name, data).is_some_and(|(_, cancelled)| cancelled)
+    }
+
+    /// Fire an event whose handlers may change its fields. Once every handler
+    /// has run, the value each key ends up with is written back into `data`
+    /// (numbers are converted to strings; nil leaves the original value).
+    /// Returns true if cancelled.
+    pub fn fire_event_mut(&self, event_name: &str, data: &mut [(&str, String)]) -> bool {
+        let fields: Vec<(&str, &str)> = data.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        let Some((table, cancelled)) = self.dispatch(event_name, &fields) else {
+            return false;
+        };
+        for (key, value) in data.iter_mut() {
+            if let Ok(Some(new_value)) = table.get::<Option<String>>(*key) {
+                *value = new_value;
+            }
+        }
+        cancelled
+    }
+
+    /// Call every listener for an event with one shared table, so later
+    /// handlers see earlier changes. Returns the table and whether a
+    /// non-monitor handler cancelled, or None if nothing is listening.
+    fn dispatch(&self, event_name: &str, data: &[(&str, &str)]) -> Option<(mlua::Table, bool)> {
         let bus = self.event_bus.lock().unwrap();
         let listeners: Vec<_> = bus.get_listeners(event_name).to_vec();
         drop(bus);
 
         if listeners.is_empty() {
-            return false;
+            return None;
         }
 
         let table = match self.lua.create_table() {
             Ok(t) => t,
             Err(e) => {
                 error!("Failed to create event table: {}", e);
-                return false;
+                return None;
             }
         };
         for (key, value) in data {
@@ -121,7 +202,14 @@ impl ScriptRuntime {
             if let Some(reg_key) = callbacks.get(&listener.listener_id) {
                 let result: Result<Option<String>, mlua::Error> = (|| {
                     let func: mlua::Function = self.lua.registry_value(reg_key)?;
-                    func.call(table.clone())
+                    // Skipped quietly rather than failing like `sandbox::call`
+                    if sandbox::suspended_mod(&self.lua, &func).is_some() {
+                        return Ok(None);
+                    }
+                    let started = Instant::now();
+                    let result = func.call(table.clone());
+                    self.timings().record(&listener.mod_id, event_name, started.elapsed());
+                    result
                 })();
 
                 match result {
@@ -141,7 +229,7 @@ impl ScriptRuntime {
             }
         }
 
-        cancelled
+        Some((table, cancelled))
     }
 }
 
@@ -152,7 +240,7 @@ fn setup_globals(
 ) -> anyhow::Result<()> {
     let pickaxe = lua.create_table().map_err(lua_err)?;
 
-    // pickaxe.log(message)
+    document(lua, "pickaxe.log(message: string)", "Write a message to the server log.");
     let log_fn = lua
         .create_function(|_, msg: String| {
             info!("[Lua] {}", msg);
@@ -164,7 +252,11 @@ fn setup_globals(
     // pickaxe.events table
     let events_table = lua.create_table().map_err(lua_err)?;
 
-    // pickaxe.events.on(event_name, callback, options?)
+    document(
+        lua,
+        "pickaxe.events.on(event_name: string, callback: fun(event: table): string?, options?: { priority?: string, mod_id?: string })",
+        "Listen for a server event. Returning \"cancel\" from the callback cancels cancellable events.",
+    );
     let bus_clone = event_bus.clone();
     let cb_clone = callbacks.clone();
     let events_on = lua
@@ -183,12 +275,11 @@ fn setup_globals(
                     Priority::Normal
                 };
 
-                let mod_id = if let Some(ref opts) = options {
-                    let m: Option<String> = opts.get("mod_id").unwrap_or(None);
-                    m.unwrap_or_else(|| "unknown".into())
-                } else {
-                    "unknown".into()
-                };
+                let mod_id = options
+                    .as_ref()
+                    .and_then(|opts| opts.get::<Option<String>>("mod_id").unwrap_or(None))
+                    .or_else(|| sandbox::calling_mod(lua_ctx))
+                    .unwrap_or_else(|| "unknown".into());
 
                 let listener_id = {
                     let mut bus = bus_clone.lock().unwrap();
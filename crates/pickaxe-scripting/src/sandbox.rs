@@ -1,6 +1,163 @@
 use crate::mod_loader::ModManifest;
-use mlua::Lua;
-use tracing::debug;
+use mlua::{FromLuaMulti, Function, HookTriggers, IntoLuaMulti, Lua, VmState};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, error, warn};
+
+/// The limit hook runs every this many VM instructions, so budgets are
+/// charged in steps of this size.
+const HOOK_INTERVAL: u32 = 1000;
+
+/// Resource limits for mod code. Zero disables a limit.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SandboxLimits {
+    /// VM instructions each mod may run per tick.
+    pub instructions_per_tick: u64,
+    /// Ceiling for the whole Lua VM (mods share one), charged to the mod
+    /// that is running when it is crossed.
+    pub memory_bytes: usize,
+    /// Ticks in which a mod may break a limit before it is suspended.
+    pub max_violations: u32,
+}
+
+/// Per-mod resource usage, updated by the limit hook.
+#[derive(Default)]
+pub struct ModUsage {
+    limits: SandboxLimits,
+    /// Instructions run this tick.
+    instructions: HashMap<String, u64>,
+    /// Mods that already broke a limit this tick.
+    violated: HashSet<String>,
+    violations: HashMap<String, u32>,
+    /// Mods whose code is aborted as soon as it runs, until restart.
+    suspended: HashSet<String>,
+}
+
+pub type SharedModUsage = Arc<Mutex<ModUsage>>;
+
+impl ModUsage {
+    pub fn new(limits: SandboxLimits) -> Self {
+        Self { limits, ..Default::default() }
+    }
+
+    /// Start a new tick's instruction budgets.
+    pub fn begin_tick(&mut self) {
+        self.instructions.clear();
+        self.violated.clear();
+    }
+
+    pub fn is_suspended(&self, mod_id: &str) -> bool {
+        self.suspended.contains(mod_id)
+    }
+
+    /// Charge instructions to a mod. Returns the error to abort its code
+    /// with once it is over budget or suspended.
+    pub fn charge(&mut self, mod_id: &str, instructions: u64) -> Result<(), String> {
+        if self.suspended.contains(mod_id) {
+            return Err(format!("Mod '{}' is suspended", mod_id));
+        }
+        let used = self.instructions.entry(mod_id.to_string()).or_default();
+        *used += instructions;
+        let limit = self.limits.instructions_per_tick;
+        if limit > 0 && *used > limit {
+            return Err(self.violation(mod_id, format!("exceeded its budget of {} instructions per tick", limit)));
+        }
+        Ok(())
+    }
+
+    /// Record that a mod pushed the VM past the memory ceiling. Returns the
+    /// error to abort its code with.
+    pub fn over_memory(&mut self, mod_id: &str, used_bytes: usize) -> String {
+        let what = format!(
+            "pushed Lua memory to {} MB, over the {} MB limit",
+            used_bytes >> 20,
+            self.limits.memory_bytes >> 20
+        );
+        self.violation(mod_id, what)
+    }
+
+    /// Log the first violation of a tick and suspend the mod once it has
+    /// broken a limit in `max_violations` ticks.
+    fn violation(&mut self, mod_id: &str, what: String) -> String {
+        let message = format!("Mod '{}' {}", mod_id, what);
+        if self.violated.insert(mod_id.to_string()) {
+            let count = self.violations.entry(mod_id.to_string()).or_default();
+            *count += 1;
+            let max = self.limits.max_violations;
+            if max > 0 && *count >= max {
+                error!("{}; suspending it after {} violations", message, count);
+                self.suspended.insert(mod_id.to_string());
+            } else {
+                warn!("{}; aborting the call", message);
+            }
+        }
+        message
+    }
+}
+
+/// The mod a chunk belongs to, from the `@<mod id>/<file>` name it was
+/// loaded with.
+pub fn mod_id_from_source(source: &str) -> Option<&str> {
+    let (mod_id, _) = source.strip_prefix('@')?.split_once('/')?;
+    (!mod_id.is_empty()).then_some(mod_id)
+}
+
+/// The mod whose Lua code called the running Rust function: the nearest
+/// stack frame defined in a mod chunk.
+pub fn calling_mod(lua: &Lua) -> Option<String> {
+    (1..32).map_while(|level| lua.inspect_stack(level)).find_map(|debug| {
+        let source = debug.source();
+        source.source.as_deref().and_then(mod_id_from_source).map(str::to_string)
+    })
+}
+
+/// The mod that defined `func`, if it has been suspended.
+pub fn suspended_mod(lua: &Lua, func: &Function) -> Option<String> {
+    // Cloned out so the app data isn't borrowed while the mod runs
+    let usage = lua.app_data_ref::<SharedModUsage>().map(|usage| Arc::clone(&usage))?;
+    let source = func.info().source?;
+    let mod_id = mod_id_from_source(&source)?;
+    usage.lock().unwrap().is_suspended(mod_id).then(|| mod_id.to_string())
+}
+
+/// Call a mod's function from Rust, failing without running it if its mod
+/// is suspended. The limit hook only runs every `HOOK_INTERVAL`
+/// instructions, so short callbacks (commands, GUI clicks, HTTP responses)
+/// would otherwise never be stopped; every stored callback goes through here.
+pub fn call<R: FromLuaMulti>(lua: &Lua, func: &Function, args: impl IntoLuaMulti) -> mlua::Result<R> {
+    if let Some(mod_id) = suspended_mod(lua, func) {
+        return Err(mlua::Error::runtime(format!("Mod '{}' is suspended", mod_id)));
+    }
+    func.call(args)
+}
+
+/// Enforce `usage`'s limits with an instruction-count hook. Code is charged
+/// to the mod whose chunk it was defined in; anything else runs unlimited.
+/// LuaJIT doesn't run hooks inside JIT-compiled traces, so the count is a
+/// lower bound for hot loops.
+pub fn install_limits(lua: &Lua, usage: SharedModUsage) {
+    let limits = usage.lock().unwrap().limits;
+    if limits.instructions_per_tick == 0 && limits.memory_bytes == 0 {
+        return;
+    }
+    lua.set_hook(HookTriggers::new().every_nth_instruction(HOOK_INTERVAL), move |lua, debug| {
+        let source = debug.source();
+        let Some(mod_id) = source.source.as_deref().and_then(mod_id_from_source) else {
+            return Ok(VmState::Continue);
+        };
+        let mut usage = usage.lock().unwrap();
+        usage.charge(mod_id, HOOK_INTERVAL as u64).map_err(mlua::Error::runtime)?;
+        if limits.memory_bytes > 0 && lua.used_memory() > limits.memory_bytes {
+            // Only blame the mod if collecting garbage doesn't get back under
+            lua.gc_collect()?;
+            let used = lua.used_memory();
+            if used > limits.memory_bytes {
+                return Err(mlua::Error::runtime(usage.over_memory(mod_id, used)));
+            }
+        }
+        Ok(VmState::Continue)
+    });
+}
 
 /// Load a mod by executing its entrypoint Lua file.
 pub fn load_mod(lua: &Lua, manifest: &ModManifest) -> anyhow::Result<()> {
@@ -32,3 +189,43 @@ pub fn load_mod(lua: &Lua, manifest: &ModManifest) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mod_id_from_source() {
+        assert_eq!(mod_id_from_source("@homes/init.lua"), Some("homes"));
+        assert_eq!(mod_id_from_source("@/init.lua"), None);
+        assert_eq!(mod_id_from_source("=[C]"), None);
+    }
+
+    #[test]
+    fn test_budget_and_suspension() {
+        let mut usage = ModUsage::new(SandboxLimits { instructions_per_tick: 2000, memory_bytes: 0, max_violations: 2 });
+        assert!(usage.charge("homes", 2000).is_ok());
+        assert!(usage.charge("homes", 1000).is_err());
+        assert!(usage.charge("other", 1000).is_ok());
+        assert!(!usage.is_suspended("homes"));
+
+        usage.begin_tick();
+        assert!(usage.charge("homes", 1000).is_ok());
+        assert!(usage.charge("homes", 2000).is_err());
+        assert!(usage.is_suspended("homes"));
+        usage.begin_tick();
+        assert!(usage.charge("homes", 1).is_err());
+    }
+
+    #[test]
+    fn test_call_suspended() {
+        let lua = Lua::new();
+        let usage = SharedModUsage::default();
+        lua.set_app_data(usage.clone());
+        let handler: Function = lua.load("return function(x) return x + 1 end").set_name("@homes/init.lua").eval().unwrap();
+        assert_eq!(call::<i32>(&lua, &handler, 1).unwrap(), 2);
+
+        usage.lock().unwrap().suspended.insert("homes".into());
+        assert!(call::<i32>(&lua, &handler, 1).is_err());
+    }
+}
@@ -0,0 +1,187 @@
+use crate::docs::document;
+use crate::runtime::lua_err;
+use crate::sandbox::{self, calling_mod};
+use mlua::{Lua, RegistryKey};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// APIs and message handlers that mods share with each other through
+/// `pickaxe.services` and `pickaxe.mods`.
+#[derive(Default)]
+pub struct ModRegistry {
+    /// Mods that loaded successfully, in load order.
+    loaded: Vec<String>,
+    /// False while mods are still loading.
+    ready: bool,
+    /// Service tables by name, with the mod that provides them.
+    services: HashMap<String, (String, RegistryKey)>,
+    /// `pickaxe.mods.on` handlers by message name, with the mod that added them.
+    handlers: HashMap<String, Vec<(String, RegistryKey)>>,
+}
+
+pub type SharedModRegistry = Arc<Mutex<ModRegistry>>;
+
+impl ModRegistry {
+    pub fn mod_loaded(&mut self, mod_id: &str) {
+        self.loaded.push(mod_id.to_string());
+    }
+
+    pub fn loaded(&self) -> &[String] {
+        &self.loaded
+    }
+
+    /// Called once every mod has loaded; missing services are final from now on.
+    pub fn finish_loading(&mut self) {
+        self.ready = true;
+    }
+
+    /// The error for `services.get` of a service nobody provides.
+    fn missing_service(&self, name: &str, caller: Option<&str>) -> String {
+        if self.ready {
+            format!("No loaded mod provides service '{}'", name)
+        } else {
+            format!(
+                "Service '{}' is not provided yet; add its provider to `after` under [mod.load_order] in {}'s pickaxe.toml, or look it up in server_start",
+                name,
+                caller.unwrap_or("this mod")
+            )
+        }
+    }
+}
+
+/// Register the `pickaxe.services` and `pickaxe.mods` APIs.
+pub fn register_api(lua: &Lua, registry: SharedModRegistry) -> anyhow::Result<()> {
+    let pickaxe: mlua::Table = lua.globals().get("pickaxe").map_err(lua_err)?;
+    let services_table = lua.create_table().map_err(lua_err)?;
+    let mods_table = lua.create_table().map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.services.provide(name: string, api: table)",
+        "Publish a table of functions for other mods under `name`. Fails if another mod already provides it.",
+    );
+    let reg = registry.clone();
+    let provide = lua
+        .create_function(move |lua, (name, api): (String, mlua::Table)| {
+            let provider = calling_mod(lua).unwrap_or_else(|| "unknown".into());
+            let mut reg = reg.lock().unwrap();
+            if let Some((existing, _)) = reg.services.get(&name) {
+                if *existing != provider {
+                    return Err(mlua::Error::runtime(format!(
+                        "Service '{}' is already provided by mod '{}'",
+                        name, existing
+                    )));
+                }
+            }
+            let key = lua.create_registry_value(api)?;
+            reg.services.insert(name, (provider, key));
+            Ok(())
+        })
+        .map_err(lua_err)?;
+    services_table.set("provide", provide).map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.services.get(name: string) -> table",
+        "The table another mod provided under `name`. Raises an error naming the fix when no mod provides it.",
+    );
+    let reg = registry.clone();
+    let get = lua
+        .create_function(move |lua, name: String| {
+            let reg = reg.lock().unwrap();
+            match reg.services.get(&name) {
+                Some((_, key)) => lua.registry_value::<mlua::Table>(key),
+                None => Err(mlua::Error::runtime(reg.missing_service(&name, calling_mod(lua).as_deref()))),
+            }
+        })
+        .map_err(lua_err)?;
+    services_table.set("get", get).map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.services.has(name: string) -> boolean, string?",
+        "Whether a service is provided, and by which mod.",
+    );
+    let reg = registry.clone();
+    let has = lua
+        .create_function(move |_, name: String| {
+            let reg = reg.lock().unwrap();
+            let provider = reg.services.get(&name).map(|(provider, _)| provider.clone());
+            Ok((provider.is_some(), provider))
+        })
+        .map_err(lua_err)?;
+    services_table.set("has", has).map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.mods.on(message: string, handler: fun(data: any, sender: string))",
+        "Handle messages sent with `pickaxe.mods.emit`. `sender` is the id of the emitting mod.",
+    );
+    let reg = registry.clone();
+    let on = lua
+        .create_function(move |lua, (message, handler): (String, mlua::Function)| {
+            let mod_id = calling_mod(lua).unwrap_or_else(|| "unknown".into());
+            let key = lua.create_registry_value(handler)?;
+            reg.lock().unwrap().handlers.entry(message).or_default().push((mod_id, key));
+            Ok(())
+        })
+        .map_err(lua_err)?;
+    mods_table.set("on", on).map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.mods.emit(message: string, data?: any) -> integer",
+        "Send a message to every mod handling it, in the order the handlers were added. Returns how many handled it; a failing handler is logged and skipped.",
+    );
+    let reg = registry.clone();
+    let emit = lua
+        .create_function(move |lua, (message, data): (String, mlua::Value)| {
+            let sender = calling_mod(lua).unwrap_or_else(|| "unknown".into());
+            // Copy the handlers out so they can add handlers or emit themselves
+            let handlers: Vec<(String, mlua::Function)> = {
+                let reg = reg.lock().unwrap();
+                let Some(handlers) = reg.handlers.get(&message) else {
+                    return Ok(0);
+                };
+                handlers
+                    .iter()
+                    .filter_map(|(mod_id, key)| Some((mod_id.clone(), lua.registry_value(key).ok()?)))
+                    .collect()
+            };
+            let mut handled = 0;
+            for (mod_id, handler) in handlers {
+                match sandbox::call::<()>(lua, &handler, (data.clone(), sender.as_str())) {
+                    Ok(()) => handled += 1,
+                    Err(e) => warn!("Mod '{}' failed handling message '{}': {}", mod_id, message, e),
+                }
+            }
+            Ok(handled)
+        })
+        .map_err(lua_err)?;
+    mods_table.set("emit", emit).map_err(lua_err)?;
+
+    document(lua, "pickaxe.mods.is_loaded(id: string) -> boolean", "Whether a mod has loaded successfully.");
+    let is_loaded = lua
+        .create_function(move |_, id: String| Ok(registry.lock().unwrap().loaded.contains(&id)))
+        .map_err(lua_err)?;
+    mods_table.set("is_loaded", is_loaded).map_err(lua_err)?;
+
+    pickaxe.set("services", services_table).map_err(lua_err)?;
+    pickaxe.set("mods", mods_table).map_err(lua_err)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_service_message() {
+        let mut reg = ModRegistry::default();
+        let early = reg.missing_service("economy", Some("shop"));
+        assert!(early.contains("'economy' is not provided yet") && early.contains("in shop's pickaxe.toml"));
+        reg.finish_loading();
+        assert_eq!(reg.missing_service("economy", Some("shop")), "No loaded mod provides service 'economy'");
+    }
+}
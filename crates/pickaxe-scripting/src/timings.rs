@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::warn;
+
+/// Ticks between repeated slow-handler warnings for the same mod and event.
+const WARN_INTERVAL_TICKS: u64 = 1200;
+
+/// Time spent in each mod's event handlers since startup or the last reset,
+/// for `/mods timings`.
+#[derive(Default)]
+pub struct EventTimings {
+    /// A single handler call slower than this is logged. Zero disables it.
+    slow_handler: Duration,
+    /// Ticks since the last reset.
+    ticks: u64,
+    /// Per (mod id, event name).
+    stats: HashMap<(String, String), HandlerStats>,
+    /// Tick of the last slow-handler warning per (mod id, event name).
+    warned_at: HashMap<(String, String), u64>,
+    /// Ticks since startup, for spacing warnings out.
+    tick_count: u64,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct HandlerStats {
+    total: Duration,
+    calls: u64,
+    max: Duration,
+}
+
+/// One mod's line in the `/mods timings` report.
+#[derive(Debug, PartialEq)]
+pub struct ModTimings {
+    pub mod_id: String,
+    /// Average milliseconds per tick across all its handlers.
+    pub ms_per_tick: f64,
+    pub calls: u64,
+    /// `(event, ms per tick, slowest call in ms)`, slowest first.
+    pub events: Vec<(String, f64, f64)>,
+}
+
+impl EventTimings {
+    pub fn set_slow_handler_ms(&mut self, ms: u64) {
+        self.slow_handler = Duration::from_millis(ms);
+    }
+
+    /// Count a tick. Called at the start of every tick.
+    pub fn tick(&mut self) {
+        self.ticks += 1;
+        self.tick_count += 1;
+    }
+
+    /// Record one handler call, warning when it went over the slow-handler budget.
+    pub fn record(&mut self, mod_id: &str, event: &str, elapsed: Duration) {
+        let key = (mod_id.to_string(), event.to_string());
+        if !self.slow_handler.is_zero() && elapsed > self.slow_handler {
+            let last = self.warned_at.get(&key).copied();
+            if last.is_none_or(|t| self.tick_count >= t + WARN_INTERVAL_TICKS) {
+                warn!(
+                    "Mod '{}' took {:.1} ms handling '{}' (budget {} ms)",
+                    mod_id,
+                    elapsed.as_secs_f64() * 1000.0,
+                    event,
+                    self.slow_handler.as_millis()
+                );
+                self.warned_at.insert(key.clone(), self.tick_count);
+            }
+        }
+        let stats = self.stats.entry(key).or_default();
+        stats.total += elapsed;
+        stats.calls += 1;
+        stats.max = stats.max.max(elapsed);
+    }
+
+    /// Start measuring from zero.
+    pub fn reset(&mut self) {
+        self.ticks = 0;
+        self.stats.clear();
+    }
+
+    /// Ticks the report covers.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Per-mod totals, the most expensive mod first.
+    pub fn report(&self) -> Vec<ModTimings> {
+        let ticks = self.ticks.max(1) as f64;
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let mut mods: HashMap<&str, ModTimings> = HashMap::new();
+        for ((mod_id, event), stats) in &self.stats {
+            let entry = mods.entry(mod_id).or_insert_with(|| ModTimings {
+                mod_id: mod_id.clone(),
+                ms_per_tick: 0.0,
+                calls: 0,
+                events: Vec::new(),
+            });
+            entry.ms_per_tick += ms(stats.total) / ticks;
+            entry.calls += stats.calls;
+            entry.events.push((event.clone(), ms(stats.total) / ticks, ms(stats.max)));
+        }
+        let mut report: Vec<ModTimings> = mods.into_values().collect();
+        for m in &mut report {
+            m.events.sort_by(|a, b| b.1.total_cmp(&a.1));
+        }
+        report.sort_by(|a, b| b.ms_per_tick.total_cmp(&a.ms_per_tick));
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let mut timings = EventTimings::default();
+        for _ in 0..4 {
+            timings.tick();
+        }
+        timings.record("shop", "player_move", Duration::from_millis(2));
+        timings.record("shop", "player_move", Duration::from_millis(6));
+        timings.record("shop", "player_join", Duration::from_millis(4));
+        timings.record("chat", "player_chat", Duration::from_millis(20));
+
+        let report = timings.report();
+        assert_eq!(report[0].mod_id, "chat");
+        assert_eq!(report[0].ms_per_tick, 5.0);
+        assert_eq!(report[1].calls, 3);
+        assert_eq!(report[1].ms_per_tick, 3.0);
+        assert_eq!(report[1].events[0], ("player_move".to_string(), 2.0, 6.0));
+
+        timings.reset();
+        assert!(timings.report().is_empty());
+        assert_eq!(timings.ticks(), 0);
+    }
+}
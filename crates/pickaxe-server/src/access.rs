@@ -0,0 +1,529 @@
+use pickaxe_types::TextComponent;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Vanilla-compatible access list files, read from the server directory.
+const WHITELIST_FILE: &str = "whitelist.json";
+const BANNED_PLAYERS_FILE: &str = "banned-players.json";
+const BANNED_IPS_FILE: &str = "banned-ips.json";
+const OPS_FILE: &str = "ops.json";
+
+/// Default ban reason, as used by vanilla.
+pub const DEFAULT_BAN_REASON: &str = "Banned by an operator.";
+
+/// Whether the whitelist is enforced. Starts from `whitelist` in server.toml
+/// and is toggled at runtime by `/whitelist on|off`.
+static WHITELIST_ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn whitelist_enabled() -> bool {
+    WHITELIST_ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn set_whitelist_enabled(enabled: bool) {
+    WHITELIST_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Operators, kept in memory since permissions are checked on nearly every
+/// command. Refreshed by `save_ops` and `reload_ops`.
+static OPS: RwLock<OpList> = RwLock::new(OpList { entries: Vec::new(), legacy: Vec::new() });
+
+struct OpList {
+    /// ops.json
+    entries: Vec<OpEntry>,
+    /// Names in the legacy config/ops.toml, which count as level 4.
+    legacy: Vec<String>,
+}
+
+impl OpList {
+    fn level(&self, name: &str, uuid: Option<Uuid>) -> u8 {
+        let from_json = self
+            .entries
+            .iter()
+            .filter(|op| op.matches(name, uuid))
+            .map(|op| op.level.min(4))
+            .max()
+            .unwrap_or(0);
+        if self.legacy_unclaimed(name) {
+            4
+        } else {
+            from_json
+        }
+    }
+
+    /// A legacy name has no UUID, so it's matched by name until its player
+    /// logs in and gets an ops.json entry.
+    fn legacy_unclaimed(&self, name: &str) -> bool {
+        self.legacy.iter().any(|op| op.eq_ignore_ascii_case(name))
+            && !self.entries.iter().any(|op| op.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Give placeholder entries for `name` the player's real UUID, and an
+    /// unclaimed legacy name an ops.json entry. Returns true if anything changed.
+    fn claim(&mut self, name: &str, uuid: Uuid) -> bool {
+        let mut changed = false;
+        for op in self.entries.iter_mut().filter(|op| op.uuid != uuid && op.matches(name, None)) {
+            op.uuid = uuid;
+            op.name = name.to_string();
+            changed = true;
+        }
+        if self.legacy_unclaimed(name) {
+            self.entries.push(OpEntry {
+                uuid,
+                name: name.to_string(),
+                level: 4,
+                bypasses_player_limit: false,
+            });
+            changed = true;
+        }
+        changed
+    }
+}
+
+/// True for an entry added before its player's real UUID was known, which
+/// holds the offline-mode UUID for its name. Only these are matched by name:
+/// in online mode anyone can take a name once its owner gives it up.
+fn is_placeholder(uuid: Uuid, name: &str) -> bool {
+    uuid == crate::network::offline_uuid(name)
+}
+
+/// Whether a list entry is for the given player: by UUID, or by name for
+/// placeholder entries.
+fn entry_matches(entry_uuid: Uuid, entry_name: &str, name: &str, uuid: Option<Uuid>) -> bool {
+    Some(entry_uuid) == uuid || (is_placeholder(entry_uuid, entry_name) && entry_name.eq_ignore_ascii_case(name))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhitelistEntry {
+    pub uuid: Uuid,
+    pub name: String,
+}
+
+impl WhitelistEntry {
+    /// Whether this entry is for the given player (see `OpEntry::matches`).
+    pub fn matches(&self, name: &str, uuid: Option<Uuid>) -> bool {
+        entry_matches(self.uuid, &self.name, name, uuid)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpEntry {
+    pub uuid: Uuid,
+    pub name: String,
+    /// Permission level 1-4.
+    pub level: u8,
+    #[serde(default)]
+    pub bypasses_player_limit: bool,
+}
+
+impl OpEntry {
+    /// Whether this entry is for the given player: by UUID, or by name for
+    /// placeholder entries.
+    pub fn matches(&self, name: &str, uuid: Option<Uuid>) -> bool {
+        entry_matches(self.uuid, &self.name, name, uuid)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerBan {
+    pub uuid: Uuid,
+    pub name: String,
+    pub created: String,
+    pub source: String,
+    pub expires: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpBan {
+    pub ip: String,
+    pub created: String,
+    pub source: String,
+    pub expires: String,
+    pub reason: String,
+}
+
+impl PlayerBan {
+    pub fn new(uuid: Uuid, name: &str, source: &str, reason: Option<&str>) -> Self {
+        Self {
+            uuid,
+            name: name.to_string(),
+            created: format_timestamp(SystemTime::now()),
+            source: source.to_string(),
+            expires: "forever".to_string(),
+            reason: reason.unwrap_or(DEFAULT_BAN_REASON).to_string(),
+        }
+    }
+
+    /// Text shown on the disconnect screen.
+    pub fn kick_message(&self) -> TextComponent {
+        ban_message("multiplayer.disconnect.banned", &self.reason, &self.expires)
+    }
+}
+
+impl IpBan {
+    pub fn new(ip: IpAddr, source: &str, reason: Option<&str>) -> Self {
+        Self {
+            ip: ip.to_string(),
+            created: format_timestamp(SystemTime::now()),
+            source: source.to_string(),
+            expires: "forever".to_string(),
+            reason: reason.unwrap_or(DEFAULT_BAN_REASON).to_string(),
+        }
+    }
+
+    /// Text shown on the disconnect screen.
+    pub fn kick_message(&self) -> TextComponent {
+        ban_message("multiplayer.disconnect.banned_ip", &self.reason, &self.expires)
+    }
+}
+
+/// Vanilla's translated ban screen: `<key>.reason`, then `<key>.expiration`
+/// for temporary bans.
+fn ban_message(key: &str, reason: &str, expires: &str) -> TextComponent {
+    let mut message = TextComponent::translatable(format!("{}.reason", key), vec![TextComponent::plain(reason)]);
+    if !is_forever(expires) {
+        message = message.append(TextComponent::translatable(
+            format!("{}.expiration", key),
+            vec![TextComponent::plain(expires)],
+        ));
+    }
+    message
+}
+
+fn is_forever(expires: &str) -> bool {
+    expires.is_empty() || expires.eq_ignore_ascii_case("forever")
+}
+
+/// True if an `expires` field lies in the past. Unparseable dates never expire.
+fn is_expired(expires: &str) -> bool {
+    if is_forever(expires) {
+        return false;
+    }
+    match parse_timestamp(expires) {
+        Some(secs) => secs <= unix_now(),
+        None => false,
+    }
+}
+
+fn load_list<T: for<'de> Deserialize<'de>>(file: &str) -> Vec<T> {
+    let path = Path::new(file);
+    if !path.exists() {
+        return Vec::new();
+    }
+    match std::fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            tracing::warn!("Failed to parse {}: {}", file, e);
+            Vec::new()
+        }),
+        Err(e) => {
+            tracing::warn!("Failed to read {}: {}", file, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Written atomically: a torn file would load as empty and lift every entry.
+fn save_list<T: Serialize>(file: &str, entries: &[T]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(entries).map_err(|e| e.to_string())?;
+    pickaxe_region::write_atomic(Path::new(file), json.as_bytes()).map_err(|e| format!("Failed to write {}: {}", file, e))
+}
+
+pub fn load_whitelist() -> Vec<WhitelistEntry> {
+    load_list(WHITELIST_FILE)
+}
+
+pub fn save_whitelist(entries: &[WhitelistEntry]) -> Result<(), String> {
+    save_list(WHITELIST_FILE, entries)
+}
+
+/// Banned players, with expired entries dropped.
+pub fn load_player_bans() -> Vec<PlayerBan> {
+    let mut bans: Vec<PlayerBan> = load_list(BANNED_PLAYERS_FILE);
+    bans.retain(|b| !is_expired(&b.expires));
+    bans
+}
+
+pub fn save_player_bans(entries: &[PlayerBan]) -> Result<(), String> {
+    save_list(BANNED_PLAYERS_FILE, entries)
+}
+
+/// Banned IPs, with expired entries dropped.
+pub fn load_ip_bans() -> Vec<IpBan> {
+    let mut bans: Vec<IpBan> = load_list(BANNED_IPS_FILE);
+    bans.retain(|b| !is_expired(&b.expires));
+    bans
+}
+
+pub fn save_ip_bans(entries: &[IpBan]) -> Result<(), String> {
+    save_list(BANNED_IPS_FILE, entries)
+}
+
+/// ops.json as it is on disk, for editing.
+pub fn load_ops() -> Vec<OpEntry> {
+    load_list(OPS_FILE)
+}
+
+pub fn save_ops(entries: &[OpEntry]) -> Result<(), String> {
+    save_list(OPS_FILE, entries)?;
+    OPS.write().unwrap_or_else(|e| e.into_inner()).entries = entries.to_vec();
+    Ok(())
+}
+
+/// Re-read ops.json and config/ops.toml into memory, returning how many
+/// operators they list.
+pub fn reload_ops() -> usize {
+    let mut ops = OPS.write().unwrap_or_else(|e| e.into_inner());
+    ops.entries = load_ops();
+    ops.legacy = crate::config::load_ops();
+    ops.entries.len() + ops.legacy.len()
+}
+
+/// Permission level (0-4) of a player. Entries in ops.json are matched by
+/// UUID, or by name for placeholder entries; unclaimed names in the legacy
+/// config/ops.toml count as level 4.
+pub fn permission_level(name: &str, uuid: Option<Uuid>) -> u8 {
+    OPS.read().unwrap_or_else(|e| e.into_inner()).level(name, uuid)
+}
+
+/// Called as a player joins: op and whitelist entries that could only be
+/// matched by their name are rewritten with the player's UUID, so nobody who
+/// takes the name later inherits them.
+pub fn claim_entries(name: &str, uuid: Uuid) {
+    let mut ops = OPS.write().unwrap_or_else(|e| e.into_inner());
+    if ops.claim(name, uuid) {
+        if let Err(e) = save_list(OPS_FILE, &ops.entries) {
+            tracing::warn!("{}", e);
+        }
+    }
+    drop(ops);
+
+    let mut whitelist = load_whitelist();
+    if claim_whitelist(&mut whitelist, name, uuid) {
+        if let Err(e) = save_whitelist(&whitelist) {
+            tracing::warn!("{}", e);
+        }
+    }
+}
+
+/// Give placeholder whitelist entries for `name` the player's real UUID.
+/// Returns true if any changed.
+fn claim_whitelist(entries: &mut [WhitelistEntry], name: &str, uuid: Uuid) -> bool {
+    let mut changed = false;
+    for entry in entries.iter_mut().filter(|w| w.uuid != uuid && w.matches(name, None)) {
+        entry.uuid = uuid;
+        entry.name = name.to_string();
+        changed = true;
+    }
+    changed
+}
+
+/// Login check: returns the disconnect reason if the player may not join.
+/// Files are re-read on every login so manual edits apply immediately.
+pub fn check_login(name: &str, uuid: Uuid, ip: IpAddr) -> Option<TextComponent> {
+    if let Some(ban) = load_player_bans()
+        .into_iter()
+        .find(|b| b.uuid == uuid || b.name.eq_ignore_ascii_case(name))
+    {
+        return Some(ban.kick_message());
+    }
+    let ip_str = ip.to_string();
+    if let Some(ban) = load_ip_bans().into_iter().find(|b| b.ip == ip_str) {
+        return Some(ban.kick_message());
+    }
+    if whitelist_enabled() {
+        let is_op = permission_level(name, Some(uuid)) > 0;
+        let listed = load_whitelist().iter().any(|w| w.matches(name, Some(uuid)));
+        if !is_op && !listed {
+            return Some(TextComponent::translatable("multiplayer.disconnect.not_whitelisted", Vec::new()));
+        }
+    }
+    None
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Format a time in vanilla's `yyyy-MM-dd HH:mm:ss Z` layout (always UTC).
+pub fn format_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} +0000",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Parse a `yyyy-MM-dd HH:mm:ss Z` timestamp into Unix seconds.
+pub(crate) fn parse_timestamp(s: &str) -> Option<i64> {
+    let mut parts = s.split_whitespace();
+    let date = parts.next()?;
+    let time = parts.next()?;
+    let offset = parts.next().unwrap_or("+0000");
+
+    let mut d = date.split('-').map(|p| p.parse::<i64>().ok());
+    let (year, month, day) = (d.next()??, d.next()??, d.next()??);
+    let mut t = time.split(':').map(|p| p.parse::<i64>().ok());
+    let (hour, minute, second) = (t.next()??, t.next()??, t.next()??);
+
+    let (sign, digits) = if let Some(rest) = offset.strip_prefix('+') {
+        (1, rest)
+    } else {
+        (-1, offset.strip_prefix('-')?)
+    };
+    if digits.len() != 4 || !digits.is_ascii() {
+        return None;
+    }
+    let offset_secs = sign * (digits[..2].parse::<i64>().ok()? * 3600 + digits[2..].parse::<i64>().ok()? * 60);
+
+    let days = days_from_civil(year, month, day);
+    Some(days * 86_400 + hour * 3600 + minute * 60 + second - offset_secs)
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Inverse of [`days_from_civil`].
+pub(crate) fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_timestamp_roundtrip() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let formatted = format_timestamp(time);
+        assert_eq!(formatted, "2023-11-14 22:13:20 +0000");
+        assert_eq!(parse_timestamp(&formatted), Some(1_700_000_000));
+        assert_eq!(parse_timestamp("2023-11-14 23:13:20 +0100"), Some(1_700_000_000));
+        assert_eq!(parse_timestamp("forever"), None);
+    }
+
+    #[test]
+    fn test_expiry() {
+        assert!(!is_expired("forever"));
+        assert!(is_expired("2000-01-01 00:00:00 +0000"));
+        assert!(!is_expired("2999-01-01 00:00:00 +0000"));
+    }
+
+    #[test]
+    fn test_ops_json() {
+        let json = r#"[{"uuid":"069a79f4-44e9-4726-a5be-fca90e38aaf5","name":"Notch","level":3,"bypassesPlayerLimit":true}]"#;
+        let ops: Vec<OpEntry> = serde_json::from_str(json).unwrap();
+        assert_eq!(ops[0].level, 3);
+        assert!(ops[0].bypasses_player_limit);
+        assert!(serde_json::to_string(&ops).unwrap().contains("\"bypassesPlayerLimit\":true"));
+    }
+
+    #[test]
+    fn test_op_matching() {
+        let real = Uuid::from_u128(7);
+        let mut ops = OpList {
+            entries: vec![
+                OpEntry {
+                    uuid: Uuid::from_u128(1),
+                    name: "Notch".into(),
+                    level: 4,
+                    bypasses_player_limit: false,
+                },
+                OpEntry {
+                    uuid: crate::network::offline_uuid("Steve"),
+                    name: "Steve".into(),
+                    level: 2,
+                    bypasses_player_limit: false,
+                },
+            ],
+            legacy: vec!["Alex".into()],
+        };
+        // Taking a former op's name isn't enough
+        assert_eq!(ops.level("Notch", Some(real)), 0);
+        assert_eq!(ops.level("notch", Some(Uuid::from_u128(1))), 4);
+        // Placeholder and legacy entries match by name until claimed
+        assert_eq!(ops.level("steve", Some(real)), 2);
+        assert_eq!(ops.level("Alex", Some(real)), 4);
+
+        assert!(ops.claim("Steve", real));
+        assert!(ops.claim("Alex", Uuid::from_u128(8)));
+        assert!(!ops.claim("Alex", Uuid::from_u128(8)));
+        assert_eq!(ops.level("Steve", Some(real)), 2);
+        assert_eq!(ops.level("Steve", Some(Uuid::from_u128(9))), 0);
+        assert_eq!(ops.level("Alex", Some(Uuid::from_u128(9))), 0);
+        assert_eq!(ops.level("Alex", Some(Uuid::from_u128(8))), 4);
+    }
+
+    #[test]
+    fn test_whitelist_matching() {
+        let real = Uuid::from_u128(7);
+        let mut entries = vec![
+            WhitelistEntry {
+                uuid: Uuid::from_u128(1),
+                name: "Notch".into(),
+            },
+            WhitelistEntry {
+                uuid: crate::network::offline_uuid("Steve"),
+                name: "Steve".into(),
+            },
+        ];
+        assert!(!entries[0].matches("Notch", Some(real)));
+        assert!(entries[0].matches("Notch", Some(Uuid::from_u128(1))));
+        assert!(entries[1].matches("steve", Some(real)));
+
+        assert!(claim_whitelist(&mut entries, "Steve", real));
+        assert!(!claim_whitelist(&mut entries, "Steve", real));
+        assert!(entries[1].matches("Steve", Some(real)));
+        assert!(!entries[1].matches("Steve", Some(Uuid::from_u128(9))));
+    }
+
+    #[test]
+    fn test_vanilla_json() {
+        let json = r#"[{"uuid":"069a79f4-44e9-4726-a5be-fca90e38aaf5","name":"Notch","created":"2024-01-01 00:00:00 +0000","source":"Server","expires":"forever","reason":"Banned by an operator."}]"#;
+        let bans: Vec<PlayerBan> = serde_json::from_str(json).unwrap();
+        assert_eq!(bans[0].name, "Notch");
+        assert_eq!(
+            bans[0].kick_message(),
+            TextComponent::translatable(
+                "multiplayer.disconnect.banned.reason",
+                vec![TextComponent::plain("Banned by an operator.")]
+            )
+        );
+    }
+}
@@ -0,0 +1,537 @@
+use pickaxe_protocol_core::{AdvancementDisplay, AdvancementEntry};
+use pickaxe_types::{ItemStack, TextComponent};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Directory in the world holding each player's `<uuid>.json` progress.
+pub const ADVANCEMENTS_DIR: &str = "advancements";
+
+/// What obtains a criterion. Triggers the server doesn't raise, and
+/// `minecraft:impossible`, can only be granted with /advancement or by mods.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trigger {
+    /// `minecraft:inventory_changed`: every item predicate is matched by some stack.
+    InventoryChanged(Vec<ItemPredicate>),
+    /// `minecraft:player_killed_entity`, optionally limited to entity types.
+    KilledEntity(Option<Vec<String>>),
+    /// `minecraft:entity_killed_player`, optionally limited to entity types.
+    KilledByEntity(Option<Vec<String>>),
+    /// `minecraft:recipe_crafted`, by recipe ID (the crafted item's name).
+    RecipeCrafted(String),
+    /// `minecraft:tick`: obtained on the next check.
+    Tick,
+    Manual,
+}
+
+/// An entry of `inventory_changed`'s `items`: any of `items` (every item
+/// if empty), at least `min_count` in one stack.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemPredicate {
+    pub items: Vec<String>,
+    pub min_count: i32,
+}
+
+impl ItemPredicate {
+    pub fn matches(&self, stack: &ItemStack) -> bool {
+        let name = pickaxe_data::item_id_to_name(stack.item_id).unwrap_or("");
+        (self.items.is_empty() || self.items.iter().any(|i| i == name)) && stack.count as i32 >= self.min_count
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frame {
+    Task,
+    Challenge,
+    Goal,
+}
+
+#[derive(Debug, Clone)]
+pub struct Display {
+    pub title: TextComponent,
+    pub description: TextComponent,
+    pub icon: Option<ItemStack>,
+    pub frame: Frame,
+    pub background: Option<String>,
+    pub show_toast: bool,
+    pub announce_to_chat: bool,
+    pub hidden: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Advancement {
+    pub id: String,
+    pub parent: Option<String>,
+    pub display: Option<Display>,
+    pub criteria: Vec<(String, Trigger)>,
+    /// Groups of criterion names; the advancement is done once every group
+    /// has one obtained.
+    pub requirements: Vec<Vec<String>>,
+    /// Experience points awarded when it is done.
+    pub experience: i32,
+    /// Where the client draws it: depth in the tree, then row.
+    position: (f32, f32),
+}
+
+/// Every loaded advancement, parents before children.
+#[derive(Default)]
+pub struct AdvancementTree {
+    advancements: Vec<Advancement>,
+    index: HashMap<String, usize>,
+}
+
+impl AdvancementTree {
+    /// Load advancements in datapack layout from `data_dir`:
+    /// `<namespace>/advancement/<path>.json` becomes `<namespace>:<path>`.
+    /// Item tags used by criteria come from `<namespace>/tags/item/`.
+    pub fn load(data_dir: &Path) -> Self {
+        let mut files = Vec::new();
+        let mut tags = HashMap::new();
+        for namespace in std::fs::read_dir(data_dir).into_iter().flatten().flatten() {
+            let ns = namespace.file_name().to_string_lossy().into_owned();
+            collect_json(&namespace.path().join("advancement"), &ns, "", &mut files);
+            let mut tag_files = Vec::new();
+            collect_json(&namespace.path().join("tags").join("item"), &ns, "", &mut tag_files);
+            for (id, json) in tag_files {
+                let values = json["values"].as_array().cloned().unwrap_or_default();
+                tags.insert(id, values.iter().filter_map(|v| v.as_str().map(unprefixed)).collect::<Vec<_>>());
+            }
+        }
+
+        let mut advancements = Vec::new();
+        for (id, json) in files {
+            match parse_advancement(&id, &json, &tags) {
+                Ok(advancement) => advancements.push(advancement),
+                Err(e) => tracing::warn!("Skipping advancement {}: {}", id, e),
+            }
+        }
+        Self::from_advancements(advancements)
+    }
+
+    /// Order parents first, drop those with a missing parent and lay out the tree.
+    pub fn from_advancements(mut pending: Vec<Advancement>) -> Self {
+        pending.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut tree = Self::default();
+        loop {
+            let before = pending.len();
+            pending.retain(|advancement| {
+                let ready = advancement.parent.as_ref().map_or(true, |p| tree.index.contains_key(p));
+                if ready {
+                    tree.index.insert(advancement.id.clone(), tree.advancements.len());
+                    tree.advancements.push(advancement.clone());
+                }
+                !ready
+            });
+            if pending.len() == before {
+                break;
+            }
+        }
+        for advancement in pending {
+            tracing::warn!("Skipping advancement {}: parent {:?} not found", advancement.id, advancement.parent);
+        }
+        tree.layout();
+        tree
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Advancement> {
+        self.index.get(id).map(|&i| &self.advancements[i])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Advancement> {
+        self.advancements.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.advancements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.advancements.is_empty()
+    }
+
+    /// Children are drawn one column right of their parent; leaves take one
+    /// row each and parents are centred on their children.
+    fn layout(&mut self) {
+        let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+        let mut roots = Vec::new();
+        for (i, advancement) in self.advancements.iter().enumerate() {
+            match advancement.parent.as_ref().and_then(|p| self.index.get(p)) {
+                Some(&parent) => children.entry(parent).or_default().push(i),
+                None => roots.push(i),
+            }
+        }
+        fn place(node: usize, depth: f32, next_row: &mut f32, children: &HashMap<usize, Vec<usize>>, out: &mut [(f32, f32)]) -> f32 {
+            let row = match children.get(&node) {
+                Some(kids) => {
+                    let rows: Vec<f32> = kids.iter().map(|&k| place(k, depth + 1.0, next_row, children, out)).collect();
+                    (rows[0] + rows[rows.len() - 1]) / 2.0
+                }
+                None => {
+                    *next_row += 1.0;
+                    *next_row - 1.0
+                }
+            };
+            out[node] = (depth, row);
+            row
+        }
+        let mut positions = vec![(0.0, 0.0); self.advancements.len()];
+        for root in roots {
+            // Each root is its own tab
+            place(root, 0.0, &mut 0.0, &children, &mut positions);
+        }
+        for (advancement, position) in self.advancements.iter_mut().zip(positions) {
+            advancement.position = position;
+        }
+    }
+
+    /// The whole tree for an UpdateAdvancements packet.
+    pub fn packet_entries(&self) -> Vec<AdvancementEntry> {
+        self.advancements
+            .iter()
+            .map(|a| AdvancementEntry {
+                id: a.id.clone(),
+                parent: a.parent.clone(),
+                display: a.display.as_ref().map(|d| AdvancementDisplay {
+                    title: d.title.clone(),
+                    description: d.description.clone(),
+                    icon: d.icon.clone(),
+                    frame: match d.frame {
+                        Frame::Task => 0,
+                        Frame::Challenge => 1,
+                        Frame::Goal => 2,
+                    },
+                    background: d.background.clone(),
+                    show_toast: d.show_toast,
+                    hidden: d.hidden,
+                    x: a.position.0,
+                    y: a.position.1,
+                }),
+                requirements: a.requirements.clone(),
+            })
+            .collect()
+    }
+}
+
+fn unprefixed(id: &str) -> String {
+    id.strip_prefix("minecraft:").unwrap_or(id).to_string()
+}
+
+/// Every `.json` file under `dir` as (`namespace:relative/path`, contents).
+fn collect_json(dir: &Path, namespace: &str, prefix: &str, out: &mut Vec<(String, Value)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = entry.path();
+        if path.is_dir() {
+            collect_json(&path, namespace, &format!("{}{}/", prefix, name), out);
+        } else if let Some(stem) = name.strip_suffix(".json") {
+            let id = format!("{}:{}{}", namespace, prefix, stem);
+            match std::fs::read(&path).map_err(|e| e.to_string()).and_then(|d| serde_json::from_slice(&d).map_err(|e| e.to_string())) {
+                Ok(json) => out.push((id, json)),
+                Err(e) => tracing::warn!("Failed to read {}: {}", path.display(), e),
+            }
+        }
+    }
+}
+
+fn parse_text(value: &Value) -> TextComponent {
+    match value {
+        Value::String(s) => TextComponent::plain(s),
+        other => serde_json::from_value(other.clone()).unwrap_or_else(|_| TextComponent::plain("")),
+    }
+}
+
+/// Item names or `#tag`s, as a string or a list.
+fn parse_items(value: &Value, tags: &HashMap<String, Vec<String>>) -> Result<Vec<String>, String> {
+    let ids: Vec<&str> = match value {
+        Value::String(s) => vec![s],
+        Value::Array(list) => list.iter().filter_map(Value::as_str).collect(),
+        _ => return Err("bad item list".into()),
+    };
+    let mut items = Vec::new();
+    for id in ids {
+        match id.strip_prefix('#') {
+            Some(tag) => {
+                let tag = if tag.contains(':') { tag.to_string() } else { format!("minecraft:{}", tag) };
+                items.extend(tags.get(&tag).ok_or_else(|| format!("unknown item tag #{}", tag))?.iter().cloned());
+            }
+            None => items.push(unprefixed(id)),
+        }
+    }
+    Ok(items)
+}
+
+/// Entity types from an entity predicate: 1.21's list of loot conditions,
+/// or the older bare `{"type": ...}` object.
+fn parse_entity_types(value: Option<&Value>) -> Result<Option<Vec<String>>, String> {
+    let predicate = match value {
+        None => return Ok(None),
+        Some(Value::Array(conditions)) => conditions
+            .iter()
+            .find(|c| c["condition"] == "minecraft:entity_properties")
+            .map(|c| &c["predicate"]),
+        Some(object) => Some(object),
+    };
+    match predicate.map(|p| &p["type"]) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(t)) if t.starts_with('#') => Err(format!("entity tags aren't supported ({})", t)),
+        Some(Value::String(t)) => Ok(Some(vec![unprefixed(t)])),
+        Some(Value::Array(types)) => Ok(Some(types.iter().filter_map(Value::as_str).map(unprefixed).collect())),
+        Some(_) => Err("bad entity type".into()),
+    }
+}
+
+fn parse_trigger(criterion: &Value, tags: &HashMap<String, Vec<String>>) -> Result<Trigger, String> {
+    let conditions = &criterion["conditions"];
+    Ok(match criterion["trigger"].as_str().map(unprefixed).as_deref() {
+        Some("inventory_changed") => {
+            let mut predicates = Vec::new();
+            for predicate in conditions["items"].as_array().into_iter().flatten() {
+                let items = match predicate.get("items") {
+                    Some(items) => parse_items(items, tags)?,
+                    None => Vec::new(),
+                };
+                let min_count = match &predicate["count"] {
+                    Value::Number(n) => n.as_i64().unwrap_or(1) as i32,
+                    other => other["min"].as_i64().unwrap_or(1) as i32,
+                };
+                predicates.push(ItemPredicate { items, min_count });
+            }
+            Trigger::InventoryChanged(predicates)
+        }
+        Some("player_killed_entity") => Trigger::KilledEntity(parse_entity_types(conditions.get("entity"))?),
+        Some("entity_killed_player") => Trigger::KilledByEntity(parse_entity_types(conditions.get("entity"))?),
+        Some("recipe_crafted") => {
+            Trigger::RecipeCrafted(unprefixed(conditions["recipe_id"].as_str().ok_or("recipe_crafted without recipe_id")?))
+        }
+        Some("tick") => Trigger::Tick,
+        Some(_) => Trigger::Manual,
+        None => return Err("criterion without a trigger".into()),
+    })
+}
+
+fn parse_advancement(id: &str, json: &Value, tags: &HashMap<String, Vec<String>>) -> Result<Advancement, String> {
+    let mut criteria = Vec::new();
+    for (name, criterion) in json["criteria"].as_object().ok_or("no criteria")? {
+        criteria.push((name.clone(), parse_trigger(criterion, tags).map_err(|e| format!("{}: {}", name, e))?));
+    }
+    // Without requirements, every criterion is needed
+    let requirements = match json["requirements"].as_array() {
+        Some(groups) => groups
+            .iter()
+            .map(|g| g.as_array().map(|g| g.iter().filter_map(|c| c.as_str().map(String::from)).collect()))
+            .collect::<Option<Vec<Vec<String>>>>()
+            .ok_or("bad requirements")?,
+        None => criteria.iter().map(|(name, _)| vec![name.clone()]).collect(),
+    };
+
+    let display = match &json["display"] {
+        Value::Null => None,
+        d => {
+            let icon = &d["icon"];
+            let icon_name = icon["id"].as_str().or_else(|| icon["item"].as_str()).unwrap_or("minecraft:stone");
+            let icon = pickaxe_data::item_name_to_id(&unprefixed(icon_name))
+                .map(|item_id| ItemStack::new(item_id, icon["count"].as_i64().unwrap_or(1).clamp(1, 99) as i8));
+            Some(Display {
+                title: parse_text(&d["title"]),
+                description: parse_text(&d["description"]),
+                icon,
+                frame: match d["frame"].as_str() {
+                    Some("challenge") => Frame::Challenge,
+                    Some("goal") => Frame::Goal,
+                    _ => Frame::Task,
+                },
+                background: d["background"].as_str().map(String::from),
+                show_toast: d["show_toast"].as_bool().unwrap_or(true),
+                announce_to_chat: d["announce_to_chat"].as_bool().unwrap_or(true),
+                hidden: d["hidden"].as_bool().unwrap_or(false),
+            })
+        }
+    };
+
+    Ok(Advancement {
+        id: id.to_string(),
+        parent: json["parent"].as_str().map(String::from),
+        display,
+        criteria,
+        requirements,
+        experience: json["rewards"]["experience"].as_i64().unwrap_or(0) as i32,
+        position: (0.0, 0.0),
+    })
+}
+
+/// A player's obtained criteria: advancement → criterion → when (Unix
+/// milliseconds). Entries for advancements the server doesn't know, like
+/// vanilla's recipe unlocks, are kept as they are.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AdvancementProgress {
+    obtained: BTreeMap<String, BTreeMap<String, i64>>,
+}
+
+impl AdvancementProgress {
+    pub fn has(&self, advancement: &str, criterion: &str) -> bool {
+        self.obtained.get(advancement).is_some_and(|c| c.contains_key(criterion))
+    }
+
+    pub fn is_done(&self, advancement: &Advancement) -> bool {
+        let obtained = self.obtained.get(&advancement.id);
+        advancement
+            .requirements
+            .iter()
+            .all(|group| group.iter().any(|c| obtained.is_some_and(|o| o.contains_key(c))))
+    }
+
+    /// Record a criterion. Returns false if it was already obtained.
+    pub fn grant(&mut self, advancement: &str, criterion: &str, now_ms: i64) -> bool {
+        let criteria = self.obtained.entry(advancement.to_string()).or_default();
+        if criteria.contains_key(criterion) {
+            return false;
+        }
+        criteria.insert(criterion.to_string(), now_ms);
+        true
+    }
+
+    /// Forget a criterion, or every criterion of the advancement when None.
+    /// Returns whether anything was obtained.
+    pub fn revoke(&mut self, advancement: &str, criterion: Option<&str>) -> bool {
+        let Some(criteria) = self.obtained.get_mut(advancement) else {
+            return false;
+        };
+        let removed = match criterion {
+            Some(c) => criteria.remove(c).is_some(),
+            None => !std::mem::take(criteria).is_empty(),
+        };
+        if criteria.is_empty() {
+            self.obtained.remove(advancement);
+        }
+        removed
+    }
+
+    /// An advancement's criteria with when each was obtained, for the packet.
+    pub fn packet_progress(&self, advancement: &Advancement) -> (String, Vec<(String, Option<i64>)>) {
+        let obtained = self.obtained.get(&advancement.id);
+        let criteria = advancement
+            .criteria
+            .iter()
+            .map(|(name, _)| (name.clone(), obtained.and_then(|o| o.get(name)).copied()))
+            .collect();
+        (advancement.id.clone(), criteria)
+    }
+
+    /// Vanilla's `advancements/<uuid>.json` layout. `done` is only known for
+    /// advancements in `tree`; others keep what they had.
+    pub fn to_json(&self, tree: &AdvancementTree) -> Vec<u8> {
+        let mut root = Map::new();
+        for (id, criteria) in &self.obtained {
+            let criteria: Map<String, Value> = criteria
+                .iter()
+                .map(|(name, ms)| {
+                    let time = UNIX_EPOCH + Duration::from_millis((*ms).max(0) as u64);
+                    (name.clone(), Value::from(crate::access::format_timestamp(time)))
+                })
+                .collect();
+            let done = tree.get(id).is_some_and(|a| self.is_done(a));
+            let mut entry = Map::new();
+            entry.insert("criteria".into(), Value::Object(criteria));
+            entry.insert("done".into(), Value::from(done));
+            root.insert(id.clone(), Value::Object(entry));
+        }
+        root.insert("DataVersion".into(), Value::from(3955));
+        serde_json::to_vec_pretty(&Value::Object(root)).unwrap_or_default()
+    }
+
+    pub fn from_json(data: &[u8]) -> Result<Self, String> {
+        let root: Map<String, Value> = serde_json::from_slice(data).map_err(|e| e.to_string())?;
+        let mut obtained = BTreeMap::new();
+        for (id, entry) in root {
+            let Some(criteria) = entry.get("criteria").and_then(Value::as_object) else {
+                continue;
+            };
+            let criteria: BTreeMap<String, i64> = criteria
+                .iter()
+                .filter_map(|(name, time)| {
+                    let secs = crate::access::parse_timestamp(time.as_str()?)?;
+                    Some((name.clone(), secs * 1000))
+                })
+                .collect();
+            if !criteria.is_empty() {
+                obtained.insert(id, criteria);
+            }
+        }
+        Ok(Self { obtained })
+    }
+
+    /// Load a player's progress from the world, empty if they have none.
+    pub fn load(world_dir: &Path, uuid: &Uuid) -> Self {
+        let path = world_dir.join(ADVANCEMENTS_DIR).join(format!("{}.json", uuid));
+        let Ok(data) = std::fs::read(&path) else {
+            return Self::default();
+        };
+        Self::from_json(&data).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring unreadable {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+}
+
+/// Milliseconds since the epoch, for newly obtained criteria.
+pub fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_and_progress() {
+        let tags = HashMap::from([("minecraft:stone_tool_materials".to_string(), vec!["cobblestone".to_string()])]);
+        let root = json!({
+            "criteria": {"crafting_table": {"trigger": "minecraft:inventory_changed",
+                "conditions": {"items": [{"items": "minecraft:crafting_table"}]}}},
+            "display": {"icon": {"id": "minecraft:grass_block"}, "title": {"translate": "advancements.story.root.title"},
+                "description": "Root", "background": "minecraft:textures/gui/advancements/backgrounds/stone.png"}
+        });
+        let stone = json!({
+            "parent": "minecraft:story/root",
+            "criteria": {
+                "get_stone": {"trigger": "minecraft:inventory_changed",
+                    "conditions": {"items": [{"items": "#minecraft:stone_tool_materials"}]}},
+                "zombie": {"trigger": "minecraft:player_killed_entity", "conditions": {"entity": [
+                    {"condition": "minecraft:entity_properties", "entity": "this", "predicate": {"type": "minecraft:zombie"}}]}}
+            },
+            "requirements": [["get_stone", "zombie"]]
+        });
+        let tree = AdvancementTree::from_advancements(vec![
+            parse_advancement("minecraft:story/mine_stone", &stone, &tags).unwrap(),
+            parse_advancement("minecraft:story/root", &root, &tags).unwrap(),
+        ]);
+        assert_eq!(tree.iter().map(|a| a.id.as_str()).collect::<Vec<_>>(), ["minecraft:story/root", "minecraft:story/mine_stone"]);
+        let mine_stone = tree.get("minecraft:story/mine_stone").unwrap();
+        assert_eq!(mine_stone.position, (1.0, 0.0));
+        assert_eq!(
+            mine_stone.criteria[0].1,
+            Trigger::InventoryChanged(vec![ItemPredicate { items: vec!["cobblestone".into()], min_count: 1 }])
+        );
+        assert_eq!(mine_stone.criteria[1].1, Trigger::KilledEntity(Some(vec!["zombie".into()])));
+
+        let mut progress = AdvancementProgress::default();
+        assert!(!progress.is_done(mine_stone));
+        assert!(progress.grant("minecraft:story/mine_stone", "zombie", 1_700_000_000_000));
+        assert!(!progress.grant("minecraft:story/mine_stone", "zombie", 1_700_000_000_000));
+        assert!(progress.is_done(mine_stone));
+
+        let loaded = AdvancementProgress::from_json(&progress.to_json(&tree)).unwrap();
+        assert_eq!(loaded, progress);
+        assert!(progress.revoke("minecraft:story/mine_stone", None));
+        assert!(!progress.has("minecraft:story/mine_stone", "zombie"));
+    }
+}
@@ -0,0 +1,77 @@
+use std::time::{Duration, Instant};
+
+/// What changed about an idle player.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AfkChange {
+    /// Idle long enough to be marked AFK.
+    Away,
+    /// Idle long enough to be disconnected.
+    Kick,
+}
+
+/// When a player last did something themselves (moved, looked around,
+/// chatted, clicked), as opposed to packets their client sends unprompted.
+pub struct Activity {
+    last_input: Instant,
+    afk: bool,
+    kicked: bool,
+}
+
+impl Activity {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            last_input: now,
+            afk: false,
+            kicked: false,
+        }
+    }
+
+    pub fn is_afk(&self) -> bool {
+        self.afk
+    }
+
+    /// The player did something at `now`. True if that brings them back
+    /// from being AFK.
+    pub fn input(&mut self, now: Instant) -> bool {
+        self.last_input = now;
+        std::mem::replace(&mut self.afk, false)
+    }
+
+    /// Check the player at `now` against the AFK and kick delays (None
+    /// disables either). Each change is reported once.
+    pub fn check(&mut self, now: Instant, afk_after: Option<Duration>, kick_after: Option<Duration>) -> Option<AfkChange> {
+        let idle = now.duration_since(self.last_input);
+        if !self.kicked && kick_after.is_some_and(|limit| idle >= limit) {
+            self.kicked = true;
+            return Some(AfkChange::Kick);
+        }
+        if !self.afk && afk_after.is_some_and(|limit| idle >= limit) {
+            self.afk = true;
+            return Some(AfkChange::Away);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_afk_then_kick() {
+        let start = Instant::now();
+        let (afk, kick) = (Some(Duration::from_secs(60)), Some(Duration::from_secs(300)));
+        let mut activity = Activity::new(start);
+        assert_eq!(activity.check(start + Duration::from_secs(59), afk, kick), None);
+        assert_eq!(activity.check(start + Duration::from_secs(60), afk, kick), Some(AfkChange::Away));
+        assert_eq!(activity.check(start + Duration::from_secs(61), afk, kick), None);
+
+        // Coming back clears AFK and restarts both delays
+        assert!(activity.input(start + Duration::from_secs(100)));
+        assert!(!activity.input(start + Duration::from_secs(101)));
+        assert_eq!(activity.check(start + Duration::from_secs(160), afk, kick), None);
+        assert_eq!(activity.check(start + Duration::from_secs(401), afk, kick), Some(AfkChange::Kick));
+        assert_eq!(activity.check(start + Duration::from_secs(402), afk, kick), Some(AfkChange::Away));
+        assert_eq!(activity.check(start + Duration::from_secs(403), afk, kick), None);
+    }
+}
@@ -0,0 +1,402 @@
+use crate::config::AuditConfig;
+use pickaxe_types::BlockPos;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, oneshot};
+
+/// Current log file; rotated copies are `audit.log.1` (newest) and up.
+const LOG_NAME: &str = "audit.log";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Command,
+    Break,
+    Place,
+    Container,
+}
+
+impl AuditAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::Command => "command",
+            AuditAction::Break => "break",
+            AuditAction::Place => "place",
+            AuditAction::Container => "container",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "command" => Some(AuditAction::Command),
+            "break" => Some(AuditAction::Break),
+            "place" => Some(AuditAction::Place),
+            "container" => Some(AuditAction::Container),
+            _ => None,
+        }
+    }
+}
+
+/// One audit record. Stored one per line, tab-separated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    /// Unix time in seconds.
+    pub time: u64,
+    pub player: String,
+    pub action: AuditAction,
+    /// Block position (the player's, for commands); none for the console.
+    pub position: Option<BlockPos>,
+    /// The command line, or the block or container type.
+    pub detail: String,
+}
+
+impl AuditEntry {
+    pub fn to_line(&self) -> String {
+        let position = match self.position {
+            Some(p) => format!("{} {} {}", p.x, p.y, p.z),
+            None => "-".to_string(),
+        };
+        let detail: String = self.detail.chars().map(|c| if c.is_control() { ' ' } else { c }).collect();
+        format!("{}\t{}\t{}\t{}\t{}", self.time, self.player, self.action.as_str(), position, detail)
+    }
+
+    pub fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(5, '\t');
+        let time = fields.next()?.parse().ok()?;
+        let player = fields.next()?.to_string();
+        let action = AuditAction::parse(fields.next()?)?;
+        let position = match fields.next()? {
+            "-" => None,
+            raw => {
+                let mut coords = raw.split(' ').map(|v| v.parse::<i32>().ok());
+                Some(BlockPos::new(coords.next()??, coords.next()??, coords.next()??))
+            }
+        };
+        let detail = fields.next().unwrap_or("").to_string();
+        Some(Self { time, player, action, position, detail })
+    }
+}
+
+/// Filters for `/audit lookup`. Every filter is optional.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AuditQuery {
+    pub player: Option<String>,
+    pub action: Option<AuditAction>,
+    /// Only entries within `radius` blocks of the center, on every axis.
+    pub area: Option<(BlockPos, i32)>,
+    /// Only entries at or after this Unix time.
+    pub since: Option<u64>,
+}
+
+impl AuditQuery {
+    /// Parse `user:<name>`, `action:<action>`, `radius:<blocks>` and
+    /// `time:<age>` filters. `center` is where the lookup is run from.
+    pub fn parse(args: &[&str], center: BlockPos, now: u64) -> Result<Self, String> {
+        let mut query = Self::default();
+        for arg in args {
+            let Some((key, value)) = arg.split_once(':') else {
+                return Err(format!("Expected <filter>:<value>, found '{}'", arg));
+            };
+            match key {
+                "user" | "u" => query.player = Some(value.to_string()),
+                "action" | "a" => {
+                    let action = AuditAction::parse(value).ok_or_else(|| {
+                        format!("Unknown action '{}', expected command, break, place or container", value)
+                    })?;
+                    query.action = Some(action);
+                }
+                "radius" | "r" => {
+                    let radius = value.parse::<i32>().ok().filter(|r| *r >= 0).ok_or_else(|| {
+                        format!("Invalid radius '{}'", value)
+                    })?;
+                    query.area = Some((center, radius));
+                }
+                "time" | "t" => {
+                    let age = parse_duration(value).ok_or_else(|| format!("Invalid time '{}', e.g. 30m, 2h or 7d", value))?;
+                    query.since = Some(now.saturating_sub(age));
+                }
+                _ => return Err(format!("Unknown filter '{}', expected user, action, radius or time", key)),
+            }
+        }
+        Ok(query)
+    }
+
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        if self.player.as_ref().is_some_and(|p| !p.eq_ignore_ascii_case(&entry.player)) {
+            return false;
+        }
+        if self.action.is_some_and(|a| a != entry.action) {
+            return false;
+        }
+        if self.since.is_some_and(|since| entry.time < since) {
+            return false;
+        }
+        if let Some((center, radius)) = self.area {
+            let Some(pos) = entry.position else {
+                return false;
+            };
+            if (pos.x - center.x).abs() > radius || (pos.y - center.y).abs() > radius || (pos.z - center.z).abs() > radius {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Parse an age such as `30s`, `15m`, `2h`, `7d` or `1w` into seconds.
+pub fn parse_duration(s: &str) -> Option<u64> {
+    let split = s.len().checked_sub(1)?;
+    let (amount, unit) = s.split_at(split);
+    let scale = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        "w" => 604800,
+        _ => return None,
+    };
+    amount.parse::<u64>().ok()?.checked_mul(scale)
+}
+
+/// Format an age in seconds as the largest whole unit, e.g. `5m` or `3d`.
+pub fn format_age(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Matching entries (newest first, at most the requested number) and the
+/// total number of matches.
+pub type LookupResult = (Vec<AuditEntry>, usize);
+
+pub enum AuditOp {
+    Record(AuditEntry),
+    Lookup(AuditQuery, usize, oneshot::Sender<LookupResult>),
+    Shutdown(oneshot::Sender<()>),
+}
+
+/// The tick loop's handle on the audit log. Records nothing when `[audit]`
+/// is disabled.
+#[derive(Default)]
+pub struct AuditLog {
+    tx: Option<mpsc::UnboundedSender<AuditOp>>,
+    commands: bool,
+    blocks: bool,
+    containers: bool,
+}
+
+impl AuditLog {
+    /// Start the writer task for `<world>/audit/`, if enabled.
+    pub fn start(config: &AuditConfig, world_dir: &Path) -> Self {
+        if !config.enabled {
+            return Self::default();
+        }
+        let (tx, rx) = mpsc::unbounded_channel();
+        let dir = world_dir.join("audit");
+        let max_bytes = config.max_file_kb.max(1) * 1024;
+        let max_files = config.max_files;
+        tokio::task::spawn_blocking(move || run_audit_task(rx, dir, max_bytes, max_files));
+        Self {
+            tx: Some(tx),
+            commands: config.commands,
+            blocks: config.blocks,
+            containers: config.containers,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.tx.is_some()
+    }
+
+    pub fn record(&self, player: &str, action: AuditAction, position: Option<BlockPos>, detail: &str) {
+        let wanted = match action {
+            AuditAction::Command => self.commands,
+            AuditAction::Break | AuditAction::Place => self.blocks,
+            AuditAction::Container => self.containers,
+        };
+        if let (true, Some(tx)) = (wanted, &self.tx) {
+            let _ = tx.send(AuditOp::Record(AuditEntry {
+                time: now(),
+                player: player.to_string(),
+                action,
+                position,
+                detail: detail.to_string(),
+            }));
+        }
+    }
+
+    /// Search the log on the writer task; the reply arrives on the receiver.
+    pub fn lookup(&self, query: AuditQuery, limit: usize) -> Option<oneshot::Receiver<LookupResult>> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.tx.as_ref()?.send(AuditOp::Lookup(query, limit, reply_tx)).ok()?;
+        Some(reply_rx)
+    }
+
+    /// Wait for queued records to be written.
+    pub async fn shutdown(&self) {
+        if let Some(tx) = &self.tx {
+            let (done_tx, done_rx) = oneshot::channel();
+            if tx.send(AuditOp::Shutdown(done_tx)).is_ok() {
+                let _ = done_rx.await;
+            }
+        }
+    }
+}
+
+/// Runs on a blocking task: appends records and answers lookups in order.
+fn run_audit_task(mut rx: mpsc::UnboundedReceiver<AuditOp>, dir: PathBuf, max_bytes: u64, max_files: u32) {
+    let _ = std::fs::create_dir_all(&dir);
+    let mut file = open_log(&dir);
+    while let Some(op) = rx.blocking_recv() {
+        match op {
+            AuditOp::Record(entry) => {
+                let Some(f) = file.as_mut() else {
+                    continue;
+                };
+                if let Err(e) = f.write_all(format!("{}\n", entry.to_line()).as_bytes()) {
+                    tracing::error!("Failed to write audit log: {}", e);
+                }
+                if f.metadata().is_ok_and(|m| m.len() >= max_bytes) {
+                    drop(file.take());
+                    rotate(&dir, max_files);
+                    file = open_log(&dir);
+                }
+            }
+            AuditOp::Lookup(query, limit, reply) => {
+                let _ = reply.send(lookup(&dir, &query, limit, max_files));
+            }
+            AuditOp::Shutdown(done) => {
+                let _ = done.send(());
+                return;
+            }
+        }
+    }
+}
+
+fn open_log(dir: &Path) -> Option<File> {
+    match OpenOptions::new().create(true).append(true).open(dir.join(LOG_NAME)) {
+        Ok(file) => Some(file),
+        Err(e) => {
+            tracing::error!("Failed to open audit log: {}", e);
+            None
+        }
+    }
+}
+
+fn rotated_path(dir: &Path, index: u32) -> PathBuf {
+    if index == 0 {
+        dir.join(LOG_NAME)
+    } else {
+        dir.join(format!("{}.{}", LOG_NAME, index))
+    }
+}
+
+/// Shift `audit.log` to `audit.log.1` and so on, dropping the oldest
+/// beyond `max_files` rotated copies.
+fn rotate(dir: &Path, max_files: u32) {
+    let _ = std::fs::remove_file(rotated_path(dir, max_files));
+    for index in (0..max_files).rev() {
+        let _ = std::fs::rename(rotated_path(dir, index), rotated_path(dir, index + 1));
+    }
+}
+
+fn lookup(dir: &Path, query: &AuditQuery, limit: usize, max_files: u32) -> LookupResult {
+    let mut found = Vec::new();
+    let mut total = 0;
+    for index in 0..=max_files {
+        let Ok(file) = File::open(rotated_path(dir, index)) else {
+            continue;
+        };
+        let mut matches: Vec<AuditEntry> = BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| AuditEntry::from_line(&line))
+            .filter(|entry| query.matches(entry))
+            .collect();
+        total += matches.len();
+        matches.reverse();
+        found.extend(matches.into_iter().take(limit.saturating_sub(found.len())));
+    }
+    (found, total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(time: u64, player: &str, action: AuditAction, pos: (i32, i32, i32), detail: &str) -> AuditEntry {
+        AuditEntry {
+            time,
+            player: player.to_string(),
+            action,
+            position: Some(BlockPos::new(pos.0, pos.1, pos.2)),
+            detail: detail.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_entry_line_roundtrip() {
+        let e = entry(1700000000, "Alice", AuditAction::Command, (1, -60, -2), "/say hi\tthere");
+        let parsed = AuditEntry::from_line(&e.to_line()).unwrap();
+        assert_eq!(parsed.detail, "/say hi there");
+        assert_eq!(parsed.position, e.position);
+
+        let console = AuditEntry { position: None, player: "Server".into(), ..e };
+        assert_eq!(AuditEntry::from_line(&console.to_line()).unwrap().position, None);
+        assert!(AuditEntry::from_line("garbage").is_none());
+    }
+
+    #[test]
+    fn test_query() {
+        let query = AuditQuery::parse(&["user:alice", "radius:5", "time:1h"], BlockPos::new(0, 64, 0), 10000).unwrap();
+        assert!(query.matches(&entry(9000, "Alice", AuditAction::Break, (3, 60, -5), "stone")));
+        assert!(!query.matches(&entry(9000, "Alice", AuditAction::Break, (6, 64, 0), "stone")));
+        assert!(!query.matches(&entry(6000, "Alice", AuditAction::Break, (0, 64, 0), "stone")));
+        assert!(!query.matches(&entry(9000, "Bob", AuditAction::Break, (0, 64, 0), "stone")));
+
+        assert!(AuditQuery::parse(&["action:explode"], BlockPos::new(0, 0, 0), 0).is_err());
+        assert!(AuditQuery::parse(&["alice"], BlockPos::new(0, 0, 0), 0).is_err());
+        assert_eq!(parse_duration("2d"), Some(172800));
+        assert_eq!(parse_duration("5"), None);
+        assert_eq!(format_age(7200), "2h");
+    }
+
+    #[test]
+    fn test_rotate_and_lookup() {
+        let dir = std::env::temp_dir().join(format!("pickaxe-audit-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |entries: &[AuditEntry]| {
+            let mut file = open_log(&dir).unwrap();
+            for e in entries {
+                file.write_all(format!("{}\n", e.to_line()).as_bytes()).unwrap();
+            }
+        };
+        write(&[entry(1, "Alice", AuditAction::Place, (0, 0, 0), "dirt")]);
+        rotate(&dir, 1);
+        write(&[
+            entry(2, "Alice", AuditAction::Break, (0, 0, 0), "dirt"),
+            entry(3, "Bob", AuditAction::Place, (0, 0, 0), "stone"),
+        ]);
+
+        let query = AuditQuery { player: Some("alice".into()), ..Default::default() };
+        let (found, total) = lookup(&dir, &query, 10, 1);
+        assert_eq!(total, 2);
+        assert_eq!(found.iter().map(|e| e.time).collect::<Vec<_>>(), vec![2, 1]);
+
+        // A second rotation drops the oldest file
+        rotate(&dir, 1);
+        let (found, total) = lookup(&dir, &AuditQuery::default(), 1, 1);
+        assert_eq!((found.len(), total), (1, 2));
+        assert_eq!(found[0].time, 3);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,103 @@
+use anyhow::Result;
+use num_bigint::BigInt;
+use pickaxe_types::{GameProfile, ProfileProperty};
+use rsa::pkcs8::EncodePublicKey;
+use rsa::{Pkcs1v15Encrypt, RsaPrivateKey};
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+use std::time::Duration;
+use uuid::Uuid;
+
+const HAS_JOINED_URL: &str = "https://sessionserver.mojang.com/session/minecraft/hasJoined";
+
+/// Key size the vanilla server uses for the login handshake.
+const KEY_BITS: usize = 1024;
+
+/// Online-mode login: the server's RSA keypair and a client for the Mojang
+/// session server. Created once at startup and shared by every connection.
+pub struct SessionAuth {
+    key: RsaPrivateKey,
+    public_der: Vec<u8>,
+    client: reqwest::Client,
+}
+
+/// Profile returned by `hasJoined`. The id has no dashes.
+#[derive(Deserialize)]
+struct SessionProfile {
+    id: String,
+    name: String,
+    #[serde(default)]
+    properties: Vec<ProfileProperty>,
+}
+
+impl SessionAuth {
+    pub fn new() -> Result<Self> {
+        let key = RsaPrivateKey::new(&mut rand::thread_rng(), KEY_BITS)?;
+        let public_der = key.to_public_key().to_public_key_der()?.into_vec();
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        Ok(Self {
+            key,
+            public_der,
+            client,
+        })
+    }
+
+    /// The public key as DER-encoded SubjectPublicKeyInfo, as sent in the
+    /// Encryption Request.
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_der
+    }
+
+    /// Decrypt a field of the client's Encryption Response.
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(self.key.decrypt(Pkcs1v15Encrypt, data)?)
+    }
+
+    /// Ask the session server whether `name` joined with this shared secret.
+    /// Returns the verified profile, including its skin textures, or None if
+    /// the client didn't authenticate.
+    pub async fn has_joined(&self, name: &str, shared_secret: &[u8]) -> Result<Option<GameProfile>> {
+        let hash = server_hash("", shared_secret, &self.public_der);
+        let response = self
+            .client
+            .get(HAS_JOINED_URL)
+            .query(&[("username", name), ("serverId", hash.as_str())])
+            .send()
+            .await?;
+        // 204 No Content means the client never joined
+        if response.status() != reqwest::StatusCode::OK {
+            return Ok(None);
+        }
+        let profile: SessionProfile = serde_json::from_str(&response.text().await?)?;
+        Ok(Some(GameProfile {
+            uuid: Uuid::parse_str(&profile.id)?,
+            name: profile.name,
+            properties: profile.properties,
+        }))
+    }
+}
+
+/// Minecraft's server hash: the SHA-1 of the server id, shared secret and
+/// public key, printed as a signed two's-complement hex number.
+pub fn server_hash(server_id: &str, shared_secret: &[u8], public_key: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(server_id.as_bytes());
+    hasher.update(shared_secret);
+    hasher.update(public_key);
+    BigInt::from_signed_bytes_be(&hasher.finalize()).to_str_radix(16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_hash() {
+        // Reference values from the protocol documentation
+        assert_eq!(server_hash("Notch", &[], &[]), "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48");
+        assert_eq!(server_hash("jeb_", &[], &[]), "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1");
+        assert_eq!(server_hash("simon", &[], &[]), "88e16a1019277b15d58faf0541e11910eb756f6");
+    }
+}
@@ -0,0 +1,251 @@
+use crate::config::{BackupConfig, BackupFormat};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// What a backup holds, relative to the world directory.
+const WORLD_PARTS: [&str; 11] = [
+    "level.dat",
+    "region",
+    "poi",
+    "DIM-1",
+    "DIM1",
+    "dimensions",
+    "playerdata",
+    "stats",
+    "advancements",
+    "data",
+    "datapacks",
+];
+
+/// Copy the world's files into a staging directory under the backup dir and
+/// return it. Runs on the saver task between two ops, so every save queued
+/// before the backup is on disk and nothing is written while it copies.
+///
+/// Region (`.mca`) files are rewritten in place and must be copied; everything else
+/// is only ever replaced by a rename, so a hard link is as good as a copy.
+pub fn snapshot(world_dir: &Path, config: &BackupConfig) -> io::Result<PathBuf> {
+    let dir = Path::new(&config.dir);
+    let staging = dir.join(format!(".{}", backup_name(SystemTime::now())));
+    fs::create_dir_all(&staging)?;
+    for part in WORLD_PARTS {
+        let src = world_dir.join(part);
+        if src.exists() {
+            copy_tree(&src, &staging.join(part))?;
+        }
+    }
+    Ok(staging)
+}
+
+/// Turn a snapshot into the final backup and prune old ones. Slow, so it
+/// runs on its own thread after the saver has moved on. Returns the backup's
+/// path.
+pub fn finish(staging: &Path, world_name: &str, config: &BackupConfig) -> io::Result<PathBuf> {
+    let dir = Path::new(&config.dir);
+    let name = staging
+        .file_name()
+        .and_then(|n| n.to_str())
+        .and_then(|n| n.strip_prefix('.'))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Bad staging directory"))?;
+    let path = match config.format {
+        BackupFormat::Zip => dir.join(format!("{}.zip", name)),
+        BackupFormat::TarZst => dir.join(format!("{}.tar.zst", name)),
+        BackupFormat::Directory => dir.join(name),
+    };
+
+    if config.format == BackupFormat::Directory {
+        fs::rename(staging, &path)?;
+    } else {
+        // Archives appear under their final name only once complete
+        let tmp = path.with_extension("tmp");
+        let written = match config.format {
+            BackupFormat::Zip => write_zip(staging, world_name, &tmp),
+            _ => write_tar_zst(staging, world_name, &tmp),
+        };
+        let _ = fs::remove_dir_all(staging);
+        if let Err(e) = written.and_then(|()| fs::rename(&tmp, &path)) {
+            let _ = fs::remove_file(&tmp);
+            return Err(e);
+        }
+    }
+
+    prune(dir, config);
+    Ok(path)
+}
+
+/// `yyyy-MM-dd_HH-mm-ss` in UTC, which sorts in time order.
+fn backup_name(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    let (year, month, day) = crate::access::civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+fn is_backup_name(name: &str) -> bool {
+    let b = name.as_bytes();
+    b.len() >= 19
+        && b[..19].iter().enumerate().all(|(i, c)| match i {
+            4 | 7 | 13 | 16 => *c == b'-',
+            10 => *c == b'_',
+            _ => c.is_ascii_digit(),
+        })
+        && !name.ends_with(".tmp")
+}
+
+fn copy_tree(src: &Path, dst: &Path) -> io::Result<()> {
+    if src.is_dir() {
+        fs::create_dir_all(dst)?;
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            // Half-written files from an interrupted save
+            if entry.file_name().to_string_lossy().ends_with(".tmp") {
+                continue;
+            }
+            copy_tree(&entry.path(), &dst.join(entry.file_name()))?;
+        }
+        return Ok(());
+    }
+    let link = src.extension().is_none_or(|e| e != "mca");
+    if link && fs::hard_link(src, dst).is_ok() {
+        return Ok(());
+    }
+    fs::copy(src, dst).map(|_| ())
+}
+
+/// Every file under `dir` with its archive path: `prefix/relative/path`.
+fn archive_entries(dir: &Path, prefix: &str, out: &mut Vec<(PathBuf, String)>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = format!("{}/{}", prefix, entry.file_name().to_string_lossy());
+        if entry.file_type()?.is_dir() {
+            archive_entries(&entry.path(), &name, out)?;
+        } else {
+            out.push((entry.path(), name));
+        }
+    }
+    Ok(())
+}
+
+fn write_zip(staging: &Path, world_name: &str, out: &Path) -> io::Result<()> {
+    let mut entries = Vec::new();
+    archive_entries(staging, world_name, &mut entries)?;
+    let mut zip = zip::ZipWriter::new(BufWriter::new(File::create(out)?));
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .large_file(true);
+    for (path, name) in entries {
+        zip.start_file(name, options).map_err(io::Error::other)?;
+        io::copy(&mut File::open(path)?, &mut zip)?;
+    }
+    zip.finish().map_err(io::Error::other)?.flush()
+}
+
+fn write_tar_zst(staging: &Path, world_name: &str, out: &Path) -> io::Result<()> {
+    let encoder = zstd::Encoder::new(BufWriter::new(File::create(out)?), 3)?;
+    let mut tar = tar::Builder::new(encoder);
+    tar.append_dir_all(world_name, staging)?;
+    tar.into_inner()?.finish()?.flush()
+}
+
+fn size_on_disk(path: &Path) -> u64 {
+    match fs::read_dir(path) {
+        Ok(entries) => entries.flatten().map(|e| size_on_disk(&e.path())).sum(),
+        Err(_) => fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+    }
+}
+
+/// Delete the oldest backups past `max_backups` or `max_size_mb`. The newest
+/// backup is always kept.
+fn prune(dir: &Path, config: &BackupConfig) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut backups: Vec<(String, PathBuf, u64)> = entries
+        .flatten()
+        .filter_map(|e| {
+            let name = e.file_name().into_string().ok()?;
+            is_backup_name(&name).then(|| (name, e.path(), size_on_disk(&e.path())))
+        })
+        .collect();
+    backups.sort();
+
+    let max_bytes = config.max_size_mb.saturating_mul(1024 * 1024);
+    let mut total: u64 = backups.iter().map(|(_, _, size)| size).sum();
+    let mut excess = match config.max_backups {
+        0 => 0,
+        max => backups.len().saturating_sub(max),
+    };
+    for (name, path, size) in &backups[..backups.len().saturating_sub(1)] {
+        if excess == 0 && (max_bytes == 0 || total <= max_bytes) {
+            break;
+        }
+        let removed = if path.is_dir() { fs::remove_dir_all(path) } else { fs::remove_file(path) };
+        match removed {
+            Ok(()) => tracing::info!("Deleted old backup {}", name),
+            Err(e) => tracing::warn!("Failed to delete old backup {}: {}", name, e),
+        }
+        excess = excess.saturating_sub(1);
+        total = total.saturating_sub(*size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_and_prune() {
+        let root = std::env::temp_dir().join(format!("pickaxe-backup-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let world = root.join("world");
+        fs::create_dir_all(world.join("region")).unwrap();
+        fs::create_dir_all(world.join("dimensions/pickaxe/lobby/region")).unwrap();
+        fs::write(world.join("level.dat"), b"level").unwrap();
+        fs::write(world.join("region/r.0.0.mca"), b"chunks").unwrap();
+        fs::write(world.join("region/r.0.0.mca.tmp"), b"partial").unwrap();
+        fs::write(world.join("dimensions/pickaxe/lobby/region/r.0.0.mca"), b"lobby").unwrap();
+        fs::write(world.join("session.lock"), b"").unwrap();
+
+        let config = BackupConfig {
+            dir: root.join("backups").to_string_lossy().into_owned(),
+            format: BackupFormat::Directory,
+            max_backups: 2,
+            ..Default::default()
+        };
+        for old in ["2020-01-01_00-00-00", "2021-01-01_00-00-00"] {
+            fs::create_dir_all(Path::new(&config.dir).join(old)).unwrap();
+        }
+        let staging = snapshot(&world, &config).unwrap();
+        // The snapshot doesn't change when the world is written afterwards
+        fs::write(world.join("region/r.0.0.mca"), b"changed").unwrap();
+        fs::write(world.join("dimensions/pickaxe/lobby/region/r.0.0.mca"), b"changed").unwrap();
+        let backup = finish(&staging, "world", &config).unwrap();
+
+        assert_eq!(fs::read(backup.join("region/r.0.0.mca")).unwrap(), b"chunks");
+        assert_eq!(fs::read(backup.join("dimensions/pickaxe/lobby/region/r.0.0.mca")).unwrap(), b"lobby");
+        assert_eq!(fs::read(backup.join("level.dat")).unwrap(), b"level");
+        assert!(!backup.join("region/r.0.0.mca.tmp").exists());
+        assert!(!backup.join("session.lock").exists());
+        let mut left: Vec<String> = fs::read_dir(&config.dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        left.sort();
+        assert_eq!(left.len(), 2);
+        assert_eq!(left[0], "2021-01-01_00-00-00");
+        assert!(is_backup_name(&left[1]));
+        let _ = fs::remove_dir_all(&root);
+    }
+}
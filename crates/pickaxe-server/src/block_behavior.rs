@@ -0,0 +1,589 @@
+use crate::bridge::{BlockHandler, BlockOverrides};
+use crate::tick::{
+    block_receives_power, broadcast_to_level, fire_block_handler, play_sound_at_block, ring_bell, spawn_crop_drops,
+    spawn_item_entity, update_redstone_neighbors, WorldState, SOUND_BLOCKS,
+};
+use hecs::World;
+use pickaxe_protocol_core::InternalPacket;
+use pickaxe_scripting::ScriptRuntime;
+use pickaxe_types::{BlockPos, ItemStack};
+use rand::Rng;
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::AtomicI32;
+use std::sync::Arc;
+
+/// Upper bound on neighbor updates processed per drain, so a feedback loop
+/// between handlers can't stall the tick (MC caps chained updates similarly).
+const MAX_NEIGHBOR_UPDATES: usize = 65536;
+
+/// Called when a block next to `pos` changed.
+/// Arguments: world, world state, position of the notified block, its current state,
+/// position of the block that changed, entity ID counter, scripting runtime.
+pub type NeighborHandler =
+    fn(&mut World, &mut WorldState, &BlockPos, i32, &BlockPos, &Arc<AtomicI32>, &ScriptRuntime);
+
+/// Called when a block tick scheduled with `WorldState::schedule_block_tick` comes due.
+/// Arguments: world, world state, position, current state, entity ID counter, scripting runtime.
+pub type TickHandler = fn(&mut World, &mut WorldState, &BlockPos, i32, &Arc<AtomicI32>, &ScriptRuntime);
+
+/// Registry of per-block neighbor-changed and scheduled tick handlers, matched by block name.
+pub struct BlockBehaviors {
+    handlers: Vec<(fn(&str) -> bool, NeighborHandler)>,
+    tick_handlers: Vec<(fn(&str) -> bool, TickHandler)>,
+}
+
+impl BlockBehaviors {
+    pub fn new() -> Self {
+        let mut behaviors = Self { handlers: Vec::new(), tick_handlers: Vec::new() };
+        register_default_behaviors(&mut behaviors);
+        behaviors
+    }
+
+    /// Register a handler for every block whose name satisfies `matches`.
+    /// Handlers are tried in registration order; the first match wins.
+    pub fn register(&mut self, matches: fn(&str) -> bool, handler: NeighborHandler) {
+        self.handlers.push((matches, handler));
+    }
+
+    /// Register a scheduled tick handler, matched the same way as neighbor handlers.
+    pub fn register_tick(&mut self, matches: fn(&str) -> bool, handler: TickHandler) {
+        self.tick_handlers.push((matches, handler));
+    }
+
+    fn handler_for(&self, name: &str) -> Option<NeighborHandler> {
+        self.handlers.iter().find(|(m, _)| m(name)).map(|(_, h)| *h)
+    }
+
+    fn tick_handler_for(&self, name: &str) -> Option<TickHandler> {
+        self.tick_handlers.iter().find(|(m, _)| m(name)).map(|(_, h)| *h)
+    }
+}
+
+/// Built-in vanilla behaviors.
+fn register_default_behaviors(behaviors: &mut BlockBehaviors) {
+    behaviors.register(pickaxe_data::needs_support, pop_if_unsupported);
+    behaviors.register(is_crop_block, break_crop_off_farmland);
+    behaviors.register(|name| name == "farmland", dry_farmland_under_solid);
+    behaviors.register(|name| name == "sponge", absorb_water);
+    behaviors.register(pickaxe_data::is_live_coral, schedule_coral_check);
+    behaviors.register_tick(pickaxe_data::is_live_coral, kill_coral_out_of_water);
+    behaviors.register(|name| name == "bell", ring_bell_on_signal);
+    behaviors.register_tick(|name| name == "lectern", end_lectern_pulse);
+    behaviors.register(|name| matches!(name, "campfire" | "soul_campfire"), update_signal_fire);
+}
+
+fn is_crop_block(name: &str) -> bool {
+    matches!(name, "wheat" | "carrots" | "potatoes" | "beetroots")
+}
+
+/// Drain queued neighbor notifications and dispatch them to block handlers.
+/// Handlers may change blocks themselves, which queues further notifications.
+/// A Lua `on_neighbor_update` handler runs first and may cancel the built-in one.
+pub fn process_neighbor_updates(
+    world: &mut World,
+    world_state: &mut WorldState,
+    next_eid: &Arc<AtomicI32>,
+    scripting: &ScriptRuntime,
+    block_overrides: &BlockOverrides,
+) {
+    let mut processed = 0;
+    while let Some((pos, source)) = world_state.level.pending_neighbor_updates.pop_front() {
+        processed += 1;
+        if processed > MAX_NEIGHBOR_UPDATES {
+            tracing::warn!("Neighbor update limit reached, dropping {} queued updates", world_state.level.pending_neighbor_updates.len() + 1);
+            world_state.level.pending_neighbor_updates.clear();
+            break;
+        }
+        // Never load chunks just to deliver a notification
+        let Some(state) = world_state.get_block_if_loaded(&pos) else { continue };
+        let response = fire_block_handler(
+            world, world_state, scripting, block_overrides, BlockHandler::NeighborUpdate, &pos, state,
+            |event| {
+                event.set("source_x", source.x)?;
+                event.set("source_y", source.y)?;
+                event.set("source_z", source.z)
+            },
+        );
+        if response.cancelled {
+            continue;
+        }
+        let Some(name) = pickaxe_data::block_state_to_name(state) else { continue };
+        let Some(handler) = world_state.block_behaviors.handler_for(name) else { continue };
+        handler(world, world_state, &pos, state, &source, next_eid, scripting);
+    }
+}
+
+/// Run every scheduled block tick that has come due. The block's handler is
+/// looked up by its state now, so a tick for a block that has since changed is dropped.
+pub fn process_scheduled_ticks(
+    world: &mut World,
+    world_state: &mut WorldState,
+    next_eid: &Arc<AtomicI32>,
+    scripting: &ScriptRuntime,
+) {
+    let now = world_state.tick_count;
+    let mut due: Vec<(BlockPos, u64)> = world_state
+        .level
+        .scheduled_block_ticks
+        .iter()
+        .filter(|(_, &at)| at <= now)
+        .map(|(pos, &at)| (*pos, at))
+        .collect();
+    if due.is_empty() {
+        return;
+    }
+    due.sort_by_key(|&(_, at)| at);
+    for (pos, _) in due {
+        world_state.level.scheduled_block_ticks.remove(&pos);
+        let Some(state) = world_state.get_block_if_loaded(&pos) else { continue };
+        let Some(name) = pickaxe_data::block_state_to_name(state) else { continue };
+        let Some(handler) = world_state.block_behaviors.tick_handler_for(name) else { continue };
+        handler(world, world_state, &pos, state, next_eid, scripting);
+    }
+}
+
+/// Torches, ladders, carpets and signs break off (dropping their item) when
+/// the block they were resting on can no longer hold them.
+fn pop_if_unsupported(
+    world: &mut World,
+    world_state: &mut WorldState,
+    pos: &BlockPos,
+    state: i32,
+    source: &BlockPos,
+    next_eid: &Arc<AtomicI32>,
+    scripting: &ScriptRuntime,
+) {
+    let Some((sx, sy, sz)) = pickaxe_data::support_offset(state) else { return };
+    let support_pos = BlockPos::new(pos.x + sx, pos.y + sy, pos.z + sz);
+    // Only the block we hang off matters
+    if support_pos != *source {
+        return;
+    }
+    let name = pickaxe_data::block_state_to_name(state).unwrap_or("");
+    if pickaxe_data::can_support(world_state.get_block(&support_pos), name) {
+        return;
+    }
+
+    world_state.set_block(pos, 0);
+    world_state.remove_block_entity(pos);
+    broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
+        position: *pos,
+        block_id: 0,
+    });
+    let drops = if world_state.game_rules.get_bool("doTileDrops") { pickaxe_data::block_state_to_drops(state) } else { &[] };
+    for &drop_id in drops {
+        spawn_item_entity(
+            world, world_state, next_eid,
+            pos.x as f64 + 0.5, pos.y as f64 + 0.25, pos.z as f64 + 0.5,
+            ItemStack::new(drop_id, 1), 10, scripting,
+        );
+    }
+}
+
+/// Crops only survive on farmland; trampling or breaking it pops the crop.
+fn break_crop_off_farmland(
+    world: &mut World,
+    world_state: &mut WorldState,
+    pos: &BlockPos,
+    state: i32,
+    source: &BlockPos,
+    next_eid: &Arc<AtomicI32>,
+    scripting: &ScriptRuntime,
+) {
+    let below = BlockPos::new(pos.x, pos.y - 1, pos.z);
+    if *source != below || pickaxe_data::is_farmland(world_state.get_block(&below)) {
+        return;
+    }
+
+    world_state.set_block(pos, 0);
+    broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
+        position: *pos,
+        block_id: 0,
+    });
+    spawn_crop_drops(world, world_state, next_eid, pos, state, scripting);
+}
+
+/// Farmland reverts to dirt when a solid block is placed on top of it.
+fn dry_farmland_under_solid(
+    world: &mut World,
+    world_state: &mut WorldState,
+    pos: &BlockPos,
+    _state: i32,
+    source: &BlockPos,
+    _next_eid: &Arc<AtomicI32>,
+    _scripting: &ScriptRuntime,
+) {
+    let above = BlockPos::new(pos.x, pos.y + 1, pos.z);
+    if *source != above || !pickaxe_data::is_solid_block(world_state.get_block(&above)) {
+        return;
+    }
+
+    let dirt = pickaxe_data::block_name_to_default_state("dirt").unwrap_or(10);
+    world_state.set_block(pos, dirt);
+    broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
+        position: *pos,
+        block_id: dirt,
+    });
+}
+
+/// Sponges soak up water within 6 blocks (at most 64 blocks of it) when placed
+/// or when water reaches them, becoming wet sponges.
+fn absorb_water(
+    world: &mut World,
+    world_state: &mut WorldState,
+    pos: &BlockPos,
+    _state: i32,
+    _source: &BlockPos,
+    next_eid: &Arc<AtomicI32>,
+    scripting: &ScriptRuntime,
+) {
+    const MAX_DEPTH: u32 = 6;
+    const MAX_ABSORBED: usize = 64;
+    let offsets = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)];
+
+    let mut absorbed = 0;
+    let mut visited: HashSet<BlockPos> = HashSet::from([*pos]);
+    let mut queue: VecDeque<(BlockPos, u32)> = VecDeque::from([(*pos, 0)]);
+    'search: while let Some((current, depth)) = queue.pop_front() {
+        for (dx, dy, dz) in offsets {
+            let next = BlockPos::new(current.x + dx, current.y + dy, current.z + dz);
+            if !visited.insert(next) {
+                continue;
+            }
+            let Some(block) = world_state.get_block_if_loaded(&next) else { continue };
+            if !take_water(world, world_state, &next, block, next_eid, scripting) {
+                continue;
+            }
+            absorbed += 1;
+            if absorbed >= MAX_ABSORBED {
+                break 'search;
+            }
+            if depth + 1 < MAX_DEPTH {
+                queue.push_back((next, depth + 1));
+            }
+        }
+    }
+    if absorbed == 0 {
+        return;
+    }
+
+    let wet = pickaxe_data::block_name_to_default_state("wet_sponge").unwrap_or(0);
+    world_state.set_block(pos, wet);
+    broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
+        position: *pos,
+        block_id: wet,
+    });
+    // Block-break particles of water around the sponge
+    broadcast_to_level(world, world_state, &InternalPacket::WorldEvent {
+        event: 2001,
+        position: *pos,
+        data: pickaxe_data::WATER_SOURCE,
+        disable_relative: false,
+    });
+    play_sound_at_block(world, pos, "block.sponge.absorb", SOUND_BLOCKS, 1.0, 1.0);
+}
+
+/// Remove the water at `pos` for a sponge: water blocks become air, waterlogged
+/// blocks dry out, and kelp/seagrass break. Returns false if there was no water.
+fn take_water(
+    world: &mut World,
+    world_state: &mut WorldState,
+    pos: &BlockPos,
+    block: i32,
+    next_eid: &Arc<AtomicI32>,
+    scripting: &ScriptRuntime,
+) -> bool {
+    let new_state = if pickaxe_data::is_water(block) {
+        0
+    } else if !pickaxe_data::is_waterlogged(block) {
+        return false;
+    } else if let Some(dried) = pickaxe_data::set_waterlogged(block, false) {
+        dried
+    } else {
+        // Underwater plants can't exist without water
+        let name = pickaxe_data::block_state_to_name(block).unwrap_or("");
+        if !matches!(name, "kelp" | "kelp_plant" | "seagrass" | "tall_seagrass") {
+            return false;
+        }
+        for &drop_id in pickaxe_data::block_state_to_drops(block) {
+            spawn_item_entity(
+                world, world_state, next_eid,
+                pos.x as f64 + 0.5, pos.y as f64 + 0.25, pos.z as f64 + 0.5,
+                ItemStack::new(drop_id, 1), 10, scripting,
+            );
+        }
+        0
+    };
+    world_state.set_block(pos, new_state);
+    broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
+        position: *pos,
+        block_id: new_state,
+    });
+    true
+}
+
+/// Whether a living coral is wet enough: plants and fans may be waterlogged,
+/// and any coral survives with water on one of its six sides.
+fn coral_has_water(world_state: &mut WorldState, pos: &BlockPos, state: i32) -> bool {
+    let name = pickaxe_data::block_state_to_name(state).unwrap_or("");
+    if !pickaxe_data::is_coral_block(name) && pickaxe_data::is_waterlogged(state) {
+        return true;
+    }
+    let offsets = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)];
+    offsets.iter().any(|(dx, dy, dz)| {
+        let adj = world_state.get_block(&BlockPos::new(pos.x + dx, pos.y + dy, pos.z + dz));
+        pickaxe_data::is_water(adj) || pickaxe_data::is_waterlogged(adj)
+    })
+}
+
+/// Coral that loses its water starts dying; it gets 3-5 seconds for the water to come back.
+fn schedule_coral_check(
+    _world: &mut World,
+    world_state: &mut WorldState,
+    pos: &BlockPos,
+    state: i32,
+    _source: &BlockPos,
+    _next_eid: &Arc<AtomicI32>,
+    _scripting: &ScriptRuntime,
+) {
+    if !coral_has_water(world_state, pos, state) {
+        let delay = rand::thread_rng().gen_range(60..100);
+        world_state.schedule_block_tick(pos, delay);
+    }
+}
+
+fn kill_coral_out_of_water(
+    world: &mut World,
+    world_state: &mut WorldState,
+    pos: &BlockPos,
+    state: i32,
+    _next_eid: &Arc<AtomicI32>,
+    _scripting: &ScriptRuntime,
+) {
+    if coral_has_water(world_state, pos, state) {
+        return;
+    }
+    let Some(dead) = pickaxe_data::dead_coral_state(state) else { return };
+    world_state.set_block(pos, dead);
+    broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
+        position: *pos,
+        block_id: dead,
+    });
+}
+
+/// Swap a block for `new_state` and tell every client, if it actually changed.
+fn replace_state(world: &World, world_state: &mut WorldState, pos: &BlockPos, old: i32, new_state: Option<i32>) -> bool {
+    match new_state {
+        Some(new_state) if new_state != old => {
+            world_state.set_block(pos, new_state);
+            broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
+                position: *pos,
+                block_id: new_state,
+            });
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Bells ring once on a rising redstone signal, struck from the side they face.
+fn ring_bell_on_signal(
+    world: &mut World,
+    world_state: &mut WorldState,
+    pos: &BlockPos,
+    state: i32,
+    _source: &BlockPos,
+    _next_eid: &Arc<AtomicI32>,
+    _scripting: &ScriptRuntime,
+) {
+    let was_powered = pickaxe_data::block_property(state, "powered") == Some("true");
+    let powered = block_receives_power(world_state, pos);
+    if powered == was_powered {
+        return;
+    }
+    if powered {
+        let face = match pickaxe_data::block_property(state, "facing") {
+            Some("north") => 2,
+            Some("south") => 3,
+            Some("west") => 4,
+            _ => 5,
+        };
+        ring_bell(world, world_state, pos, face);
+    }
+    let value = if powered { "true" } else { "false" };
+    replace_state(world, world_state, pos, state, pickaxe_data::set_block_property(state, "powered", value));
+}
+
+/// The redstone pulse from turning a lectern's page ends after 2 ticks.
+fn end_lectern_pulse(
+    world: &mut World,
+    world_state: &mut WorldState,
+    pos: &BlockPos,
+    state: i32,
+    _next_eid: &Arc<AtomicI32>,
+    _scripting: &ScriptRuntime,
+) {
+    if replace_state(world, world_state, pos, state, pickaxe_data::set_block_property(state, "powered", "false")) {
+        update_redstone_neighbors(world, world_state, pos);
+    }
+}
+
+/// Campfires on a hay bale send up tall signal smoke.
+fn update_signal_fire(
+    world: &mut World,
+    world_state: &mut WorldState,
+    pos: &BlockPos,
+    state: i32,
+    _source: &BlockPos,
+    _next_eid: &Arc<AtomicI32>,
+    _scripting: &ScriptRuntime,
+) {
+    let below = world_state.get_block(&BlockPos::new(pos.x, pos.y - 1, pos.z));
+    let signal = pickaxe_data::block_state_to_name(below) == Some("hay_block");
+    let value = if signal { "true" } else { "false" };
+    replace_state(world, world_state, pos, state, pickaxe_data::set_block_property(state, "signal_fire", value));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playerdata::PlayerDataStore;
+    use crate::tick::open_level_storage;
+    use pickaxe_region::ChunkCompression;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::sync::mpsc;
+
+    struct Harness {
+        world: World,
+        world_state: WorldState,
+        next_eid: Arc<AtomicI32>,
+        scripting: ScriptRuntime,
+        overrides: BlockOverrides,
+        _save_rx: mpsc::UnboundedReceiver<crate::tick::SaveOp>,
+        dir: std::path::PathBuf,
+    }
+
+    impl Harness {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("pickaxe-behavior-test-{}-{}", name, std::process::id()));
+            let storage = open_level_storage(&dir, "minecraft:overworld", ChunkCompression::default()).unwrap();
+            let (save_tx, save_rx) = mpsc::unbounded_channel();
+            let player_data = Arc::new(PlayerDataStore::new(dir.join("playerdata")));
+            let next_eid = Arc::new(AtomicI32::new(1));
+            Self {
+                world: World::new(),
+                world_state: WorldState::new(storage, save_tx, player_data, next_eid.clone()),
+                next_eid,
+                scripting: ScriptRuntime::new().unwrap(),
+                overrides: BlockOverrides::default(),
+                _save_rx: save_rx,
+                dir,
+            }
+        }
+
+        fn set(&mut self, pos: BlockPos, name: &str) {
+            let state = pickaxe_data::block_name_to_default_state(name).unwrap();
+            self.world_state.set_block(&pos, state);
+            self.drain();
+        }
+
+        fn name_at(&mut self, pos: BlockPos) -> &'static str {
+            pickaxe_data::block_state_to_name(self.world_state.get_block(&pos)).unwrap()
+        }
+
+        fn drain(&mut self) {
+            process_neighbor_updates(&mut self.world, &mut self.world_state, &self.next_eid, &self.scripting, &self.overrides);
+        }
+    }
+
+    impl Drop for Harness {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn empty_behaviors() -> BlockBehaviors {
+        BlockBehaviors { handlers: Vec::new(), tick_handlers: Vec::new() }
+    }
+
+    static FIRST_CALLS: AtomicUsize = AtomicUsize::new(0);
+    static SECOND_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    fn count_first(_: &mut World, _: &mut WorldState, _: &BlockPos, _: i32, _: &BlockPos, _: &Arc<AtomicI32>, _: &ScriptRuntime) {
+        FIRST_CALLS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn count_second(_: &mut World, _: &mut WorldState, _: &BlockPos, _: i32, _: &BlockPos, _: &Arc<AtomicI32>, _: &ScriptRuntime) {
+        SECOND_CALLS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let mut h = Harness::new("dispatch");
+        let mut behaviors = empty_behaviors();
+        behaviors.register(|name| name == "stone", count_first);
+        behaviors.register(|name| name == "stone", count_second);
+        assert!(behaviors.handler_for("dirt").is_none());
+        h.world_state.block_behaviors = behaviors;
+
+        // The new stone is notified about itself; its air neighbors have no handler
+        h.set(BlockPos::new(0, 100, 0), "stone");
+        assert_eq!(FIRST_CALLS.load(Ordering::Relaxed), 1);
+        assert_eq!(SECOND_CALLS.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_unsupported_torch_pops() {
+        let mut h = Harness::new("torch");
+        let (base, torch) = (BlockPos::new(0, 100, 0), BlockPos::new(0, 101, 0));
+        h.set(base, "stone");
+        h.set(torch, "torch");
+        assert_eq!(h.name_at(torch), "torch");
+
+        h.set(base, "air");
+        assert_eq!(h.name_at(torch), "air");
+        assert_eq!(h.world.query::<&crate::ecs::ItemEntity>().iter().count(), 1);
+    }
+
+    #[test]
+    fn test_crops_and_farmland() {
+        let mut h = Harness::new("farmland");
+        let (soil, crop) = (BlockPos::new(0, 100, 0), BlockPos::new(0, 101, 0));
+        h.set(soil, "farmland");
+        h.set(crop, "wheat");
+        h.set(soil, "dirt");
+        assert_eq!(h.name_at(crop), "air");
+
+        h.set(soil, "farmland");
+        h.set(crop, "stone");
+        assert_eq!(h.name_at(soil), "dirt");
+    }
+
+    static LOOP_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    /// Re-notifies itself forever, like two handlers feeding each other.
+    fn feedback(
+        _: &mut World,
+        world_state: &mut WorldState,
+        pos: &BlockPos,
+        _: i32,
+        source: &BlockPos,
+        _: &Arc<AtomicI32>,
+        _: &ScriptRuntime,
+    ) {
+        LOOP_CALLS.fetch_add(1, Ordering::Relaxed);
+        world_state.level.pending_neighbor_updates.push_back((*pos, *source));
+    }
+
+    #[test]
+    fn test_update_cap_ends_cascade() {
+        let mut h = Harness::new("cap");
+        let mut behaviors = empty_behaviors();
+        behaviors.register(|name| name == "stone", feedback);
+        h.world_state.block_behaviors = behaviors;
+
+        h.set(BlockPos::new(0, 100, 0), "stone");
+        assert!(h.world_state.level.pending_neighbor_updates.is_empty());
+        assert!((1..=MAX_NEIGHBOR_UPDATES).contains(&LOOP_CALLS.load(Ordering::Relaxed)));
+    }
+}
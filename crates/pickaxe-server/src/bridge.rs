@@ -1,8 +1,14 @@
+use crate::command_args::ArgType;
 use crate::ecs::*;
+use crate::effects::{ParticleEffect, ParticleEmitter, ParticleShape, Point};
+use crate::http::{HttpRequest, HttpResult, SharedHttpClient};
+use crate::storage::{ModStorage, SharedModStorage};
 use hecs::World;
-use mlua::Lua;
+use mlua::{Lua, LuaSerdeExt};
 use pickaxe_protocol_core::InternalPacket;
 use pickaxe_scripting::bridge::LuaGameContext;
+use pickaxe_scripting::docs::document;
+use pickaxe_scripting::sandbox;
 use pickaxe_types::{BlockPos, GameMode, ItemStack, TextComponent, Vec3d};
 use rand::Rng;
 use std::collections::HashMap;
@@ -13,16 +19,144 @@ use std::sync::{Arc, Mutex};
 pub struct LuaCommand {
     pub name: String,
     pub handler_key: mlua::RegistryKey,
+    /// Operator level (0-4) required to run and see the command.
+    pub permission_level: u8,
+    /// Typed arguments from the `args` schema, shown in the client's command tree.
+    pub args: Vec<LuaCommandArg>,
+}
+
+/// One entry of a Lua command's `args` schema.
+pub struct LuaCommandArg {
+    pub name: String,
+    pub ty: ArgType,
+    pub optional: bool,
+    /// `suggest(sender_name, partial)` callback for live completions.
+    pub suggest: Option<mlua::RegistryKey>,
 }
 
 /// Shared storage for Lua-registered commands.
 pub type LuaCommands = Arc<Mutex<Vec<LuaCommand>>>;
 
-/// Override for a block's properties (hardness, drops, harvest tools).
+/// Callbacks for an open `pickaxe.gui` menu, kept in `WorldState::guis`.
+pub struct LuaGui {
+    /// The player viewing the menu.
+    pub player: hecs::Entity,
+    pub on_click: Option<mlua::RegistryKey>,
+    pub on_close: Option<mlua::RegistryKey>,
+}
+
+/// A `pickaxe.players.request_cookie` waiting for the client's answer, kept
+/// in `WorldState::cookie_requests`.
+pub struct LuaCookieRequest {
+    pub player: hecs::Entity,
+    pub key: String,
+    pub callback: mlua::RegistryKey,
+}
+
+/// Behavior attached to a mob with `pickaxe.entities.set_ai`, kept in
+/// `WorldState::mob_ai`. The mob's built-in goals are paused while it is set.
+#[derive(Default)]
+pub struct LuaMobAi {
+    pub on_tick: Option<mlua::RegistryKey>,
+    pub on_target: Option<mlua::RegistryKey>,
+    pub on_hurt: Option<mlua::RegistryKey>,
+    /// Where `move_to` sent the mob, with its speed multiplier.
+    pub move_target: Option<(Vec3d, f64)>,
+    /// Melee hit queued by `attack`, with its damage.
+    pub pending_attack: Option<(hecs::Entity, f32)>,
+}
+
+/// Which `LuaMobAi` callback to run.
+#[derive(Clone, Copy)]
+pub enum MobAiHandler {
+    Tick,
+    Target,
+    Hurt,
+}
+
+impl LuaMobAi {
+    pub fn handler(&self, handler: MobAiHandler) -> Option<&mlua::RegistryKey> {
+        match handler {
+            MobAiHandler::Tick => self.on_tick.as_ref(),
+            MobAiHandler::Target => self.on_target.as_ref(),
+            MobAiHandler::Hurt => self.on_hurt.as_ref(),
+        }
+    }
+}
+
+/// An item registered by a Lua mod with `pickaxe.items.register_custom`.
+pub struct LuaCustomItem {
+    /// The vanilla item the client sees.
+    pub base_item: i32,
+    pub name: Option<TextComponent>,
+    pub lore: Vec<TextComponent>,
+    pub on_use: Option<mlua::RegistryKey>,
+    pub on_attack: Option<mlua::RegistryKey>,
+}
+
+impl LuaCustomItem {
+    /// A stack of this item tagged with its custom id.
+    pub fn create(&self, id: &str, count: i8) -> ItemStack {
+        let name = pickaxe_data::item_id_to_name(self.base_item).unwrap_or("");
+        let max_durability = pickaxe_data::item_max_durability(name);
+        let mut stack = ItemStack::with_durability(self.base_item, count, max_durability.max(0));
+        stack.custom_name = self.name.clone();
+        stack.lore = self.lore.clone();
+        stack.custom_id = Some(id.to_string());
+        stack
+    }
+}
+
+/// Shared storage for Lua custom items, keyed by custom id.
+pub type LuaCustomItems = Arc<Mutex<HashMap<String, LuaCustomItem>>>;
+
+/// Override for a block's properties (hardness, drops, harvest tools) and
+/// Lua handlers for its behavior.
 pub struct BlockOverride {
     pub hardness: Option<f64>,
     pub drops: Option<Vec<i32>>,
     pub harvest_tools: Option<Vec<i32>>,
+    /// Handlers only run for states with all of these property values.
+    pub properties: Vec<(String, String)>,
+    pub on_interact: Option<mlua::RegistryKey>,
+    pub on_break: Option<mlua::RegistryKey>,
+    pub on_neighbor_update: Option<mlua::RegistryKey>,
+    pub on_random_tick: Option<mlua::RegistryKey>,
+}
+
+/// A Lua block handler, dispatched from the matching server system.
+#[derive(Clone, Copy)]
+pub enum BlockHandler {
+    Interact,
+    Break,
+    NeighborUpdate,
+    RandomTick,
+}
+
+impl BlockOverride {
+    fn handler(&self, handler: BlockHandler) -> Option<&mlua::RegistryKey> {
+        match handler {
+            BlockHandler::Interact => self.on_interact.as_ref(),
+            BlockHandler::Break => self.on_break.as_ref(),
+            BlockHandler::NeighborUpdate => self.on_neighbor_update.as_ref(),
+            BlockHandler::RandomTick => self.on_random_tick.as_ref(),
+        }
+    }
+
+    fn matches_state(&self, state: i32) -> bool {
+        self.properties
+            .iter()
+            .all(|(key, value)| pickaxe_data::block_property(state, key) == Some(value.as_str()))
+    }
+}
+
+/// What a Lua block handler asked for.
+#[derive(Default)]
+pub struct BlockResponse {
+    /// The handler returned "cancel": skip the built-in behavior.
+    pub cancelled: bool,
+    /// `on_break` returned `{drops = {...}}`: spawn these instead of the normal drops.
+    pub drops: Option<Vec<ItemStack>>,
 }
 
 /// Shared storage for Lua block overrides, keyed by block name.
@@ -78,6 +212,11 @@ fn find_player_by_name(world: &World, name: &str) -> Option<hecs::Entity> {
         .map(|(e, _)| e)
 }
 
+/// Find any entity by its protocol entity ID.
+fn find_entity_by_id(world: &World, entity_id: i32) -> Option<hecs::Entity> {
+    world.query::<&EntityId>().iter().find(|(_, eid)| eid.0 == entity_id).map(|(e, _)| e)
+}
+
 /// Give an item to a player entity, returning true on success.
 /// Stacks into existing matching slots before using empty ones.
 fn give_item_to_player(world: &mut World, entity: hecs::Entity, item_id: i32, count: i8) -> bool {
@@ -135,11 +274,39 @@ fn give_item_to_player(world: &mut World, entity: hecs::Entity, item_id: i32, co
 
 // ── World API ──────────────────────────────────────────────────────────
 
+/// Furthest `pickaxe.world.raycast` looks.
+const MAX_RAYCAST_DISTANCE: f64 = 256.0;
+/// Largest `pickaxe.world.find_blocks` and `pickaxe.entities.nearby` radius.
+const MAX_FIND_RADIUS: f64 = 32.0;
+
+/// Convert an `{x, y, z}` table to a position.
+fn lua_vec3d(table: &mlua::Table) -> mlua::Result<Vec3d> {
+    Ok(Vec3d::new(table.get("x")?, table.get("y")?, table.get("z")?))
+}
+
+fn is_air_block(state: i32) -> bool {
+    matches!(pickaxe_data::block_state_to_name(state), None | Some("air" | "cave_air" | "void_air"))
+}
+
+/// Whether a sign side argument names the back; nil means the front.
+fn sign_side_is_back(side: Option<&str>) -> mlua::Result<bool> {
+    match side {
+        None | Some("front") => Ok(false),
+        Some("back") => Ok(true),
+        Some(other) => Err(mlua::Error::runtime(format!("Unknown sign side '{}'", other))),
+    }
+}
+
 /// Register `pickaxe.world` API on the Lua VM.
 pub fn register_world_api(lua: &Lua) -> anyhow::Result<()> {
     let pickaxe: mlua::Table = lua.globals().get("pickaxe").map_err(lua_err)?;
     let world_table = lua.create_table().map_err(lua_err)?;
 
+    document(
+        lua,
+        "pickaxe.world.get_block(x: integer, y: integer, z: integer) -> integer",
+        "Block state id at a position.",
+    );
     world_table
         .set(
             "get_block",
@@ -150,6 +317,11 @@ pub fn register_world_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
+    document(
+        lua,
+        "pickaxe.world.set_block(x: integer, y: integer, z: integer, state_id: integer)",
+        "Set the block state at a position.",
+    );
     world_table
         .set(
             "set_block",
@@ -160,6 +332,7 @@ pub fn register_world_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
+    document(lua, "pickaxe.world.get_time() -> integer", "Time of day in ticks, 0-23999.");
     world_table
         .set(
             "get_time",
@@ -168,19 +341,25 @@ pub fn register_world_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
+    document(lua, "pickaxe.world.set_time(time: integer)", "Set the time of day in ticks.");
     world_table
         .set(
             "set_time",
             lua.create_function(|lua, time: i64| {
                 with_world_state(lua, |ws| {
                     ws.time_of_day = time.rem_euclid(24000);
+                    ws.time_dirty = true;
                 })
             })
             .map_err(lua_err)?,
         )
         .map_err(lua_err)?;
 
-    // pickaxe.world.get_block_entity(x, y, z) -> table or nil
+    document(
+        lua,
+        "pickaxe.world.get_block_entity(x: integer, y: integer, z: integer) -> table?",
+        "Contents of the block entity at a position (chest, furnace, sign, ...), with its `type`.",
+    );
     world_table
         .set(
             "get_block_entity",
@@ -313,14 +492,172 @@ pub fn register_world_api(lua: &Lua) -> anyhow::Result<()> {
                             let _ = table.set("is_waxed", *is_waxed);
                             Some(mlua::Value::Table(table))
                         }
+                        crate::tick::BlockEntity::Bell { ringing_ticks } => {
+                            let table = lua.create_table().ok()?;
+                            let _ = table.set("type", "bell");
+                            let _ = table.set("ringing", *ringing_ticks > 0);
+                            Some(mlua::Value::Table(table))
+                        }
+                        crate::tick::BlockEntity::Lectern { book, page } => {
+                            let table = lua.create_table().ok()?;
+                            let _ = table.set("type", "lectern");
+                            if let Some(item) = book {
+                                let t = lua.create_table().ok()?;
+                                let _ = t.set("id", item.item_id);
+                                let _ = t.set("name", pickaxe_data::item_id_to_name(item.item_id).unwrap_or("unknown"));
+                                let _ = t.set("count", item.count);
+                                let _ = table.set("book", t);
+                                let _ = table.set("page", *page);
+                            }
+                            Some(mlua::Value::Table(table))
+                        }
+                        crate::tick::BlockEntity::Campfire { items, cook_times, cook_totals } => {
+                            let table = lua.create_table().ok()?;
+                            let _ = table.set("type", "campfire");
+                            let slots = lua.create_table().ok()?;
+                            for (i, slot) in items.iter().enumerate() {
+                                if let Some(item) = slot {
+                                    let t = lua.create_table().ok()?;
+                                    let _ = t.set("id", item.item_id);
+                                    let _ = t.set("name", pickaxe_data::item_id_to_name(item.item_id).unwrap_or("unknown"));
+                                    let _ = t.set("count", item.count);
+                                    let _ = t.set("cook_time", cook_times[i]);
+                                    let _ = t.set("cook_total", cook_totals[i]);
+                                    let _ = slots.set(i + 1, t);
+                                }
+                            }
+                            let _ = table.set("items", slots);
+                            Some(mlua::Value::Table(table))
+                        }
+                    }
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.world.get_sign_text(x: integer, y: integer, z: integer, side?: \"front\"|\"back\") -> string[]?",
+        "The four lines on one side of a sign (front by default).",
+    );
+    world_table
+        .set(
+            "get_sign_text",
+            lua.create_function(|lua, (x, y, z, side): (i32, i32, i32, Option<String>)| {
+                let back = sign_side_is_back(side.as_deref())?;
+                let lines = with_world_state(lua, |ws| match ws.get_block_entity(&BlockPos::new(x, y, z)) {
+                    Some(crate::tick::BlockEntity::Sign { front_text, back_text, .. }) => {
+                        Some(if back { back_text.clone() } else { front_text.clone() })
+                    }
+                    _ => None,
+                })?;
+                lines.map(|lines| lua.create_sequence_from(lines)).transpose()
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.world.set_sign_text(x: integer, y: integer, z: integer, lines: string[], side?: \"front\"|\"back\") -> boolean",
+        "Replace the text on one side of a sign (front by default); missing lines are cleared. False if there is no sign.",
+    );
+    world_table
+        .set(
+            "set_sign_text",
+            lua.create_function(|lua, (x, y, z, lines, side): (i32, i32, i32, Vec<String>, Option<String>)| {
+                let back = sign_side_is_back(side.as_deref())?;
+                let pos = BlockPos::new(x, y, z);
+                with_game(lua, |world, ws| {
+                    let Some(crate::tick::BlockEntity::Sign { front_text, back_text, .. }) = ws.get_block_entity_mut(&pos) else {
+                        return false;
+                    };
+                    let text = if back { back_text } else { front_text };
+                    for (i, line) in text.iter_mut().enumerate() {
+                        *line = lines.get(i).cloned().unwrap_or_default();
+                    }
+                    crate::tick::block_entity_changed(world, ws, &pos);
+                    true
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.world.get_furnace(x: integer, y: integer, z: integer) -> table?",
+        "Fuel and smelting progress of a furnace: `lit`, `burn_time` and `burn_duration` (ticks of fuel left, of the current fuel), `cook_progress` and `cook_total`.",
+    );
+    world_table
+        .set(
+            "get_furnace",
+            lua.create_function(|lua, (x, y, z): (i32, i32, i32)| {
+                let furnace = with_world_state(lua, |ws| match ws.get_block_entity(&BlockPos::new(x, y, z)) {
+                    Some(crate::tick::BlockEntity::Furnace { burn_time, burn_duration, cook_progress, cook_total, .. }) => {
+                        Some((*burn_time, *burn_duration, *cook_progress, *cook_total))
                     }
+                    _ => None,
+                })?;
+                let Some((burn_time, burn_duration, cook_progress, cook_total)) = furnace else {
+                    return Ok(None);
+                };
+                let table = lua.create_table()?;
+                table.set("lit", burn_time > 0)?;
+                table.set("burn_time", burn_time)?;
+                table.set("burn_duration", burn_duration)?;
+                table.set("cook_progress", cook_progress)?;
+                table.set("cook_total", cook_total)?;
+                Ok(Some(table))
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.world.get_container_slot(x: integer, y: integer, z: integer, slot: integer) -> table?",
+        "The item in a chest (slots 0-26), furnace (0 input, 1 fuel, 2 output) or brewing stand (0-2 bottles, 3 ingredient, 4 fuel) slot, as `{item_name, item_id, count}`.",
+    );
+    world_table
+        .set(
+            "get_container_slot",
+            lua.create_function(|lua, (x, y, z, slot): (i32, i32, i32, usize)| {
+                let item = with_world_state(lua, |ws| {
+                    ws.get_block_entity(&BlockPos::new(x, y, z)).and_then(|be| be.slot(slot)).cloned().flatten()
+                })?;
+                item.map(|item| lua_item_table(lua, &item)).transpose()
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.world.set_container_slot(x: integer, y: integer, z: integer, slot: integer, item: string|table|nil) -> boolean",
+        "Put an item (a name or `{item, count?, name?}`) in a container slot, numbered as in `get_container_slot`; nil empties it. False if there is no such slot.",
+    );
+    world_table
+        .set(
+            "set_container_slot",
+            lua.create_function(|lua, (x, y, z, slot, item): (i32, i32, i32, usize, mlua::Value)| {
+                let item = lua_item_stack(lua, item)?;
+                let pos = BlockPos::new(x, y, z);
+                with_game(lua, |world, ws| {
+                    let Some(target) = ws.get_block_entity_mut(&pos).and_then(|be| be.slot_mut(slot)) else {
+                        return false;
+                    };
+                    *target = item;
+                    crate::tick::block_entity_changed(world, ws, &pos);
+                    true
                 })
             })
             .map_err(lua_err)?,
         )
         .map_err(lua_err)?;
 
-    // pickaxe.world.get_weather() -> "clear" | "rain" | "thunder"
+    document(lua, "pickaxe.world.get_weather() -> \"clear\"|\"rain\"|\"thunder\"", "Current weather.");
     world_table
         .set(
             "get_weather",
@@ -340,37 +677,121 @@ pub fn register_world_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.world.set_weather(type, duration_ticks)
-    // type: "clear" | "rain" | "thunder"
+    document(
+        lua,
+        "pickaxe.world.set_weather(type: \"clear\"|\"rain\"|\"thunder\"|boolean, duration_or_thunder?: integer|boolean, duration_ticks?: integer)",
+        "Change the weather for a number of ticks (default 6000). Takes a weather type and duration, or `rain, thunder, duration`\nbooleans as in `set_weather(true, false, 1200)`.",
+    );
     world_table
         .set(
             "set_weather",
-            lua.create_function(|lua, (weather_type, duration): (String, Option<i32>)| {
+            lua.create_function(|lua, (first, second, third): (mlua::Value, mlua::Value, Option<i32>)| {
+                let (kind, duration) = match first {
+                    mlua::Value::Boolean(rain) => {
+                        let thunder = lua.unpack::<Option<bool>>(second)?.unwrap_or(false);
+                        let kind = if thunder { "thunder" } else if rain { "rain" } else { "clear" };
+                        (kind.to_string(), third)
+                    }
+                    other => (lua.unpack::<String>(other)?, lua.unpack::<Option<i32>>(second)?),
+                };
+                let changed = with_world_state(lua, |ws| {
+                    crate::tick::set_weather(ws, &kind, Some(duration.unwrap_or(6000)))
+                })?;
+                if !changed {
+                    return Err(mlua::Error::runtime(format!("Unknown weather '{}'", kind)));
+                }
+                Ok(())
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.world.get_tps() -> number, number",
+        "Ticks per second (at most 20) and average milliseconds per tick, over the last 5 seconds.",
+    );
+    world_table
+        .set(
+            "get_tps",
+            lua.create_function(|lua, ()| {
+                with_world_state(lua, |ws| (ws.tick_stats.tps(), ws.tick_stats.average_mspt()))
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.world.get_load() -> string",
+        "How much work the server is shedding to keep up: \"normal\", \"strained\" or \"overloaded\". The `load_change` event fires when it changes.",
+    );
+    world_table
+        .set(
+            "get_load",
+            lua.create_function(|lua, ()| with_world_state(lua, |ws| ws.load.level().name()))
+                .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.world.backup() -> boolean",
+        "Start a world backup as /backup now does. False if backups are disabled or one is already running.",
+    );
+    world_table
+        .set(
+            "backup",
+            lua.create_function(|lua, ()| {
                 with_world_state(lua, |ws| {
-                    let duration = duration.unwrap_or(6000);
-                    match weather_type.as_str() {
-                        "clear" => {
-                            ws.raining = false;
-                            ws.thundering = false;
-                            ws.clear_weather_time = duration;
-                            ws.rain_time = 0;
-                            ws.thunder_time = 0;
-                        }
-                        "rain" => {
-                            ws.raining = true;
-                            ws.thundering = false;
-                            ws.clear_weather_time = 0;
-                            ws.rain_time = duration;
-                            ws.thunder_time = 0;
-                        }
-                        "thunder" => {
-                            ws.raining = true;
-                            ws.thundering = true;
-                            ws.clear_weather_time = 0;
-                            ws.rain_time = duration;
-                            ws.thunder_time = duration;
-                        }
-                        _ => {}
+                    if !ws.backup.enabled || ws.backup_requested || ws.backup_running() {
+                        return false;
+                    }
+                    ws.backup_requested = true;
+                    true
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.world.spawn_lightning(x: number, y: number, z: number)",
+        "Strike lightning at a position, in any weather. It strikes during the weather update, later this tick or early next tick,\nand damages entities nearby like natural lightning.",
+    );
+    world_table
+        .set(
+            "spawn_lightning",
+            lua.create_function(|lua, (x, y, z): (f64, f64, f64)| {
+                with_world_state(lua, |ws| ws.pending_lightning.push(Vec3d::new(x, y, z)))
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.world.play_global_sound(sound_name: string, volume?: number, pitch?: number)",
+        "Play a sound to every online player at their own position, so all hear it alike wherever they are.",
+    );
+    world_table
+        .set(
+            "play_global_sound",
+            lua.create_function(|lua, (sound_name, volume, pitch): (String, Option<f32>, Option<f32>)| {
+                with_world(lua, |world| {
+                    let seed: i64 = rand::random();
+                    for (_, (sender, pos)) in world.query::<(&ConnectionSender, &Position)>().iter() {
+                        let _ = sender.0.send(InternalPacket::SoundEffect {
+                            sound_name: sound_name.clone(),
+                            source: 0, // master
+                            x: pos.0.x,
+                            y: pos.0.y,
+                            z: pos.0.z,
+                            volume: volume.unwrap_or(1.0),
+                            pitch: pitch.unwrap_or(1.0),
+                            seed,
+                        });
                     }
                 })
             })
@@ -378,6 +799,132 @@ pub fn register_world_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
+    document(
+        lua,
+        "pickaxe.world.raycast(origin: {x: number, y: number, z: number}, dir: {x: number, y: number, z: number}, max_dist?: number, include_fluids?: boolean) -> table?",
+        "First solid block along a ray: `x`, `y`, `z`, `block`, `state`, `face`, `distance` and `hit_x/y/z`.\nOnly loaded chunks are checked; air (and fluids unless included) is passed through.",
+    );
+    world_table
+        .set(
+            "raycast",
+            lua.create_function(
+                |lua, (origin, dir, max_dist, include_fluids): (mlua::Table, mlua::Table, Option<f64>, Option<bool>)| {
+                    let origin = lua_vec3d(&origin)?;
+                    let dir = lua_vec3d(&dir)?;
+                    let max_dist = max_dist.unwrap_or(64.0).clamp(0.0, MAX_RAYCAST_DISTANCE);
+                    let include_fluids = include_fluids.unwrap_or(false);
+                    let hit = with_world_state(lua, |ws| {
+                        crate::raycast::raycast(origin, dir, max_dist, |pos| {
+                            let state = ws.get_block_if_loaded(pos).unwrap_or(0);
+                            !is_air_block(state) && (include_fluids || !pickaxe_data::is_fluid(state))
+                        })
+                        .map(|hit| (hit, ws.get_block_if_loaded(&hit.block).unwrap_or(0)))
+                    })?;
+                    let Some((hit, state)) = hit else {
+                        return Ok(None);
+                    };
+                    let table = lua.create_table()?;
+                    table.set("x", hit.block.x)?;
+                    table.set("y", hit.block.y)?;
+                    table.set("z", hit.block.z)?;
+                    table.set("block", pickaxe_data::block_state_to_name(state).unwrap_or("unknown"))?;
+                    table.set("state", state)?;
+                    table.set("face", hit.face)?;
+                    table.set("distance", hit.distance)?;
+                    table.set("hit_x", hit.point.x)?;
+                    table.set("hit_y", hit.point.y)?;
+                    table.set("hit_z", hit.point.z)?;
+                    Ok(Some(table))
+                },
+            )
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.world.find_blocks(center: {x: number, y: number, z: number}, radius: number, block_name: string) -> table[]",
+        "Blocks of a type as `{x, y, z, state}`, nearest first. Searches loaded chunks within `radius` (at most 32) blocks.",
+    );
+    world_table
+        .set(
+            "find_blocks",
+            lua.create_function(|lua, (center, radius, block_name): (mlua::Table, f64, String)| {
+                let center = lua_vec3d(&center)?;
+                let radius = radius.clamp(0.0, MAX_FIND_RADIUS);
+                let block_name = block_name.strip_prefix("minecraft:").unwrap_or(&block_name).to_string();
+                let mut found = with_world_state(lua, |ws| {
+                    let mut found = Vec::new();
+                    let r = radius.ceil() as i32;
+                    let (cx, cy, cz) = (center.x.floor() as i32, center.y.floor() as i32, center.z.floor() as i32);
+                    for x in cx - r..=cx + r {
+                        for y in cy - r..=cy + r {
+                            for z in cz - r..=cz + r {
+                                let pos = BlockPos::new(x, y, z);
+                                let dist_sq = (x as f64 + 0.5 - center.x).powi(2)
+                                    + (y as f64 + 0.5 - center.y).powi(2)
+                                    + (z as f64 + 0.5 - center.z).powi(2);
+                                if dist_sq > radius * radius {
+                                    continue;
+                                }
+                                let Some(state) = ws.get_block_if_loaded(&pos) else {
+                                    continue;
+                                };
+                                if pickaxe_data::block_state_to_name(state) == Some(block_name.as_str()) {
+                                    found.push((dist_sq, pos, state));
+                                }
+                            }
+                        }
+                    }
+                    found
+                })?;
+                found.sort_by(|a, b| a.0.total_cmp(&b.0));
+                let result = lua.create_table()?;
+                for (i, (_, pos, state)) in found.into_iter().enumerate() {
+                    let entry = lua.create_table()?;
+                    entry.set("x", pos.x)?;
+                    entry.set("y", pos.y)?;
+                    entry.set("z", pos.z)?;
+                    entry.set("state", state)?;
+                    result.set(i + 1, entry)?;
+                }
+                Ok(result)
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.world.find_poi(center: {x: number, y: number, z: number}, radius: integer, type?: string) -> table[]",
+        "Points of interest (beds, workstations, bells, portals, lightning rods...) as `{x, y, z, type, free_tickets}`, nearest first. Searches loaded chunks within `radius` blocks; `type` is like `minecraft:home`.",
+    );
+    world_table
+        .set(
+            "find_poi",
+            lua.create_function(|lua, (center, radius, poi_type): (mlua::Table, i32, Option<String>)| {
+                let center = lua_vec3d(&center)?;
+                let center = BlockPos::new(center.x.floor() as i32, center.y.floor() as i32, center.z.floor() as i32);
+                let poi_type = poi_type.map(|t| crate::datapacks::qualify(&t));
+                let found: Vec<pickaxe_world::PoiRecord> = with_world_state(lua, |ws| {
+                    ws.find_poi(&center, radius.max(0), poi_type.as_deref()).into_iter().cloned().collect()
+                })?;
+                let result = lua.create_table()?;
+                for (i, record) in found.into_iter().enumerate() {
+                    let entry = lua.create_table()?;
+                    entry.set("x", record.pos.x)?;
+                    entry.set("y", record.pos.y)?;
+                    entry.set("z", record.pos.z)?;
+                    entry.set("type", record.poi_type)?;
+                    entry.set("free_tickets", record.free_tickets)?;
+                    result.set(i + 1, entry)?;
+                }
+                Ok(result)
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
     pickaxe.set("world", world_table).map_err(lua_err)?;
     Ok(())
 }
@@ -389,7 +936,7 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
     let pickaxe: mlua::Table = lua.globals().get("pickaxe").map_err(lua_err)?;
     let players_table = lua.create_table().map_err(lua_err)?;
 
-    // pickaxe.players.list() -> {"Steve", "Alex", ...}
+    document(lua, "pickaxe.players.list() -> string[]", "Names of online players.");
     players_table
         .set(
             "list",
@@ -407,7 +954,11 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.get(name) -> {name, x, y, z, game_mode, held_slot} or nil
+    document(
+        lua,
+        "pickaxe.players.get(name: string) -> table?",
+        "An online player's name, position, game mode, held slot, health and food.",
+    );
     players_table
         .set(
             "get",
@@ -450,36 +1001,98 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.teleport(name, x, y, z) -> bool
+    document(
+        lua,
+        "pickaxe.players.select(selector: string, origin_name?: string) -> table[]",
+        "Entities matching a target selector such as `@a[distance=..10]`, as `{type, entity_id, name?, x, y, z}`.\n`origin_name` is the player that @s, @p and distance= are relative to.",
+    );
+    players_table
+        .set(
+            "select",
+            lua.create_function(|lua, (selector, origin): (String, Option<String>)| {
+                let sel = crate::selector::parse(&selector).map_err(mlua::Error::runtime)?;
+                with_world(lua, |world| -> mlua::Result<mlua::Table> {
+                    let executor = origin.as_deref().and_then(|n| find_player_by_name(world, n));
+                    let origin_pos = executor
+                        .and_then(|e| world.get::<&Position>(e).ok().map(|p| p.0))
+                        .unwrap_or(Vec3d::new(0.0, 0.0, 0.0));
+                    let results = lua.create_table()?;
+                    let matched = crate::selector::resolve(world, executor, origin_pos, &sel);
+                    for (i, entity) in matched.into_iter().enumerate() {
+                        let entry = lua.create_table()?;
+                        entry.set("type", crate::selector::entity_type_name(world, entity).unwrap_or("unknown"))?;
+                        if let Ok(id) = world.get::<&EntityId>(entity) {
+                            entry.set("entity_id", id.0)?;
+                        }
+                        if let Ok(profile) = world.get::<&Profile>(entity) {
+                            entry.set("name", profile.0.name.clone())?;
+                        }
+                        if let Ok(pos) = world.get::<&Position>(entity) {
+                            entry.set("x", pos.0.x)?;
+                            entry.set("y", pos.0.y)?;
+                            entry.set("z", pos.0.z)?;
+                        }
+                        results.set(i + 1, entry)?;
+                    }
+                    Ok(results)
+                })?
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.players.teleport(name: string, x: number, y: number, z: number, yaw?: number, pitch?: number, dimension?: string) -> boolean",
+        "Teleport an online player, sending the chunks around the destination first. Without yaw and pitch the player keeps\nfacing the same way. The only dimension is `minecraft:overworld`; any other raises an error.",
+    );
     players_table
         .set(
             "teleport",
-            lua.create_function(|lua, (name, x, y, z): (String, f64, f64, f64)| {
-                with_world(lua, |world| {
-                    let entity = match find_player_by_name(world, &name) {
-                        Some(e) => e,
-                        None => return false,
-                    };
-                    if let Ok(mut pos) = world.get::<&mut Position>(entity) {
-                        pos.0 = Vec3d::new(x, y, z);
+            lua.create_function(
+                |lua,
+                 (name, x, y, z, yaw, pitch, dimension): (
+                    String,
+                    f64,
+                    f64,
+                    f64,
+                    Option<f32>,
+                    Option<f32>,
+                    Option<String>,
+                )| {
+                    if let Some(dimension) = dimension {
+                        if dimension.strip_prefix("minecraft:").unwrap_or(&dimension) != "overworld" {
+                            return Err(mlua::Error::runtime(format!("Unknown dimension '{}'", dimension)));
+                        }
                     }
-                    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
-                        let _ = sender.0.send(InternalPacket::SynchronizePlayerPosition {
-                            position: Vec3d::new(x, y, z),
-                            yaw: 0.0,
-                            pitch: 0.0,
-                            flags: 0,
-                            teleport_id: 99,
-                        });
+                    if !(x.is_finite() && y.is_finite() && z.is_finite()) {
+                        return Ok(false);
                     }
-                    true
-                })
-            })
+                    with_game(lua, |world, ws| {
+                        let Some(entity) = find_player_by_name(world, &name) else {
+                            return false;
+                        };
+                        let rotation = match (yaw, pitch) {
+                            (None, None) => None,
+                            (yaw, pitch) => {
+                                let rot = world.get::<&Rotation>(entity).map(|r| (r.yaw, r.pitch)).unwrap_or((0.0, 0.0));
+                                Some((yaw.unwrap_or(rot.0), pitch.unwrap_or(rot.1)))
+                            }
+                        };
+                        crate::tick::teleport_entity(world, ws, entity, Vec3d::new(x, y, z), rotation);
+                        true
+                    })
+                },
+            )
             .map_err(lua_err)?,
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.set_game_mode(name, mode) -> bool
+    document(
+        lua,
+        "pickaxe.players.set_game_mode(name: string, mode: string) -> boolean",
+        "Set a player's game mode (`survival`, `creative`, `adventure` or `spectator`).",
+    );
     players_table
         .set(
             "set_game_mode",
@@ -496,15 +1109,7 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
                         Some(e) => e,
                         None => return false,
                     };
-                    if let Ok(mut gm) = world.get::<&mut PlayerGameMode>(entity) {
-                        gm.0 = mode;
-                    }
-                    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
-                        let _ = sender.0.send(InternalPacket::GameEvent {
-                            event: 3,
-                            value: mode.id() as f32,
-                        });
-                    }
+                    crate::tick::apply_game_mode(world, entity, mode);
                     true
                 })
             })
@@ -512,7 +1117,11 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.send_message(name, text) -> bool
+    document(
+        lua,
+        "pickaxe.players.send_message(name: string, text: string) -> boolean",
+        "Send a chat message to one player.",
+    );
     players_table
         .set(
             "send_message",
@@ -537,7 +1146,7 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.broadcast(text)
+    document(lua, "pickaxe.players.broadcast(text: string)", "Send a chat message to every player.");
     players_table
         .set(
             "broadcast",
@@ -556,21 +1165,107 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.give(name, item_name, count) -> bool
+    document(
+        lua,
+        "pickaxe.players.send_json(name: string, component: table|string) -> boolean",
+        "Send a text component table or JSON string to one player.",
+    );
     players_table
         .set(
-            "give",
-            lua.create_function(|lua, (name, item_name, count): (String, String, Option<i8>)| {
+            "send_json",
+            lua.create_function(|lua, (name, component): (String, mlua::Value)| {
+                let content = lua_text_component(lua, component)?;
                 with_world(lua, |world| {
-                    let item_name = item_name
-                        .strip_prefix("minecraft:")
-                        .unwrap_or(&item_name);
-                    let item_id = match pickaxe_data::item_name_to_id(item_name) {
-                        Some(id) => id,
-                        None => return false,
+                    let Some(entity) = find_player_by_name(world, &name) else {
+                        return false;
                     };
-                    let entity = match find_player_by_name(world, &name) {
-                        Some(e) => e,
+                    match world.get::<&ConnectionSender>(entity) {
+                        Ok(sender) => {
+                            let _ = sender.0.send(InternalPacket::SystemChatMessage { content, overlay: false });
+                            true
+                        }
+                        Err(_) => false,
+                    }
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.players.broadcast_json(component: table|string)",
+        "Send a text component table or JSON string to every player.",
+    );
+    players_table
+        .set(
+            "broadcast_json",
+            lua.create_function(|lua, component: mlua::Value| {
+                let content = lua_text_component(lua, component)?;
+                with_world(lua, |world| {
+                    let packet = InternalPacket::SystemChatMessage { content, overlay: false };
+                    for (_e, sender) in world.query::<&ConnectionSender>().iter() {
+                        let _ = sender.0.send(packet.clone());
+                    }
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.players.kick(name: string, reason?: table|string) -> boolean",
+        "Disconnect a player, showing a text component or string on their disconnect screen.",
+    );
+    players_table
+        .set(
+            "kick",
+            lua.create_function(|lua, (name, reason): (String, Option<mlua::Value>)| {
+                let reason = match reason {
+                    Some(reason) => lua_text_component(lua, reason)?,
+                    None => TextComponent::translatable("multiplayer.disconnect.kicked", Vec::new()),
+                };
+                with_world(lua, |world| {
+                    let Some(entity) = find_player_by_name(world, &name) else {
+                        return false;
+                    };
+                    crate::tick::disconnect(world, entity, reason);
+                    true
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.players.give(name: string, item_name: string, count?: integer) -> boolean",
+        "Give a player items. `item_name` may carry components or SNBT as /give does, e.g. 'diamond_sword{Damage:10}'.",
+    );
+    players_table
+        .set(
+            "give",
+            lua.create_function(|lua, (name, item_name, count): (String, String, Option<i8>)| {
+                with_world(lua, |world| {
+                    if item_name.contains(['[', '{']) {
+                        let (Ok(mut stack), Some(entity)) =
+                            (crate::tick::parse_item_spec(&item_name), find_player_by_name(world, &name))
+                        else {
+                            return false;
+                        };
+                        stack.count = count.unwrap_or(1).max(1);
+                        return crate::tick::insert_into_inventory(world, entity, &stack) > 0;
+                    }
+                    let item_name = item_name
+                        .strip_prefix("minecraft:")
+                        .unwrap_or(&item_name);
+                    let item_id = match pickaxe_data::item_name_to_id(item_name) {
+                        Some(id) => id,
+                        None => return false,
+                    };
+                    let entity = match find_player_by_name(world, &name) {
+                        Some(e) => e,
                         None => return false,
                     };
                     give_item_to_player(world, entity, item_id, count.unwrap_or(1).max(1))
@@ -580,19 +1275,41 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.is_op(name) -> bool
+    document(lua, "pickaxe.players.is_op(name: string) -> boolean", "Whether a player is an operator.");
     players_table
         .set(
             "is_op",
-            lua.create_function(|_lua, name: String| {
-                let ops = crate::config::load_ops();
-                Ok(ops.iter().any(|op| op.eq_ignore_ascii_case(&name)))
+            lua.create_function(|lua, name: String| {
+                let uuid = with_world(lua, |world| {
+                    let entity = find_player_by_name(world, &name)?;
+                    world.get::<&Profile>(entity).ok().map(|p| p.0.uuid)
+                });
+                Ok(crate::access::permission_level(&name, uuid.ok().flatten()) > 0)
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(lua, "pickaxe.players.permission_level(name: string) -> integer", "A player's operator level, 0-4.");
+    players_table
+        .set(
+            "permission_level",
+            lua.create_function(|lua, name: String| {
+                let uuid = with_world(lua, |world| {
+                    let entity = find_player_by_name(world, &name)?;
+                    world.get::<&Profile>(entity).ok().map(|p| p.0.uuid)
+                });
+                Ok(crate::access::permission_level(&name, uuid.ok().flatten()))
             })
             .map_err(lua_err)?,
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.get_health(name) -> {health, max_health, food, saturation, exhaustion} or nil
+    document(
+        lua,
+        "pickaxe.players.get_health(name: string) -> table?",
+        "`health`, `max_health`, `food`, `saturation` and `exhaustion` of a player.",
+    );
     players_table
         .set(
             "get_health",
@@ -614,7 +1331,7 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.set_health(name, health) -> bool
+    document(lua, "pickaxe.players.set_health(name: string, health: number) -> boolean", "Set a player's health.");
     players_table
         .set(
             "set_health",
@@ -647,7 +1364,11 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.set_food(name, food, saturation?) -> bool
+    document(
+        lua,
+        "pickaxe.players.set_food(name: string, food: integer, saturation?: number) -> boolean",
+        "Set a player's food level and saturation.",
+    );
     players_table
         .set(
             "set_food",
@@ -680,8 +1401,11 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.damage(name, amount, source?) -> bool
-    // Respects invulnerability, triggers death if health reaches 0.
+    document(
+        lua,
+        "pickaxe.players.damage(name: string, amount: number, source?: string) -> boolean",
+        "Damage a player. Respects invulnerability and kills the player if health reaches 0.",
+    );
     // Does NOT fire player_damage Lua event (caller IS Lua — avoids re-entrancy).
     players_table
         .set(
@@ -769,7 +1493,11 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.heal(name, amount) -> bool
+    document(
+        lua,
+        "pickaxe.players.heal(name: string, amount: number) -> boolean",
+        "Heal a player, up to their max health.",
+    );
     players_table
         .set(
             "heal",
@@ -798,7 +1526,7 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.get_exhaustion(name) -> number or nil
+    document(lua, "pickaxe.players.get_exhaustion(name: string) -> number?", "A player's food exhaustion.");
     players_table
         .set(
             "get_exhaustion",
@@ -813,7 +1541,11 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.set_exhaustion(name, value) -> bool
+    document(
+        lua,
+        "pickaxe.players.set_exhaustion(name: string, value: number) -> boolean",
+        "Set a player's food exhaustion.",
+    );
     players_table
         .set(
             "set_exhaustion",
@@ -833,7 +1565,11 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.feed(name, nutrition, saturation_modifier) -> bool
+    document(
+        lua,
+        "pickaxe.players.feed(name: string, nutrition: integer, saturation_modifier: number) -> boolean",
+        "Feed a player as if they ate food with these values.",
+    );
     // MC formula: foodLevel = clamp(foodLevel + nutrition, 0, 20)
     //             saturation = clamp(saturation + nutrition * saturation_modifier * 2.0, 0, foodLevel)
     players_table
@@ -873,7 +1609,11 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.add_effect(name, effect_name, duration_ticks, amplifier) -> bool
+    document(
+        lua,
+        "pickaxe.players.add_effect(name: string, effect_name: string, duration_ticks: integer, amplifier: integer) -> boolean",
+        "Give a player a status effect.",
+    );
     players_table
         .set(
             "add_effect",
@@ -919,7 +1659,11 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.remove_effect(name, effect_name) -> bool
+    document(
+        lua,
+        "pickaxe.players.remove_effect(name: string, effect_name: string) -> boolean",
+        "Remove a status effect from a player.",
+    );
     players_table
         .set(
             "remove_effect",
@@ -954,7 +1698,11 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.get_effects(name) -> table or nil
+    document(
+        lua,
+        "pickaxe.players.get_effects(name: string) -> table[]?",
+        "A player's active effects as `{name, duration, amplifier}`.",
+    );
     players_table
         .set(
             "get_effects",
@@ -995,7 +1743,7 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.clear_effects(name) -> bool
+    document(lua, "pickaxe.players.clear_effects(name: string) -> boolean", "Remove all of a player's status effects.");
     players_table
         .set(
             "clear_effects",
@@ -1029,7 +1777,11 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.send_title(name, title, subtitle?, fade_in?, stay?, fade_out?)
+    document(
+        lua,
+        "pickaxe.players.send_title(name: string, title: string, subtitle?: string, fade_in?: integer, stay?: integer, fade_out?: integer)",
+        "Show a title to a player. Times are in ticks.",
+    );
     players_table
         .set(
             "send_title",
@@ -1049,22 +1801,12 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
                             None => return false,
                         };
                         if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
-                            // Send animation times first
-                            let _ = sender.0.send(InternalPacket::SetTitlesAnimation {
-                                fade_in: fade_in.unwrap_or(10),
-                                stay: stay.unwrap_or(70),
-                                fade_out: fade_out.unwrap_or(20),
-                            });
-                            // Send subtitle if provided
-                            if let Some(sub) = subtitle {
-                                let _ = sender.0.send(InternalPacket::SetSubtitleText {
-                                    text: TextComponent::plain(&sub),
-                                });
-                            }
-                            // Send title (must come after subtitle to display both)
-                            let _ = sender.0.send(InternalPacket::SetTitleText {
-                                text: TextComponent::plain(&title),
-                            });
+                            crate::tick::send_title(
+                                &sender,
+                                crate::tick::parse_text_arg(&title),
+                                subtitle.as_deref().map(crate::tick::parse_text_arg),
+                                Some((fade_in.unwrap_or(10), stay.unwrap_or(70), fade_out.unwrap_or(20))),
+                            );
                         }
                         true
                     })
@@ -1074,7 +1816,33 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.send_actionbar(name, text)
+    document(
+        lua,
+        "pickaxe.players.clear_title(name: string, reset?: boolean)",
+        "Hide a player's title; `reset` also restores the default times.",
+    );
+    players_table
+        .set(
+            "clear_title",
+            lua.create_function(|lua, (name, reset): (String, Option<bool>)| {
+                with_world(lua, |world| {
+                    let entity = match find_player_by_name(world, &name) {
+                        Some(e) => e,
+                        None => return false,
+                    };
+                    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+                        let _ = sender.0.send(InternalPacket::ClearTitles {
+                            reset: reset.unwrap_or(false),
+                        });
+                    }
+                    true
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(lua, "pickaxe.players.send_actionbar(name: string, text: string)", "Show text above a player's hotbar.");
     players_table
         .set(
             "send_actionbar",
@@ -1086,7 +1854,100 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
                     };
                     if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
                         let _ = sender.0.send(InternalPacket::SetActionBarText {
-                            text: TextComponent::plain(&text),
+                            text: crate::tick::parse_text_arg(&text),
+                        });
+                    }
+                    true
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.players.send_resource_pack(name: string, url: string, opts?: table) -> string?",
+        "Offer a resource pack with `opts = {hash?, required?, prompt?}`; `hash` is the pack's hex SHA-1.\nReturns the pack's ID, or nil if the player is offline. Responses fire `resource_pack_status`.",
+    );
+    players_table
+        .set(
+            "send_resource_pack",
+            lua.create_function(|lua, (name, url, opts): (String, String, Option<mlua::Table>)| {
+                let (hash, required, prompt) = match opts {
+                    Some(opts) => (
+                        opts.get::<Option<String>>("hash")?.unwrap_or_default(),
+                        opts.get::<Option<bool>>("required")?.unwrap_or(false),
+                        match opts.get::<mlua::Value>("prompt")? {
+                            mlua::Value::Nil => None,
+                            prompt => Some(lua_text_component(lua, prompt)?),
+                        },
+                    ),
+                    None => (String::new(), false, None),
+                };
+                if !crate::resource_pack::valid_hash(&hash) {
+                    return Err(mlua::Error::runtime("hash must be 40 hex digits"));
+                }
+                let uuid = crate::resource_pack::pack_uuid(&url);
+                with_world(lua, |world| {
+                    let entity = find_player_by_name(world, &name)?;
+                    let sender = world.get::<&ConnectionSender>(entity).ok()?;
+                    let _ = sender.0.send(InternalPacket::AddResourcePack {
+                        uuid,
+                        url,
+                        hash: hash.to_ascii_lowercase(),
+                        forced: required,
+                        prompt,
+                    });
+                    Some(uuid.to_string())
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.players.remove_resource_pack(name: string, id?: string) -> boolean",
+        "Unload a pack sent with `send_resource_pack`, or every server pack if `id` is nil.",
+    );
+    players_table
+        .set(
+            "remove_resource_pack",
+            lua.create_function(|lua, (name, id): (String, Option<String>)| {
+                let uuid = id
+                    .map(|id| uuid::Uuid::parse_str(&id).map_err(|_| mlua::Error::runtime(format!("Invalid pack ID '{}'", id))))
+                    .transpose()?;
+                with_world(lua, |world| {
+                    let Some(entity) = find_player_by_name(world, &name) else {
+                        return false;
+                    };
+                    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+                        let _ = sender.0.send(InternalPacket::RemoveResourcePack { uuid });
+                    }
+                    true
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.players.transfer(name: string, host: string, port?: integer) -> boolean",
+        "Send a player to another server (default port 25565), which must accept transfers.\nCookies stored on the player go with them.",
+    );
+    players_table
+        .set(
+            "transfer",
+            lua.create_function(|lua, (name, host, port): (String, String, Option<u16>)| {
+                with_world(lua, |world| {
+                    let Some(entity) = find_player_by_name(world, &name) else {
+                        return false;
+                    };
+                    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+                        let _ = sender.0.send(InternalPacket::Transfer {
+                            host,
+                            port: port.unwrap_or(25565) as i32,
                         });
                     }
                     true
@@ -1096,7 +1957,93 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.get_inventory(name) -> table of {slot_index, item_id, item_name, count, damage?}
+    document(
+        lua,
+        "pickaxe.players.was_transferred(name: string) -> boolean",
+        "Whether a player arrived with a transfer from another server.",
+    );
+    players_table
+        .set(
+            "was_transferred",
+            lua.create_function(|lua, name: String| {
+                with_world(lua, |world| {
+                    find_player_by_name(world, &name).is_some_and(|entity| world.get::<&Transferred>(entity).is_ok())
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.players.store_cookie(name: string, key: string, data: string) -> boolean",
+        "Store data on the client under a `namespace:path` key; it survives transfers but not reconnects.\nSigned with the `[transfer]` secret when one is set, for this player and key, valid for `cookie_ttl_seconds`.",
+    );
+    players_table
+        .set(
+            "store_cookie",
+            lua.create_function(|lua, (name, key, data): (String, String, mlua::String)| {
+                if !crate::channels::valid_channel(&key) {
+                    return Err(mlua::Error::runtime(format!("Invalid cookie key '{}'", key)));
+                }
+                with_game(lua, |world, ws| {
+                    if data.as_bytes().len() > crate::cookies::max_payload(&ws.cookie_secret) {
+                        return Err(mlua::Error::runtime(format!(
+                            "Cookie data is limited to {} bytes",
+                            crate::cookies::max_payload(&ws.cookie_secret)
+                        )));
+                    }
+                    let Some(entity) = find_player_by_name(world, &name) else {
+                        return Ok(false);
+                    };
+                    let Ok(uuid) = world.get::<&Profile>(entity).map(|p| p.0.uuid) else {
+                        return Ok(false);
+                    };
+                    let expires_at = (crate::chat::now_millis() / 1000).saturating_add(ws.cookie_ttl);
+                    let payload = crate::cookies::sign(&ws.cookie_secret, &key, uuid, expires_at, &data.as_bytes());
+                    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+                        let _ = sender.0.send(InternalPacket::StoreCookie { key, payload });
+                    }
+                    Ok(true)
+                })?
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.players.request_cookie(name: string, key: string, callback: function) -> boolean",
+        "Ask a player's client for a stored cookie. `callback(data)` gets nil if there is none,\nits signature doesn't match, or it has expired.",
+    );
+    players_table
+        .set(
+            "request_cookie",
+            lua.create_function(|lua, (name, key, callback): (String, String, mlua::Function)| {
+                if !crate::channels::valid_channel(&key) {
+                    return Err(mlua::Error::runtime(format!("Invalid cookie key '{}'", key)));
+                }
+                let callback = lua.create_registry_value(callback)?;
+                with_game(lua, |world, ws| {
+                    let Some(entity) = find_player_by_name(world, &name) else {
+                        return false;
+                    };
+                    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+                        let _ = sender.0.send(InternalPacket::CookieRequest { key: key.clone() });
+                    }
+                    ws.cookie_requests.push(LuaCookieRequest { player: entity, key, callback });
+                    true
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.players.get_inventory(name: string) -> table[]?",
+        "A player's non-empty slots as `{slot, item_id, item_name, count, damage}`.",
+    );
     players_table
         .set(
             "get_inventory",
@@ -1126,7 +2073,11 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    // pickaxe.players.set_inventory_slot(name, slot, item_name_or_nil, count?) -> bool
+    document(
+        lua,
+        "pickaxe.players.set_inventory_slot(name: string, slot: integer, item_name: string?, count?: integer) -> boolean",
+        "Set or, with a nil item, clear an inventory slot.",
+    );
     players_table
         .set(
             "set_inventory_slot",
@@ -1177,87 +2128,353 @@ pub fn register_players_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    pickaxe.set("players", players_table).map_err(lua_err)?;
-    Ok(())
-}
-
-// ── Sounds API ───────────────────────────────────────────────────────
-
-/// Register `pickaxe.sounds` API on the Lua VM.
-pub fn register_sounds_api(lua: &Lua) -> anyhow::Result<()> {
-    let pickaxe: mlua::Table = lua.globals().get("pickaxe").map_err(lua_err)?;
-    let sounds_table = lua.create_table().map_err(lua_err)?;
-
-    // pickaxe.sounds.play(x, y, z, sound_name, volume?, pitch?) -> bool
-    sounds_table
+    document(
+        lua,
+        "pickaxe.players.set_data(name: string, key: string, value: any) -> boolean",
+        "Store a value for an online player, saved in their player data so it survives relogs and restarts.\nValues are booleans, numbers, strings or tables of those; nil deletes the key. Prefix keys with your mod id.",
+    );
+    players_table
         .set(
-            "play",
-            lua.create_function(
-                |lua, (x, y, z, sound_name, volume, pitch): (f64, f64, f64, String, Option<f32>, Option<f32>)| {
-                    with_world(lua, |world| {
-                        let vol = volume.unwrap_or(1.0);
-                        let p = pitch.unwrap_or(1.0);
-                        let packet = InternalPacket::SoundEffect {
-                            sound_name,
-                            source: 0, // master
-                            x,
-                            y,
-                            z,
-                            volume: vol,
-                            pitch: p,
-                            seed: rand::random(),
-                        };
-                        for (_, sender) in world.query::<&ConnectionSender>().iter() {
-                            let _ = sender.0.send(packet.clone());
-                        }
-                        true
-                    })
-                },
-            )
+            "set_data",
+            lua.create_function(|lua, (name, key, value): (String, String, mlua::Value)| {
+                let value = match value {
+                    mlua::Value::Nil => None,
+                    value => {
+                        let json: serde_json::Value = lua.from_value(value)?;
+                        let nbt = crate::playerdata::json_to_nbt(&json)
+                            .map_err(|e| mlua::Error::runtime(format!("Can't save player data '{}': {}", key, e)))?;
+                        Some(nbt)
+                    }
+                };
+                with_world(lua, |world| {
+                    let Some(entity) = find_player_by_name(world, &name) else {
+                        return false;
+                    };
+                    let Ok(mut data) = world.get::<&mut PlayerModData>(entity) else {
+                        return false;
+                    };
+                    match value {
+                        Some(value) => data.0.insert(key, value),
+                        None => data.0.remove(&key),
+                    };
+                    true
+                })
+            })
             .map_err(lua_err)?,
         )
         .map_err(lua_err)?;
 
-    pickaxe.set("sounds", sounds_table).map_err(lua_err)?;
-    Ok(())
-}
+    document(
+        lua,
+        "pickaxe.players.get_data(name: string, key: string) -> any",
+        "A value stored with `set_data` for an online player, or nil.",
+    );
+    players_table
+        .set(
+            "get_data",
+            lua.create_function(|lua, (name, key): (String, String)| {
+                let value = with_world(lua, |world| {
+                    let entity = find_player_by_name(world, &name)?;
+                    let data = world.get::<&PlayerModData>(entity).ok()?;
+                    data.0.get(&key).map(crate::playerdata::nbt_to_json)
+                })?;
+                match value {
+                    Some(value) => lua.to_value(&value),
+                    None => Ok(mlua::Value::Nil),
+                }
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
 
-// ── Particles API ────────────────────────────────────────────────────
+    document(
+        lua,
+        "pickaxe.players.get_stat(name: string, category: string, stat: string) -> integer?",
+        "A statistic of an online player, e.g. `get_stat(\"Steve\", \"mined\", \"stone\")` or `(\"Steve\", \"custom\", \"play_time\")`.\nnil for unknown categories or players who aren't online.",
+    );
+    players_table
+        .set(
+            "get_stat",
+            lua.create_function(|lua, (name, category, stat): (String, String, String)| {
+                with_world(lua, |world| {
+                    let stat_type = crate::stats::StatType::from_name(category.strip_prefix("minecraft:").unwrap_or(&category))?;
+                    let entity = find_player_by_name(world, &name)?;
+                    let stats = world.get::<&crate::stats::Statistics>(entity).ok()?;
+                    Some(stats.get(stat_type, stat.strip_prefix("minecraft:").unwrap_or(&stat)))
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
 
-/// Register `pickaxe.particles` API on the Lua VM.
-pub fn register_particles_api(lua: &Lua) -> anyhow::Result<()> {
-    let pickaxe: mlua::Table = lua.globals().get("pickaxe").map_err(lua_err)?;
-    let particles_table = lua.create_table().map_err(lua_err)?;
+    document(
+        lua,
+        "pickaxe.players.grant_advancement(name: string, advancement: string, criterion: string?) -> boolean",
+        "Grant an online player one criterion of an advancement, or all of them. True if anything was newly granted.",
+    );
+    players_table
+        .set(
+            "grant_advancement",
+            lua.create_function(|lua, (name, id, criterion): (String, String, Option<String>)| {
+                with_game(lua, |world, ws| {
+                    let Some(entity) = find_player_by_name(world, &name) else {
+                        return false;
+                    };
+                    let criteria: Vec<String> = match criterion {
+                        Some(c) => vec![c],
+                        None => ws
+                            .advancements
+                            .get(&id)
+                            .map(|a| a.criteria.iter().map(|(c, _)| c.clone()).collect())
+                            .unwrap_or_default(),
+                    };
+                    let mut granted = false;
+                    for c in criteria {
+                        granted |= crate::tick::grant_criterion(world, ws, entity, &id, &c);
+                    }
+                    granted
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
 
-    // pickaxe.particles.spawn(x, y, z, particle_id, count?, offset_x?, offset_y?, offset_z?, speed?) -> bool
-    particles_table
+    document(
+        lua,
+        "pickaxe.players.revoke_advancement(name: string, advancement: string, criterion: string?) -> boolean",
+        "Revoke one criterion of an advancement from an online player, or all of them. True if they had any.",
+    );
+    players_table
         .set(
-            "spawn",
-            lua.create_function(
-                |lua,
-                 (x, y, z, particle_id, count, offset_x, offset_y, offset_z, speed): (
-                    f64,
-                    f64,
-                    f64,
-                    i32,
-                    Option<i32>,
-                    Option<f32>,
-                    Option<f32>,
-                    Option<f32>,
-                    Option<f32>,
-                )| {
+            "revoke_advancement",
+            lua.create_function(|lua, (name, id, criterion): (String, String, Option<String>)| {
+                with_game(lua, |world, ws| {
+                    let Some(entity) = find_player_by_name(world, &name) else {
+                        return false;
+                    };
+                    crate::tick::revoke_criteria(world, ws, entity, &id, criterion.as_deref())
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.players.has_advancement(name: string, advancement: string) -> boolean?",
+        "Whether an online player has completed an advancement, e.g. `\"minecraft:story/mine_diamond\"`.\nnil for unknown advancements or players who aren't online.",
+    );
+    players_table
+        .set(
+            "has_advancement",
+            lua.create_function(|lua, (name, id): (String, String)| {
+                with_game(lua, |world, ws| {
+                    let advancement = ws.advancements.get(&id)?;
+                    let entity = find_player_by_name(world, &name)?;
+                    let progress = world.get::<&crate::advancements::AdvancementProgress>(entity).ok()?;
+                    Some(progress.is_done(advancement))
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.players.offline(name_or_uuid: string) -> OfflinePlayer?",
+        "Saved data of a player as a handle with `uuid`, `name` and methods to read and edit it:\n`position()`, `set_position(x, y, z)`, `xp()`, `set_xp_level(level)`, `health()`, `game_mode()`, `inventory()`,\n`set_slot(slot, item?)`, `clear_inventory(item?)`, `homes()`, `set_home(name, x, y, z, yaw?, pitch?)`, `remove_home(name)`,\n`get_nbt(key)` and `set_nbt(key, snbt?)` for any top-level tag. Edits are kept until `save()`, which writes them\nthrough the saver. It refuses while the player is online, or once their data has been saved since the handle\nwas loaded (they joined in between, say); load a fresh handle then.",
+    );
+    players_table
+        .set(
+            "offline",
+            lua.create_function(|lua, name: String| {
+                let player = with_world_state(lua, |ws| ws.player_data.load_offline(&name))?;
+                player.map(|p| lua.create_userdata(OfflinePlayerHandle(p))).transpose()
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    pickaxe.set("players", players_table).map_err(lua_err)?;
+    Ok(())
+}
+
+/// Handle returned by `pickaxe.players.offline`, holding an editable copy of
+/// a player's saved data.
+struct OfflinePlayerHandle(crate::playerdata::OfflinePlayer);
+
+impl mlua::UserData for OfflinePlayerHandle {
+    fn add_fields<F: mlua::UserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("uuid", |_, this| Ok(this.0.uuid.to_string()));
+        fields.add_field_method_get("name", |_, this| Ok(this.0.name.clone()));
+    }
+
+    fn add_methods<M: mlua::UserDataMethods<Self>>(methods: &mut M) {
+        // player:position() -> x, y, z
+        methods.add_method("position", |_, this, ()| {
+            let pos = this.0.position();
+            Ok((pos.x, pos.y, pos.z))
+        });
+
+        methods.add_method_mut("set_position", |_, this, (x, y, z): (f64, f64, f64)| {
+            this.0.set_position(Vec3d::new(x, y, z));
+            Ok(())
+        });
+
+        // player:xp() -> level, progress, total
+        methods.add_method("xp", |_, this, ()| Ok(this.0.xp()));
+
+        methods.add_method_mut("set_xp_level", |_, this, level: i32| {
+            this.0.set_xp_level(level);
+            Ok(())
+        });
+
+        methods.add_method("health", |_, this, ()| Ok(this.0.health()));
+
+        // player:game_mode() -> "survival" | "creative" | "adventure" | "spectator"
+        methods.add_method("game_mode", |_, this, ()| {
+            Ok(match this.0.game_mode() {
+                GameMode::Survival => "survival",
+                GameMode::Creative => "creative",
+                GameMode::Adventure => "adventure",
+                GameMode::Spectator => "spectator",
+            })
+        });
+
+        // player:inventory() -> {[slot] = {item_name, item_id, count}}
+        methods.add_method("inventory", |lua, this, ()| {
+            let table = lua.create_table()?;
+            for (slot, item) in this.0.inventory().iter().enumerate() {
+                if let Some(item) = item {
+                    table.set(slot, lua_item_table(lua, item)?)?;
+                }
+            }
+            Ok(table)
+        });
+
+        // player:set_slot(slot, item_or_nil) -> bool (false for unsaved slots)
+        methods.add_method_mut("set_slot", |lua, this, (slot, item): (usize, mlua::Value)| {
+            let item = lua_item_stack(lua, item)?;
+            Ok(slot < 46 && this.0.set_slot(slot, item))
+        });
+
+        // player:clear_inventory(item?) -> number of stacks removed
+        methods.add_method_mut("clear_inventory", |_, this, item: Option<String>| {
+            let item_id = match item {
+                Some(name) => {
+                    let name = name.strip_prefix("minecraft:").unwrap_or(&name).to_string();
+                    let id = pickaxe_data::item_name_to_id(&name)
+                        .ok_or_else(|| mlua::Error::runtime(format!("Unknown item '{}'", name)))?;
+                    Some(id)
+                }
+                None => None,
+            };
+            let mut removed = 0;
+            for (slot, stack) in this.0.inventory().iter().enumerate() {
+                if stack.as_ref().is_some_and(|s| item_id.is_none_or(|id| s.item_id == id)) {
+                    this.0.set_slot(slot, None);
+                    removed += 1;
+                }
+            }
+            Ok(removed)
+        });
+
+        // player:homes() -> {[name] = {x, y, z, yaw, pitch}}
+        methods.add_method("homes", |lua, this, ()| {
+            let table = lua.create_table()?;
+            for (name, loc) in this.0.homes() {
+                let t = lua.create_table()?;
+                t.set("x", loc.position.x)?;
+                t.set("y", loc.position.y)?;
+                t.set("z", loc.position.z)?;
+                t.set("yaw", loc.yaw)?;
+                t.set("pitch", loc.pitch)?;
+                table.set(name, t)?;
+            }
+            Ok(table)
+        });
+
+        // player:set_home(name, x, y, z, yaw?, pitch?) -> bool (false for bad names)
+        methods.add_method_mut(
+            "set_home",
+            |_, this, (name, x, y, z, yaw, pitch): (String, f64, f64, f64, Option<f32>, Option<f32>)| {
+                if !crate::teleports::valid_name(&name) {
+                    return Ok(false);
+                }
+                let mut homes = this.0.homes();
+                homes.insert(
+                    name.to_lowercase(),
+                    crate::teleports::Location {
+                        position: Vec3d::new(x, y, z),
+                        yaw: yaw.unwrap_or(0.0),
+                        pitch: pitch.unwrap_or(0.0),
+                    },
+                );
+                this.0.set_homes(&homes);
+                Ok(true)
+            },
+        );
+
+        methods.add_method_mut("remove_home", |_, this, name: String| {
+            let mut homes = this.0.homes();
+            let removed = homes.remove(&name.to_lowercase()).is_some();
+            this.0.set_homes(&homes);
+            Ok(removed)
+        });
+
+        // player:get_nbt(key) -> SNBT string or nil
+        methods.add_method("get_nbt", |_, this, key: String| Ok(this.0.tag(&key).map(|v| v.to_string())));
+
+        // player:set_nbt(key, snbt_or_nil); nil removes the tag
+        methods.add_method_mut("set_nbt", |_, this, (key, snbt): (String, Option<String>)| {
+            let value = snbt
+                .as_deref()
+                .map(pickaxe_nbt::parse_snbt)
+                .transpose()
+                .map_err(|e| mlua::Error::runtime(format!("Invalid SNBT for '{}': {}", key, e)))?;
+            this.0.set_tag(&key, value);
+            Ok(())
+        });
+
+        // player:save() -> bool (false while the player is online or the handle is stale)
+        methods.add_method_mut("save", |lua, this, ()| {
+            with_game(lua, |world, ws| {
+                let online = world.query::<&Profile>().iter().any(|(_, p)| p.0.uuid == this.0.uuid);
+                !online && ws.player_data.save_offline(&mut this.0, &ws.save_tx)
+            })
+        });
+    }
+}
+
+// ── Sounds API ───────────────────────────────────────────────────────
+
+/// Register `pickaxe.sounds` API on the Lua VM.
+pub fn register_sounds_api(lua: &Lua) -> anyhow::Result<()> {
+    let pickaxe: mlua::Table = lua.globals().get("pickaxe").map_err(lua_err)?;
+    let sounds_table = lua.create_table().map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.sounds.play(x: number, y: number, z: number, sound_name: string, volume?: number, pitch?: number) -> boolean",
+        "Play a sound at a position for everyone.",
+    );
+    sounds_table
+        .set(
+            "play",
+            lua.create_function(
+                |lua, (x, y, z, sound_name, volume, pitch): (f64, f64, f64, String, Option<f32>, Option<f32>)| {
                     with_world(lua, |world| {
-                        let packet = InternalPacket::LevelParticles {
-                            particle_id,
-                            long_distance: true,
+                        let vol = volume.unwrap_or(1.0);
+                        let p = pitch.unwrap_or(1.0);
+                        let packet = InternalPacket::SoundEffect {
+                            sound_name,
+                            source: 0, // master
                             x,
                             y,
                             z,
-                            offset_x: offset_x.unwrap_or(0.0),
-                            offset_y: offset_y.unwrap_or(0.0),
-                            offset_z: offset_z.unwrap_or(0.0),
-                            max_speed: speed.unwrap_or(0.0),
-                            count: count.unwrap_or(1),
+                            volume: vol,
+                            pitch: p,
+                            seed: rand::random(),
                         };
                         for (_, sender) in world.query::<&ConnectionSender>().iter() {
                             let _ = sender.0.send(packet.clone());
@@ -1270,449 +2487,2546 @@ pub fn register_particles_api(lua: &Lua) -> anyhow::Result<()> {
         )
         .map_err(lua_err)?;
 
-    pickaxe.set("particles", particles_table).map_err(lua_err)?;
-    Ok(())
-}
-
-// ── Commands API ──────────────────────────────────────────────────────
-
-/// Register `pickaxe.commands` API on the Lua VM.
-pub fn register_commands_api(lua: &Lua, lua_commands: LuaCommands) -> anyhow::Result<()> {
-    let pickaxe: mlua::Table = lua.globals().get("pickaxe").map_err(lua_err)?;
-    let commands_table = lua.create_table().map_err(lua_err)?;
-
-    // pickaxe.commands.register(name, handler)
-    commands_table
+    document(
+        lua,
+        "pickaxe.sounds.emit(sound_name: string, opts: table)",
+        "Play a sound with `opts = {x, y, z, category?, volume?, pitch?, min_pitch?, max_pitch?, players?}`.\nPitch is picked from min_pitch..max_pitch when given. `players` limits who hears it (a name or list of names).",
+    );
+    sounds_table
+        .set(
+            "emit",
+            lua.create_function(|lua, (sound_name, opts): (String, mlua::Table)| {
+                let category = opts.get::<Option<String>>("category")?.unwrap_or_else(|| "master".into());
+                let source = crate::effects::sound_category(&category)
+                    .ok_or_else(|| mlua::Error::runtime(format!("Unknown sound category '{}'", category)))?;
+                let pitch = opts.get::<Option<f32>>("pitch")?.unwrap_or(1.0);
+                let min_pitch = opts.get::<Option<f32>>("min_pitch")?.unwrap_or(pitch);
+                let max_pitch = opts.get::<Option<f32>>("max_pitch")?.unwrap_or(min_pitch).max(min_pitch);
+                let packet = InternalPacket::SoundEffect {
+                    sound_name,
+                    source,
+                    x: opts.get("x")?,
+                    y: opts.get("y")?,
+                    z: opts.get("z")?,
+                    volume: opts.get::<Option<f32>>("volume")?.unwrap_or(1.0),
+                    pitch: min_pitch + rand::random::<f32>() * (max_pitch - min_pitch),
+                    seed: rand::random(),
+                };
+                let players = lua_player_names(opts.get("players")?)?;
+                with_world(lua, |world| {
+                    for (_, (profile, sender)) in world.query::<(&Profile, &ConnectionSender)>().iter() {
+                        if players.is_empty() || players.iter().any(|name| name.eq_ignore_ascii_case(&profile.0.name)) {
+                            let _ = sender.0.send(packet.clone());
+                        }
+                    }
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    pickaxe.set("sounds", sounds_table).map_err(lua_err)?;
+    Ok(())
+}
+
+// ── Text API ─────────────────────────────────────────────────────────
+
+/// Convert a Lua text component (table or JSON string) to a `TextComponent`.
+fn lua_text_component(lua: &Lua, value: mlua::Value) -> mlua::Result<TextComponent> {
+    match value {
+        mlua::Value::String(s) => Ok(crate::tick::parse_text_arg(&s.to_str()?)),
+        mlua::Value::Table(_) => lua.from_value(value),
+        other => Err(mlua::Error::runtime(format!(
+            "expected text component table or string, got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+/// Register `pickaxe.text` API on the Lua VM.
+pub fn register_text_api(lua: &Lua) -> anyhow::Result<()> {
+    let pickaxe: mlua::Table = lua.globals().get("pickaxe").map_err(lua_err)?;
+    let text_table = lua.create_table().map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.text.legacy(text: string, prefix?: string) -> table",
+        "Translate `&a`-style formatting codes into a text component (prefix defaults to `&`).",
+    );
+    text_table
+        .set(
+            "legacy",
+            lua.create_function(|lua, (text, prefix): (String, Option<String>)| {
+                let prefix = prefix.and_then(|p| p.chars().next()).unwrap_or('&');
+                lua.to_value(&TextComponent::from_legacy(&text, prefix))
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(lua, "pickaxe.text.to_json(component: table|string) -> string", "Serialize a text component to JSON.");
+    text_table
+        .set(
+            "to_json",
+            lua.create_function(|lua, component: mlua::Value| {
+                Ok(lua_text_component(lua, component)?.to_json())
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    pickaxe.set("text", text_table).map_err(lua_err)?;
+    Ok(())
+}
+
+// ── GUI API ──────────────────────────────────────────────────────────
+
+/// Handle returned by `pickaxe.gui.open`, identifying one opened menu.
+#[derive(Clone, Copy)]
+struct GuiHandle(u64);
+
+/// The player and container ID of an open `pickaxe.gui` menu.
+fn find_gui(world: &World, ws: &crate::tick::WorldState, gui_id: u64) -> Option<(hecs::Entity, u8)> {
+    let entity = ws.guis.get(&gui_id)?.player;
+    let open = world.get::<&OpenContainer>(entity).ok()?;
+    match open.menu {
+        Menu::Custom { gui_id: id, .. } if id == gui_id => Some((entity, open.container_id)),
+        _ => None,
+    }
+}
+
+/// Convert a Lua item (an item name, or a table with `item`, `count` and
+/// `name`) to an `ItemStack`. nil means an empty slot.
+fn lua_item_stack(lua: &Lua, value: mlua::Value) -> mlua::Result<Option<ItemStack>> {
+    let (item_name, count, name) = match value {
+        mlua::Value::Nil => return Ok(None),
+        mlua::Value::String(s) => (s.to_str()?.to_string(), 1, None),
+        mlua::Value::Table(t) => (t.get::<String>("item")?, t.get::<Option<i8>>("count")?.unwrap_or(1), t.get::<mlua::Value>("name")?),
+        other => {
+            return Err(mlua::Error::runtime(format!(
+                "expected item name or table, got {}",
+                other.type_name()
+            )))
+        }
+    };
+    let item_name = item_name.strip_prefix("minecraft:").unwrap_or(&item_name);
+    let item_id = pickaxe_data::item_name_to_id(item_name)
+        .ok_or_else(|| mlua::Error::runtime(format!("Unknown item '{}'", item_name)))?;
+    let max_stack = pickaxe_data::item_max_stack_size(item_id) as i8;
+    let mut stack = ItemStack::new(item_id, count.clamp(1, max_stack));
+    stack.custom_name = lua_item_text(lua, name)?;
+    Ok(Some(stack))
+}
+
+/// Convert an item name or lore line (a `&`-coded string or a component
+/// table) to text. Item text is italic by default in the client, so it is
+/// made non-italic unless a mod says otherwise.
+fn lua_item_text(lua: &Lua, value: mlua::Value) -> mlua::Result<Option<TextComponent>> {
+    let mut text = match value {
+        mlua::Value::Nil => return Ok(None),
+        mlua::Value::String(s) => TextComponent::from_legacy(&s.to_str()?, '&'),
+        other => lua_text_component(lua, other)?,
+    };
+    text.italic.get_or_insert(false);
+    Ok(Some(text))
+}
+
+/// An item as a Lua table of `item_name`, `item_id` and `count`.
+fn lua_item_table(lua: &Lua, item: &ItemStack) -> mlua::Result<mlua::Table> {
+    let table = lua.create_table()?;
+    table.set("item_name", pickaxe_data::item_id_to_name(item.item_id).unwrap_or("unknown"))?;
+    table.set("item_id", item.item_id)?;
+    table.set("count", item.count)?;
+    if let Some(id) = &item.custom_id {
+        table.set("custom_id", id.as_str())?;
+    }
+    Ok(table)
+}
+
+/// Store a callback for an open menu. Returns false if it has closed.
+fn set_gui_callback(lua: &Lua, gui_id: u64, callback: Option<mlua::Function>, on_click: bool) -> mlua::Result<bool> {
+    let key = callback.map(|f| lua.create_registry_value(f)).transpose()?;
+    with_world_state(lua, |ws| match ws.guis.get_mut(&gui_id) {
+        Some(gui) => {
+            if on_click {
+                gui.on_click = key;
+            } else {
+                gui.on_close = key;
+            }
+            true
+        }
+        None => false,
+    })
+}
+
+impl mlua::UserData for GuiHandle {
+    fn add_fields<F: mlua::UserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("id", |_, this| Ok(this.0));
+    }
+
+    fn add_methods<M: mlua::UserDataMethods<Self>>(methods: &mut M) {
+        // gui:set_slot(slot, item_or_nil) -> bool (false once the menu has closed)
+        methods.add_method("set_slot", |lua, this, (slot, item): (usize, mlua::Value)| {
+            let item = lua_item_stack(lua, item)?;
+            with_game(lua, |world, ws| {
+                let Some((entity, container_id)) = find_gui(world, ws, this.0) else {
+                    return false;
+                };
+                let state_id = {
+                    let Ok(mut open) = world.get::<&mut OpenContainer>(entity) else {
+                        return false;
+                    };
+                    let Menu::Custom { ref mut slots, .. } = open.menu else {
+                        return false;
+                    };
+                    let Some(target) = slots.get_mut(slot) else {
+                        return false;
+                    };
+                    *target = item.clone();
+                    open.state_id
+                };
+                if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+                    let _ = sender.0.send(InternalPacket::SetContainerSlot {
+                        window_id: container_id,
+                        state_id,
+                        slot: slot as i16,
+                        item,
+                    });
+                }
+                true
+            })
+        });
+
+        // gui:get_slot(slot) -> {item_name, item_id, count} or nil
+        methods.add_method("get_slot", |lua, this, slot: usize| {
+            let item = with_game(lua, |world, ws| {
+                let (entity, _) = find_gui(world, ws, this.0)?;
+                let open = world.get::<&OpenContainer>(entity).ok()?;
+                match &open.menu {
+                    Menu::Custom { slots, .. } => slots.get(slot).cloned().flatten(),
+                    _ => None,
+                }
+            })?;
+            item.map(|item| lua_item_table(lua, &item)).transpose()
+        });
+
+        // gui:on_click(function(event) ... end) — event has gui, player, slot,
+        // button, mode and item. Clicks are cancelled unless it returns "allow".
+        methods.add_method("on_click", |lua, this, callback: Option<mlua::Function>| {
+            set_gui_callback(lua, this.0, callback, true)
+        });
+
+        // gui:on_close(function(gui, player) ... end)
+        methods.add_method("on_close", |lua, this, callback: Option<mlua::Function>| {
+            set_gui_callback(lua, this.0, callback, false)
+        });
+
+        // gui:is_open() -> bool
+        methods.add_method("is_open", |lua, this, ()| with_world_state(lua, |ws| ws.guis.contains_key(&this.0)));
+
+        // gui:close() -> bool; runs the close callback
+        methods.add_method("close", |lua, this, ()| {
+            let closed = with_game(lua, |world, ws| {
+                let (entity, _) = find_gui(world, ws, this.0)?;
+                let (gui_id, container_id, gui) = crate::tick::take_custom_gui(world, ws, entity)?;
+                if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+                    let _ = sender.0.send(InternalPacket::ContainerClose { container_id: container_id as i32 });
+                }
+                let name = world.get::<&Profile>(entity).map(|p| p.0.name.clone()).unwrap_or_default();
+                Some((gui_id, gui, name))
+            })?;
+            Ok(match closed {
+                Some((gui_id, gui, name)) => {
+                    close_gui(lua, gui_id, gui, &name);
+                    true
+                }
+                None => false,
+            })
+        });
+    }
+}
+
+/// Run a menu's close callback, if it has one.
+fn close_gui(lua: &Lua, gui_id: u64, gui: LuaGui, player: &str) {
+    if let Some(func) = gui.on_close.and_then(|key| lua.registry_value::<mlua::Function>(&key).ok()) {
+        call_gui_close(lua, func, gui_id, player);
+    }
+}
+
+/// Call an `on_close` callback as `callback(gui, player)`.
+pub fn call_gui_close(lua: &Lua, callback: mlua::Function, gui_id: u64, player: &str) {
+    if let Err(e) = sandbox::call::<()>(lua, &callback, (GuiHandle(gui_id), player.to_string())) {
+        tracing::warn!("Lua GUI close handler error: {}", e);
+    }
+}
+
+/// Call an `on_click` callback with an event table. Returns true if it
+/// returned "allow".
+pub fn call_gui_click(
+    lua: &Lua,
+    callback: mlua::Function,
+    gui_id: u64,
+    player: &str,
+    slot: i16,
+    button: i8,
+    mode: i32,
+    item: Option<&ItemStack>,
+) -> bool {
+    let result = (|| {
+        let event = lua.create_table()?;
+        event.set("gui", GuiHandle(gui_id))?;
+        event.set("player", player)?;
+        event.set("slot", slot)?;
+        event.set("button", button)?;
+        event.set("mode", mode)?;
+        if let Some(item) = item {
+            event.set("item", lua_item_table(lua, item)?)?;
+        }
+        sandbox::call::<Option<String>>(lua, &callback, event)
+    })();
+    match result {
+        Ok(response) => response.as_deref() == Some("allow"),
+        Err(e) => {
+            tracing::warn!("Lua GUI click handler error: {}", e);
+            false
+        }
+    }
+}
+
+/// Run a `pickaxe.entities.set_ai` callback with a table describing the mob
+/// plus whatever `fill` adds. Returns true if it returned "cancel".
+pub fn call_mob_ai_handler(
+    lua: &Lua,
+    callback: mlua::Function,
+    mob: hecs::Entity,
+    fill: impl FnOnce(&mlua::Table) -> mlua::Result<()>,
+) -> bool {
+    let result = (|| {
+        let event = with_world(lua, |world| -> mlua::Result<mlua::Table> {
+            let event = lua_entity_table(lua, world, mob)?;
+            let target = world.get::<&MobEntity>(mob).ok().and_then(|m| m.target);
+            if let Some(target_id) = target.and_then(|t| world.get::<&EntityId>(t).ok().map(|e| e.0)) {
+                event.set("target", target_id)?;
+            }
+            Ok(event)
+        })??;
+        fill(&event)?;
+        sandbox::call::<Option<String>>(lua, &callback, event)
+    })();
+    match result {
+        Ok(response) => response.as_deref() == Some("cancel"),
+        Err(e) => {
+            tracing::warn!("Lua mob AI handler error: {}", e);
+            false
+        }
+    }
+}
+
+/// Register `pickaxe.gui` API on the Lua VM.
+pub fn register_gui_api(lua: &Lua) -> anyhow::Result<()> {
+    let pickaxe: mlua::Table = lua.globals().get("pickaxe").map_err(lua_err)?;
+    let gui_table = lua.create_table().map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.gui.open(player: string, rows: integer, title: table|string, handlers?: { on_click?: function, on_close?: function }) -> userdata?",
+        "Open a chest-style menu of 1-6 rows; slots are numbered from 0.\nReplaces (and closes) any menu the player already has open from a mod.",
+    );
+    gui_table
+        .set(
+            "open",
+            lua.create_function(
+                |lua, (name, rows, title, handlers): (String, u8, mlua::Value, Option<mlua::Table>)| {
+                    if !(1..=6).contains(&rows) {
+                        return Err(mlua::Error::runtime("rows must be between 1 and 6"));
+                    }
+                    let title = lua_text_component(lua, title)?;
+                    let (on_click, on_close) = match &handlers {
+                        Some(h) => (h.get::<Option<mlua::Function>>("on_click")?, h.get::<Option<mlua::Function>>("on_close")?),
+                        None => (None, None),
+                    };
+                    let on_click = on_click.map(|f| lua.create_registry_value(f)).transpose()?;
+                    let on_close = on_close.map(|f| lua.create_registry_value(f)).transpose()?;
+                    let opened = with_game(lua, |world, ws| {
+                        let entity = find_player_by_name(world, &name)?;
+                        let replaced = crate::tick::take_custom_gui(world, ws, entity);
+                        let gui_id = crate::tick::open_custom_gui(world, ws, entity, rows, title);
+                        if let Some(gui) = ws.guis.get_mut(&gui_id) {
+                            gui.on_click = on_click;
+                            gui.on_close = on_close;
+                        }
+                        Some((gui_id, replaced))
+                    })?;
+                    let Some((gui_id, replaced)) = opened else {
+                        return Ok(None);
+                    };
+                    if let Some((old_id, _, old)) = replaced {
+                        close_gui(lua, old_id, old, &name);
+                    }
+                    Ok(Some(GuiHandle(gui_id)))
+                },
+            )
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    pickaxe.set("gui", gui_table).map_err(lua_err)?;
+    Ok(())
+}
+
+/// Look up the Lua handler registered for a block state, if any.
+pub fn block_handler(lua: &Lua, overrides: &BlockOverrides, state: i32, handler: BlockHandler) -> Option<mlua::Function> {
+    let name = pickaxe_data::block_state_to_name(state)?;
+    let map = overrides.lock().ok()?;
+    let block_override = map.get(name).filter(|o| o.matches_state(state))?;
+    lua.registry_value(block_override.handler(handler)?).ok()
+}
+
+/// Names of blocks with an `on_random_tick` handler.
+pub fn random_tick_blocks(overrides: &BlockOverrides) -> std::collections::HashSet<String> {
+    overrides
+        .lock()
+        .map(|map| map.iter().filter(|(_, o)| o.on_random_tick.is_some()).map(|(name, _)| name.clone()).collect())
+        .unwrap_or_default()
+}
+
+/// Call a block handler with the common event fields plus those added by
+/// `fill`, and read back what it returned.
+pub fn call_block_handler(
+    lua: &Lua,
+    callback: mlua::Function,
+    pos: &BlockPos,
+    state: i32,
+    fill: impl FnOnce(&mlua::Table) -> mlua::Result<()>,
+) -> BlockResponse {
+    let result = (|| {
+        let event = lua.create_table()?;
+        event.set("x", pos.x)?;
+        event.set("y", pos.y)?;
+        event.set("z", pos.z)?;
+        event.set("block", pickaxe_data::block_state_to_name(state).unwrap_or("unknown"))?;
+        event.set("state", state)?;
+        let properties = lua.create_table()?;
+        if let Some((_, props)) = pickaxe_data::block_state_to_properties(state) {
+            for (key, value) in props {
+                properties.set(key, value)?;
+            }
+        }
+        event.set("properties", properties)?;
+        fill(&event)?;
+        match sandbox::call::<mlua::Value>(lua, &callback, event)? {
+            mlua::Value::String(s) => Ok(BlockResponse { cancelled: &*s.to_str()? == "cancel", drops: None }),
+            mlua::Value::Table(t) => {
+                let drops = match t.get::<Option<mlua::Table>>("drops")? {
+                    Some(items) => Some(
+                        items
+                            .sequence_values::<mlua::Value>()
+                            .filter_map(|item| item.and_then(|item| lua_item_stack(lua, item)).transpose())
+                            .collect::<mlua::Result<Vec<_>>>()?,
+                    ),
+                    None => None,
+                };
+                Ok(BlockResponse { cancelled: false, drops })
+            }
+            _ => Ok(BlockResponse::default()),
+        }
+    })();
+    result.unwrap_or_else(|e| {
+        tracing::warn!("Lua block handler error: {}", e);
+        BlockResponse::default()
+    })
+}
+
+// ── Items API ────────────────────────────────────────────────────────
+
+/// Whether a custom item id is acceptable (`namespace:name` in lowercase
+/// letters, digits, `_`, `-`, `.` and `/`).
+fn valid_custom_id(id: &str) -> bool {
+    let Some((namespace, name)) = id.split_once(':') else {
+        return false;
+    };
+    let valid = |s: &str| {
+        !s.is_empty() && s.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-' | '.' | '/'))
+    };
+    valid(namespace) && valid(name) && !name.contains(':')
+}
+
+/// Look up a custom item's `on_use` (or `on_attack`) callback.
+pub fn custom_item_callback(lua: &Lua, items: &LuaCustomItems, id: &str, attack: bool) -> Option<mlua::Function> {
+    let items = items.lock().ok()?;
+    let item = items.get(id)?;
+    let key = if attack { item.on_attack.as_ref() } else { item.on_use.as_ref() }?;
+    lua.registry_value(key).ok()
+}
+
+/// Call a custom item callback with an event table built by `fill`.
+/// Returns true if it returned "cancel".
+fn call_custom_item(
+    lua: &Lua,
+    callback: mlua::Function,
+    player: &str,
+    id: &str,
+    fill: impl FnOnce(&mlua::Table) -> mlua::Result<()>,
+) -> bool {
+    let result = (|| {
+        let event = lua.create_table()?;
+        event.set("player", player)?;
+        event.set("item", id)?;
+        fill(&event)?;
+        sandbox::call::<Option<String>>(lua, &callback, event)
+    })();
+    match result {
+        Ok(response) => response.as_deref() == Some("cancel"),
+        Err(e) => {
+            tracing::warn!("Lua custom item handler error: {}", e);
+            false
+        }
+    }
+}
+
+/// Call an `on_use` callback; the event has `player`, `item` and `hand`
+/// ("main_hand" or "off_hand"). Returns true if it returned "cancel".
+pub fn call_custom_item_use(lua: &Lua, callback: mlua::Function, player: &str, id: &str, hand: i32) -> bool {
+    call_custom_item(lua, callback, player, id, |event| {
+        event.set("hand", if hand == 1 { "off_hand" } else { "main_hand" })
+    })
+}
+
+/// Call an `on_attack` callback; the event has `player`, `item`, `target`
+/// (entity id) and `target_type`. Returns true if it returned "cancel".
+pub fn call_custom_item_attack(
+    lua: &Lua,
+    callback: mlua::Function,
+    player: &str,
+    id: &str,
+    target: i32,
+    target_type: Option<&str>,
+) -> bool {
+    call_custom_item(lua, callback, player, id, |event| {
+        event.set("target", target)?;
+        event.set("target_type", target_type)
+    })
+}
+
+fn lock_custom_items(
+    items: &LuaCustomItems,
+) -> mlua::Result<std::sync::MutexGuard<'_, HashMap<String, LuaCustomItem>>> {
+    items
+        .lock()
+        .map_err(|e| mlua::Error::runtime(format!("Lock poisoned: {}", e)))
+}
+
+/// Register `pickaxe.items` API on the Lua VM.
+pub fn register_items_api(lua: &Lua, custom_items: LuaCustomItems) -> anyhow::Result<()> {
+    let pickaxe: mlua::Table = lua.globals().get("pickaxe").map_err(lua_err)?;
+    let items_table = lua.create_table().map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.items.register_custom(base_item: string, def: { id: string, name?: string, lore?: string[], on_use?: function, on_attack?: function }) -> string",
+        "Register a custom item built on a vanilla one. `id` is \"namespace:name\"; registering the same id again replaces it.\non_use(event) and on_attack(event) may return \"cancel\" to skip the base item's behaviour.",
+    );
+    let items = custom_items.clone();
+    items_table
+        .set(
+            "register_custom",
+            lua.create_function(move |lua, (base_item, def): (String, mlua::Table)| {
+                let base_name = base_item.strip_prefix("minecraft:").unwrap_or(&base_item);
+                let base_item = pickaxe_data::item_name_to_id(base_name)
+                    .ok_or_else(|| mlua::Error::runtime(format!("Unknown item '{}'", base_name)))?;
+                let id: String = def.get("id")?;
+                if !valid_custom_id(&id) {
+                    return Err(mlua::Error::runtime(format!(
+                        "invalid custom item id '{}' (expected namespace:name)",
+                        id
+                    )));
+                }
+                let name = lua_item_text(lua, def.get("name")?)?;
+                let lore = match def.get::<Option<mlua::Table>>("lore")? {
+                    Some(lines) => lines
+                        .sequence_values::<mlua::Value>()
+                        .filter_map(|line| line.and_then(|line| lua_item_text(lua, line)).transpose())
+                        .collect::<mlua::Result<Vec<_>>>()?,
+                    None => Vec::new(),
+                };
+                let on_use = def
+                    .get::<Option<mlua::Function>>("on_use")?
+                    .map(|f| lua.create_registry_value(f))
+                    .transpose()?;
+                let on_attack = def
+                    .get::<Option<mlua::Function>>("on_attack")?
+                    .map(|f| lua.create_registry_value(f))
+                    .transpose()?;
+                lock_custom_items(&items)?.insert(
+                    id.clone(),
+                    LuaCustomItem { base_item, name, lore, on_use, on_attack },
+                );
+                Ok(id)
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.items.give_custom(player: string, id: string, count?: integer) -> boolean",
+        "Give a player a custom item; false if nothing fit.",
+    );
+    let items = custom_items.clone();
+    items_table
+        .set(
+            "give_custom",
+            lua.create_function(move |lua, (name, id, count): (String, String, Option<i8>)| {
+                let stack = {
+                    let items = lock_custom_items(&items)?;
+                    let item = items
+                        .get(&id)
+                        .ok_or_else(|| mlua::Error::runtime(format!("Unknown custom item '{}'", id)))?;
+                    item.create(&id, count.unwrap_or(1).max(1))
+                };
+                let max_stack = pickaxe_data::item_max_stack_size(stack.item_id);
+                with_world(lua, |world| {
+                    let Some(entity) = find_player_by_name(world, &name) else {
+                        return false;
+                    };
+                    let (inserted, state_id, slots) = {
+                        let Ok(mut inv) = world.get::<&mut Inventory>(entity) else {
+                            return false;
+                        };
+                        let (inserted, changed) = inv.insert_stack(&stack, max_stack);
+                        let slots: Vec<_> = changed.into_iter().map(|i| (i, inv.slots[i].clone())).collect();
+                        (inserted, inv.state_id, slots)
+                    };
+                    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+                        for (slot, item) in slots {
+                            let _ = sender.0.send(InternalPacket::SetContainerSlot {
+                                window_id: 0,
+                                state_id,
+                                slot: slot as i16,
+                                item,
+                            });
+                        }
+                    }
+                    inserted > 0
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.items.held_custom_id(player: string) -> string?",
+        "Id of the custom item in a player's main hand.",
+    );
+    items_table
+        .set(
+            "held_custom_id",
+            lua.create_function(|lua, name: String| {
+                with_world(lua, |world| {
+                    let entity = find_player_by_name(world, &name)?;
+                    let held = world.get::<&HeldSlot>(entity).ok()?.0;
+                    let inv = world.get::<&Inventory>(entity).ok()?;
+                    inv.slots[36 + held as usize].as_ref()?.custom_id.clone()
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    pickaxe.set("items", items_table).map_err(lua_err)?;
+    Ok(())
+}
+
+// ── Particles API ────────────────────────────────────────────────────
+
+/// A field of an optional options table.
+fn opt_field<T: mlua::FromLua>(opts: Option<&mlua::Table>, key: &str) -> mlua::Result<Option<T>> {
+    match opts {
+        Some(opts) => opts.get(key),
+        None => Ok(None),
+    }
+}
+
+/// Convert an `{x, y, z}` table field to a point.
+fn lua_point(table: &mlua::Table, key: &str) -> mlua::Result<Point> {
+    let point: mlua::Table = table
+        .get::<Option<mlua::Table>>(key)?
+        .ok_or_else(|| mlua::Error::runtime(format!("shape needs '{}' = {{x, y, z}}", key)))?;
+    Ok((point.get("x")?, point.get("y")?, point.get("z")?))
+}
+
+/// Convert a shape table: `{type = "line", from, to, step?}`,
+/// `{type = "sphere", center, radius, points?}` or
+/// `{type = "cuboid", min, max, step?, solid?}`.
+fn lua_particle_shape(shape: &mlua::Table) -> mlua::Result<ParticleShape> {
+    let kind: String = shape.get("type")?;
+    match kind.as_str() {
+        "line" => Ok(ParticleShape::Line {
+            from: lua_point(shape, "from")?,
+            to: lua_point(shape, "to")?,
+            step: shape.get::<Option<f64>>("step")?.unwrap_or(0.25),
+        }),
+        "sphere" => Ok(ParticleShape::Sphere {
+            center: lua_point(shape, "center")?,
+            radius: shape.get("radius")?,
+            points: shape.get::<Option<usize>>("points")?.unwrap_or(64),
+        }),
+        "cuboid" => Ok(ParticleShape::Cuboid {
+            min: lua_point(shape, "min")?,
+            max: lua_point(shape, "max")?,
+            step: shape.get::<Option<f64>>("step")?.unwrap_or(0.5),
+            solid: shape.get::<Option<bool>>("solid")?.unwrap_or(false),
+        }),
+        other => Err(mlua::Error::runtime(format!("Unknown shape type '{}'", other))),
+    }
+}
+
+/// The effect and target players from the options of a shape call:
+/// `{count?, offset_x?, offset_y?, offset_z?, speed?, players?}`.
+fn lua_particle_options(particle_id: i32, opts: Option<&mlua::Table>) -> mlua::Result<(ParticleEffect, Vec<String>)> {
+    let effect = ParticleEffect {
+        particle_id,
+        count: opt_field(opts, "count")?.unwrap_or(1),
+        offset: (
+            opt_field(opts, "offset_x")?.unwrap_or(0.0),
+            opt_field(opts, "offset_y")?.unwrap_or(0.0),
+            opt_field(opts, "offset_z")?.unwrap_or(0.0),
+        ),
+        speed: opt_field(opts, "speed")?.unwrap_or(0.0),
+    };
+    let players = lua_player_names(opt_field(opts, "players")?.unwrap_or(mlua::Value::Nil))?;
+    Ok((effect, players))
+}
+
+/// Target players: nil for everyone, or a name or list of names.
+fn lua_player_names(value: mlua::Value) -> mlua::Result<Vec<String>> {
+    match value {
+        mlua::Value::Nil => Ok(Vec::new()),
+        mlua::Value::String(s) => Ok(vec![s.to_str()?.to_string()]),
+        mlua::Value::Table(t) => t.sequence_values::<String>().collect(),
+        other => Err(mlua::Error::runtime(format!(
+            "expected player name or list of names, got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+/// Register `pickaxe.particles` API on the Lua VM.
+pub fn register_particles_api(lua: &Lua) -> anyhow::Result<()> {
+    let pickaxe: mlua::Table = lua.globals().get("pickaxe").map_err(lua_err)?;
+    let particles_table = lua.create_table().map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.particles.spawn(x: number, y: number, z: number, particle_id: integer, count?: integer, offset_x?: number, offset_y?: number, offset_z?: number, speed?: number) -> boolean",
+        "Spawn particles at a position.",
+    );
+    particles_table
+        .set(
+            "spawn",
+            lua.create_function(
+                |lua,
+                 (x, y, z, particle_id, count, offset_x, offset_y, offset_z, speed): (
+                    f64,
+                    f64,
+                    f64,
+                    i32,
+                    Option<i32>,
+                    Option<f32>,
+                    Option<f32>,
+                    Option<f32>,
+                    Option<f32>,
+                )| {
+                    with_world(lua, |world| {
+                        let packet = InternalPacket::LevelParticles {
+                            particle_id,
+                            long_distance: true,
+                            x,
+                            y,
+                            z,
+                            offset_x: offset_x.unwrap_or(0.0),
+                            offset_y: offset_y.unwrap_or(0.0),
+                            offset_z: offset_z.unwrap_or(0.0),
+                            max_speed: speed.unwrap_or(0.0),
+                            count: count.unwrap_or(1),
+                        };
+                        for (_, sender) in world.query::<&ConnectionSender>().iter() {
+                            let _ = sender.0.send(packet.clone());
+                        }
+                        true
+                    })
+                },
+            )
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.particles.shape(particle_id: integer, shape: table, opts?: table) -> integer",
+        "Draw particles over a shape once and return the number of points.\n`shape` is `{type = \"line\", from, to, step?}`, `{type = \"sphere\", center, radius, points?}` or `{type = \"cuboid\", min, max, step?, solid?}`.\n`opts` takes `count`, `offset_x/y/z`, `speed` and `players`.",
+    );
+    particles_table
+        .set(
+            "shape",
+            lua.create_function(|lua, (particle_id, shape, opts): (i32, mlua::Table, Option<mlua::Table>)| {
+                let points = lua_particle_shape(&shape)?.points();
+                let (effect, players) = lua_particle_options(particle_id, opts.as_ref())?;
+                with_world(lua, |world| {
+                    crate::tick::send_particle_points(world, &effect, &points, &players);
+                    points.len()
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.particles.emitter(particle_id: integer, shape: table, opts?: table) -> integer",
+        "Redraw a shape every `opts.interval` ticks (default 1) for `opts.duration` ticks, or until stopped. Returns the emitter id.",
+    );
+    particles_table
+        .set(
+            "emitter",
+            lua.create_function(|lua, (particle_id, shape, opts): (i32, mlua::Table, Option<mlua::Table>)| {
+                let points = lua_particle_shape(&shape)?.points();
+                let (effect, players) = lua_particle_options(particle_id, opts.as_ref())?;
+                let interval = opt_field::<u64>(opts.as_ref(), "interval")?.unwrap_or(1).max(1);
+                let duration = opt_field::<u64>(opts.as_ref(), "duration")?;
+                with_world_state(lua, |ws| {
+                    let started = ws.tick_count;
+                    ws.particle_emitters.add(ParticleEmitter {
+                        effect,
+                        points,
+                        players,
+                        interval,
+                        started,
+                        until: duration.map(|d| started + d),
+                    })
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(lua, "pickaxe.particles.stop(emitter_id: integer) -> boolean", "Stop a particle emitter.");
+    particles_table
+        .set(
+            "stop",
+            lua.create_function(|lua, id: u64| with_world_state(lua, |ws| ws.particle_emitters.remove(id)))
+                .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    pickaxe.set("particles", particles_table).map_err(lua_err)?;
+    Ok(())
+}
+
+// ── Scoreboard API ───────────────────────────────────────────────────
+
+/// Broadcast a scoreboard change, converting the result to Lua's `ok, err` convention.
+fn scoreboard_result(world: &World, result: Result<InternalPacket, String>) -> (bool, Option<String>) {
+    match result {
+        Ok(packet) => {
+            crate::tick::broadcast_to_all(world, &packet);
+            (true, None)
+        }
+        Err(e) => (false, Some(e)),
+    }
+}
+
+/// Register `pickaxe.scoreboard` API on the Lua VM.
+pub fn register_scoreboard_api(lua: &Lua) -> anyhow::Result<()> {
+    use crate::scoreboard::Criteria;
+
+    let pickaxe: mlua::Table = lua.globals().get("pickaxe").map_err(lua_err)?;
+    let scoreboard_table = lua.create_table().map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.scoreboard.add_objective(name: string, criteria: string, display_name?: string) -> boolean, string?",
+        "Create an objective.",
+    );
+    scoreboard_table
+        .set(
+            "add_objective",
+            lua.create_function(|lua, (name, criteria, display_name): (String, String, Option<String>)| {
+                with_game(lua, |world, ws| {
+                    let Some(criteria) = Criteria::parse(&criteria) else {
+                        return (false, Some(format!("Unknown criteria '{}'", criteria)));
+                    };
+                    let result = ws.scoreboard.add_objective(&name, criteria, display_name.as_deref());
+                    scoreboard_result(world, result)
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(lua, "pickaxe.scoreboard.remove_objective(name: string) -> boolean, string?", "Remove an objective.");
+    scoreboard_table
+        .set(
+            "remove_objective",
+            lua.create_function(|lua, name: String| {
+                with_game(lua, |world, ws| {
+                    let result = ws.scoreboard.remove_objective(&name);
+                    scoreboard_result(world, result)
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(lua, "pickaxe.scoreboard.objectives() -> table[]", "All objectives as `{name, criteria, display_name}`.");
+    scoreboard_table
+        .set(
+            "objectives",
+            lua.create_function(|lua, ()| {
+                with_world_state(lua, |ws| -> mlua::Result<mlua::Table> {
+                    let table = lua.create_table()?;
+                    for (i, objective) in ws.scoreboard.objectives().iter().enumerate() {
+                        let entry = lua.create_table()?;
+                        entry.set("name", objective.name.clone())?;
+                        entry.set("criteria", objective.criteria.name())?;
+                        entry.set("display_name", objective.display_name.clone())?;
+                        table.set(i + 1, entry)?;
+                    }
+                    Ok(table)
+                })?
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.scoreboard.set_display(slot: string, objective?: string) -> boolean, string?",
+        "Show an objective in a display slot: \"list\", \"sidebar\", \"below_name\" or \"sidebar.team.<color>\". nil clears the slot.",
+    );
+    scoreboard_table
+        .set(
+            "set_display",
+            lua.create_function(|lua, (slot, objective): (String, Option<String>)| {
+                with_game(lua, |world, ws| {
+                    let result = ws.scoreboard.set_display(&slot, objective.as_deref());
+                    scoreboard_result(world, result)
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(lua, "pickaxe.scoreboard.get_score(holder: string, objective: string) -> integer?", "A score, if set.");
+    scoreboard_table
+        .set(
+            "get_score",
+            lua.create_function(|lua, (holder, objective): (String, String)| {
+                with_world_state(lua, |ws| ws.scoreboard.get_score(&holder, &objective))
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.scoreboard.set_score(holder: string, objective: string, value: integer) -> boolean, string?",
+        "Set a score.",
+    );
+    scoreboard_table
+        .set(
+            "set_score",
+            lua.create_function(|lua, (holder, objective, value): (String, String, i32)| {
+                with_game(lua, |world, ws| {
+                    let result = ws.scoreboard.set_score(&holder, &objective, value);
+                    scoreboard_result(world, result)
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.scoreboard.add_score(holder: string, objective: string, delta: integer) -> integer?, string?",
+        "Add to a score and return the new value.",
+    );
+    scoreboard_table
+        .set(
+            "add_score",
+            lua.create_function(|lua, (holder, objective, delta): (String, String, i32)| {
+                with_game(lua, |world, ws| match ws.scoreboard.add_score(&holder, &objective, delta) {
+                    Ok((value, packet)) => {
+                        crate::tick::broadcast_to_all(world, &packet);
+                        (Some(value), None)
+                    }
+                    Err(e) => (None, Some(e)),
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.scoreboard.reset_score(holder: string, objective?: string) -> boolean, string?",
+        "Clear a score, or all of a holder's scores.",
+    );
+    scoreboard_table
+        .set(
+            "reset_score",
+            lua.create_function(|lua, (holder, objective): (String, Option<String>)| {
+                with_game(lua, |world, ws| {
+                    let result = ws.scoreboard.reset_score(&holder, objective.as_deref());
+                    scoreboard_result(world, result)
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    pickaxe.set("scoreboard", scoreboard_table).map_err(lua_err)?;
+    Ok(())
+}
+
+// ── Commands API ──────────────────────────────────────────────────────
+
+/// Register `pickaxe.commands` API on the Lua VM.
+pub fn register_commands_api(lua: &Lua, lua_commands: LuaCommands) -> anyhow::Result<()> {
+    let pickaxe: mlua::Table = lua.globals().get("pickaxe").map_err(lua_err)?;
+    let commands_table = lua.create_table().map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.commands.register(name: string, schema_or_handler: table|function, handler_or_level?: function|integer, permission_level?: integer)",
+        "Register a chat command, called as `handler(sender_name, args, ctx)` where `ctx` is the execution context: executor, x, y, z,\nyaw, pitch, dimension. An optional schema `{args = {{name, type?, optional?, suggest?}, ...}}` before the handler gives the\nclient typed arguments; types are string, text (rest of line), integer, number, boolean, player and block_pos.\n`suggest(sender_name, partial)` returns completions for an argument as it is typed.",
+    );
+    commands_table
+        .set(
+            "register",
+            lua.create_function(
+                move |lua, (name, first, second, third): (String, mlua::Value, mlua::Value, Option<u8>)| {
+                    let (schema, handler, level) = match first {
+                        mlua::Value::Function(handler) => (None, handler, lua.unpack::<Option<u8>>(second)?),
+                        mlua::Value::Table(schema) => (Some(schema), lua.unpack::<mlua::Function>(second)?, third),
+                        _ => return Err(mlua::Error::runtime("expected an argument schema or a handler function")),
+                    };
+                    let level = level.unwrap_or(0);
+                    if level > 4 {
+                        return Err(mlua::Error::runtime("permission level must be between 0 and 4"));
+                    }
+                    let args = match schema {
+                        Some(schema) => lua_command_args(lua, &schema)?,
+                        None => Vec::new(),
+                    };
+                    let key = lua
+                        .create_registry_value(handler)
+                        .map_err(|e| mlua::Error::runtime(format!("Failed to store handler: {}", e)))?;
+                    let mut cmds = lua_commands
+                        .lock()
+                        .map_err(|e| mlua::Error::runtime(format!("Lock poisoned: {}", e)))?;
+                    cmds.push(LuaCommand {
+                        name: name.clone(),
+                        handler_key: key,
+                        permission_level: level,
+                        args,
+                    });
+                    Ok(())
+                },
+            )
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    pickaxe.set("commands", commands_table).map_err(lua_err)?;
+    Ok(())
+}
+
+/// Parse the `args` list of a command schema. Required arguments can't follow
+/// optional ones, and nothing can follow a `text` argument.
+fn lua_command_args(lua: &Lua, schema: &mlua::Table) -> mlua::Result<Vec<LuaCommandArg>> {
+    let mut parsed: Vec<LuaCommandArg> = Vec::new();
+    let Some(args) = schema.get::<Option<mlua::Table>>("args")? else {
+        return Ok(parsed);
+    };
+    for arg in args.sequence_values::<mlua::Table>() {
+        let arg = arg?;
+        let name: String = arg.get("name")?;
+        let type_name = arg.get::<Option<String>>("type")?.unwrap_or_else(|| "string".into());
+        let Some(ty) = ArgType::from_name(&type_name) else {
+            return Err(mlua::Error::runtime(format!(
+                "Argument '{}' has unknown type '{}' (expected one of: {})",
+                name,
+                type_name,
+                ArgType::NAMES
+            )));
+        };
+        let optional = arg.get::<Option<bool>>("optional")?.unwrap_or(false);
+        if let Some(last) = parsed.last() {
+            if last.ty == ArgType::Text {
+                return Err(mlua::Error::runtime(format!("Argument '{}' follows text argument '{}'", name, last.name)));
+            }
+            if last.optional && !optional {
+                return Err(mlua::Error::runtime(format!("Required argument '{}' follows an optional one", name)));
+            }
+        }
+        let suggest = match arg.get::<Option<mlua::Function>>("suggest")? {
+            Some(f) => Some(lua.create_registry_value(f)?),
+            None => None,
+        };
+        parsed.push(LuaCommandArg { name, ty, optional, suggest });
+    }
+    Ok(parsed)
+}
+
+// ── Blocks API ────────────────────────────────────────────────────────
+
+/// Register `pickaxe.blocks` API on the Lua VM.
+pub fn register_blocks_api(lua: &Lua, overrides: BlockOverrides) -> anyhow::Result<()> {
+    let pickaxe: mlua::Table = lua.globals().get("pickaxe").map_err(lua_err)?;
+    let blocks_table = lua.create_table().map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.blocks.register(name: string, props: table)",
+        "Override a block: `{hardness?, drops?, harvest_tools?, properties?, on_interact?, on_break?, on_neighbor_update?, on_random_tick?}`.\nHandlers get an event table (x, y, z, block, state, properties) and may return \"cancel\" to skip the built-in behavior.",
+    );
+    // props = { hardness = 1.5, drops = {"cobblestone"}, harvest_tools = {"wooden_pickaxe", ...} }
+    // Handlers, each called with an event table (x, y, z, block, state, properties):
+    //   on_interact(event)        — right-click; event.player, event.hand, event.face
+    //   on_break(event)           — player break; event.player. May return {drops = {item, ...}}
+    //   on_neighbor_update(event) — adjacent block changed; event.source_x/y/z
+    //   on_random_tick(event)     — random tick, at the randomTickSpeed rate
+    // Returning "cancel" skips the built-in behavior. `properties = {lit = "true"}`
+    // limits the handlers to matching block states.
+    let overrides_clone = overrides.clone();
+    blocks_table
+        .set(
+            "register",
+            lua.create_function(move |lua, (name, props): (String, mlua::Table)| {
+                let name = name.strip_prefix("minecraft:").unwrap_or(&name).to_string();
+                let hardness: Option<f64> = props.get("hardness").unwrap_or(None);
+
+                let drops: Option<Vec<i32>> = match props.get::<Option<mlua::Table>>("drops") {
+                    Ok(Some(tbl)) => {
+                        let mut ids = Vec::new();
+                        for pair in tbl.sequence_values::<String>() {
+                            if let Ok(item_name) = pair {
+                                let clean = item_name
+                                    .strip_prefix("minecraft:")
+                                    .unwrap_or(&item_name)
+                                    .to_string();
+                                if let Some(id) = pickaxe_data::item_name_to_id(&clean) {
+                                    ids.push(id);
+                                }
+                            }
+                        }
+                        Some(ids)
+                    }
+                    _ => None,
+                };
+
+                let harvest_tools: Option<Vec<i32>> =
+                    match props.get::<Option<mlua::Table>>("harvest_tools") {
+                        Ok(Some(tbl)) => {
+                            let mut ids = Vec::new();
+                            for pair in tbl.sequence_values::<String>() {
+                                if let Ok(tool_name) = pair {
+                                    let clean = tool_name
+                                        .strip_prefix("minecraft:")
+                                        .unwrap_or(&tool_name)
+                                        .to_string();
+                                    if let Some(id) = pickaxe_data::item_name_to_id(&clean) {
+                                        ids.push(id);
+                                    }
+                                }
+                            }
+                            Some(ids)
+                        }
+                        _ => None,
+                    };
+
+                let properties = match props.get::<Option<mlua::Table>>("properties")? {
+                    Some(tbl) => tbl
+                        .pairs::<String, mlua::Value>()
+                        .map(|pair| {
+                            let (key, value) = pair?;
+                            let value = match value {
+                                mlua::Value::String(s) => s.to_str()?.to_string(),
+                                mlua::Value::Boolean(b) => b.to_string(),
+                                mlua::Value::Integer(i) => i.to_string(),
+                                other => {
+                                    return Err(mlua::Error::runtime(format!(
+                                        "invalid value for block property '{}': {}",
+                                        key,
+                                        other.type_name()
+                                    )))
+                                }
+                            };
+                            Ok((key, value))
+                        })
+                        .collect::<mlua::Result<Vec<_>>>()?,
+                    None => Vec::new(),
+                };
+                let handler = |key: &str| -> mlua::Result<Option<mlua::RegistryKey>> {
+                    props
+                        .get::<Option<mlua::Function>>(key)?
+                        .map(|f| lua.create_registry_value(f))
+                        .transpose()
+                };
+                let block_override = BlockOverride {
+                    hardness,
+                    drops,
+                    harvest_tools,
+                    properties,
+                    on_interact: handler("on_interact")?,
+                    on_break: handler("on_break")?,
+                    on_neighbor_update: handler("on_neighbor_update")?,
+                    on_random_tick: handler("on_random_tick")?,
+                };
+
+                let mut map = overrides_clone
+                    .lock()
+                    .map_err(|e| mlua::Error::runtime(format!("Lock poisoned: {}", e)))?;
+                map.insert(name, block_override);
+                Ok(())
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(lua, "pickaxe.blocks.get_hardness(name: string) -> number?", "Hardness of an overridden block.");
+    let overrides_clone = overrides.clone();
+    blocks_table
+        .set(
+            "get_hardness",
+            lua.create_function(move |_lua, name: String| {
+                let map = overrides_clone
+                    .lock()
+                    .map_err(|e| mlua::Error::runtime(format!("Lock poisoned: {}", e)))?;
+                Ok(map.get(&name).and_then(|o| o.hardness))
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(lua, "pickaxe.blocks.get_drops(name: string) -> string[]?", "Drops of an overridden block.");
+    let overrides_clone = overrides.clone();
+    blocks_table
+        .set(
+            "get_drops",
+            lua.create_function(move |_lua, name: String| {
+                let map = overrides_clone
+                    .lock()
+                    .map_err(|e| mlua::Error::runtime(format!("Lock poisoned: {}", e)))?;
+                Ok(map.get(&name).and_then(|o| {
+                    o.drops.as_ref().map(|ids| {
+                        ids.iter()
+                            .filter_map(|id| pickaxe_data::item_id_to_name(*id))
+                            .map(|s| s.to_string())
+                            .collect::<Vec<String>>()
+                    })
+                }))
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    pickaxe.set("blocks", blocks_table).map_err(lua_err)?;
+    Ok(())
+}
+
+// ── Entities API ──────────────────────────────────────────────────────
+
+/// Helper context that also includes next_eid for entity spawning.
+pub struct LuaEntitiesContext {
+    pub next_eid: Arc<AtomicI32>,
+}
+
+/// Describe an entity as returned by `pickaxe.entities.get`.
+fn lua_entity_table(lua: &Lua, world: &World, e: hecs::Entity) -> mlua::Result<mlua::Table> {
+    let table = lua.create_table()?;
+    if let Ok(eid) = world.get::<&EntityId>(e) {
+        table.set("id", eid.0)?;
+    }
+    if let Ok(pos) = world.get::<&Position>(e) {
+        table.set("x", pos.0.x)?;
+        table.set("y", pos.0.y)?;
+        table.set("z", pos.0.z)?;
+    }
+
+    if let Ok(profile) = world.get::<&Profile>(e) {
+        table.set("type", "player")?;
+        table.set("name", profile.0.name.clone())?;
+    } else if let Ok(item_ent) = world.get::<&ItemEntity>(e) {
+        table.set("type", "item")?;
+        table.set("item_id", item_ent.item.item_id)?;
+        table.set("item_count", item_ent.item.count)?;
+        let item_name = pickaxe_data::item_id_to_name(item_ent.item.item_id).unwrap_or("unknown");
+        table.set("item_name", item_name)?;
+        table.set("age", item_ent.age)?;
+    } else if let Ok(mob) = world.get::<&MobEntity>(e) {
+        table.set("type", "mob")?;
+        let mob_name = pickaxe_data::mob_type_name(mob.mob_type).unwrap_or("unknown");
+        table.set("mob_type", mob_name)?;
+        table.set("health", mob.health)?;
+        table.set("max_health", mob.max_health)?;
+    } else if let Ok(display) = world.get::<&DisplayEntity>(e) {
+        table.set("type", "display")?;
+        table.set("display_type", display.content.type_name())?;
+    }
+    Ok(table)
+}
+
+/// Convert display text (a `&`-coded string or a component table) to a `TextComponent`.
+fn lua_display_text(lua: &Lua, value: mlua::Value) -> mlua::Result<TextComponent> {
+    match value {
+        mlua::Value::String(s) => Ok(TextComponent::from_legacy(&s.to_str()?, '&')),
+        other => lua_text_component(lua, other),
+    }
+}
+
+/// Spawn a display entity at `pos`, reading the `billboard`, `scale`,
+/// `glowing`, `glow_color`, `yaw` and `pitch` options every display accepts.
+fn spawn_display(lua: &Lua, pos: &mlua::Table, content: DisplayContent, opts: &mlua::Table) -> mlua::Result<i32> {
+    let pos = lua_vec3d(pos)?;
+    let billboard = match opts.get::<Option<String>>("billboard")? {
+        Some(name) => DisplayBillboard::from_name(&name)
+            .ok_or_else(|| mlua::Error::runtime(format!("Unknown billboard '{}'", name)))?,
+        None => DisplayBillboard::Fixed,
+    };
+    let display = DisplayEntity {
+        content,
+        billboard,
+        scale: opts.get::<Option<f32>>("scale")?.unwrap_or(1.0),
+        glow_color: opts.get::<Option<i64>>("glow_color")?.map(|c| c as i32),
+    };
+    let glowing = opts.get::<Option<bool>>("glowing")?.unwrap_or(false);
+    let rotation = Rotation {
+        yaw: opts.get::<Option<f32>>("yaw")?.unwrap_or(0.0),
+        pitch: opts.get::<Option<f32>>("pitch")?.unwrap_or(0.0),
+    };
+
+    let next_eid = lua
+        .app_data_ref::<LuaEntitiesContext>()
+        .ok_or_else(|| mlua::Error::runtime("Entities context not available"))?
+        .next_eid
+        .clone();
+    with_world(lua, |world| {
+        let eid = next_eid.fetch_add(1, Ordering::Relaxed);
+        let entity = world.spawn((EntityId(eid), EntityUuid(uuid::Uuid::new_v4()), Position(pos), rotation, display));
+        if glowing {
+            let _ = world.insert_one(entity, Glowing);
+        }
+        eid
+    })
+}
+
+/// A non-player entity by entity ID, for the functions that change how one looks.
+fn find_non_player(world: &World, entity_id: i32) -> Option<hecs::Entity> {
+    find_entity_by_id(world, entity_id).filter(|&e| world.get::<&Profile>(e).is_err())
+}
+
+/// Register `pickaxe.entities` API on the Lua VM.
+pub fn register_entities_api(lua: &Lua, next_eid: Arc<AtomicI32>) -> anyhow::Result<()> {
+    let pickaxe: mlua::Table = lua.globals().get("pickaxe").map_err(lua_err)?;
+    let entities_table = lua.create_table().map_err(lua_err)?;
+
+    // Store next_eid in app data for entity spawning
+    lua.set_app_data(LuaEntitiesContext {
+        next_eid: next_eid.clone(),
+    });
+
+    document(
+        lua,
+        "pickaxe.entities.spawn_item(x: number, y: number, z: number, item_name: string, count?: integer) -> integer?",
+        "Drop an item entity and return its entity id.",
+    );
+    entities_table
+        .set(
+            "spawn_item",
+            lua.create_function(
+                |lua, (x, y, z, item_name, count): (f64, f64, f64, String, Option<i8>)| {
+                    let item_name = item_name
+                        .strip_prefix("minecraft:")
+                        .unwrap_or(&item_name)
+                        .to_string();
+                    let item_id = match pickaxe_data::item_name_to_id(&item_name) {
+                        Some(id) => id,
+                        None => return Ok(mlua::Value::Nil),
+                    };
+                    let count = count.unwrap_or(1).max(1);
+
+                    let next_eid = lua
+                        .app_data_ref::<LuaEntitiesContext>()
+                        .ok_or_else(|| mlua::Error::runtime("Entities context not available"))?
+                        .next_eid
+                        .clone();
+
+                    let ctx = get_context(lua)?;
+                    let world = unsafe { &mut *(ctx.world_ptr as *mut World) };
+                    let ws = unsafe { &mut *(ctx.world_state_ptr as *mut crate::tick::WorldState) };
+
+                    // We need a scripting reference, but we can't get it from here easily.
+                    // Instead, directly spawn the entity without firing the Lua event
+                    // (the caller is already in Lua context).
+                    let eid = next_eid.fetch_add(1, Ordering::Relaxed);
+                    let uuid = uuid::Uuid::new_v4();
+
+                    let mut rng = rand::thread_rng();
+                    let vx = rng.gen_range(-0.1..0.1);
+                    let vz = rng.gen_range(-0.1..0.1);
+
+                    world.spawn((
+                        EntityId(eid),
+                        EntityUuid(uuid),
+                        Position(Vec3d::new(x, y, z)),
+                        PreviousPosition(Vec3d::new(x, y, z)),
+                        Velocity(Vec3d::new(vx, 0.2, vz)),
+                        OnGround(false),
+                        ItemEntity {
+                            item: ItemStack::new(item_id, count),
+                            pickup_delay: 10,
+                            age: 0,
+                        },
+                        Rotation {
+                            yaw: 0.0,
+                            pitch: 0.0,
+                        },
+                    ));
+
+                    let _ = ws; // touched for consistency
+                    Ok(mlua::Value::Integer(eid as i64))
+                },
+            )
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.entities.spawn_mob(x: number, y: number, z: number, mob_type_name: string) -> integer?",
+        "Spawn a mob and return its entity id.",
+    );
+    entities_table
+        .set(
+            "spawn_mob",
+            lua.create_function(
+                |lua, (x, y, z, mob_name): (f64, f64, f64, String)| {
+                    let mob_name = mob_name
+                        .strip_prefix("minecraft:")
+                        .unwrap_or(&mob_name)
+                        .to_string();
+                    let mob_type = match pickaxe_data::mob_name_to_type(&mob_name) {
+                        Some(id) => id,
+                        None => return Ok(mlua::Value::Nil),
+                    };
+
+                    let next_eid = lua
+                        .app_data_ref::<LuaEntitiesContext>()
+                        .ok_or_else(|| mlua::Error::runtime("Entities context not available"))?
+                        .next_eid
+                        .clone();
+
+                    let ctx = get_context(lua)?;
+                    let world = unsafe { &mut *(ctx.world_ptr as *mut World) };
+
+                    let eid = next_eid.fetch_add(1, Ordering::Relaxed);
+                    let max_hp = pickaxe_data::mob_max_health(mob_type);
+                    let yaw: f32 = rand::thread_rng().gen_range(0.0..360.0);
+
+                    world.spawn((
+                        EntityId(eid),
+                        EntityUuid(uuid::Uuid::new_v4()),
+                        Position(Vec3d::new(x, y, z)),
+                        PreviousPosition(Vec3d::new(x, y, z)),
+                        Rotation { yaw, pitch: 0.0 },
+                        PreviousRotation { yaw, pitch: 0.0 },
+                        OnGround(true),
+                        Velocity(Vec3d::new(0.0, 0.0, 0.0)),
+                        MobEntity {
+                            mob_type,
+                            health: max_hp,
+                            max_health: max_hp,
+                            target: None,
+                            ai_state: MobAiState::Idle,
+                            ai_timer: rand::thread_rng().gen_range(20..100),
+                            ambient_sound_timer: rand::thread_rng().gen_range(100..300),
+                            no_damage_ticks: 0,
+                            fuse_timer: -1,
+                            attack_cooldown: 0,
+                            no_ai: false,
+                        },
+                    ));
+
+                    Ok(mlua::Value::Integer(eid as i64))
+                },
+            )
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.entities.summon(entity_name: string, x: number, y: number, z: number, nbt?: string) -> integer?, string?",
+        "Summon an entity as /summon does. `nbt` is an SNBT string (e.g. '{CustomName:\"Bob\",NoAI:1b}').",
+    );
+    entities_table
+        .set(
+            "summon",
+            lua.create_function(
+                |lua, (name, x, y, z, nbt): (String, f64, f64, f64, Option<String>)| {
+                    let nbt = match nbt.as_deref().map(pickaxe_nbt::parse_snbt).transpose() {
+                        Ok(nbt) => nbt,
+                        Err(e) => return Ok((None, Some(format!("Invalid NBT: {}", e)))),
+                    };
+                    with_game(lua, |world, ws| {
+                        match crate::tick::summon_entity(world, ws, &name, Vec3d::new(x, y, z), nbt.as_ref()) {
+                            Ok((_, eid)) => (Some(eid), None),
+                            Err(e) => (None, Some(e)),
+                        }
+                    })
+                },
+            )
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(lua, "pickaxe.entities.remove(entity_id: integer) -> boolean", "Remove an item, mob or display.");
+    entities_table
+        .set(
+            "remove",
+            lua.create_function(|lua, entity_id: i32| {
+                with_world(lua, |world| {
+                    let mut found = None;
+                    for (e, eid) in world.query::<&EntityId>().iter() {
+                        if eid.0 == entity_id {
+                            // Only remove non-player entities
+                            if world.get::<&Profile>(e).is_err() {
+                                found = Some(e);
+                            }
+                            break;
+                        }
+                    }
+                    if let Some(entity) = found {
+                        // Remove from tracked entities
+                        for (_e, tracked) in world.query::<&mut TrackedEntities>().iter() {
+                            tracked.visible.remove(&entity_id);
+                        }
+                        // Broadcast removal
+                        for (_e, sender) in world.query::<&ConnectionSender>().iter() {
+                            let _ = sender.0.send(InternalPacket::RemoveEntities {
+                                entity_ids: vec![entity_id],
+                            });
+                        }
+                        let _ = world.despawn(entity);
+                        true
+                    } else {
+                        false
+                    }
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.entities.get(entity_id: integer) -> table?",
+        "An entity's id, type and position, plus item, mob or display details.",
+    );
+    entities_table
+        .set(
+            "get",
+            lua.create_function(|lua, entity_id: i32| {
+                with_world(lua, |world| -> mlua::Result<Option<mlua::Table>> {
+                    match find_entity_by_id(world, entity_id) {
+                        Some(e) => lua_entity_table(lua, world, e).map(Some),
+                        None => Ok(None),
+                    }
+                })?
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.entities.set_velocity(entity_id: integer, vx: number, vy: number, vz: number) -> boolean",
+        "Set an entity's velocity in blocks per tick.",
+    );
+    entities_table
+        .set(
+            "set_velocity",
+            lua.create_function(|lua, (entity_id, vx, vy, vz): (i32, f64, f64, f64)| {
+                with_world(lua, |world| {
+                    for (e, eid) in world.query::<&EntityId>().iter() {
+                        if eid.0 == entity_id {
+                            if let Ok(mut vel) = world.get::<&mut Velocity>(e) {
+                                vel.0 = Vec3d::new(vx, vy, vz);
+                                return true;
+                            }
+                            break;
+                        }
+                    }
+                    false
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(lua, "pickaxe.entities.list() -> table[]", "Every item and mob, as in `entities.get`.");
+    entities_table
+        .set(
+            "list",
+            lua.create_function(|lua, ()| {
+                with_world(lua, |world| -> Vec<mlua::Value> {
+                    let mut result = Vec::new();
+                    for (_e, (eid, pos, item_ent)) in world
+                        .query::<(&EntityId, &Position, &ItemEntity)>()
+                        .iter()
+                    {
+                        if let Ok(table) = lua.create_table() {
+                            let _ = table.set("id", eid.0);
+                            let _ = table.set("type", "item");
+                            let _ = table.set("x", pos.0.x);
+                            let _ = table.set("y", pos.0.y);
+                            let _ = table.set("z", pos.0.z);
+                            let _ = table.set("item_id", item_ent.item.item_id);
+                            let _ = table.set("item_count", item_ent.item.count);
+                            let item_name =
+                                pickaxe_data::item_id_to_name(item_ent.item.item_id)
+                                    .unwrap_or("unknown");
+                            let _ = table.set("item_name", item_name);
+                            result.push(mlua::Value::Table(table));
+                        }
+                    }
+                    for (_e, (eid, pos, mob)) in world
+                        .query::<(&EntityId, &Position, &MobEntity)>()
+                        .iter()
+                    {
+                        if let Ok(table) = lua.create_table() {
+                            let _ = table.set("id", eid.0);
+                            let _ = table.set("type", "mob");
+                            let _ = table.set("x", pos.0.x);
+                            let _ = table.set("y", pos.0.y);
+                            let _ = table.set("z", pos.0.z);
+                            let mob_name = pickaxe_data::mob_type_name(mob.mob_type)
+                                .unwrap_or("unknown");
+                            let _ = table.set("mob_type", mob_name);
+                            let _ = table.set("health", mob.health);
+                            result.push(mlua::Value::Table(table));
+                        }
+                    }
+                    result
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.entities.nearby(pos: {x: number, y: number, z: number}, radius: number, filter?: string) -> table[]",
+        "Entities within a radius, nearest first, each with its `distance`.\n`filter` is \"player\", \"mob\", \"item\", \"display\" or a mob type such as \"zombie\".",
+    );
+    entities_table
+        .set(
+            "nearby",
+            lua.create_function(|lua, (pos, radius, filter): (mlua::Table, f64, Option<String>)| {
+                let center = lua_vec3d(&pos)?;
+                let radius = radius.clamp(0.0, MAX_FIND_RADIUS);
+                with_world(lua, |world| -> mlua::Result<mlua::Table> {
+                    let mut found = Vec::new();
+                    for (e, (_, pos)) in world.query::<(&EntityId, &Position)>().iter() {
+                        let distance = ((pos.0.x - center.x).powi(2)
+                            + (pos.0.y - center.y).powi(2)
+                            + (pos.0.z - center.z).powi(2))
+                        .sqrt();
+                        if distance > radius {
+                            continue;
+                        }
+                        let kind = if world.get::<&Profile>(e).is_ok() {
+                            "player"
+                        } else if world.get::<&ItemEntity>(e).is_ok() {
+                            "item"
+                        } else if let Ok(mob) = world.get::<&MobEntity>(e) {
+                            let mob_type = pickaxe_data::mob_type_name(mob.mob_type).unwrap_or("unknown");
+                            if filter.as_deref().is_some_and(|f| f != "mob" && f != mob_type) {
+                                continue;
+                            }
+                            "mob"
+                        } else if world.get::<&DisplayEntity>(e).is_ok() {
+                            "display"
+                        } else {
+                            continue;
+                        };
+                        if kind != "mob" && filter.as_deref().is_some_and(|f| f != kind) {
+                            continue;
+                        }
+                        found.push((distance, e));
+                    }
+                    found.sort_by(|a, b| a.0.total_cmp(&b.0));
+                    let result = lua.create_table()?;
+                    for (i, (distance, e)) in found.into_iter().enumerate() {
+                        let table = lua_entity_table(lua, world, e)?;
+                        table.set("distance", distance)?;
+                        result.set(i + 1, table)?;
+                    }
+                    Ok(result)
+                })?
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.entities.set_ai(entity_id: integer, handlers: { on_tick?: function, on_target?: function, on_hurt?: function }) -> boolean",
+        "Hand a mob to Lua: its built-in wandering, targeting and attacks stop.\non_tick runs every tick; on_target when a player comes within 16 blocks or hurts it, and on_hurt before damage. Both may return \"cancel\".",
+    );
+    entities_table
+        .set(
+            "set_ai",
+            lua.create_function(|lua, (entity_id, handlers): (i32, mlua::Table)| {
+                let key = |name: &str| -> mlua::Result<Option<mlua::RegistryKey>> {
+                    handlers.get::<Option<mlua::Function>>(name)?.map(|f| lua.create_registry_value(f)).transpose()
+                };
+                let ai = LuaMobAi {
+                    on_tick: key("on_tick")?,
+                    on_target: key("on_target")?,
+                    on_hurt: key("on_hurt")?,
+                    ..Default::default()
+                };
+                with_game(lua, |world, ws| {
+                    let Some(entity) = find_entity_by_id(world, entity_id) else {
+                        return false;
+                    };
+                    let Ok(mut mob) = world.get::<&mut MobEntity>(entity) else {
+                        return false;
+                    };
+                    mob.target = None;
+                    mob.ai_state = MobAiState::Idle;
+                    mob.fuse_timer = -1;
+                    ws.mob_ai.insert(entity, ai);
+                    true
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(lua, "pickaxe.entities.clear_ai(entity_id: integer) -> boolean", "Give a mob back to its built-in AI.");
+    entities_table
+        .set(
+            "clear_ai",
+            lua.create_function(|lua, entity_id: i32| {
+                with_game(lua, |world, ws| {
+                    find_entity_by_id(world, entity_id).is_some_and(|entity| ws.mob_ai.remove(&entity).is_some())
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.entities.move_to(entity_id: integer, x: number, y: number, z: number, speed?: number) -> boolean",
+        "Walk a Lua-controlled mob toward a point; `speed` multiplies its normal speed.",
+    );
+    entities_table
+        .set(
+            "move_to",
+            lua.create_function(|lua, (entity_id, x, y, z, speed): (i32, f64, f64, f64, Option<f64>)| {
+                with_game(lua, |world, ws| match find_entity_by_id(world, entity_id).and_then(|e| ws.mob_ai.get_mut(&e)) {
+                    Some(ai) => {
+                        ai.move_target = Some((Vec3d::new(x, y, z), speed.unwrap_or(1.0).clamp(0.0, 4.0)));
+                        true
+                    }
+                    None => false,
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(lua, "pickaxe.entities.stop(entity_id: integer) -> boolean", "Stop a Lua-controlled mob's movement.");
+    entities_table
         .set(
-            "register",
-            lua.create_function(move |lua, (name, handler): (String, mlua::Function)| {
-                let key = lua
-                    .create_registry_value(handler)
-                    .map_err(|e| mlua::Error::runtime(format!("Failed to store handler: {}", e)))?;
-                let mut cmds = lua_commands
-                    .lock()
-                    .map_err(|e| mlua::Error::runtime(format!("Lock poisoned: {}", e)))?;
-                cmds.push(LuaCommand {
-                    name: name.clone(),
-                    handler_key: key,
-                });
-                Ok(())
+            "stop",
+            lua.create_function(|lua, entity_id: i32| {
+                with_game(lua, |world, ws| match find_entity_by_id(world, entity_id).and_then(|e| ws.mob_ai.get_mut(&e)) {
+                    Some(ai) => {
+                        ai.move_target = None;
+                        true
+                    }
+                    None => false,
+                })
             })
             .map_err(lua_err)?,
         )
         .map_err(lua_err)?;
 
-    pickaxe.set("commands", commands_table).map_err(lua_err)?;
-    Ok(())
-}
+    document(
+        lua,
+        "pickaxe.entities.look_at(entity_id: integer, x: number, y: number, z: number) -> boolean",
+        "Turn a mob to face a point.",
+    );
+    entities_table
+        .set(
+            "look_at",
+            lua.create_function(|lua, (entity_id, x, y, z): (i32, f64, f64, f64)| {
+                with_world(lua, |world| {
+                    let Some(entity) = find_entity_by_id(world, entity_id) else {
+                        return false;
+                    };
+                    let Ok(pos) = world.get::<&Position>(entity).map(|p| p.0) else {
+                        return false;
+                    };
+                    let Ok(mut rot) = world.get::<&mut Rotation>(entity) else {
+                        return false;
+                    };
+                    let (dx, dy, dz) = (x - pos.x, y - (pos.y + 1.5), z - pos.z);
+                    rot.yaw = (-dx.atan2(dz)).to_degrees() as f32;
+                    rot.pitch = (-dy.atan2((dx * dx + dz * dz).sqrt())).to_degrees() as f32;
+                    true
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
 
-// ── Blocks API ────────────────────────────────────────────────────────
+    document(
+        lua,
+        "pickaxe.entities.attack(entity_id: integer, target_id: integer, damage?: number) -> boolean",
+        "Queue a melee hit from a Lua-controlled mob, dealt this tick if the target is within reach and the mob's attack cooldown has run out.",
+    );
+    entities_table
+        .set(
+            "attack",
+            lua.create_function(|lua, (entity_id, target_id, damage): (i32, i32, Option<f32>)| {
+                with_game(lua, |world, ws| {
+                    let (Some(entity), Some(target)) = (find_entity_by_id(world, entity_id), find_entity_by_id(world, target_id)) else {
+                        return false;
+                    };
+                    let Ok(mob) = world.get::<&MobEntity>(entity) else {
+                        return false;
+                    };
+                    let (Ok(pos), Ok(target_pos)) = (world.get::<&Position>(entity), world.get::<&Position>(target)) else {
+                        return false;
+                    };
+                    let dist = ((target_pos.0.x - pos.0.x).powi(2)
+                        + (target_pos.0.y - pos.0.y).powi(2)
+                        + (target_pos.0.z - pos.0.z).powi(2))
+                    .sqrt();
+                    if entity == target || mob.attack_cooldown > 0 || dist > crate::tick::MOB_MELEE_REACH {
+                        return false;
+                    }
+                    let Some(ai) = ws.mob_ai.get_mut(&entity) else {
+                        return false;
+                    };
+                    ai.pending_attack = Some((target, damage.unwrap_or_else(|| pickaxe_data::mob_attack_damage(mob.mob_type))));
+                    true
+                })
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
 
-/// Register `pickaxe.blocks` API on the Lua VM.
-pub fn register_blocks_api(lua: &Lua, overrides: BlockOverrides) -> anyhow::Result<()> {
-    let pickaxe: mlua::Table = lua.globals().get("pickaxe").map_err(lua_err)?;
-    let blocks_table = lua.create_table().map_err(lua_err)?;
+    document(
+        lua,
+        "pickaxe.entities.spawn_text_display(pos: {x: number, y: number, z: number}, text: string|table, opts?: { billboard?: string, scale?: number, glowing?: boolean, glow_color?: integer, yaw?: number, pitch?: number, line_width?: integer, background?: integer, shadow?: boolean, see_through?: boolean }) -> integer",
+        "Spawn floating text, for holograms and labels, and return its entity id. `text` is a `&`-coded string or a component.\n`billboard` is \"fixed\" (default), \"vertical\", \"horizontal\" or \"center\" (always faces the viewer); `background` is ARGB.",
+    );
+    entities_table
+        .set(
+            "spawn_text_display",
+            lua.create_function(|lua, (pos, text, opts): (mlua::Table, mlua::Value, Option<mlua::Table>)| {
+                let opts = match opts {
+                    Some(opts) => opts,
+                    None => lua.create_table()?,
+                };
+                let content = DisplayContent::Text {
+                    text: lua_display_text(lua, text)?,
+                    line_width: opts.get::<Option<i32>>("line_width")?.unwrap_or(200),
+                    background: opts.get::<Option<i64>>("background")?.map(|c| c as i32),
+                    shadow: opts.get::<Option<bool>>("shadow")?.unwrap_or(false),
+                    see_through: opts.get::<Option<bool>>("see_through")?.unwrap_or(false),
+                };
+                spawn_display(lua, &pos, content, &opts)
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
 
-    // pickaxe.blocks.register(name, props)
-    // props = { hardness = 1.5, drops = {"cobblestone"}, harvest_tools = {"wooden_pickaxe", ...} }
-    let overrides_clone = overrides.clone();
-    blocks_table
+    document(
+        lua,
+        "pickaxe.entities.spawn_item_display(pos: {x: number, y: number, z: number}, item: string|table, opts?: table) -> integer",
+        "Spawn a floating item and return its entity id. Takes the same `opts` as `spawn_text_display`, minus the text ones.",
+    );
+    entities_table
         .set(
-            "register",
-            lua.create_function(move |_lua, (name, props): (String, mlua::Table)| {
-                let hardness: Option<f64> = props.get("hardness").unwrap_or(None);
+            "spawn_item_display",
+            lua.create_function(|lua, (pos, item, opts): (mlua::Table, mlua::Value, Option<mlua::Table>)| {
+                let opts = match opts {
+                    Some(opts) => opts,
+                    None => lua.create_table()?,
+                };
+                let item = lua_item_stack(lua, item)?.ok_or_else(|| mlua::Error::runtime("item is required"))?;
+                spawn_display(lua, &pos, DisplayContent::Item(item), &opts)
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
 
-                let drops: Option<Vec<i32>> = match props.get::<Option<mlua::Table>>("drops") {
-                    Ok(Some(tbl)) => {
-                        let mut ids = Vec::new();
-                        for pair in tbl.sequence_values::<String>() {
-                            if let Ok(item_name) = pair {
-                                let clean = item_name
-                                    .strip_prefix("minecraft:")
-                                    .unwrap_or(&item_name)
-                                    .to_string();
-                                if let Some(id) = pickaxe_data::item_name_to_id(&clean) {
-                                    ids.push(id);
-                                }
-                            }
-                        }
-                        Some(ids)
+    document(
+        lua,
+        "pickaxe.entities.spawn_block_display(pos: {x: number, y: number, z: number}, block: string|integer, opts?: table) -> integer",
+        "Spawn a block-shaped display and return its entity id. `block` is a block name (default state) or a state id.",
+    );
+    entities_table
+        .set(
+            "spawn_block_display",
+            lua.create_function(|lua, (pos, block, opts): (mlua::Table, mlua::Value, Option<mlua::Table>)| {
+                let opts = match opts {
+                    Some(opts) => opts,
+                    None => lua.create_table()?,
+                };
+                let state = match block {
+                    mlua::Value::Integer(state) => state as i32,
+                    mlua::Value::String(name) => {
+                        let name = name.to_str()?.to_string();
+                        let name = name.strip_prefix("minecraft:").unwrap_or(&name);
+                        pickaxe_data::block_name_to_default_state(name)
+                            .ok_or_else(|| mlua::Error::runtime(format!("Unknown block '{}'", name)))?
+                    }
+                    other => {
+                        return Err(mlua::Error::runtime(format!(
+                            "expected block name or state id, got {}",
+                            other.type_name()
+                        )))
                     }
-                    _ => None,
                 };
+                spawn_display(lua, &pos, DisplayContent::Block(state), &opts)
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
 
-                let harvest_tools: Option<Vec<i32>> =
-                    match props.get::<Option<mlua::Table>>("harvest_tools") {
-                        Ok(Some(tbl)) => {
-                            let mut ids = Vec::new();
-                            for pair in tbl.sequence_values::<String>() {
-                                if let Ok(tool_name) = pair {
-                                    let clean = tool_name
-                                        .strip_prefix("minecraft:")
-                                        .unwrap_or(&tool_name)
-                                        .to_string();
-                                    if let Some(id) = pickaxe_data::item_name_to_id(&clean) {
-                                        ids.push(id);
-                                    }
-                                }
-                            }
-                            Some(ids)
-                        }
-                        _ => None,
+    document(
+        lua,
+        "pickaxe.entities.set_text(entity_id: integer, text: string|table) -> boolean",
+        "Change a text display's text.",
+    );
+    entities_table
+        .set(
+            "set_text",
+            lua.create_function(|lua, (entity_id, text): (i32, mlua::Value)| {
+                let new_text = lua_display_text(lua, text)?;
+                with_world(lua, |world| {
+                    let Some(entity) = find_entity_by_id(world, entity_id) else {
+                        return false;
                     };
-
-                let mut map = overrides_clone
-                    .lock()
-                    .map_err(|e| mlua::Error::runtime(format!("Lock poisoned: {}", e)))?;
-                map.insert(
-                    name,
-                    BlockOverride {
-                        hardness,
-                        drops,
-                        harvest_tools,
-                    },
-                );
-                Ok(())
+                    let Ok(mut display) = world.get::<&mut DisplayEntity>(entity) else {
+                        return false;
+                    };
+                    let DisplayContent::Text { text, .. } = &mut display.content else {
+                        return false;
+                    };
+                    *text = new_text;
+                    let metadata = pickaxe_protocol_v1_21::build_text_display_text_metadata(text);
+                    drop(display);
+                    crate::tick::broadcast_to_all(world, &InternalPacket::SetEntityMetadata { entity_id, metadata });
+                    true
+                })
             })
             .map_err(lua_err)?,
         )
         .map_err(lua_err)?;
 
-    // pickaxe.blocks.get_hardness(name) -> number or nil
-    let overrides_clone = overrides.clone();
-    blocks_table
+    document(
+        lua,
+        "pickaxe.entities.set_custom_name(entity_id: integer, name?: string|table, visible?: boolean) -> boolean",
+        "Set or clear (nil) the name shown above a non-player entity. `visible` (default true) shows it even when not looked at.",
+    );
+    entities_table
         .set(
-            "get_hardness",
-            lua.create_function(move |_lua, name: String| {
-                let map = overrides_clone
-                    .lock()
-                    .map_err(|e| mlua::Error::runtime(format!("Lock poisoned: {}", e)))?;
-                Ok(map.get(&name).and_then(|o| o.hardness))
+            "set_custom_name",
+            lua.create_function(|lua, (entity_id, name, visible): (i32, mlua::Value, Option<bool>)| {
+                let name = match name {
+                    mlua::Value::Nil => None,
+                    other => Some(lua_display_text(lua, other)?),
+                };
+                let visible = visible.unwrap_or(true);
+                with_world(lua, |world| {
+                    let Some(entity) = find_non_player(world, entity_id) else {
+                        return false;
+                    };
+                    let metadata = match name {
+                        Some(text) => {
+                            let metadata = pickaxe_protocol_v1_21::build_custom_name_metadata(&text, visible);
+                            let _ = world.insert_one(entity, CustomName { text, visible });
+                            metadata
+                        }
+                        None => {
+                            let _ = world.remove_one::<CustomName>(entity);
+                            vec![
+                                pickaxe_protocol_core::EntityMetadataEntry {
+                                    index: 2,
+                                    type_id: 6,
+                                    data: vec![0], // present = false
+                                },
+                                pickaxe_protocol_core::EntityMetadataEntry {
+                                    index: 3,
+                                    type_id: 8,
+                                    data: vec![0],
+                                },
+                            ]
+                        }
+                    };
+                    crate::tick::broadcast_to_all(world, &InternalPacket::SetEntityMetadata { entity_id, metadata });
+                    true
+                })
             })
             .map_err(lua_err)?,
         )
         .map_err(lua_err)?;
 
-    // pickaxe.blocks.get_drops(name) -> {item_name, ...} or nil
-    let overrides_clone = overrides.clone();
-    blocks_table
+    document(
+        lua,
+        "pickaxe.entities.set_glowing(entity_id: integer, glowing: boolean) -> boolean",
+        "Turn the glowing outline on or off for a non-player entity.",
+    );
+    entities_table
         .set(
-            "get_drops",
-            lua.create_function(move |_lua, name: String| {
-                let map = overrides_clone
-                    .lock()
-                    .map_err(|e| mlua::Error::runtime(format!("Lock poisoned: {}", e)))?;
-                Ok(map.get(&name).and_then(|o| {
-                    o.drops.as_ref().map(|ids| {
-                        ids.iter()
-                            .filter_map(|id| pickaxe_data::item_id_to_name(*id))
-                            .map(|s| s.to_string())
-                            .collect::<Vec<String>>()
-                    })
-                }))
+            "set_glowing",
+            lua.create_function(|lua, (entity_id, glowing): (i32, bool)| {
+                with_world(lua, |world| {
+                    let Some(entity) = find_non_player(world, entity_id) else {
+                        return false;
+                    };
+                    if glowing {
+                        let _ = world.insert_one(entity, Glowing);
+                    } else {
+                        let _ = world.remove_one::<Glowing>(entity);
+                    }
+                    let metadata = pickaxe_protocol_v1_21::build_entity_flags_metadata(if glowing { 0x40 } else { 0 });
+                    crate::tick::broadcast_to_all(world, &InternalPacket::SetEntityMetadata { entity_id, metadata });
+                    true
+                })
             })
             .map_err(lua_err)?,
         )
         .map_err(lua_err)?;
 
-    pickaxe.set("blocks", blocks_table).map_err(lua_err)?;
+    pickaxe.set("entities", entities_table).map_err(lua_err)?;
     Ok(())
 }
 
-// ── Entities API ──────────────────────────────────────────────────────
+// ── Placeholders API ─────────────────────────────────────────────────
 
-/// Helper context that also includes next_eid for entity spawning.
-pub struct LuaEntitiesContext {
-    pub next_eid: Arc<AtomicI32>,
+/// Shared storage for Lua placeholder callbacks, keyed by name.
+pub type LuaPlaceholders = Arc<Mutex<HashMap<String, mlua::RegistryKey>>>;
+
+/// Value of a built-in placeholder. The `player_*` ones need a player.
+fn builtin_placeholder(world: &World, ws: &crate::tick::WorldState, player: Option<hecs::Entity>, name: &str) -> Option<String> {
+    match name {
+        "online" => return Some(world.query::<&Profile>().iter().count().to_string()),
+        "tps" => return Some(format!("{:.1}", ws.tick_stats.tps())),
+        "mspt" => return Some(format!("{:.1}", ws.tick_stats.average_mspt())),
+        "load" => return Some(ws.load.level().name().to_string()),
+        "world_time" => return Some(ws.time_of_day.to_string()),
+        _ => {}
+    }
+    let player = player?;
+    match name {
+        "player_name" => world.get::<&Profile>(player).ok().map(|p| p.0.name.clone()),
+        "player_health" => world.get::<&Health>(player).ok().map(|h| format!("{:.1}", h.current)),
+        "player_max_health" => world.get::<&Health>(player).ok().map(|h| format!("{:.1}", h.max)),
+        "player_food" => world.get::<&FoodData>(player).ok().map(|f| f.food_level.to_string()),
+        "player_level" => world.get::<&ExperienceData>(player).ok().map(|xp| xp.level.to_string()),
+        "player_ping" => world.get::<&KeepAlive>(player).ok().map(|ka| ka.latency_ms.to_string()),
+        _ => None,
+    }
 }
 
-/// Register `pickaxe.entities` API on the Lua VM.
-pub fn register_entities_api(lua: &Lua, next_eid: Arc<AtomicI32>) -> anyhow::Result<()> {
+/// Resolve the `%name%` placeholders in `template` as seen by `player`, or
+/// for server-wide text when None. Mod placeholders take precedence over the
+/// built-in ones; unknown ones are left as written.
+pub fn format_placeholders(lua: &Lua, placeholders: &LuaPlaceholders, template: &str, player: Option<&str>) -> String {
+    let mut values = HashMap::new();
+    for name in crate::placeholders::names(template) {
+        let callback = placeholders
+            .lock()
+            .ok()
+            .and_then(|p| p.get(name).and_then(|key| lua.registry_value::<mlua::Function>(key).ok()));
+        let value = match callback {
+            Some(callback) => match sandbox::call::<mlua::Value>(lua, &callback, player) {
+                Ok(mlua::Value::Nil) => None,
+                Ok(value) => value.to_string().ok(),
+                Err(e) => {
+                    tracing::warn!("Lua placeholder %{}% error: {}", name, e);
+                    None
+                }
+            },
+            None => with_game(lua, |world, ws| {
+                let entity = player.and_then(|p| find_player_by_name(world, p));
+                builtin_placeholder(world, ws, entity, name)
+            })
+            .ok()
+            .flatten(),
+        };
+        if let Some(value) = value {
+            values.insert(name, value);
+        }
+    }
+    crate::placeholders::fill(template, &values)
+}
+
+/// Register `pickaxe.placeholders` API on the Lua VM.
+pub fn register_placeholders_api(lua: &Lua, placeholders: LuaPlaceholders) -> anyhow::Result<()> {
     let pickaxe: mlua::Table = lua.globals().get("pickaxe").map_err(lua_err)?;
-    let entities_table = lua.create_table().map_err(lua_err)?;
+    let placeholders_table = lua.create_table().map_err(lua_err)?;
 
-    // Store next_eid in app data for entity spawning
-    lua.set_app_data(LuaEntitiesContext {
-        next_eid: next_eid.clone(),
-    });
+    document(
+        lua,
+        "pickaxe.placeholders.register(name: string, callback: fun(player: string?): any)",
+        "Provide `%name%` in chat formats, the tab list, the MOTD and other server text.\n`callback` gets the viewing player's name, or nil for server-wide text; returning nil leaves the placeholder as written.",
+    );
+    let placeholders_clone = placeholders.clone();
+    placeholders_table
+        .set(
+            "register",
+            lua.create_function(move |lua, (name, callback): (String, mlua::Function)| {
+                if !crate::placeholders::valid_name(&name) {
+                    return Err(mlua::Error::runtime(format!(
+                        "Invalid placeholder name '{}' (use lowercase letters, digits and _)",
+                        name
+                    )));
+                }
+                let key = lua.create_registry_value(callback)?;
+                placeholders_clone
+                    .lock()
+                    .map_err(|e| mlua::Error::runtime(format!("Lock poisoned: {}", e)))?
+                    .insert(name, key);
+                Ok(())
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
 
-    // pickaxe.entities.spawn_item(x, y, z, item_name, count) -> entity_id or nil
-    entities_table
+    document(
+        lua,
+        "pickaxe.placeholders.format(template: string, player?: string) -> string",
+        "Resolve `%name%` placeholders in a template as seen by a player. Built in: online, tps, mspt, load, world_time, and player_name, player_health, player_max_health, player_food, player_level, player_ping.",
+    );
+    placeholders_table
         .set(
-            "spawn_item",
-            lua.create_function(
-                |lua, (x, y, z, item_name, count): (f64, f64, f64, String, Option<i8>)| {
-                    let item_name = item_name
-                        .strip_prefix("minecraft:")
-                        .unwrap_or(&item_name)
-                        .to_string();
-                    let item_id = match pickaxe_data::item_name_to_id(&item_name) {
-                        Some(id) => id,
-                        None => return Ok(mlua::Value::Nil),
-                    };
-                    let count = count.unwrap_or(1).max(1);
+            "format",
+            lua.create_function(move |lua, (template, player): (String, Option<String>)| {
+                Ok(format_placeholders(lua, &placeholders, &template, player.as_deref()))
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
 
-                    let next_eid = lua
-                        .app_data_ref::<LuaEntitiesContext>()
-                        .ok_or_else(|| mlua::Error::runtime("Entities context not available"))?
-                        .next_eid
-                        .clone();
+    pickaxe.set("placeholders", placeholders_table).map_err(lua_err)?;
+    Ok(())
+}
 
-                    let ctx = get_context(lua)?;
-                    let world = unsafe { &mut *(ctx.world_ptr as *mut World) };
-                    let ws = unsafe { &mut *(ctx.world_state_ptr as *mut crate::tick::WorldState) };
+// ── Storage API ──────────────────────────────────────────────────────
 
-                    // We need a scripting reference, but we can't get it from here easily.
-                    // Instead, directly spawn the entity without firing the Lua event
-                    // (the caller is already in Lua context).
-                    let eid = next_eid.fetch_add(1, Ordering::Relaxed);
-                    let uuid = uuid::Uuid::new_v4();
+/// Check a `pickaxe.storage` namespace, which becomes a file name.
+fn storage_namespace(namespace: &str) -> mlua::Result<()> {
+    if crate::storage::valid_namespace(namespace) {
+        Ok(())
+    } else {
+        Err(mlua::Error::runtime(format!(
+            "invalid storage namespace '{}' (use lowercase letters, digits, '_', '-' and '.')",
+            namespace
+        )))
+    }
+}
+
+fn lock_storage(storage: &SharedModStorage) -> mlua::Result<std::sync::MutexGuard<'_, ModStorage>> {
+    storage
+        .lock()
+        .map_err(|e| mlua::Error::runtime(format!("Lock poisoned: {}", e)))
+}
+
+/// Register `pickaxe.storage` API on the Lua VM. Unlike the game APIs it is
+/// usable while mods load, so settings can be read in init.lua.
+pub fn register_storage_api(lua: &Lua, storage: SharedModStorage) -> anyhow::Result<()> {
+    let pickaxe: mlua::Table = lua.globals().get("pickaxe").map_err(lua_err)?;
+    let storage_table = lua.create_table().map_err(lua_err)?;
 
-                    let mut rng = rand::thread_rng();
-                    let vx = rng.gen_range(-0.1..0.1);
-                    let vz = rng.gen_range(-0.1..0.1);
+    document(lua, "pickaxe.storage.get(namespace: string, key: string) -> any", "A stored value, or nil.");
+    let s = storage.clone();
+    storage_table
+        .set(
+            "get",
+            lua.create_function(move |lua, (namespace, key): (String, String)| {
+                storage_namespace(&namespace)?;
+                match lock_storage(&s)?.get(&namespace, &key) {
+                    Some(value) => lua.to_value(value),
+                    None => Ok(mlua::Value::Nil),
+                }
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
 
-                    world.spawn((
-                        EntityId(eid),
-                        EntityUuid(uuid),
-                        Position(Vec3d::new(x, y, z)),
-                        PreviousPosition(Vec3d::new(x, y, z)),
-                        Velocity(Vec3d::new(vx, 0.2, vz)),
-                        OnGround(false),
-                        ItemEntity {
-                            item: ItemStack::new(item_id, count),
-                            pickup_delay: 10,
-                            age: 0,
-                        },
-                        Rotation {
-                            yaw: 0.0,
-                            pitch: 0.0,
-                        },
-                    ));
+    document(
+        lua,
+        "pickaxe.storage.set(namespace: string, key: string, value: any)",
+        "Store a value: a boolean, number, string or table of those. nil deletes the key.",
+    );
+    let s = storage.clone();
+    storage_table
+        .set(
+            "set",
+            lua.create_function(move |lua, (namespace, key, value): (String, String, mlua::Value)| {
+                storage_namespace(&namespace)?;
+                if value.is_nil() {
+                    lock_storage(&s)?.delete(&namespace, &key);
+                    return Ok(());
+                }
+                let value: serde_json::Value = lua.from_value(value)?;
+                lock_storage(&s)?.set(&namespace, &key, value);
+                Ok(())
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
 
-                    let _ = ws; // touched for consistency
-                    Ok(mlua::Value::Integer(eid as i64))
-                },
-            )
+    document(
+        lua,
+        "pickaxe.storage.delete(namespace: string, key: string) -> boolean",
+        "Delete a key; true if it existed.",
+    );
+    let s = storage.clone();
+    storage_table
+        .set(
+            "delete",
+            lua.create_function(move |_, (namespace, key): (String, String)| {
+                storage_namespace(&namespace)?;
+                Ok(lock_storage(&s)?.delete(&namespace, &key))
+            })
             .map_err(lua_err)?,
         )
         .map_err(lua_err)?;
 
-    // pickaxe.entities.spawn_mob(x, y, z, mob_type_name) -> entity_id or nil
-    entities_table
+    document(lua, "pickaxe.storage.keys(namespace: string) -> string[]", "Keys in a namespace, sorted.");
+    storage_table
         .set(
-            "spawn_mob",
-            lua.create_function(
-                |lua, (x, y, z, mob_name): (f64, f64, f64, String)| {
-                    let mob_name = mob_name
-                        .strip_prefix("minecraft:")
-                        .unwrap_or(&mob_name)
-                        .to_string();
-                    let mob_type = match pickaxe_data::mob_name_to_type(&mob_name) {
-                        Some(id) => id,
-                        None => return Ok(mlua::Value::Nil),
-                    };
+            "keys",
+            lua.create_function(move |_, namespace: String| {
+                storage_namespace(&namespace)?;
+                Ok(lock_storage(&storage)?.keys(&namespace))
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
 
-                    let next_eid = lua
-                        .app_data_ref::<LuaEntitiesContext>()
-                        .ok_or_else(|| mlua::Error::runtime("Entities context not available"))?
-                        .next_eid
-                        .clone();
+    pickaxe.set("storage", storage_table).map_err(lua_err)?;
+    Ok(())
+}
 
-                    let ctx = get_context(lua)?;
-                    let world = unsafe { &mut *(ctx.world_ptr as *mut World) };
+// ── HTTP API ─────────────────────────────────────────────────────────
 
-                    let eid = next_eid.fetch_add(1, Ordering::Relaxed);
-                    let max_hp = pickaxe_data::mob_max_health(mob_type);
-                    let yaw: f32 = rand::thread_rng().gen_range(0.0..360.0);
+/// Build `pickaxe.http.get` or `pickaxe.http.post`.
+fn http_request_fn(lua: &Lua, http: SharedHttpClient, post: bool) -> mlua::Result<mlua::Function> {
+    lua.create_function(
+        move |lua, (url, opts, callback): (String, mlua::Value, Option<mlua::Function>)| {
+            // The options table may be left out: (url, callback)
+            let (opts, callback) = match (opts, callback) {
+                (mlua::Value::Function(f), None) => (None, f),
+                (mlua::Value::Table(t), Some(f)) => (Some(t), f),
+                (mlua::Value::Nil, Some(f)) => (None, f),
+                _ => return Err(mlua::Error::runtime("expected (url, [opts], callback)")),
+            };
+            let mut request = HttpRequest { post, url, headers: Vec::new(), body: None };
+            if let Some(opts) = opts {
+                if let Some(headers) = opts.get::<Option<mlua::Table>>("headers")? {
+                    for pair in headers.pairs::<String, String>() {
+                        request.headers.push(pair?);
+                    }
+                }
+                request.body = opts.get::<Option<String>>("body")?;
+                if let Some(json) = opts.get::<Option<mlua::Value>>("json")? {
+                    let json: serde_json::Value = lua.from_value(json)?;
+                    request.body = Some(json.to_string());
+                    if !request.headers.iter().any(|(name, _)| name.eq_ignore_ascii_case("content-type")) {
+                        request.headers.push(("Content-Type".into(), "application/json".into()));
+                    }
+                }
+            }
+            let key = lua.create_registry_value(callback)?;
+            let sent = http
+                .lock()
+                .map_err(|e| mlua::Error::runtime(format!("Lock poisoned: {}", e)))?
+                .send(request, key);
+            Ok(match sent {
+                Ok(id) => (Some(id), None),
+                Err(e) => (None, Some(e)),
+            })
+        },
+    )
+}
 
-                    world.spawn((
-                        EntityId(eid),
-                        EntityUuid(uuid::Uuid::new_v4()),
-                        Position(Vec3d::new(x, y, z)),
-                        PreviousPosition(Vec3d::new(x, y, z)),
-                        Rotation { yaw, pitch: 0.0 },
-                        PreviousRotation { yaw, pitch: 0.0 },
-                        OnGround(true),
-                        Velocity(Vec3d::new(0.0, 0.0, 0.0)),
-                        MobEntity {
-                            mob_type,
-                            health: max_hp,
-                            max_health: max_hp,
-                            target: None,
-                            ai_state: MobAiState::Idle,
-                            ai_timer: rand::thread_rng().gen_range(20..100),
-                            ambient_sound_timer: rand::thread_rng().gen_range(100..300),
-                            no_damage_ticks: 0,
-                            fuse_timer: -1,
-                            attack_cooldown: 0,
-                        },
-                    ));
+/// Call a `pickaxe.http` callback with the response table: `ok`, `status`,
+/// `headers`, `body` and, for JSON responses, the decoded `json`; or `ok =
+/// false` and `error`.
+pub fn call_http_callback(lua: &Lua, callback: mlua::Function, result: HttpResult) {
+    let call = (|| {
+        let response = lua.create_table()?;
+        match result {
+            Ok(r) => {
+                response.set("ok", true)?;
+                response.set("status", r.status)?;
+                let is_json = r.headers.iter().any(|(name, value)| name == "content-type" && value.contains("json"));
+                let headers = lua.create_table()?;
+                for (name, value) in r.headers {
+                    headers.set(name, value)?;
+                }
+                response.set("headers", headers)?;
+                if is_json {
+                    if let Ok(json) = serde_json::from_str::<serde_json::Value>(&r.body) {
+                        response.set("json", lua.to_value(&json)?)?;
+                    }
+                }
+                response.set("body", r.body)?;
+            }
+            Err(e) => {
+                response.set("ok", false)?;
+                response.set("error", e)?;
+            }
+        }
+        sandbox::call::<()>(lua, &callback, response)
+    })();
+    if let Err(e) = call {
+        tracing::warn!("Lua HTTP callback error: {}", e);
+    }
+}
 
-                    Ok(mlua::Value::Integer(eid as i64))
-                },
-            )
+/// Register `pickaxe.http` API on the Lua VM. Requests are checked against
+/// `[http]` in server.toml and run in the background; callbacks run on a
+/// later tick with the game APIs available.
+pub fn register_http_api(lua: &Lua, http: SharedHttpClient) -> anyhow::Result<()> {
+    let pickaxe: mlua::Table = lua.globals().get("pickaxe").map_err(lua_err)?;
+    let http_table = lua.create_table().map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.http.get(url: string, opts?: { headers?: table<string, string> }, callback: fun(response: table)) -> integer?, string?",
+        "Fetch a URL in the background; `callback` gets `{ok, status, headers, body, json?}` or `{ok = false, error}`.\nReturns the request id, or nil and an error message.",
+    );
+    document(
+        lua,
+        "pickaxe.http.post(url: string, opts?: { headers?: table<string, string>, body?: string, json?: table }, callback: fun(response: table)) -> integer?, string?",
+        "POST to a URL in the background, as `http.get`. `json` is encoded as the body.",
+    );
+    http_table
+        .set("get", http_request_fn(lua, http.clone(), false).map_err(lua_err)?)
+        .map_err(lua_err)?;
+    http_table
+        .set("post", http_request_fn(lua, http, true).map_err(lua_err)?)
+        .map_err(lua_err)?;
+
+    pickaxe.set("http", http_table).map_err(lua_err)?;
+    Ok(())
+}
+
+// ── Channels API ─────────────────────────────────────────────────────
+
+/// Plugin channel handlers registered by mods, keyed by channel.
+pub type LuaChannels = Arc<Mutex<HashMap<String, mlua::RegistryKey>>>;
+
+/// Register `pickaxe.channels` API on the Lua VM.
+pub fn register_channels_api(lua: &Lua, channels: LuaChannels) -> anyhow::Result<()> {
+    let pickaxe: mlua::Table = lua.globals().get("pickaxe").map_err(lua_err)?;
+    let channels_table = lua.create_table().map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.channels.register(channel: string, handler: fun(player: string, data: string))",
+        "Receive plugin messages on a `namespace:path` channel. `data` is the raw payload.\nJoining clients are told the server listens on every registered channel.",
+    );
+    channels_table
+        .set(
+            "register",
+            lua.create_function(move |lua, (channel, handler): (String, mlua::Function)| {
+                if !crate::channels::valid_channel(&channel) {
+                    return Err(mlua::Error::runtime(format!(
+                        "Invalid channel '{}' (use namespace:path in lowercase, not minecraft:)",
+                        channel
+                    )));
+                }
+                let key = lua.create_registry_value(handler)?;
+                channels
+                    .lock()
+                    .map_err(|e| mlua::Error::runtime(format!("Lock poisoned: {}", e)))?
+                    .insert(channel, key);
+                Ok(())
+            })
             .map_err(lua_err)?,
         )
         .map_err(lua_err)?;
 
-    // pickaxe.entities.remove(entity_id) -> bool
-    entities_table
+    document(
+        lua,
+        "pickaxe.channels.send(player: string, channel: string, data: string) -> boolean",
+        "Send a plugin message with a raw payload. False if the player is offline.",
+    );
+    channels_table
         .set(
-            "remove",
-            lua.create_function(|lua, entity_id: i32| {
+            "send",
+            lua.create_function(|lua, (name, channel, data): (String, String, mlua::String)| {
+                let data = data.as_bytes().to_vec();
                 with_world(lua, |world| {
-                    let mut found = None;
-                    for (e, eid) in world.query::<&EntityId>().iter() {
-                        if eid.0 == entity_id {
-                            // Only remove non-player entities
-                            if world.get::<&Profile>(e).is_err() {
-                                found = Some(e);
-                            }
-                            break;
-                        }
-                    }
-                    if let Some(entity) = found {
-                        // Remove from tracked entities
-                        for (_e, tracked) in world.query::<&mut TrackedEntities>().iter() {
-                            tracked.visible.remove(&entity_id);
-                        }
-                        // Broadcast removal
-                        for (_e, sender) in world.query::<&ConnectionSender>().iter() {
-                            let _ = sender.0.send(InternalPacket::RemoveEntities {
-                                entity_ids: vec![entity_id],
-                            });
-                        }
-                        let _ = world.despawn(entity);
-                        true
-                    } else {
-                        false
+                    let Some(entity) = find_player_by_name(world, &name) else {
+                        return false;
+                    };
+                    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+                        let _ = sender.0.send(InternalPacket::PluginMessage { channel, data });
                     }
+                    true
                 })
             })
             .map_err(lua_err)?,
         )
         .map_err(lua_err)?;
 
-    // pickaxe.entities.get(entity_id) -> table or nil
-    entities_table
+    document(
+        lua,
+        "pickaxe.channels.has(player: string, channel: string) -> boolean",
+        "Whether a player's client registered a channel, i.e. has the mod that uses it.",
+    );
+    channels_table
         .set(
-            "get",
-            lua.create_function(|lua, entity_id: i32| {
-                with_world(lua, |world| -> Option<mlua::Value> {
-                    for (e, eid) in world.query::<&EntityId>().iter() {
-                        if eid.0 == entity_id {
-                            let table = lua.create_table().ok()?;
-                            let _ = table.set("id", entity_id);
-
-                            if let Ok(pos) = world.get::<&Position>(e) {
-                                let _ = table.set("x", pos.0.x);
-                                let _ = table.set("y", pos.0.y);
-                                let _ = table.set("z", pos.0.z);
-                            }
-
-                            if world.get::<&Profile>(e).is_ok() {
-                                let _ = table.set("type", "player");
-                            } else if let Ok(item_ent) = world.get::<&ItemEntity>(e) {
-                                let _ = table.set("type", "item");
-                                let _ = table.set("item_id", item_ent.item.item_id);
-                                let _ = table.set("item_count", item_ent.item.count);
-                                let item_name =
-                                    pickaxe_data::item_id_to_name(item_ent.item.item_id)
-                                        .unwrap_or("unknown");
-                                let _ = table.set("item_name", item_name);
-                                let _ = table.set("age", item_ent.age);
-                            } else if let Ok(mob) = world.get::<&MobEntity>(e) {
-                                let _ = table.set("type", "mob");
-                                let mob_name = pickaxe_data::mob_type_name(mob.mob_type)
-                                    .unwrap_or("unknown");
-                                let _ = table.set("mob_type", mob_name);
-                                let _ = table.set("health", mob.health);
-                                let _ = table.set("max_health", mob.max_health);
-                            }
-
-                            return Some(mlua::Value::Table(table));
-                        }
-                    }
-                    None
+            "has",
+            lua.create_function(|lua, (name, channel): (String, String)| {
+                with_world(lua, |world| {
+                    find_player_by_name(world, &name)
+                        .and_then(|entity| world.get::<&ClientChannels>(entity).ok().map(|c| c.channels.contains(&channel)))
+                        .unwrap_or(false)
                 })
             })
             .map_err(lua_err)?,
         )
         .map_err(lua_err)?;
 
-    // pickaxe.entities.set_velocity(entity_id, vx, vy, vz) -> bool
-    entities_table
+    document(
+        lua,
+        "pickaxe.channels.brand(player: string) -> string?",
+        "The client's brand, such as `vanilla` or `fabric`.",
+    );
+    channels_table
         .set(
-            "set_velocity",
-            lua.create_function(|lua, (entity_id, vx, vy, vz): (i32, f64, f64, f64)| {
+            "brand",
+            lua.create_function(|lua, name: String| {
                 with_world(lua, |world| {
-                    for (e, eid) in world.query::<&EntityId>().iter() {
-                        if eid.0 == entity_id {
-                            if let Ok(mut vel) = world.get::<&mut Velocity>(e) {
-                                vel.0 = Vec3d::new(vx, vy, vz);
-                                return true;
-                            }
-                            break;
-                        }
-                    }
-                    false
+                    let entity = find_player_by_name(world, &name)?;
+                    let channels = world.get::<&ClientChannels>(entity).ok()?;
+                    channels.brand.clone()
                 })
             })
             .map_err(lua_err)?,
         )
         .map_err(lua_err)?;
 
-    // pickaxe.entities.list() -> table of entity tables (items + mobs)
-    entities_table
+    pickaxe.set("channels", channels_table).map_err(lua_err)?;
+    Ok(())
+}
+
+/// Register `pickaxe.worlds`: the server's worlds (levels) and moving players
+/// between them.
+pub fn register_worlds_api(lua: &Lua) -> anyhow::Result<()> {
+    let pickaxe: mlua::Table = lua.globals().get("pickaxe").map_err(lua_err)?;
+    let worlds_table = lua.create_table().map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.worlds.list() -> string[]",
+        "Names of the server's worlds, `minecraft:overworld` first.",
+    );
+    worlds_table
         .set(
             "list",
-            lua.create_function(|lua, ()| {
-                with_world(lua, |world| -> Vec<mlua::Value> {
-                    let mut result = Vec::new();
-                    for (_e, (eid, pos, item_ent)) in world
-                        .query::<(&EntityId, &Position, &ItemEntity)>()
-                        .iter()
-                    {
-                        if let Ok(table) = lua.create_table() {
-                            let _ = table.set("id", eid.0);
-                            let _ = table.set("type", "item");
-                            let _ = table.set("x", pos.0.x);
-                            let _ = table.set("y", pos.0.y);
-                            let _ = table.set("z", pos.0.z);
-                            let _ = table.set("item_id", item_ent.item.item_id);
-                            let _ = table.set("item_count", item_ent.item.count);
-                            let item_name =
-                                pickaxe_data::item_id_to_name(item_ent.item.item_id)
-                                    .unwrap_or("unknown");
-                            let _ = table.set("item_name", item_name);
-                            result.push(mlua::Value::Table(table));
-                        }
-                    }
-                    for (_e, (eid, pos, mob)) in world
-                        .query::<(&EntityId, &Position, &MobEntity)>()
+            lua.create_function(|lua, ()| with_world_state(lua, |ws| ws.level_names()))
+                .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.worlds.get(name: string) -> {name, dimension_type, generator, spawn, loaded_chunks, players}?",
+        "A world's settings and how busy it is; `spawn` is `{x, y, z}` and `players` a list of names. Nil for unknown worlds.",
+    );
+    worlds_table
+        .set(
+            "get",
+            lua.create_function(|lua, name: String| {
+                let name = crate::datapacks::qualify(&name);
+                let info = with_game(lua, |world, ws| {
+                    let level = ws.get_level(&name)?;
+                    let players: Vec<String> = world
+                        .query::<(&Profile, Option<&Dimension>)>()
                         .iter()
-                    {
-                        if let Ok(table) = lua.create_table() {
-                            let _ = table.set("id", eid.0);
-                            let _ = table.set("type", "mob");
-                            let _ = table.set("x", pos.0.x);
-                            let _ = table.set("y", pos.0.y);
-                            let _ = table.set("z", pos.0.z);
-                            let mob_name = pickaxe_data::mob_type_name(mob.mob_type)
-                                .unwrap_or("unknown");
-                            let _ = table.set("mob_type", mob_name);
-                            let _ = table.set("health", mob.health);
-                            result.push(mlua::Value::Table(table));
-                        }
-                    }
-                    result
+                        .filter(|(_, (_, dim))| dim.map_or(crate::tick::DEFAULT_LEVEL, |d| d.0.as_str()) == name)
+                        .map(|(_, (profile, _))| profile.0.name.clone())
+                        .collect();
+                    let dimension_type = ws
+                        .registries
+                        .dimension_types
+                        .get(level.dimension_type as usize)
+                        .map(|d| d.name.clone())
+                        .unwrap_or_default();
+                    Some((dimension_type, level.generator.name(), level.spawn, level.loaded_chunk_count(), players))
+                })?;
+                let Some((dimension_type, generator, spawn, loaded_chunks, players)) = info else {
+                    return Ok(None);
+                };
+                let table = lua.create_table()?;
+                table.set("name", name.as_str())?;
+                table.set("dimension_type", dimension_type)?;
+                table.set("generator", generator)?;
+                let spawn_table = lua.create_table()?;
+                spawn_table.set("x", spawn.x)?;
+                spawn_table.set("y", spawn.y)?;
+                spawn_table.set("z", spawn.z)?;
+                table.set("spawn", spawn_table)?;
+                table.set("loaded_chunks", loaded_chunks)?;
+                table.set("players", players)?;
+                Ok(Some(table))
+            })
+            .map_err(lua_err)?,
+        )
+        .map_err(lua_err)?;
+
+    document(
+        lua,
+        "pickaxe.worlds.teleport(player: string, name: string) -> boolean",
+        "Move an online player to a world's spawn. False if the player is offline or the world is unknown.",
+    );
+    worlds_table
+        .set(
+            "teleport",
+            lua.create_function(|lua, (player, name): (String, String)| {
+                let name = crate::datapacks::qualify(&name);
+                with_game(lua, |world, ws| {
+                    let Some(entity) = find_player_by_name(world, &player) else {
+                        return false;
+                    };
+                    crate::tick::move_to_level(world, ws, entity, &name).is_ok()
                 })
             })
             .map_err(lua_err)?,
         )
         .map_err(lua_err)?;
 
-    pickaxe.set("entities", entities_table).map_err(lua_err)?;
+    pickaxe.set("worlds", worlds_table).map_err(lua_err)?;
     Ok(())
 }
@@ -0,0 +1,71 @@
+use bytes::BytesMut;
+use pickaxe_protocol_core::{read_string, write_string};
+
+/// The client or server software name, shown on the F3 screen.
+pub const BRAND: &str = "minecraft:brand";
+/// Channels a side listens on, separated by NUL bytes.
+pub const REGISTER: &str = "minecraft:register";
+pub const UNREGISTER: &str = "minecraft:unregister";
+
+/// Sent as the server's brand.
+pub const SERVER_BRAND: &str = "Pickaxe";
+
+/// Payload of a brand message: a single protocol string.
+pub fn brand_payload(brand: &str) -> Vec<u8> {
+    let mut buf = BytesMut::new();
+    write_string(&mut buf, brand);
+    buf.to_vec()
+}
+
+pub fn read_brand(data: &[u8]) -> Option<String> {
+    read_string(&mut BytesMut::from(data), 32767).ok()
+}
+
+/// Channel names in a register or unregister payload.
+pub fn parse_channel_list(data: &[u8]) -> Vec<String> {
+    data.split(|&b| b == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+        .collect()
+}
+
+pub fn channel_list_payload<'a>(channels: impl IntoIterator<Item = &'a String>) -> Vec<u8> {
+    let names: Vec<&str> = channels.into_iter().map(String::as_str).collect();
+    names.join("\0").into_bytes()
+}
+
+/// A `namespace:path` identifier that isn't reserved by the protocol.
+pub fn valid_channel(channel: &str) -> bool {
+    let Some((namespace, path)) = channel.split_once(':') else {
+        return false;
+    };
+    let valid = |part: &str, extra: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "_-.".contains(c) || extra.contains(c))
+    };
+    namespace != "minecraft" && valid(namespace, "") && valid(path, "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payloads() {
+        assert_eq!(read_brand(&brand_payload("Pickaxe")).as_deref(), Some("Pickaxe"));
+        let channels = vec!["voicechat:request_secret".to_string(), "bungeecord:main".to_string()];
+        assert_eq!(parse_channel_list(&channel_list_payload(&channels)), channels);
+        assert!(parse_channel_list(b"").is_empty());
+    }
+
+    #[test]
+    fn test_valid_channel() {
+        assert!(valid_channel("voicechat:request_secret"));
+        assert!(valid_channel("my_mod:sync/v2"));
+        assert!(!valid_channel("minecraft:brand"));
+        assert!(!valid_channel("NoNamespace"));
+        assert!(!valid_channel("mod:"));
+    }
+}
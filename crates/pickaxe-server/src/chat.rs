@@ -0,0 +1,248 @@
+use anyhow::Result;
+use base64::Engine;
+use pickaxe_protocol_core::ChatSession;
+use rsa::pkcs8::DecodePublicKey;
+use rsa::{Pkcs1v15Sign, RsaPublicKey};
+use serde::Deserialize;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+const PUBLIC_KEYS_URL: &str = "https://api.minecraftservices.com/publickeys";
+
+/// Signatures a client can acknowledge with each message.
+pub const LAST_SEEN_WINDOW: usize = 20;
+
+/// Signed messages a client may leave unacknowledged before it is
+/// disconnected, as in vanilla.
+pub const MAX_PENDING: usize = 4096;
+
+/// Secure chat settings, fixed at startup.
+#[derive(Default)]
+pub struct SecureChat {
+    /// Relay signed chat and require players to have a valid chat session.
+    pub enabled: bool,
+    /// Mojang's keys for player certificates, which sign chat session keys.
+    pub mojang_keys: Vec<RsaPublicKey>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PublicKeys {
+    player_certificate_keys: Vec<PublicKeyEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PublicKeyEntry {
+    public_key: String,
+}
+
+impl SecureChat {
+    /// Fetch the keys Mojang signs chat session keys with.
+    pub async fn fetch() -> Result<Self> {
+        let client = reqwest::Client::builder().timeout(Duration::from_secs(10)).build()?;
+        let keys: PublicKeys = client.get(PUBLIC_KEYS_URL).send().await?.error_for_status()?.json().await?;
+        let mojang_keys = keys
+            .player_certificate_keys
+            .iter()
+            .filter_map(|entry| base64::engine::general_purpose::STANDARD.decode(&entry.public_key).ok())
+            .filter_map(|der| RsaPublicKey::from_public_key_der(&der).ok())
+            .collect::<Vec<_>>();
+        if mojang_keys.is_empty() {
+            anyhow::bail!("no player certificate keys in response");
+        }
+        Ok(Self {
+            enabled: true,
+            mojang_keys,
+        })
+    }
+
+    /// Check a Player Session's key was issued by Mojang to this player and
+    /// hasn't expired. Returns the key to verify their messages with, or the
+    /// reason to disconnect them.
+    pub fn validate_session(&self, player: Uuid, session: &ChatSession, now_ms: i64) -> Result<RsaPublicKey, &'static str> {
+        if session.expires_at < now_ms {
+            return Err("Chat disabled due to expired profile public key. Please try reconnecting.");
+        }
+        let mut payload = Vec::with_capacity(24 + session.public_key.len());
+        payload.extend_from_slice(player.as_bytes());
+        payload.extend_from_slice(&session.expires_at.to_be_bytes());
+        payload.extend_from_slice(&session.public_key);
+        let hashed = Sha1::digest(&payload);
+        let signed_by_mojang = self
+            .mojang_keys
+            .iter()
+            .any(|key| key.verify(Pkcs1v15Sign::new::<Sha1>(), &hashed, &session.key_signature).is_ok());
+        if !signed_by_mojang {
+            return Err("Invalid signature for profile public key. Try restarting your game.");
+        }
+        RsaPublicKey::from_public_key_der(&session.public_key).map_err(|_| "Invalid profile public key.")
+    }
+}
+
+/// A chat message as its sender signed it.
+pub struct SignedBody<'a> {
+    pub sender: Uuid,
+    pub session_id: Uuid,
+    /// Position in the sender's chain this session.
+    pub index: i32,
+    pub salt: i64,
+    /// Milliseconds since the epoch; only whole seconds are signed.
+    pub timestamp: i64,
+    pub message: &'a str,
+    pub last_seen: &'a [Vec<u8>],
+}
+
+impl SignedBody<'_> {
+    fn digest(&self) -> Vec<u8> {
+        let mut hasher = Sha256::new();
+        // Signature format version
+        hasher.update(1i32.to_be_bytes());
+        hasher.update(self.sender.as_bytes());
+        hasher.update(self.session_id.as_bytes());
+        hasher.update(self.index.to_be_bytes());
+        hasher.update(self.salt.to_be_bytes());
+        hasher.update((self.timestamp / 1000).to_be_bytes());
+        hasher.update((self.message.len() as i32).to_be_bytes());
+        hasher.update(self.message.as_bytes());
+        hasher.update((self.last_seen.len() as i32).to_be_bytes());
+        for signature in self.last_seen {
+            hasher.update(signature);
+        }
+        hasher.finalize().to_vec()
+    }
+
+    pub fn verify(&self, key: &RsaPublicKey, signature: &[u8]) -> bool {
+        key.verify(Pkcs1v15Sign::new::<Sha256>(), &self.digest(), signature).is_ok()
+    }
+}
+
+/// Why a client's view of the chat doesn't match what it was sent.
+#[derive(Debug, PartialEq)]
+pub struct LastSeenError;
+
+/// Signed messages sent to one player, which they acknowledge as seen when
+/// they chat. Mirrors the client's own tracker, so both agree on the
+/// signatures each message covers.
+pub struct LastSeenMessages {
+    /// Oldest first. None is a message the client chose not to acknowledge;
+    /// `(signature, pending)` is awaiting or has had acknowledgement.
+    tracked: VecDeque<Option<(Vec<u8>, bool)>>,
+}
+
+impl Default for LastSeenMessages {
+    fn default() -> Self {
+        Self {
+            tracked: std::iter::repeat_with(|| None).take(LAST_SEEN_WINDOW).collect(),
+        }
+    }
+}
+
+impl LastSeenMessages {
+    /// Track a signed message sent to the player. Fails once more than
+    /// `MAX_PENDING` are tracked, for a client that never acknowledges them.
+    pub fn add_pending(&mut self, signature: Vec<u8>) -> Result<(), LastSeenError> {
+        self.tracked.push_back(Some((signature, true)));
+        if self.tracked.len() > MAX_PENDING {
+            return Err(LastSeenError);
+        }
+        Ok(())
+    }
+
+    /// Drop the `offset` oldest messages, which the client has moved past.
+    pub fn apply_offset(&mut self, offset: i32) -> Result<(), LastSeenError> {
+        let movable = self.tracked.len() - LAST_SEEN_WINDOW;
+        if offset < 0 || offset as usize > movable {
+            return Err(LastSeenError);
+        }
+        self.tracked.drain(..offset as usize);
+        Ok(())
+    }
+
+    /// Apply a chat message's acknowledgements. Returns the signatures the
+    /// client saw, which its message signature covers.
+    pub fn apply_update(&mut self, offset: i32, acknowledged: [u8; 3]) -> Result<Vec<Vec<u8>>, LastSeenError> {
+        self.apply_offset(offset)?;
+        let mut seen = Vec::new();
+        for i in 0..LAST_SEEN_WINDOW {
+            let entry = &mut self.tracked[i];
+            if acknowledged[i / 8] & (1 << (i % 8)) != 0 {
+                let Some((signature, pending)) = entry else {
+                    return Err(LastSeenError);
+                };
+                *pending = false;
+                seen.push(signature.clone());
+            } else {
+                // A message can't be un-acknowledged
+                if matches!(entry, Some((_, false))) {
+                    return Err(LastSeenError);
+                }
+                *entry = None;
+            }
+        }
+        Ok(seen)
+    }
+}
+
+pub fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_seen_messages() {
+        let mut last_seen = LastSeenMessages::default();
+        last_seen.add_pending(vec![1]).unwrap();
+        last_seen.add_pending(vec![2]).unwrap();
+        // The client moved both new messages into its window and saw them
+        assert_eq!(last_seen.apply_update(2, [0, 0, 0b1100]), Ok(vec![vec![1], vec![2]]));
+        // Can't move past messages that weren't sent
+        assert_eq!(last_seen.apply_offset(1), Err(LastSeenError));
+        // Or drop one it already acknowledged
+        assert_eq!(last_seen.apply_update(0, [0, 0, 0b0100]), Err(LastSeenError));
+    }
+
+    #[test]
+    fn test_too_many_pending() {
+        let mut last_seen = LastSeenMessages::default();
+        for _ in LAST_SEEN_WINDOW..MAX_PENDING {
+            assert_eq!(last_seen.add_pending(vec![0; 256]), Ok(()));
+        }
+        assert_eq!(last_seen.add_pending(vec![0; 256]), Err(LastSeenError));
+
+        // Acknowledging messages makes room again
+        let mut last_seen = LastSeenMessages::default();
+        for _ in LAST_SEEN_WINDOW..MAX_PENDING {
+            last_seen.add_pending(vec![0; 256]).unwrap();
+        }
+        last_seen.apply_offset(100).unwrap();
+        assert_eq!(last_seen.add_pending(vec![0; 256]), Ok(()));
+    }
+
+    #[test]
+    fn test_signed_body() {
+        let key = rsa::RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+        let body = SignedBody {
+            sender: Uuid::from_u128(1),
+            session_id: Uuid::from_u128(2),
+            index: 0,
+            salt: 42,
+            timestamp: 1_700_000_000_123,
+            message: "hello",
+            last_seen: &[vec![7; 256]],
+        };
+        let signature = key.sign(Pkcs1v15Sign::new::<Sha256>(), &body.digest()).unwrap();
+        assert!(body.verify(&key.to_public_key(), &signature));
+        let tampered = SignedBody { message: "hellO", ..body };
+        assert!(!tampered.verify(&key.to_public_key(), &signature));
+    }
+}
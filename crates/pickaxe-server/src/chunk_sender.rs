@@ -0,0 +1,184 @@
+use std::collections::HashSet;
+
+/// Chunks per tick a client is sent before it first reports its rate.
+const INITIAL_CHUNKS_PER_TICK: f32 = 9.0;
+
+/// Batches a client may have in flight once it has acknowledged one.
+const MAX_UNACKNOWLEDGED_BATCHES: u32 = 10;
+
+/// The square of chunks a player can see: `radius` chunks either side of a
+/// center chunk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct View {
+    pub center_x: i32,
+    pub center_z: i32,
+    pub radius: i32,
+}
+
+impl View {
+    pub fn new(center_x: i32, center_z: i32, radius: i32) -> Self {
+        Self {
+            center_x,
+            center_z,
+            radius,
+        }
+    }
+
+    pub fn contains(&self, chunk_x: i32, chunk_z: i32) -> bool {
+        (chunk_x - self.center_x).abs() <= self.radius && (chunk_z - self.center_z).abs() <= self.radius
+    }
+
+    fn chunks(self) -> impl Iterator<Item = (i32, i32)> {
+        let xs = (self.center_x - self.radius)..=(self.center_x + self.radius);
+        xs.flat_map(move |x| ((self.center_z - self.radius)..=(self.center_z + self.radius)).map(move |z| (x, z)))
+    }
+}
+
+/// Chunks waiting to be sent to one player. They go out in batches, nearest
+/// first, at the rate the client reports after each batch it finishes
+/// loading, so teleports and large view distances don't arrive all at once.
+pub struct ChunkSender {
+    pending: HashSet<(i32, i32)>,
+    chunks_per_tick: f32,
+    /// Chunks the next batch may hold; builds up by `chunks_per_tick` a tick.
+    quota: f32,
+    unacknowledged: u32,
+    /// One until the client first reports its rate.
+    max_unacknowledged: u32,
+}
+
+impl Default for ChunkSender {
+    fn default() -> Self {
+        Self {
+            pending: HashSet::new(),
+            chunks_per_tick: INITIAL_CHUNKS_PER_TICK,
+            quota: 0.0,
+            unacknowledged: 0,
+            max_unacknowledged: 1,
+        }
+    }
+}
+
+impl ChunkSender {
+    pub fn queue(&mut self, chunk_x: i32, chunk_z: i32) {
+        self.pending.insert((chunk_x, chunk_z));
+    }
+
+    /// Queue every chunk in a view.
+    pub fn queue_view(&mut self, view: View) {
+        for (x, z) in view.chunks() {
+            self.queue(x, z);
+        }
+    }
+
+    /// The player moved or changed their view distance. Queues the chunks
+    /// that came into view and returns the ones the client should unload.
+    pub fn move_view(&mut self, from: View, to: View) -> Vec<(i32, i32)> {
+        let unload = from
+            .chunks()
+            .filter(|&(x, z)| !to.contains(x, z) && !self.forget(x, z))
+            .collect();
+        for (x, z) in to.chunks() {
+            if !from.contains(x, z) {
+                self.queue(x, z);
+            }
+        }
+        unload
+    }
+
+    /// Drop everything queued, for when the client has discarded its world.
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Stop tracking a chunk that left the player's view. True if it was
+    /// still queued, so the client never had it and needn't unload it.
+    pub fn forget(&mut self, chunk_x: i32, chunk_z: i32) -> bool {
+        self.pending.remove(&(chunk_x, chunk_z))
+    }
+
+    /// The chunks to send this tick, nearest to the center first. Empty while
+    /// waiting on the client to acknowledge earlier batches.
+    pub fn next_batch(&mut self, center_x: i32, center_z: i32) -> Vec<(i32, i32)> {
+        if self.unacknowledged >= self.max_unacknowledged {
+            return Vec::new();
+        }
+        self.quota = (self.quota + self.chunks_per_tick).min(self.chunks_per_tick.max(1.0));
+        if self.quota < 1.0 || self.pending.is_empty() {
+            return Vec::new();
+        }
+        let mut batch: Vec<(i32, i32)> = self.pending.iter().copied().collect();
+        batch.sort_by_key(|&(x, z)| (x - center_x).pow(2) + (z - center_z).pow(2));
+        batch.truncate(self.quota as usize);
+        for chunk in &batch {
+            self.pending.remove(chunk);
+        }
+        self.quota -= batch.len() as f32;
+        self.unacknowledged += 1;
+        batch
+    }
+
+    /// The client loaded a batch and can take `chunks_per_tick` from now on.
+    pub fn acknowledge(&mut self, chunks_per_tick: f32) {
+        self.unacknowledged = self.unacknowledged.saturating_sub(1);
+        self.chunks_per_tick = if chunks_per_tick.is_nan() {
+            0.01
+        } else {
+            chunks_per_tick.clamp(0.01, 64.0)
+        };
+        if self.unacknowledged == 0 {
+            self.quota = 1.0;
+        }
+        self.max_unacknowledged = MAX_UNACKNOWLEDGED_BATCHES;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batches_follow_client_rate() {
+        let mut sender = ChunkSender::default();
+        sender.queue_view(View::new(0, 0, 2));
+        let first = sender.next_batch(0, 0);
+        assert_eq!(first.len(), 9);
+        assert_eq!(first[0], (0, 0));
+        // Nothing more until the client acknowledges the first batch
+        assert!(sender.next_batch(0, 0).is_empty());
+
+        sender.acknowledge(2.0);
+        assert_eq!(sender.next_batch(0, 0).len(), 2);
+        // Up to ten batches may now be in flight, at two chunks a tick
+        assert_eq!(sender.next_batch(0, 0).len(), 2);
+    }
+
+    #[test]
+    fn test_forget_queued_chunk() {
+        let mut sender = ChunkSender::default();
+        sender.queue(3, 4);
+        assert!(sender.forget(3, 4));
+        assert!(!sender.forget(3, 4));
+        assert!(sender.next_batch(0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_move_view() {
+        let mut sender = ChunkSender::default();
+        sender.queue_view(View::new(0, 0, 1));
+        assert_eq!(sender.next_batch(0, 0).len(), 9);
+        sender.acknowledge(9.0);
+
+        // Walking far away unloads everything sent and streams in a new square
+        let unload = sender.move_view(View::new(0, 0, 1), View::new(5, 0, 1));
+        assert_eq!(unload.len(), 9);
+        let batch = sender.next_batch(5, 0);
+        assert_eq!(batch.len(), 9);
+        assert!(batch.iter().all(|&(x, z)| View::new(5, 0, 1).contains(x, z)));
+
+        // Chunks that leave view before they're sent are just dropped
+        sender.queue(6, 2);
+        let unload = sender.move_view(View::new(5, 0, 2), View::new(5, 0, 1));
+        assert_eq!(unload.len(), 15);
+    }
+}
@@ -0,0 +1,89 @@
+/// Type of an argument in a Lua command's `args` schema.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArgType {
+    /// One word (`string`).
+    Word,
+    /// The rest of the line (`text`).
+    Text,
+    Integer,
+    Number,
+    Boolean,
+    /// An online player's name, completed by the client (`player`).
+    Player,
+    /// Three coordinates (`block_pos`).
+    BlockPos,
+}
+
+impl ArgType {
+    pub const NAMES: &'static str = "string, text, integer, number, boolean, player, block_pos";
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "string" => Self::Word,
+            "text" => Self::Text,
+            "integer" => Self::Integer,
+            "number" => Self::Number,
+            "boolean" => Self::Boolean,
+            "player" => Self::Player,
+            "block_pos" => Self::BlockPos,
+            _ => return None,
+        })
+    }
+
+    /// Brigadier parser identifier and its encoded properties.
+    pub fn parser(self) -> (&'static str, Option<Vec<u8>>) {
+        match self {
+            // String mode: 0 = single word, 2 = greedy phrase
+            Self::Word => ("brigadier:string", Some(vec![0])),
+            Self::Text => ("brigadier:string", Some(vec![2])),
+            // Flags 0: no min or max
+            Self::Integer => ("brigadier:integer", Some(vec![0])),
+            Self::Number => ("brigadier:double", Some(vec![0])),
+            Self::Boolean => ("brigadier:bool", None),
+            Self::Player => ("minecraft:game_profile", None),
+            Self::BlockPos => ("minecraft:block_pos", None),
+        }
+    }
+
+    /// Words the argument takes, or None for the rest of the line.
+    fn words(self) -> Option<usize> {
+        match self {
+            Self::Text => None,
+            Self::BlockPos => Some(3),
+            _ => Some(1),
+        }
+    }
+}
+
+/// Which argument the word being typed at the end of `input` (the text after
+/// the command name) belongs to, and the byte offset where its value starts.
+/// A `text` argument starts at its first word. None past the last argument.
+pub fn completion_target(args: &[ArgType], input: &str) -> Option<(usize, usize)> {
+    let mut starts = vec![0];
+    starts.extend(input.match_indices(' ').map(|(i, _)| i + 1));
+    let current = starts.len() - 1;
+    let mut first = 0;
+    for (i, ty) in args.iter().enumerate() {
+        match ty.words() {
+            None => return Some((i, starts[first])),
+            Some(n) if current < first + n => return Some((i, starts[current])),
+            Some(n) => first += n,
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completion_target() {
+        let args = [ArgType::Word, ArgType::BlockPos, ArgType::Text];
+        assert_eq!(completion_target(&args, ""), Some((0, 0)));
+        assert_eq!(completion_target(&args, "ba"), Some((0, 0)));
+        assert_eq!(completion_target(&args, "base 1 "), Some((1, 7)));
+        assert_eq!(completion_target(&args, "base 1 2 3 hello wor"), Some((2, 11)));
+        assert_eq!(completion_target(&[ArgType::Integer], "5 "), None);
+    }
+}
@@ -1,7 +1,10 @@
+use pickaxe_data::registries::{Biome, DimensionType, Registries};
+use pickaxe_region::ChunkCompression;
 use serde::Deserialize;
 use std::path::Path;
+use std::time::Duration;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct ServerConfig {
     #[serde(default = "default_bind")]
     pub bind: String,
@@ -11,12 +14,110 @@ pub struct ServerConfig {
     pub max_players: u32,
     #[serde(default = "default_motd")]
     pub motd: String,
+    /// Encrypt logins and verify players with the Mojang session server.
     #[serde(default)]
     pub online_mode: bool,
+    /// Most chunks in each direction a player is sent; clients asking for
+    /// less get what they ask for.
     #[serde(default = "default_view_distance")]
     pub view_distance: u32,
+    /// Chunks around each player where crops grow and mobs move. Never more
+    /// than the player's view distance.
+    #[serde(default = "default_simulation_distance")]
+    pub simulation_distance: u32,
+    /// World ticks per second at startup; `/tick rate` changes it at runtime.
+    #[serde(default = "default_tick_rate")]
+    pub tick_rate: f32,
     #[serde(default = "default_world_dir")]
     pub world_dir: String,
+    /// How chunks are compressed when saved. Existing chunks in any format
+    /// are still read.
+    #[serde(default)]
+    pub region_compression: ChunkCompression,
+    /// Only allow players listed in whitelist.json (and ops) to join.
+    #[serde(default)]
+    pub whitelist: bool,
+    /// Announce the server in the LAN section of players' server lists, like
+    /// a singleplayer world opened to LAN.
+    #[serde(default)]
+    pub lan_broadcast: bool,
+    /// Addresses to listen on besides `bind`/`port`.
+    #[serde(default)]
+    pub listeners: Vec<ListenerConfig>,
+    #[serde(default)]
+    pub messaging: MessagingConfig,
+    #[serde(default)]
+    pub tpa: TpaConfig,
+    #[serde(default)]
+    pub teleports: TeleportsConfig,
+    #[serde(default)]
+    pub tab_list: TabListConfig,
+    #[serde(default)]
+    pub audit: AuditConfig,
+    #[serde(default)]
+    pub http: HttpConfig,
+    #[serde(default)]
+    pub scripting: ScriptingConfig,
+    #[serde(default)]
+    pub proxy: ProxyConfig,
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    #[serde(default)]
+    pub status: StatusConfig,
+    #[serde(default)]
+    pub resource_pack: ResourcePackConfig,
+    #[serde(default)]
+    pub transfer: TransferConfig,
+    #[serde(default)]
+    pub chat: ChatConfig,
+    #[serde(default)]
+    pub keep_alive: KeepAliveConfig,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub afk: AfkConfig,
+    #[serde(default)]
+    pub capture: CaptureConfig,
+    #[serde(default)]
+    pub saving: SavingConfig,
+    #[serde(default)]
+    pub overload: OverloadConfig,
+    #[serde(default)]
+    pub backup: BackupConfig,
+    #[serde(default)]
+    pub registries: RegistriesConfig,
+    #[serde(default)]
+    pub advancements: AdvancementsConfig,
+    #[serde(default)]
+    pub schematics: SchematicsConfig,
+    #[serde(default)]
+    pub structures: StructuresConfig,
+    /// Worlds besides the overworld, each a dimension with its own folder.
+    #[serde(default)]
+    pub worlds: Vec<WorldConfig>,
+}
+
+/// `[messaging]` — private message formats. `&` color codes are translated;
+/// `{sender}`, `{receiver}` and `{message}` are substituted.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MessagingConfig {
+    /// Shown to the sender of a private message.
+    pub outgoing: String,
+    /// Shown to the receiver.
+    pub incoming: String,
+    /// Shown to operators with social spy enabled.
+    pub spy: String,
+}
+
+impl Default for MessagingConfig {
+    fn default() -> Self {
+        Self {
+            outgoing: "&7&oYou whisper to {receiver}: {message}".into(),
+            incoming: "&7&o{sender} whispers to you: {message}".into(),
+            spy: "&8[Spy] {sender} -> {receiver}: {message}".into(),
+        }
+    }
 }
 
 fn default_bind() -> String {
@@ -39,6 +140,14 @@ fn default_view_distance() -> u32 {
     8
 }
 
+fn default_simulation_distance() -> u32 {
+    6
+}
+
+fn default_tick_rate() -> f32 {
+    20.0
+}
+
 fn default_world_dir() -> String {
     "world".to_string()
 }
@@ -52,12 +161,677 @@ impl Default for ServerConfig {
             motd: default_motd(),
             online_mode: false,
             view_distance: default_view_distance(),
+            simulation_distance: default_simulation_distance(),
+            tick_rate: default_tick_rate(),
             world_dir: default_world_dir(),
+            region_compression: ChunkCompression::default(),
+            whitelist: false,
+            lan_broadcast: false,
+            listeners: Vec::new(),
+            messaging: MessagingConfig::default(),
+            tpa: TpaConfig::default(),
+            teleports: TeleportsConfig::default(),
+            tab_list: TabListConfig::default(),
+            audit: AuditConfig::default(),
+            http: HttpConfig::default(),
+            scripting: ScriptingConfig::default(),
+            proxy: ProxyConfig::default(),
+            limits: LimitsConfig::default(),
+            status: StatusConfig::default(),
+            resource_pack: ResourcePackConfig::default(),
+            transfer: TransferConfig::default(),
+            chat: ChatConfig::default(),
+            keep_alive: KeepAliveConfig::default(),
+            metrics: MetricsConfig::default(),
+            afk: AfkConfig::default(),
+            capture: CaptureConfig::default(),
+            backup: BackupConfig::default(),
+            registries: RegistriesConfig::default(),
+            advancements: AdvancementsConfig::default(),
+            schematics: SchematicsConfig::default(),
+            structures: StructuresConfig::default(),
+            worlds: Vec::new(),
+        }
+    }
+}
+
+/// `[tpa]` — the built-in /tpa, /tpaccept, /tpdeny teleport request module.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TpaConfig {
+    pub enabled: bool,
+    /// Seconds before an unanswered request lapses.
+    pub timeout_seconds: u64,
+    /// Operator level (0-4) required to use the commands.
+    pub permission_level: u8,
+}
+
+impl Default for TpaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            timeout_seconds: 120,
+            permission_level: 0,
+        }
+    }
+}
+
+/// `[teleports]` — built-in /spawn, /sethome + /home and /warp commands.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TeleportsConfig {
+    pub spawn: bool,
+    pub homes: bool,
+    /// Homes each player may set.
+    pub max_homes: u32,
+    pub warps: bool,
+}
+
+impl Default for TeleportsConfig {
+    fn default() -> Self {
+        Self {
+            spawn: true,
+            homes: true,
+            max_homes: 3,
+            warps: true,
+        }
+    }
+}
+
+/// `[tab_list]` — player list header and footer. `&` color codes are
+/// translated; `{online}`, `{max}` and `{tps}` are substituted.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TabListConfig {
+    pub header: String,
+    pub footer: String,
+    /// Seconds between refreshes of the header, footer and player pings.
+    pub refresh_seconds: u64,
+}
+
+impl Default for TabListConfig {
+    fn default() -> Self {
+        Self {
+            header: "&6Pickaxe Server".into(),
+            footer: "&7{online}/{max} online &8| &7TPS: {tps}".into(),
+            refresh_seconds: 5,
+        }
+    }
+}
+
+/// `[audit]` — append-only log of commands, block changes and container
+/// access under `<world>/audit/`, searchable with /audit lookup.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AuditConfig {
+    pub enabled: bool,
+    pub commands: bool,
+    /// Block breaks and placements.
+    pub blocks: bool,
+    pub containers: bool,
+    /// Size at which `audit.log` is rotated.
+    pub max_file_kb: u64,
+    /// Rotated files kept besides the current one.
+    pub max_files: u32,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            commands: true,
+            blocks: true,
+            containers: true,
+            max_file_kb: 8192,
+            max_files: 5,
+        }
+    }
+}
+
+/// `[http]` — outbound HTTP requests made by Lua mods with `pickaxe.http`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    pub enabled: bool,
+    /// Hosts mods may contact; `*.example.com` also matches subdomains.
+    pub allowed_domains: Vec<String>,
+    /// Requests allowed across all mods in any 60-second window.
+    pub requests_per_minute: u32,
+    pub timeout_seconds: u64,
+    /// Larger responses are rejected.
+    pub max_response_kb: u64,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allowed_domains: Vec::new(),
+            requests_per_minute: 60,
+            timeout_seconds: 10,
+            max_response_kb: 1024,
+        }
+    }
+}
+
+/// `[scripting]` — resource limits for Lua mods. Zero disables a limit.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ScriptingConfig {
+    /// VM instructions each mod may run per tick before its call is aborted.
+    pub instructions_per_tick: u64,
+    /// Memory ceiling for the Lua VM shared by all mods.
+    pub memory_limit_mb: u64,
+    /// Ticks in which a mod may break a limit before it is suspended.
+    pub max_violations: u32,
+    /// Event handler calls slower than this are logged (see `/mods timings`).
+    pub slow_handler_ms: u64,
+}
+
+impl Default for ScriptingConfig {
+    fn default() -> Self {
+        Self {
+            instructions_per_tick: 10_000_000,
+            memory_limit_mb: 512,
+            max_violations: 5,
+            slow_handler_ms: 10,
+        }
+    }
+}
+
+/// `[proxy]` — accept players forwarded by a Velocity or BungeeCord proxy,
+/// which authenticates them and passes on their UUID, skin and address.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ProxyConfig {
+    pub mode: ProxyMode,
+    /// Velocity's forwarding secret, used to verify forwarded logins.
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyMode {
+    /// Players connect directly.
+    #[default]
+    None,
+    /// Velocity modern forwarding over the `velocity:player_info` channel.
+    Velocity,
+    /// BungeeCord `ip_forward`, carried in the handshake address.
+    Bungeecord,
+}
+
+/// `[[listeners]]` — an extra address to accept players on.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ListenerConfig {
+    pub bind: String,
+    pub port: u16,
+    /// How players arrive on this listener; `[proxy] mode` if unset.
+    #[serde(default)]
+    pub proxy: Option<ProxyMode>,
+}
+
+impl ListenerConfig {
+    pub fn proxy_mode(&self) -> ProxyMode {
+        self.proxy.unwrap_or_default()
+    }
+}
+
+/// `[limits]` — protection against clients flooding the server. Clients
+/// that exceed a limit are disconnected. Zero disables a limit.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LimitsConfig {
+    /// Largest packet a client may send, before or after decompression.
+    pub max_packet_kb: u64,
+    /// Average packets a player may send per second, measured over 5 seconds.
+    pub packets_per_second: u32,
+    /// Minimum time between logins from one IP address. Not applied behind
+    /// a proxy, where every player shares the proxy's address.
+    pub login_throttle_ms: u64,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_packet_kb: 2048,
+            packets_per_second: 300,
+            login_throttle_ms: 4000,
+        }
+    }
+}
+
+/// `[status]` — how the server appears in the client's server list. The
+/// MOTD itself is the top-level `motd`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct StatusConfig {
+    /// 64x64 PNG shown next to the server.
+    pub icon: String,
+    /// Online players listed when hovering over the player count.
+    pub player_sample: usize,
+    /// Shown instead of the Minecraft version when set.
+    pub version_name: String,
+    /// Reported instead of the client's protocol when non-zero. Any other
+    /// protocol makes the client show `version_name` as incompatible.
+    pub protocol: i32,
+}
+
+impl Default for StatusConfig {
+    fn default() -> Self {
+        Self {
+            icon: "server-icon.png".into(),
+            player_sample: 12,
+            version_name: String::new(),
+            protocol: 0,
+        }
+    }
+}
+
+/// `[resource_pack]` — a pack offered to every player while they join.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ResourcePackConfig {
+    /// Download URL; empty disables the pack.
+    pub url: String,
+    /// Hex SHA-1 of the pack file, so clients can verify and cache it.
+    pub sha1: String,
+    /// Shown as required; the client disconnects the player if declined.
+    pub required: bool,
+    /// Message on the prompt. `&` color codes are translated.
+    pub prompt: String,
+    /// Kick players whose client declines or fails to load the pack.
+    pub kick_on_decline: bool,
+}
+
+/// `[transfer]` — players handed over from other servers with the Transfer
+/// packet, and the cookies they carry between them.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TransferConfig {
+    /// Let players in who were transferred here; otherwise they're
+    /// disconnected.
+    pub accept: bool,
+    /// Shared by every server on the network to sign cookies set from Lua,
+    /// so one server can trust what another stored. Empty stores them as-is.
+    pub secret: String,
+    /// How long a signed cookie is accepted after it was stored.
+    pub cookie_ttl_seconds: u64,
+}
+
+impl Default for TransferConfig {
+    fn default() -> Self {
+        Self {
+            accept: false,
+            secret: String::new(),
+            cookie_ttl_seconds: 3600,
+        }
+    }
+}
+
+/// `[chat]` — how player chat is relayed.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ChatConfig {
+    /// Relay chat signed by the sender's client, so others can verify and
+    /// report it, and refuse chat from players without a valid session. Only
+    /// possible with Mojang accounts (online mode or behind a proxy); false
+    /// runs in unsigned mode.
+    pub secure: bool,
+}
+
+impl Default for ChatConfig {
+    fn default() -> Self {
+        Self { secure: true }
+    }
+}
+
+/// `[keep_alive]` — dropping players whose connection has gone away.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct KeepAliveConfig {
+    /// Seconds between keep-alive pings.
+    pub interval_seconds: u64,
+    /// Seconds a ping may go unanswered before the player times out.
+    pub timeout_seconds: u64,
+    /// Seconds without any packet from the client, catching connections that
+    /// died without closing. 0 disables.
+    pub idle_timeout_seconds: u64,
+}
+
+impl Default for KeepAliveConfig {
+    fn default() -> Self {
+        Self {
+            interval_seconds: 15,
+            timeout_seconds: 30,
+            idle_timeout_seconds: 60,
+        }
+    }
+}
+
+impl KeepAliveConfig {
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        (self.idle_timeout_seconds > 0).then(|| Duration::from_secs(self.idle_timeout_seconds))
+    }
+}
+
+/// `[afk]` — players who stop playing: marked AFK after a while, and
+/// optionally disconnected to free their slot.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AfkConfig {
+    /// Seconds without input before a player is marked AFK. 0 disables.
+    pub after_seconds: u64,
+    /// Minutes without input before a player is disconnected, like vanilla's
+    /// `player-idle-timeout`. 0 never disconnects.
+    pub kick_after_minutes: u64,
+    /// Appended to AFK players' names in the tab list; `&` color codes are
+    /// supported. Empty leaves names alone.
+    pub suffix: String,
+}
+
+impl Default for AfkConfig {
+    fn default() -> Self {
+        Self {
+            after_seconds: 300,
+            kick_after_minutes: 0,
+            suffix: " &7[AFK]".into(),
+        }
+    }
+}
+
+impl AfkConfig {
+    pub fn afk_after(&self) -> Option<Duration> {
+        (self.after_seconds > 0).then(|| Duration::from_secs(self.after_seconds))
+    }
+
+    pub fn kick_after(&self) -> Option<Duration> {
+        (self.kick_after_minutes > 0).then(|| Duration::from_secs(self.kick_after_minutes * 60))
+    }
+}
+
+/// `[metrics]` — an HTTP endpoint serving Prometheus metrics at `/metrics`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    pub bind: String,
+    pub port: u16,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: "127.0.0.1".into(),
+            port: 9225,
+        }
+    }
+}
+
+/// `[capture]` — record every packet of each connection to a file in `dir`,
+/// for debugging the protocol.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CaptureConfig {
+    pub enabled: bool,
+    pub dir: String,
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: "captures".into(),
+        }
+    }
+}
+
+/// `[saving]` — how often the world is saved on its own.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SavingConfig {
+    /// Ticks between saves of players, level.dat, the scoreboard and other
+    /// world data. 0 only saves on /save-all and shutdown.
+    pub autosave_ticks: u64,
+    /// Most changed chunks serialized per tick; the rest wait for the next
+    /// ticks, so saving a large world doesn't stall one tick. 0 for no limit.
+    pub chunks_per_tick: usize,
+}
+
+impl Default for SavingConfig {
+    fn default() -> Self {
+        Self {
+            autosave_ticks: 1200,
+            chunks_per_tick: 32,
+        }
+    }
+}
+
+/// `[overload]` — shedding optional work when ticks run over budget, so a
+/// busy server slows down gracefully instead of falling further behind.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct OverloadConfig {
+    pub enabled: bool,
+    /// Average tick time, as a percentage of the tick budget, at which the
+    /// server counts as strained.
+    pub strained_percent: f64,
+    /// Average tick time at which it counts as overloaded.
+    pub overloaded_percent: f64,
+    /// How long the load must stay above a threshold before shedding more.
+    pub sustain_seconds: u64,
+    /// How long it must stay below before shedding less.
+    pub recover_seconds: u64,
+}
+
+impl Default for OverloadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            strained_percent: 80.0,
+            overloaded_percent: 100.0,
+            sustain_seconds: 5,
+            recover_seconds: 30,
+        }
+    }
+}
+
+/// `[backup]` — copies of the world taken on a schedule and by `/backup now`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BackupConfig {
+    pub enabled: bool,
+    /// Minutes between scheduled backups; 0 only backs up on request.
+    pub interval_minutes: u64,
+    pub dir: String,
+    pub format: BackupFormat,
+    /// Most backups to keep, oldest deleted first. 0 keeps them all.
+    pub max_backups: usize,
+    /// Most disk space all backups may use, in MiB. 0 for no limit.
+    pub max_size_mb: u64,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: 60,
+            dir: "backups".into(),
+            format: BackupFormat::default(),
+            max_backups: 10,
+            max_size_mb: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupFormat {
+    #[default]
+    Zip,
+    #[serde(rename = "tar.zst")]
+    TarZst,
+    /// A plain copy of the world folder.
+    Directory,
+}
+
+/// `[registries]` — dimension types and biomes sent to clients on top of the
+/// built-in ones. An entry named like a built-in one replaces it.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RegistriesConfig {
+    pub dimension_types: Vec<DimensionType>,
+    pub biomes: Vec<Biome>,
+}
+
+impl RegistriesConfig {
+    pub fn build(&self) -> Registries {
+        let mut registries = Registries::default();
+        for dimension_type in &self.dimension_types {
+            registries.add_dimension_type(dimension_type.clone());
+        }
+        for biome in &self.biomes {
+            registries.add_biome(biome.clone());
+        }
+        registries
+    }
+}
+
+/// `[advancements]` — advancements loaded from vanilla-format JSON.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AdvancementsConfig {
+    pub enabled: bool,
+    /// Datapack-style directory: `<namespace>/advancement/**/*.json` and the
+    /// item tags they use in `<namespace>/tags/item/`.
+    pub data_dir: String,
+}
+
+impl Default for AdvancementsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            data_dir: "data".into(),
         }
     }
 }
 
+/// `[schematics]` — where `/schem` reads and writes `.schem` files.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SchematicsConfig {
+    pub dir: String,
+    /// Most blocks one schematic may hold, for saving and pasting.
+    pub max_blocks: usize,
+}
+
+impl Default for SchematicsConfig {
+    fn default() -> Self {
+        Self {
+            dir: "schematics".into(),
+            max_blocks: 4 * 1024 * 1024,
+        }
+    }
+}
+
+/// `[structures]` — vanilla structure templates for `/place template` and
+/// `/structure save`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct StructuresConfig {
+    /// Datapack-style directory searched for `<namespace>/structure/**/*.nbt`
+    /// after the world's `generated/` templates.
+    pub data_dir: String,
+    /// Most blocks one template may hold, for saving and placing.
+    pub max_blocks: usize,
+}
+
+impl Default for StructuresConfig {
+    fn default() -> Self {
+        Self {
+            data_dir: "data".into(),
+            max_blocks: 4 * 1024 * 1024,
+        }
+    }
+}
+
+/// `[[worlds]]` — an extra world players can be sent to with `/world tp`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorldConfig {
+    /// Dimension name, like `minecraft:the_nether` or `pickaxe:lobby`.
+    pub name: String,
+    /// Dimension type from the registries, which sets the sky and lighting.
+    #[serde(default = "default_dimension_type")]
+    pub dimension_type: String,
+    #[serde(default)]
+    pub generator: WorldGenerator,
+    /// Where players arrive.
+    #[serde(default = "default_world_spawn")]
+    pub spawn: [f64; 3],
+}
+
+/// How a world's new chunks are made.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorldGenerator {
+    /// The overworld's superflat layers.
+    #[default]
+    Flat,
+    /// Nothing but a stone platform under the spawn point.
+    Void,
+}
+
+impl WorldGenerator {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Flat => "flat",
+            Self::Void => "void",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "flat" => Some(Self::Flat),
+            "void" => Some(Self::Void),
+            _ => None,
+        }
+    }
+}
+
+fn default_dimension_type() -> String {
+    "minecraft:overworld".into()
+}
+
+fn default_world_spawn() -> [f64; 3] {
+    [0.5, -49.0, 0.5]
+}
+
 impl ServerConfig {
+    /// Every address to listen on, `bind`/`port` first, with their proxy
+    /// modes filled in.
+    pub fn listeners(&self) -> Vec<ListenerConfig> {
+        let main = ListenerConfig {
+            bind: self.bind.clone(),
+            port: self.port,
+            proxy: None,
+        };
+        std::iter::once(main)
+            .chain(self.listeners.iter().cloned())
+            .map(|listener| ListenerConfig {
+                proxy: Some(listener.proxy.unwrap_or(self.proxy.mode)),
+                ..listener
+            })
+            .collect()
+    }
+
+    /// The config connections to `listener` see, with its proxy mode.
+    pub fn for_listener(&self, listener: &ListenerConfig) -> ServerConfig {
+        let mut config = self.clone();
+        config.proxy.mode = listener.proxy_mode();
+        config
+    }
+
     pub fn load(path: &Path) -> anyhow::Result<Self> {
         if path.exists() {
             let contents = std::fs::read_to_string(path)?;
@@ -70,7 +844,8 @@ impl ServerConfig {
     }
 }
 
-/// Separate ops file — hot-reloaded on every permission check.
+/// Legacy ops file (all entries are level 4) — read with ops.json at startup
+/// and by `/ops reload`. ops.json is handled in `access`.
 #[derive(Debug, Deserialize, Default)]
 struct OpsConfig {
     #[serde(default)]
@@ -0,0 +1,94 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+/// Largest cookie payload the client stores.
+pub const MAX_COOKIE_SIZE: usize = 5120;
+
+/// Length of the expiry, in Unix seconds, stored after a signed cookie's payload.
+const EXPIRY_LEN: usize = 8;
+
+/// Length of the HMAC-SHA256 signature appended to a signed cookie.
+const SIGNATURE_LEN: usize = 32;
+
+/// Append the expiry and an HMAC-SHA256 signature made with the shared
+/// `[transfer]` secret, so servers on the network can trust cookies the
+/// client hands back. The signature also covers the cookie key and the
+/// player, so a cookie can't be returned under another key, replayed by
+/// another player or used after `expires_at`. Unchanged if there's no secret.
+pub fn sign(secret: &str, key: &str, player: Uuid, expires_at: i64, payload: &[u8]) -> Vec<u8> {
+    let mut cookie = payload.to_vec();
+    if let Some(mac) = mac(secret, key, player, expires_at, payload) {
+        cookie.extend_from_slice(&expires_at.to_be_bytes());
+        cookie.extend_from_slice(&mac.finalize().into_bytes());
+    }
+    cookie
+}
+
+/// The payload of a cookie made by `sign`, or None if the signature doesn't
+/// match this key and player or the cookie expired before `now` (Unix
+/// seconds). Unchanged if there's no secret.
+pub fn verify(secret: &str, key: &str, player: Uuid, now: i64, cookie: &[u8]) -> Option<Vec<u8>> {
+    if secret.is_empty() {
+        return Some(cookie.to_vec());
+    }
+    let payload_len = cookie.len().checked_sub(EXPIRY_LEN + SIGNATURE_LEN)?;
+    let (payload, rest) = cookie.split_at(payload_len);
+    let (expiry, signature) = rest.split_at(EXPIRY_LEN);
+    let expires_at = i64::from_be_bytes(expiry.try_into().ok()?);
+    mac(secret, key, player, expires_at, payload)?.verify_slice(signature).ok()?;
+    if expires_at < now {
+        return None;
+    }
+    Some(payload.to_vec())
+}
+
+/// Room left for a payload once the expiry and signature are added.
+pub fn max_payload(secret: &str) -> usize {
+    if secret.is_empty() {
+        MAX_COOKIE_SIZE
+    } else {
+        MAX_COOKIE_SIZE - EXPIRY_LEN - SIGNATURE_LEN
+    }
+}
+
+fn mac(secret: &str, key: &str, player: Uuid, expires_at: i64, payload: &[u8]) -> Option<Hmac<Sha256>> {
+    if secret.is_empty() {
+        return None;
+    }
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).ok()?;
+    // Length-prefixed, so the key can't run into the fields after it
+    mac.update(&(key.len() as u32).to_be_bytes());
+    mac.update(key.as_bytes());
+    mac.update(player.as_bytes());
+    mac.update(&expires_at.to_be_bytes());
+    mac.update(payload);
+    Some(mac)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let (steve, alex) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let cookie = sign("secret", "lobby:return", steve, 1000, b"lobby");
+        assert_eq!(cookie.len(), 5 + EXPIRY_LEN + SIGNATURE_LEN);
+        assert_eq!(verify("secret", "lobby:return", steve, 1000, &cookie).as_deref(), Some(&b"lobby"[..]));
+        assert!(verify("other", "lobby:return", steve, 1000, &cookie).is_none());
+        assert!(verify("secret", "short", steve, 1000, b"short").is_none());
+
+        // Bound to the key, the player and the expiry
+        assert!(verify("secret", "lobby:rank", steve, 1000, &cookie).is_none());
+        assert!(verify("secret", "lobby:return", alex, 1000, &cookie).is_none());
+        assert!(verify("secret", "lobby:return", steve, 1001, &cookie).is_none());
+        let mut extended = cookie.clone();
+        extended[5..5 + EXPIRY_LEN].copy_from_slice(&i64::MAX.to_be_bytes());
+        assert!(verify("secret", "lobby:return", steve, 1001, &extended).is_none());
+
+        // Without a secret cookies are passed through as-is
+        assert_eq!(sign("", "lobby:return", steve, 1000, b"lobby"), b"lobby");
+        assert_eq!(verify("", "lobby:return", alex, 2000, b"lobby").as_deref(), Some(&b"lobby"[..]));
+    }
+}
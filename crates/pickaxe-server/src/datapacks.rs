@@ -0,0 +1,244 @@
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Directory in the world holding datapack folders.
+pub const DATAPACKS_DIR: &str = "datapacks";
+
+/// How deeply functions may call each other, so a recursive function stops
+/// before the stack does.
+pub const MAX_FUNCTION_DEPTH: u32 = 64;
+
+/// Functions and function tags from the world's datapacks.
+#[derive(Debug, Default)]
+pub struct FunctionLibrary {
+    /// Command lines without the leading `/`, by `namespace:path`.
+    functions: BTreeMap<String, Arc<[String]>>,
+    /// Function IDs, with nested tags expanded, by `namespace:path`.
+    tags: BTreeMap<String, Vec<String>>,
+    /// Folder names of the loaded packs
+    packs: Vec<String>,
+}
+
+impl FunctionLibrary {
+    /// Load every folder pack in `<world>/datapacks`, in name order; a later
+    /// pack's function replaces an earlier one with the same ID, and tags
+    /// merge unless `replace` is set. Both the 1.21 `function` folders and the
+    /// older `functions` ones are read. Packs without `pack.mcmeta` and
+    /// zipped packs are skipped.
+    pub fn load(world_dir: &Path) -> Self {
+        let mut packs: Vec<_> = fs::read_dir(world_dir.join(DATAPACKS_DIR))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.is_dir())
+            .collect();
+        packs.sort();
+
+        let mut library = Self::default();
+        // Tag entries before nested tags are expanded
+        let mut raw_tags: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for pack in packs {
+            if !pack.join("pack.mcmeta").is_file() {
+                tracing::warn!("Skipping datapack {}: no pack.mcmeta", pack.display());
+                continue;
+            }
+            library.packs.push(pack.file_name().unwrap_or_default().to_string_lossy().into_owned());
+            for namespace_dir in fs::read_dir(pack.join("data")).into_iter().flatten().flatten() {
+                let Some(namespace) = namespace_dir.file_name().to_str().map(String::from) else {
+                    continue;
+                };
+                let root = namespace_dir.path();
+                for folder in ["functions", "function"] {
+                    for (path, file) in files(&root.join(folder), "mcfunction") {
+                        let id = format!("{}:{}", namespace, path);
+                        let source = fs::read_to_string(&file).unwrap_or_default();
+                        match parse_function(&source) {
+                            Ok(commands) => {
+                                library.functions.insert(id, commands.into());
+                            }
+                            Err(e) => tracing::warn!("Skipping function {}: {}", id, e),
+                        }
+                    }
+                }
+                for folder in ["tags/functions", "tags/function"] {
+                    for (path, file) in files(&root.join(folder), "json") {
+                        let id = format!("{}:{}", namespace, path);
+                        let json = fs::read(&file).ok().and_then(|d| serde_json::from_slice::<Value>(&d).ok());
+                        let Some(json) = json else {
+                            tracing::warn!("Skipping unreadable function tag {}", file.display());
+                            continue;
+                        };
+                        let tag = raw_tags.entry(id).or_default();
+                        if json.get("replace").and_then(Value::as_bool) == Some(true) {
+                            tag.clear();
+                        }
+                        for value in json.get("values").and_then(Value::as_array).into_iter().flatten() {
+                            // Entries are IDs or {"id": ..., "required": ...}
+                            if let Some(entry) = value.as_str().or_else(|| value.get("id").and_then(Value::as_str)) {
+                                tag.push(entry.to_string());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        for id in raw_tags.keys() {
+            let mut functions = Vec::new();
+            expand_tag(&raw_tags, id, &mut HashSet::new(), &mut functions);
+            functions.retain(|f| {
+                let known = library.functions.contains_key(f);
+                if !known {
+                    tracing::warn!("Function tag #{} names unknown function {}", id, f);
+                }
+                known
+            });
+            library.tags.insert(id.clone(), functions);
+        }
+        library
+    }
+
+    /// A function's command lines.
+    pub fn get(&self, id: &str) -> Option<Arc<[String]>> {
+        self.functions.get(&qualify(id)).cloned()
+    }
+
+    /// The functions in a tag, in order. `None` if no pack defines it.
+    pub fn tag(&self, id: &str) -> Option<&[String]> {
+        self.tags.get(&qualify(id)).map(Vec::as_slice)
+    }
+
+    pub fn len(&self) -> usize {
+        self.functions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    /// How many datapacks were loaded.
+    pub fn pack_count(&self) -> usize {
+        self.packs.len()
+    }
+
+    /// Folder names of the loaded datapacks, in load order.
+    pub fn pack_names(&self) -> &[String] {
+        &self.packs
+    }
+}
+
+/// An ID with the `minecraft` namespace filled in if it has none.
+pub fn qualify(id: &str) -> String {
+    if id.contains(':') {
+        id.to_string()
+    } else {
+        format!("minecraft:{}", id)
+    }
+}
+
+/// The commands in a `.mcfunction` file: one per line, `#` comments and
+/// blank lines skipped, lines ending in `\` joined with the next.
+pub fn parse_function(source: &str) -> Result<Vec<String>, String> {
+    let mut commands = Vec::new();
+    let mut pending = String::new();
+    for (number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if pending.is_empty() && (line.is_empty() || line.starts_with('#')) {
+            continue;
+        }
+        if let Some(start) = line.strip_suffix('\\') {
+            pending.push_str(start.trim_start());
+            continue;
+        }
+        pending.push_str(line);
+        let command = std::mem::take(&mut pending);
+        if command.starts_with('$') {
+            return Err(format!("line {}: macro lines are not supported", number + 1));
+        }
+        commands.push(command.strip_prefix('/').unwrap_or(&command).to_string());
+    }
+    if !pending.is_empty() {
+        return Err("the last line ends in \\".into());
+    }
+    Ok(commands)
+}
+
+/// Files under `dir` with the extension, as (path without extension using
+/// `/`, file).
+fn files(dir: &Path, extension: &str) -> Vec<(String, PathBuf)> {
+    let mut found = Vec::new();
+    let mut stack = vec![dir.to_path_buf()];
+    while let Some(current) = stack.pop() {
+        for path in fs::read_dir(&current).into_iter().flatten().flatten().map(|e| e.path()) {
+            if path.is_dir() {
+                stack.push(path);
+            } else if path.extension().and_then(|e| e.to_str()) == Some(extension) {
+                let Ok(relative) = path.strip_prefix(dir) else {
+                    continue;
+                };
+                let relative = relative.with_extension("");
+                let parts: Option<Vec<&str>> = relative.iter().map(|p| p.to_str()).collect();
+                if let Some(parts) = parts {
+                    found.push((parts.join("/"), path.clone()));
+                }
+            }
+        }
+    }
+    found
+}
+
+fn expand_tag(raw: &BTreeMap<String, Vec<String>>, id: &str, seen: &mut HashSet<String>, out: &mut Vec<String>) {
+    if !seen.insert(id.to_string()) {
+        return;
+    }
+    let Some(tag) = raw.get(id) else {
+        return;
+    };
+    for value in tag {
+        match value.strip_prefix('#') {
+            Some(nested) => expand_tag(raw, &qualify(nested), seen, out),
+            None => {
+                let function = qualify(value);
+                if !out.contains(&function) {
+                    out.push(function);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_function() {
+        let source = "# setup\n\nsay hi\n/time set day\nexecute as @a \\\n    run say bye\n";
+        assert_eq!(parse_function(source).unwrap(), ["say hi", "time set day", "execute as @a run say bye"]);
+        assert!(parse_function("$say $(name)").is_err());
+    }
+
+    #[test]
+    fn test_load_packs_and_tags() {
+        let world = std::env::temp_dir().join(format!("pickaxe-datapack-test-{}", std::process::id()));
+        let pack = world.join("datapacks/demo");
+        fs::create_dir_all(pack.join("data/demo/function/util")).unwrap();
+        fs::create_dir_all(pack.join("data/minecraft/tags/function")).unwrap();
+        fs::write(pack.join("pack.mcmeta"), r#"{"pack":{"pack_format":48,"description":""}}"#).unwrap();
+        fs::write(pack.join("data/demo/function/util/greet.mcfunction"), "say hello\n").unwrap();
+        fs::write(pack.join("data/minecraft/tags/function/load.json"), r#"{"values":["demo:util/greet","demo:missing"]}"#).unwrap();
+        fs::write(pack.join("data/minecraft/tags/function/tick.json"), r##"{"values":["#load",{"id":"demo:util/greet"}]}"##).unwrap();
+
+        let library = FunctionLibrary::load(&world);
+        assert_eq!(library.pack_names(), ["demo"]);
+        assert_eq!(library.get("demo:util/greet").as_deref(), Some(&["say hello".to_string()][..]));
+        assert_eq!(library.tag("load"), Some(&["demo:util/greet".to_string()][..]));
+        assert_eq!(library.tag("minecraft:tick"), Some(&["demo:util/greet".to_string()][..]));
+        assert_eq!(library.tag("demo:none"), None);
+        let _ = fs::remove_dir_all(&world);
+    }
+}
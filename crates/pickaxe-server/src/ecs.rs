@@ -1,5 +1,5 @@
 use pickaxe_protocol_core::InternalPacket;
-use pickaxe_types::{BlockPos, GameMode, GameProfile, ItemStack, Vec3d};
+use pickaxe_types::{BlockPos, GameMode, GameProfile, ItemStack, TextComponent, Vec3d};
 use std::collections::{HashMap, HashSet};
 use tokio::sync::mpsc;
 use uuid::Uuid;
@@ -28,20 +28,127 @@ pub struct PlayerGameMode(pub GameMode);
 /// Channel to send packets to this player's network writer task.
 pub struct ConnectionSender(pub mpsc::UnboundedSender<InternalPacket>);
 
+/// The world a player is in, when it isn't the default one (see
+/// `tick::Level`). Players without it are in the overworld.
+pub struct Dimension(pub String);
+
 /// Current chunk coordinates (for chunk streaming).
 pub struct ChunkPosition {
     pub chunk_x: i32,
     pub chunk_z: i32,
 }
 
-/// Player's view distance.
+/// Remote IP address of a player's connection (for IP bans).
+pub struct PeerAddress(pub std::net::IpAddr);
+
+/// Packets and bytes the player's connection has sent and received.
+pub struct NetworkStats(pub crate::net_stats::SharedNetStats);
+
+/// Marks a player who arrived with a Transfer from another server.
+pub struct Transferred;
+
+/// A player's verified chat session, which their signed chat is checked
+/// against.
+pub struct ChatSigner {
+    pub session: pickaxe_protocol_core::ChatSession,
+    pub key: rsa::RsaPublicKey,
+    /// Index the player's next message must have in their chain.
+    pub next_index: i32,
+    /// Timestamp of their last message; messages can't go back in time.
+    pub last_timestamp: i64,
+}
+
+/// Signed messages relayed to a player, for checking what they acknowledge.
+#[derive(Default)]
+pub struct LastSeen(pub crate::chat::LastSeenMessages);
+
+/// The client's brand and the plugin channels it registered.
+#[derive(Debug, Clone, Default)]
+pub struct ClientChannels {
+    pub brand: Option<String>,
+    pub channels: std::collections::HashSet<String>,
+}
+
+impl ClientChannels {
+    /// Track a `minecraft:brand`, `register` or `unregister` message.
+    /// False for any other channel.
+    pub fn record(&mut self, channel: &str, data: &[u8]) -> bool {
+        match channel {
+            crate::channels::BRAND => self.brand = crate::channels::read_brand(data),
+            crate::channels::REGISTER => self.channels.extend(crate::channels::parse_channel_list(data)),
+            crate::channels::UNREGISTER => {
+                for name in crate::channels::parse_channel_list(data) {
+                    self.channels.remove(&name);
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Name of the player `/reply` answers (the last private message partner).
+pub struct ReplyTarget(pub String);
+
+/// Corners picked with `/schem pos1` and `/schem pos2`, for `/schem save`.
+#[derive(Default)]
+pub struct SchematicSelection {
+    pub pos1: Option<BlockPos>,
+    pub pos2: Option<BlockPos>,
+}
+
+/// A player's /sethome destinations by name (saved in playerdata).
+pub struct Homes(pub std::collections::BTreeMap<String, crate::teleports::Location>);
+
+/// Values mods stored with `pickaxe.players.set_data`, by key (saved in playerdata).
+pub struct PlayerModData(pub std::collections::BTreeMap<String, pickaxe_nbt::NbtValue>);
+
+/// Marker: this operator sees other players' private messages.
+pub struct SocialSpy;
+
+/// Marker for the server console's command source. The entity has no
+/// Profile or Position; its command feedback goes to the log.
+pub struct ConsoleSource;
+
+/// Where, as whom and with what authority a command runs. /execute derives
+/// new contexts from the sender's; while a command runs, its context is
+/// attached to the source entity so the command helpers honour it.
+#[derive(Debug, Clone)]
+pub struct ExecutionContext {
+    /// The command's `@s`.
+    pub entity: hecs::Entity,
+    pub position: Vec3d,
+    /// (yaw, pitch)
+    pub rotation: (f32, f32),
+    pub dimension: String,
+    /// Receives command feedback: the player or console that issued it.
+    pub output: hecs::Entity,
+    pub permission_level: u8,
+}
+
+/// Player's view distance: what their client asked for, capped by the server.
 pub struct ViewDistance(pub i32);
 
+/// Chunks around a player that are ticked (crops, random ticks, mob AI).
+pub struct SimulationDistance(pub i32);
+
+/// Options the client last sent in Client Information.
+#[derive(Debug, Clone)]
+pub struct ClientSettings {
+    pub locale: String,
+    /// Render distance the client asked for, in chunks.
+    pub view_distance: i32,
+}
+
 /// Keep-alive tracking for a player connection.
 pub struct KeepAlive {
     pub last_response: std::time::Instant,
     pub last_sent: std::time::Instant,
+    /// When any packet last arrived from the client.
+    pub last_packet: std::time::Instant,
     pub pending: Option<i64>,
+    /// Smoothed round-trip time in milliseconds, as shown in the tab list.
+    pub latency_ms: i32,
 }
 
 impl KeepAlive {
@@ -50,7 +157,60 @@ impl KeepAlive {
         Self {
             last_response: now,
             last_sent: now,
+            last_packet: now,
             pending: None,
+            latency_ms: 0,
+        }
+    }
+
+    /// Fold a keep-alive round trip into the latency (vanilla weights it 1/4).
+    pub fn record_response(&mut self, now: std::time::Instant) {
+        let rtt = now.duration_since(self.last_sent).as_millis().min(60_000) as i32;
+        self.latency_ms = (self.latency_ms * 3 + rtt) / 4;
+        self.pending = None;
+        self.last_response = now;
+    }
+
+    /// Why the connection counts as dead at `now`, if it does: a ping went
+    /// unanswered for `timeout`, or nothing arrived for `idle_timeout`.
+    pub fn timed_out(
+        &self,
+        now: std::time::Instant,
+        timeout: std::time::Duration,
+        idle_timeout: Option<std::time::Duration>,
+    ) -> Option<TimedOut> {
+        if self.pending.is_some() && now.duration_since(self.last_sent) >= timeout {
+            Some(TimedOut::KeepAlive)
+        } else if idle_timeout.is_some_and(|idle| now.duration_since(self.last_packet) >= idle) {
+            Some(TimedOut::Idle)
+        } else {
+            None
+        }
+    }
+}
+
+/// Marks a player disconnected for timing out, so leaving fires
+/// `player_timeout` rather than counting as a clean quit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimedOut {
+    KeepAlive,
+    Idle,
+}
+
+impl TimedOut {
+    /// Reason passed to Lua's `player_timeout` event.
+    pub fn name(self) -> &'static str {
+        match self {
+            TimedOut::KeepAlive => "keep_alive",
+            TimedOut::Idle => "idle",
+        }
+    }
+
+    /// Disconnect screen shown to the player.
+    pub fn message(self) -> TextComponent {
+        match self {
+            TimedOut::KeepAlive => TextComponent::translatable("disconnect.timeout", Vec::new()),
+            TimedOut::Idle => TextComponent::translatable("multiplayer.disconnect.idling", Vec::new()),
         }
     }
 }
@@ -140,6 +300,44 @@ impl Inventory {
         None
     }
 
+    /// Insert as much of `stack` as fits: tops up matching stacks first, then
+    /// fills empty slots, hotbar (36-44) before main inventory (9-35).
+    /// Returns how many items were inserted and which slots changed.
+    pub fn insert_stack(&mut self, stack: &ItemStack, max_stack: i32) -> (i8, Vec<usize>) {
+        let max_stack = max_stack.clamp(1, i8::MAX as i32) as i8;
+        let mut remaining = stack.count;
+        let mut changed = Vec::new();
+        for i in (36..=44).chain(9..=35) {
+            if remaining <= 0 {
+                break;
+            }
+            if let Some(ref existing) = self.slots[i] {
+                if existing.stacks_with(stack) && existing.count < max_stack {
+                    let to_add = remaining.min(max_stack - existing.count);
+                    let mut merged = existing.clone();
+                    merged.count += to_add;
+                    self.set_slot(i, Some(merged));
+                    remaining -= to_add;
+                    changed.push(i);
+                }
+            }
+        }
+        for i in (36..=44).chain(9..=35) {
+            if remaining <= 0 {
+                break;
+            }
+            if self.slots[i].is_none() {
+                let to_add = remaining.min(max_stack);
+                let mut placed = stack.clone();
+                placed.count = to_add;
+                self.set_slot(i, Some(placed));
+                remaining -= to_add;
+                changed.push(i);
+            }
+        }
+        (stack.count - remaining, changed)
+    }
+
     /// Convert to packet format.
     pub fn to_slot_vec(&self) -> Vec<Option<ItemStack>> {
         self.slots.to_vec()
@@ -215,6 +413,13 @@ pub enum Menu {
         rename: Option<String>,
         repair_cost: i32,
     },
+    Lectern { pos: BlockPos },
+    /// A chest-style menu opened by a Lua mod (`pickaxe.gui.open`).
+    Custom {
+        gui_id: u64,
+        /// Rows of 9 slots, 1-6
+        slots: Vec<Option<ItemStack>>,
+    },
 }
 
 /// Tracks the container a player currently has open.
@@ -222,6 +427,9 @@ pub struct OpenContainer {
     pub container_id: u8,
     pub menu: Menu,
     pub state_id: i32,
+    /// The item on the cursor after the last accepted click, restored when
+    /// a click is cancelled
+    pub carried: Option<ItemStack>,
 }
 
 /// Tracks a player actively eating food.
@@ -287,6 +495,30 @@ pub struct SleepingState {
     pub sleep_timer: u32, // ticks spent sleeping; skip at 100
 }
 
+/// A teleport sent to a player that they haven't confirmed yet. Movement they
+/// send meanwhile is from before the teleport and is ignored.
+pub struct PendingTeleport {
+    pub id: i32,
+    pub position: Vec3d,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub flags: u8,
+    /// Tick the teleport was last sent; it is resent after a second
+    pub sent_tick: u64,
+}
+
+impl PendingTeleport {
+    pub fn packet(&self) -> InternalPacket {
+        InternalPacket::SynchronizePlayerPosition {
+            position: self.position,
+            yaw: self.yaw,
+            pitch: self.pitch,
+            flags: self.flags,
+            teleport_id: self.id,
+        }
+    }
+}
+
 /// Marks an entity as a mob with AI.
 pub struct MobEntity {
     pub mob_type: i32,   // entity type ID (from pickaxe_data MOB_* constants)
@@ -299,6 +531,7 @@ pub struct MobEntity {
     pub no_damage_ticks: i32,   // invulnerability after hit
     pub fuse_timer: i32,        // creeper fuse countdown (-1 = not fusing, 0 = explode)
     pub attack_cooldown: u32,   // skeleton arrow / generic attack cooldown
+    pub no_ai: bool,            // NoAI: never moves, targets or attacks
 }
 
 /// Arrow projectile component.
@@ -393,6 +626,85 @@ pub struct TntEntity {
     pub owner: Option<hecs::Entity>,        // who ignited it
 }
 
+/// Armor stand entity component. Armor stands are stationary and have no AI.
+pub struct ArmorStand;
+
+/// Custom name shown above an entity. Named mobs never despawn.
+pub struct CustomName {
+    pub text: TextComponent,
+    pub visible: bool, // always shown, not just when looked at
+}
+
+/// Marker for an entity drawn with the glowing outline.
+pub struct Glowing;
+
+/// Display entity (text, item or block) spawned by a mod. Displays are
+/// client-side renderings with no physics, AI or hitbox.
+pub struct DisplayEntity {
+    pub content: DisplayContent,
+    pub billboard: DisplayBillboard,
+    pub scale: f32,
+    pub glow_color: Option<i32>, // RGB; None uses the team color
+}
+
+/// What a display entity shows.
+pub enum DisplayContent {
+    Text {
+        text: TextComponent,
+        line_width: i32,
+        background: Option<i32>, // ARGB; None uses the client default
+        shadow: bool,
+        see_through: bool,
+    },
+    Item(ItemStack),
+    Block(i32),
+}
+
+impl DisplayContent {
+    /// Protocol entity type ID.
+    pub fn entity_type(&self) -> i32 {
+        match self {
+            DisplayContent::Text { .. } => pickaxe_data::ENTITY_TEXT_DISPLAY,
+            DisplayContent::Item(_) => pickaxe_data::ENTITY_ITEM_DISPLAY,
+            DisplayContent::Block(_) => pickaxe_data::ENTITY_BLOCK_DISPLAY,
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            DisplayContent::Text { .. } => "text_display",
+            DisplayContent::Item(_) => "item_display",
+            DisplayContent::Block(_) => "block_display",
+        }
+    }
+}
+
+/// How a display entity turns to face the viewer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DisplayBillboard {
+    Fixed,
+    Vertical,
+    Horizontal,
+    Center,
+}
+
+impl DisplayBillboard {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "fixed" => Some(Self::Fixed),
+            "vertical" => Some(Self::Vertical),
+            "horizontal" => Some(Self::Horizontal),
+            "center" => Some(Self::Center),
+            _ => None,
+        }
+    }
+
+    /// Protocol billboard constraint byte.
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+}
+
 /// Current AI behavior state for a mob.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum MobAiState {
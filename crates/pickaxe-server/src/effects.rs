@@ -0,0 +1,206 @@
+use pickaxe_protocol_core::InternalPacket;
+use std::collections::HashMap;
+
+/// Most points one shape is spread over; larger shapes are cut short.
+pub const MAX_SHAPE_POINTS: usize = 2048;
+
+/// Sound category names in SoundSource order. The client has a volume slider
+/// for each.
+const SOUND_CATEGORIES: [&str; 10] =
+    ["master", "music", "record", "weather", "block", "hostile", "neutral", "player", "ambient", "voice"];
+
+pub type Point = (f64, f64, f64);
+
+/// The SoundSource ordinal for a category name.
+pub fn sound_category(name: &str) -> Option<u8> {
+    SOUND_CATEGORIES.iter().position(|&c| c == name).map(|i| i as u8)
+}
+
+/// A shape to spread particles over.
+#[derive(Debug, Clone)]
+pub enum ParticleShape {
+    /// Points `step` blocks apart from `from` to `to`, both ends included.
+    Line { from: Point, to: Point, step: f64 },
+    /// `points` points spread evenly over the surface.
+    Sphere { center: Point, radius: f64, points: usize },
+    /// Points `step` blocks apart along the edges, or throughout when `solid`.
+    Cuboid { min: Point, max: Point, step: f64, solid: bool },
+}
+
+impl ParticleShape {
+    pub fn points(&self) -> Vec<Point> {
+        let mut points = Vec::new();
+        match *self {
+            ParticleShape::Line { from, to, step } => {
+                let length = ((to.0 - from.0).powi(2) + (to.1 - from.1).powi(2) + (to.2 - from.2).powi(2)).sqrt();
+                let n = steps(length, step);
+                for i in 0..=n {
+                    let t = if n == 0 { 0.0 } else { i as f64 / n as f64 };
+                    points.push((
+                        from.0 + (to.0 - from.0) * t,
+                        from.1 + (to.1 - from.1) * t,
+                        from.2 + (to.2 - from.2) * t,
+                    ));
+                }
+            }
+            ParticleShape::Sphere { center, radius, points: n } => {
+                // Fibonacci lattice: even coverage without clustering at the poles
+                let golden_angle = std::f64::consts::PI * (3.0 - 5f64.sqrt());
+                let n = n.min(MAX_SHAPE_POINTS);
+                for i in 0..n {
+                    let y = 1.0 - 2.0 * (i as f64 + 0.5) / n as f64;
+                    let ring = (1.0 - y * y).sqrt();
+                    let theta = golden_angle * i as f64;
+                    points.push((
+                        center.0 + radius * ring * theta.cos(),
+                        center.1 + radius * y,
+                        center.2 + radius * ring * theta.sin(),
+                    ));
+                }
+            }
+            ParticleShape::Cuboid { min, max, step, solid } => {
+                let xs = grid(min.0, max.0, step);
+                let ys = grid(min.1, max.1, step);
+                let zs = grid(min.2, max.2, step);
+                let is_end = |i: usize, len: usize| i == 0 || i == len - 1;
+                let z_ends = [zs[0], zs[zs.len() - 1]];
+                let z_ends = if zs.len() == 1 { &z_ends[..1] } else { &z_ends[..] };
+                'outer: for (i, &x) in xs.iter().enumerate() {
+                    for (j, &y) in ys.iter().enumerate() {
+                        // Edge points lie on at least two faces
+                        let faces = is_end(i, xs.len()) as u8 + is_end(j, ys.len()) as u8;
+                        let z_values = match faces {
+                            _ if solid => &zs[..],
+                            2 => &zs[..],
+                            1 => z_ends,
+                            _ => continue,
+                        };
+                        for &z in z_values {
+                            if points.len() >= MAX_SHAPE_POINTS {
+                                break 'outer;
+                            }
+                            points.push((x, y, z));
+                        }
+                    }
+                }
+            }
+        }
+        points.truncate(MAX_SHAPE_POINTS);
+        points
+    }
+}
+
+/// Intervals of about `step` needed to cover `length`.
+fn steps(length: f64, step: f64) -> usize {
+    if length <= 0.0 || step <= 0.0 {
+        return 0;
+    }
+    ((length / step).ceil() as usize).min(MAX_SHAPE_POINTS - 1)
+}
+
+/// Evenly spaced values from `min` to `max`, both included.
+fn grid(min: f64, max: f64, step: f64) -> Vec<f64> {
+    let n = steps(max - min, step);
+    if n == 0 {
+        return vec![min];
+    }
+    (0..=n).map(|i| min + (max - min) * i as f64 / n as f64).collect()
+}
+
+/// How each point of a shape is drawn.
+#[derive(Debug, Clone)]
+pub struct ParticleEffect {
+    pub particle_id: i32,
+    /// Particles per point.
+    pub count: i32,
+    pub offset: (f32, f32, f32),
+    pub speed: f32,
+}
+
+impl ParticleEffect {
+    pub fn packet(&self, (x, y, z): Point) -> InternalPacket {
+        InternalPacket::LevelParticles {
+            particle_id: self.particle_id,
+            long_distance: true,
+            x,
+            y,
+            z,
+            offset_x: self.offset.0,
+            offset_y: self.offset.1,
+            offset_z: self.offset.2,
+            max_speed: self.speed,
+            count: self.count,
+        }
+    }
+}
+
+/// A shape redrawn every `interval` ticks, started with `pickaxe.particles.emitter`.
+pub struct ParticleEmitter {
+    pub effect: ParticleEffect,
+    pub points: Vec<Point>,
+    /// Players who see it, by name; everyone when empty.
+    pub players: Vec<String>,
+    pub interval: u64,
+    pub started: u64,
+    /// Last tick it is drawn on; None runs until stopped.
+    pub until: Option<u64>,
+}
+
+#[derive(Default)]
+pub struct ParticleEmitters {
+    emitters: HashMap<u64, ParticleEmitter>,
+    next_id: u64,
+}
+
+impl ParticleEmitters {
+    pub fn add(&mut self, emitter: ParticleEmitter) -> u64 {
+        self.next_id += 1;
+        self.emitters.insert(self.next_id, emitter);
+        self.next_id
+    }
+
+    /// Returns whether the emitter existed.
+    pub fn remove(&mut self, id: u64) -> bool {
+        self.emitters.remove(&id).is_some()
+    }
+
+    /// Emitters to draw on `tick`, after dropping expired ones.
+    pub fn due(&mut self, tick: u64) -> impl Iterator<Item = &ParticleEmitter> {
+        self.emitters.retain(|_, e| e.until.is_none_or(|until| tick <= until));
+        self.emitters.values().filter(move |e| (tick - e.started) % e.interval.max(1) == 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shape_points() {
+        let line = ParticleShape::Line { from: (0.0, 0.0, 0.0), to: (2.0, 0.0, 0.0), step: 0.5 };
+        let points = line.points();
+        assert_eq!(points.len(), 5);
+        assert_eq!(points[4], (2.0, 0.0, 0.0));
+
+        let sphere = ParticleShape::Sphere { center: (0.0, 64.0, 0.0), radius: 3.0, points: 40 };
+        let points = sphere.points();
+        assert_eq!(points.len(), 40);
+        assert!(points.iter().all(|&(x, y, z)| ((x * x + (y - 64.0).powi(2) + z * z).sqrt() - 3.0).abs() < 1e-9));
+
+        // 3x3x3 grid points; the edges skip the centre and the 6 face centres
+        let cube = |solid| ParticleShape::Cuboid { min: (0.0, 0.0, 0.0), max: (2.0, 2.0, 2.0), step: 1.0, solid };
+        assert_eq!(cube(false).points().len(), 20);
+        assert_eq!(cube(true).points().len(), 27);
+
+        let huge = ParticleShape::Cuboid { min: (0.0, 0.0, 0.0), max: (100.0, 100.0, 100.0), step: 0.1, solid: true };
+        assert_eq!(huge.points().len(), MAX_SHAPE_POINTS);
+    }
+
+    #[test]
+    fn test_sound_category() {
+        assert_eq!(sound_category("master"), Some(0));
+        assert_eq!(sound_category("hostile"), Some(5));
+        assert_eq!(sound_category("voice"), Some(9));
+        assert_eq!(sound_category("loud"), None);
+    }
+}
@@ -0,0 +1,133 @@
+use crate::selector::{self, EntitySelector};
+
+/// One link of an /execute chain. Coordinates and rotations are kept raw,
+/// since `~` and `^` resolve against the context each link runs in.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step<'a> {
+    /// `as <targets>` — run as each matched entity.
+    As(EntitySelector),
+    /// `at <targets>` — run from each matched entity's position and rotation.
+    At(EntitySelector),
+    /// `positioned <x> <y> <z>`
+    Positioned([&'a str; 3]),
+    /// `positioned as <targets>`
+    PositionedAs(EntitySelector),
+    /// `rotated <yaw> <pitch>`
+    Rotated([&'a str; 2]),
+    /// `rotated as <targets>`
+    RotatedAs(EntitySelector),
+    /// `if|unless block <x> <y> <z> <block>`; `block` has no namespace.
+    IfBlock { negate: bool, pos: [&'a str; 3], block: &'a str },
+    /// `if|unless entity <targets>`
+    IfEntity { negate: bool, selector: EntitySelector },
+}
+
+/// A parsed /execute command: the context modifiers and conditions, then the
+/// command to run (without a leading `/`). Without `run`, the chain is a test.
+#[derive(Debug, PartialEq)]
+pub struct Chain<'a> {
+    pub steps: Vec<Step<'a>>,
+    pub run: Option<&'a str>,
+}
+
+pub fn parse(args: &str) -> Result<Chain<'_>, String> {
+    let tokens = crate::tick::split_command_args(args);
+    let mut steps = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        match tokens[i] {
+            "run" => {
+                let command = arg(&tokens, i + 1)?;
+                // Keep the command's own spacing by slicing the original input
+                let start = command.as_ptr() as usize - args.as_ptr() as usize;
+                let command = args[start..].trim_end();
+                return Ok(Chain { steps, run: Some(command.strip_prefix('/').unwrap_or(command)) });
+            }
+            "as" => {
+                steps.push(Step::As(selector_arg(&tokens, i + 1)?));
+                i += 2;
+            }
+            "at" => {
+                steps.push(Step::At(selector_arg(&tokens, i + 1)?));
+                i += 2;
+            }
+            "positioned" if tokens.get(i + 1) == Some(&"as") => {
+                steps.push(Step::PositionedAs(selector_arg(&tokens, i + 2)?));
+                i += 3;
+            }
+            "positioned" => {
+                steps.push(Step::Positioned([arg(&tokens, i + 1)?, arg(&tokens, i + 2)?, arg(&tokens, i + 3)?]));
+                i += 4;
+            }
+            "rotated" if tokens.get(i + 1) == Some(&"as") => {
+                steps.push(Step::RotatedAs(selector_arg(&tokens, i + 2)?));
+                i += 3;
+            }
+            "rotated" => {
+                steps.push(Step::Rotated([arg(&tokens, i + 1)?, arg(&tokens, i + 2)?]));
+                i += 3;
+            }
+            keyword @ ("if" | "unless") => {
+                let negate = keyword == "unless";
+                match arg(&tokens, i + 1)? {
+                    "block" => {
+                        let pos = [arg(&tokens, i + 2)?, arg(&tokens, i + 3)?, arg(&tokens, i + 4)?];
+                        let raw = arg(&tokens, i + 5)?;
+                        let block = raw.strip_prefix("minecraft:").unwrap_or(raw);
+                        if pickaxe_data::block_name_to_default_state(block).is_none() {
+                            return Err(format!("Unknown block type: {}", raw));
+                        }
+                        steps.push(Step::IfBlock { negate, pos, block });
+                        i += 6;
+                    }
+                    "entity" => {
+                        steps.push(Step::IfEntity { negate, selector: selector_arg(&tokens, i + 2)? });
+                        i += 3;
+                    }
+                    other => return Err(format!("Unknown /execute condition: {}", other)),
+                }
+            }
+            other => return Err(format!("Unknown /execute subcommand: {}", other)),
+        }
+    }
+    Ok(Chain { steps, run: None })
+}
+
+fn arg<'a>(tokens: &[&'a str], index: usize) -> Result<&'a str, String> {
+    tokens
+        .get(index)
+        .copied()
+        .ok_or_else(|| "Incomplete /execute command".to_string())
+}
+
+fn selector_arg(tokens: &[&str], index: usize) -> Result<EntitySelector, String> {
+    selector::parse(arg(tokens, index)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_chain() {
+        let chain = parse("as @a[distance=..5] at @s positioned ~ ~1 ~ if block ~ ~-1 ~ minecraft:stone run say  hi  there").unwrap();
+        assert_eq!(chain.steps.len(), 4);
+        assert!(matches!(chain.steps[0], Step::As(_)));
+        assert_eq!(chain.steps[2], Step::Positioned(["~", "~1", "~"]));
+        assert_eq!(chain.steps[3], Step::IfBlock { negate: false, pos: ["~", "~-1", "~"], block: "stone" });
+        assert_eq!(chain.run, Some("say  hi  there"));
+
+        let test = parse("unless entity @e[type=zombie] rotated as @p").unwrap();
+        assert!(matches!(test.steps[0], Step::IfEntity { negate: true, .. }));
+        assert!(matches!(test.steps[1], Step::RotatedAs(_)));
+        assert_eq!(test.run, None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("positioned ~ ~").is_err());
+        assert!(parse("if block 0 0 0 not_a_block").is_err());
+        assert!(parse("facing 0 0 0").is_err());
+        assert!(parse("as @a run").is_err());
+    }
+}
@@ -0,0 +1,191 @@
+use pickaxe_nbt::NbtValue;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A gamerule value. Vanilla rules are either booleans or integers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameRuleValue {
+    Bool(bool),
+    Int(i32),
+}
+
+impl GameRuleValue {
+    /// Parse `raw` as the same kind of value as `self`.
+    fn parse_like(&self, raw: &str) -> Option<GameRuleValue> {
+        match self {
+            GameRuleValue::Bool(_) => raw.parse().ok().map(GameRuleValue::Bool),
+            GameRuleValue::Int(_) => raw.parse().ok().map(GameRuleValue::Int),
+        }
+    }
+}
+
+impl fmt::Display for GameRuleValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameRuleValue::Bool(v) => write!(f, "{}", v),
+            GameRuleValue::Int(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// Vanilla gamerules and their defaults (MC 1.21.1).
+const DEFAULTS: &[(&str, GameRuleValue)] = &[
+    ("announceAdvancements", GameRuleValue::Bool(true)),
+    ("blockExplosionDropDecay", GameRuleValue::Bool(true)),
+    ("commandBlockOutput", GameRuleValue::Bool(true)),
+    ("disableElytraMovementCheck", GameRuleValue::Bool(false)),
+    ("disableRaids", GameRuleValue::Bool(false)),
+    ("doDaylightCycle", GameRuleValue::Bool(true)),
+    ("doEntityDrops", GameRuleValue::Bool(true)),
+    ("doFireTick", GameRuleValue::Bool(true)),
+    ("doImmediateRespawn", GameRuleValue::Bool(false)),
+    ("doInsomnia", GameRuleValue::Bool(true)),
+    ("doLimitedCrafting", GameRuleValue::Bool(false)),
+    ("doMobLoot", GameRuleValue::Bool(true)),
+    ("doMobSpawning", GameRuleValue::Bool(true)),
+    ("doPatrolSpawning", GameRuleValue::Bool(true)),
+    ("doTileDrops", GameRuleValue::Bool(true)),
+    ("doTraderSpawning", GameRuleValue::Bool(true)),
+    ("doVinesSpread", GameRuleValue::Bool(true)),
+    ("doWardenSpawning", GameRuleValue::Bool(true)),
+    ("doWeatherCycle", GameRuleValue::Bool(true)),
+    ("drowningDamage", GameRuleValue::Bool(true)),
+    ("enderPearlsVanishOnDeath", GameRuleValue::Bool(true)),
+    ("fallDamage", GameRuleValue::Bool(true)),
+    ("fireDamage", GameRuleValue::Bool(true)),
+    ("forgiveDeadPlayers", GameRuleValue::Bool(true)),
+    ("freezeDamage", GameRuleValue::Bool(true)),
+    ("globalSoundEvents", GameRuleValue::Bool(true)),
+    ("keepInventory", GameRuleValue::Bool(false)),
+    ("lavaSourceConversion", GameRuleValue::Bool(false)),
+    ("logAdminCommands", GameRuleValue::Bool(true)),
+    ("maxCommandChainLength", GameRuleValue::Int(65536)),
+    ("maxEntityCramming", GameRuleValue::Int(24)),
+    ("mobExplosionDropDecay", GameRuleValue::Bool(true)),
+    ("mobGriefing", GameRuleValue::Bool(true)),
+    ("naturalRegeneration", GameRuleValue::Bool(true)),
+    ("playersSleepingPercentage", GameRuleValue::Int(100)),
+    ("randomTickSpeed", GameRuleValue::Int(3)),
+    ("reducedDebugInfo", GameRuleValue::Bool(false)),
+    ("sendCommandFeedback", GameRuleValue::Bool(true)),
+    ("showDeathMessages", GameRuleValue::Bool(true)),
+    ("snowAccumulationHeight", GameRuleValue::Int(1)),
+    ("spawnRadius", GameRuleValue::Int(10)),
+    ("spectatorsGenerateChunks", GameRuleValue::Bool(true)),
+    ("tntExplosionDropDecay", GameRuleValue::Bool(false)),
+    ("universalAnger", GameRuleValue::Bool(false)),
+    ("waterSourceConversion", GameRuleValue::Bool(true)),
+];
+
+/// World gamerules, stored in level.dat under `Data.GameRules`.
+pub struct GameRules {
+    values: BTreeMap<&'static str, GameRuleValue>,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameRules {
+    pub fn new() -> Self {
+        Self {
+            values: DEFAULTS.iter().copied().collect(),
+        }
+    }
+
+    /// All rule names, sorted.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.values.keys().copied()
+    }
+
+    pub fn get(&self, name: &str) -> Option<GameRuleValue> {
+        self.values.get(name).copied()
+    }
+
+    /// Value of a boolean rule (false for unknown or integer rules).
+    pub fn get_bool(&self, name: &str) -> bool {
+        matches!(self.get(name), Some(GameRuleValue::Bool(true)))
+    }
+
+    /// Value of an integer rule (0 for unknown or boolean rules).
+    pub fn get_int(&self, name: &str) -> i32 {
+        match self.get(name) {
+            Some(GameRuleValue::Int(v)) => v,
+            _ => 0,
+        }
+    }
+
+    /// Set a rule from its string form, returning the new value.
+    pub fn set(&mut self, name: &str, raw: &str) -> Result<GameRuleValue, String> {
+        let Some((&key, current)) = self.values.get_key_value(name) else {
+            return Err(format!("Unknown gamerule: {}", name));
+        };
+        let value = current.parse_like(raw).ok_or_else(|| match current {
+            GameRuleValue::Bool(_) => format!("Invalid boolean '{}', expected true or false", raw),
+            GameRuleValue::Int(_) => format!("Invalid integer '{}'", raw),
+        })?;
+        self.values.insert(key, value);
+        Ok(value)
+    }
+
+    /// Serialize as the level.dat `GameRules` compound (vanilla stores strings).
+    pub fn to_nbt(&self) -> NbtValue {
+        NbtValue::Compound(
+            self.values
+                .iter()
+                .map(|(name, value)| (name.to_string(), NbtValue::String(value.to_string())))
+                .collect(),
+        )
+    }
+
+    /// Load rules from a level.dat `GameRules` compound. Unknown rules and
+    /// unparseable values are ignored, keeping the default.
+    pub fn load_nbt(&mut self, nbt: &NbtValue) {
+        let NbtValue::Compound(entries) = nbt else {
+            return;
+        };
+        for (name, value) in entries {
+            if let Some(raw) = value.as_str() {
+                let _ = self.set(name, raw);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_and_set() {
+        let mut rules = GameRules::new();
+        assert!(!rules.get_bool("keepInventory"));
+        assert_eq!(rules.get_int("randomTickSpeed"), 3);
+
+        assert_eq!(rules.set("keepInventory", "true"), Ok(GameRuleValue::Bool(true)));
+        assert!(rules.get_bool("keepInventory"));
+        assert_eq!(rules.set("randomTickSpeed", "10"), Ok(GameRuleValue::Int(10)));
+        assert_eq!(rules.get_int("randomTickSpeed"), 10);
+
+        assert!(rules.set("keepInventory", "yes").is_err());
+        assert!(rules.set("randomTickSpeed", "fast").is_err());
+        assert!(rules.set("noSuchRule", "true").is_err());
+    }
+
+    #[test]
+    fn test_nbt_roundtrip() {
+        let mut rules = GameRules::new();
+        rules.set("doDaylightCycle", "false").unwrap();
+        rules.set("playersSleepingPercentage", "50").unwrap();
+        let nbt = rules.to_nbt();
+        assert_eq!(nbt.get("doDaylightCycle").and_then(|v| v.as_str()), Some("false"));
+
+        let mut loaded = GameRules::new();
+        loaded.load_nbt(&nbt);
+        assert!(!loaded.get_bool("doDaylightCycle"));
+        assert_eq!(loaded.get_int("playersSleepingPercentage"), 50);
+        assert!(loaded.get_bool("doFireTick"));
+    }
+}
@@ -0,0 +1,211 @@
+use crate::config::HttpConfig;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// Window for `requests_per_minute`.
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// A request made with `pickaxe.http.get` or `pickaxe.http.post`.
+pub struct HttpRequest {
+    pub post: bool,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+/// A response, or why the request failed.
+pub type HttpResult = Result<HttpResponse, String>;
+
+/// Whether `host` is on the allow-list. Entries match exactly; `*.example.com`
+/// also matches `example.com` and any subdomain.
+pub fn domain_allowed(allowed: &[String], host: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    allowed.iter().any(|entry| {
+        let entry = entry.to_ascii_lowercase();
+        match entry.strip_prefix("*.") {
+            Some(base) => host == base || host.strip_suffix(base).is_some_and(|sub| sub.ends_with('.')),
+            None => host == entry,
+        }
+    })
+}
+
+/// Sliding-window request limit.
+pub struct RateLimiter {
+    limit: usize,
+    recent: VecDeque<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: u32) -> Self {
+        Self { limit: limit as usize, recent: VecDeque::new() }
+    }
+
+    /// Count a request made at `now`, unless the window is already full.
+    pub fn try_acquire(&mut self, now: Instant) -> bool {
+        while self.recent.front().is_some_and(|&t| now.duration_since(t) >= RATE_WINDOW) {
+            self.recent.pop_front();
+        }
+        if self.recent.len() >= self.limit {
+            return false;
+        }
+        self.recent.push_back(now);
+        true
+    }
+}
+
+/// Outbound HTTP for Lua mods, configured by `[http]`. Requests run on the
+/// Tokio runtime; the tick loop collects finished ones with `take_completed`
+/// and runs their callbacks.
+pub struct HttpClient {
+    allowed_domains: Vec<String>,
+    max_response_bytes: u64,
+    /// None when disabled.
+    client: Option<reqwest::Client>,
+    limiter: RateLimiter,
+    next_id: u64,
+    callbacks: HashMap<u64, mlua::RegistryKey>,
+    results_tx: mpsc::UnboundedSender<(u64, HttpResult)>,
+    results_rx: mpsc::UnboundedReceiver<(u64, HttpResult)>,
+}
+
+/// Shared between the Lua API and the tick loop.
+pub type SharedHttpClient = Arc<Mutex<HttpClient>>;
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        Self::new(&HttpConfig::default())
+    }
+}
+
+impl HttpClient {
+    pub fn new(config: &HttpConfig) -> Self {
+        let client = if config.enabled {
+            // Redirects aren't followed, so a response can't lead off the allow-list
+            reqwest::Client::builder()
+                .timeout(Duration::from_secs(config.timeout_seconds))
+                .redirect(reqwest::redirect::Policy::none())
+                .build()
+                .map_err(|e| tracing::error!("Failed to create HTTP client: {}", e))
+                .ok()
+        } else {
+            None
+        };
+        let (results_tx, results_rx) = mpsc::unbounded_channel();
+        Self {
+            allowed_domains: config.allowed_domains.clone(),
+            max_response_bytes: config.max_response_kb * 1024,
+            client,
+            limiter: RateLimiter::new(config.requests_per_minute),
+            next_id: 0,
+            callbacks: HashMap::new(),
+            results_tx,
+            results_rx,
+        }
+    }
+
+    /// Check a request against the config and start it; `callback` is
+    /// returned with the result from `take_completed`. Returns the request id.
+    pub fn send(&mut self, request: HttpRequest, callback: mlua::RegistryKey) -> Result<u64, String> {
+        let Some(client) = &self.client else {
+            return Err("HTTP requests are disabled in server.toml".into());
+        };
+        let url = reqwest::Url::parse(&request.url).map_err(|e| format!("invalid URL: {}", e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err("only http and https URLs are allowed".into());
+        }
+        let host = url.host_str().unwrap_or_default();
+        if !domain_allowed(&self.allowed_domains, host) {
+            return Err(format!("host '{}' is not in allowed_domains", host));
+        }
+        if !self.limiter.try_acquire(Instant::now()) {
+            return Err("rate limit reached".into());
+        }
+
+        let mut builder = if request.post { client.post(url) } else { client.get(url) };
+        for (name, value) in request.headers {
+            builder = builder.header(name, value);
+        }
+        if let Some(body) = request.body {
+            builder = builder.body(body);
+        }
+        self.next_id += 1;
+        let id = self.next_id;
+        self.callbacks.insert(id, callback);
+        let max_bytes = self.max_response_bytes;
+        let results_tx = self.results_tx.clone();
+        tokio::spawn(async move {
+            let result = perform(builder, max_bytes).await;
+            let _ = results_tx.send((id, result));
+        });
+        Ok(id)
+    }
+
+    /// Finished requests, each with the callback it was sent with.
+    pub fn take_completed(&mut self) -> Vec<(mlua::RegistryKey, HttpResult)> {
+        let mut completed = Vec::new();
+        while let Ok((id, result)) = self.results_rx.try_recv() {
+            if let Some(callback) = self.callbacks.remove(&id) {
+                completed.push((callback, result));
+            }
+        }
+        completed
+    }
+}
+
+async fn perform(builder: reqwest::RequestBuilder, max_bytes: u64) -> HttpResult {
+    let mut response = builder.send().await.map_err(|e| e.to_string())?;
+    let too_large = || format!("response larger than {} KB", max_bytes / 1024);
+    if response.content_length().is_some_and(|len| len > max_bytes) {
+        return Err(too_large());
+    }
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned()))
+        .collect();
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        if (body.len() + chunk.len()) as u64 > max_bytes {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(HttpResponse { status, headers, body: String::from_utf8_lossy(&body).into_owned() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain_allowed() {
+        let allowed = vec!["api.example.com".to_string(), "*.discord.com".to_string()];
+        assert!(domain_allowed(&allowed, "api.example.com"));
+        assert!(domain_allowed(&allowed, "API.Example.com"));
+        assert!(!domain_allowed(&allowed, "example.com"));
+        assert!(domain_allowed(&allowed, "discord.com"));
+        assert!(domain_allowed(&allowed, "canary.discord.com"));
+        assert!(!domain_allowed(&allowed, "evildiscord.com"));
+        assert!(!domain_allowed(&[], "api.example.com"));
+    }
+
+    #[test]
+    fn test_rate_limiter_window() {
+        let mut limiter = RateLimiter::new(2);
+        let start = Instant::now();
+        assert!(limiter.try_acquire(start));
+        assert!(limiter.try_acquire(start + Duration::from_secs(10)));
+        assert!(!limiter.try_acquire(start + Duration::from_secs(30)));
+        assert!(limiter.try_acquire(start + Duration::from_secs(60)));
+        assert!(!limiter.try_acquire(start + Duration::from_secs(65)));
+    }
+}
@@ -0,0 +1,60 @@
+use crate::status::SharedServerList;
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tracing::{debug, info, warn};
+
+/// Where singleplayer worlds opened to LAN are announced.
+const LAN_GROUP: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(224, 0, 2, 60), 4445);
+
+/// Time between announcements, as vanilla.
+const INTERVAL: Duration = Duration::from_millis(1500);
+
+/// Announce the server to the LAN section of the multiplayer screen, the
+/// way an open-to-LAN world does.
+pub async fn announce(server_list: SharedServerList, port: u16) {
+    let socket = match UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            warn!("Couldn't open a socket for LAN announcements: {}", e);
+            return;
+        }
+    };
+    info!("Announcing the server on the local network");
+    let mut interval = tokio::time::interval(INTERVAL);
+    loop {
+        interval.tick().await;
+        let motd = server_list.lock().map(|info| info.motd.clone()).unwrap_or_default();
+        if let Err(e) = socket.send_to(announcement(&motd, port).as_bytes(), LAN_GROUP).await {
+            debug!("LAN announcement failed: {}", e);
+        }
+    }
+}
+
+/// The datagram an open-to-LAN world sends. Clients show the MOTD's first
+/// line, with `&` color codes as `§` codes, and join on the sender's address.
+pub fn announcement(motd: &str, port: u16) -> String {
+    let line = motd.lines().next().unwrap_or("");
+    let mut text = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        let is_code = chars
+            .peek()
+            .is_some_and(|code| matches!(code.to_ascii_lowercase(), '0'..='9' | 'a'..='f' | 'k'..='o' | 'r'));
+        text.push(if c == '&' && is_code { '§' } else { c });
+    }
+    format!("[MOTD]{}[/MOTD][AD]{}[/AD]", text, port)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_announcement() {
+        assert_eq!(
+            announcement("&aPickaxe & friends\nSecond line", 25565),
+            "[MOTD]§aPickaxe & friends[/MOTD][AD]25565[/AD]"
+        );
+    }
+}
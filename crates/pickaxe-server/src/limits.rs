@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Window packet rates are measured over, so short bursts (joining, opening
+/// inventories) don't trip the limit.
+const PACKET_WINDOW: Duration = Duration::from_secs(5);
+
+/// Counts a player's inbound packets in fixed windows.
+pub struct PacketRateLimiter {
+    /// Packets allowed per window; 0 disables the limit.
+    limit: u64,
+    window_start: Instant,
+    count: u64,
+}
+
+impl PacketRateLimiter {
+    pub fn new(packets_per_second: u32, now: Instant) -> Self {
+        Self {
+            limit: packets_per_second as u64 * PACKET_WINDOW.as_secs(),
+            window_start: now,
+            count: 0,
+        }
+    }
+
+    /// Count a packet received at `now`. False once the window's limit is
+    /// exceeded.
+    pub fn record(&mut self, now: Instant) -> bool {
+        if self.limit == 0 {
+            return true;
+        }
+        if now.duration_since(self.window_start) >= PACKET_WINDOW {
+            self.window_start = now;
+            self.count = 0;
+        }
+        self.count += 1;
+        self.count <= self.limit
+    }
+}
+
+/// Minimum time between login attempts from one IP address.
+pub struct LoginThrottle {
+    /// Zero disables throttling.
+    interval: Duration,
+    last_attempt: HashMap<IpAddr, Instant>,
+}
+
+/// Shared by every connection's login.
+pub type SharedLoginThrottle = Arc<Mutex<LoginThrottle>>;
+
+impl LoginThrottle {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_attempt: HashMap::new(),
+        }
+    }
+
+    /// Record a login attempt from `ip` at `now`. False if the previous one
+    /// was too recent; a refused attempt doesn't restart the wait.
+    pub fn try_login(&mut self, ip: IpAddr, now: Instant) -> bool {
+        if self.interval.is_zero() {
+            return true;
+        }
+        // Forget addresses whose wait is over so the map stays small
+        let interval = self.interval;
+        self.last_attempt.retain(|_, last| now.duration_since(*last) < interval);
+        if self.last_attempt.contains_key(&ip) {
+            return false;
+        }
+        self.last_attempt.insert(ip, now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packet_rate_limiter() {
+        let start = Instant::now();
+        let mut limiter = PacketRateLimiter::new(2, start);
+        for _ in 0..10 {
+            assert!(limiter.record(start));
+        }
+        assert!(!limiter.record(start + Duration::from_secs(1)));
+        // A new window starts over
+        assert!(limiter.record(start + PACKET_WINDOW));
+    }
+
+    #[test]
+    fn test_login_throttle() {
+        let start = Instant::now();
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let mut throttle = LoginThrottle::new(Duration::from_secs(4));
+        assert!(throttle.try_login(ip, start));
+        assert!(!throttle.try_login(ip, start + Duration::from_secs(1)));
+        assert!(throttle.try_login("203.0.113.8".parse().unwrap(), start + Duration::from_secs(1)));
+        assert!(throttle.try_login(ip, start + Duration::from_secs(4)));
+    }
+}
@@ -1,17 +1,61 @@
+mod access;
+mod advancements;
+mod afk;
+mod audit;
+mod auth;
+mod backup;
+mod block_behavior;
 mod bridge;
+mod channels;
+mod chat;
+mod chunk_sender;
+mod command_args;
 mod config;
+mod cookies;
+mod datapacks;
+mod gamerules;
 mod ecs;
+mod effects;
+mod execute;
+mod http;
+mod lan;
+mod limits;
+mod messaging;
+mod metrics;
+mod net_stats;
 mod network;
+mod overload;
+mod placeholders;
+mod playerdata;
+mod profiler;
+mod proxy;
+mod raycast;
+mod resource_pack;
+mod save_stats;
+mod schematics;
+mod scoreboard;
+mod selector;
+mod status;
+mod stats;
+mod storage;
+mod structures;
+mod tablist;
+mod teleports;
 mod tick;
+mod tick_rate;
+mod tpa;
+mod tracking;
 
 use config::ServerConfig;
+use pickaxe_scripting::sandbox::SandboxLimits;
 use pickaxe_scripting::ScriptRuntime;
 use std::path::Path;
 use std::sync::atomic::{AtomicI32, AtomicUsize};
 use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::net::TcpListener;
 use tokio::sync::mpsc;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -29,15 +73,32 @@ async fn main() -> anyhow::Result<()> {
         "Config loaded: bind={}:{}, max_players={}, online_mode={}",
         config.bind, config.port, config.max_players, config.online_mode
     );
+    access::set_whitelist_enabled(config.whitelist);
+    access::reload_ops();
+    tpa::configure(&config.tpa);
+    teleports::configure(&config.teleports);
 
     // Shared entity ID counter
     let next_eid = Arc::new(AtomicI32::new(1));
 
     // Initialize Lua scripting (must stay on this thread — Lua VM is !Send)
     let scripting = ScriptRuntime::new()?;
-    // Shared storage for Lua-registered commands and block overrides
+    scripting.set_limits(SandboxLimits {
+        instructions_per_tick: config.scripting.instructions_per_tick,
+        memory_bytes: (config.scripting.memory_limit_mb as usize) << 20,
+        max_violations: config.scripting.max_violations,
+    });
+    scripting.timings().set_slow_handler_ms(config.scripting.slow_handler_ms);
+    // Shared storage for Lua-registered commands, block overrides and custom items
     let lua_commands: bridge::LuaCommands = Arc::new(Mutex::new(Vec::new()));
     let block_overrides: bridge::BlockOverrides = Arc::new(Mutex::new(std::collections::HashMap::new()));
+    let custom_items: bridge::LuaCustomItems = Arc::new(Mutex::new(std::collections::HashMap::new()));
+    let placeholders: bridge::LuaPlaceholders = Arc::new(Mutex::new(std::collections::HashMap::new()));
+    let lua_channels: bridge::LuaChannels = Arc::new(Mutex::new(std::collections::HashMap::new()));
+    // Mod key-value storage, loaded before mods so init.lua can read it
+    let storage_dir = Path::new(&config.world_dir).join("data").join(storage::STORAGE_DIR);
+    let mod_storage: storage::SharedModStorage = Arc::new(Mutex::new(storage::ModStorage::load(&storage_dir)));
+    let http_client: http::SharedHttpClient = Arc::new(Mutex::new(http::HttpClient::new(&config.http)));
     // Register bridge APIs before mods load so they're available in init.lua
     bridge::register_world_api(scripting.lua())?;
     bridge::register_players_api(scripting.lua())?;
@@ -46,6 +107,22 @@ async fn main() -> anyhow::Result<()> {
     bridge::register_entities_api(scripting.lua(), next_eid.clone())?;
     bridge::register_sounds_api(scripting.lua())?;
     bridge::register_particles_api(scripting.lua())?;
+    bridge::register_scoreboard_api(scripting.lua())?;
+    bridge::register_text_api(scripting.lua())?;
+    bridge::register_gui_api(scripting.lua())?;
+    bridge::register_items_api(scripting.lua(), custom_items.clone())?;
+    bridge::register_storage_api(scripting.lua(), mod_storage.clone())?;
+    bridge::register_http_api(scripting.lua(), http_client.clone())?;
+    bridge::register_placeholders_api(scripting.lua(), placeholders.clone())?;
+    bridge::register_channels_api(scripting.lua(), lua_channels.clone())?;
+    bridge::register_worlds_api(scripting.lua())?;
+
+    // `--dump-lua-api [dir]` writes the API reference instead of starting
+    let mut args = std::env::args().skip_while(|arg| arg != "--dump-lua-api");
+    if args.next().is_some() {
+        let dir = args.next().unwrap_or_else(|| "lua-api".into());
+        return dump_lua_api(scripting.lua(), Path::new(&dir));
+    }
     scripting.load_mods(&[Path::new("lua")])?;
 
     // Fire server_start event synchronously
@@ -56,27 +133,119 @@ async fn main() -> anyhow::Result<()> {
     // Channel for new players entering play state
     let (new_player_tx, new_player_rx) = mpsc::unbounded_channel::<tick::NewPlayer>();
 
-    // Player count for status responses
+    // Player count, MOTD, player sample and icon for status responses
     let player_count = Arc::new(AtomicUsize::new(0));
+    let server_list: status::SharedServerList = Arc::new(Mutex::new(status::ServerListInfo {
+        motd: config.motd.clone(),
+        sample: Vec::new(),
+        favicon: status::load_favicon(Path::new(&config.status.icon)),
+    }));
+
+    // Refuses rapid reconnects from one address
+    let login_throttle: limits::SharedLoginThrottle = Arc::new(Mutex::new(limits::LoginThrottle::new(
+        std::time::Duration::from_millis(config.limits.login_throttle_ms),
+    )));
+
+    // Online mode verifies players with the Mojang session server, unless a
+    // proxy has already done so
+    let listeners = config.listeners();
+    for listener in listeners.iter().filter(|l| l.proxy_mode() != config::ProxyMode::None) {
+        if listener.proxy_mode() == config::ProxyMode::Velocity && config.proxy.secret.is_empty() {
+            anyhow::bail!("proxy.secret must be set to Velocity's forwarding secret");
+        }
+        info!(
+            "Accepting players forwarded by a {:?} proxy on {}:{}",
+            listener.proxy_mode(),
+            listener.bind,
+            listener.port
+        );
+    }
+    let direct = listeners.iter().any(|l| l.proxy_mode() == config::ProxyMode::None);
+    let session_auth = if !direct {
+        None
+    } else if config.online_mode {
+        info!("Generating keypair for online mode");
+        Some(Arc::new(auth::SessionAuth::new()?))
+    } else {
+        warn!("Running in offline mode: player names and UUIDs are not verified");
+        None
+    };
+
+    // Signed chat needs Mojang accounts, whose chat keys Mojang signs
+    let secure_chat = if !config.chat.secure {
+        chat::SecureChat::default()
+    } else if !config.online_mode && listeners.iter().all(|l| l.proxy_mode() == config::ProxyMode::None) {
+        info!("Chat is unsigned in offline mode");
+        chat::SecureChat::default()
+    } else {
+        match chat::SecureChat::fetch().await {
+            Ok(secure_chat) => secure_chat,
+            Err(e) => {
+                warn!("Couldn't fetch Mojang's public keys, chat will be unsigned: {}", e);
+                chat::SecureChat::default()
+            }
+        }
+    };
+
+    // TCP listeners, each accepting players on its own task
+    let mut accept_loops = tokio::task::JoinSet::new();
+    for listener_config in &listeners {
+        let listener = network::listen(&listener_config.bind, listener_config.port)
+            .await
+            .map_err(|e| anyhow::anyhow!("Couldn't listen on {}:{}: {}", listener_config.bind, listener_config.port, e))?;
+        info!("Listening on {}", listener.local_addr()?);
+        // Proxied players were authenticated by the proxy
+        let session_auth = session_auth
+            .clone()
+            .filter(|_| listener_config.proxy_mode() == config::ProxyMode::None);
+        accept_loops.spawn(accept_loop(
+            listener,
+            Arc::new(config.for_listener(listener_config)),
+            new_player_tx.clone(),
+            next_eid.clone(),
+            player_count.clone(),
+            server_list.clone(),
+            session_auth,
+            login_throttle.clone(),
+        ));
+    }
 
-    // TCP listener
-    let addr = format!("{}:{}", config.bind, config.port);
-    let listener = TcpListener::bind(&addr).await?;
-    info!("Listening on {}", addr);
+    if config.lan_broadcast {
+        tokio::spawn(lan::announce(server_list.clone(), config.port));
+    }
+
+    if config.metrics.enabled {
+        let metrics_addr = format!("{}:{}", config.metrics.bind, config.metrics.port);
+        match TcpListener::bind(&metrics_addr).await {
+            Ok(listener) => {
+                tokio::spawn(metrics::serve(listener));
+            }
+            Err(e) => warn!("Couldn't serve metrics on {}: {}", metrics_addr, e),
+        }
+    }
 
     // Create save channel and spawn saver task
     let world_dir = std::path::PathBuf::from(&config.world_dir);
     let (save_tx, save_rx) = mpsc::unbounded_channel::<tick::SaveOp>();
+    let player_data = Arc::new(playerdata::PlayerDataStore::new(world_dir.join("playerdata")));
     let saver_world_dir = world_dir.clone();
-    tokio::task::spawn_blocking(move || tick::run_saver_task(save_rx, saver_world_dir));
+    let saver_player_data = player_data.clone();
+    let region_compression = config.region_compression;
+    // Finish chunk writes a crash cut short before anything reads the regions
+    let journal = tick::replay_chunk_journal(&world_dir, region_compression)?;
+    tokio::task::spawn_blocking(move || {
+        tick::run_saver_task(save_rx, saver_world_dir, saver_player_data, region_compression, journal)
+    });
 
-    // Create region storage for WorldState (read path only).
-    // The saver task has its own RegionStorage for writes. This is safe because
+    // Create the default level's region storage for WorldState (read path only).
+    // The saver task has its own LevelStorage for writes. This is safe because
     // the read path only loads chunks on first access (cache miss), and once cached
     // they stay in memory. The write path only appends/overwrites on disk.
-    let region_dir = world_dir.join("region");
-    std::fs::create_dir_all(&region_dir)?;
-    let region_storage = pickaxe_region::RegionStorage::new(region_dir)?;
+    let level_storage = tick::open_level_storage(&world_dir, tick::DEFAULT_LEVEL, region_compression)?;
+
+    // Console commands typed on stdin, run by the tick loop
+    let (console_tx, console_rx) = mpsc::unbounded_channel::<String>();
+    tokio::spawn(read_console(console_tx));
 
     // Graceful shutdown channel
     let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
@@ -95,10 +264,10 @@ async fn main() -> anyhow::Result<()> {
     let tick_next_eid = next_eid.clone();
 
     tokio::select! {
-        _ = tick::run_tick_loop(tick_config, scripting, new_player_rx, tick_player_count, lua_commands, block_overrides, tick_next_eid, save_tx, region_storage, shutdown_rx) => {
+        _ = tick::run_tick_loop(tick_config, scripting, new_player_rx, tick_player_count, lua_commands, block_overrides, mod_storage, custom_items, placeholders, lua_channels, secure_chat, http_client, server_list.clone(), tick_next_eid, save_tx, player_data, level_storage, console_rx, shutdown_rx) => {
             info!("Server shut down cleanly");
         }
-        _ = accept_loop(listener, config, new_player_tx, next_eid, player_count) => {
+        _ = accept_loops.join_next() => {
             error!("Accept loop exited unexpectedly");
         }
     }
@@ -106,12 +275,47 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Write LuaLS stubs (`pickaxe.lua`) and a markdown reference (`API.md`)
+/// for every function in the `pickaxe` table to `dir`.
+fn dump_lua_api(lua: &mlua::Lua, dir: &Path) -> anyhow::Result<()> {
+    let entries = pickaxe_scripting::docs::collect(lua).map_err(|e| anyhow::anyhow!("{}", e))?;
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(dir.join("pickaxe.lua"), pickaxe_scripting::docs::lua_stubs(&entries))?;
+    std::fs::write(dir.join("API.md"), pickaxe_scripting::docs::markdown(&entries))?;
+    let undocumented = entries.iter().filter(|e| e.doc.is_none()).count();
+    info!("Wrote {} Lua API functions to {} ({} undocumented)", entries.len(), dir.display(), undocumented);
+    Ok(())
+}
+
+/// Forward non-empty stdin lines (an optional leading `/` is stripped) to the tick loop.
+async fn read_console(console_tx: mpsc::UnboundedSender<String>) {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    loop {
+        match lines.next_line().await {
+            Ok(Some(line)) => {
+                let command = line.trim().trim_start_matches('/');
+                if !command.is_empty() && console_tx.send(command.to_string()).is_err() {
+                    break;
+                }
+            }
+            Ok(None) => break,
+            Err(e) => {
+                error!("Failed to read console input: {}", e);
+                break;
+            }
+        }
+    }
+}
+
 async fn accept_loop(
     listener: TcpListener,
     config: Arc<ServerConfig>,
     new_player_tx: mpsc::UnboundedSender<tick::NewPlayer>,
     next_eid: Arc<AtomicI32>,
     player_count: Arc<AtomicUsize>,
+    server_list: status::SharedServerList,
+    session_auth: Option<Arc<auth::SessionAuth>>,
+    login_throttle: limits::SharedLoginThrottle,
 ) {
     loop {
         match listener.accept().await {
@@ -121,13 +325,21 @@ async fn accept_loop(
                 let tx = new_player_tx.clone();
                 let eid = next_eid.clone();
                 let pc = player_count.clone();
+                let server_list = server_list.clone();
+                let session_auth = session_auth.clone();
+                let login_throttle = login_throttle.clone();
                 tokio::spawn(async move {
                     network::handle_connection(
                         socket,
                         config,
+                        session_auth,
+                        login_throttle,
                         tx,
                         eid,
-                        move || pc.load(std::sync::atomic::Ordering::Relaxed),
+                        move || {
+                            let info = server_list.lock().map(|info| info.clone()).unwrap_or_default();
+                            (pc.load(std::sync::atomic::Ordering::Relaxed), info)
+                        },
                     )
                     .await;
                 });
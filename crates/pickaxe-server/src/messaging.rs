@@ -0,0 +1,34 @@
+use pickaxe_types::TextComponent;
+
+/// Render a private message format (see `MessagingConfig`).
+///
+/// Names are substituted before `&` codes are translated; the message is
+/// substituted afterwards so players can't inject formatting.
+pub fn format_message(format: &str, sender: &str, receiver: &str, message: &str) -> TextComponent {
+    let format = format.replace("{sender}", sender).replace("{receiver}", receiver);
+    let mut text = TextComponent::from_legacy(&format, '&');
+    substitute(&mut text, "{message}", message);
+    text
+}
+
+fn substitute(text: &mut TextComponent, placeholder: &str, value: &str) {
+    if text.text.contains(placeholder) {
+        text.text = text.text.replace(placeholder, value);
+    }
+    for child in &mut text.extra {
+        substitute(child, placeholder, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_message() {
+        let text = format_message("&7{sender} -> {receiver}: &f{message}", "Alice", "Bob", "hi &cthere");
+        assert_eq!(text.plain_text(), "Alice -> Bob: hi &cthere");
+        assert_eq!(text.extra[0].color.as_deref(), Some("gray"));
+        assert_eq!(text.extra[1].color.as_deref(), Some("white"));
+    }
+}
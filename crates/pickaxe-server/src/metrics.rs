@@ -0,0 +1,154 @@
+use crate::net_stats::{NetStats, Traffic};
+use crate::overload::LoadStats;
+use crate::save_stats::SaveStats;
+use std::collections::HashMap;
+use std::fmt::Write;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info};
+
+/// Longest request head read before answering.
+const MAX_REQUEST_BYTES: usize = 8192;
+
+/// Serve `GET /metrics` in the Prometheus text format.
+pub async fn serve(listener: TcpListener) {
+    if let Ok(addr) = listener.local_addr() {
+        info!("Serving metrics on http://{}/metrics", addr);
+    }
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(async move {
+                    if let Err(e) = respond(stream).await {
+                        debug!("Metrics request failed: {}", e);
+                    }
+                });
+            }
+            Err(e) => debug!("Failed to accept metrics connection: {}", e),
+        }
+    }
+}
+
+async fn respond(mut stream: TcpStream) -> std::io::Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < MAX_REQUEST_BYTES {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    let request_line = String::from_utf8_lossy(&request);
+    let (status, body) = match request_line.split_whitespace().take(2).collect::<Vec<_>>()[..] {
+        ["GET", "/metrics"] => {
+            let stats = crate::net_stats::SERVER.lock().map(|s| s.clone()).unwrap_or_default();
+            let saves = crate::save_stats::SAVER.lock().map(|s| s.clone()).unwrap_or_default();
+            let load = crate::overload::LOAD.lock().map(|s| s.clone()).unwrap_or_default();
+            ("200 OK", render(&stats, &saves, &load))
+        }
+        _ => ("404 Not Found", String::from("Not found\n")),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Server metrics in the Prometheus text exposition format.
+pub fn render(net: &NetStats, saves: &SaveStats, load: &LoadStats) -> String {
+    let mut out = String::new();
+    let directions = [("in", net.inbound()), ("out", net.outbound())];
+    write_counter(
+        &mut out,
+        "pickaxe_network_packets_total",
+        "Play packets by direction and packet ID.",
+        &directions,
+        |t| t.packets,
+    );
+    write_counter(
+        &mut out,
+        "pickaxe_network_bytes_total",
+        "Uncompressed play packet bytes by direction and packet ID.",
+        &directions,
+        |t| t.bytes,
+    );
+    let save_metrics = [
+        ("pickaxe_saver_queue_depth", "gauge", "Ops waiting for the saver task.", saves.queue_depth as f64),
+        ("pickaxe_saver_pending_chunks", "gauge", "Changed chunks lined up to be saved on later ticks.", saves.pending_chunks as f64),
+        ("pickaxe_saver_ops_total", "counter", "Ops the saver task has finished.", saves.ops as f64),
+        ("pickaxe_saver_chunks_total", "counter", "Chunks written to region files.", saves.chunks as f64),
+        ("pickaxe_saver_flush_seconds_sum", "counter", "Time spent encoding, journaling and writing chunk batches.", saves.flush_time.as_secs_f64()),
+        ("pickaxe_saver_flush_seconds_count", "counter", "Chunk batches encoded, journaled and written.", saves.flushes as f64),
+        ("pickaxe_saver_flush_seconds_max", "gauge", "Slowest chunk batch since startup.", saves.max_flush_time.as_secs_f64()),
+    ];
+    let tick_metrics = [
+        ("pickaxe_tick_tps", "gauge", "Ticks per second over the last 5 seconds.", load.tps),
+        ("pickaxe_tick_mspt", "gauge", "Average milliseconds per tick over the last 5 seconds.", load.mspt),
+        ("pickaxe_tick_mspt_max", "gauge", "Slowest tick in the last 5 seconds, in milliseconds.", load.max_mspt),
+        ("pickaxe_tick_load_level", "gauge", "Work shed to keep up: 0 normal, 1 strained, 2 overloaded.", load.level as u8 as f64),
+        ("pickaxe_tick_overloads_total", "counter", "Times the server started shedding more work.", load.escalations as f64),
+    ];
+    for (name, kind, help, value) in save_metrics.into_iter().chain(tick_metrics) {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        let _ = writeln!(out, "{} {}", name, value);
+    }
+    out
+}
+
+fn write_counter(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    directions: &[(&str, &HashMap<i32, Traffic>)],
+    value: impl Fn(&Traffic) -> u64,
+) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    for (direction, traffic) in directions {
+        let mut ids: Vec<&i32> = traffic.keys().collect();
+        ids.sort();
+        for id in ids {
+            let _ = writeln!(
+                out,
+                "{}{{direction=\"{}\",packet_id=\"0x{:02X}\"}} {}",
+                name,
+                direction,
+                id,
+                value(&traffic[id])
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::overload::LoadLevel;
+
+    #[test]
+    fn test_render() {
+        let mut net = NetStats::default();
+        net.record_out(0x27, 4000);
+        net.record_out(0x27, 1000);
+        net.record_in(0x1A, 30);
+        let mut saves = SaveStats { queue_depth: 7, ..Default::default() };
+        saves.record_flush(12, std::time::Duration::from_millis(250));
+        let load = LoadStats { tps: 18.5, level: LoadLevel::Overloaded, escalations: 3, ..Default::default() };
+        let text = render(&net, &saves, &load);
+        assert!(text.contains("# TYPE pickaxe_network_bytes_total counter\n"));
+        assert!(text.contains("pickaxe_network_packets_total{direction=\"out\",packet_id=\"0x27\"} 2\n"));
+        assert!(text.contains("pickaxe_network_bytes_total{direction=\"in\",packet_id=\"0x1A\"} 30\n"));
+        assert!(text.contains("# TYPE pickaxe_saver_queue_depth gauge\npickaxe_saver_queue_depth 7\n"));
+        assert!(text.contains("pickaxe_saver_chunks_total 12\n"));
+        assert!(text.contains("pickaxe_saver_flush_seconds_sum 0.25\n"));
+        assert!(text.contains("pickaxe_tick_tps 18.5\n"));
+        assert!(text.contains("pickaxe_tick_load_level 2\n"));
+        assert!(text.contains("pickaxe_tick_overloads_total 3\n"));
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+
+/// Packets and bytes of one kind of traffic.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Traffic {
+    pub packets: u64,
+    pub bytes: u64,
+}
+
+impl Traffic {
+    fn add(&mut self, other: Traffic) {
+        self.packets += other.packets;
+        self.bytes += other.bytes;
+    }
+}
+
+/// Play-state traffic of a connection, split by packet ID. Sizes are the
+/// uncompressed packet lengths, so they show what the server produced
+/// rather than what compression made of it.
+#[derive(Debug, Clone, Default)]
+pub struct NetStats {
+    inbound: HashMap<i32, Traffic>,
+    outbound: HashMap<i32, Traffic>,
+    /// Packets waiting in the outgoing queue when last written.
+    pub queued: usize,
+}
+
+/// Shared between a connection's reader and writer and the tick loop.
+pub type SharedNetStats = Arc<Mutex<NetStats>>;
+
+/// Traffic of every connection since startup.
+pub static SERVER: LazyLock<Mutex<NetStats>> = LazyLock::new(Mutex::default);
+
+impl NetStats {
+    pub fn record_in(&mut self, packet_id: i32, bytes: usize) {
+        self.inbound.entry(packet_id).or_default().add(Traffic { packets: 1, bytes: bytes as u64 });
+    }
+
+    pub fn record_out(&mut self, packet_id: i32, bytes: usize) {
+        self.outbound.entry(packet_id).or_default().add(Traffic { packets: 1, bytes: bytes as u64 });
+    }
+
+    pub fn inbound(&self) -> &HashMap<i32, Traffic> {
+        &self.inbound
+    }
+
+    pub fn outbound(&self) -> &HashMap<i32, Traffic> {
+        &self.outbound
+    }
+
+    pub fn total_in(&self) -> Traffic {
+        total(&self.inbound)
+    }
+
+    pub fn total_out(&self) -> Traffic {
+        total(&self.outbound)
+    }
+
+    /// The `count` outbound packet IDs with the most bytes, largest first.
+    pub fn top_outbound(&self, count: usize) -> Vec<(i32, Traffic)> {
+        let mut top: Vec<(i32, Traffic)> = self.outbound.iter().map(|(&id, &t)| (id, t)).collect();
+        top.sort_by(|a, b| b.1.bytes.cmp(&a.1.bytes).then(a.0.cmp(&b.0)));
+        top.truncate(count);
+        top
+    }
+}
+
+fn total(traffic: &HashMap<i32, Traffic>) -> Traffic {
+    let mut sum = Traffic::default();
+    for t in traffic.values() {
+        sum.add(*t);
+    }
+    sum
+}
+
+/// Count a packet on its connection and in the server totals.
+pub fn record(connection: &SharedNetStats, outbound: bool, packet_id: i32, bytes: usize) {
+    for stats in [&**connection, &*SERVER] {
+        if let Ok(mut stats) = stats.lock() {
+            if outbound {
+                stats.record_out(packet_id, bytes);
+            } else {
+                stats.record_in(packet_id, bytes);
+            }
+        }
+    }
+}
+
+/// Byte count in B, KiB or MiB.
+pub fn format_bytes(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+/// `0x27: 310 packets, 1.2 MiB, ...` for log lines and `/debug net`.
+pub fn describe(top: &[(i32, Traffic)]) -> String {
+    top.iter()
+        .map(|(id, t)| format!("0x{:02X}: {} packets, {}", id, t.packets, format_bytes(t.bytes)))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_totals_and_top() {
+        let mut stats = NetStats::default();
+        stats.record_out(0x27, 40_000);
+        stats.record_out(0x27, 30_000);
+        stats.record_out(0x1F, 20);
+        stats.record_out(0x40, 900);
+        stats.record_in(0x1A, 30);
+
+        assert_eq!(stats.total_out(), Traffic { packets: 4, bytes: 70_920 });
+        assert_eq!(stats.total_in(), Traffic { packets: 1, bytes: 30 });
+        let top = stats.top_outbound(2);
+        assert_eq!(top.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![0x27, 0x40]);
+        assert_eq!(describe(&top[..1]), "0x27: 2 packets, 68.4 KiB");
+        assert_eq!(format_bytes(512), "512 B");
+    }
+}
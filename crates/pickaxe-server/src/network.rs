@@ -1,42 +1,105 @@
-use crate::config::ServerConfig;
+use crate::auth::SessionAuth;
+use crate::config::{ProxyMode, ServerConfig};
+use crate::ecs::{ClientChannels, ClientSettings};
+use crate::limits::{PacketRateLimiter, SharedLoginThrottle};
+use crate::net_stats::{self, SharedNetStats};
+use crate::status::{self, ServerListInfo};
+use crate::proxy::{self, ForwardedPlayer};
 use crate::tick::{InboundPacket, NewPlayer};
 use anyhow::Result;
+use pickaxe_protocol_core::capture::{CaptureWriter, Direction, SharedCapture};
 use pickaxe_protocol_core::{
-    Connection, ConnectionState, ConnectionWriter, InternalPacket, KnownPack,
+    Connection, ConnectionState, ConnectionWriter, InternalPacket, KnownPack, HANDSHAKE_TRANSFER,
 };
+use pickaxe_protocol_v1_20_6::V1_20_6Adapter;
 use pickaxe_protocol_v1_21::V1_21Adapter;
 use pickaxe_protocol_core::ProtocolAdapter;
-use pickaxe_types::GameProfile;
+use pickaxe_types::{GameProfile, TextComponent};
+use socket2::{Domain, Protocol, Socket, Type};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicI32, Ordering};
-use tokio::net::TcpStream;
+use std::time::{Duration, Instant};
+use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::mpsc;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
+/// Versions clients may join with, newest first.
+const SUPPORTED_VERSIONS: &str = "1.21.1, 1.21, 1.20.6 and 1.20.5";
+
+/// How long a flood kick waits for its disconnect message to be sent.
+const KICK_FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Extra time the reader waits past `[keep_alive]`'s idle timeout, so the
+/// tick loop times the player out first and only dead sockets it can't
+/// reach are closed here.
+const IDLE_GRACE: Duration = Duration::from_secs(5);
+
+/// Outgoing packets queued for one player before the writer logs what's
+/// filling the queue, and how often it may log it again.
+const BACKLOG_WARN_PACKETS: usize = 2048;
+const BACKLOG_WARN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The adapter for a client's protocol version, or None if it isn't supported.
+fn adapter_for(protocol_version: i32) -> Option<Arc<dyn ProtocolAdapter>> {
+    match protocol_version {
+        767 => Some(Arc::new(V1_21Adapter::new())),
+        766 => Some(Arc::new(V1_20_6Adapter::new())),
+        _ => None,
+    }
+}
+
+/// Listen on `host:port`. `host` is an IP address, optionally in brackets,
+/// or a name to resolve. IPv6 listeners only accept IPv6, so `::` and
+/// `0.0.0.0` can listen on the same port.
+pub async fn listen(host: &str, port: u16) -> Result<TcpListener> {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let addr = match host.parse::<std::net::IpAddr>() {
+        Ok(ip) => std::net::SocketAddr::new(ip, port),
+        Err(_) => tokio::net::lookup_host((host, port))
+            .await?
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No address found for {}", host))?,
+    };
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    if addr.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
+    socket.set_reuse_address(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(1024)?;
+    socket.set_nonblocking(true)?;
+    Ok(TcpListener::from_std(socket.into())?)
+}
+
 /// Handle a single client connection through handshake → login → configuration.
 /// Once in play state, splits into reader/writer tasks and registers with the tick loop.
 pub async fn handle_connection(
     stream: TcpStream,
     config: Arc<ServerConfig>,
+    session_auth: Option<Arc<SessionAuth>>,
+    login_throttle: SharedLoginThrottle,
     new_player_tx: mpsc::UnboundedSender<NewPlayer>,
     next_eid: Arc<AtomicI32>,
-    player_count_fn: impl Fn() -> usize,
+    status_fn: impl Fn() -> (usize, ServerListInfo),
 ) {
     let peer = stream
         .peer_addr()
         .unwrap_or_else(|_| "unknown".parse().unwrap());
     let mut conn = Connection::new(stream);
-    let adapter = V1_21Adapter::new();
+    if config.limits.max_packet_kb > 0 {
+        conn.set_max_packet_size((config.limits.max_packet_kb as usize) << 10);
+    }
 
     if let Err(e) = handle_pre_play(
         &mut conn,
-        &adapter,
         &config,
+        session_auth.as_deref(),
+        &login_throttle,
         peer,
         new_player_tx,
         next_eid,
-        &player_count_fn,
+        &status_fn,
     )
     .await
     {
@@ -46,20 +109,23 @@ pub async fn handle_connection(
 
 async fn handle_pre_play(
     conn: &mut Connection,
-    adapter: &V1_21Adapter,
     config: &ServerConfig,
+    session_auth: Option<&SessionAuth>,
+    login_throttle: &SharedLoginThrottle,
     peer: std::net::SocketAddr,
     new_player_tx: mpsc::UnboundedSender<NewPlayer>,
     next_eid: Arc<AtomicI32>,
-    player_count_fn: &impl Fn() -> usize,
+    status_fn: &impl Fn() -> (usize, ServerListInfo),
 ) -> Result<()> {
     // === Handshake ===
-    let (id, mut data) = conn.read_packet().await?;
-    let packet = adapter.decode_packet(ConnectionState::Handshaking, id, &mut data)?;
+    // The handshake layout is the same in every version, so any adapter can read it.
+    let default_adapter: Arc<dyn ProtocolAdapter> = Arc::new(V1_21Adapter::new());
+    let (_, packet) = receive(conn, default_adapter.as_ref(), ConnectionState::Handshaking).await?;
 
-    let next_state = match packet {
+    let (protocol_version, server_address, next_state) = match packet {
         InternalPacket::Handshake {
             protocol_version,
+            server_address,
             next_state,
             ..
         } => {
@@ -67,27 +133,114 @@ async fn handle_pre_play(
                 "Handshake from {}: protocol={}, next_state={}",
                 peer, protocol_version, next_state
             );
-            if protocol_version != adapter.protocol_version() {
-                warn!(
-                    "Client {} has protocol version {}, expected {}",
-                    peer,
-                    protocol_version,
-                    adapter.protocol_version()
-                );
-            }
-            next_state
+            (protocol_version, server_address, next_state)
         }
         _ => return Err(anyhow::anyhow!("Expected handshake packet")),
     };
+    let adapter = adapter_for(protocol_version);
 
     match ConnectionState::from_handshake_next(next_state) {
         Some(ConnectionState::Status) => {
-            handle_status(conn, adapter, config, player_count_fn).await
+            // Unsupported clients are shown our default version, so the
+            // server list marks it as incompatible.
+            let adapter = adapter.unwrap_or(default_adapter);
+            handle_status(conn, adapter.as_ref(), config, status_fn).await
         }
         Some(ConnectionState::Login) => {
-            let profile = handle_login(conn, adapter, config).await?;
-            handle_configuration(conn, adapter, config).await?;
-            enter_play(conn, adapter, profile, new_player_tx, next_eid).await
+            let Some(adapter) = adapter else {
+                warn!("Client {} has unsupported protocol version {}", peer, protocol_version);
+                disconnect(
+                    conn,
+                    default_adapter.as_ref(),
+                    ConnectionState::Login,
+                    TextComponent::translatable(
+                        "multiplayer.disconnect.incompatible",
+                        vec![TextComponent::plain(SUPPORTED_VERSIONS)],
+                    ),
+                )
+                .await?;
+                return Err(anyhow::anyhow!("Unsupported protocol version {}", protocol_version));
+            };
+            if config.capture.enabled {
+                match start_capture(&config.capture.dir, peer) {
+                    Ok(capture) => conn.set_capture(capture),
+                    Err(e) => warn!("Couldn't capture packets from {}: {}", peer, e),
+                }
+            }
+            let transferred = next_state == HANDSHAKE_TRANSFER;
+            if transferred && !config.transfer.accept {
+                info!("Refused transfer from {}", peer);
+                disconnect(
+                    conn,
+                    adapter.as_ref(),
+                    ConnectionState::Login,
+                    TextComponent::translatable("multiplayer.disconnect.transfers_disabled", Vec::new()),
+                )
+                .await?;
+                return Err(anyhow::anyhow!("Transfers are disabled"));
+            }
+            // Behind a proxy every player arrives from the proxy's address
+            if config.proxy.mode == ProxyMode::None {
+                let allowed = login_throttle
+                    .lock()
+                    .map(|mut throttle| throttle.try_login(peer.ip(), Instant::now()))
+                    .unwrap_or(true);
+                if !allowed {
+                    info!("Throttled login from {}", peer);
+                    disconnect(
+                        conn,
+                        adapter.as_ref(),
+                        ConnectionState::Login,
+                        TextComponent::plain("Connection throttled! Please wait before reconnecting."),
+                    )
+                    .await?;
+                    return Err(anyhow::anyhow!("Login throttled"));
+                }
+            }
+            // BungeeCord forwards the player in the handshake address
+            let bungee_forward = if config.proxy.mode == ProxyMode::Bungeecord {
+                match proxy::parse_bungee_address(&server_address) {
+                    Some(forwarded) => Some(forwarded),
+                    None => {
+                        warn!("Connection from {} wasn't forwarded by BungeeCord", peer);
+                        disconnect(
+                            conn,
+                            adapter.as_ref(),
+                            ConnectionState::Login,
+                            TextComponent::plain(
+                                "This server requires you to connect through BungeeCord with ip_forward enabled.",
+                            ),
+                        )
+                        .await?;
+                        return Err(anyhow::anyhow!("Missing BungeeCord forwarding data"));
+                    }
+                }
+            } else {
+                None
+            };
+            let (profile, peer) = handle_login(
+                conn,
+                adapter.as_ref(),
+                config,
+                session_auth,
+                bungee_forward,
+                peer,
+            )
+            .await?;
+            let client = handle_configuration(conn, adapter.as_ref(), config).await?;
+            enter_play(
+                conn,
+                adapter,
+                profile,
+                client,
+                transferred,
+                peer,
+                config.limits.packets_per_second,
+                config.keep_alive.idle_timeout().map(|idle| idle + IDLE_GRACE),
+                new_player_tx,
+                next_eid,
+            )
+            .await
         }
         _ => Err(anyhow::anyhow!("Invalid next state: {}", next_state)),
     }
@@ -95,21 +248,26 @@ async fn handle_pre_play(
 
 async fn handle_status(
     conn: &mut Connection,
-    adapter: &V1_21Adapter,
+    adapter: &dyn ProtocolAdapter,
     config: &ServerConfig,
-    player_count_fn: &impl Fn() -> usize,
+    status_fn: &impl Fn() -> (usize, ServerListInfo),
 ) -> Result<()> {
     loop {
-        let (id, mut data) = conn.read_packet().await?;
-        let packet = adapter.decode_packet(ConnectionState::Status, id, &mut data)?;
+        let (_, packet) = receive(conn, adapter, ConnectionState::Status).await?;
 
         match packet {
             InternalPacket::StatusRequest => {
-                let player_count = player_count_fn();
-                let response_json = format!(
-                    r#"{{"version":{{"name":"1.21.1","protocol":767}},"players":{{"max":{},"online":{}}},"description":{{"text":"{}"}}}}"#,
-                    config.max_players, player_count, config.motd
-                );
+                let (player_count, info) = status_fn();
+                let version_name = match config.status.version_name.as_str() {
+                    "" => adapter.version_name(),
+                    name => name,
+                };
+                let protocol = match config.status.protocol {
+                    0 => adapter.protocol_version(),
+                    protocol => protocol,
+                };
+                let response_json =
+                    status::response_json(&info, version_name, protocol, player_count, config.max_players);
                 send_packet(
                     conn,
                     adapter,
@@ -135,22 +293,66 @@ async fn handle_status(
     }
 }
 
+/// Returns the player's profile and address, which differ from the
+/// connection's when a proxy forwards them.
 async fn handle_login(
     conn: &mut Connection,
-    adapter: &V1_21Adapter,
+    adapter: &dyn ProtocolAdapter,
     config: &ServerConfig,
-) -> Result<GameProfile> {
-    let (id, mut data) = conn.read_packet().await?;
-    let packet = adapter.decode_packet(ConnectionState::Login, id, &mut data)?;
+    session_auth: Option<&SessionAuth>,
+    bungee_forward: Option<ForwardedPlayer>,
+    mut peer: std::net::SocketAddr,
+) -> Result<(GameProfile, std::net::SocketAddr)> {
+    let (_, packet) = receive(conn, adapter, ConnectionState::Login).await?;
 
-    let (name, client_uuid) = match packet {
+    let name = match packet {
         InternalPacket::LoginStart { name, uuid } => {
             info!("Login Start from: {} ({})", name, uuid);
-            (name, uuid)
+            name
         }
         _ => return Err(anyhow::anyhow!("Expected Login Start")),
     };
 
+    // Behind a proxy the player was authenticated there; otherwise online
+    // mode encrypts the connection and verifies the player with Mojang
+    let forwarded = match config.proxy.mode {
+        ProxyMode::Velocity => Some(velocity_forward(conn, adapter, &config.proxy.secret).await?),
+        ProxyMode::Bungeecord => bungee_forward.map(Ok),
+        ProxyMode::None => None,
+    };
+    let verified = match (forwarded, session_auth) {
+        (Some(forwarded), _) => Some(forwarded.map(|forwarded| {
+            peer.set_ip(forwarded.address);
+            forwarded.into_profile(&name)
+        })),
+        (None, Some(session_auth)) => Some(authenticate(conn, adapter, session_auth, &name).await?),
+        (None, None) => None,
+    };
+    let profile = match verified {
+        Some(verified) => match verified {
+            Ok(profile) => profile,
+            Err(reason) => {
+                info!("Failed to authenticate {} ({}): {}", name, peer, reason);
+                disconnect(conn, adapter, ConnectionState::Login, TextComponent::plain(reason)).await?;
+                return Err(anyhow::anyhow!("Authentication failed"));
+            }
+        },
+        None => GameProfile {
+            uuid: offline_uuid(&name),
+            name: name.clone(),
+            properties: Vec::new(),
+        },
+    };
+    let name = profile.name.clone();
+    let uuid = profile.uuid;
+
+    // Bans and whitelist
+    if let Some(reason) = crate::access::check_login(&name, uuid, peer.ip()) {
+        info!("Refused login from {} ({}): {}", name, peer, reason.plain_text());
+        disconnect(conn, adapter, ConnectionState::Login, reason).await?;
+        return Err(anyhow::anyhow!("Login refused"));
+    }
+
     // Enable compression
     let compression_threshold = 256;
     send_packet(
@@ -164,19 +366,6 @@ async fn handle_login(
     .await?;
     conn.enable_compression(compression_threshold);
 
-    // Build profile
-    let uuid = if config.online_mode {
-        client_uuid
-    } else {
-        offline_uuid(&name)
-    };
-
-    let profile = GameProfile {
-        uuid,
-        name: name.clone(),
-        properties: Vec::new(),
-    };
-
     send_packet(
         conn,
         adapter,
@@ -188,8 +377,7 @@ async fn handle_login(
     .await?;
 
     // Wait for Login Acknowledged
-    let (id, mut data) = conn.read_packet().await?;
-    let ack = adapter.decode_packet(ConnectionState::Login, id, &mut data)?;
+    let (_, ack) = receive(conn, adapter, ConnectionState::Login).await?;
     match ack {
         InternalPacket::LoginAcknowledged => {
             debug!("Login acknowledged by {}", name);
@@ -197,14 +385,121 @@ async fn handle_login(
         _ => return Err(anyhow::anyhow!("Expected Login Acknowledged")),
     }
 
-    Ok(profile)
+    Ok((profile, peer))
+}
+
+/// Ask Velocity for the player's details over a login plugin message.
+/// Returns them, or the message to disconnect the client with.
+async fn velocity_forward(
+    conn: &mut Connection,
+    adapter: &dyn ProtocolAdapter,
+    secret: &str,
+) -> Result<std::result::Result<ForwardedPlayer, String>> {
+    let message_id: i32 = rand::random();
+    send_packet(
+        conn,
+        adapter,
+        ConnectionState::Login,
+        &InternalPacket::LoginPluginRequest {
+            message_id,
+            channel: proxy::VELOCITY_CHANNEL.into(),
+            data: vec![proxy::VELOCITY_FORWARDING_VERSION],
+        },
+    )
+    .await?;
+
+    let (_, packet) = receive(conn, adapter, ConnectionState::Login).await?;
+    match packet {
+        InternalPacket::LoginPluginResponse {
+            message_id: response_id,
+            data,
+        } if response_id == message_id => Ok(match data {
+            Some(data) => proxy::parse_velocity_response(secret, &data)
+                .ok_or_else(|| "Unable to verify player details.".to_string()),
+            // The channel is unknown to anything but Velocity
+            None => Err("This server requires you to connect through Velocity.".into()),
+        }),
+        _ => Err(anyhow::anyhow!("Expected Login Plugin Response")),
+    }
+}
+
+/// Run the encryption handshake and check the session server. Returns the
+/// verified profile, or the message to disconnect the client with.
+async fn authenticate(
+    conn: &mut Connection,
+    adapter: &dyn ProtocolAdapter,
+    session_auth: &SessionAuth,
+    name: &str,
+) -> Result<std::result::Result<GameProfile, String>> {
+    let verify_token: [u8; 4] = rand::random();
+    send_packet(
+        conn,
+        adapter,
+        ConnectionState::Login,
+        &InternalPacket::EncryptionRequest {
+            server_id: String::new(),
+            public_key: session_auth.public_key().to_vec(),
+            verify_token: verify_token.to_vec(),
+        },
+    )
+    .await?;
+
+    let (_, packet) = receive(conn, adapter, ConnectionState::Login).await?;
+    let (shared_secret, token) = match packet {
+        InternalPacket::EncryptionResponse {
+            shared_secret,
+            verify_token,
+        } => (
+            session_auth.decrypt(&shared_secret)?,
+            session_auth.decrypt(&verify_token)?,
+        ),
+        _ => return Err(anyhow::anyhow!("Expected Encryption Response")),
+    };
+    if token != verify_token || shared_secret.len() != 16 {
+        return Err(anyhow::anyhow!("Invalid Encryption Response"));
+    }
+    // Everything after the response is encrypted, including a disconnect
+    conn.enable_encryption(&shared_secret);
+
+    match session_auth.has_joined(name, &shared_secret).await {
+        Ok(Some(profile)) => {
+            debug!("Authenticated {} as {}", name, profile.uuid);
+            Ok(Ok(profile))
+        }
+        Ok(None) => Ok(Err("Failed to verify username!".into())),
+        Err(e) => {
+            warn!("Session server request for {} failed: {}", name, e);
+            Ok(Err("Authentication servers are down. Please try again later.".into()))
+        }
+    }
 }
 
+/// What the client told the server about itself during configuration.
+#[derive(Default)]
+struct ClientConfiguration {
+    channels: ClientChannels,
+    settings: Option<ClientSettings>,
+}
+
+/// Returns the brand, plugin channels and options the client sent along the way.
 async fn handle_configuration(
     conn: &mut Connection,
-    adapter: &V1_21Adapter,
-    _config: &ServerConfig,
-) -> Result<()> {
+    adapter: &dyn ProtocolAdapter,
+    config: &ServerConfig,
+) -> Result<ClientConfiguration> {
+    let mut client = ClientConfiguration::default();
+
+    send_packet(
+        conn,
+        adapter,
+        ConnectionState::Configuration,
+        &InternalPacket::PluginMessage {
+            channel: crate::channels::BRAND.into(),
+            data: crate::channels::brand_payload(crate::channels::SERVER_BRAND),
+        },
+    )
+    .await?;
+
     send_packet(
         conn,
         adapter,
@@ -219,21 +514,18 @@ async fn handle_configuration(
     )
     .await?;
 
-    let (id, mut data) = conn.read_packet().await?;
-    let packet = adapter.decode_packet(ConnectionState::Configuration, id, &mut data)?;
-    match packet {
-        InternalPacket::KnownPacksResponse { packs } => {
-            debug!("Client knows {} packs", packs.len());
-        }
-        _ => {
-            debug!(
-                "Expected Known Packs response, got something else (id=0x{:02X}), continuing",
-                id
-            );
+    loop {
+        let (id, packet) = receive(conn, adapter, ConnectionState::Configuration).await?;
+        match packet {
+            InternalPacket::KnownPacksResponse { packs } => {
+                debug!("Client knows {} packs", packs.len());
+                break;
+            }
+            packet => note_config_packet(&mut client, id, packet),
         }
     }
 
-    let registries = adapter.registry_data();
+    let registries = adapter.registry_data(&config.registries.build());
     for registry_packet in &registries {
         send_packet(
             conn,
@@ -244,6 +536,23 @@ async fn handle_configuration(
         .await?;
     }
 
+    // The server resource pack is loaded before the player enters the world
+    if let Some(pack) = crate::resource_pack::server_pack(&config.resource_pack) {
+        send_packet(conn, adapter, ConnectionState::Configuration, &pack).await?;
+        let result = await_resource_pack(conn, adapter, &mut client).await?;
+        debug!("Resource pack {}", crate::resource_pack::status_name(result));
+        if config.resource_pack.kick_on_decline && crate::resource_pack::is_rejected(result) {
+            disconnect(
+                conn,
+                adapter,
+                ConnectionState::Configuration,
+                TextComponent::translatable("multiplayer.requiredTexturePrompt.disconnect", Vec::new()),
+            )
+            .await?;
+            return Err(anyhow::anyhow!("Resource pack {}", crate::resource_pack::status_name(result)));
+        }
+    }
+
     send_packet(
         conn,
         adapter,
@@ -253,26 +562,37 @@ async fn handle_configuration(
     .await?;
 
     loop {
-        let (id, mut data) = conn.read_packet().await?;
-        let packet = adapter.decode_packet(ConnectionState::Configuration, id, &mut data)?;
+        let (id, packet) = receive(conn, adapter, ConnectionState::Configuration).await?;
         match packet {
             InternalPacket::FinishConfigurationAck => {
                 debug!("Configuration finished");
-                return Ok(());
+                return Ok(client);
             }
-            InternalPacket::ClientInformation {
+            packet => note_config_packet(&mut client, id, packet),
+        }
+    }
+}
+
+/// Handle a configuration packet that arrives while waiting for another.
+fn note_config_packet(client: &mut ClientConfiguration, id: i32, packet: InternalPacket) {
+    match packet {
+        InternalPacket::ClientInformation {
+            locale,
+            view_distance,
+            ..
+        } => {
+            debug!("Client info: locale={}, view_distance={}", locale, view_distance);
+            client.settings = Some(ClientSettings {
                 locale,
-                view_distance,
-                ..
-            } => {
-                debug!("Client info: locale={}, view_distance={}", locale, view_distance);
-            }
-            InternalPacket::PluginMessage { channel, .. } => {
-                debug!("Plugin message: {}", channel);
-            }
-            _ => {
-                debug!("Ignoring config packet id=0x{:02X}", id);
-            }
+                view_distance: view_distance as i32,
+            });
+        }
+        InternalPacket::PluginMessage { channel, data } => {
+            debug!("Plugin message: {}", channel);
+            client.channels.record(&channel, &data);
+        }
+        _ => {
+            debug!("Ignoring config packet id=0x{:02X}", id);
         }
     }
 }
@@ -281,8 +601,13 @@ async fn handle_configuration(
 /// and registering with the tick loop.
 async fn enter_play(
     conn: &mut Connection,
-    _adapter: &V1_21Adapter,
+    adapter: Arc<dyn ProtocolAdapter>,
     profile: GameProfile,
+    client: ClientConfiguration,
+    transferred: bool,
+    peer: std::net::SocketAddr,
+    packets_per_second: u32,
+    idle_timeout: Option<Duration>,
     new_player_tx: mpsc::UnboundedSender<NewPlayer>,
     next_eid: Arc<AtomicI32>,
 ) -> Result<()> {
@@ -297,10 +622,20 @@ async fn enter_play(
     // Take ownership of the connection internals for split I/O
     let connection = std::mem::replace(conn, Connection::new_dummy());
 
+    // Lets the reader kick a client that floods the server
+    let kick_tx = out_tx.clone();
+
+    let traffic = SharedNetStats::default();
+
     // Register with the tick loop
     let _ = new_player_tx.send(NewPlayer {
         entity_id,
         profile: profile.clone(),
+        address: peer.ip(),
+        channels: client.channels,
+        settings: client.settings,
+        transferred,
+        net_stats: traffic.clone(),
         packet_tx: out_tx,
         packet_rx: in_rx,
     });
@@ -311,26 +646,73 @@ async fn enter_play(
     let player_name = profile.name.clone();
 
     // Writer task: reads packets from channel, encodes and sends them
-    let write_adapter = V1_21Adapter::new();
-    let writer_handle = tokio::spawn(async move {
+    let write_adapter = adapter.clone();
+    let write_stats = traffic.clone();
+    let mut writer_handle = tokio::spawn(async move {
         let mut writer = writer;
+        let mut last_backlog_warning: Option<Instant> = None;
         while let Some(packet) = out_rx.recv().await {
-            if let Err(e) = encode_and_send(&mut writer, &write_adapter, &packet).await {
+            let queued = out_rx.len();
+            if let Ok(mut stats) = write_stats.lock() {
+                stats.queued = queued;
+            }
+            if queued >= BACKLOG_WARN_PACKETS
+                && last_backlog_warning.is_none_or(|last| last.elapsed() >= BACKLOG_WARN_INTERVAL)
+            {
+                let top = write_stats.lock().map(|stats| stats.top_outbound(5)).unwrap_or_default();
+                warn!(
+                    "Outgoing queue for {} backed up with {} packets; most sent: {}",
+                    player_name,
+                    queued,
+                    net_stats::describe(&top)
+                );
+                last_backlog_warning = Some(Instant::now());
+            }
+            if let Err(e) = encode_and_send(&mut writer, write_adapter.as_ref(), &packet, &write_stats).await {
                 debug!("Writer error for {}: {}", player_name, e);
                 break;
             }
+            // Nothing may follow a disconnect
+            if matches!(packet, InternalPacket::Disconnect { .. }) {
+                break;
+            }
         }
     });
 
     // Reader task: reads packets from TCP, decodes and forwards to tick loop
-    let read_adapter = V1_21Adapter::new();
+    let read_adapter = adapter;
     let reader_name = profile.name.clone();
-    let _reader_result = async {
+    let reading = async {
         let mut reader = reader;
+        let mut rate_limiter = PacketRateLimiter::new(packets_per_second, Instant::now());
         loop {
-            match reader.read_packet().await {
+            // A connection that died without closing never returns a packet
+            let read = match idle_timeout {
+                Some(limit) => match tokio::time::timeout(limit, reader.read_packet()).await {
+                    Ok(read) => read,
+                    Err(_) => {
+                        debug!("No packets from {} for {:?}, closing", reader_name, limit);
+                        return false;
+                    }
+                },
+                None => reader.read_packet().await,
+            };
+            match read {
                 Ok((id, mut data)) => {
-                    match read_adapter.decode_packet(ConnectionState::Play, id, &mut data) {
+                    net_stats::record(&traffic, false, id, pickaxe_protocol_core::varint_len(id) + data.len());
+                    let payload = reader.capture().map(|_| data.clone());
+                    if !rate_limiter.record(Instant::now()) {
+                        warn!("Disconnecting {} for sending too many packets", reader_name);
+                        let _ = kick_tx.send(InternalPacket::Disconnect {
+                            reason: TextComponent::translatable("disconnect.exceeded_packet_rate", Vec::new()),
+                        });
+                        return true;
+                    }
+                    let decoded = read_adapter.decode_packet(ConnectionState::Play, id, &mut data);
+                    if let (Some(capture), Some(payload)) = (reader.capture(), payload) {
+                        record(capture, Direction::Serverbound, ConnectionState::Play, id, &payload, decoded.as_ref().ok());
+                    }
+                    match decoded {
                         Ok(packet) => {
                             if in_tx
                                 .send(InboundPacket {
@@ -339,7 +721,7 @@ async fn enter_play(
                                 })
                                 .is_err()
                             {
-                                break; // Tick loop shut down
+                                return false; // Tick loop shut down
                             }
                         }
                         Err(e) => {
@@ -349,49 +731,153 @@ async fn enter_play(
                 }
                 Err(e) => {
                     debug!("Reader error for {}: {}", reader_name, e);
-                    break;
+                    return false;
                 }
             }
         }
-    }
-    .await;
+    };
+
+    // The writer stops after a kick or a failed write. Stop reading then
+    // too, so a client that ignores the kick can't hold on to its slot.
+    let kicked = tokio::select! {
+        kicked = reading => kicked,
+        _ = &mut writer_handle => false,
+    };
 
     // Reader finished = client disconnected. Drop the inbound sender, which
     // will cause the tick loop to detect disconnection on the next tick.
     drop(in_tx);
 
-    // Wait for writer to finish flushing
+    // Give the writer a moment to deliver the kick message, then stop it
+    if kicked {
+        let _ = tokio::time::timeout(KICK_FLUSH_TIMEOUT, &mut writer_handle).await;
+    }
     writer_handle.abort();
 
     Ok(())
 }
 
+/// Wait for the client's final response to the server resource pack.
+async fn await_resource_pack(
+    conn: &mut Connection,
+    adapter: &dyn ProtocolAdapter,
+    client: &mut ClientConfiguration,
+) -> Result<i32> {
+    loop {
+        let (id, packet) = receive(conn, adapter, ConnectionState::Configuration).await?;
+        match packet {
+            InternalPacket::ResourcePackResponse { result, .. } if crate::resource_pack::is_final(result) => {
+                return Ok(result);
+            }
+            packet => note_config_packet(client, id, packet),
+        }
+    }
+}
+
 async fn encode_and_send(
     writer: &mut ConnectionWriter,
-    adapter: &V1_21Adapter,
+    adapter: &dyn ProtocolAdapter,
     packet: &InternalPacket,
+    stats: &SharedNetStats,
 ) -> Result<()> {
-    let encoded = adapter.encode_packet(ConnectionState::Play, packet)?;
-    let mut data = encoded;
+    // Broadcasts reuse the bytes the first writer on this protocol encoded
+    if let InternalPacket::Shared(shared) = packet {
+        let (packet_id, payload) = shared.encode(adapter, ConnectionState::Play)?;
+        return write_encoded(writer, packet_id, &payload, shared.packet(), stats).await;
+    }
+    let mut data = adapter.encode_packet(ConnectionState::Play, packet)?;
     let packet_id = pickaxe_protocol_core::read_varint(&mut data)?;
-    writer.write_packet(packet_id, &data).await
+    write_encoded(writer, packet_id, &data, packet, stats).await
+}
+
+async fn write_encoded(
+    writer: &mut ConnectionWriter,
+    packet_id: i32,
+    payload: &[u8],
+    packet: &InternalPacket,
+    stats: &SharedNetStats,
+) -> Result<()> {
+    net_stats::record(stats, true, packet_id, pickaxe_protocol_core::varint_len(packet_id) + payload.len());
+    if let Some(capture) = writer.capture() {
+        record(capture, Direction::Clientbound, ConnectionState::Play, packet_id, payload, Some(packet));
+    }
+    writer.write_packet(packet_id, payload).await
 }
 
 /// Send an InternalPacket using the adapter's encode.
 async fn send_packet(
     conn: &mut Connection,
-    adapter: &V1_21Adapter,
+    adapter: &dyn ProtocolAdapter,
     state: ConnectionState,
     packet: &InternalPacket,
 ) -> Result<()> {
     let encoded = adapter.encode_packet(state, packet)?;
     let mut data = encoded;
     let packet_id = pickaxe_protocol_core::read_varint(&mut data)?;
+    if let Some(capture) = conn.capture() {
+        record(capture, Direction::Clientbound, state, packet_id, &data, Some(packet));
+    }
     conn.write_packet(packet_id, &data).await
 }
 
+/// Read and decode the next packet, recording it if the connection is
+/// captured. Returns the packet ID with the packet.
+async fn receive(
+    conn: &mut Connection,
+    adapter: &dyn ProtocolAdapter,
+    state: ConnectionState,
+) -> Result<(i32, InternalPacket)> {
+    let (id, mut data) = conn.read_packet().await?;
+    let payload = conn.capture().map(|_| data.clone());
+    let packet = adapter.decode_packet(state, id, &mut data);
+    if let (Some(capture), Some(payload)) = (conn.capture(), payload) {
+        record(capture, Direction::Serverbound, state, id, &payload, packet.as_ref().ok());
+    }
+    Ok((id, packet?))
+}
+
+fn record(
+    capture: &SharedCapture,
+    direction: Direction,
+    state: ConnectionState,
+    packet_id: i32,
+    payload: &[u8],
+    packet: Option<&InternalPacket>,
+) {
+    if let Ok(mut capture) = capture.lock() {
+        if let Err(e) = capture.record(direction, state, packet_id, payload, packet) {
+            debug!("Failed to record packet: {}", e);
+        }
+    }
+}
+
+/// Start capturing a connection to `<dir>/<time>-<address>.pxcap`.
+fn start_capture(dir: &str, peer: std::net::SocketAddr) -> Result<SharedCapture> {
+    std::fs::create_dir_all(dir)?;
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let name = format!("{}-{}.pxcap", millis, peer.to_string().replace([':', '[', ']'], "_"));
+    let path = std::path::Path::new(dir).join(name);
+    let file: Box<dyn std::io::Write + Send> = Box::new(std::fs::File::create(&path)?);
+    info!("Capturing packets from {} to {}", peer, path.display());
+    Ok(Arc::new(std::sync::Mutex::new(CaptureWriter::new(file)?)))
+}
+
+/// Show `reason` on the client's disconnect screen before the connection is
+/// dropped. The adapter encodes it for the login, configuration or play state.
+async fn disconnect(
+    conn: &mut Connection,
+    adapter: &dyn ProtocolAdapter,
+    state: ConnectionState,
+    reason: TextComponent,
+) -> Result<()> {
+    send_packet(conn, adapter, state, &InternalPacket::Disconnect { reason }).await
+}
+
 /// Generate an offline-mode UUID from a player name.
-fn offline_uuid(name: &str) -> Uuid {
+pub(crate) fn offline_uuid(name: &str) -> Uuid {
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
     let input = format!("OfflinePlayer:{}", name);
@@ -0,0 +1,143 @@
+use crate::config::OverloadConfig;
+use std::sync::{LazyLock, Mutex};
+
+/// How much optional work the server is shedding to keep up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LoadLevel {
+    #[default]
+    Normal,
+    Strained,
+    Overloaded,
+}
+
+impl LoadLevel {
+    pub fn name(self) -> &'static str {
+        match self {
+            LoadLevel::Normal => "normal",
+            LoadLevel::Strained => "strained",
+            LoadLevel::Overloaded => "overloaded",
+        }
+    }
+
+    /// Whether natural mob spawning runs this tick: every other tick when
+    /// strained, never when overloaded.
+    pub fn spawns_mobs(self, tick_count: u64) -> bool {
+        match self {
+            LoadLevel::Normal => true,
+            LoadLevel::Strained => tick_count.is_multiple_of(2),
+            LoadLevel::Overloaded => false,
+        }
+    }
+
+    /// Whether purely cosmetic broadcasts, like particle emitters and
+    /// ambient mob sounds, are sent.
+    pub fn sends_cosmetics(self) -> bool {
+        self == LoadLevel::Normal
+    }
+
+    /// How many times further apart random ticks run.
+    pub fn random_tick_divisor(self) -> u64 {
+        match self {
+            LoadLevel::Normal => 1,
+            LoadLevel::Strained => 2,
+            LoadLevel::Overloaded => 4,
+        }
+    }
+}
+
+/// Picks the load level from the rolling tick time. A level only changes
+/// once the load has stayed past its threshold for a while, so one slow
+/// tick (a world save, a big explosion) doesn't flip it back and forth.
+#[derive(Debug, Default)]
+pub struct LoadMonitor {
+    config: OverloadConfig,
+    level: LoadLevel,
+    /// Ticks in a row the measured level has differed from `level`.
+    pending_ticks: u64,
+}
+
+impl LoadMonitor {
+    pub fn new(config: &OverloadConfig) -> Self {
+        Self {
+            config: config.clone(),
+            ..Self::default()
+        }
+    }
+
+    pub fn level(&self) -> LoadLevel {
+        self.level
+    }
+
+    /// Feed one tick's average milliseconds per tick against the tick
+    /// budget. Returns the new level when it changes.
+    pub fn update(&mut self, mspt: f64, target_mspt: f64) -> Option<LoadLevel> {
+        if !self.config.enabled || target_mspt <= 0.0 {
+            return None;
+        }
+        let percent = mspt / target_mspt * 100.0;
+        let measured = if percent >= self.config.overloaded_percent {
+            LoadLevel::Overloaded
+        } else if percent >= self.config.strained_percent {
+            LoadLevel::Strained
+        } else {
+            LoadLevel::Normal
+        };
+        if measured == self.level {
+            self.pending_ticks = 0;
+            return None;
+        }
+        self.pending_ticks += 1;
+        let seconds = if measured > self.level { self.config.sustain_seconds } else { self.config.recover_seconds };
+        if self.pending_ticks < seconds * 20 {
+            return None;
+        }
+        self.pending_ticks = 0;
+        self.level = measured;
+        Some(measured)
+    }
+}
+
+/// Tick timings and load, for the metrics endpoint.
+#[derive(Debug, Clone, Default)]
+pub struct LoadStats {
+    pub tps: f64,
+    pub mspt: f64,
+    pub max_mspt: f64,
+    pub level: LoadLevel,
+    /// Times the server has started shedding more work.
+    pub escalations: u64,
+}
+
+/// The tick loop's load, updated every tick.
+pub static LOAD: LazyLock<Mutex<LoadStats>> = LazyLock::new(Mutex::default);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escalate_and_recover() {
+        let config = OverloadConfig { sustain_seconds: 1, recover_seconds: 2, ..Default::default() };
+        let mut monitor = LoadMonitor::new(&config);
+
+        // A short spike isn't enough
+        for _ in 0..10 {
+            assert_eq!(monitor.update(70.0, 50.0), None);
+        }
+        assert_eq!(monitor.update(20.0, 50.0), None);
+        for _ in 0..19 {
+            monitor.update(45.0, 50.0);
+        }
+        assert_eq!(monitor.update(45.0, 50.0), Some(LoadLevel::Strained));
+        assert!(!monitor.level().sends_cosmetics());
+        assert!(monitor.level().spawns_mobs(4) && !monitor.level().spawns_mobs(5));
+
+        for _ in 0..39 {
+            assert_eq!(monitor.update(10.0, 50.0), None);
+        }
+        assert_eq!(monitor.update(10.0, 50.0), Some(LoadLevel::Normal));
+
+        let mut disabled = LoadMonitor::new(&OverloadConfig { enabled: false, ..Default::default() });
+        assert_eq!(disabled.update(500.0, 50.0), None);
+    }
+}
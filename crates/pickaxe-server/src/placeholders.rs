@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+/// Placeholder names used in a template, as `%name%` with lowercase letters,
+/// digits and underscores. Each name is listed once.
+pub fn names(template: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    for (_, name) in tokens(template) {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// Replace each `%name%` that has a value; unknown placeholders are left as
+/// written.
+pub fn fill(template: &str, values: &HashMap<&str, String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut last = 0;
+    for (start, name) in tokens(template) {
+        if let Some(value) = values.get(name) {
+            out.push_str(&template[last..start]);
+            out.push_str(value);
+            last = start + name.len() + 2;
+        }
+    }
+    out.push_str(&template[last..]);
+    out
+}
+
+/// Whether `name` can be used as `%name%`.
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(is_name_char)
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'
+}
+
+/// `(start, name)` of each `%name%` token.
+fn tokens(template: &str) -> Vec<(usize, &str)> {
+    let mut tokens = Vec::new();
+    let mut rest = 0;
+    while let Some(offset) = template[rest..].find('%') {
+        let start = rest + offset;
+        let after = &template[start + 1..];
+        let len = after.find(|c: char| !is_name_char(c)).unwrap_or(after.len());
+        if len > 0 && after[len..].starts_with('%') {
+            tokens.push((start, &after[..len]));
+            rest = start + len + 2;
+        } else {
+            rest = start + 1;
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names() {
+        assert_eq!(names("%player_name% has %player_health% hp, %player_name%"), vec!["player_name", "player_health"]);
+        assert_eq!(names("100% sure, 50%off %Bad% %%"), Vec::<&str>::new());
+        assert!(valid_name("economy_balance") && !valid_name("Balance") && !valid_name(""));
+    }
+
+    #[test]
+    fn test_fill() {
+        let values = HashMap::from([("tps", "20.0".to_string()), ("player_name", "Steve".to_string())]);
+        assert_eq!(fill("&7%player_name% | TPS %tps%", &values), "&7Steve | TPS 20.0");
+        assert_eq!(fill("%unknown% at 100% %tps%", &values), "%unknown% at 100% 20.0");
+    }
+}
@@ -0,0 +1,390 @@
+use crate::teleports::{self, Location};
+use crate::tick::{
+    ecs_slot_to_nbt, item_stack_from_nbt, item_stack_to_nbt, nbt_slot_to_ecs, xp_needed_for_level, SaveOp,
+};
+use bytes::BytesMut;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use pickaxe_nbt::{NbtLimits, NbtValue};
+use pickaxe_types::{GameMode, ItemStack, Vec3d};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+/// Player data files (`<world>/playerdata/<uuid>.dat`), shared by the tick loop
+/// and the background saver task.
+///
+/// Saves are staged here before the saver writes them, so readers see the newest
+/// data even while it is still queued. The staging lock is held for the whole
+/// file write (and for disk reads), so the tick loop never reads a half-written
+/// file and offline edits can't be overwritten by an older queued save.
+pub struct PlayerDataStore {
+    dir: PathBuf,
+    /// Newest not-yet-written save per player.
+    pending: Mutex<HashMap<Uuid, Vec<u8>>>,
+    /// Lowercase name → (last known name, UUID), for looking up offline players.
+    names: Mutex<HashMap<String, (String, Uuid)>>,
+    /// Saves queued per player this run, so an offline edit can tell that the
+    /// data it was loaded from has since been replaced.
+    revisions: Mutex<HashMap<Uuid, u64>>,
+}
+
+impl PlayerDataStore {
+    /// Open the playerdata directory, indexing the last known name of every saved player.
+    pub fn new(dir: PathBuf) -> Self {
+        let _ = std::fs::create_dir_all(&dir);
+        let mut names = HashMap::new();
+        if let Ok(entries) = std::fs::read_dir(&dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().and_then(|e| e.to_str()) != Some("dat") {
+                    continue;
+                }
+                let Some(uuid) = path.file_stem().and_then(|s| s.to_str()).and_then(|s| Uuid::parse_str(s).ok()) else {
+                    continue;
+                };
+                let name = std::fs::read(&path).ok()
+                    .and_then(|data| decode(&data))
+                    .and_then(|nbt| nbt.get("LastKnownName").and_then(|v| v.as_str()).map(str::to_string));
+                if let Some(name) = name {
+                    names.insert(name.to_lowercase(), (name, uuid));
+                }
+            }
+        }
+        tracing::info!("Indexed {} saved players", names.len());
+        Self {
+            dir,
+            pending: Mutex::new(HashMap::new()),
+            names: Mutex::new(names),
+            revisions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn path(&self, uuid: &Uuid) -> PathBuf {
+        self.dir.join(format!("{}.dat", uuid))
+    }
+
+    /// Stage a player's gzipped NBT and queue it for the saver task.
+    pub fn queue_save(&self, uuid: Uuid, name: &str, data: Vec<u8>, save_tx: &mpsc::UnboundedSender<SaveOp>) {
+        if let Ok(mut names) = self.names.lock() {
+            names.insert(name.to_lowercase(), (name.to_string(), uuid));
+        }
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.insert(uuid, data);
+            if let Ok(mut revisions) = self.revisions.lock() {
+                *revisions.entry(uuid).or_default() += 1;
+            }
+        }
+        let _ = save_tx.send(SaveOp::Player(uuid));
+    }
+
+    fn revision(&self, uuid: &Uuid) -> u64 {
+        self.revisions.lock().ok().and_then(|r| r.get(uuid).copied()).unwrap_or(0)
+    }
+
+    /// Read a player's gzipped NBT: the newest staged save if there is one, else the file on disk.
+    pub fn load(&self, uuid: &Uuid) -> Option<Vec<u8>> {
+        let pending = self.pending.lock().ok()?;
+        if let Some(data) = pending.get(uuid) {
+            return Some(data.clone());
+        }
+        std::fs::read(self.path(uuid)).ok()
+    }
+
+    /// Write a player's staged save to disk. Called by the saver task; a no-op if
+    /// an earlier queued op already wrote the newest data.
+    pub fn flush(&self, uuid: &Uuid) {
+        let Ok(mut pending) = self.pending.lock() else { return };
+        let Some(data) = pending.remove(uuid) else { return };
+        if let Err(e) = pickaxe_region::write_atomic(&self.path(uuid), &data) {
+            tracing::error!("Failed to write player data {}: {}", uuid, e);
+        }
+    }
+
+    /// Resolve a player name (case-insensitive) or UUID string to a saved player.
+    pub fn lookup(&self, name_or_uuid: &str) -> Option<(String, Uuid)> {
+        let names = self.names.lock().ok()?;
+        if let Some(found) = names.get(&name_or_uuid.to_lowercase()) {
+            return Some(found.clone());
+        }
+        let uuid = Uuid::parse_str(name_or_uuid).ok()?;
+        let name = names.values()
+            .find(|(_, u)| *u == uuid)
+            .map(|(n, _)| n.clone())
+            .unwrap_or_else(|| uuid.to_string());
+        Some((name, uuid))
+    }
+
+    /// Load a saved player for inspection or editing.
+    pub fn load_offline(&self, name_or_uuid: &str) -> Option<OfflinePlayer> {
+        let (name, uuid) = self.lookup(name_or_uuid)?;
+        let revision = self.revision(&uuid);
+        let nbt = decode(&self.load(&uuid)?)?;
+        Some(OfflinePlayer { uuid, name, nbt, revision })
+    }
+
+    /// Queue an edited offline player for saving. Refused (returning false) if
+    /// the player's data was saved since it was loaded, for example because
+    /// they joined and left in between; saving would bring back the old data.
+    pub fn save_offline(&self, player: &mut OfflinePlayer, save_tx: &mpsc::UnboundedSender<SaveOp>) -> bool {
+        if self.revision(&player.uuid) != player.revision {
+            return false;
+        }
+        self.queue_save(player.uuid, &player.name, encode(&player.nbt), save_tx);
+        player.revision = self.revision(&player.uuid);
+        true
+    }
+}
+
+/// Gunzip and parse a player data file.
+pub fn decode(data: &[u8]) -> Option<NbtValue> {
+    let (_, nbt) = NbtValue::read_root_named_from(GzDecoder::new(data), NbtLimits::FILE).ok()?;
+    Some(nbt)
+}
+
+/// Serialize and gzip a player data compound.
+pub fn encode(nbt: &NbtValue) -> Vec<u8> {
+    let mut buf = BytesMut::new();
+    nbt.write_root_named("", &mut buf);
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    let _ = encoder.write_all(&buf);
+    encoder.finish().unwrap_or_default()
+}
+
+/// A saved player's data, loaded while they are offline. Edits keep every
+/// field this server doesn't understand intact.
+pub struct OfflinePlayer {
+    pub uuid: Uuid,
+    pub name: String,
+    nbt: NbtValue,
+    /// The store's revision of this player when loaded.
+    revision: u64,
+}
+
+impl OfflinePlayer {
+    pub fn position(&self) -> Vec3d {
+        let coord = |i: usize| {
+            self.nbt.get("Pos")
+                .and_then(|v| v.as_list())
+                .and_then(|l| l.get(i))
+                .and_then(|v| v.as_double())
+                .unwrap_or(0.0)
+        };
+        Vec3d::new(coord(0), coord(1), coord(2))
+    }
+
+    pub fn set_position(&mut self, pos: Vec3d) {
+        self.nbt.set("Pos", NbtValue::List(vec![
+            NbtValue::Double(pos.x),
+            NbtValue::Double(pos.y),
+            NbtValue::Double(pos.z),
+        ]));
+    }
+
+    pub fn health(&self) -> f32 {
+        self.nbt.get("Health").and_then(|v| v.as_float()).unwrap_or(20.0)
+    }
+
+    pub fn game_mode(&self) -> GameMode {
+        match self.nbt.get("playerGameType").and_then(|v| v.as_int()).unwrap_or(0) {
+            1 => GameMode::Creative,
+            2 => GameMode::Adventure,
+            3 => GameMode::Spectator,
+            _ => GameMode::Survival,
+        }
+    }
+
+    /// XP as (level, progress into the level, total points).
+    pub fn xp(&self) -> (i32, f32, i32) {
+        (
+            self.nbt.get("XpLevel").and_then(|v| v.as_int()).unwrap_or(0),
+            self.nbt.get("XpP").and_then(|v| v.as_float()).unwrap_or(0.0),
+            self.nbt.get("XpTotal").and_then(|v| v.as_int()).unwrap_or(0),
+        )
+    }
+
+    /// Set the XP level, resetting progress and recomputing the point total.
+    pub fn set_xp_level(&mut self, level: i32) {
+        let level = level.max(0);
+        let total: i32 = (0..level).map(xp_needed_for_level).sum();
+        self.nbt.set("XpLevel", NbtValue::Int(level));
+        self.nbt.set("XpP", NbtValue::Float(0.0));
+        self.nbt.set("XpTotal", NbtValue::Int(total));
+    }
+
+    /// Inventory in ECS slot order (see `Inventory`).
+    pub fn inventory(&self) -> [Option<ItemStack>; 46] {
+        let mut slots: [Option<ItemStack>; 46] = std::array::from_fn(|_| None);
+        if let Some(list) = self.nbt.get("Inventory").and_then(|v| v.as_list()) {
+            for entry in list {
+                if let Some((nbt_slot, stack)) = item_stack_from_nbt(entry) {
+                    if let Some(ecs_slot) = nbt_slot_to_ecs(nbt_slot) {
+                        slots[ecs_slot] = Some(stack);
+                    }
+                }
+            }
+        }
+        slots
+    }
+
+    /// Replace (or clear) one ECS inventory slot. Returns false for slots that
+    /// aren't saved (crafting grid).
+    pub fn set_slot(&mut self, ecs_slot: usize, item: Option<ItemStack>) -> bool {
+        let Some(nbt_slot) = ecs_slot_to_nbt(ecs_slot) else { return false };
+        let mut list: Vec<NbtValue> = self.nbt.get("Inventory")
+            .and_then(|v| v.as_list())
+            .map(|l| l.to_vec())
+            .unwrap_or_default();
+        list.retain(|entry| entry.get("Slot").and_then(|v| v.as_byte()) != Some(nbt_slot));
+        if let Some(stack) = item {
+            list.push(item_stack_to_nbt(nbt_slot, &stack));
+        }
+        self.nbt.set("Inventory", NbtValue::List(list));
+        true
+    }
+
+    /// Homes set with /sethome.
+    pub fn homes(&self) -> BTreeMap<String, Location> {
+        self.nbt.get("PickaxeHomes").map(teleports::locations_from_nbt).unwrap_or_default()
+    }
+
+    pub fn set_homes(&mut self, homes: &BTreeMap<String, Location>) {
+        if homes.is_empty() {
+            self.nbt.remove("PickaxeHomes");
+        } else {
+            self.nbt.set("PickaxeHomes", teleports::locations_to_nbt(homes));
+        }
+    }
+
+    /// A top-level tag of the saved data, for edits the accessors above don't cover.
+    pub fn tag(&self, key: &str) -> Option<&NbtValue> {
+        self.nbt.get(key)
+    }
+
+    pub fn set_tag(&mut self, key: &str, value: Option<NbtValue>) {
+        match value {
+            Some(value) => self.nbt.set(key, value),
+            None => {
+                self.nbt.remove(key);
+            }
+        }
+    }
+}
+
+/// Convert a value set with `pickaxe.players.set_data` to NBT for the
+/// `PickaxeData` compound. Booleans become bytes, integers longs and other
+/// numbers doubles. Lists mixing integers and other numbers are saved as
+/// doubles; other mixed lists and nulls can't be saved.
+pub fn json_to_nbt(value: &serde_json::Value) -> Result<NbtValue, String> {
+    use serde_json::Value;
+    Ok(match value {
+        Value::Null => return Err("nil can't be saved inside a value".into()),
+        Value::Bool(b) => NbtValue::Byte(*b as i8),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => NbtValue::Long(i),
+            None => NbtValue::Double(n.as_f64().unwrap_or(0.0)),
+        },
+        Value::String(s) => NbtValue::String(s.clone()),
+        Value::Array(items) => {
+            let mut list = items.iter().map(json_to_nbt).collect::<Result<Vec<_>, _>>()?;
+            if list.iter().any(|v| v.tag_id() != list[0].tag_id()) {
+                if !list.iter().all(|v| matches!(v, NbtValue::Long(_) | NbtValue::Double(_))) {
+                    return Err("lists must hold values of one type".into());
+                }
+                for v in &mut list {
+                    if let NbtValue::Long(i) = *v {
+                        *v = NbtValue::Double(i as f64);
+                    }
+                }
+            }
+            NbtValue::List(list)
+        }
+        Value::Object(fields) => NbtValue::Compound(
+            fields.iter().map(|(k, v)| Ok((k.clone(), json_to_nbt(v)?))).collect::<Result<_, String>>()?,
+        ),
+    })
+}
+
+/// The inverse of `json_to_nbt`, for reading `PickaxeData` back.
+pub fn nbt_to_json(nbt: &NbtValue) -> serde_json::Value {
+    use serde_json::Value;
+    match nbt {
+        NbtValue::Byte(b) => Value::Bool(*b != 0),
+        NbtValue::Short(v) => Value::from(*v),
+        NbtValue::Int(v) => Value::from(*v),
+        NbtValue::Long(v) => Value::from(*v),
+        NbtValue::Float(v) => Value::from(*v),
+        NbtValue::Double(v) => Value::from(*v),
+        NbtValue::String(s) => Value::String(s.clone()),
+        NbtValue::ByteArray(v) => v.iter().map(|&b| Value::from(b)).collect(),
+        NbtValue::IntArray(v) => v.iter().map(|&i| Value::from(i)).collect(),
+        NbtValue::LongArray(v) => v.iter().map(|&l| Value::from(l)).collect(),
+        NbtValue::List(items) => items.iter().map(nbt_to_json).collect(),
+        NbtValue::Compound(fields) => Value::Object(fields.iter().map(|(k, v)| (k.clone(), nbt_to_json(v))).collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_mod_data_round_trip() {
+        let value = json!({"kit_used": 1718000000, "done": true, "quests": ["mine", "craft"], "ratio": 0.5});
+        assert_eq!(nbt_to_json(&json_to_nbt(&value).unwrap()), value);
+        assert_eq!(nbt_to_json(&json_to_nbt(&json!([1, 2.5])).unwrap()), json!([1.0, 2.5]));
+        assert!(json_to_nbt(&json!([1, "two"])).is_err());
+        assert!(json_to_nbt(&json!({"a": null})).is_err());
+    }
+
+    #[test]
+    fn test_offline_edits_keep_other_tags() {
+        let mut player = OfflinePlayer {
+            uuid: Uuid::nil(),
+            name: "Steve".into(),
+            nbt: NbtValue::Compound(vec![("Custom".into(), NbtValue::Int(7))]),
+            revision: 0,
+        };
+        let mut homes = BTreeMap::new();
+        homes.insert("base".to_string(), Location { position: Vec3d::new(1.0, 64.0, 2.0), yaw: 90.0, pitch: 0.0 });
+        player.set_homes(&homes);
+        assert!(player.set_slot(36, Some(ItemStack::new(1, 5))));
+
+        let player = OfflinePlayer { nbt: decode(&encode(&player.nbt)).unwrap(), ..player };
+        assert_eq!(player.homes(), homes);
+        assert_eq!(player.inventory()[36].as_ref().map(|s| s.count), Some(5));
+        assert_eq!(player.tag("Custom"), Some(&NbtValue::Int(7)));
+
+        let mut player = player;
+        player.set_homes(&BTreeMap::new());
+        player.set_tag("Custom", None);
+        assert!(player.tag("PickaxeHomes").is_none() && player.tag("Custom").is_none());
+    }
+
+    #[test]
+    fn test_stale_offline_save_refused() {
+        let dir = std::env::temp_dir().join(format!("pickaxe-playerdata-test-{}", std::process::id()));
+        let store = PlayerDataStore::new(dir.clone());
+        let (save_tx, _save_rx) = mpsc::unbounded_channel();
+        let uuid = Uuid::from_u128(1);
+        let data = encode(&NbtValue::Compound(vec![("LastKnownName".into(), NbtValue::String("Alex".into()))]));
+        store.queue_save(uuid, "Alex", data.clone(), &save_tx);
+
+        let mut stale = store.load_offline("alex").unwrap();
+        let mut fresh = store.load_offline(&uuid.to_string()).unwrap();
+        // A handle may save repeatedly, but other copies are now stale
+        assert!(store.save_offline(&mut fresh, &save_tx));
+        assert!(store.save_offline(&mut fresh, &save_tx));
+        assert!(!store.save_offline(&mut stale, &save_tx));
+
+        // The player joining and leaving saves their live data
+        store.queue_save(uuid, "Alex", data, &save_tx);
+        assert!(!store.save_offline(&mut fresh, &save_tx));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
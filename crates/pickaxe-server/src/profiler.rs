@@ -0,0 +1,230 @@
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Ticks the rolling averages cover.
+const WINDOW_TICKS: usize = 100;
+
+/// Where `/debug timings dump` writes reports.
+pub const DUMP_DIR: &str = "debug";
+
+/// Time spent in each tick system while `/debug timings` is running, so a
+/// slow tick can be traced to the system that made it slow.
+#[derive(Default)]
+pub struct TickProfiler {
+    /// When profiling started; `None` while stopped.
+    started: Option<Instant>,
+    /// How long the last run went on, once stopped.
+    ran_for: Duration,
+    /// Ticks profiled.
+    ticks: u64,
+    /// Whole-tick times, like the systems'.
+    tick: SystemTimings,
+    /// Per system, in the order they first ran.
+    systems: Vec<(&'static str, SystemTimings)>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct SystemTimings {
+    total: Duration,
+    calls: u64,
+    /// Slowest single tick.
+    max: Duration,
+    /// Time in the tick being profiled.
+    this_tick: Duration,
+    /// Time in each of the last `WINDOW_TICKS` ticks.
+    recent: VecDeque<Duration>,
+}
+
+impl SystemTimings {
+    fn end_tick(&mut self) {
+        self.max = self.max.max(self.this_tick);
+        if self.recent.len() == WINDOW_TICKS {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(std::mem::take(&mut self.this_tick));
+    }
+
+    fn rolling(&self) -> Duration {
+        self.recent.iter().sum::<Duration>().checked_div(self.recent.len() as u32).unwrap_or_default()
+    }
+}
+
+/// One system's line in the `/debug timings` report.
+#[derive(Debug, PartialEq)]
+pub struct SystemReport {
+    pub name: &'static str,
+    pub calls: u64,
+    /// Average milliseconds per tick since profiling started.
+    pub ms_per_tick: f64,
+    /// Average over the last `WINDOW_TICKS` ticks.
+    pub rolling_ms: f64,
+    /// The slowest tick, in milliseconds.
+    pub max_ms: f64,
+}
+
+/// The server's tick profiler.
+pub static PROFILER: LazyLock<Mutex<TickProfiler>> = LazyLock::new(Mutex::default);
+
+/// Record time spent in a tick system; ignored while profiling is stopped.
+pub fn record(system: &'static str, elapsed: Duration) {
+    if let Ok(mut profiler) = PROFILER.lock() {
+        profiler.record(system, elapsed);
+    }
+}
+
+impl TickProfiler {
+    /// Start profiling from zero.
+    pub fn start(&mut self, now: Instant) {
+        *self = Self {
+            started: Some(now),
+            ..Self::default()
+        };
+    }
+
+    /// Stop profiling, keeping what was measured for the report.
+    pub fn stop(&mut self, now: Instant) {
+        if let Some(started) = self.started.take() {
+            self.ran_for = now.duration_since(started);
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started.is_some()
+    }
+
+    pub fn record(&mut self, system: &'static str, elapsed: Duration) {
+        if !self.is_running() {
+            return;
+        }
+        let index = match self.systems.iter().position(|(name, _)| *name == system) {
+            Some(index) => index,
+            None => {
+                self.systems.push((system, SystemTimings::default()));
+                self.systems.len() - 1
+            }
+        };
+        let timings = &mut self.systems[index].1;
+        timings.total += elapsed;
+        timings.calls += 1;
+        timings.this_tick += elapsed;
+    }
+
+    /// Close a tick that took `elapsed` in total. Called at the end of every tick.
+    pub fn end_tick(&mut self, elapsed: Duration) {
+        if !self.is_running() {
+            return;
+        }
+        self.ticks += 1;
+        self.tick.total += elapsed;
+        self.tick.calls += 1;
+        self.tick.this_tick = elapsed;
+        self.tick.end_tick();
+        for (_, timings) in &mut self.systems {
+            timings.end_tick();
+        }
+    }
+
+    /// Ticks the report covers.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// How long profiling has been running, or ran for.
+    pub fn duration(&self, now: Instant) -> Duration {
+        self.started.map_or(self.ran_for, |started| now.duration_since(started))
+    }
+
+    /// The whole tick's line: average, rolling average and slowest tick.
+    pub fn tick_report(&self) -> SystemReport {
+        self.report_for("tick", &self.tick)
+    }
+
+    /// Per-system times, the most expensive first.
+    pub fn report(&self) -> Vec<SystemReport> {
+        let mut report: Vec<SystemReport> =
+            self.systems.iter().map(|(name, timings)| self.report_for(name, timings)).collect();
+        report.sort_by(|a, b| b.ms_per_tick.total_cmp(&a.ms_per_tick));
+        report
+    }
+
+    fn report_for(&self, name: &'static str, timings: &SystemTimings) -> SystemReport {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        SystemReport {
+            name,
+            calls: timings.calls,
+            ms_per_tick: ms(timings.total) / self.ticks.max(1) as f64,
+            rolling_ms: ms(timings.rolling()),
+            max_ms: ms(timings.max),
+        }
+    }
+
+    /// The report as JSON, for `/debug timings dump`.
+    pub fn to_json(&self, now: Instant) -> Value {
+        let line = |r: &SystemReport| {
+            json!({
+                "name": r.name,
+                "calls": r.calls,
+                "ms_per_tick": r.ms_per_tick,
+                "rolling_ms": r.rolling_ms,
+                "max_ms": r.max_ms,
+            })
+        };
+        json!({
+            "running": self.is_running(),
+            "seconds": self.duration(now).as_secs_f64(),
+            "ticks": self.ticks,
+            "window_ticks": WINDOW_TICKS,
+            "tick": line(&self.tick_report()),
+            "systems": self.report().iter().map(line).collect::<Vec<_>>(),
+        })
+    }
+
+    /// Write the JSON report to `<dir>/timings-<unix seconds>.json`.
+    pub fn dump(&self, dir: &Path, now: Instant) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let path = dir.join(format!("timings-{}.json", secs));
+        fs::write(&path, serde_json::to_string_pretty(&self.to_json(now))?)?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let mut profiler = TickProfiler::default();
+        let now = Instant::now();
+        profiler.record("tick_fluids", Duration::from_millis(50));
+        profiler.end_tick(Duration::from_millis(50));
+        assert_eq!(profiler.ticks(), 0);
+
+        profiler.start(now);
+        for i in 0..4 {
+            profiler.record("tick_mob_ai", Duration::from_millis(2));
+            if i % 2 == 0 {
+                profiler.record("tick_fluids", Duration::from_millis(6));
+            }
+            profiler.end_tick(Duration::from_millis(10));
+        }
+        profiler.stop(now + Duration::from_secs(1));
+        profiler.record("tick_mob_ai", Duration::from_millis(100));
+
+        let report = profiler.report();
+        assert_eq!(report[0], SystemReport { name: "tick_fluids", calls: 2, ms_per_tick: 3.0, rolling_ms: 3.0, max_ms: 6.0 });
+        assert_eq!(report[1].calls, 4);
+        assert_eq!(report[1].max_ms, 2.0);
+        assert_eq!(profiler.tick_report().ms_per_tick, 10.0);
+
+        let json = profiler.to_json(now);
+        assert_eq!(json["seconds"], 1.0);
+        assert_eq!(json["systems"][0]["name"], "tick_fluids");
+    }
+}
@@ -0,0 +1,141 @@
+use bytes::BytesMut;
+use hmac::{Hmac, Mac};
+use pickaxe_protocol_core::{read_string, read_u8, read_uuid, read_varint};
+use pickaxe_types::{GameProfile, ProfileProperty};
+use sha2::Sha256;
+use std::net::IpAddr;
+use uuid::Uuid;
+
+/// Login plugin channel Velocity answers with the player's details.
+pub const VELOCITY_CHANNEL: &str = "velocity:player_info";
+
+/// Modern forwarding version requested from Velocity. Version 1 carries the
+/// address, UUID, name and properties, which is all the server reads.
+pub const VELOCITY_FORWARDING_VERSION: u8 = 1;
+
+/// Length of the HMAC-SHA256 signature that prefixes Velocity's payload.
+const SIGNATURE_LEN: usize = 32;
+
+/// A player's details as forwarded by the proxy in front of the server.
+#[derive(Debug)]
+pub struct ForwardedPlayer {
+    /// The player's own address, not the proxy's.
+    pub address: IpAddr,
+    pub uuid: Uuid,
+    /// Only Velocity forwards the name; BungeeCord's is taken from Login Start.
+    pub name: Option<String>,
+    pub properties: Vec<ProfileProperty>,
+}
+
+impl ForwardedPlayer {
+    pub fn into_profile(self, login_name: &str) -> GameProfile {
+        GameProfile {
+            uuid: self.uuid,
+            name: self.name.unwrap_or_else(|| login_name.to_string()),
+            properties: self.properties,
+        }
+    }
+}
+
+/// Split the handshake address BungeeCord's `ip_forward` writes:
+/// `host\0ip\0uuid[\0properties json]`, with the UUID undashed.
+pub fn parse_bungee_address(address: &str) -> Option<ForwardedPlayer> {
+    let mut parts = address.split('\0');
+    let _host = parts.next()?;
+    let address = parts.next()?.parse().ok()?;
+    let uuid = Uuid::parse_str(parts.next()?).ok()?;
+    let properties = match parts.next() {
+        Some(json) => serde_json::from_str(json).ok()?,
+        None => Vec::new(),
+    };
+    Some(ForwardedPlayer {
+        address,
+        uuid,
+        name: None,
+        properties,
+    })
+}
+
+/// Check the signature on Velocity's `player_info` response against the
+/// forwarding secret and read the player's details. None if the signature
+/// doesn't match or the payload is malformed.
+pub fn parse_velocity_response(secret: &str, data: &[u8]) -> Option<ForwardedPlayer> {
+    if data.len() < SIGNATURE_LEN {
+        return None;
+    }
+    let (signature, payload) = data.split_at(SIGNATURE_LEN);
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).ok()?;
+    mac.update(payload);
+    mac.verify_slice(signature).ok()?;
+
+    let mut buf = BytesMut::from(payload);
+    let version = read_varint(&mut buf).ok()?;
+    if version < VELOCITY_FORWARDING_VERSION as i32 {
+        return None;
+    }
+    let address = read_string(&mut buf, 255).ok()?.parse().ok()?;
+    let uuid = read_uuid(&mut buf).ok()?;
+    let name = read_string(&mut buf, 16).ok()?;
+    let count = read_varint(&mut buf).ok()?;
+    let mut properties = Vec::new();
+    for _ in 0..count {
+        let name = read_string(&mut buf, 32767).ok()?;
+        let value = read_string(&mut buf, 32767).ok()?;
+        let signature = if read_u8(&mut buf).ok()? != 0 {
+            Some(read_string(&mut buf, 32767).ok()?)
+        } else {
+            None
+        };
+        properties.push(ProfileProperty {
+            name,
+            value,
+            signature,
+        });
+    }
+    Some(ForwardedPlayer {
+        address,
+        uuid,
+        name: Some(name),
+        properties,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pickaxe_protocol_core::{write_string, write_uuid, write_varint};
+
+    #[test]
+    fn test_parse_bungee_address() {
+        let address = "play.example.com\u{0}203.0.113.7\u{0}069a79f444e94726a5befca90e38aaf5\u{0}\
+            [{\"name\":\"textures\",\"value\":\"abc\",\"signature\":\"sig\"}]";
+        let player = parse_bungee_address(address).unwrap();
+        assert_eq!(player.address.to_string(), "203.0.113.7");
+        assert_eq!(player.uuid.to_string(), "069a79f4-44e9-4726-a5be-fca90e38aaf5");
+        assert_eq!(player.properties[0].signature.as_deref(), Some("sig"));
+        assert_eq!(player.into_profile("Notch").name, "Notch");
+
+        // A plain hostname means the proxy isn't forwarding
+        assert!(parse_bungee_address("play.example.com").is_none());
+    }
+
+    #[test]
+    fn test_parse_velocity_response() {
+        let uuid = Uuid::parse_str("069a79f444e94726a5befca90e38aaf5").unwrap();
+        let mut payload = BytesMut::new();
+        write_varint(&mut payload, 1);
+        write_string(&mut payload, "203.0.113.7");
+        write_uuid(&mut payload, &uuid);
+        write_string(&mut payload, "Notch");
+        write_varint(&mut payload, 0);
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"secret").unwrap();
+        mac.update(&payload);
+        let mut data = mac.finalize().into_bytes().to_vec();
+        data.extend_from_slice(&payload);
+
+        let player = parse_velocity_response("secret", &data).unwrap();
+        assert_eq!(player.uuid, uuid);
+        assert_eq!(player.name.as_deref(), Some("Notch"));
+        assert!(parse_velocity_response("wrong", &data).is_none());
+    }
+}
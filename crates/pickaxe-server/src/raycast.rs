@@ -0,0 +1,113 @@
+use pickaxe_types::{BlockPos, Vec3d};
+
+/// The first block a ray stopped at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub block: BlockPos,
+    /// Face the ray entered through, numbered as in block placement:
+    /// 0 down, 1 up, 2 north, 3 south, 4 west, 5 east.
+    pub face: u8,
+    /// Distance from the origin to `point`.
+    pub distance: f64,
+    /// Where the ray entered the block.
+    pub point: Vec3d,
+}
+
+/// Visit the blocks a ray passes through in order, starting with the one
+/// containing `origin`, and return the first that `is_hit` accepts within
+/// `max_distance`.
+pub fn raycast(origin: Vec3d, dir: Vec3d, max_distance: f64, mut is_hit: impl FnMut(&BlockPos) -> bool) -> Option<RayHit> {
+    let length = (dir.x * dir.x + dir.y * dir.y + dir.z * dir.z).sqrt();
+    if length == 0.0 || !length.is_finite() {
+        return None;
+    }
+    let o = [origin.x, origin.y, origin.z];
+    let d = [dir.x / length, dir.y / length, dir.z / length];
+    let mut cell = [o[0].floor() as i32, o[1].floor() as i32, o[2].floor() as i32];
+    let mut step = [0i32; 3];
+    // Distance along the ray to the next boundary on each axis, and between boundaries
+    let mut t_max = [f64::INFINITY; 3];
+    let mut t_delta = [f64::INFINITY; 3];
+    for a in 0..3 {
+        if d[a] > 0.0 {
+            step[a] = 1;
+            t_max[a] = (cell[a] as f64 + 1.0 - o[a]) / d[a];
+            t_delta[a] = 1.0 / d[a];
+        } else if d[a] < 0.0 {
+            step[a] = -1;
+            t_max[a] = (o[a] - cell[a] as f64) / -d[a];
+            t_delta[a] = -1.0 / d[a];
+        }
+    }
+
+    // The origin block counts as entered along the ray's main axis
+    let mut axis = (0..3).max_by(|&a, &b| d[a].abs().total_cmp(&d[b].abs())).unwrap_or(0);
+    let mut t = 0.0;
+    loop {
+        let block = BlockPos::new(cell[0], cell[1], cell[2]);
+        if is_hit(&block) {
+            return Some(RayHit {
+                block,
+                face: entry_face(axis, step[axis]),
+                distance: t,
+                point: Vec3d::new(o[0] + d[0] * t, o[1] + d[1] * t, o[2] + d[2] * t),
+            });
+        }
+        axis = (0..3).min_by(|&a, &b| t_max[a].total_cmp(&t_max[b])).unwrap_or(0);
+        t = t_max[axis];
+        if t > max_distance {
+            return None;
+        }
+        cell[axis] += step[axis];
+        t_max[axis] += t_delta[axis];
+    }
+}
+
+/// The face a ray moving `step` along `axis` (0 x, 1 y, 2 z) enters through.
+fn entry_face(axis: usize, step: i32) -> u8 {
+    match (axis, step > 0) {
+        (0, true) => 4,
+        (0, false) => 5,
+        (1, true) => 0,
+        (1, false) => 1,
+        (_, true) => 2,
+        (_, false) => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raycast_hits() {
+        let origin = Vec3d::new(0.5, 65.5, 0.5);
+        let wall = |pos: &BlockPos| pos.x == 3;
+        let hit = raycast(origin, Vec3d::new(2.0, 0.0, 0.0), 10.0, wall).unwrap();
+        assert_eq!(hit.block, BlockPos::new(3, 65, 0));
+        assert_eq!(hit.face, 4);
+        assert!((hit.distance - 2.5).abs() < 1e-9);
+        assert!((hit.point.x - 3.0).abs() < 1e-9);
+
+        let ground = |pos: &BlockPos| pos.y <= 63;
+        let hit = raycast(origin, Vec3d::new(0.0, -1.0, 0.0), 10.0, ground).unwrap();
+        assert_eq!(hit.block, BlockPos::new(0, 63, 0));
+        assert_eq!(hit.face, 1);
+        assert!((hit.distance - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_raycast_misses() {
+        let origin = Vec3d::new(0.5, 65.5, 0.5);
+        assert_eq!(raycast(origin, Vec3d::new(1.0, 0.0, 0.0), 2.0, |pos| pos.x == 3), None);
+        assert_eq!(raycast(origin, Vec3d::new(0.0, 0.0, 0.0), 10.0, |_| true), None);
+        // Diagonal rays step through every block they touch
+        let mut visited = Vec::new();
+        raycast(origin, Vec3d::new(1.0, 1.0, 0.0), 2.0, |pos| {
+            visited.push(*pos);
+            false
+        });
+        assert_eq!(visited[0], BlockPos::new(0, 65, 0));
+        assert!(visited.windows(2).all(|w| (w[0].x - w[1].x).abs() + (w[0].y - w[1].y).abs() + (w[0].z - w[1].z).abs() == 1));
+    }
+}
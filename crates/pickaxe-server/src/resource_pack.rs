@@ -0,0 +1,95 @@
+use crate::config::ResourcePackConfig;
+use pickaxe_protocol_core::InternalPacket;
+use pickaxe_types::TextComponent;
+use sha1::{Digest, Sha1};
+use uuid::Uuid;
+
+/// Resource Pack Response results.
+pub const LOADED: i32 = 0;
+pub const DECLINED: i32 = 1;
+pub const DOWNLOAD_FAILED: i32 = 2;
+pub const ACCEPTED: i32 = 3;
+pub const DOWNLOADED: i32 = 4;
+pub const INVALID_URL: i32 = 5;
+pub const RELOAD_FAILED: i32 = 6;
+pub const DISCARDED: i32 = 7;
+
+/// Name passed to Lua's `resource_pack_status` event.
+pub fn status_name(result: i32) -> &'static str {
+    match result {
+        LOADED => "loaded",
+        DECLINED => "declined",
+        DOWNLOAD_FAILED => "download_failed",
+        ACCEPTED => "accepted",
+        DOWNLOADED => "downloaded",
+        INVALID_URL => "invalid_url",
+        RELOAD_FAILED => "reload_failed",
+        DISCARDED => "discarded",
+        _ => "unknown",
+    }
+}
+
+/// Whether the client is done with the pack, one way or the other.
+pub fn is_final(result: i32) -> bool {
+    !matches!(result, ACCEPTED | DOWNLOADED)
+}
+
+/// Whether the player ended up without the pack.
+pub fn is_rejected(result: i32) -> bool {
+    matches!(result, DECLINED | DOWNLOAD_FAILED | INVALID_URL | RELOAD_FAILED)
+}
+
+/// A stable ID for a pack URL, so clients reuse their cached download.
+pub fn pack_uuid(url: &str) -> Uuid {
+    let digest = Sha1::digest(url.as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Builder::from_sha1_bytes(bytes).into_uuid()
+}
+
+/// A hash the client accepts: 40 hex digits, or empty to skip the check.
+pub fn valid_hash(hash: &str) -> bool {
+    hash.is_empty() || (hash.len() == 40 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// The Add Resource Pack packet for the `[resource_pack]` config, sent
+/// during configuration. None if no pack is configured.
+pub fn server_pack(config: &ResourcePackConfig) -> Option<InternalPacket> {
+    if config.url.is_empty() {
+        return None;
+    }
+    let hash = if valid_hash(&config.sha1) {
+        config.sha1.to_ascii_lowercase()
+    } else {
+        tracing::warn!("Ignoring resource_pack.sha1, which must be 40 hex digits");
+        String::new()
+    };
+    Some(InternalPacket::AddResourcePack {
+        uuid: pack_uuid(&config.url),
+        url: config.url.clone(),
+        hash,
+        forced: config.required,
+        prompt: (!config.prompt.is_empty()).then(|| TextComponent::from_legacy(&config.prompt, '&')),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_pack() {
+        let mut config = ResourcePackConfig::default();
+        assert!(server_pack(&config).is_none());
+
+        config.url = "https://example.com/pack.zip".into();
+        config.sha1 = "not a hash".into();
+        let Some(InternalPacket::AddResourcePack { uuid, hash, .. }) = server_pack(&config) else {
+            panic!("a configured pack should be sent");
+        };
+        assert_eq!(uuid, pack_uuid("https://example.com/pack.zip"));
+        assert_eq!(uuid.get_version_num(), 5);
+        assert!(hash.is_empty());
+        assert!(is_rejected(DECLINED) && !is_final(ACCEPTED));
+    }
+}
@@ -0,0 +1,54 @@
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+/// How the saver task is keeping up, for the metrics endpoint and `/debug save`.
+#[derive(Debug, Clone, Default)]
+pub struct SaveStats {
+    /// Ops waiting in the saver's queue when it last took one.
+    pub queue_depth: usize,
+    /// Changed chunks waiting on the tick loop for their turn to be queued.
+    pub pending_chunks: usize,
+    /// Ops the saver has finished.
+    pub ops: u64,
+    /// Chunks written to region files.
+    pub chunks: u64,
+    /// Chunk batches encoded, journaled and written.
+    pub flushes: u64,
+    /// Time spent on chunk batches in total, and on the slowest one.
+    pub flush_time: Duration,
+    pub max_flush_time: Duration,
+}
+
+/// The saver task's stats since startup.
+pub static SAVER: LazyLock<Mutex<SaveStats>> = LazyLock::new(Mutex::default);
+
+impl SaveStats {
+    /// Count a batch of `chunks` that took `elapsed` to encode, journal and write.
+    pub fn record_flush(&mut self, chunks: usize, elapsed: Duration) {
+        self.chunks += chunks as u64;
+        self.flushes += 1;
+        self.flush_time += elapsed;
+        self.max_flush_time = self.max_flush_time.max(elapsed);
+    }
+
+    /// Average time per chunk batch.
+    pub fn mean_flush_time(&self) -> Duration {
+        self.flush_time.checked_div(self.flushes as u32).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_flush() {
+        let mut stats = SaveStats::default();
+        assert_eq!(stats.mean_flush_time(), Duration::ZERO);
+        stats.record_flush(40, Duration::from_millis(30));
+        stats.record_flush(2, Duration::from_millis(10));
+        assert_eq!((stats.chunks, stats.flushes), (42, 2));
+        assert_eq!(stats.mean_flush_time(), Duration::from_millis(20));
+        assert_eq!(stats.max_flush_time, Duration::from_millis(30));
+    }
+}
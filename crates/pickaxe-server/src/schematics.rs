@@ -0,0 +1,72 @@
+use bytes::BytesMut;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use pickaxe_nbt::{NbtLimits, NbtValue};
+use pickaxe_world::Schematic;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Schematic names are file names without `.schem`: letters, digits, `_` and `-`.
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty() && name.len() <= 64 && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+}
+
+fn path(dir: &str, name: &str) -> PathBuf {
+    Path::new(dir).join(format!("{}.schem", name))
+}
+
+/// Read `<dir>/<name>.schem`. Also returns how many of its block states
+/// were unknown and became air.
+pub fn load(dir: &str, name: &str) -> Result<(Schematic, usize), String> {
+    let file = File::open(path(dir, name)).map_err(|e| format!("Can't open schematic {}: {}", name, e))?;
+    let (_, nbt) = NbtValue::read_root_named_from(GzDecoder::new(file), NbtLimits::FILE)
+        .map_err(|e| format!("Can't read schematic {}: {}", name, e))?;
+    Schematic::from_nbt(&nbt).map_err(|e| format!("Can't read schematic {}: {}", name, e))
+}
+
+/// Write `<dir>/<name>.schem`, replacing any schematic of that name.
+pub fn save(dir: &str, name: &str, schematic: &Schematic) -> std::io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let mut buf = BytesMut::new();
+    schematic.to_nbt().write_root_named("", &mut buf);
+    let path = path(dir, name);
+    let tmp = path.with_extension("schem.tmp");
+    let mut encoder = GzEncoder::new(File::create(&tmp)?, Compression::default());
+    encoder.write_all(&buf)?;
+    encoder.finish()?.sync_all()?;
+    fs::rename(&tmp, &path)?;
+    Ok(path)
+}
+
+/// Names of the schematics in `dir`, sorted.
+pub fn list(dir: &str) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|e| e.file_name().to_str()?.strip_suffix(".schem").map(String::from))
+        .collect();
+    names.sort();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("pickaxe-schem-test-{}", std::process::id()));
+        let dir = dir.to_string_lossy().into_owned();
+        let mut schematic = Schematic::new(2, 1, 1);
+        schematic.set_block(1, 0, 0, pickaxe_data::block_name_to_default_state("stone").unwrap());
+
+        assert!(valid_name("house_2") && !valid_name("../house") && !valid_name(""));
+        save(&dir, "house", &schematic).unwrap();
+        assert_eq!(load(&dir, "house").unwrap(), (schematic, 0));
+        assert_eq!(list(&dir), ["house"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,798 @@
+use bytes::BytesMut;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use pickaxe_nbt::{nbt_compound, NbtLimits, NbtValue};
+use pickaxe_protocol_core::{InternalPacket, TeamInfo};
+use pickaxe_types::TextComponent;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Team colors in display slot order (`sidebar.team.<color>` → slot 3 + index).
+const TEAM_COLORS: [&str; 16] = [
+    "black", "dark_blue", "dark_green", "dark_aqua", "dark_red", "dark_purple", "gold", "gray",
+    "dark_gray", "blue", "green", "aqua", "red", "light_purple", "yellow", "white",
+];
+
+/// Number of display slots: list, sidebar, below_name, plus 16 team sidebars.
+const DISPLAY_SLOTS: usize = 19;
+
+/// Valid values for a team's nametagVisibility and deathMessageVisibility.
+const VISIBILITY_OPTIONS: [&str; 4] = ["always", "never", "hideForOtherTeams", "hideForOwnTeam"];
+
+/// Valid values for a team's collisionRule.
+const COLLISION_OPTIONS: [&str; 4] = ["always", "never", "pushOtherTeams", "pushOwnTeam"];
+
+/// Chat formatting ID for "reset" (no team color).
+const COLOR_RESET: i32 = 21;
+
+/// What drives an objective's scores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Criteria {
+    /// Only changed by commands and scripts.
+    Dummy,
+    /// Incremented when the player dies.
+    DeathCount,
+    /// Incremented when the player kills another player.
+    PlayerKillCount,
+    /// Mirrors the player's health (read-only).
+    Health,
+}
+
+impl Criteria {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "dummy" => Some(Self::Dummy),
+            "deathCount" => Some(Self::DeathCount),
+            "playerKillCount" => Some(Self::PlayerKillCount),
+            "health" => Some(Self::Health),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Dummy => "dummy",
+            Self::DeathCount => "deathCount",
+            Self::PlayerKillCount => "playerKillCount",
+            Self::Health => "health",
+        }
+    }
+
+    /// Read-only criteria can't be changed by commands or scripts.
+    pub fn is_read_only(self) -> bool {
+        self == Self::Health
+    }
+
+    /// Protocol render type: 0 = integer, 1 = hearts.
+    fn render_type(self) -> i32 {
+        if self == Self::Health { 1 } else { 0 }
+    }
+}
+
+/// A scoreboard objective.
+#[derive(Debug, Clone)]
+pub struct Objective {
+    pub name: String,
+    pub criteria: Criteria,
+    pub display_name: String,
+}
+
+/// A player team. Members are score holder names.
+#[derive(Debug, Clone)]
+pub struct Team {
+    pub name: String,
+    pub display_name: String,
+    /// Index into the 16 chat colors; None for reset.
+    pub color: Option<usize>,
+    pub prefix: String,
+    pub suffix: String,
+    pub friendly_fire: bool,
+    pub see_friendly_invisibles: bool,
+    pub name_tag_visibility: String,
+    pub death_message_visibility: String,
+    pub collision_rule: String,
+    pub members: Vec<String>,
+}
+
+impl Team {
+    fn new(name: &str, display_name: Option<&str>) -> Self {
+        Self {
+            name: name.to_string(),
+            display_name: display_name.unwrap_or(name).to_string(),
+            color: None,
+            prefix: String::new(),
+            suffix: String::new(),
+            friendly_fire: true,
+            see_friendly_invisibles: true,
+            name_tag_visibility: "always".into(),
+            death_message_visibility: "always".into(),
+            collision_rule: "always".into(),
+            members: Vec::new(),
+        }
+    }
+
+    pub fn color_name(&self) -> &'static str {
+        self.color.map(|c| TEAM_COLORS[c]).unwrap_or("reset")
+    }
+
+    fn info(&self) -> TeamInfo {
+        TeamInfo {
+            display_name: TextComponent::plain(&self.display_name),
+            friendly_flags: self.friendly_fire as u8 | (self.see_friendly_invisibles as u8) << 1,
+            name_tag_visibility: self.name_tag_visibility.clone(),
+            collision_rule: self.collision_rule.clone(),
+            color: self.color.map(|c| c as i32).unwrap_or(COLOR_RESET),
+            prefix: TextComponent::plain(&self.prefix),
+            suffix: TextComponent::plain(&self.suffix),
+        }
+    }
+
+    fn packet(&self, mode: u8, entities: Vec<String>) -> InternalPacket {
+        InternalPacket::UpdateTeams {
+            name: self.name.clone(),
+            mode,
+            info: matches!(mode, 0 | 2).then(|| self.info()),
+            entities,
+        }
+    }
+}
+
+/// Scoreboard objectives, scores, display slots, and teams. Persisted to
+/// `<world>/data/scoreboard.dat` in the vanilla layout.
+///
+/// Mutating methods return the packet that must be broadcast so every
+/// client's scoreboard stays in sync.
+pub struct Scoreboard {
+    /// Objectives in creation order.
+    objectives: Vec<Objective>,
+    /// objective name → (score holder → score)
+    scores: HashMap<String, HashMap<String, i32>>,
+    display_slots: [Option<String>; DISPLAY_SLOTS],
+    teams: Vec<Team>,
+    /// Set on every change; cleared when the scoreboard is queued for saving.
+    pub dirty: bool,
+}
+
+impl Scoreboard {
+    pub fn new() -> Self {
+        Self {
+            objectives: Vec::new(),
+            scores: HashMap::new(),
+            display_slots: Default::default(),
+            teams: Vec::new(),
+            dirty: false,
+        }
+    }
+
+    pub fn objectives(&self) -> &[Objective] {
+        &self.objectives
+    }
+
+    pub fn objective(&self, name: &str) -> Option<&Objective> {
+        self.objectives.iter().find(|o| o.name == name)
+    }
+
+    pub fn add_objective(&mut self, name: &str, criteria: Criteria, display_name: Option<&str>) -> Result<InternalPacket, String> {
+        if name.is_empty() || name.len() > 16 || !name.chars().all(|c| c.is_ascii_alphanumeric() || "_-.+".contains(c)) {
+            return Err(format!("Invalid objective name '{}'", name));
+        }
+        if self.objective(name).is_some() {
+            return Err(format!("An objective already exists by the name '{}'", name));
+        }
+        let objective = Objective {
+            name: name.to_string(),
+            criteria,
+            display_name: display_name.unwrap_or(name).to_string(),
+        };
+        let packet = objective_packet(&objective, 0);
+        self.objectives.push(objective);
+        self.dirty = true;
+        Ok(packet)
+    }
+
+    pub fn remove_objective(&mut self, name: &str) -> Result<InternalPacket, String> {
+        let idx = self
+            .objectives
+            .iter()
+            .position(|o| o.name == name)
+            .ok_or_else(|| format!("Unknown objective '{}'", name))?;
+        let objective = self.objectives.remove(idx);
+        self.scores.remove(name);
+        for slot in self.display_slots.iter_mut() {
+            if slot.as_deref() == Some(name) {
+                *slot = None;
+            }
+        }
+        self.dirty = true;
+        // Removing the objective also clears its scores and display slots client-side.
+        Ok(objective_packet(&objective, 1))
+    }
+
+    pub fn set_display_name(&mut self, name: &str, display_name: &str) -> Result<InternalPacket, String> {
+        let objective = self
+            .objectives
+            .iter_mut()
+            .find(|o| o.name == name)
+            .ok_or_else(|| format!("Unknown objective '{}'", name))?;
+        objective.display_name = display_name.to_string();
+        self.dirty = true;
+        Ok(objective_packet(objective, 2))
+    }
+
+    /// Show `objective` in a display slot, or clear the slot with None.
+    pub fn set_display(&mut self, slot: &str, objective: Option<&str>) -> Result<InternalPacket, String> {
+        let position = display_slot_id(slot).ok_or_else(|| format!("Unknown display slot '{}'", slot))?;
+        if let Some(name) = objective {
+            if self.objective(name).is_none() {
+                return Err(format!("Unknown objective '{}'", name));
+            }
+        }
+        self.display_slots[position] = objective.map(str::to_string);
+        self.dirty = true;
+        Ok(InternalPacket::DisplayObjective {
+            position: position as i32,
+            objective: objective.unwrap_or("").to_string(),
+        })
+    }
+
+    pub fn get_score(&self, holder: &str, objective: &str) -> Option<i32> {
+        self.scores.get(objective)?.get(holder).copied()
+    }
+
+    /// All scores held by `holder`, in objective order.
+    pub fn scores_of(&self, holder: &str) -> Vec<(&str, i32)> {
+        self.objectives
+            .iter()
+            .filter_map(|o| Some((o.name.as_str(), self.get_score(holder, &o.name)?)))
+            .collect()
+    }
+
+    /// Every holder with at least one score, sorted by name.
+    pub fn holders(&self) -> Vec<&str> {
+        let mut holders: Vec<&str> = self
+            .scores
+            .values()
+            .flat_map(|s| s.keys().map(String::as_str))
+            .collect();
+        holders.sort_unstable();
+        holders.dedup();
+        holders
+    }
+
+    /// Set a score from a command or script. Fails for read-only criteria.
+    pub fn set_score(&mut self, holder: &str, objective: &str, value: i32) -> Result<InternalPacket, String> {
+        let criteria = self
+            .objective(objective)
+            .ok_or_else(|| format!("Unknown objective '{}'", objective))?
+            .criteria;
+        if criteria.is_read_only() {
+            return Err(format!("Objective '{}' is read-only", objective));
+        }
+        Ok(self.write_score(holder, objective, value))
+    }
+
+    /// Add to a score (missing scores start at 0). Returns the new value.
+    pub fn add_score(&mut self, holder: &str, objective: &str, delta: i32) -> Result<(i32, InternalPacket), String> {
+        let value = self.get_score(holder, objective).unwrap_or(0).wrapping_add(delta);
+        let packet = self.set_score(holder, objective, value)?;
+        Ok((value, packet))
+    }
+
+    /// Reset `holder`'s score in one objective, or in all of them.
+    pub fn reset_score(&mut self, holder: &str, objective: Option<&str>) -> Result<InternalPacket, String> {
+        match objective {
+            Some(name) => {
+                if self.objective(name).is_none() {
+                    return Err(format!("Unknown objective '{}'", name));
+                }
+                if let Some(scores) = self.scores.get_mut(name) {
+                    scores.remove(holder);
+                }
+            }
+            None => {
+                for scores in self.scores.values_mut() {
+                    scores.remove(holder);
+                }
+            }
+        }
+        self.dirty = true;
+        Ok(InternalPacket::ResetScore {
+            entity_name: holder.to_string(),
+            objective: objective.map(str::to_string),
+        })
+    }
+
+    /// Bump every objective with `criteria` for `holder` by one.
+    pub fn increment_criteria(&mut self, holder: &str, criteria: Criteria) -> Vec<InternalPacket> {
+        let names: Vec<String> = self
+            .objectives
+            .iter()
+            .filter(|o| o.criteria == criteria)
+            .map(|o| o.name.clone())
+            .collect();
+        names
+            .iter()
+            .map(|name| {
+                let value = self.get_score(holder, name).unwrap_or(0).wrapping_add(1);
+                self.write_score(holder, name, value)
+            })
+            .collect()
+    }
+
+    /// Set every objective with `criteria` for `holder` to `value`, skipping
+    /// unchanged scores. Used for criteria mirrored from player state.
+    pub fn sync_criteria(&mut self, holder: &str, criteria: Criteria, value: i32) -> Vec<InternalPacket> {
+        let names: Vec<String> = self
+            .objectives
+            .iter()
+            .filter(|o| o.criteria == criteria)
+            .filter(|o| self.get_score(holder, &o.name) != Some(value))
+            .map(|o| o.name.clone())
+            .collect();
+        names
+            .iter()
+            .map(|name| self.write_score(holder, name, value))
+            .collect()
+    }
+
+    fn write_score(&mut self, holder: &str, objective: &str, value: i32) -> InternalPacket {
+        self.scores
+            .entry(objective.to_string())
+            .or_default()
+            .insert(holder.to_string(), value);
+        self.dirty = true;
+        InternalPacket::UpdateScore {
+            entity_name: holder.to_string(),
+            objective: objective.to_string(),
+            value,
+        }
+    }
+
+    pub fn teams(&self) -> &[Team] {
+        &self.teams
+    }
+
+    pub fn team(&self, name: &str) -> Option<&Team> {
+        self.teams.iter().find(|t| t.name == name)
+    }
+
+    /// The team `member` belongs to, if any.
+    pub fn team_of(&self, member: &str) -> Option<&Team> {
+        self.teams.iter().find(|t| t.members.iter().any(|m| m == member))
+    }
+
+    pub fn add_team(&mut self, name: &str, display_name: Option<&str>) -> Result<InternalPacket, String> {
+        if name.is_empty() || name.len() > 16 || !name.chars().all(|c| c.is_ascii_alphanumeric() || "_-.+".contains(c)) {
+            return Err(format!("Invalid team name '{}'", name));
+        }
+        if self.team(name).is_some() {
+            return Err(format!("A team already exists by the name '{}'", name));
+        }
+        let team = Team::new(name, display_name);
+        let packet = team.packet(0, Vec::new());
+        self.teams.push(team);
+        self.dirty = true;
+        Ok(packet)
+    }
+
+    pub fn remove_team(&mut self, name: &str) -> Result<InternalPacket, String> {
+        let idx = self
+            .teams
+            .iter()
+            .position(|t| t.name == name)
+            .ok_or_else(|| format!("Unknown team '{}'", name))?;
+        let team = self.teams.remove(idx);
+        self.dirty = true;
+        Ok(team.packet(1, Vec::new()))
+    }
+
+    /// Move `members` into `team`, removing them from any previous team first.
+    pub fn join_team(&mut self, team: &str, members: &[String]) -> Result<Vec<InternalPacket>, String> {
+        if self.team(team).is_none() {
+            return Err(format!("Unknown team '{}'", team));
+        }
+        let mut packets = Vec::new();
+        for old in self.teams.iter_mut().filter(|t| t.name != team) {
+            let leaving: Vec<String> = old.members.iter().filter(|m| members.contains(m)).cloned().collect();
+            if !leaving.is_empty() {
+                old.members.retain(|m| !members.contains(m));
+                packets.push(old.packet(4, leaving));
+            }
+        }
+        let target = self.teams.iter_mut().find(|t| t.name == team).unwrap();
+        let joining: Vec<String> = members.iter().filter(|m| !target.members.contains(m)).cloned().collect();
+        if !joining.is_empty() {
+            target.members.extend(joining.iter().cloned());
+            packets.push(target.packet(3, joining));
+        }
+        self.dirty = true;
+        Ok(packets)
+    }
+
+    /// Remove `members` from whatever teams they are on.
+    pub fn leave_team(&mut self, members: &[String]) -> Vec<InternalPacket> {
+        let mut packets = Vec::new();
+        for team in self.teams.iter_mut() {
+            let leaving: Vec<String> = team.members.iter().filter(|m| members.contains(m)).cloned().collect();
+            if !leaving.is_empty() {
+                team.members.retain(|m| !members.contains(m));
+                packets.push(team.packet(4, leaving));
+            }
+        }
+        if !packets.is_empty() {
+            self.dirty = true;
+        }
+        packets
+    }
+
+    /// Remove every member from `team`.
+    pub fn empty_team(&mut self, name: &str) -> Result<(usize, InternalPacket), String> {
+        let team = self
+            .teams
+            .iter_mut()
+            .find(|t| t.name == name)
+            .ok_or_else(|| format!("Unknown team '{}'", name))?;
+        let members = std::mem::take(&mut team.members);
+        self.dirty = true;
+        Ok((members.len(), team.packet(4, members)))
+    }
+
+    /// Change a team option using vanilla `/team modify` names.
+    pub fn modify_team(&mut self, name: &str, option: &str, value: &str) -> Result<InternalPacket, String> {
+        let team = self
+            .teams
+            .iter_mut()
+            .find(|t| t.name == name)
+            .ok_or_else(|| format!("Unknown team '{}'", name))?;
+        let parse_bool = |v: &str| match v {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(format!("Expected true or false, got '{}'", v)),
+        };
+        let one_of = |v: &str, options: &[&str]| {
+            if options.contains(&v) {
+                Ok(v.to_string())
+            } else {
+                Err(format!("Expected one of {}, got '{}'", options.join(", "), v))
+            }
+        };
+        match option {
+            "displayName" => team.display_name = value.to_string(),
+            "prefix" => team.prefix = value.to_string(),
+            "suffix" => team.suffix = value.to_string(),
+            "color" => {
+                team.color = match value {
+                    "reset" => None,
+                    _ => Some(
+                        TEAM_COLORS
+                            .iter()
+                            .position(|c| *c == value)
+                            .ok_or_else(|| format!("Unknown color '{}'", value))?,
+                    ),
+                }
+            }
+            "friendlyFire" => team.friendly_fire = parse_bool(value)?,
+            "seeFriendlyInvisibles" => team.see_friendly_invisibles = parse_bool(value)?,
+            "nametagVisibility" => team.name_tag_visibility = one_of(value, &VISIBILITY_OPTIONS)?,
+            "deathMessageVisibility" => team.death_message_visibility = one_of(value, &VISIBILITY_OPTIONS)?,
+            "collisionRule" => team.collision_rule = one_of(value, &COLLISION_OPTIONS)?,
+            _ => return Err(format!("Unknown team option '{}'", option)),
+        }
+        self.dirty = true;
+        Ok(team.packet(2, Vec::new()))
+    }
+
+    /// Full scoreboard state for a joining player.
+    pub fn sync_packets(&self) -> Vec<InternalPacket> {
+        let mut packets: Vec<InternalPacket> = self.objectives.iter().map(|o| objective_packet(o, 0)).collect();
+        for objective in &self.objectives {
+            if let Some(scores) = self.scores.get(&objective.name) {
+                for (holder, value) in scores {
+                    packets.push(InternalPacket::UpdateScore {
+                        entity_name: holder.clone(),
+                        objective: objective.name.clone(),
+                        value: *value,
+                    });
+                }
+            }
+        }
+        for (position, slot) in self.display_slots.iter().enumerate() {
+            if let Some(name) = slot {
+                packets.push(InternalPacket::DisplayObjective {
+                    position: position as i32,
+                    objective: name.clone(),
+                });
+            }
+        }
+        for team in &self.teams {
+            packets.push(team.packet(0, team.members.clone()));
+        }
+        packets
+    }
+
+    /// Serialize to gzip-compressed NBT (vanilla `scoreboard.dat` layout).
+    pub fn serialize(&self) -> Vec<u8> {
+        let objectives = self
+            .objectives
+            .iter()
+            .map(|o| {
+                nbt_compound! {
+                    "Name" => NbtValue::String(o.name.clone()),
+                    "CriteriaName" => NbtValue::String(o.criteria.name().into()),
+                    "DisplayName" => NbtValue::String(TextComponent::plain(&o.display_name).to_json()),
+                    "RenderType" => NbtValue::String(if o.criteria.render_type() == 1 { "hearts" } else { "integer" }.into())
+                }
+            })
+            .collect();
+        let mut player_scores = Vec::new();
+        for objective in &self.objectives {
+            if let Some(scores) = self.scores.get(&objective.name) {
+                let mut holders: Vec<_> = scores.iter().collect();
+                holders.sort_by(|a, b| a.0.cmp(b.0));
+                for (holder, value) in holders {
+                    player_scores.push(nbt_compound! {
+                        "Name" => NbtValue::String(holder.clone()),
+                        "Objective" => NbtValue::String(objective.name.clone()),
+                        "Score" => NbtValue::Int(*value),
+                        "Locked" => NbtValue::Byte(0)
+                    });
+                }
+            }
+        }
+        let display_slots = NbtValue::Compound(
+            self.display_slots
+                .iter()
+                .enumerate()
+                .filter_map(|(i, slot)| Some((display_slot_name(i), NbtValue::String(slot.clone()?))))
+                .collect(),
+        );
+
+        let teams = self
+            .teams
+            .iter()
+            .map(|t| {
+                nbt_compound! {
+                    "Name" => NbtValue::String(t.name.clone()),
+                    "DisplayName" => NbtValue::String(TextComponent::plain(&t.display_name).to_json()),
+                    "MemberNamePrefix" => NbtValue::String(TextComponent::plain(&t.prefix).to_json()),
+                    "MemberNameSuffix" => NbtValue::String(TextComponent::plain(&t.suffix).to_json()),
+                    "TeamColor" => NbtValue::String(t.color_name().into()),
+                    "AllowFriendlyFire" => NbtValue::Byte(t.friendly_fire as i8),
+                    "SeeFriendlyInvisibles" => NbtValue::Byte(t.see_friendly_invisibles as i8),
+                    "NameTagVisibility" => NbtValue::String(t.name_tag_visibility.clone()),
+                    "DeathMessageVisibility" => NbtValue::String(t.death_message_visibility.clone()),
+                    "CollisionRule" => NbtValue::String(t.collision_rule.clone()),
+                    "Players" => NbtValue::List(t.members.iter().cloned().map(NbtValue::String).collect())
+                }
+            })
+            .collect();
+
+        let nbt = nbt_compound! {
+            "DataVersion" => NbtValue::Int(3955),
+            "data" => nbt_compound! {
+                "Objectives" => NbtValue::List(objectives),
+                "PlayerScores" => NbtValue::List(player_scores),
+                "DisplaySlots" => display_slots,
+                "Teams" => NbtValue::List(teams)
+            }
+        };
+        let mut buf = BytesMut::new();
+        nbt.write_root_named("", &mut buf);
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        let _ = encoder.write_all(&buf);
+        encoder.finish().unwrap_or_default()
+    }
+
+    /// Load from gzip-compressed NBT. Unknown criteria are loaded as dummy.
+    pub fn deserialize(data: &[u8]) -> Option<Self> {
+        let (_, nbt) = NbtValue::read_root_named_from(GzDecoder::new(data), NbtLimits::FILE).ok()?;
+        let data = nbt.get("data")?;
+
+        let mut scoreboard = Self::new();
+        for entry in data.get("Objectives").and_then(|v| v.as_list()).unwrap_or(&[]) {
+            let Some(name) = entry.get("Name").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            let criteria = entry
+                .get("CriteriaName")
+                .and_then(|v| v.as_str())
+                .and_then(Criteria::parse)
+                .unwrap_or(Criteria::Dummy);
+            let display_name = entry
+                .get("DisplayName")
+                .and_then(|v| v.as_str())
+                .map(component_text)
+                .unwrap_or_else(|| name.to_string());
+            scoreboard.objectives.push(Objective {
+                name: name.to_string(),
+                criteria,
+                display_name,
+            });
+        }
+        for entry in data.get("PlayerScores").and_then(|v| v.as_list()).unwrap_or(&[]) {
+            let holder = entry.get("Name").and_then(|v| v.as_str());
+            let objective = entry.get("Objective").and_then(|v| v.as_str());
+            let score = entry.get("Score").and_then(|v| v.as_int());
+            if let (Some(holder), Some(objective), Some(score)) = (holder, objective, score) {
+                if scoreboard.objective(objective).is_some() {
+                    scoreboard
+                        .scores
+                        .entry(objective.to_string())
+                        .or_default()
+                        .insert(holder.to_string(), score);
+                }
+            }
+        }
+        for i in 0..DISPLAY_SLOTS {
+            let name = data
+                .get("DisplaySlots")
+                .and_then(|slots| slots.get(&display_slot_name(i)))
+                .and_then(|v| v.as_str());
+            if let Some(name) = name.filter(|n| scoreboard.objective(n).is_some()) {
+                scoreboard.display_slots[i] = Some(name.to_string());
+            }
+        }
+        for entry in data.get("Teams").and_then(|v| v.as_list()).unwrap_or(&[]) {
+            let Some(name) = entry.get("Name").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            let text = |key: &str| entry.get(key).and_then(|v| v.as_str()).map(component_text);
+            let flag = |key: &str| entry.get(key).and_then(|v| v.as_byte()).map(|b| b != 0).unwrap_or(true);
+            let option = |key: &str, options: &[&str]| {
+                entry
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .filter(|v| options.contains(v))
+                    .unwrap_or("always")
+                    .to_string()
+            };
+            let mut team = Team::new(name, None);
+            team.display_name = text("DisplayName").unwrap_or_else(|| name.to_string());
+            team.prefix = text("MemberNamePrefix").unwrap_or_default();
+            team.suffix = text("MemberNameSuffix").unwrap_or_default();
+            team.color = entry
+                .get("TeamColor")
+                .and_then(|v| v.as_str())
+                .and_then(|c| TEAM_COLORS.iter().position(|name| *name == c));
+            team.friendly_fire = flag("AllowFriendlyFire");
+            team.see_friendly_invisibles = flag("SeeFriendlyInvisibles");
+            team.name_tag_visibility = option("NameTagVisibility", &VISIBILITY_OPTIONS);
+            team.death_message_visibility = option("DeathMessageVisibility", &VISIBILITY_OPTIONS);
+            team.collision_rule = option("CollisionRule", &COLLISION_OPTIONS);
+            team.members = entry
+                .get("Players")
+                .and_then(|v| v.as_list())
+                .unwrap_or(&[])
+                .iter()
+                .filter_map(|p| p.as_str().map(str::to_string))
+                .collect();
+            scoreboard.teams.push(team);
+        }
+        Some(scoreboard)
+    }
+}
+
+/// Plain text of a stored JSON text component, or the raw string if it isn't JSON.
+fn component_text(json: &str) -> String {
+    serde_json::from_str::<TextComponent>(json)
+        .map(|c| c.text)
+        .unwrap_or_else(|_| json.to_string())
+}
+
+fn objective_packet(objective: &Objective, mode: u8) -> InternalPacket {
+    InternalPacket::UpdateObjectives {
+        name: objective.name.clone(),
+        mode,
+        display_name: TextComponent::plain(&objective.display_name),
+        render_type: objective.criteria.render_type(),
+    }
+}
+
+/// Protocol position for a display slot name.
+pub fn display_slot_id(slot: &str) -> Option<usize> {
+    match slot {
+        "list" => Some(0),
+        "sidebar" => Some(1),
+        "below_name" | "belowName" => Some(2),
+        _ => {
+            let color = slot.strip_prefix("sidebar.team.")?;
+            TEAM_COLORS.iter().position(|c| *c == color).map(|i| 3 + i)
+        }
+    }
+}
+
+fn display_slot_name(position: usize) -> String {
+    match position {
+        0 => "list".into(),
+        1 => "sidebar".into(),
+        2 => "below_name".into(),
+        _ => format!("sidebar.team.{}", TEAM_COLORS[position - 3]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scores_and_read_only_criteria() {
+        let mut sb = Scoreboard::new();
+        sb.add_objective("kills", Criteria::PlayerKillCount, Some("Kills")).unwrap();
+        sb.add_objective("hp", Criteria::Health, None).unwrap();
+        assert!(sb.add_objective("kills", Criteria::Dummy, None).is_err());
+
+        sb.set_score("Steve", "kills", 3).unwrap();
+        let (value, _) = sb.add_score("Steve", "kills", 2).unwrap();
+        assert_eq!(value, 5);
+        assert_eq!(sb.increment_criteria("Steve", Criteria::PlayerKillCount).len(), 1);
+        assert_eq!(sb.get_score("Steve", "kills"), Some(6));
+
+        assert!(sb.set_score("Steve", "hp", 1).is_err());
+        assert_eq!(sb.sync_criteria("Steve", Criteria::Health, 20).len(), 1);
+        assert!(sb.sync_criteria("Steve", Criteria::Health, 20).is_empty());
+
+        sb.reset_score("Steve", Some("kills")).unwrap();
+        assert_eq!(sb.get_score("Steve", "kills"), None);
+        assert_eq!(sb.get_score("Steve", "hp"), Some(20));
+    }
+
+    #[test]
+    fn test_display_slots() {
+        let mut sb = Scoreboard::new();
+        sb.add_objective("deaths", Criteria::DeathCount, None).unwrap();
+        assert!(sb.set_display("sidebar", Some("missing")).is_err());
+        assert!(sb.set_display("nowhere", Some("deaths")).is_err());
+        assert_eq!(display_slot_id("sidebar.team.white"), Some(18));
+
+        sb.set_display("sidebar", Some("deaths")).unwrap();
+        sb.remove_objective("deaths").unwrap();
+        assert!(sb.sync_packets().is_empty());
+    }
+
+    #[test]
+    fn test_team_membership() {
+        let mut sb = Scoreboard::new();
+        sb.add_team("red", None).unwrap();
+        sb.add_team("blue", Some("Blue Team")).unwrap();
+        let members = vec!["Steve".to_string(), "Alex".to_string()];
+        assert_eq!(sb.join_team("red", &members).unwrap().len(), 1);
+
+        // Switching teams removes from the old one first
+        let packets = sb.join_team("blue", &members[..1]).unwrap();
+        assert_eq!(packets.len(), 2);
+        assert_eq!(sb.team_of("Steve").unwrap().name, "blue");
+        assert_eq!(sb.team_of("Alex").unwrap().name, "red");
+
+        assert!(sb.modify_team("red", "color", "purple").is_err());
+        sb.modify_team("red", "color", "dark_red").unwrap();
+        sb.modify_team("red", "friendlyFire", "false").unwrap();
+        assert!(!sb.team("red").unwrap().friendly_fire);
+
+        assert_eq!(sb.leave_team(&members).len(), 2);
+        assert!(sb.team_of("Alex").is_none());
+    }
+
+    #[test]
+    fn test_serialize_roundtrip() {
+        let mut sb = Scoreboard::new();
+        sb.add_objective("deaths", Criteria::DeathCount, Some("Deaths")).unwrap();
+        sb.increment_criteria("Alex", Criteria::DeathCount);
+        sb.set_display("list", Some("deaths")).unwrap();
+        sb.add_team("red", None).unwrap();
+        sb.modify_team("red", "prefix", "[R] ").unwrap();
+        sb.modify_team("red", "collisionRule", "never").unwrap();
+        sb.join_team("red", &["Alex".to_string()]).unwrap();
+
+        let loaded = Scoreboard::deserialize(&sb.serialize()).unwrap();
+        let objective = loaded.objective("deaths").unwrap();
+        assert_eq!(objective.criteria, Criteria::DeathCount);
+        assert_eq!(objective.display_name, "Deaths");
+        assert_eq!(loaded.get_score("Alex", "deaths"), Some(1));
+        let team = loaded.team_of("Alex").unwrap();
+        assert_eq!(team.prefix, "[R] ");
+        assert_eq!(team.collision_rule, "never");
+        assert_eq!(loaded.sync_packets().len(), 4);
+        assert!(!loaded.dirty);
+    }
+}
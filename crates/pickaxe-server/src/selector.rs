@@ -0,0 +1,399 @@
+use crate::ecs::*;
+use hecs::World;
+use pickaxe_types::{GameMode, Vec3d};
+use rand::seq::SliceRandom;
+
+/// The `@` variable (or plain name) at the head of a target selector.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectorBase {
+    /// `@a` — all online players.
+    AllPlayers,
+    /// `@p` — the nearest player.
+    NearestPlayer,
+    /// `@r` — a random player.
+    RandomPlayer,
+    /// `@e` — all entities.
+    AllEntities,
+    /// `@s` — the entity executing the command.
+    Executor,
+    /// A bare player name.
+    Name(String),
+}
+
+/// Result ordering applied before `limit`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SelectorSort {
+    Arbitrary,
+    Nearest,
+    Furthest,
+    Random,
+}
+
+/// A parsed target selector such as `@e[type=zombie,distance=..10]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntitySelector {
+    pub base: SelectorBase,
+    /// Entity type filter (`player`, `zombie`, `item`, ...) and whether it is negated.
+    pub entity_type: Option<(String, bool)>,
+    /// Inclusive distance bounds from the origin.
+    pub distance: Option<(Option<f64>, Option<f64>)>,
+    /// Player name filter and whether it is negated.
+    pub name: Option<(String, bool)>,
+    /// Game mode filter and whether it is negated.
+    pub game_mode: Option<(GameMode, bool)>,
+    /// Origin overrides from `x=`, `y=`, `z=`.
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub z: Option<f64>,
+    pub limit: Option<usize>,
+    pub sort: Option<SelectorSort>,
+}
+
+impl EntitySelector {
+    fn new(base: SelectorBase) -> Self {
+        Self {
+            base,
+            entity_type: None,
+            distance: None,
+            name: None,
+            game_mode: None,
+            x: None,
+            y: None,
+            z: None,
+            limit: None,
+            sort: None,
+        }
+    }
+
+    /// Whether this selector can only ever match players.
+    pub fn players_only(&self) -> bool {
+        match self.base {
+            SelectorBase::AllEntities | SelectorBase::Executor => {
+                matches!(&self.entity_type, Some((t, false)) if t == "player")
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Returns true if `arg` looks like a selector variable rather than a plain word.
+pub fn is_selector(arg: &str) -> bool {
+    arg.starts_with('@')
+}
+
+/// Parse a target selector. Plain words are treated as player names.
+pub fn parse(input: &str) -> Result<EntitySelector, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("Empty selector".into());
+    }
+    if !is_selector(input) {
+        return Ok(EntitySelector::new(SelectorBase::Name(input.to_string())));
+    }
+
+    let (head, args) = match input.find('[') {
+        Some(i) => {
+            let rest = &input[i + 1..];
+            let body = rest
+                .strip_suffix(']')
+                .ok_or_else(|| format!("Unterminated selector arguments in '{}'", input))?;
+            (&input[..i], Some(body))
+        }
+        None => (input, None),
+    };
+
+    let base = match head {
+        "@a" => SelectorBase::AllPlayers,
+        "@p" => SelectorBase::NearestPlayer,
+        "@r" => SelectorBase::RandomPlayer,
+        "@e" => SelectorBase::AllEntities,
+        "@s" => SelectorBase::Executor,
+        _ => return Err(format!("Unknown selector type '{}'", head)),
+    };
+    let mut sel = EntitySelector::new(base);
+
+    for arg in args.unwrap_or("").split(',') {
+        let arg = arg.trim();
+        if arg.is_empty() {
+            continue;
+        }
+        let (key, value) = arg
+            .split_once('=')
+            .ok_or_else(|| format!("Expected key=value in selector, got '{}'", arg))?;
+        let (key, value) = (key.trim(), value.trim());
+        let (negated, bare) = match value.strip_prefix('!') {
+            Some(v) => (true, v.trim()),
+            None => (false, value),
+        };
+        match key {
+            "type" => {
+                let name = bare.strip_prefix("minecraft:").unwrap_or(bare);
+                if !is_known_type(name) {
+                    return Err(format!("Unknown entity type '{}'", bare));
+                }
+                sel.entity_type = Some((name.to_string(), negated));
+            }
+            "distance" => {
+                let (min, max) = parse_range(value)?;
+                if min.is_some_and(|m| m < 0.0) || max.is_some_and(|m| m < 0.0) {
+                    return Err("Distance cannot be negative".into());
+                }
+                sel.distance = Some((min, max));
+            }
+            "name" => sel.name = Some((bare.to_string(), negated)),
+            "gamemode" => {
+                let mode = match bare {
+                    "survival" => GameMode::Survival,
+                    "creative" => GameMode::Creative,
+                    "adventure" => GameMode::Adventure,
+                    "spectator" => GameMode::Spectator,
+                    _ => return Err(format!("Unknown game mode '{}'", bare)),
+                };
+                sel.game_mode = Some((mode, negated));
+            }
+            "x" | "y" | "z" => {
+                let v: f64 = value
+                    .parse()
+                    .map_err(|_| format!("Invalid {} coordinate '{}'", key, value))?;
+                match key {
+                    "x" => sel.x = Some(v),
+                    "y" => sel.y = Some(v),
+                    _ => sel.z = Some(v),
+                }
+            }
+            "limit" => {
+                let n: usize = value
+                    .parse()
+                    .map_err(|_| format!("Invalid limit '{}'", value))?;
+                if n == 0 {
+                    return Err("Limit must be at least 1".into());
+                }
+                sel.limit = Some(n);
+            }
+            "sort" => {
+                sel.sort = Some(match value {
+                    "arbitrary" => SelectorSort::Arbitrary,
+                    "nearest" => SelectorSort::Nearest,
+                    "furthest" => SelectorSort::Furthest,
+                    "random" => SelectorSort::Random,
+                    _ => return Err(format!("Unknown sort '{}'", value)),
+                });
+            }
+            _ => return Err(format!("Unknown selector option '{}'", key)),
+        }
+    }
+
+    if sel.entity_type.is_some() && !matches!(sel.base, SelectorBase::AllEntities | SelectorBase::Executor) {
+        return Err(format!("Option 'type' is not applicable to {}", head));
+    }
+
+    Ok(sel)
+}
+
+/// Parse a vanilla number range: `5`, `..5`, `5..`, or `2..5`.
+fn parse_range(value: &str) -> Result<(Option<f64>, Option<f64>), String> {
+    let num = |s: &str| -> Result<Option<f64>, String> {
+        if s.is_empty() {
+            Ok(None)
+        } else {
+            s.parse::<f64>()
+                .map(Some)
+                .map_err(|_| format!("Invalid range '{}'", value))
+        }
+    };
+    match value.split_once("..") {
+        Some((lo, hi)) => {
+            let (min, max) = (num(lo)?, num(hi)?);
+            if min.is_none() && max.is_none() {
+                return Err(format!("Invalid range '{}'", value));
+            }
+            if let (Some(a), Some(b)) = (min, max) {
+                if a > b {
+                    return Err(format!("Invalid range '{}': min exceeds max", value));
+                }
+            }
+            Ok((min, max))
+        }
+        None => {
+            let v = num(value)?;
+            Ok((v, v))
+        }
+    }
+}
+
+fn is_known_type(name: &str) -> bool {
+    matches!(name, "player" | "item" | "arrow" | "tnt" | "fishing_bobber")
+        || pickaxe_data::mob_name_to_type(name).is_some()
+}
+
+/// Vanilla entity type name for an ECS entity, if it is one selectors can see.
+pub fn entity_type_name(world: &World, entity: hecs::Entity) -> Option<&'static str> {
+    if world.get::<&Profile>(entity).is_ok() {
+        return Some("player");
+    }
+    if let Ok(mob) = world.get::<&MobEntity>(entity) {
+        return pickaxe_data::mob_type_name(mob.mob_type);
+    }
+    if world.get::<&ItemEntity>(entity).is_ok() {
+        return Some("item");
+    }
+    if world.get::<&ArrowEntity>(entity).is_ok() {
+        return Some("arrow");
+    }
+    if world.get::<&TntEntity>(entity).is_ok() {
+        return Some("tnt");
+    }
+    if world.get::<&FishingBobber>(entity).is_ok() {
+        return Some("fishing_bobber");
+    }
+    if world.get::<&ArmorStand>(entity).is_ok() {
+        return Some("armor_stand");
+    }
+    if let Ok(display) = world.get::<&DisplayEntity>(entity) {
+        return Some(display.content.type_name());
+    }
+    None
+}
+
+/// Resolve a selector to matching entities.
+///
+/// `executor` is the entity running the command (if any) and `origin` is the
+/// position distances and `@p` are measured from.
+pub fn resolve(
+    world: &World,
+    executor: Option<hecs::Entity>,
+    origin: Vec3d,
+    sel: &EntitySelector,
+) -> Vec<hecs::Entity> {
+    let origin = Vec3d::new(
+        sel.x.unwrap_or(origin.x),
+        sel.y.unwrap_or(origin.y),
+        sel.z.unwrap_or(origin.z),
+    );
+
+    let candidates: Vec<hecs::Entity> = match &sel.base {
+        SelectorBase::Name(name) => {
+            return world
+                .query::<&Profile>()
+                .iter()
+                .find(|(_, p)| p.0.name.eq_ignore_ascii_case(name))
+                .map(|(e, _)| vec![e])
+                .unwrap_or_default();
+        }
+        SelectorBase::Executor => executor.into_iter().collect(),
+        SelectorBase::AllPlayers | SelectorBase::NearestPlayer | SelectorBase::RandomPlayer => world
+            .query::<&Profile>()
+            .iter()
+            .map(|(e, _)| e)
+            .collect(),
+        SelectorBase::AllEntities => world
+            .query::<&Position>()
+            .iter()
+            .map(|(e, _)| e)
+            .filter(|&e| entity_type_name(world, e).is_some())
+            .collect(),
+    };
+
+    let mut matched: Vec<(hecs::Entity, f64)> = candidates
+        .into_iter()
+        .filter_map(|e| {
+            let pos = world.get::<&Position>(e).ok()?.0;
+            let (dx, dy, dz) = (pos.x - origin.x, pos.y - origin.y, pos.z - origin.z);
+            let dist = (dx * dx + dy * dy + dz * dz).sqrt();
+            if let Some((ref want, negated)) = sel.entity_type {
+                let is = entity_type_name(world, e) == Some(want.as_str());
+                if is == negated {
+                    return None;
+                }
+            }
+            if let Some((min, max)) = sel.distance {
+                if min.is_some_and(|m| dist < m) || max.is_some_and(|m| dist > m) {
+                    return None;
+                }
+            }
+            if let Some((ref want, negated)) = sel.name {
+                let is = world
+                    .get::<&Profile>(e)
+                    .map(|p| p.0.name.eq_ignore_ascii_case(want))
+                    .unwrap_or(false);
+                if is == negated {
+                    return None;
+                }
+            }
+            if let Some((mode, negated)) = sel.game_mode {
+                let is = world
+                    .get::<&PlayerGameMode>(e)
+                    .map(|gm| gm.0 == mode)
+                    .unwrap_or(false);
+                if is == negated {
+                    return None;
+                }
+            }
+            Some((e, dist))
+        })
+        .collect();
+
+    let (default_sort, default_limit) = match sel.base {
+        SelectorBase::NearestPlayer => (SelectorSort::Nearest, Some(1)),
+        SelectorBase::RandomPlayer => (SelectorSort::Random, Some(1)),
+        _ => (SelectorSort::Arbitrary, None),
+    };
+    match sel.sort.unwrap_or(default_sort) {
+        SelectorSort::Arbitrary => {}
+        SelectorSort::Nearest => matched.sort_by(|a, b| a.1.total_cmp(&b.1)),
+        SelectorSort::Furthest => matched.sort_by(|a, b| b.1.total_cmp(&a.1)),
+        SelectorSort::Random => matched.shuffle(&mut rand::thread_rng()),
+    }
+    if let Some(limit) = sel.limit.or(default_limit) {
+        matched.truncate(limit);
+    }
+
+    matched.into_iter().map(|(e, _)| e).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plain_name() {
+        let sel = parse("Steve").unwrap();
+        assert_eq!(sel.base, SelectorBase::Name("Steve".into()));
+        assert!(sel.players_only());
+    }
+
+    #[test]
+    fn test_parse_type_and_distance() {
+        let sel = parse("@e[type=minecraft:zombie,distance=..10]").unwrap();
+        assert_eq!(sel.base, SelectorBase::AllEntities);
+        assert_eq!(sel.entity_type, Some(("zombie".into(), false)));
+        assert_eq!(sel.distance, Some((None, Some(10.0))));
+        assert!(!sel.players_only());
+    }
+
+    #[test]
+    fn test_parse_ranges() {
+        assert_eq!(parse_range("5").unwrap(), (Some(5.0), Some(5.0)));
+        assert_eq!(parse_range("2..").unwrap(), (Some(2.0), None));
+        assert_eq!(parse_range("1.5..3").unwrap(), (Some(1.5), Some(3.0)));
+        assert!(parse_range("..").is_err());
+        assert!(parse_range("5..2").is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("@x").is_err());
+        assert!(parse("@e[type=dragon_thing]").is_err());
+        assert!(parse("@a[type=zombie]").is_err());
+        assert!(parse("@e[limit=0]").is_err());
+        assert!(parse("@e[distance=..5").is_err());
+    }
+
+    #[test]
+    fn test_parse_negation_and_sort() {
+        let sel = parse("@e[type=!player,sort=furthest,limit=3]").unwrap();
+        assert_eq!(sel.entity_type, Some(("player".into(), true)));
+        assert_eq!(sel.sort, Some(SelectorSort::Furthest));
+        assert_eq!(sel.limit, Some(3));
+    }
+}
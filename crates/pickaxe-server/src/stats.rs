@@ -0,0 +1,181 @@
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+use uuid::Uuid;
+
+/// Directory in the world holding `<uuid>.json` statistics files.
+pub const STATS_DIR: &str = "stats";
+
+/// Statistic categories, in `stat_type` registry order (their protocol IDs).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StatType {
+    Mined,
+    Crafted,
+    Used,
+    Broken,
+    PickedUp,
+    Dropped,
+    Killed,
+    KilledBy,
+    Custom,
+}
+
+const STAT_TYPES: [(StatType, &str); 9] = [
+    (StatType::Mined, "mined"),
+    (StatType::Crafted, "crafted"),
+    (StatType::Used, "used"),
+    (StatType::Broken, "broken"),
+    (StatType::PickedUp, "picked_up"),
+    (StatType::Dropped, "dropped"),
+    (StatType::Killed, "killed"),
+    (StatType::KilledBy, "killed_by"),
+    (StatType::Custom, "custom"),
+];
+
+impl StatType {
+    pub fn name(self) -> &'static str {
+        STAT_TYPES[self as usize].1
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        STAT_TYPES.iter().find(|(_, n)| *n == name).map(|(t, _)| *t)
+    }
+
+    /// Protocol ID of a statistic in this category, if the server knows it.
+    fn stat_id(self, key: &str) -> Option<i32> {
+        match self {
+            StatType::Mined => pickaxe_data::block_name_to_id(key),
+            StatType::Killed | StatType::KilledBy => pickaxe_data::entity_name_to_type(key),
+            StatType::Custom => CUSTOM_STATS.iter().position(|s| *s == key).map(|i| i as i32),
+            _ => pickaxe_data::item_name_to_id(key),
+        }
+    }
+}
+
+/// The `custom_stat` registry for 1.21.1, in ID order.
+const CUSTOM_STATS: &[&str] = &[
+    "leave_game", "play_time", "total_world_time", "time_since_death", "time_since_rest",
+    "sneak_time", "walk_one_cm", "crouch_one_cm", "sprint_one_cm", "walk_on_water_one_cm",
+    "fall_one_cm", "climb_one_cm", "fly_one_cm", "walk_under_water_one_cm", "minecart_one_cm",
+    "boat_one_cm", "pig_one_cm", "horse_one_cm", "aviate_one_cm", "swim_one_cm",
+    "strider_one_cm", "jump", "drop", "damage_dealt", "damage_dealt_absorbed",
+    "damage_dealt_resisted", "damage_taken", "damage_blocked_by_shield", "damage_absorbed",
+    "damage_resisted", "deaths", "mob_kills", "animals_bred", "player_kills", "fish_caught",
+    "talked_to_villager", "traded_with_villager", "eat_cake_slice", "fill_cauldron",
+    "use_cauldron", "clean_armor", "clean_banner", "clean_shulker_box",
+    "interact_with_brewingstand", "interact_with_beacon", "inspect_dropper", "inspect_hopper",
+    "inspect_dispenser", "play_noteblock", "tune_noteblock", "pot_flower",
+    "trigger_trapped_chest", "open_enderchest", "enchant_item", "play_record",
+    "interact_with_furnace", "interact_with_crafting_table", "open_chest", "sleep_in_bed",
+    "open_shulker_box", "open_barrel", "interact_with_blast_furnace", "interact_with_smoker",
+    "interact_with_lectern", "interact_with_campfire", "interact_with_cartography_table",
+    "interact_with_loom", "interact_with_stonecutter", "bell_ring", "raid_trigger", "raid_win",
+    "interact_with_anvil", "interact_with_grindstone", "target_hit",
+    "interact_with_smithing_table",
+];
+
+/// A player's statistics, keyed by category and unprefixed registry name.
+/// Entries the server doesn't track are kept so vanilla's are not lost.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Statistics {
+    values: BTreeMap<(StatType, String), i32>,
+}
+
+impl Statistics {
+    pub fn get(&self, stat_type: StatType, key: &str) -> i32 {
+        self.values.get(&(stat_type, key.to_string())).copied().unwrap_or(0)
+    }
+
+    pub fn add(&mut self, stat_type: StatType, key: &str, amount: i32) {
+        let value = self.values.entry((stat_type, key.to_string())).or_insert(0);
+        *value = value.saturating_add(amount);
+    }
+
+    pub fn set(&mut self, stat_type: StatType, key: &str, value: i32) {
+        self.values.insert((stat_type, key.to_string()), value);
+    }
+
+    /// Add to a `minecraft:custom` statistic, like `play_time` or `jump`.
+    pub fn custom(&mut self, key: &str, amount: i32) {
+        self.add(StatType::Custom, key, amount);
+    }
+
+    /// Entries for the Award Statistics packet: (category, statistic, value).
+    pub fn packet_entries(&self) -> Vec<(i32, i32, i32)> {
+        self.values
+            .iter()
+            .filter_map(|((stat_type, key), value)| Some((*stat_type as i32, stat_type.stat_id(key)?, *value)))
+            .collect()
+    }
+
+    /// Vanilla's `stats/<uuid>.json` layout.
+    pub fn to_json(&self) -> Vec<u8> {
+        let mut stats = Map::new();
+        for ((stat_type, key), value) in &self.values {
+            let category = stats
+                .entry(format!("minecraft:{}", stat_type.name()))
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Value::Object(category) = category {
+                category.insert(format!("minecraft:{}", key), Value::from(*value));
+            }
+        }
+        let mut root = Map::new();
+        root.insert("stats".into(), Value::Object(stats));
+        root.insert("DataVersion".into(), Value::from(3955));
+        serde_json::to_vec(&Value::Object(root)).unwrap_or_default()
+    }
+
+    pub fn from_json(data: &[u8]) -> Result<Self, String> {
+        let root: Value = serde_json::from_slice(data).map_err(|e| e.to_string())?;
+        let stats = root.get("stats").and_then(Value::as_object).ok_or("missing \"stats\"")?;
+        let mut values = BTreeMap::new();
+        for (category, entries) in stats {
+            let name = category.strip_prefix("minecraft:").unwrap_or(category);
+            let (Some(stat_type), Some(entries)) = (StatType::from_name(name), entries.as_object()) else {
+                continue;
+            };
+            for (key, value) in entries {
+                if let Some(value) = value.as_i64() {
+                    let key = key.strip_prefix("minecraft:").unwrap_or(key);
+                    values.insert((stat_type, key.to_string()), value.clamp(0, i32::MAX as i64) as i32);
+                }
+            }
+        }
+        Ok(Self { values })
+    }
+
+    /// Load a player's statistics from the world, empty if they have none.
+    pub fn load(world_dir: &Path, uuid: &Uuid) -> Self {
+        let path = world_dir.join(STATS_DIR).join(format!("{}.json", uuid));
+        let Ok(data) = std::fs::read(&path) else {
+            return Self::default();
+        };
+        Self::from_json(&data).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring unreadable {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_json_round_trip() {
+        let mut stats = Statistics::default();
+        stats.add(StatType::Mined, "stone", 3);
+        stats.add(StatType::Killed, "zombie", 1);
+        stats.custom("play_time", 200);
+        stats.custom("play_time", 20);
+
+        let json: Value = serde_json::from_slice(&stats.to_json()).unwrap();
+        assert_eq!(json["stats"]["minecraft:mined"]["minecraft:stone"], 3);
+        assert_eq!(json["stats"]["minecraft:custom"]["minecraft:play_time"], 220);
+        assert_eq!(Statistics::from_json(&stats.to_json()).unwrap(), stats);
+
+        let entries = stats.packet_entries();
+        assert!(entries.contains(&(8, 1, 220)));
+        assert!(entries.contains(&(6, pickaxe_data::entity_name_to_type("zombie").unwrap(), 1)));
+    }
+}
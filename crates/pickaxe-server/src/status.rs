@@ -0,0 +1,81 @@
+use base64::Engine;
+use pickaxe_types::TextComponent;
+use serde_json::json;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Size the client requires of a server icon.
+const ICON_SIZE: u32 = 64;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// What the server list shows besides the version and player count.
+#[derive(Debug, Clone, Default)]
+pub struct ServerListInfo {
+    /// MOTD with placeholders resolved; `&` color codes are translated when
+    /// the response is built.
+    pub motd: String,
+    /// Players listed when hovering over the player count.
+    pub sample: Vec<(String, Uuid)>,
+    /// `server-icon.png` as a data URI.
+    pub favicon: Option<String>,
+}
+
+/// Loaded at startup; the MOTD and sample are refreshed by the tick loop.
+pub type SharedServerList = Arc<Mutex<ServerListInfo>>;
+
+/// Read a server icon into a data URI. None, with a warning, if it isn't a
+/// 64x64 PNG; None silently if there's no file.
+pub fn load_favicon(path: &Path) -> Option<String> {
+    let png = std::fs::read(path).ok()?;
+    // The IHDR chunk follows the signature: length, type, width, height
+    let dimension = |offset: usize| png.get(offset..offset + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+    if !png.starts_with(PNG_SIGNATURE) || dimension(16) != Some(ICON_SIZE) || dimension(20) != Some(ICON_SIZE) {
+        tracing::warn!("{} must be a {}x{} PNG image", path.display(), ICON_SIZE, ICON_SIZE);
+        return None;
+    }
+    Some(format!(
+        "data:image/png;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(&png)
+    ))
+}
+
+/// The Status Response JSON.
+pub fn response_json(info: &ServerListInfo, version_name: &str, protocol: i32, online: usize, max: u32) -> String {
+    let sample: Vec<_> = info
+        .sample
+        .iter()
+        .map(|(name, uuid)| json!({ "name": name, "id": uuid.to_string() }))
+        .collect();
+    let description = serde_json::to_value(TextComponent::from_legacy(&info.motd, '&')).unwrap_or_default();
+    let mut response = json!({
+        "version": { "name": version_name, "protocol": protocol },
+        "players": { "max": max, "online": online, "sample": sample },
+        "description": description,
+    });
+    if let Some(favicon) = &info.favicon {
+        response["favicon"] = json!(favicon);
+    }
+    response.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_json() {
+        let info = ServerListInfo {
+            motd: "&6Pickaxe\n&7Line two".into(),
+            sample: vec![("Notch".into(), Uuid::nil())],
+            favicon: Some("data:image/png;base64,AAAA".into()),
+        };
+        let response: serde_json::Value = serde_json::from_str(&response_json(&info, "Pickaxe 1.21.1", 767, 1, 20)).unwrap();
+        assert_eq!(response["version"]["protocol"], 767);
+        assert_eq!(response["players"]["sample"][0]["name"], "Notch");
+        assert_eq!(response["description"]["extra"][0]["color"], "gold");
+        assert_eq!(response["description"]["extra"][0]["text"], "Pickaxe\n");
+        assert_eq!(response["favicon"], "data:image/png;base64,AAAA");
+    }
+}
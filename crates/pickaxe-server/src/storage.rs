@@ -0,0 +1,136 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Directory under the world's `data` directory holding one file per namespace.
+pub const STORAGE_DIR: &str = "mods";
+
+/// Key-value data persisted for Lua mods (`pickaxe.storage`), one JSON file
+/// per namespace at `data/mods/<namespace>.json`.
+#[derive(Default)]
+pub struct ModStorage {
+    namespaces: BTreeMap<String, Namespace>,
+}
+
+#[derive(Default)]
+struct Namespace {
+    values: BTreeMap<String, Value>,
+    /// Changed since the last save.
+    dirty: bool,
+}
+
+/// Shared between the Lua API and the tick loop, which queues saves.
+pub type SharedModStorage = Arc<Mutex<ModStorage>>;
+
+/// Whether a namespace is acceptable as a file name (lowercase letters,
+/// digits, `_`, `-` and `.`).
+pub fn valid_namespace(namespace: &str) -> bool {
+    !namespace.is_empty()
+        && namespace.len() <= 64
+        && !namespace.starts_with('.')
+        && namespace.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-' | '.'))
+}
+
+impl ModStorage {
+    /// Load every namespace file in `dir`. Unreadable files are skipped.
+    pub fn load(dir: &Path) -> Self {
+        let mut storage = Self::default();
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return storage;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(namespace) = path.file_name().and_then(|n| n.to_str()).and_then(|n| n.strip_suffix(".json")) else {
+                continue;
+            };
+            if !valid_namespace(namespace) {
+                continue;
+            }
+            match std::fs::read(&path).map_err(|e| e.to_string()).and_then(|d| Self::deserialize(&d)) {
+                Ok(values) => {
+                    storage.namespaces.insert(namespace.to_string(), Namespace { values, dirty: false });
+                }
+                Err(e) => tracing::error!("Failed to load mod storage {:?}: {}", path, e),
+            }
+        }
+        storage
+    }
+
+    pub fn get(&self, namespace: &str, key: &str) -> Option<&Value> {
+        self.namespaces.get(namespace)?.values.get(key)
+    }
+
+    pub fn set(&mut self, namespace: &str, key: &str, value: Value) {
+        let ns = self.namespaces.entry(namespace.to_string()).or_default();
+        ns.values.insert(key.to_string(), value);
+        ns.dirty = true;
+    }
+
+    /// Returns whether the key existed.
+    pub fn delete(&mut self, namespace: &str, key: &str) -> bool {
+        let Some(ns) = self.namespaces.get_mut(namespace) else {
+            return false;
+        };
+        let removed = ns.values.remove(key).is_some();
+        ns.dirty |= removed;
+        removed
+    }
+
+    pub fn keys(&self, namespace: &str) -> Vec<String> {
+        self.namespaces.get(namespace).map(|ns| ns.values.keys().cloned().collect()).unwrap_or_default()
+    }
+
+    /// Serialize every changed namespace as `(file name, contents)` and mark
+    /// it clean.
+    pub fn take_dirty(&mut self) -> Vec<(String, Vec<u8>)> {
+        self.namespaces
+            .iter_mut()
+            .filter(|(_, ns)| ns.dirty)
+            .map(|(name, ns)| {
+                ns.dirty = false;
+                let data = serde_json::to_vec_pretty(&ns.values).unwrap_or_default();
+                (format!("{}.json", name), data)
+            })
+            .collect()
+    }
+
+    fn deserialize(data: &[u8]) -> Result<BTreeMap<String, Value>, String> {
+        serde_json::from_slice(data).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_set_delete_and_dirty() {
+        let mut storage = ModStorage::default();
+        storage.set("economy", "balance.Alice", json!(150));
+        storage.set("economy", "config", json!({"currency": "emerald"}));
+        assert_eq!(storage.get("economy", "balance.Alice"), Some(&json!(150)));
+        assert_eq!(storage.keys("economy"), vec!["balance.Alice", "config"]);
+
+        let dirty = storage.take_dirty();
+        assert_eq!(dirty.len(), 1);
+        assert_eq!(dirty[0].0, "economy.json");
+        let loaded = ModStorage::deserialize(&dirty[0].1).unwrap();
+        assert_eq!(loaded.get("config"), Some(&json!({"currency": "emerald"})));
+        assert!(storage.take_dirty().is_empty());
+
+        assert!(!storage.delete("economy", "missing"));
+        assert!(storage.take_dirty().is_empty());
+        assert!(storage.delete("economy", "config"));
+        assert_eq!(storage.take_dirty().len(), 1);
+    }
+
+    #[test]
+    fn test_valid_namespace() {
+        assert!(valid_namespace("homes_plus-2"));
+        assert!(!valid_namespace(""));
+        assert!(!valid_namespace("../level"));
+        assert!(!valid_namespace("Homes"));
+    }
+}
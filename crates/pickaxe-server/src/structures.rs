@@ -0,0 +1,84 @@
+use bytes::BytesMut;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use pickaxe_nbt::{NbtLimits, NbtValue};
+use pickaxe_world::StructureTemplate;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Split a template ID like `village/plains/town_centers/plains_fountain_01`
+/// into namespace and path, defaulting to `minecraft`. Paths are lowercase
+/// letters, digits and `_-./`, without `..`.
+pub fn parse_id(id: &str) -> Option<(&str, &str)> {
+    let (namespace, path) = id.split_once(':').unwrap_or(("minecraft", id));
+    let valid = |s: &str, extra: &[u8]| {
+        !s.is_empty() && s.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b"_-.".contains(&b) || extra.contains(&b))
+    };
+    let safe = valid(namespace, b"") && valid(path, b"/") && path.split('/').all(|p| !p.is_empty() && p != "." && p != "..");
+    safe.then_some((namespace, path))
+}
+
+/// Where `/structure save` writes a template, as vanilla's structure blocks do.
+fn generated_path(world_dir: &Path, namespace: &str, path: &str) -> PathBuf {
+    world_dir.join("generated").join(namespace).join("structures").join(format!("{}.nbt", path))
+}
+
+/// Read a template: the world's saved ones first, then the data directory's.
+/// Also returns how many of its block states were unknown and became air.
+pub fn load(world_dir: &Path, data_dir: &str, id: &str) -> Result<(StructureTemplate, usize), String> {
+    let (namespace, path) = parse_id(id).ok_or_else(|| format!("Invalid template ID {}", id))?;
+    let file = [
+        generated_path(world_dir, namespace, path),
+        Path::new(data_dir).join(namespace).join("structure").join(format!("{}.nbt", path)),
+    ]
+    .into_iter()
+    .find_map(|p| File::open(p).ok())
+    .ok_or_else(|| format!("Template {}:{} not found", namespace, path))?;
+    let (_, nbt) = NbtValue::read_root_named_from(GzDecoder::new(file), NbtLimits::FILE)
+        .map_err(|e| format!("Can't read template {}: {}", id, e))?;
+    StructureTemplate::from_nbt(&nbt).map_err(|e| format!("Can't read template {}: {}", id, e))
+}
+
+/// Write a template to `<world>/generated/<namespace>/structures/`.
+pub fn save(world_dir: &Path, id: &str, template: &StructureTemplate) -> std::io::Result<PathBuf> {
+    let (namespace, path) = parse_id(id)
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("Invalid template ID {}", id)))?;
+    let path = generated_path(world_dir, namespace, path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut buf = BytesMut::new();
+    template.to_nbt().write_root_named("", &mut buf);
+    let tmp = path.with_extension("nbt.tmp");
+    let mut encoder = GzEncoder::new(File::create(&tmp)?, Compression::default());
+    encoder.write_all(&buf)?;
+    encoder.finish()?.sync_all()?;
+    fs::rename(&tmp, &path)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pickaxe_world::StructureBlock;
+
+    #[test]
+    fn test_save_and_load() {
+        let world = std::env::temp_dir().join(format!("pickaxe-structure-test-{}", std::process::id()));
+        let template = StructureTemplate {
+            size: [1, 1, 1],
+            blocks: vec![StructureBlock { pos: [0, 0, 0], state: pickaxe_data::block_name_to_default_state("stone").unwrap(), nbt: None }],
+            entities: Vec::new(),
+        };
+
+        assert_eq!(parse_id("houses/small"), Some(("minecraft", "houses/small")));
+        assert_eq!(parse_id("pickaxe:../up"), None);
+        save(&world, "pickaxe:houses/small", &template).unwrap();
+        assert!(world.join("generated/pickaxe/structures/houses/small.nbt").exists());
+        assert_eq!(load(&world, "missing", "pickaxe:houses/small").unwrap(), (template, 0));
+        assert!(load(&world, "missing", "houses/small").is_err());
+        let _ = fs::remove_dir_all(&world);
+    }
+}
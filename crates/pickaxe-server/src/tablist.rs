@@ -0,0 +1,22 @@
+use pickaxe_types::TextComponent;
+
+/// Render a tab list header or footer template (see `TabListConfig`).
+pub fn render(template: &str, online: usize, max: u32, tps: f64) -> TextComponent {
+    let text = template
+        .replace("{online}", &online.to_string())
+        .replace("{max}", &max.to_string())
+        .replace("{tps}", &format!("{:.1}", tps));
+    TextComponent::from_legacy(&text, '&')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let text = render("&7{online}/{max} online &8| TPS: {tps}", 3, 20, 19.96);
+        assert_eq!(text.plain_text(), "3/20 online | TPS: 20.0");
+        assert_eq!(text.extra[0].color.as_deref(), Some("gray"));
+    }
+}
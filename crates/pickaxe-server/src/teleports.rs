@@ -0,0 +1,172 @@
+use bytes::BytesMut;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use pickaxe_nbt::{nbt_compound, nbt_list, NbtLimits, NbtValue};
+use pickaxe_types::Vec3d;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// Which of the built-in /spawn, /home and /warp commands are enabled
+/// (`[teleports]` in server.toml).
+static SPAWN_ENABLED: AtomicBool = AtomicBool::new(false);
+static HOMES_ENABLED: AtomicBool = AtomicBool::new(false);
+static WARPS_ENABLED: AtomicBool = AtomicBool::new(false);
+static MAX_HOMES: AtomicU32 = AtomicU32::new(0);
+
+pub const HOME_COMMANDS: &[&str] = &["sethome", "home", "delhome", "homes"];
+pub const WARP_COMMANDS: &[&str] = &["warp", "setwarp", "delwarp"];
+
+/// Home name used when /sethome and /home are given none.
+pub const DEFAULT_HOME: &str = "home";
+
+pub fn configure(config: &crate::config::TeleportsConfig) {
+    SPAWN_ENABLED.store(config.spawn, Ordering::Relaxed);
+    HOMES_ENABLED.store(config.homes, Ordering::Relaxed);
+    WARPS_ENABLED.store(config.warps, Ordering::Relaxed);
+    MAX_HOMES.store(config.max_homes, Ordering::Relaxed);
+}
+
+/// Whether a /spawn, home or warp command is enabled. Other names are always enabled.
+pub fn command_enabled(name: &str) -> bool {
+    if name == "spawn" {
+        SPAWN_ENABLED.load(Ordering::Relaxed)
+    } else if HOME_COMMANDS.contains(&name) {
+        HOMES_ENABLED.load(Ordering::Relaxed)
+    } else if WARP_COMMANDS.contains(&name) {
+        WARPS_ENABLED.load(Ordering::Relaxed)
+    } else {
+        true
+    }
+}
+
+pub fn max_homes() -> usize {
+    MAX_HOMES.load(Ordering::Relaxed) as usize
+}
+
+/// A saved teleport destination.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Location {
+    pub position: Vec3d,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl Location {
+    pub fn to_nbt(&self) -> NbtValue {
+        nbt_compound! {
+            "Pos" => nbt_list![
+                NbtValue::Double(self.position.x),
+                NbtValue::Double(self.position.y),
+                NbtValue::Double(self.position.z)
+            ],
+            "Rotation" => nbt_list![NbtValue::Float(self.yaw), NbtValue::Float(self.pitch)]
+        }
+    }
+
+    pub fn from_nbt(nbt: &NbtValue) -> Option<Self> {
+        let pos = nbt.get("Pos")?.as_list()?;
+        let rot = nbt.get("Rotation").and_then(|v| v.as_list()).unwrap_or(&[]);
+        Some(Self {
+            position: Vec3d::new(pos.first()?.as_double()?, pos.get(1)?.as_double()?, pos.get(2)?.as_double()?),
+            yaw: rot.first().and_then(|v| v.as_float()).unwrap_or(0.0),
+            pitch: rot.get(1).and_then(|v| v.as_float()).unwrap_or(0.0),
+        })
+    }
+}
+
+/// Named locations as an NBT compound (player homes and warps).
+pub fn locations_to_nbt(locations: &BTreeMap<String, Location>) -> NbtValue {
+    NbtValue::Compound(locations.iter().map(|(name, loc)| (name.clone(), loc.to_nbt())).collect())
+}
+
+pub fn locations_from_nbt(nbt: &NbtValue) -> BTreeMap<String, Location> {
+    let NbtValue::Compound(entries) = nbt else {
+        return BTreeMap::new();
+    };
+    entries
+        .iter()
+        .filter_map(|(name, value)| Some((name.clone(), Location::from_nbt(value)?)))
+        .collect()
+}
+
+/// Whether a home or warp name is acceptable (letters, digits, `_` and `-`).
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty() && name.len() <= 32 && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Admin-defined warps, stored in `data/warps.dat` in the world directory.
+#[derive(Default)]
+pub struct Warps {
+    pub warps: BTreeMap<String, Location>,
+    /// Changed since the last save.
+    pub dirty: bool,
+}
+
+impl Warps {
+    pub fn set(&mut self, name: &str, location: Location) {
+        self.warps.insert(name.to_lowercase(), location);
+        self.dirty = true;
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let removed = self.warps.remove(&name.to_lowercase()).is_some();
+        self.dirty |= removed;
+        removed
+    }
+
+    pub fn get(&self, name: &str) -> Option<Location> {
+        self.warps.get(&name.to_lowercase()).copied()
+    }
+
+    /// Gzip-compressed NBT, in the same layout as other world data files.
+    pub fn serialize(&self) -> Vec<u8> {
+        let nbt = nbt_compound! {
+            "DataVersion" => NbtValue::Int(3955),
+            "data" => nbt_compound! {
+                "Warps" => locations_to_nbt(&self.warps)
+            }
+        };
+        let mut buf = BytesMut::new();
+        nbt.write_root_named("", &mut buf);
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        let _ = encoder.write_all(&buf);
+        encoder.finish().unwrap_or_default()
+    }
+
+    pub fn deserialize(data: &[u8]) -> Option<Self> {
+        let (_, nbt) = NbtValue::read_root_named_from(GzDecoder::new(data), NbtLimits::FILE).ok()?;
+        let warps = locations_from_nbt(nbt.get("data")?.get("Warps")?);
+        Some(Self { warps, dirty: false })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warps_roundtrip() {
+        let mut warps = Warps::default();
+        let location = Location { position: Vec3d::new(10.5, -60.0, -3.25), yaw: 90.0, pitch: -10.0 };
+        warps.set("Market", location);
+        assert!(warps.dirty);
+        assert_eq!(warps.get("market"), Some(location));
+
+        let loaded = Warps::deserialize(&warps.serialize()).unwrap();
+        assert_eq!(loaded.get("MARKET"), Some(location));
+        assert!(!loaded.dirty);
+
+        assert!(warps.remove("market"));
+        assert!(!warps.remove("market"));
+    }
+
+    #[test]
+    fn test_valid_name() {
+        assert!(valid_name("home_2"));
+        assert!(!valid_name(""));
+        assert!(!valid_name("a b"));
+        assert!(!valid_name("x".repeat(33).as_str()));
+    }
+}
@@ -1,20 +1,53 @@
-use crate::config::ServerConfig;
+use crate::advancements::{AdvancementProgress, AdvancementTree, Trigger};
+use crate::afk::{Activity, AfkChange};
+use crate::audit::{self, AuditAction, AuditLog, AuditQuery};
+use crate::block_behavior::{self, BlockBehaviors};
+use crate::bridge::{BlockHandler, BlockResponse, MobAiHandler};
+use crate::chunk_sender::{ChunkSender, View};
+use crate::command_args::{self, ArgType};
+use crate::datapacks::FunctionLibrary;
+use crate::config::{AfkConfig, BackupConfig, KeepAliveConfig, MessagingConfig, ServerConfig, WorldGenerator};
+use crate::gamerules::GameRules;
 use crate::ecs::*;
+use crate::execute::{self, Step};
+use crate::overload::{LoadLevel, LoadMonitor};
+use crate::playerdata::PlayerDataStore;
+use crate::scoreboard::{Criteria, Scoreboard};
+use crate::selector;
+use crate::stats::{StatType, Statistics};
+use crate::effects::{ParticleEffect, ParticleEmitters, Point};
+use crate::http::SharedHttpClient;
+use crate::storage::SharedModStorage;
+use crate::teleports::{self, Location, Warps};
+use crate::tick_rate::TickRate;
+use crate::tpa::{self, TpaRequests};
+use crate::tracking::{ChunkIndex, TrackedKind};
 use bytes::BytesMut;
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
 use hecs::World;
-use pickaxe_nbt::{nbt_compound, nbt_list, NbtValue};
-use pickaxe_protocol_core::{player_info_actions, CommandNode, InternalPacket, PlayerInfoEntry};
-use pickaxe_protocol_v1_21::{build_item_metadata, build_sleeping_metadata, build_tnt_metadata, build_wake_metadata, V1_21Adapter};
-use pickaxe_region::RegionStorage;
-use pickaxe_scripting::ScriptRuntime;
-use pickaxe_types::{BlockPos, GameMode, GameProfile, ItemStack, TextComponent, Vec3d};
-use pickaxe_world::{generate_flat_chunk_at, Chunk};
+use pickaxe_data::registries::Registries;
+use pickaxe_nbt::{nbt_compound, nbt_list, NbtLimits, NbtValue};
+use pickaxe_protocol_core::{player_info_actions, ChatSession, CommandNode, InternalPacket, PlayerInfoEntry};
+use pickaxe_protocol_v1_21::{
+    build_block_display_metadata, build_custom_name_metadata, build_display_metadata, build_entity_flags_metadata,
+    build_item_display_metadata, build_item_metadata, build_sleeping_metadata, build_text_display_metadata,
+    build_tnt_metadata, build_wake_metadata, V1_21Adapter,
+};
+use pickaxe_region::{write_atomic, ChunkCompression, ChunkJournal, JournalEntry, RegionStorage};
+use pickaxe_scripting::{sandbox, ScriptRuntime};
+use pickaxe_types::{
+    AttributeModifier, AttributeOperation, BannerLayer, BlockPos, ClickEvent, EquipmentSlotGroup, GameMode, GameProfile, ItemStack,
+    PotionContents, PotionContentsEffect, TextComponent, Vec3d,
+};
+use pickaxe_world::{
+    generate_flat_chunk_at, poi_type, Chunk, ChunkPoi, Mirror, Placement, PoiRecord, Schematic, SchematicBlockEntity, StructureBlock,
+    StructureTemplate,
+};
 use rand::Rng;
-use std::collections::HashMap;
-use std::io::{Read as _, Write as _};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::io::Write as _;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicI32, Ordering};
 use std::sync::Arc;
@@ -36,6 +69,15 @@ pub struct InboundPacket {
 pub struct NewPlayer {
     pub entity_id: i32,
     pub profile: GameProfile,
+    pub address: std::net::IpAddr,
+    /// Brand and channels the client sent during configuration.
+    pub channels: ClientChannels,
+    /// Options the client sent during configuration, if it sent any.
+    pub settings: Option<ClientSettings>,
+    /// Whether another server sent the player here with a Transfer.
+    pub transferred: bool,
+    /// Traffic counters kept by the connection's reader and writer.
+    pub net_stats: crate::net_stats::SharedNetStats,
     pub packet_tx: mpsc::UnboundedSender<InternalPacket>,
     pub packet_rx: mpsc::UnboundedReceiver<InboundPacket>,
 }
@@ -57,23 +99,19 @@ struct PlayerSaveData {
     xp_progress: f32,
     xp_total: i32,
     spawn_point: Option<(BlockPos, f32)>, // bed position + yaw
+    homes: BTreeMap<String, Location>,
+    mod_data: BTreeMap<String, NbtValue>,
+    /// The level the player logged out in.
+    dimension: String,
 }
 
 /// Serialize a block entity to vanilla-compatible NBT for chunk storage.
 fn serialize_block_entity(pos: &BlockPos, be: &BlockEntity) -> NbtValue {
     match be {
         BlockEntity::Chest { inventory } => {
-            let mut items = Vec::new();
-            for (i, slot) in inventory.iter().enumerate() {
-                if let Some(item) = slot {
-                    let name = pickaxe_data::item_id_to_name(item.item_id).unwrap_or("air");
-                    items.push(nbt_compound! {
-                        "Slot" => NbtValue::Byte(i as i8),
-                        "id" => NbtValue::String(format!("minecraft:{}", name)),
-                        "Count" => NbtValue::Byte(item.count)
-                    });
-                }
-            }
+            let items: Vec<NbtValue> = inventory.iter().enumerate()
+                .filter_map(|(i, slot)| slot.as_ref().map(|item| item_stack_to_nbt(i as i8, item)))
+                .collect();
             nbt_compound! {
                 "id" => NbtValue::String("minecraft:chest".into()),
                 "x" => NbtValue::Int(pos.x),
@@ -187,6 +225,41 @@ fn serialize_block_entity(pos: &BlockPos, be: &BlockEntity) -> NbtValue {
                 "Fuel" => NbtValue::Byte(*fuel_uses as i8)
             }
         }
+        BlockEntity::Bell { .. } => {
+            nbt_compound! {
+                "id" => NbtValue::String("minecraft:bell".into()),
+                "x" => NbtValue::Int(pos.x),
+                "y" => NbtValue::Int(pos.y),
+                "z" => NbtValue::Int(pos.z)
+            }
+        }
+        BlockEntity::Lectern { book, page } => {
+            let mut entries = vec![
+                ("id".to_string(), NbtValue::String("minecraft:lectern".into())),
+                ("x".to_string(), NbtValue::Int(pos.x)),
+                ("y".to_string(), NbtValue::Int(pos.y)),
+                ("z".to_string(), NbtValue::Int(pos.z)),
+            ];
+            if let Some(book) = book {
+                entries.push(("Book".into(), item_stack_to_nbt(0, book)));
+                entries.push(("Page".into(), NbtValue::Int(*page)));
+            }
+            NbtValue::Compound(entries)
+        }
+        BlockEntity::Campfire { items, cook_times, cook_totals } => {
+            let items: Vec<NbtValue> = items.iter().enumerate()
+                .filter_map(|(i, slot)| slot.as_ref().map(|item| item_stack_to_nbt(i as i8, item)))
+                .collect();
+            nbt_compound! {
+                "id" => NbtValue::String("minecraft:campfire".into()),
+                "x" => NbtValue::Int(pos.x),
+                "y" => NbtValue::Int(pos.y),
+                "z" => NbtValue::Int(pos.z),
+                "Items" => NbtValue::List(items),
+                "CookingTimes" => NbtValue::IntArray(cook_times.to_vec()),
+                "CookingTotalTimes" => NbtValue::IntArray(cook_totals.to_vec())
+            }
+        }
     }
 }
 
@@ -204,14 +277,10 @@ fn deserialize_block_entity(nbt: &NbtValue) -> Option<(BlockPos, BlockEntity)> {
         "chest" => {
             let mut inventory: [Option<ItemStack>; 27] = std::array::from_fn(|_| None);
             if let Some(items_list) = nbt.get("Items").and_then(|v| v.as_list()) {
-                for item_nbt in items_list {
-                    let slot = item_nbt.get("Slot").and_then(|v| v.as_byte())? as usize;
-                    let item_id_str = item_nbt.get("id").and_then(|v| v.as_str())?;
-                    let name = item_id_str.strip_prefix("minecraft:").unwrap_or(item_id_str);
-                    let item_id = pickaxe_data::item_name_to_id(name)?;
-                    let count = item_nbt.get("Count").and_then(|v| v.as_byte()).unwrap_or(1);
-                    if slot < 27 {
-                        inventory[slot] = Some(ItemStack::new(item_id, count));
+                // Skip items we can't read rather than losing the chest
+                for (slot, stack) in items_list.iter().filter_map(item_stack_from_nbt) {
+                    if (0..27).contains(&slot) {
+                        inventory[slot as usize] = Some(stack);
                     }
                 }
             }
@@ -233,7 +302,7 @@ fn deserialize_block_entity(nbt: &NbtValue) -> Option<(BlockPos, BlockEntity)> {
                         Some(id) => id,
                         None => continue,
                     };
-                    let count = item_nbt.get("Count").and_then(|v| v.as_byte()).unwrap_or(1);
+                    let count = item_count(item_nbt);
                     let stack = ItemStack::new(item_id, count);
                     match slot {
                         0 => input = Some(stack),
@@ -267,7 +336,7 @@ fn deserialize_block_entity(nbt: &NbtValue) -> Option<(BlockPos, BlockEntity)> {
                         Some(id) => id,
                         None => continue,
                     };
-                    let count = item_nbt.get("Count").and_then(|v| v.as_byte()).unwrap_or(1);
+                    let count = item_count(item_nbt);
                     let mut stack = ItemStack::new(item_id, count);
                     // Restore potion type from tag.Potion
                     if let Some(tag) = item_nbt.get("tag") {
@@ -330,10 +399,263 @@ fn deserialize_block_entity(nbt: &NbtValue) -> Option<(BlockPos, BlockEntity)> {
                 front_text, back_text, color, has_glowing_text, is_waxed,
             }))
         }
+        "bell" => Some((pos, BlockEntity::Bell { ringing_ticks: 0 })),
+        "lectern" => {
+            let book = nbt.get("Book").and_then(|b| item_stack_from_nbt(b)).map(|(_, stack)| stack);
+            let page = nbt.get("Page").and_then(|v| v.as_int()).unwrap_or(0);
+            Some((pos, BlockEntity::Lectern { book, page }))
+        }
+        "campfire" => {
+            let mut items: [Option<ItemStack>; 4] = std::array::from_fn(|_| None);
+            if let Some(items_list) = nbt.get("Items").and_then(|v| v.as_list()) {
+                for item_nbt in items_list {
+                    if let Some((slot, stack)) = item_stack_from_nbt(item_nbt) {
+                        if (0..4).contains(&slot) {
+                            items[slot as usize] = Some(stack);
+                        }
+                    }
+                }
+            }
+            let read_times = |key: &str| -> [i32; 4] {
+                let values = nbt.get(key).and_then(|v| v.as_int_array()).unwrap_or(&[]);
+                std::array::from_fn(|i| values.get(i).copied().unwrap_or(0))
+            };
+            Some((pos, BlockEntity::Campfire {
+                items,
+                cook_times: read_times("CookingTimes"),
+                cook_totals: read_times("CookingTotalTimes"),
+            }))
+        }
+        _ => None,
+    }
+}
+
+/// Map an ECS inventory slot to the vanilla player NBT slot number.
+pub(crate) fn ecs_slot_to_nbt(ecs_slot: usize) -> Option<i8> {
+    match ecs_slot {
+        36..=44 => Some((ecs_slot - 36) as i8),    // hotbar: ECS 36-44 → NBT 0-8
+        9..=35 => Some(ecs_slot as i8),              // main: ECS 9-35 → NBT 9-35
+        5..=8 => Some((100 + (ecs_slot - 5)) as i8), // armor: ECS 5-8 → NBT 100-103
+        45 => Some(-106),                             // offhand: ECS 45 → NBT -106
+        _ => None,
+    }
+}
+
+/// Map a vanilla player NBT slot number to an ECS inventory slot.
+pub(crate) fn nbt_slot_to_ecs(nbt_slot: i8) -> Option<usize> {
+    match nbt_slot {
+        0..=8 => Some((nbt_slot as usize) + 36),        // hotbar: NBT 0-8 → ECS 36-44
+        9..=35 => Some(nbt_slot as usize),               // main: NBT 9-35 → ECS 9-35
+        100..=103 => Some((nbt_slot - 100) as usize + 5), // armor: NBT 100-103 → ECS 5-8
+        -106 => Some(45),                                 // offhand: NBT -106 → ECS 45
         _ => None,
     }
 }
 
+/// Serialize an inventory item to a player data NBT entry.
+pub(crate) fn item_stack_to_nbt(nbt_slot: i8, stack: &ItemStack) -> NbtValue {
+    let item_name = format!(
+        "minecraft:{}",
+        pickaxe_data::item_id_to_name(stack.item_id).unwrap_or("air")
+    );
+    let mut entries: Vec<(String, NbtValue)> = vec![
+        ("Slot".into(), NbtValue::Byte(nbt_slot)),
+        ("id".into(), NbtValue::String(item_name)),
+        ("count".into(), NbtValue::Byte(stack.count)),
+    ];
+    if stack.max_damage > 0 {
+        entries.push(("MaxDamage".into(), NbtValue::Int(stack.max_damage)));
+        if stack.damage > 0 {
+            entries.push(("Damage".into(), NbtValue::Int(stack.damage)));
+        }
+    }
+    if !stack.enchantments.is_empty() {
+        let ench_list: Vec<NbtValue> = stack.enchantments.iter().map(|(id, lvl)| {
+            let ench_name = format!("minecraft:{}", pickaxe_data::enchantment_id_to_name(*id).unwrap_or("unknown"));
+            NbtValue::Compound(vec![
+                ("id".into(), NbtValue::String(ench_name)),
+                ("lvl".into(), NbtValue::Short(*lvl as i16)),
+            ])
+        }).collect();
+        entries.push(("Enchantments".into(), NbtValue::List(ench_list)));
+    }
+    if let Some(rgb) = stack.dyed_color {
+        entries.push(("DyedColor".into(), NbtValue::Int(rgb)));
+    }
+    if let Some(name) = &stack.custom_name {
+        entries.push(("CustomName".into(), NbtValue::String(name.to_json())));
+    }
+    if !stack.lore.is_empty() {
+        let lines = stack.lore.iter().map(|line| NbtValue::String(line.to_json())).collect();
+        entries.push(("Lore".into(), NbtValue::List(lines)));
+    }
+    if stack.unbreakable {
+        entries.push(("Unbreakable".into(), NbtValue::Byte(1)));
+    }
+    if !stack.attribute_modifiers.is_empty() {
+        let modifiers = stack.attribute_modifiers.iter().map(|modifier| {
+            let attribute = pickaxe_data::attribute_id_to_name(modifier.attribute).unwrap_or("unknown");
+            nbt_compound! {
+                "type" => NbtValue::String(format!("minecraft:{}", attribute)),
+                "id" => NbtValue::String(modifier.id.clone()),
+                "amount" => NbtValue::Double(modifier.amount),
+                "operation" => NbtValue::String(modifier.operation.name().into()),
+                "slot" => NbtValue::String(modifier.slot.name().into())
+            }
+        }).collect();
+        entries.push(("AttributeModifiers".into(), NbtValue::List(modifiers)));
+    }
+    if let Some(contents) = &stack.potion_contents {
+        let mut fields = Vec::new();
+        if let Some(potion) = contents.potion.and_then(pickaxe_data::potion_registry_name) {
+            fields.push(("potion".to_string(), NbtValue::String(format!("minecraft:{}", potion))));
+        }
+        if let Some(color) = contents.custom_color {
+            fields.push(("custom_color".to_string(), NbtValue::Int(color)));
+        }
+        if !contents.custom_effects.is_empty() {
+            let effects = contents.custom_effects.iter().map(|effect| {
+                let name = pickaxe_data::effect_id_to_name(effect.effect_id).unwrap_or("unknown");
+                nbt_compound! {
+                    "id" => NbtValue::String(format!("minecraft:{}", name)),
+                    "amplifier" => NbtValue::Int(effect.amplifier),
+                    "duration" => NbtValue::Int(effect.duration),
+                    "ambient" => NbtValue::Byte(effect.ambient as i8),
+                    "show_particles" => NbtValue::Byte(effect.show_particles as i8),
+                    "show_icon" => NbtValue::Byte(effect.show_icon as i8)
+                }
+            }).collect();
+            fields.push(("custom_effects".to_string(), NbtValue::List(effects)));
+        }
+        entries.push(("PotionContents".into(), NbtValue::Compound(fields)));
+    }
+    if !stack.banner_patterns.is_empty() {
+        let layers = stack.banner_patterns.iter().map(|layer| {
+            let pattern = pickaxe_data::registries::banner_pattern_name(layer.pattern).unwrap_or("base");
+            nbt_compound! {
+                "pattern" => NbtValue::String(format!("minecraft:{}", pattern)),
+                "color" => NbtValue::String(pickaxe_data::dye_color_name(layer.color).unwrap_or("white").into())
+            }
+        }).collect();
+        entries.push(("BannerPatterns".into(), NbtValue::List(layers)));
+    }
+    if let Some(data) = pickaxe_protocol_core::custom_data_nbt(stack) {
+        entries.push(("CustomData".into(), data));
+    }
+    NbtValue::Compound(entries)
+}
+
+/// Deserialize a player data NBT inventory entry into (NBT slot, item).
+/// An item's stack size: vanilla's int `count`, or the byte `Count` older
+/// pickaxe block entities use.
+fn item_count(item: &NbtValue) -> i8 {
+    item.get("count")
+        .or_else(|| item.get("Count"))
+        .and_then(|v| v.as_number())
+        .map(|count| count as i8)
+        .unwrap_or(1)
+}
+
+pub(crate) fn item_stack_from_nbt(entry: &NbtValue) -> Option<(i8, ItemStack)> {
+    let nbt_slot = entry.get("Slot").and_then(|v| v.as_byte())?;
+    let id_str = entry.get("id").and_then(|v| v.as_str())?;
+    let count = item_count(entry);
+
+    // Strip "minecraft:" prefix
+    let name = id_str.strip_prefix("minecraft:").unwrap_or(id_str);
+    let item_id = pickaxe_data::item_name_to_id(name)?;
+
+    let mut stack = ItemStack::new(item_id, count);
+    stack.max_damage = entry.get("MaxDamage").and_then(|v| v.as_int()).unwrap_or(0);
+    stack.damage = entry.get("Damage").and_then(|v| v.as_int()).unwrap_or(0);
+    stack.dyed_color = entry.get("DyedColor").and_then(|v| v.as_int());
+    stack.custom_name = entry.get("CustomName").and_then(|v| v.as_str()).map(parse_text_arg);
+    if let Some(lines) = entry.get("Lore").and_then(|v| v.as_list()) {
+        stack.lore = lines.iter().filter_map(|v| v.as_str()).map(parse_text_arg).collect();
+    }
+    if let Some(data) = entry.get("CustomData") {
+        (stack.custom_id, stack.custom_data) = pickaxe_protocol_core::split_custom_data(data);
+    }
+    stack.unbreakable = entry.get("Unbreakable").and_then(|v| v.as_byte()) == Some(1);
+    if let Some(modifiers) = entry.get("AttributeModifiers") {
+        stack.attribute_modifiers = attribute_modifiers_from_nbt(modifiers);
+    }
+    stack.potion_contents = entry.get("PotionContents").map(potion_contents_from_nbt);
+    if let Some(layers) = entry.get("BannerPatterns") {
+        stack.banner_patterns = banner_patterns_from_nbt(layers);
+    }
+    // Load enchantments
+    if let Some(ench_list) = entry.get("Enchantments").and_then(|v| v.as_list()) {
+        for ench_nbt in ench_list {
+            let ench_id_str = ench_nbt.get("id").and_then(|v| v.as_str()).unwrap_or("");
+            let ench_name = ench_id_str.strip_prefix("minecraft:").unwrap_or(ench_id_str);
+            let lvl = ench_nbt.get("lvl").and_then(|v| v.as_short()).unwrap_or(1) as i32;
+            if let Some(eid) = pickaxe_data::enchantment_name_to_id(ench_name) {
+                stack.enchantments.push((eid, lvl));
+            }
+        }
+    }
+    Some((nbt_slot, stack))
+}
+
+/// Read vanilla's `attribute_modifiers` form: a list of `{type, id, amount,
+/// operation, slot}`, or `{modifiers: [...]}`. Unknown attributes are dropped.
+fn attribute_modifiers_from_nbt(nbt: &NbtValue) -> Vec<AttributeModifier> {
+    let modifiers = nbt.get("modifiers").unwrap_or(nbt).as_list().unwrap_or(&[]);
+    modifiers.iter().filter_map(|modifier| {
+        let attribute = modifier.get("type")?.as_str()?;
+        let slot = match modifier.get("slot").and_then(|v| v.as_str()) {
+            Some(slot) => EquipmentSlotGroup::from_name(slot)?,
+            None => EquipmentSlotGroup::Any,
+        };
+        Some(AttributeModifier {
+            attribute: pickaxe_data::attribute_name_to_id(attribute.strip_prefix("minecraft:").unwrap_or(attribute))?,
+            id: modifier.get("id")?.as_str()?.to_string(),
+            amount: modifier.get("amount")?.as_number()?,
+            operation: AttributeOperation::from_name(modifier.get("operation")?.as_str()?)?,
+            slot,
+        })
+    }).collect()
+}
+
+/// Read vanilla's `potion_contents` form: a potion name, or `{potion,
+/// custom_color, custom_effects}`. Unknown potions and effects are dropped.
+fn potion_contents_from_nbt(nbt: &NbtValue) -> PotionContents {
+    let potion_id = |name: &str| pickaxe_data::potion_registry_id(name.strip_prefix("minecraft:").unwrap_or(name));
+    if let Some(name) = nbt.as_str() {
+        return PotionContents { potion: potion_id(name), ..Default::default() };
+    }
+    let flag = |effect: &NbtValue, key: &str, default: bool| effect.get(key).and_then(|v| v.as_byte()).map_or(default, |b| b != 0);
+    let effects = nbt.get("custom_effects").and_then(|v| v.as_list()).unwrap_or(&[]);
+    PotionContents {
+        potion: nbt.get("potion").and_then(|v| v.as_str()).and_then(potion_id),
+        custom_color: nbt.get("custom_color").and_then(|v| v.as_number()).map(|n| n as i32),
+        custom_effects: effects.iter().filter_map(|effect| {
+            let id = effect.get("id")?.as_str()?;
+            Some(PotionContentsEffect {
+                effect_id: pickaxe_data::effect_name_to_id(id.strip_prefix("minecraft:").unwrap_or(id))?,
+                amplifier: effect.get("amplifier").and_then(|v| v.as_number()).map_or(0, |n| n as i32),
+                duration: effect.get("duration").and_then(|v| v.as_number()).map_or(1, |n| n as i32),
+                ambient: flag(effect, "ambient", false),
+                show_particles: flag(effect, "show_particles", true),
+                show_icon: flag(effect, "show_icon", true),
+            })
+        }).collect(),
+    }
+}
+
+/// Read vanilla's `banner_patterns` form: a list of `{pattern, color}`.
+/// Unknown patterns and colors are dropped.
+fn banner_patterns_from_nbt(nbt: &NbtValue) -> Vec<BannerLayer> {
+    nbt.as_list().unwrap_or(&[]).iter().filter_map(|layer| {
+        let pattern = layer.get("pattern")?.as_str()?;
+        Some(BannerLayer {
+            pattern: pickaxe_data::registries::banner_pattern_id(pattern.strip_prefix("minecraft:").unwrap_or(pattern))?,
+            color: pickaxe_data::dye_color_id(layer.get("color")?.as_str()?)?,
+        })
+    }).collect()
+}
+
 /// Serialize a player entity's ECS components to gzip-compressed vanilla-compatible NBT.
 fn serialize_player_data(world: &World, entity: hecs::Entity) -> Option<Vec<u8>> {
     let pos = world.get::<&Position>(entity).ok()?;
@@ -347,44 +669,16 @@ fn serialize_player_data(world: &World, entity: hecs::Entity) -> Option<Vec<u8>>
     let gm = world.get::<&PlayerGameMode>(entity).ok()?;
     let xp = world.get::<&ExperienceData>(entity).ok();
     let spawn_point = world.get::<&SpawnPoint>(entity).ok();
+    let profile = world.get::<&Profile>(entity).ok();
+    let homes = world.get::<&Homes>(entity).ok();
+    let mod_data = world.get::<&PlayerModData>(entity).ok();
+    let dimension = player_level(world, entity);
 
     // Build inventory NBT list with vanilla slot mapping
     let mut inv_items = Vec::new();
     for (ecs_slot, item) in inv.slots.iter().enumerate() {
-        if let Some(stack) = item {
-            let nbt_slot: i8 = match ecs_slot {
-                36..=44 => (ecs_slot - 36) as i8,    // hotbar: ECS 36-44 → NBT 0-8
-                9..=35 => ecs_slot as i8,              // main: ECS 9-35 → NBT 9-35
-                5..=8 => (100 + (ecs_slot - 5)) as i8, // armor: ECS 5-8 → NBT 100-103
-                45 => -106,                             // offhand: ECS 45 → NBT -106
-                _ => continue,
-            };
-            let item_name = format!(
-                "minecraft:{}",
-                pickaxe_data::item_id_to_name(stack.item_id).unwrap_or("air")
-            );
-            let mut entries: Vec<(String, NbtValue)> = vec![
-                ("Slot".into(), NbtValue::Byte(nbt_slot)),
-                ("id".into(), NbtValue::String(item_name)),
-                ("count".into(), NbtValue::Byte(stack.count)),
-            ];
-            if stack.max_damage > 0 {
-                entries.push(("MaxDamage".into(), NbtValue::Int(stack.max_damage)));
-                if stack.damage > 0 {
-                    entries.push(("Damage".into(), NbtValue::Int(stack.damage)));
-                }
-            }
-            if !stack.enchantments.is_empty() {
-                let ench_list: Vec<NbtValue> = stack.enchantments.iter().map(|(id, lvl)| {
-                    let ench_name = format!("minecraft:{}", pickaxe_data::enchantment_id_to_name(*id).unwrap_or("unknown"));
-                    NbtValue::Compound(vec![
-                        ("id".into(), NbtValue::String(ench_name)),
-                        ("lvl".into(), NbtValue::Short(*lvl as i16)),
-                    ])
-                }).collect();
-                entries.push(("Enchantments".into(), NbtValue::List(ench_list)));
-            }
-            inv_items.push(NbtValue::Compound(entries));
+        if let (Some(stack), Some(nbt_slot)) = (item, ecs_slot_to_nbt(ecs_slot)) {
+            inv_items.push(item_stack_to_nbt(nbt_slot, stack));
         }
     }
 
@@ -408,12 +702,28 @@ fn serialize_player_data(world: &World, entity: hecs::Entity) -> Option<Vec<u8>>
         "Inventory" => NbtValue::List(inv_items),
         "SelectedItemSlot" => NbtValue::Int(held.0 as i32),
         "playerGameType" => NbtValue::Int(gm.0.id() as i32),
-        "Dimension" => NbtValue::String("minecraft:overworld".into()),
+        "Dimension" => NbtValue::String(dimension),
         "XpLevel" => NbtValue::Int(xp.as_ref().map(|x| x.level).unwrap_or(0)),
         "XpP" => NbtValue::Float(xp.as_ref().map(|x| x.progress).unwrap_or(0.0)),
         "XpTotal" => NbtValue::Int(xp.as_ref().map(|x| x.total_xp).unwrap_or(0))
     };
 
+    // Last known name, so offline players can be looked up by name
+    if let Some(profile) = profile {
+        nbt.set("LastKnownName", NbtValue::String(profile.0.name.clone()));
+    }
+
+    // /sethome destinations (not a vanilla tag; vanilla ignores it)
+    if let Some(homes) = homes.filter(|h| !h.0.is_empty()) {
+        nbt.set("PickaxeHomes", teleports::locations_to_nbt(&homes.0));
+    }
+
+    // Values set with pickaxe.players.set_data (not a vanilla tag either)
+    if let Some(mod_data) = mod_data.filter(|d| !d.0.is_empty()) {
+        let entries = mod_data.0.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        nbt.set("PickaxeData", NbtValue::Compound(entries));
+    }
+
     // Add bed spawn point if set (vanilla format)
     if let Some(sp) = spawn_point {
         if let NbtValue::Compound(ref mut entries) = nbt {
@@ -421,7 +731,7 @@ fn serialize_player_data(world: &World, entity: hecs::Entity) -> Option<Vec<u8>>
             entries.push(("SpawnY".into(), NbtValue::Int(sp.position.y)));
             entries.push(("SpawnZ".into(), NbtValue::Int(sp.position.z)));
             entries.push(("SpawnAngle".into(), NbtValue::Float(sp.yaw)));
-            entries.push(("SpawnDimension".into(), NbtValue::String("minecraft:overworld".into())));
+            entries.push(("SpawnDimension".into(), NbtValue::String(DEFAULT_LEVEL.into())));
         }
     }
 
@@ -435,13 +745,7 @@ fn serialize_player_data(world: &World, entity: hecs::Entity) -> Option<Vec<u8>>
 
 /// Deserialize gzip-compressed vanilla NBT into a PlayerSaveData struct.
 fn deserialize_player_data(data: &[u8]) -> Option<PlayerSaveData> {
-    // Gzip decompress
-    let mut decoder = GzDecoder::new(data);
-    let mut decompressed = Vec::new();
-    decoder.read_to_end(&mut decompressed).ok()?;
-
-    // Parse NBT
-    let (_, nbt) = NbtValue::read_root_named(&decompressed).ok()?;
+    let (_, nbt) = NbtValue::read_root_named_from(GzDecoder::new(data), NbtLimits::FILE).ok()?;
 
     // Extract position
     let pos_list = nbt.get("Pos")?.as_list()?;
@@ -472,44 +776,8 @@ fn deserialize_player_data(data: &[u8]) -> Option<PlayerSaveData> {
     let mut slots: [Option<ItemStack>; 46] = std::array::from_fn(|_| None);
     if let Some(inv_list) = nbt.get("Inventory").and_then(|v| v.as_list()) {
         for entry in inv_list {
-            let nbt_slot = entry.get("Slot").and_then(|v| v.as_byte());
-            let id_str = entry.get("id").and_then(|v| v.as_str());
-            let count = entry.get("count").and_then(|v| v.as_byte()).unwrap_or(1);
-
-            if let (Some(nbt_slot), Some(id_str)) = (nbt_slot, id_str) {
-                // Strip "minecraft:" prefix
-                let name = id_str.strip_prefix("minecraft:").unwrap_or(id_str);
-                let item_id = match pickaxe_data::item_name_to_id(name) {
-                    Some(id) => id,
-                    None => continue,
-                };
-
-                // Map NBT slot → ECS slot
-                let ecs_slot: usize = match nbt_slot {
-                    0..=8 => (nbt_slot as usize) + 36,    // hotbar: NBT 0-8 → ECS 36-44
-                    9..=35 => nbt_slot as usize,            // main: NBT 9-35 → ECS 9-35
-                    100..=103 => (nbt_slot - 100) as usize + 5, // armor: NBT 100-103 → ECS 5-8
-                    -106 => 45,                              // offhand: NBT -106 → ECS 45
-                    _ => continue,
-                };
-
-                if ecs_slot < 46 {
-                    let max_damage = entry.get("MaxDamage").and_then(|v| v.as_int()).unwrap_or(0);
-                    let damage = entry.get("Damage").and_then(|v| v.as_int()).unwrap_or(0);
-                    let mut stack = ItemStack::new(item_id, count);
-                    stack.max_damage = max_damage;
-                    stack.damage = damage;
-                    // Load enchantments
-                    if let Some(ench_list) = entry.get("Enchantments").and_then(|v| v.as_list()) {
-                        for ench_nbt in ench_list {
-                            let ench_id_str = ench_nbt.get("id").and_then(|v| v.as_str()).unwrap_or("");
-                            let ench_name = ench_id_str.strip_prefix("minecraft:").unwrap_or(ench_id_str);
-                            let lvl = ench_nbt.get("lvl").and_then(|v| v.as_short()).unwrap_or(1) as i32;
-                            if let Some(eid) = pickaxe_data::enchantment_name_to_id(ench_name) {
-                                stack.enchantments.push((eid, lvl));
-                            }
-                        }
-                    }
+            if let Some((nbt_slot, stack)) = item_stack_from_nbt(entry) {
+                if let Some(ecs_slot) = nbt_slot_to_ecs(nbt_slot) {
                     slots[ecs_slot] = Some(stack);
                 }
             }
@@ -544,55 +812,199 @@ fn deserialize_player_data(data: &[u8]) -> Option<PlayerSaveData> {
         xp_progress,
         xp_total,
         spawn_point,
+        homes: nbt.get("PickaxeHomes").map(teleports::locations_from_nbt).unwrap_or_default(),
+        mod_data: match nbt.get("PickaxeData") {
+            Some(NbtValue::Compound(entries)) => entries.iter().cloned().collect(),
+            _ => BTreeMap::new(),
+        },
+        dimension: nbt.get("Dimension").and_then(|v| v.as_str()).unwrap_or(DEFAULT_LEVEL).to_string(),
     })
 }
 
+/// The level a player is in.
+pub(crate) fn player_level(world: &World, entity: hecs::Entity) -> String {
+    world
+        .get::<&Dimension>(entity)
+        .map(|d| d.0.clone())
+        .unwrap_or_else(|_| DEFAULT_LEVEL.to_string())
+}
+
+/// Where new players appear and players without a bed respawn.
+fn world_spawn() -> Vec3d {
+    Vec3d::new(0.5, -49.0, 0.5)
+}
+
 /// Save all currently-connected players' data.
-fn save_all_players(world: &World, save_tx: &mpsc::UnboundedSender<SaveOp>) {
+fn save_all_players(world: &World, world_state: &WorldState) {
     for (entity, profile) in world.query::<&Profile>().iter() {
         if let Some(data) = serialize_player_data(world, entity) {
-            let _ = save_tx.send(SaveOp::Player(profile.0.uuid, data));
+            world_state.player_data.queue_save(profile.0.uuid, &profile.0.name, data, &world_state.save_tx);
         }
+        save_player_progress(world, world_state, entity, profile.0.uuid);
     }
 }
 
-/// Save all chunks that contain block entities.
-fn save_block_entity_chunks(world_state: &WorldState) {
-    use std::collections::HashSet;
-    let mut saved_chunks = HashSet::new();
-    for pos in world_state.block_entities.keys() {
-        let chunk_pos = pos.chunk_pos();
-        if saved_chunks.insert(chunk_pos) {
-            world_state.queue_chunk_save(chunk_pos);
-        }
+/// Queue a player's `stats/<uuid>.json` and `advancements/<uuid>.json` for writing.
+fn save_player_progress(world: &World, world_state: &WorldState, entity: hecs::Entity, uuid: Uuid) {
+    if let Ok(stats) = world.get::<&Statistics>(entity) {
+        let _ = world_state.save_tx.send(SaveOp::Stats(uuid, stats.to_json()));
+    }
+    if world_state.advancements.is_empty() {
+        return;
+    }
+    if let Ok(progress) = world.get::<&AdvancementProgress>(entity) {
+        let data = progress.to_json(&world_state.advancements);
+        let _ = world_state.save_tx.send(SaveOp::Advancements(uuid, data));
+    }
+}
+
+/// Save all chunks that contain block entities, in every level.
+fn save_block_entity_chunks(world_state: &mut WorldState) {
+    for level in world_state.all_levels_mut() {
+        let saved_chunks: HashSet<ChunkPos> = level.block_entities.keys().map(|pos| pos.chunk_pos()).collect();
+        level.dirty_chunks.extend(saved_chunks);
+    }
+}
+
+/// Queue a save of all players, level.dat (and each other level's), the
+/// scoreboard, warps and mod storage. Changed and block entity chunks are
+/// lined up to be written a few per tick; call `flush_dirty_chunks` after
+/// this when they must be written now.
+fn save_world(world: &World, world_state: &mut WorldState, config: &ServerConfig) {
+    save_all_players(world, world_state);
+    save_block_entity_chunks(world_state);
+    world_state.queue_dirty_chunks();
+    let level_data = serialize_level_dat(world_state, config);
+    let _ = world_state.save_tx.send(SaveOp::LevelDat(level_data));
+    for level in world_state.all_levels().filter(|level| level.name != DEFAULT_LEVEL) {
+        let dimension_type = world_state
+            .registries
+            .dimension_types
+            .get(level.dimension_type as usize)
+            .map(|d| d.name.as_str())
+            .unwrap_or(DEFAULT_LEVEL);
+        let data = serialize_dimension_level_dat(level, dimension_type);
+        let _ = world_state.save_tx.send(SaveOp::DimensionLevelDat(level.name.clone(), data));
+    }
+    save_scoreboard(world_state);
+    if world_state.warps.dirty {
+        world_state.warps.dirty = false;
+        let _ = world_state.save_tx.send(SaveOp::Warps(world_state.warps.serialize()));
+    }
+    save_mod_storage(world_state);
+}
+
+/// Queue changed `pickaxe.storage` namespaces for saving.
+fn save_mod_storage(world_state: &WorldState) {
+    let Ok(mut storage) = world_state.mod_storage.lock() else {
+        return;
+    };
+    for (name, data) in storage.take_dirty() {
+        let _ = world_state.save_tx.send(SaveOp::ModStorage(name, data));
+    }
+}
+
+/// Queue scoreboard.dat for saving if anything changed since the last save.
+fn save_scoreboard(world_state: &mut WorldState) {
+    if world_state.scoreboard.dirty {
+        world_state.scoreboard.dirty = false;
+        let data = world_state.scoreboard.serialize();
+        let _ = world_state.save_tx.send(SaveOp::Scoreboard(data));
     }
 }
 
 /// Serialize level.dat to gzip-compressed NBT (vanilla-compatible format).
-fn serialize_level_dat(world_state: &WorldState, _config: &ServerConfig) -> Vec<u8> {
+/// The world's `level.dat`. Starts from the Data compound it was loaded from,
+/// so what vanilla keeps there and this server doesn't manage (DragonFight,
+/// CustomBossEvents, scheduled events, the world border...) survives; then
+/// writes what this server tracks and vanilla's defaults for anything missing.
+fn serialize_level_dat(world_state: &WorldState, config: &ServerConfig) -> Vec<u8> {
+    let mut data = match &world_state.level_dat {
+        data @ NbtValue::Compound(_) => data.clone(),
+        _ => NbtValue::Compound(Vec::new()),
+    };
+    let spawn = world_spawn();
+    let last_played = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    let owned = [
+        ("DataVersion", NbtValue::Int(3955)),
+        // Anvil format
+        ("version", NbtValue::Int(19133)),
+        ("Version", nbt_compound! {
+            "Id" => NbtValue::Int(3955),
+            "Name" => NbtValue::String("1.21.1".into()),
+            "Series" => NbtValue::String("main".into()),
+            "Snapshot" => NbtValue::Byte(0)
+        }),
+        ("SpawnX", NbtValue::Int(spawn.x.floor() as i32)),
+        ("SpawnY", NbtValue::Int(spawn.y.floor() as i32)),
+        ("SpawnZ", NbtValue::Int(spawn.z.floor() as i32)),
+        ("SpawnAngle", NbtValue::Float(0.0)),
+        ("Time", NbtValue::Long(world_state.world_age)),
+        ("DayTime", NbtValue::Long(world_state.time_of_day)),
+        ("LastPlayed", NbtValue::Long(last_played)),
+        ("GameType", NbtValue::Int(0)),
+        ("Difficulty", NbtValue::Byte(world_state.difficulty as i8)),
+        ("hardcore", NbtValue::Byte(0)),
+        ("allowCommands", NbtValue::Byte(1)),
+        ("initialized", NbtValue::Byte(1)),
+        ("WasModded", NbtValue::Byte(1)),
+        ("raining", NbtValue::Byte(world_state.raining as i8)),
+        ("thundering", NbtValue::Byte(world_state.thundering as i8)),
+        ("rainTime", NbtValue::Int(world_state.rain_time)),
+        ("thunderTime", NbtValue::Int(world_state.thunder_time)),
+        ("clearWeatherTime", NbtValue::Int(world_state.clear_weather_time)),
+        ("GameRules", merge_compound(data.get("GameRules"), world_state.game_rules.to_nbt())),
+        ("WorldGenSettings", world_gen_settings(world_state, data.get("WorldGenSettings"))),
+        ("DataPacks", data_packs(world_state, data.get("DataPacks"))),
+    ];
+    for (key, value) in owned {
+        data.set(key, value);
+    }
+
+    let level_name = std::path::Path::new(&config.world_dir)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "world".into());
+    let defaults = [
+        ("LevelName", NbtValue::String(level_name)),
+        ("DifficultyLocked", NbtValue::Byte(0)),
+        ("enabled_features", NbtValue::List(vec![NbtValue::String("minecraft:vanilla".into())])),
+        ("WanderingTraderSpawnChance", NbtValue::Int(25)),
+        ("WanderingTraderSpawnDelay", NbtValue::Int(24000)),
+        ("DragonFight", nbt_compound! {
+            "NeedsStateScanning" => NbtValue::Byte(1),
+            "DragonKilled" => NbtValue::Byte(0),
+            "PreviouslyKilled" => NbtValue::Byte(0)
+        }),
+        ("CustomBossEvents", NbtValue::Compound(Vec::new())),
+        ("ScheduledEvents", NbtValue::List(Vec::new())),
+        ("BorderCenterX", NbtValue::Double(0.0)),
+        ("BorderCenterZ", NbtValue::Double(0.0)),
+        ("BorderSize", NbtValue::Double(5.9999968E7)),
+        ("BorderSizeLerpTarget", NbtValue::Double(5.9999968E7)),
+        ("BorderSizeLerpTime", NbtValue::Long(0)),
+        ("BorderSafeZone", NbtValue::Double(5.0)),
+        ("BorderDamagePerBlock", NbtValue::Double(0.2)),
+        ("BorderWarningBlocks", NbtValue::Double(5.0)),
+        ("BorderWarningTime", NbtValue::Double(15.0)),
+    ];
+    for (key, value) in defaults {
+        if data.get(key).is_none() {
+            data.set(key, value);
+        }
+    }
+    let mut brands = data.get("ServerBrands").and_then(|v| v.as_list()).map(<[_]>::to_vec).unwrap_or_default();
+    if !brands.iter().any(|b| b.as_str() == Some("pickaxe")) {
+        brands.push(NbtValue::String("pickaxe".into()));
+    }
+    data.set("ServerBrands", NbtValue::List(brands));
+
     let nbt = nbt_compound! {
         "DataVersion" => NbtValue::Int(3955),
-        "Data" => nbt_compound! {
-            "LevelName" => NbtValue::String("Pickaxe World".into()),
-            "SpawnX" => NbtValue::Int(0),
-            "SpawnY" => NbtValue::Int(-59),
-            "SpawnZ" => NbtValue::Int(0),
-            "Time" => NbtValue::Long(world_state.world_age),
-            "DayTime" => NbtValue::Long(world_state.time_of_day),
-            "GameType" => NbtValue::Int(0),
-            "Difficulty" => NbtValue::Byte(2),
-            "hardcore" => NbtValue::Byte(0),
-            "allowCommands" => NbtValue::Byte(1),
-            "raining" => NbtValue::Byte(world_state.raining as i8),
-            "thundering" => NbtValue::Byte(world_state.thundering as i8),
-            "rainTime" => NbtValue::Int(world_state.rain_time),
-            "thunderTime" => NbtValue::Int(world_state.thunder_time),
-            "clearWeatherTime" => NbtValue::Int(world_state.clear_weather_time),
-            "Version" => nbt_compound! {
-                "Name" => NbtValue::String("1.21.1".into()),
-                "Id" => NbtValue::Int(767)
-            }
-        }
+        "Data" => data
     };
 
     let mut buf = BytesMut::new();
@@ -603,6 +1015,105 @@ fn serialize_level_dat(world_state: &WorldState, _config: &ServerConfig) -> Vec<
     encoder.finish().unwrap_or_default()
 }
 
+/// `saved` with `values`' entries added or replacing its own.
+fn merge_compound(saved: Option<&NbtValue>, values: NbtValue) -> NbtValue {
+    let mut merged = match saved {
+        Some(saved @ NbtValue::Compound(_)) => saved.clone(),
+        _ => NbtValue::Compound(Vec::new()),
+    };
+    if let NbtValue::Compound(entries) = values {
+        for (key, value) in entries {
+            merged.set(&key, value);
+        }
+    }
+    merged
+}
+
+/// level.dat's WorldGenSettings: the seed, and a generator for each level.
+/// Dimensions this server doesn't run are kept as saved.
+fn world_gen_settings(world_state: &WorldState, saved: Option<&NbtValue>) -> NbtValue {
+    let mut settings = merge_compound(saved, nbt_compound! { "seed" => NbtValue::Long(world_state.seed) });
+    for (key, value) in [("generate_features", NbtValue::Byte(1)), ("bonus_chest", NbtValue::Byte(0))] {
+        if settings.get(key).is_none() {
+            settings.set(key, value);
+        }
+    }
+    let mut dimensions = merge_compound(settings.get("dimensions"), NbtValue::Compound(Vec::new()));
+    for level in world_state.all_levels() {
+        let dimension_type = world_state
+            .registries
+            .dimension_types
+            .get(level.dimension_type as usize)
+            .map(|d| d.name.clone())
+            .unwrap_or_else(|| DEFAULT_LEVEL.into());
+        dimensions.set(&level.name, nbt_compound! {
+            "type" => NbtValue::String(dimension_type),
+            "generator" => flat_generator_settings(level.generator)
+        });
+    }
+    settings.set("dimensions", dimensions);
+    settings
+}
+
+/// A generator as vanilla's `minecraft:flat` generator settings: the flat
+/// world's layers, or the void preset.
+fn flat_generator_settings(generator: WorldGenerator) -> NbtValue {
+    let (biome, layers): (&str, &[(&str, i32)]) = match generator {
+        WorldGenerator::Flat => (
+            "minecraft:plains",
+            &[("minecraft:bedrock", 1), ("minecraft:stone", 10), ("minecraft:dirt", 2), ("minecraft:grass_block", 1)],
+        ),
+        WorldGenerator::Void => ("minecraft:the_void", &[("minecraft:air", 1)]),
+    };
+    let layers = layers
+        .iter()
+        .map(|(block, height)| {
+            nbt_compound! {
+                "block" => NbtValue::String((*block).into()),
+                "height" => NbtValue::Int(*height)
+            }
+        })
+        .collect();
+    nbt_compound! {
+        "type" => NbtValue::String("minecraft:flat".into()),
+        "settings" => nbt_compound! {
+            "biome" => NbtValue::String(biome.into()),
+            "features" => NbtValue::Byte(0),
+            "lakes" => NbtValue::Byte(0),
+            "layers" => NbtValue::List(layers),
+            "structure_overrides" => NbtValue::List(Vec::new())
+        }
+    }
+}
+
+/// The generator saved flat generator settings describe, if they're ours.
+fn generator_from_settings(generator: &NbtValue) -> Option<WorldGenerator> {
+    if generator.get("type")?.as_str()? != "minecraft:flat" {
+        return None;
+    }
+    let biome = generator.get("settings")?.get("biome")?.as_str()?;
+    Some(if biome == "minecraft:the_void" { WorldGenerator::Void } else { WorldGenerator::Flat })
+}
+
+/// level.dat's DataPacks: vanilla and every loaded world datapack enabled.
+fn data_packs(world_state: &WorldState, saved: Option<&NbtValue>) -> NbtValue {
+    let enabled: Vec<String> = std::iter::once("vanilla".to_string())
+        .chain(world_state.functions.pack_names().iter().map(|name| format!("file/{}", name)))
+        .collect();
+    let disabled: Vec<NbtValue> = saved
+        .and_then(|p| p.get("Disabled"))
+        .and_then(|v| v.as_list())
+        .unwrap_or(&[])
+        .iter()
+        .filter(|pack| pack.as_str().is_some_and(|name| !enabled.iter().any(|e| e == name)))
+        .cloned()
+        .collect();
+    nbt_compound! {
+        "Enabled" => NbtValue::List(enabled.into_iter().map(NbtValue::String).collect()),
+        "Disabled" => NbtValue::List(disabled)
+    }
+}
+
 /// Deserialized level.dat data.
 struct LevelDatData {
     world_age: i64,
@@ -612,16 +1123,19 @@ struct LevelDatData {
     rain_time: i32,
     thunder_time: i32,
     clear_weather_time: i32,
+    game_rules: GameRules,
+    difficulty: i32,
+    seed: Option<i64>,
+    /// The overworld's generator, if it's one this server has
+    generator: Option<WorldGenerator>,
+    /// The whole Data compound
+    data: NbtValue,
 }
 
 /// Load world state from a gzip-compressed level.dat file.
 fn load_level_dat(path: &std::path::Path) -> Option<LevelDatData> {
-    let data = std::fs::read(path).ok()?;
-    let mut decoder = GzDecoder::new(&data[..]);
-    let mut decompressed = Vec::new();
-    decoder.read_to_end(&mut decompressed).ok()?;
-
-    let (_, nbt) = NbtValue::read_root_named(&decompressed).ok()?;
+    let file = std::io::BufReader::new(std::fs::File::open(path).ok()?);
+    let (_, nbt) = NbtValue::read_root_named_from(GzDecoder::new(file), NbtLimits::FILE).ok()?;
     let data_nbt = nbt.get("Data")?;
     let world_age = data_nbt.get("Time")?.as_long()?;
     let time_of_day = data_nbt.get("DayTime")?.as_long()?;
@@ -630,6 +1144,16 @@ fn load_level_dat(path: &std::path::Path) -> Option<LevelDatData> {
     let rain_time = data_nbt.get("rainTime").and_then(|v| v.as_int()).unwrap_or(0);
     let thunder_time = data_nbt.get("thunderTime").and_then(|v| v.as_int()).unwrap_or(0);
     let clear_weather_time = data_nbt.get("clearWeatherTime").and_then(|v| v.as_int()).unwrap_or(0);
+    let mut game_rules = GameRules::new();
+    if let Some(rules) = data_nbt.get("GameRules") {
+        game_rules.load_nbt(rules);
+    }
+    let difficulty = data_nbt.get("Difficulty").and_then(|v| v.as_byte()).map(|d| d.clamp(0, 3) as i32).unwrap_or(2);
+    let world_gen = data_nbt.get("WorldGenSettings");
+    let seed = world_gen.and_then(|w| w.get("seed")).and_then(|v| v.as_long());
+    let generator = world_gen
+        .and_then(|w| w.get("dimensions")?.get(DEFAULT_LEVEL)?.get("generator"))
+        .and_then(generator_from_settings);
     Some(LevelDatData {
         world_age,
         time_of_day,
@@ -638,61 +1162,253 @@ fn load_level_dat(path: &std::path::Path) -> Option<LevelDatData> {
         rain_time,
         thunder_time,
         clear_weather_time,
+        game_rules,
+        difficulty,
+        seed,
+        generator,
+        data: data_nbt.clone(),
+    })
+}
+
+/// A non-default level's `level.dat`: what it was created with, so
+/// changing `[[worlds]]` later can't mix two generators in one world.
+fn serialize_dimension_level_dat(level: &Level, dimension_type: &str) -> Vec<u8> {
+    let nbt = nbt_compound! {
+        "DataVersion" => NbtValue::Int(3955),
+        "Data" => nbt_compound! {
+            "LevelName" => NbtValue::String(level.name.clone()),
+            "DimensionType" => NbtValue::String(dimension_type.into()),
+            "Generator" => NbtValue::String(level.generator.name().into()),
+            "SpawnX" => NbtValue::Double(level.spawn.x),
+            "SpawnY" => NbtValue::Double(level.spawn.y),
+            "SpawnZ" => NbtValue::Double(level.spawn.z)
+        }
+    };
+
+    let mut buf = BytesMut::new();
+    nbt.write_root_named("", &mut buf);
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    let _ = encoder.write_all(&buf);
+    encoder.finish().unwrap_or_default()
+}
+
+/// The generator a non-default level's `level.dat` says it was created with.
+fn load_dimension_generator(path: &std::path::Path) -> Option<WorldGenerator> {
+    let file = std::io::BufReader::new(std::fs::File::open(path).ok()?);
+    let (_, nbt) = NbtValue::read_root_named_from(GzDecoder::new(file), NbtLimits::FILE).ok()?;
+    WorldGenerator::from_name(nbt.get("Data")?.get("Generator")?.as_str()?)
+}
+
+/// A chunk as it was when queued for saving. The saver task encodes it, so
+/// the tick loop only pays for the copy.
+pub struct ChunkSnapshot {
+    level: String,
+    pos: ChunkPos,
+    chunk: Chunk,
+    block_entities: Vec<(BlockPos, BlockEntity)>,
+    /// Points of interest, for the level's `poi/` region files
+    poi: Option<ChunkPoi>,
+    world_age: i64,
+}
+
+impl ChunkSnapshot {
+    /// The chunk's NBT for the journal and region files, and its POI NBT.
+    fn encode(&self) -> (JournalEntry, Option<Vec<u8>>) {
+        let mut nbt = self.chunk.to_nbt(self.pos.x, self.pos.z, self.world_age);
+        let be_list = self.block_entities.iter().map(|(pos, be)| serialize_block_entity(pos, be)).collect();
+        if let NbtValue::Compound(ref mut entries) = nbt {
+            entries.push(("block_entities".into(), NbtValue::List(be_list)));
+        }
+        let mut buf = BytesMut::new();
+        nbt.write_root_named("", &mut buf);
+        let entry = JournalEntry { level: self.level.clone(), chunk_x: self.pos.x, chunk_z: self.pos.z, data: buf.to_vec() };
+        let poi = self.poi.as_ref().map(|poi| {
+            let mut buf = BytesMut::new();
+            poi.to_nbt().write_root_named("", &mut buf);
+            buf.to_vec()
+        });
+        (entry, poi)
+    }
+}
+
+/// Encode a batch of chunk snapshots, split across the machine's cores when
+/// there is more than one.
+fn encode_chunks(batch: &[ChunkSnapshot]) -> Vec<(JournalEntry, Option<Vec<u8>>)> {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get()).min(batch.len());
+    if threads <= 1 {
+        return batch.iter().map(ChunkSnapshot::encode).collect();
+    }
+    let per_thread = batch.len().div_ceil(threads);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = batch
+            .chunks(per_thread)
+            .map(|part| scope.spawn(move || part.iter().map(ChunkSnapshot::encode).collect::<Vec<_>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+            .collect()
     })
 }
 
 /// Operations queued for the background saver task.
 pub enum SaveOp {
-    Chunk(i32, i32, Vec<u8>),
-    Player(uuid::Uuid, Vec<u8>),
+    /// A chunk and its points of interest, encoded by the saver.
+    Chunk(Box<ChunkSnapshot>),
+    /// Flush the player's data staged in `PlayerDataStore`.
+    Player(uuid::Uuid),
     LevelDat(Vec<u8>),
+    /// Gzipped `level.dat` in a non-default level's dimension folder.
+    DimensionLevelDat(String, Vec<u8>),
+    /// Gzipped `data/scoreboard.dat`.
+    Scoreboard(Vec<u8>),
+    /// Gzipped `data/warps.dat`.
+    Warps(Vec<u8>),
+    /// A player's `stats/<uuid>.json`.
+    Stats(uuid::Uuid, Vec<u8>),
+    Advancements(uuid::Uuid, Vec<u8>),
+    /// A `pickaxe.storage` namespace: file name in `data/mods/` and JSON contents.
+    ModStorage(String, Vec<u8>),
+    /// Rewrite the named levels' region files without their unused sectors.
+    Compact(Vec<String>),
+    /// Snapshot the world for a backup, replying with where it was written.
+    Backup(BackupConfig, tokio::sync::oneshot::Sender<Result<PathBuf, String>>),
+    /// Reply once every earlier op has been written.
+    Flush(tokio::sync::oneshot::Sender<()>),
     Shutdown(tokio::sync::oneshot::Sender<()>),
 }
 
+/// Journal size at which the saver syncs the region files and clears it.
+const JOURNAL_CHECKPOINT_BYTES: u64 = 16 * 1024 * 1024;
+
 /// Runs on a background Tokio blocking task. Processes SaveOps sequentially.
+///
+/// Chunks go through `journal` first: every chunk op already queued is
+/// appended and synced in one batch, then written to the region files. If
+/// the journal can't be written, each chunk is synced as it's written instead.
 pub fn run_saver_task(
     mut rx: mpsc::UnboundedReceiver<SaveOp>,
     world_dir: PathBuf,
+    player_data: Arc<PlayerDataStore>,
+    compression: ChunkCompression,
+    mut journal: ChunkJournal,
 ) {
-    let region_dir = world_dir.join("region");
-    let playerdata_dir = world_dir.join("playerdata");
-    let _ = std::fs::create_dir_all(&region_dir);
-    let _ = std::fs::create_dir_all(&playerdata_dir);
+    // Each level's region files, opened when first needed
+    let mut region_storages: HashMap<String, LevelStorage> = HashMap::new();
+    // An op taken off the queue while batching chunks
+    let mut pending: Option<SaveOp> = None;
 
-    let mut region_storage = match RegionStorage::new(region_dir) {
-        Ok(s) => s,
-        Err(e) => {
-            tracing::error!("Failed to init region storage: {}", e);
-            return;
+    while let Some(op) = pending.take().or_else(|| rx.blocking_recv()) {
+        if let Ok(mut stats) = crate::save_stats::SAVER.lock() {
+            stats.queue_depth = rx.len();
+            stats.ops += 1;
         }
-    };
-
-    while let Some(op) = rx.blocking_recv() {
         match op {
-            SaveOp::Chunk(cx, cz, data) => {
-                if let Err(e) = region_storage.write_chunk(cx, cz, &data) {
-                    tracing::error!("Failed to save chunk ({}, {}): {}", cx, cz, e);
+            SaveOp::Chunk(snapshot) => {
+                let started = Instant::now();
+                let mut snapshots = vec![*snapshot];
+                while let Ok(op) = rx.try_recv() {
+                    match op {
+                        SaveOp::Chunk(snapshot) => snapshots.push(*snapshot),
+                        op => {
+                            pending = Some(op);
+                            break;
+                        }
+                    }
                 }
-            }
-            SaveOp::Player(uuid, data) => {
-                let path = playerdata_dir.join(format!("{}.dat", uuid));
-                let tmp_path = playerdata_dir.join(format!("{}.dat.tmp", uuid));
-                if let Err(e) = std::fs::write(&tmp_path, &data) {
-                    tracing::error!("Failed to write player data {}: {}", uuid, e);
-                } else if let Err(e) = std::fs::rename(&tmp_path, &path) {
-                    tracing::error!("Failed to rename player data {}: {}", uuid, e);
+                let (batch, pois): (Vec<JournalEntry>, Vec<Option<Vec<u8>>>) = encode_chunks(&snapshots).into_iter().unzip();
+                let journaled = append_to_journal(&mut journal, &batch);
+                if !journaled {
+                    tracing::error!("Writing {} chunks without the journal, syncing each one", batch.len());
                 }
+                for (entry, poi) in batch.iter().zip(pois) {
+                    let Some(storage) = level_storage(&mut region_storages, &world_dir, &entry.level, compression) else {
+                        continue;
+                    };
+                    if let Err(e) = storage.chunks.write_chunk(entry.chunk_x, entry.chunk_z, &entry.data) {
+                        tracing::error!("Failed to save chunk ({}, {}) in {}: {}", entry.chunk_x, entry.chunk_z, entry.level, e);
+                    }
+                    let Some(poi) = poi else {
+                        continue;
+                    };
+                    if let Err(e) = storage.poi.write_chunk(entry.chunk_x, entry.chunk_z, &poi) {
+                        tracing::error!("Failed to save POI of chunk ({}, {}) in {}: {}", entry.chunk_x, entry.chunk_z, entry.level, e);
+                    }
+                    // Nothing can repair a torn write, so keep it to one chunk
+                    if !journaled {
+                        if let Err(e) = storage.sync() {
+                            tracing::error!("Failed to sync region files of {}: {}", entry.level, e);
+                        }
+                    }
+                }
+                if journal.len() >= JOURNAL_CHECKPOINT_BYTES {
+                    checkpoint_journal(&mut journal, &region_storages);
+                }
+                if let Ok(mut stats) = crate::save_stats::SAVER.lock() {
+                    stats.record_flush(batch.len(), started.elapsed());
+                }
+            }
+            SaveOp::Player(uuid) => {
+                player_data.flush(&uuid);
             }
             SaveOp::LevelDat(data) => {
-                let path = world_dir.join("level.dat");
-                let tmp_path = world_dir.join("level.dat.tmp");
-                if let Err(e) = std::fs::write(&tmp_path, &data) {
+                if let Err(e) = write_atomic(&world_dir.join("level.dat"), &data) {
                     tracing::error!("Failed to write level.dat: {}", e);
-                } else if let Err(e) = std::fs::rename(&tmp_path, &path) {
-                    tracing::error!("Failed to rename level.dat: {}", e);
                 }
             }
+            SaveOp::DimensionLevelDat(level, data) => {
+                let Some(dir) = pickaxe_region::dimension_dir(&world_dir, &level) else {
+                    continue;
+                };
+                if let Err(e) = std::fs::create_dir_all(&dir).and_then(|_| write_atomic(&dir.join("level.dat"), &data)) {
+                    tracing::error!("Failed to write level.dat for {}: {}", level, e);
+                }
+            }
+            SaveOp::Scoreboard(data) => write_data_file(&world_dir, "scoreboard.dat", &data),
+            SaveOp::Warps(data) => write_data_file(&world_dir, "warps.dat", &data),
+            SaveOp::Stats(uuid, data) => write_player_json(&world_dir, crate::stats::STATS_DIR, &uuid, &data),
+            SaveOp::Advancements(uuid, data) => {
+                write_player_json(&world_dir, crate::advancements::ADVANCEMENTS_DIR, &uuid, &data)
+            }
+            SaveOp::ModStorage(name, data) => {
+                write_data_file(&world_dir, &format!("{}/{}", crate::storage::STORAGE_DIR, name), &data)
+            }
+            SaveOp::Compact(levels) => {
+                let mut reclaimed = 0;
+                for level in levels {
+                    let compacted = level_storage(&mut region_storages, &world_dir, &level, compression)
+                        .map(|storage| -> std::io::Result<u64> { Ok(storage.chunks.compact()? + storage.poi.compact()?) });
+                    match compacted {
+                        Some(Ok(bytes)) => reclaimed += bytes,
+                        Some(Err(e)) => tracing::error!("Failed to compact region files of {}: {}", level, e),
+                        None => {}
+                    }
+                }
+                tracing::info!("Compacted region files, reclaimed {} KiB", reclaimed / 1024);
+            }
+            SaveOp::Backup(config, done) => match crate::backup::snapshot(&world_dir, &config) {
+                Ok(staging) => {
+                    // Archiving takes a while; saves carry on meanwhile
+                    let world_name = world_dir
+                        .file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_else(|| "world".into());
+                    std::thread::spawn(move || {
+                        let result = crate::backup::finish(&staging, &world_name, &config);
+                        let _ = done.send(result.map_err(|e| e.to_string()));
+                    });
+                }
+                Err(e) => {
+                    let _ = done.send(Err(e.to_string()));
+                }
+            },
+            SaveOp::Flush(done) => {
+                checkpoint_journal(&mut journal, &region_storages);
+                let _ = done.send(());
+            }
             SaveOp::Shutdown(done) => {
+                checkpoint_journal(&mut journal, &region_storages);
                 tracing::info!("Saver task shutting down");
                 let _ = done.send(());
                 return;
@@ -701,30 +1417,154 @@ pub fn run_saver_task(
     }
 }
 
-/// Block entity data for container blocks.
-#[derive(Debug, Clone)]
-pub enum BlockEntity {
-    Chest {
-        inventory: [Option<ItemStack>; 27],
-    },
-    Furnace {
-        input: Option<ItemStack>,
-        fuel: Option<ItemStack>,
-        output: Option<ItemStack>,
-        burn_time: i16,
-        burn_duration: i16,
-        cook_progress: i16,
-        cook_total: i16,
-    },
-    BrewingStand {
-        /// Slots 0-2: potion bottles (input/output)
-        bottles: [Option<ItemStack>; 3],
-        /// Slot 3: ingredient/reagent
-        ingredient: Option<ItemStack>,
-        /// Slot 4: fuel (blaze powder)
-        fuel: Option<ItemStack>,
-        /// Ticks remaining in current brew (0 = not brewing, counts down from 400)
-        brew_time: i16,
+/// Append a batch of chunks to the journal. An append that fails is cut off
+/// and tried once more; returns false if that fails too.
+fn append_to_journal(journal: &mut ChunkJournal, batch: &[JournalEntry]) -> bool {
+    for _ in 0..2 {
+        let Err(e) = journal.append(batch) else {
+            return true;
+        };
+        tracing::error!("Failed to journal {} chunks: {}", batch.len(), e);
+        if let Err(e) = journal.rewind() {
+            tracing::error!("Failed to truncate {}: {}", journal.path().display(), e);
+            return false;
+        }
+    }
+    false
+}
+
+/// Sync the region files and clear the journal. If a sync fails the journal
+/// is kept, so its chunks are written again on the next start.
+fn checkpoint_journal(journal: &mut ChunkJournal, region_storages: &HashMap<String, LevelStorage>) {
+    if journal.is_empty() {
+        return;
+    }
+    for (level, storage) in region_storages {
+        if let Err(e) = storage.sync() {
+            tracing::error!("Failed to sync region files of {}: {}", level, e);
+            return;
+        }
+    }
+    if let Err(e) = journal.clear() {
+        tracing::error!("Failed to clear {}: {}", journal.path().display(), e);
+    }
+}
+
+/// Write the chunks a crash left in the world's journal to their region
+/// files, then open the journal for the saver.
+pub fn replay_chunk_journal(world_dir: &std::path::Path, compression: ChunkCompression) -> std::io::Result<ChunkJournal> {
+    let (mut journal, entries) = ChunkJournal::open(&world_dir.join(pickaxe_region::JOURNAL_FILE))?;
+    if entries.is_empty() {
+        return Ok(journal);
+    }
+    let mut region_storages = HashMap::new();
+    for entry in &entries {
+        if let Some(storage) = level_storage(&mut region_storages, world_dir, &entry.level, compression) {
+            storage.chunks.write_chunk(entry.chunk_x, entry.chunk_z, &entry.data)?;
+        }
+    }
+    for storage in region_storages.values() {
+        storage.sync()?;
+    }
+    journal.clear()?;
+    info!("Replayed {} chunk writes from {}", entries.len(), journal.path().display());
+    Ok(journal)
+}
+
+/// A level's region files: its chunks and their points of interest.
+pub struct LevelStorage {
+    pub chunks: RegionStorage,
+    pub poi: RegionStorage,
+}
+
+impl LevelStorage {
+    /// Sync both the chunk and POI region files. POI writes aren't journaled,
+    /// so this is what keeps them in step with the chunks at a checkpoint.
+    pub fn sync(&self) -> std::io::Result<()> {
+        self.chunks.sync()?;
+        self.poi.sync()
+    }
+}
+
+/// Region storage for a level's dimension folder, creating its region,
+/// entities and poi folders.
+pub fn open_level_storage(world_dir: &std::path::Path, level: &str, compression: ChunkCompression) -> std::io::Result<LevelStorage> {
+    let dir = pickaxe_region::dimension_dir(world_dir, level)
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("Invalid dimension name {}", level)))?;
+    let mut chunks = RegionStorage::new(pickaxe_region::create_dimension_dirs(&dir)?)?;
+    chunks.set_compression(compression);
+    let mut poi = RegionStorage::new(dir.join(pickaxe_region::POI_DIR))?;
+    poi.set_compression(compression);
+    Ok(LevelStorage { chunks, poi })
+}
+
+/// The saver's storage for a level, opened on first use.
+fn level_storage<'a>(
+    storages: &'a mut HashMap<String, LevelStorage>,
+    world_dir: &std::path::Path,
+    level: &str,
+    compression: ChunkCompression,
+) -> Option<&'a mut LevelStorage> {
+    if !storages.contains_key(level) {
+        match open_level_storage(world_dir, level, compression) {
+            Ok(storage) => {
+                storages.insert(level.to_string(), storage);
+            }
+            Err(e) => {
+                tracing::error!("Failed to init region storage for {}: {}", level, e);
+                return None;
+            }
+        }
+    }
+    storages.get_mut(level)
+}
+
+/// Atomically write a file in the world's `data` directory (`name` may
+/// include a subdirectory).
+fn write_data_file(world_dir: &std::path::Path, name: &str, data: &[u8]) {
+    let path = world_dir.join("data").join(name);
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Err(e) = write_atomic(&path, data) {
+        tracing::error!("Failed to write {}: {}", name, e);
+    }
+}
+
+/// Atomically write a player's `<dir>/<uuid>.json` in the world.
+fn write_player_json(world_dir: &std::path::Path, dir: &str, uuid: &Uuid, data: &[u8]) {
+    let dir = world_dir.join(dir);
+    let _ = std::fs::create_dir_all(&dir);
+    let path = dir.join(format!("{}.json", uuid));
+    if let Err(e) = write_atomic(&path, data) {
+        tracing::error!("Failed to write {}: {}", path.display(), e);
+    }
+}
+
+/// Block entity data for container blocks.
+#[derive(Debug, Clone)]
+pub enum BlockEntity {
+    Chest {
+        inventory: [Option<ItemStack>; 27],
+    },
+    Furnace {
+        input: Option<ItemStack>,
+        fuel: Option<ItemStack>,
+        output: Option<ItemStack>,
+        burn_time: i16,
+        burn_duration: i16,
+        cook_progress: i16,
+        cook_total: i16,
+    },
+    BrewingStand {
+        /// Slots 0-2: potion bottles (input/output)
+        bottles: [Option<ItemStack>; 3],
+        /// Slot 3: ingredient/reagent
+        ingredient: Option<ItemStack>,
+        /// Slot 4: fuel (blaze powder)
+        fuel: Option<ItemStack>,
+        /// Ticks remaining in current brew (0 = not brewing, counts down from 400)
+        brew_time: i16,
         /// Fuel uses remaining (0-20, each blaze powder = 20)
         fuel_uses: i16,
     },
@@ -740,160 +1580,318 @@ pub enum BlockEntity {
         /// Whether the sign is waxed (prevents editing)
         is_waxed: bool,
     },
+    Bell {
+        /// Ticks left in the current ring animation (0 = still)
+        ringing_ticks: i32,
+    },
+    Lectern {
+        /// The writable or written book on the lectern
+        book: Option<ItemStack>,
+        /// Page the book is open to
+        page: i32,
+    },
+    Campfire {
+        /// The four cooking slots, one per side of the fire
+        items: [Option<ItemStack>; 4],
+        /// Ticks each slot has cooked so far
+        cook_times: [i32; 4],
+        /// Ticks each slot needs in total
+        cook_totals: [i32; 4],
+    },
 }
 
-/// World state: chunk storage.
-pub struct WorldState {
+impl BlockEntity {
+    /// A container slot, numbered as in the container's menu: chest 0-26;
+    /// furnace 0 input, 1 fuel, 2 output; brewing stand 0-2 bottles,
+    /// 3 ingredient, 4 fuel.
+    pub fn slot(&self, slot: usize) -> Option<&Option<ItemStack>> {
+        match self {
+            BlockEntity::Chest { inventory } => inventory.get(slot),
+            BlockEntity::Furnace { input, fuel, output, .. } => [input, fuel, output].get(slot).copied(),
+            BlockEntity::BrewingStand { bottles, ingredient, fuel, .. } => match slot {
+                0..=2 => Some(&bottles[slot]),
+                3 => Some(ingredient),
+                4 => Some(fuel),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Mutable access to a container slot; see `slot`.
+    pub fn slot_mut(&mut self, slot: usize) -> Option<&mut Option<ItemStack>> {
+        match self {
+            BlockEntity::Chest { inventory } => inventory.get_mut(slot),
+            BlockEntity::Furnace { input, fuel, output, .. } => [input, fuel, output].into_iter().nth(slot),
+            BlockEntity::BrewingStand { bottles, ingredient, fuel, .. } => match slot {
+                0..=2 => Some(&mut bottles[slot]),
+                3 => Some(ingredient),
+                4 => Some(fuel),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Rolling tick timings, for /worldstats, /tps and overload shedding.
+pub struct TickStats {
+    /// The last `HISTORY` tick times, newest last.
+    recent: VecDeque<Duration>,
+    /// How long a tick should take at the current tick rate.
+    target: Duration,
+}
+
+impl TickStats {
+    /// Number of ticks the default averages cover (5 seconds).
+    const WINDOW: usize = 100;
+    /// Number of ticks kept, for the longest average (5 minutes).
+    const HISTORY: usize = 6000;
+
+    pub fn new() -> Self {
+        Self {
+            recent: VecDeque::with_capacity(Self::HISTORY),
+            target: Duration::from_millis(50),
+        }
+    }
+
+    pub fn set_target(&mut self, target: Duration) {
+        self.target = target;
+    }
+
+    /// The tick budget in milliseconds.
+    pub fn target_mspt(&self) -> f64 {
+        self.target.as_secs_f64() * 1000.0
+    }
+
+    pub fn record(&mut self, elapsed: Duration) {
+        if self.recent.len() == Self::HISTORY {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(elapsed);
+    }
+
+    /// Mean milliseconds per tick over the window.
+    pub fn average_mspt(&self) -> f64 {
+        self.average_mspt_over(Self::WINDOW)
+    }
+
+    /// Mean milliseconds per tick over the last `ticks` ticks.
+    pub fn average_mspt_over(&self, ticks: usize) -> f64 {
+        let count = self.recent.len().min(ticks);
+        if count == 0 {
+            return 0.0;
+        }
+        self.recent.iter().rev().take(count).map(|d| d.as_secs_f64() * 1000.0).sum::<f64>() / count as f64
+    }
+
+    /// Slowest tick in the window, in milliseconds.
+    pub fn max_mspt(&self) -> f64 {
+        self.recent.iter().rev().take(Self::WINDOW).map(|d| d.as_secs_f64() * 1000.0).fold(0.0, f64::max)
+    }
+
+    /// Effective ticks per second, capped at the tick rate.
+    pub fn tps(&self) -> f64 {
+        self.tps_over(Self::WINDOW)
+    }
+
+    /// Effective ticks per second over the last `ticks` ticks.
+    pub fn tps_over(&self, ticks: usize) -> f64 {
+        let mspt = self.average_mspt_over(ticks).max(self.target_mspt());
+        if mspt > 0.0 { 1000.0 / mspt } else { 0.0 }
+    }
+}
+
+/// The dimension players join, whose level.dat holds the world's time,
+/// weather and game rules.
+pub const DEFAULT_LEVEL: &str = "minecraft:overworld";
+
+/// One world's blocks, stored in its own dimension folder (see
+/// `pickaxe_region::dimension_dir`). The level being worked on is
+/// `WorldState::level`; `WorldState::switch_level` swaps another in.
+pub struct Level {
+    /// Dimension name, like `minecraft:overworld`.
+    pub name: String,
+    /// Network ID of its dimension type in the registries.
+    pub dimension_type: i32,
+    /// Where players arrive with `/world tp`.
+    pub spawn: Vec3d,
+    pub generator: WorldGenerator,
     chunks: HashMap<ChunkPos, Chunk>,
-    pub world_age: i64,
-    pub time_of_day: i64,
-    pub tick_count: u64,
-    region_storage: RegionStorage,
-    pub save_tx: mpsc::UnboundedSender<SaveOp>,
+    /// When each loaded chunk was last queued for saving
+    chunk_saved_at: HashMap<ChunkPos, Instant>,
+    storage: LevelStorage,
+    /// Points of interest in each loaded chunk, kept up to date as blocks change
+    poi: HashMap<ChunkPos, ChunkPoi>,
     pub block_entities: HashMap<BlockPos, BlockEntity>,
-    pub next_eid: Arc<AtomicI32>,
-    // Gamerules
-    pub keep_inventory: bool,
-    pub natural_regeneration: bool,
-    pub difficulty: i32, // 0=peaceful, 1=easy, 2=normal, 3=hard
-    // Weather state
-    pub raining: bool,
-    pub thundering: bool,
-    pub rain_time: i32,      // ticks until rain toggles
-    pub thunder_time: i32,   // ticks until thunder toggles
-    pub clear_weather_time: i32,
-    pub rain_level: f32,     // 0.0-1.0, gradual transition
-    pub thunder_level: f32,  // 0.0-1.0, gradual transition
+    /// Queued (notified_pos, changed_pos) pairs, drained by block_behavior each tick
+    pub pending_neighbor_updates: VecDeque<(BlockPos, BlockPos)>,
+    /// Block ticks scheduled for later (pos → due tick), run by block_behavior
+    pub scheduled_block_ticks: HashMap<BlockPos, u64>,
+    /// Chunks changed since they were last queued for saving
+    dirty_chunks: HashSet<ChunkPos>,
+    /// Dirty chunks waiting their turn to be written, a few per tick
+    save_queue: VecDeque<ChunkPos>,
+    /// Chunks loaded since the last `fire_chunk_load_events`, and whether each was newly generated
+    loaded_chunks: Vec<(ChunkPos, bool)>,
 }
 
-impl WorldState {
-    pub fn new(region_storage: RegionStorage, save_tx: mpsc::UnboundedSender<SaveOp>, next_eid: Arc<AtomicI32>) -> Self {
+impl Level {
+    pub fn new(name: &str, dimension_type: i32, spawn: Vec3d, generator: WorldGenerator, storage: LevelStorage) -> Self {
         Self {
+            name: name.to_string(),
+            dimension_type,
+            spawn,
+            generator,
             chunks: HashMap::new(),
-            world_age: 0,
-            time_of_day: 0,
-            tick_count: 0,
-            region_storage,
-            save_tx,
+            chunk_saved_at: HashMap::new(),
+            storage,
+            poi: HashMap::new(),
             block_entities: HashMap::new(),
-            next_eid,
-            keep_inventory: false,
-            natural_regeneration: true,
-            difficulty: 2, // normal
-            raining: false,
-            thundering: false,
-            rain_time: 12000 + rand::random::<i32>().unsigned_abs() as i32 % 168000,
-            thunder_time: 12000 + rand::random::<i32>().unsigned_abs() as i32 % 168000,
-            clear_weather_time: 0,
-            rain_level: 0.0,
-            thunder_level: 0.0,
+            pending_neighbor_updates: VecDeque::new(),
+            scheduled_block_ticks: HashMap::new(),
+            dirty_chunks: HashSet::new(),
+            save_queue: VecDeque::new(),
+            loaded_chunks: Vec::new(),
         }
     }
 
     /// Ensures a chunk is loaded (from disk or generated) and returns a mutable reference.
     fn ensure_chunk(&mut self, pos: ChunkPos) -> &mut Chunk {
         if !self.chunks.contains_key(&pos) {
-            // Try loading from disk
-            if let Ok(Some(nbt_bytes)) = self.region_storage.read_chunk(pos.x, pos.z) {
-                if let Ok((_, nbt)) = NbtValue::read_root_named(&nbt_bytes) {
-                    if let Some(chunk) = Chunk::from_nbt(&nbt) {
-                        // Load block entities from chunk NBT
-                        if let Some(be_list) = nbt.get("block_entities").and_then(|v| v.as_list()) {
-                            for be_nbt in be_list {
-                                if let Some((be_pos, be)) = deserialize_block_entity(be_nbt) {
-                                    self.block_entities.insert(be_pos, be);
+            // Try loading from disk; a chunk that can't be read is regenerated
+            let nbt_bytes = match self.storage.chunks.read_chunk(pos.x, pos.z) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    tracing::warn!("Failed to read chunk ({}, {}) in {}: {}", pos.x, pos.z, self.name, e);
+                    None
+                }
+            };
+            if let Some(nbt_bytes) = nbt_bytes {
+                match NbtValue::read_root_named(&nbt_bytes) {
+                    Ok((_, nbt)) => {
+                        if let Some(chunk) = Chunk::from_nbt(&nbt) {
+                            // Load block entities from chunk NBT
+                            if let Some(be_list) = nbt.get("block_entities").and_then(|v| v.as_list()) {
+                                for be_nbt in be_list {
+                                    if let Some((be_pos, be)) = deserialize_block_entity(be_nbt) {
+                                        self.block_entities.insert(be_pos, be);
+                                    }
                                 }
                             }
+                            let poi = self.read_poi(pos).unwrap_or_else(|| ChunkPoi::scan(&chunk, pos.x, pos.z));
+                            self.poi.insert(pos, poi);
+                            self.chunks.insert(pos, chunk);
+                            self.loaded_chunks.push((pos, false));
+                            return self.chunks.get_mut(&pos).unwrap();
                         }
-                        self.chunks.insert(pos, chunk);
-                        return self.chunks.get_mut(&pos).unwrap();
                     }
+                    Err(e) => tracing::warn!("Chunk ({}, {}) in {} has malformed NBT: {}", pos.x, pos.z, self.name, e),
                 }
             }
-            // Generate with ore distribution based on chunk coordinates
-            let chunk = generate_flat_chunk_at(pos.x, pos.z);
+            let chunk = match self.generator {
+                // Generate with ore distribution based on chunk coordinates
+                WorldGenerator::Flat => generate_flat_chunk_at(pos.x, pos.z),
+                WorldGenerator::Void => generate_void_chunk_at(pos, self.spawn),
+            };
+            self.poi.insert(pos, ChunkPoi::scan(&chunk, pos.x, pos.z));
             self.chunks.insert(pos, chunk);
+            self.loaded_chunks.push((pos, true));
+            self.dirty_chunks.insert(pos);
         }
         self.chunks.get_mut(&pos).unwrap()
     }
 
-    /// Queue a chunk for background saving.
-    fn queue_chunk_save(&self, pos: ChunkPos) {
+    /// A chunk's saved points of interest. `None` if there are none on disk
+    /// or they can't be trusted, so the chunk is scanned instead.
+    fn read_poi(&mut self, pos: ChunkPos) -> Option<ChunkPoi> {
+        let bytes = match self.storage.poi.read_chunk(pos.x, pos.z) {
+            Ok(bytes) => bytes?,
+            Err(e) => {
+                tracing::warn!("Failed to read POI of chunk ({}, {}) in {}: {}", pos.x, pos.z, self.name, e);
+                return None;
+            }
+        };
+        let (_, nbt) = NbtValue::read_root_named(&bytes).ok()?;
+        ChunkPoi::from_nbt(&nbt)
+    }
+
+    /// Send a copy of a chunk to the saver task, regardless of /save-off.
+    fn write_chunk(&mut self, pos: ChunkPos, world_age: i64, save_tx: &mpsc::UnboundedSender<SaveOp>) {
+        self.dirty_chunks.remove(&pos);
         if let Some(chunk) = self.chunks.get(&pos) {
-            let mut nbt = chunk.to_nbt(pos.x, pos.z, self.world_age);
-            // Inject block entities for this chunk
             let chunk_min_x = pos.x * 16;
             let chunk_min_z = pos.z * 16;
-            let mut be_list = Vec::new();
-            for (be_pos, be) in &self.block_entities {
-                if be_pos.x >= chunk_min_x && be_pos.x < chunk_min_x + 16
-                    && be_pos.z >= chunk_min_z && be_pos.z < chunk_min_z + 16
-                {
-                    be_list.push(serialize_block_entity(be_pos, be));
-                }
-            }
-            if let NbtValue::Compound(ref mut entries) = nbt {
-                entries.push(("block_entities".into(), NbtValue::List(be_list)));
-            }
-            let mut buf = BytesMut::new();
-            nbt.write_root_named("", &mut buf);
-            let _ = self.save_tx.send(SaveOp::Chunk(pos.x, pos.z, buf.to_vec()));
+            let block_entities = self
+                .block_entities
+                .iter()
+                .filter(|(be_pos, _)| {
+                    be_pos.x >= chunk_min_x && be_pos.x < chunk_min_x + 16
+                        && be_pos.z >= chunk_min_z && be_pos.z < chunk_min_z + 16
+                })
+                .map(|(be_pos, be)| (*be_pos, be.clone()))
+                .collect();
+            let snapshot = ChunkSnapshot {
+                level: self.name.clone(),
+                pos,
+                chunk: chunk.clone(),
+                block_entities,
+                poi: self.poi.get(&pos).cloned(),
+                world_age,
+            };
+            let _ = save_tx.send(SaveOp::Chunk(Box::new(snapshot)));
+            self.chunk_saved_at.insert(pos, Instant::now());
         }
     }
 
-    pub fn get_chunk_packet(&mut self, chunk_x: i32, chunk_z: i32) -> InternalPacket {
-        let pos = ChunkPos::new(chunk_x, chunk_z);
-        self.ensure_chunk(pos);
-        self.chunks.get(&pos).unwrap().to_packet(chunk_x, chunk_z)
-    }
-
-    pub fn set_block(&mut self, pos: &BlockPos, state_id: i32) -> i32 {
-        let chunk_pos = pos.chunk_pos();
-        let local_x = (pos.x.rem_euclid(16)) as usize;
-        let local_z = (pos.z.rem_euclid(16)) as usize;
-        self.ensure_chunk(chunk_pos);
-        let chunk = self.chunks.get_mut(&chunk_pos).unwrap();
-        let old = chunk.set_block(local_x, pos.y, local_z, state_id);
-        self.queue_chunk_save(chunk_pos);
-        old
-    }
-
-    pub fn get_block(&mut self, pos: &BlockPos) -> i32 {
-        let chunk_pos = pos.chunk_pos();
-        let local_x = (pos.x.rem_euclid(16)) as usize;
-        let local_z = (pos.z.rem_euclid(16)) as usize;
-        self.ensure_chunk(chunk_pos);
-        self.chunks.get(&chunk_pos).unwrap().get_block(local_x, pos.y, local_z)
-    }
-
-    /// Returns a block state only if the chunk is already loaded (no disk I/O).
-    pub fn get_block_if_loaded(&self, pos: &BlockPos) -> Option<i32> {
-        let chunk_pos = pos.chunk_pos();
-        let local_x = (pos.x.rem_euclid(16)) as usize;
-        let local_z = (pos.z.rem_euclid(16)) as usize;
-        self.chunks.get(&chunk_pos).map(|c| c.get_block(local_x, pos.y, local_z))
-    }
-
-    pub fn get_block_entity(&self, pos: &BlockPos) -> Option<&BlockEntity> {
-        self.block_entities.get(pos)
+    /// Queue every changed chunk for saving.
+    fn flush_dirty_chunks(&mut self, world_age: i64, save_tx: &mpsc::UnboundedSender<SaveOp>) {
+        self.save_queue.clear();
+        for pos in std::mem::take(&mut self.dirty_chunks) {
+            self.write_chunk(pos, world_age, save_tx);
+        }
     }
 
-    pub fn get_block_entity_mut(&mut self, pos: &BlockPos) -> Option<&mut BlockEntity> {
-        self.block_entities.get_mut(pos)
+    /// Line up every changed chunk to be written by `write_queued_chunks`,
+    /// behind those already waiting. Chunks changed after this wait for the
+    /// next call.
+    fn queue_dirty_chunks(&mut self) {
+        let queued: HashSet<ChunkPos> = self.save_queue.iter().copied().collect();
+        let mut new: Vec<ChunkPos> = self.dirty_chunks.iter().filter(|pos| !queued.contains(pos)).copied().collect();
+        new.sort_by_key(|pos| (pos.x, pos.z));
+        self.save_queue.extend(new);
     }
 
-    pub fn set_block_entity(&mut self, pos: BlockPos, entity: BlockEntity) {
-        self.block_entities.insert(pos, entity);
+    /// Write up to `limit` queued chunks. Returns how many were written.
+    fn write_queued_chunks(&mut self, limit: usize, world_age: i64, save_tx: &mpsc::UnboundedSender<SaveOp>) -> usize {
+        let mut written = 0;
+        while written < limit {
+            let Some(pos) = self.save_queue.pop_front() else {
+                break;
+            };
+            // Unloading or a full flush may have written it already
+            if self.dirty_chunks.contains(&pos) {
+                self.write_chunk(pos, world_age, save_tx);
+                written += 1;
+            }
+        }
+        written
     }
 
-    pub fn remove_block_entity(&mut self, pos: &BlockPos) -> Option<BlockEntity> {
-        self.block_entities.remove(pos)
+    pub fn loaded_chunk_count(&self) -> usize {
+        self.chunks.len()
     }
 
     /// Unload chunks that are not within any player's view distance.
-    /// Saves chunks to disk before removing them from memory.
+    /// Saves changed chunks to disk before removing them from memory.
     /// Also removes block entities belonging to unloaded chunks.
-    pub fn unload_distant_chunks(&mut self, player_chunks: &[(i32, i32, i32)]) {
+    /// Returns the chunks that were unloaded.
+    fn unload_distant_chunks(
+        &mut self,
+        player_chunks: &[(i32, i32, i32)],
+        world_age: i64,
+        save_tx: &mpsc::UnboundedSender<SaveOp>,
+    ) -> Vec<ChunkPos> {
         // player_chunks: &[(chunk_x, chunk_z, view_distance)]
         let chunks_to_unload: Vec<ChunkPos> = self.chunks.keys()
             .filter(|pos| {
@@ -905,14 +1903,18 @@ impl WorldState {
             .collect();
 
         if chunks_to_unload.is_empty() {
-            return;
+            return chunks_to_unload;
         }
 
         let count = chunks_to_unload.len();
         for pos in &chunks_to_unload {
-            // Save before unloading
-            self.queue_chunk_save(*pos);
+            // Save before unloading, if there's anything new to save
+            if self.dirty_chunks.contains(pos) {
+                self.write_chunk(*pos, world_age, save_tx);
+            }
             self.chunks.remove(pos);
+            self.chunk_saved_at.remove(pos);
+            self.poi.remove(pos);
 
             // Remove block entities in this chunk
             let chunk_min_x = pos.x * 16;
@@ -921,104 +1923,661 @@ impl WorldState {
                 !(be_pos.x >= chunk_min_x && be_pos.x < chunk_min_x + 16
                     && be_pos.z >= chunk_min_z && be_pos.z < chunk_min_z + 16)
             });
+            // Scheduled ticks aren't persisted; drop them with the chunk
+            self.scheduled_block_ticks.retain(|tick_pos, _| tick_pos.chunk_pos() != *pos);
         }
-        info!("Unloaded {} distant chunks ({} remain)", count, self.chunks.len());
+        info!("Unloaded {} distant chunks in {} ({} remain)", count, self.name, self.chunks.len());
+        chunks_to_unload
     }
 }
 
-/// The main game loop. Runs at 20 TPS on the main thread.
-/// Owns the hecs World, the Lua ScriptRuntime, and all game state.
-pub async fn run_tick_loop(
-    config: Arc<ServerConfig>,
-    scripting: ScriptRuntime,
-    mut new_player_rx: mpsc::UnboundedReceiver<NewPlayer>,
-    player_count: Arc<std::sync::atomic::AtomicUsize>,
-    lua_commands: crate::bridge::LuaCommands,
-    block_overrides: crate::bridge::BlockOverrides,
-    next_eid: Arc<AtomicI32>,
-    save_tx: mpsc::UnboundedSender<SaveOp>,
-    region_storage: RegionStorage,
-    shutdown_rx: tokio::sync::watch::Receiver<bool>,
-) {
-    let adapter = V1_21Adapter::new();
-    let mut world = World::new();
-    let mut world_state = WorldState::new(region_storage, save_tx, next_eid.clone());
-
-    // Load level.dat if it exists (restores world_age, time_of_day, weather)
-    let level_dat_path = PathBuf::from(&config.world_dir).join("level.dat");
-    if let Some(level_data) = load_level_dat(&level_dat_path) {
-        world_state.world_age = level_data.world_age;
-        world_state.time_of_day = level_data.time_of_day;
-        world_state.raining = level_data.raining;
-        world_state.thundering = level_data.thundering;
-        world_state.rain_time = level_data.rain_time;
-        world_state.thunder_time = level_data.thunder_time;
-        world_state.clear_weather_time = level_data.clear_weather_time;
-        if level_data.raining {
-            world_state.rain_level = 1.0;
-        }
-        if level_data.thundering {
-            world_state.thunder_level = 1.0;
-        }
-        info!("Loaded level.dat: world_age={}, time_of_day={}, raining={}, thundering={}",
-            level_data.world_age, level_data.time_of_day, level_data.raining, level_data.thundering);
-    }
-
-    // Pre-generate spawn chunks so the first player join is instant
-    let vd = config.view_distance as i32;
-    let total = (2 * vd + 1) * (2 * vd + 1);
-    info!("Preparing spawn area ({} chunks)...", total);
-    for cx in -vd..=vd {
-        for cz in -vd..=vd {
-            world_state.ensure_chunk(ChunkPos::new(cx, cz));
+/// An empty chunk, except for vanilla's 33x33 stone start platform under
+/// the spawn point.
+fn generate_void_chunk_at(pos: ChunkPos, spawn: Vec3d) -> Chunk {
+    const PLATFORM_RADIUS: i32 = 16;
+    let mut chunk = Chunk::new();
+    let stone = pickaxe_data::block_name_to_default_state("stone").unwrap_or(1);
+    let (spawn_x, spawn_y, spawn_z) = (spawn.x.floor() as i32, spawn.y.floor() as i32 - 1, spawn.z.floor() as i32);
+    for local_x in 0..16 {
+        for local_z in 0..16 {
+            let (x, z) = (pos.x * 16 + local_x, pos.z * 16 + local_z);
+            if (x - spawn_x).abs() <= PLATFORM_RADIUS && (z - spawn_z).abs() <= PLATFORM_RADIUS {
+                chunk.set_block(local_x as usize, spawn_y, local_z as usize, stone);
+            }
         }
     }
-    info!("Spawn area ready");
+    chunk
+}
 
-    // Collect inbound packet receivers from all active players
-    // We store them separately since hecs components must be Send
-    let mut inbound_receivers: HashMap<i32, mpsc::UnboundedReceiver<InboundPacket>> =
-        HashMap::new();
+/// World state: the levels and everything shared between them.
+pub struct WorldState {
+    /// The level blocks are read from and written to. Work on another level
+    /// switches it in first and the default level back afterwards.
+    pub level: Level,
+    /// The other levels, by name.
+    levels: BTreeMap<String, Level>,
+    pub tick_stats: TickStats,
+    /// Tick rate and freeze state, set with /tick.
+    pub tick_rate: TickRate,
+    pub world_age: i64,
+    pub time_of_day: i64,
+    pub tick_count: u64,
+    pub save_tx: mpsc::UnboundedSender<SaveOp>,
+    pub player_data: Arc<PlayerDataStore>,
+    pub next_eid: Arc<AtomicI32>,
+    pub game_rules: GameRules,
+    pub difficulty: i32, // 0=peaceful, 1=easy, 2=normal, 3=hard
+    /// World seed, stored in level.dat `WorldGenSettings` (not yet used by the flat generator)
+    pub seed: i64,
+    // Weather state
+    pub raining: bool,
+    pub thundering: bool,
+    pub rain_time: i32,      // ticks until rain toggles
+    pub thunder_time: i32,   // ticks until thunder toggles
+    pub clear_weather_time: i32,
+    pub rain_level: f32,     // 0.0-1.0, gradual transition
+    pub thunder_level: f32,  // 0.0-1.0, gradual transition
+    /// Rain state last broadcast to clients, so changes made outside the
+    /// weather cycle (commands, Lua) still send START/STOP_RAINING
+    pub synced_raining: bool,
+    /// Set when time_of_day is changed outside the tick loop; forces an UpdateTime next tick
+    pub time_dirty: bool,
+    pub block_behaviors: BlockBehaviors,
+    pub scoreboard: Scoreboard,
+    /// Private message formats from `[messaging]` in server.toml
+    pub messaging: MessagingConfig,
+    /// Pending /tpa requests
+    pub tpa: TpaRequests,
+    /// Set by /stop; the tick loop saves and exits
+    pub stop_requested: bool,
+    /// Cleared by /save-off: chunk writes and the periodic autosave are paused
+    pub saving_enabled: bool,
+    /// /save-all runs waiting for the saver task, with the entity to notify
+    pending_saves: Vec<(tokio::sync::oneshot::Receiver<()>, hecs::Entity)>,
+    pub backup: BackupConfig,
+    /// Set by `pickaxe.world.backup()`; the tick loop starts the backup
+    pub backup_requested: bool,
+    /// The running backup, with who asked for it
+    pending_backup: Option<(tokio::sync::oneshot::Receiver<Result<PathBuf, String>>, Option<hecs::Entity>)>,
+    /// Named /warp destinations, saved to data/warps.dat
+    pub warps: Warps,
+    /// Open `pickaxe.gui` menus and their Lua callbacks, by GUI id
+    pub guis: HashMap<u64, crate::bridge::LuaGui>,
+    next_gui_id: u64,
+    /// Last id given to a tracked teleport (see `teleport_entity`)
+    next_teleport_id: i32,
+    /// `pickaxe.storage` data, shared with the Lua API
+    pub mod_storage: SharedModStorage,
+    /// Items registered with `pickaxe.items.register_custom`, by custom id
+    pub custom_items: crate::bridge::LuaCustomItems,
+    /// Callbacks registered with `pickaxe.placeholders.register`, by name
+    pub placeholders: crate::bridge::LuaPlaceholders,
+    /// Handlers registered with `pickaxe.channels.register`, by channel
+    pub channels: crate::bridge::LuaChannels,
+    /// Signs and checks cookies set from Lua (`[transfer]` secret)
+    pub cookie_secret: String,
+    /// Seconds a signed cookie stays valid
+    pub cookie_ttl: i64,
+    /// `pickaxe.players.request_cookie` calls waiting for a response
+    pub cookie_requests: Vec<crate::bridge::LuaCookieRequest>,
+    /// Whether chat is signed, and the keys to check chat sessions with
+    pub secure_chat: crate::chat::SecureChat,
+    /// `pickaxe.http` requests, shared with the Lua API
+    pub http: SharedHttpClient,
+    /// Repeating `pickaxe.particles.emitter` shapes
+    pub particle_emitters: ParticleEmitters,
+    /// Mobs given Lua behavior with `pickaxe.entities.set_ai`
+    pub mob_ai: HashMap<hecs::Entity, crate::bridge::LuaMobAi>,
+    /// Whether ticks are running over budget, and what is being shed
+    pub load: LoadMonitor,
+    /// Entities by chunk for tracking and proximity checks, rebuilt by
+    /// `tick_entity_tracking` at the end of each tick
+    pub entity_index: ChunkIndex,
+    /// Strikes requested with `pickaxe.world.spawn_lightning`, run by `tick_lightning`
+    pub pending_lightning: Vec<Vec3d>,
+    /// Audit log from `[audit]` in server.toml; records nothing when disabled
+    pub audit: AuditLog,
+    /// /audit lookups waiting for the audit task, with the entity to notify
+    pending_lookups: Vec<(tokio::sync::oneshot::Receiver<crate::audit::LookupResult>, hecs::Entity)>,
+    /// Registries sent during configuration, which chunk biomes are resolved against
+    pub registries: Registries,
+    /// Advancements from `[advancements]`, empty when disabled
+    pub advancements: Arc<AdvancementTree>,
+    /// Functions from the world's datapacks, run by /function and the
+    /// `#minecraft:load` and `#minecraft:tick` tags
+    pub functions: Arc<FunctionLibrary>,
+    /// level.dat's Data compound as loaded, so fields this server doesn't
+    /// manage are written back unchanged
+    pub level_dat: NbtValue,
+    /// How many functions are running inside each other
+    function_depth: u32,
+    /// Commands run since the outermost function started, against
+    /// `maxCommandChainLength`
+    function_commands: usize,
+}
 
-    let tick_duration = Duration::from_millis(50); // 20 TPS
-    let mut tick_count: u64 = 0;
+impl WorldState {
+    pub fn new(
+        storage: LevelStorage,
+        save_tx: mpsc::UnboundedSender<SaveOp>,
+        player_data: Arc<PlayerDataStore>,
+        next_eid: Arc<AtomicI32>,
+    ) -> Self {
+        Self {
+            level: Level::new(DEFAULT_LEVEL, 0, world_spawn(), WorldGenerator::Flat, storage),
+            levels: BTreeMap::new(),
+            tick_stats: TickStats::new(),
+            tick_rate: TickRate::new(20.0),
+            world_age: 0,
+            time_of_day: 0,
+            tick_count: 0,
+            save_tx,
+            player_data,
+            next_eid,
+            game_rules: GameRules::new(),
+            difficulty: 2, // normal
+            seed: rand::random(),
+            raining: false,
+            thundering: false,
+            rain_time: 12000 + rand::random::<i32>().unsigned_abs() as i32 % 168000,
+            thunder_time: 12000 + rand::random::<i32>().unsigned_abs() as i32 % 168000,
+            clear_weather_time: 0,
+            rain_level: 0.0,
+            thunder_level: 0.0,
+            synced_raining: false,
+            time_dirty: false,
+            block_behaviors: BlockBehaviors::new(),
+            scoreboard: Scoreboard::new(),
+            messaging: MessagingConfig::default(),
+            tpa: TpaRequests::default(),
+            stop_requested: false,
+            saving_enabled: true,
+            pending_saves: Vec::new(),
+            backup: BackupConfig::default(),
+            backup_requested: false,
+            pending_backup: None,
+            warps: Warps::default(),
+            guis: HashMap::new(),
+            next_gui_id: 0,
+            // Above the fixed ids of the join, respawn and wake-up teleports
+            next_teleport_id: 1000,
+            mod_storage: SharedModStorage::default(),
+            custom_items: crate::bridge::LuaCustomItems::default(),
+            placeholders: crate::bridge::LuaPlaceholders::default(),
+            channels: crate::bridge::LuaChannels::default(),
+            cookie_secret: String::new(),
+            cookie_ttl: 0,
+            cookie_requests: Vec::new(),
+            secure_chat: crate::chat::SecureChat::default(),
+            http: SharedHttpClient::default(),
+            particle_emitters: ParticleEmitters::default(),
+            mob_ai: HashMap::new(),
+            entity_index: ChunkIndex::default(),
+            load: LoadMonitor::default(),
+            pending_lightning: Vec::new(),
+            audit: AuditLog::default(),
+            pending_lookups: Vec::new(),
+            registries: Registries::default(),
+            advancements: Arc::new(AdvancementTree::default()),
+            functions: Arc::new(FunctionLibrary::default()),
+            level_dat: NbtValue::Compound(Vec::new()),
+            function_depth: 0,
+            function_commands: 0,
+        }
+    }
+
+    /// Ensures a chunk of the current level is loaded and returns a mutable reference.
+    fn ensure_chunk(&mut self, pos: ChunkPos) -> &mut Chunk {
+        self.level.ensure_chunk(pos)
+    }
+
+    /// Add a level, which players can then be moved to.
+    pub fn add_level(&mut self, level: Level) {
+        self.levels.insert(level.name.clone(), level);
+    }
+
+    /// Every level's name, the default first.
+    pub fn level_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.all_levels().map(|level| level.name.clone()).collect();
+        names.sort_by(|a, b| (a != DEFAULT_LEVEL, a).cmp(&(b != DEFAULT_LEVEL, b)));
+        names
+    }
+
+    pub fn has_level(&self, name: &str) -> bool {
+        self.level.name == name || self.levels.contains_key(name)
+    }
+
+    /// A level by name, whether or not it's the current one.
+    pub fn get_level(&self, name: &str) -> Option<&Level> {
+        if self.level.name == name {
+            Some(&self.level)
+        } else {
+            self.levels.get(name)
+        }
+    }
+
+    fn all_levels(&self) -> impl Iterator<Item = &Level> {
+        std::iter::once(&self.level).chain(self.levels.values())
+    }
+
+    fn all_levels_mut(&mut self) -> impl Iterator<Item = &mut Level> {
+        std::iter::once(&mut self.level).chain(self.levels.values_mut())
+    }
+
+    /// Make `name` the current level. Returns false, leaving the current
+    /// level as it was, if there is no such level.
+    pub fn switch_level(&mut self, name: &str) -> bool {
+        if self.level.name == name {
+            return true;
+        }
+        let Some(mut next) = self.levels.remove(name) else {
+            return false;
+        };
+        std::mem::swap(&mut self.level, &mut next);
+        self.levels.insert(next.name.clone(), next);
+        true
+    }
+
+    pub fn backup_running(&self) -> bool {
+        self.pending_backup.is_some()
+    }
+
+    /// Note that a chunk needs saving. It is written on the next flush, so a
+    /// burst of block changes costs one write instead of one per block.
+    fn mark_chunk_dirty(&mut self, pos: ChunkPos) {
+        self.level.dirty_chunks.insert(pos);
+    }
+
+    /// Queue every level's changed chunks for saving.
+    fn flush_dirty_chunks(&mut self) {
+        let (world_age, save_tx) = (self.world_age, self.save_tx.clone());
+        for level in self.all_levels_mut() {
+            level.flush_dirty_chunks(world_age, &save_tx);
+        }
+    }
+
+    /// Line up every level's changed chunks to be written over the next ticks.
+    fn queue_dirty_chunks(&mut self) {
+        for level in self.all_levels_mut() {
+            level.queue_dirty_chunks();
+        }
+    }
+
+    /// Write up to `limit` queued chunks across all levels, 0 for all of them.
+    fn write_queued_chunks(&mut self, limit: usize) {
+        let (world_age, save_tx) = (self.world_age, self.save_tx.clone());
+        let mut remaining = if limit == 0 { usize::MAX } else { limit };
+        for level in self.all_levels_mut() {
+            remaining -= level.write_queued_chunks(remaining, world_age, &save_tx);
+        }
+    }
+
+    /// Chunks lined up to be written on later ticks, in every level.
+    fn queued_chunk_count(&self) -> usize {
+        self.all_levels().map(|level| level.save_queue.len()).sum()
+    }
+
+    pub fn is_chunk_loaded(&self, pos: &ChunkPos) -> bool {
+        self.level.chunks.contains_key(pos)
+    }
+
+    /// Chunks loaded in every level.
+    pub fn loaded_chunk_count(&self) -> usize {
+        self.level.loaded_chunk_count() + self.levels.values().map(Level::loaded_chunk_count).sum::<usize>()
+    }
+
+    /// When the chunk was last queued for saving, if it has been since loading.
+    pub fn chunk_last_saved(&self, pos: &ChunkPos) -> Option<Instant> {
+        self.level.chunk_saved_at.get(pos).copied()
+    }
+
+    /// Whether the chunk has changes waiting for the next flush.
+    pub fn is_chunk_dirty(&self, pos: &ChunkPos) -> bool {
+        self.level.dirty_chunks.contains(pos)
+    }
+
+    pub fn get_chunk_packet(&mut self, chunk_x: i32, chunk_z: i32) -> InternalPacket {
+        let pos = ChunkPos::new(chunk_x, chunk_z);
+        self.ensure_chunk(pos);
+        self.level.chunks.get(&pos).unwrap().to_packet(chunk_x, chunk_z, &self.registries)
+    }
+
+    pub fn set_block(&mut self, pos: &BlockPos, state_id: i32) -> i32 {
+        let old = self.set_block_without_updates(pos, state_id);
+        if old != state_id {
+            // The new block is told about itself too (vanilla onPlace)
+            self.level.pending_neighbor_updates.push_back((*pos, *pos));
+            self.notify_neighbors(pos);
+        }
+        old
+    }
+
+    /// Set a block without notifying it or its neighbors, for bulk edits
+    /// like schematic pastes that must not fall apart as they are placed.
+    pub fn set_block_without_updates(&mut self, pos: &BlockPos, state_id: i32) -> i32 {
+        let chunk_pos = pos.chunk_pos();
+        let local_x = (pos.x.rem_euclid(16)) as usize;
+        let local_z = (pos.z.rem_euclid(16)) as usize;
+        let chunk = self.ensure_chunk(chunk_pos);
+        let old = chunk.set_block(local_x, pos.y, local_z, state_id);
+        if old != state_id {
+            self.mark_chunk_dirty(chunk_pos);
+            let (old_poi, new_poi) = (poi_type(old), poi_type(state_id));
+            if old_poi.is_some() || new_poi.is_some() {
+                self.level.poi.entry(chunk_pos).or_default().set(*pos, new_poi);
+            }
+        }
+        old
+    }
+
+    /// Loaded points of interest within `radius` blocks (a cube, as vanilla
+    /// searches) of `center`, optionally of one type, nearest first.
+    pub fn find_poi(&self, center: &BlockPos, radius: i32, poi_type: Option<&str>) -> Vec<&PoiRecord> {
+        let (min, max) = (
+            BlockPos::new(center.x.saturating_sub(radius), 0, center.z.saturating_sub(radius)).chunk_pos(),
+            BlockPos::new(center.x.saturating_add(radius), 0, center.z.saturating_add(radius)).chunk_pos(),
+        );
+        let mut found: Vec<&PoiRecord> = self
+            .level
+            .poi
+            .iter()
+            .filter(|(pos, _)| (min.x..=max.x).contains(&pos.x) && (min.z..=max.z).contains(&pos.z))
+            .flat_map(|(_, poi)| poi.records())
+            .filter(|r| poi_type.is_none_or(|t| r.poi_type == t))
+            .filter(|r| {
+                (r.pos.x - center.x).abs() <= radius && (r.pos.y - center.y).abs() <= radius && (r.pos.z - center.z).abs() <= radius
+            })
+            .collect();
+        found.sort_by_key(|r| {
+            let (dx, dy, dz) = ((r.pos.x - center.x) as i64, (r.pos.y - center.y) as i64, (r.pos.z - center.z) as i64);
+            dx * dx + dy * dy + dz * dz
+        });
+        found
+    }
+
+    /// Schedule a block tick at `pos` in `delay` ticks, unless one is already pending there.
+    pub fn schedule_block_tick(&mut self, pos: &BlockPos, delay: u64) {
+        let due = self.tick_count + delay;
+        self.level.scheduled_block_ticks.entry(*pos).or_insert(due);
+    }
+
+    /// Queue a neighbor-changed notification for the six blocks around `pos`.
+    /// Delivered to per-block handlers by `block_behavior::process_neighbor_updates`.
+    pub fn notify_neighbors(&mut self, pos: &BlockPos) {
+        let offsets = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)];
+        for (dx, dy, dz) in offsets {
+            let neighbor = BlockPos::new(pos.x + dx, pos.y + dy, pos.z + dz);
+            self.level.pending_neighbor_updates.push_back((neighbor, *pos));
+        }
+    }
+
+    pub fn get_block(&mut self, pos: &BlockPos) -> i32 {
+        let chunk_pos = pos.chunk_pos();
+        let local_x = (pos.x.rem_euclid(16)) as usize;
+        let local_z = (pos.z.rem_euclid(16)) as usize;
+        self.ensure_chunk(chunk_pos).get_block(local_x, pos.y, local_z)
+    }
+
+    /// World Y of the first sky-exposed block in the column containing `pos`.
+    pub fn height_at(&mut self, x: i32, z: i32) -> i32 {
+        let chunk_pos = BlockPos::new(x, 0, z).chunk_pos();
+        self.ensure_chunk(chunk_pos).height_at(x.rem_euclid(16) as usize, z.rem_euclid(16) as usize)
+    }
+
+    /// Whether rain is falling on `pos` (raining, and nothing above it).
+    pub fn is_raining_at(&mut self, pos: &BlockPos) -> bool {
+        self.raining && pos.y >= self.height_at(pos.x, pos.z)
+    }
+
+    /// Returns a block state only if the chunk is already loaded (no disk I/O).
+    pub fn get_block_if_loaded(&self, pos: &BlockPos) -> Option<i32> {
+        let chunk_pos = pos.chunk_pos();
+        let local_x = (pos.x.rem_euclid(16)) as usize;
+        let local_z = (pos.z.rem_euclid(16)) as usize;
+        self.level.chunks.get(&chunk_pos).map(|c| c.get_block(local_x, pos.y, local_z))
+    }
+
+    pub fn get_block_entity(&self, pos: &BlockPos) -> Option<&BlockEntity> {
+        self.level.block_entities.get(pos)
+    }
+
+    pub fn get_block_entity_mut(&mut self, pos: &BlockPos) -> Option<&mut BlockEntity> {
+        self.level.block_entities.get_mut(pos)
+    }
+
+    pub fn set_block_entity(&mut self, pos: BlockPos, entity: BlockEntity) {
+        self.level.block_entities.insert(pos, entity);
+    }
+
+    pub fn remove_block_entity(&mut self, pos: &BlockPos) -> Option<BlockEntity> {
+        self.level.block_entities.remove(pos)
+    }
+
+    /// Unload each level's chunks outside the view of the players in it
+    /// (`(level, chunk_x, chunk_z, view_distance)`), saving changed ones
+    /// first. Returns the unloaded chunks with their level.
+    pub fn unload_distant_chunks(&mut self, player_chunks: &[(String, i32, i32, i32)]) -> Vec<(String, ChunkPos)> {
+        let (world_age, save_tx) = (self.world_age, self.save_tx.clone());
+        let mut unloaded = Vec::new();
+        for level in self.all_levels_mut() {
+            let in_level: Vec<(i32, i32, i32)> = player_chunks
+                .iter()
+                .filter(|(name, ..)| *name == level.name)
+                .map(|&(_, x, z, vd)| (x, z, vd))
+                .collect();
+            for pos in level.unload_distant_chunks(&in_level, world_age, &save_tx) {
+                unloaded.push((level.name.clone(), pos));
+            }
+        }
+        unloaded
+    }
+}
+
+/// Add the `[[worlds]]` levels. Worlds that can't be loaded are skipped
+/// with a warning.
+fn add_configured_levels(world_state: &mut WorldState, config: &ServerConfig) {
+    let world_dir = std::path::Path::new(&config.world_dir);
+    for world_config in &config.worlds {
+        let name = &crate::datapacks::qualify(&world_config.name);
+        let Some(dir) = pickaxe_region::dimension_dir(world_dir, name) else {
+            warn!("Skipping world {}: not a valid dimension name", name);
+            continue;
+        };
+        if world_state.has_level(name) {
+            warn!("Skipping world {}: listed twice", name);
+            continue;
+        }
+        let Some(dimension_type) = world_state.registries.dimension_type_id(&world_config.dimension_type) else {
+            warn!("Skipping world {}: unknown dimension type {}", name, world_config.dimension_type);
+            continue;
+        };
+        // Chunks are stored and sent with the overworld's fixed height
+        let height = &world_state.registries.dimension_types[dimension_type as usize];
+        let full_height = pickaxe_world::SECTION_COUNT as i32 * 16;
+        if height.min_y != pickaxe_world::MIN_Y || height.height != full_height {
+            warn!(
+                "Skipping world {}: dimension type {} must have min_y {} and height {}",
+                name, world_config.dimension_type, pickaxe_world::MIN_Y, full_height
+            );
+            continue;
+        }
+        let mut generator = world_config.generator;
+        if let Some(saved) = load_dimension_generator(&dir.join("level.dat")) {
+            if saved != generator {
+                warn!("World {} was created with the {} generator; keeping it", name, saved.name());
+                generator = saved;
+            }
+        }
+        match open_level_storage(world_dir, name, config.region_compression) {
+            Ok(storage) => {
+                let [x, y, z] = world_config.spawn;
+                world_state.add_level(Level::new(name, dimension_type, Vec3d::new(x, y, z), generator, storage));
+                info!("Loaded world {} ({} generator)", name, generator.name());
+            }
+            Err(e) => warn!("Skipping world {}: {}", name, e),
+        }
+    }
+}
+
+/// How often changed chunks are lined up for writing (5 seconds). Block
+/// changes in between are batched into one write per chunk.
+const CHUNK_FLUSH_INTERVAL: u64 = 100;
+
+/// Ticks between unloading chunks no player can see.
+const CHUNK_UNLOAD_INTERVAL: u64 = 1200;
+
+/// The main game loop. Runs at 20 TPS on the main thread.
+/// Owns the hecs World, the Lua ScriptRuntime, and all game state.
+pub async fn run_tick_loop(
+    config: Arc<ServerConfig>,
+    scripting: ScriptRuntime,
+    mut new_player_rx: mpsc::UnboundedReceiver<NewPlayer>,
+    player_count: Arc<std::sync::atomic::AtomicUsize>,
+    lua_commands: crate::bridge::LuaCommands,
+    block_overrides: crate::bridge::BlockOverrides,
+    mod_storage: SharedModStorage,
+    custom_items: crate::bridge::LuaCustomItems,
+    placeholders: crate::bridge::LuaPlaceholders,
+    channels: crate::bridge::LuaChannels,
+    secure_chat: crate::chat::SecureChat,
+    http: SharedHttpClient,
+    server_list: crate::status::SharedServerList,
+    next_eid: Arc<AtomicI32>,
+    save_tx: mpsc::UnboundedSender<SaveOp>,
+    player_data: Arc<PlayerDataStore>,
+    storage: LevelStorage,
+    mut console_rx: mpsc::UnboundedReceiver<String>,
+    shutdown_rx: tokio::sync::watch::Receiver<bool>,
+) {
+    let adapter = V1_21Adapter::new();
+    let mut world = World::new();
+    // Command source for lines typed into the server console
+    let console = world.spawn((ConsoleSource,));
+    let mut world_state = WorldState::new(storage, save_tx, player_data, next_eid.clone());
+    world_state.messaging = config.messaging.clone();
+    world_state.backup = config.backup.clone();
+    world_state.load = LoadMonitor::new(&config.overload);
+    world_state.registries = config.registries.build();
+    if config.advancements.enabled {
+        let tree = AdvancementTree::load(std::path::Path::new(&config.advancements.data_dir));
+        info!("Loaded {} advancements", tree.len());
+        world_state.advancements = Arc::new(tree);
+    }
+    world_state.tpa = TpaRequests::new(config.tpa.timeout_seconds * 20);
+    world_state.mod_storage = mod_storage;
+    world_state.custom_items = custom_items;
+    world_state.placeholders = placeholders;
+    world_state.channels = channels;
+    world_state.cookie_secret = config.transfer.secret.clone();
+    world_state.cookie_ttl = i64::try_from(config.transfer.cookie_ttl_seconds).unwrap_or(i64::MAX);
+    world_state.tick_rate = TickRate::new(config.tick_rate);
+    world_state.secure_chat = secure_chat;
+    world_state.http = http;
+    world_state.audit = AuditLog::start(&config.audit, &PathBuf::from(&config.world_dir));
+
+    // Load level.dat if it exists (restores world_age, time_of_day, weather)
+    let level_dat_path = PathBuf::from(&config.world_dir).join("level.dat");
+    if let Some(level_data) = load_level_dat(&level_dat_path) {
+        world_state.world_age = level_data.world_age;
+        world_state.time_of_day = level_data.time_of_day;
+        world_state.raining = level_data.raining;
+        world_state.thundering = level_data.thundering;
+        world_state.rain_time = level_data.rain_time;
+        world_state.thunder_time = level_data.thunder_time;
+        world_state.clear_weather_time = level_data.clear_weather_time;
+        world_state.game_rules = level_data.game_rules;
+        world_state.difficulty = level_data.difficulty;
+        if let Some(generator) = level_data.generator {
+            world_state.level.generator = generator;
+        }
+        world_state.level_dat = level_data.data;
+        if let Some(seed) = level_data.seed {
+            world_state.seed = seed;
+        }
+        if level_data.raining {
+            world_state.rain_level = 1.0;
+            world_state.synced_raining = true;
+        }
+        if level_data.thundering {
+            world_state.thunder_level = 1.0;
+        }
+        info!("Loaded level.dat: world_age={}, time_of_day={}, raining={}, thundering={}",
+            level_data.world_age, level_data.time_of_day, level_data.raining, level_data.thundering);
+    }
+    add_configured_levels(&mut world_state, &config);
+
+    let scoreboard_path = PathBuf::from(&config.world_dir).join("data").join("scoreboard.dat");
+    if let Some(scoreboard) = std::fs::read(&scoreboard_path).ok().and_then(|d| Scoreboard::deserialize(&d)) {
+        info!("Loaded scoreboard: {} objectives", scoreboard.objectives().len());
+        world_state.scoreboard = scoreboard;
+    }
+    let warps_path = PathBuf::from(&config.world_dir).join("data").join("warps.dat");
+    if let Some(warps) = std::fs::read(&warps_path).ok().and_then(|d| Warps::deserialize(&d)) {
+        info!("Loaded {} warps", warps.warps.len());
+        world_state.warps = warps;
+    }
+    let functions = FunctionLibrary::load(std::path::Path::new(&config.world_dir));
+    if functions.pack_count() > 0 {
+        info!("Loaded {} functions from {} datapacks", functions.len(), functions.pack_count());
+    }
+    world_state.functions = Arc::new(functions);
+
+    // Pre-generate spawn chunks so the first player join is instant
+    let vd = config.view_distance as i32;
+    let total = (2 * vd + 1) * (2 * vd + 1);
+    info!("Preparing spawn area ({} chunks)...", total);
+    for cx in -vd..=vd {
+        for cz in -vd..=vd {
+            world_state.ensure_chunk(ChunkPos::new(cx, cz));
+        }
+    }
+    info!("Spawn area ready");
+    run_function_tag(&config, &mut world, &mut world_state, console, "minecraft:load", &scripting, &lua_commands);
+
+    // Collect inbound packet receivers from all active players
+    // We store them separately since hecs components must be Send
+    let mut inbound_receivers: HashMap<i32, mpsc::UnboundedReceiver<InboundPacket>> =
+        HashMap::new();
 
-    info!("Tick loop started (20 TPS)");
+    let mut tick_count: u64 = 0;
+
+    info!("Tick loop started ({} TPS)", world_state.tick_rate.rate());
 
     loop {
-        // Check for shutdown signal
-        if *shutdown_rx.borrow() {
+        // Check for shutdown signal (Ctrl-C or /stop)
+        if *shutdown_rx.borrow() || world_state.stop_requested {
             info!("Shutting down...");
-            // Save all players
-            save_all_players(&world, &world_state.save_tx);
-            // Save all chunks containing block entities
-            save_block_entity_chunks(&world_state);
-            // Save level.dat
-            let level_data = serialize_level_dat(&world_state, &config);
-            let _ = world_state.save_tx.send(SaveOp::LevelDat(level_data));
+            save_world(&world, &mut world_state, &config);
+            world_state.flush_dirty_chunks();
             // Signal saver to flush and stop
             let (done_tx, done_rx) = tokio::sync::oneshot::channel();
             let _ = world_state.save_tx.send(SaveOp::Shutdown(done_tx));
             let _ = done_rx.await;
+            world_state.audit.shutdown().await;
             info!("World saved. Goodbye!");
             return;
         }
 
         let tick_start = Instant::now();
+        scripting.begin_tick();
+        // A frozen world still serves players; only the world stands still
+        let world_ticks = world_state.tick_rate.advance();
 
         // 1. Accept new players
-        while let Ok(new_player) = new_player_rx.try_recv() {
-            handle_new_player(
-                &config,
-                &adapter,
-                &mut world,
-                &mut world_state,
-                &mut inbound_receivers,
-                new_player,
-                &scripting,
-                &lua_commands,
-            );
-        }
+        timed("handle_new_player", || {
+            while let Ok(new_player) = new_player_rx.try_recv() {
+                handle_new_player(
+                    &config,
+                    &adapter,
+                    &mut world,
+                    &mut world_state,
+                    &mut inbound_receivers,
+                    new_player,
+                    &scripting,
+                    &lua_commands,
+                );
+            }
+        });
 
         // 2. Process inbound packets from all players
         let mut packets: Vec<InboundPacket> = Vec::new();
@@ -1050,96 +2609,234 @@ pub async fn run_tick_loop(
         world_state.tick_count = tick_count;
 
         // 4. Process packets
-        for pkt in packets {
-            process_packet(
-                &config,
-                &adapter,
-                &mut world,
-                &mut world_state,
-                pkt,
-                &scripting,
-                &lua_commands,
-                &block_overrides,
-                &next_eid,
-            );
-        }
+        timed("process_packet", || {
+            for pkt in packets {
+                process_packet(
+                    &config,
+                    &adapter,
+                    &mut world,
+                    &mut world_state,
+                    pkt,
+                    &scripting,
+                    &lua_commands,
+                    &block_overrides,
+                    &next_eid,
+                );
+            }
+        });
+        timed("run_command", || {
+            while let Ok(line) = console_rx.try_recv() {
+                info!("Console issued command: /{}", line);
+                world_state.audit.record("Server", AuditAction::Command, None, &format!("/{}", line));
+                let ctx = execution_context(&world, console);
+                run_command(&config, &mut world, &mut world_state, &ctx, &line, &scripting, &lua_commands);
+            }
+        });
+        timed("run_function_tag", || {
+            run_function_tag(&config, &mut world, &mut world_state, console, "minecraft:tick", &scripting, &lua_commands)
+        });
+        timed("process_neighbor_updates", || {
+            block_behavior::process_neighbor_updates(&mut world, &mut world_state, &next_eid, &scripting, &block_overrides)
+        });
 
         // 5. Tick systems
-        tick_keep_alive(&adapter, &mut world, tick_count);
-        tick_attack_cooldown(&mut world);
-        tick_shield_cooldown(&mut world);
-        tick_void_damage(&mut world, &mut world_state, &scripting);
-        tick_drowning_and_lava(&mut world, &mut world_state, &scripting);
-        tick_health_hunger(&mut world, &mut world_state, &scripting, tick_count);
-        tick_effects(&mut world, &mut world_state, &scripting, tick_count);
-        tick_eating(&mut world);
-        tick_sleeping(&mut world, &mut world_state, &scripting);
-        tick_buttons(&mut world, &mut world_state);
-        tick_item_physics(&mut world, &mut world_state, &scripting);
-        tick_arrow_physics(&mut world, &mut world_state, &next_eid, &scripting);
-        tick_fishing_bobbers(&mut world, &mut world_state);
-        tick_tnt_entities(&mut world, &mut world_state, &next_eid, &scripting);
-        if tick_count % 4 == 0 {
-            tick_item_pickup(&mut world, &mut world_state, &scripting);
-        }
-        // Crop growth + farmland moisture (every 68 ticks ≈ 3.4s, simulating random ticks)
-        if tick_count % 68 == 0 {
-            tick_farming(&world, &mut world_state);
-        }
-        // Fire tick (every 35 ticks ≈ 1.75s, simulating MC's 30-40 tick random delay)
-        if tick_count % 35 == 0 {
-            tick_fire(&mut world, &mut world_state, &next_eid, &scripting);
-        }
-        // Fluid tick: water every 5 ticks, lava every 30 ticks
-        if tick_count % 5 == 0 {
-            tick_fluids(&world, &mut world_state, true, tick_count % 30 == 0);
-        }
-        tick_furnaces(&world, &mut world_state);
-        tick_brewing_stands(&world, &mut world_state);
-        tick_mob_ai(&mut world, &mut world_state, &scripting, &next_eid);
-        tick_mob_spawning(&mut world, &world_state, &next_eid, tick_count);
-        if tick_count % 100 == 0 {
-            tick_mob_despawn(&mut world);
-        }
-        tick_entity_tracking(&mut world);
-        tick_entity_movement_broadcast(&mut world);
-        tick_world_time(&world, &mut world_state, tick_count);
-        tick_weather_cycle(&world, &mut world_state, &scripting);
-        tick_lightning(&mut world, &mut world_state, &next_eid, &scripting);
-        tick_block_breaking(&mut world, tick_count);
-
-        // Periodic player/world data save (every 60 seconds = 1200 ticks)
-        if tick_count % 1200 == 0 && tick_count > 0 {
-            save_all_players(&world, &world_state.save_tx);
-            save_block_entity_chunks(&world_state);
-            let level_data = serialize_level_dat(&world_state, &config);
-            let _ = world_state.save_tx.send(SaveOp::LevelDat(level_data));
-
-            // Unload chunks not in any player's view distance
-            let player_chunks: Vec<(i32, i32, i32)> = world
-                .query::<(&ChunkPosition, &ViewDistance)>()
+        timed("tick_keep_alive", || tick_keep_alive(&mut world, &config.keep_alive, tick_count));
+        timed("tick_afk", || tick_afk(&mut world, &mut world_state, &scripting, &config.afk, tick_count));
+        timed("tick_chunk_sending", || tick_chunk_sending(&mut world, &mut world_state));
+        timed("tick_attack_cooldown", || tick_attack_cooldown(&mut world));
+        timed("tick_shield_cooldown", || tick_shield_cooldown(&mut world));
+        timed("tick_void_damage", || tick_void_damage(&mut world, &mut world_state, &scripting));
+        timed("tick_drowning_and_lava", || tick_drowning_and_lava(&mut world, &mut world_state, &scripting));
+        timed("tick_health_hunger", || tick_health_hunger(&mut world, &mut world_state, &scripting, tick_count));
+        timed("tick_effects", || tick_effects(&mut world, &mut world_state, &scripting, tick_count));
+        timed("tick_eating", || tick_eating(&mut world));
+        timed("tick_statistics", || tick_statistics(&mut world));
+        timed("tick_advancements", || tick_advancements(&mut world, &mut world_state, tick_count));
+        if world_ticks {
+            tick_world(&mut world, &mut world_state, &scripting, &block_overrides, &next_eid, tick_count);
+        }
+        timed("tick_entity_tracking", || tick_entity_tracking(&mut world, &mut world_state.entity_index));
+        timed("tick_entity_movement_broadcast", || tick_entity_movement_broadcast(&mut world, tick_count));
+        timed("tick_block_breaking", || tick_block_breaking(&mut world, tick_count));
+        timed("tick_scoreboard_health", || tick_scoreboard_health(&world, &mut world_state));
+        timed("tick_tpa_requests", || tick_tpa_requests(&world, &mut world_state));
+        timed("tick_pending_teleports", || tick_pending_teleports(&mut world, &world_state));
+        if tick_count % (config.tab_list.refresh_seconds * 20).max(1) == 0 {
+            timed("tick_tab_list", || tick_tab_list(&mut world, &mut world_state, &scripting, &config));
+            timed("refresh_server_list", || refresh_server_list(&mut world, &mut world_state, &scripting, &config, &server_list));
+        }
+        timed("tick_pending_saves", || tick_pending_saves(&world, &mut world_state));
+        timed("tick_backups", || tick_backups(&world, &mut world_state, &config, tick_count));
+        timed("tick_pending_lookups", || tick_pending_lookups(&world, &mut world_state));
+        timed("tick_http_responses", || tick_http_responses(&mut world, &mut world_state, &scripting));
+        timed("tick_particle_emitters", || tick_particle_emitters(&world, &mut world_state));
+        timed("fire_chunk_load_events", || fire_chunk_load_events(&mut world, &mut world_state, &scripting));
+
+        // Line up chunks changed since the last flush and write a few of them
+        // each tick, paused by /save-off
+        if world_state.saving_enabled {
+            if tick_count % CHUNK_FLUSH_INTERVAL == 0 {
+                world_state.queue_dirty_chunks();
+            }
+            timed("write_queued_chunks", || world_state.write_queued_chunks(config.saving.chunks_per_tick));
+        }
+        if let Ok(mut stats) = crate::save_stats::SAVER.lock() {
+            stats.pending_chunks = world_state.queued_chunk_count();
+        }
+
+        // Periodic player/world data save, paused by /save-off
+        let autosave = config.saving.autosave_ticks;
+        if autosave > 0 && tick_count % autosave == 0 && tick_count > 0 && world_state.saving_enabled {
+            timed("save_world", || save_world(&world, &mut world_state, &config));
+        }
+
+        if tick_count % CHUNK_UNLOAD_INTERVAL == 0 && tick_count > 0 && world_state.saving_enabled {
+            // Unload chunks not in the view of any player in their level
+            let player_chunks: Vec<(String, i32, i32, i32)> = world
+                .query::<(&ChunkPosition, &ViewDistance, Option<&Dimension>)>()
                 .iter()
-                .map(|(_, (cp, vd))| (cp.chunk_x, cp.chunk_z, vd.0))
+                .map(|(_, (cp, vd, dimension))| {
+                    let level = dimension.map_or(DEFAULT_LEVEL, |d| d.0.as_str()).to_string();
+                    (level, cp.chunk_x, cp.chunk_z, vd.0)
+                })
                 .collect();
-            world_state.unload_distant_chunks(&player_chunks);
+            for (level, pos) in world_state.unload_distant_chunks(&player_chunks) {
+                scripting.fire_event_in_context(
+                    "chunk_unload",
+                    &[("x", &pos.x.to_string()), ("z", &pos.z.to_string()), ("world", &level)],
+                    &mut world as *mut _ as *mut (),
+                    &mut world_state as *mut _ as *mut (),
+                );
+            }
         }
 
         tick_count += 1;
 
         // Sleep for remainder of tick
+        let tick_duration = world_state.tick_rate.interval();
         let elapsed = tick_start.elapsed();
+        world_state.tick_stats.set_target(tick_duration);
+        world_state.tick_stats.record(elapsed);
+        if let Ok(mut profiler) = crate::profiler::PROFILER.lock() {
+            profiler.end_tick(elapsed);
+        }
+        update_load(&mut world, &mut world_state, &scripting);
         if elapsed < tick_duration {
             tokio::time::sleep(tick_duration - elapsed).await;
-        } else if tick_count % 100 == 0 {
+        } else if tick_count % 100 == 0 && !world_state.tick_rate.sprinting() {
             // Only warn occasionally to avoid log spam
             warn!(
-                "Tick {} took {:?} (over 50ms budget)",
-                tick_count, elapsed
+                "Tick {} took {:?} (over {:?} budget)",
+                tick_count, elapsed, tick_duration
             );
         }
     }
 }
 
+/// Run a tick system, timing it under `system` for `/debug timings`.
+fn timed<T>(system: &'static str, run: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = run();
+    crate::profiler::record(system, start.elapsed());
+    result
+}
+
+/// Publish the tick timings and move the load level, shedding mob spawning,
+/// cosmetic broadcasts and random ticks while the server can't keep up.
+fn update_load(world: &mut World, world_state: &mut WorldState, scripting: &ScriptRuntime) {
+    let stats = &world_state.tick_stats;
+    let (tps, mspt, max_mspt, target) = (stats.tps(), stats.average_mspt(), stats.max_mspt(), stats.target_mspt());
+    let previous = world_state.load.level();
+    let changed = world_state.load.update(mspt, target);
+    if let Ok(mut load) = crate::overload::LOAD.lock() {
+        load.tps = tps;
+        load.mspt = mspt;
+        load.max_mspt = max_mspt;
+        load.level = world_state.load.level();
+        if changed.is_some_and(|level| level > previous) {
+            load.escalations += 1;
+        }
+    }
+    let Some(level) = changed else {
+        return;
+    };
+    match level {
+        LoadLevel::Normal => info!("Server load is back to normal ({:.1} ms/tick)", mspt),
+        _ => warn!(
+            "Server is {} ({:.1} ms/tick against a {:.1} ms budget); shedding mob spawning, cosmetics and random ticks",
+            level.name(), mspt, target
+        ),
+    }
+    scripting.fire_event_in_context(
+        "load_change",
+        &[("level", level.name()), ("previous", previous.name()), ("mspt", &format!("{:.1}", mspt))],
+        world as *mut _ as *mut (),
+        world_state as *mut _ as *mut (),
+    );
+}
+
+/// Systems that advance the world rather than serve players: physics, mobs,
+/// blocks, time and weather. Skipped while `/tick freeze` is in effect.
+fn tick_world(
+    world: &mut World,
+    world_state: &mut WorldState,
+    scripting: &ScriptRuntime,
+    block_overrides: &crate::bridge::BlockOverrides,
+    next_eid: &Arc<AtomicI32>,
+    tick_count: u64,
+) {
+    timed("tick_sleeping", || tick_sleeping(world, world_state, scripting));
+    timed("tick_buttons", || tick_buttons(world, world_state));
+    timed("tick_item_physics", || tick_item_physics(world, world_state, scripting));
+    timed("tick_arrow_physics", || tick_arrow_physics(world, world_state, next_eid, scripting));
+    timed("tick_fishing_bobbers", || tick_fishing_bobbers(world, world_state));
+    timed("tick_tnt_entities", || tick_tnt_entities(world, world_state, next_eid, scripting));
+    if tick_count % 4 == 0 {
+        timed("tick_item_pickup", || tick_item_pickup(world, world_state, scripting));
+    }
+    // Crop growth + farmland moisture (every 68 ticks ≈ 3.4s at the default
+    // randomTickSpeed of 3, simulating random ticks; 0 disables growth),
+    // spread further apart under load
+    let random_tick_speed = world_state.game_rules.get_int("randomTickSpeed");
+    let random_tick_interval = (204 / random_tick_speed.max(1) as u64).max(1) * world_state.load.level().random_tick_divisor();
+    if random_tick_speed > 0 && tick_count % random_tick_interval == 0 {
+        timed("tick_farming", || tick_farming(world, world_state));
+        timed("tick_lua_random_ticks", || tick_lua_random_ticks(world, world_state, scripting, block_overrides));
+    }
+    timed("tick_precipitation", || tick_precipitation(world, world_state));
+    // Fire tick (every 35 ticks ≈ 1.75s, simulating MC's 30-40 tick random delay)
+    if tick_count % 35 == 0 {
+        timed("tick_fire", || tick_fire(world, world_state, next_eid, scripting));
+    }
+    // Fluid tick: water every 5 ticks, lava every 30 ticks
+    if tick_count % 5 == 0 {
+        timed("tick_fluids", || tick_fluids(world, world_state, true, tick_count % 30 == 0, next_eid, scripting));
+    }
+    timed("tick_furnaces", || tick_furnaces(world, world_state));
+    timed("tick_brewing_stands", || tick_brewing_stands(world, world_state));
+    timed("tick_campfires", || tick_campfires(world, world_state, next_eid, scripting));
+    timed("tick_bells", || tick_bells(world_state));
+    timed("tick_mob_ai", || tick_mob_ai(world, world_state, scripting, next_eid));
+    if world_state.load.level().spawns_mobs(tick_count) {
+        timed("tick_mob_spawning", || tick_mob_spawning(world, world_state, next_eid, tick_count));
+    }
+    if tick_count % 100 == 0 {
+        timed("tick_mob_despawn", || tick_mob_despawn(world, world_state));
+    }
+    timed("tick_world_time", || tick_world_time(world, world_state, tick_count));
+    timed("tick_weather_cycle", || tick_weather_cycle(world, world_state, scripting));
+    timed("tick_lightning", || tick_lightning(world, world_state, next_eid, scripting));
+    timed("process_scheduled_ticks", || block_behavior::process_scheduled_ticks(world, world_state, next_eid, scripting));
+    timed("process_neighbor_updates", || block_behavior::process_neighbor_updates(world, world_state, next_eid, scripting, block_overrides));
+}
+
+/// View and simulation distance for a client that asked to see `requested`
+/// chunks: what it asked for, within the server's limits.
+fn negotiate_distances(config: &ServerConfig, requested: i32) -> (i32, i32) {
+    let view_distance = requested.max(2).min(config.view_distance as i32);
+    (view_distance, view_distance.min(config.simulation_distance as i32))
+}
+
 fn handle_new_player(
     config: &ServerConfig,
     _adapter: &V1_21Adapter,
@@ -1156,22 +2853,28 @@ fn handle_new_player(
 
     info!("{} entering play state (eid={})", profile.name, entity_id);
 
-    let view_distance = config.view_distance as i32;
-
-    // Try loading saved player data from disk
-    let player_data_path = PathBuf::from(&config.world_dir)
-        .join("playerdata")
-        .join(format!("{}.dat", profile.uuid));
-    let saved = if player_data_path.exists() {
-        std::fs::read(&player_data_path)
-            .ok()
-            .and_then(|data| deserialize_player_data(&data))
-    } else {
-        None
-    };
-
-    // Determine values from saved data or defaults
-    let spawn_pos = saved.as_ref().map(|s| s.position).unwrap_or(Vec3d::new(0.5, -49.0, 0.5));
+    let settings = new_player.settings.clone().unwrap_or_else(|| ClientSettings {
+        locale: "en_us".into(),
+        view_distance: config.view_distance as i32,
+    });
+    let (view_distance, simulation_distance) = negotiate_distances(config, settings.view_distance);
+
+    // Try loading saved player data (staged or on disk)
+    let saved = world_state.player_data.load(&profile.uuid)
+        .and_then(|data| deserialize_player_data(&data));
+
+    // Determine values from saved data or defaults; a player whose level is
+    // gone starts over at the world spawn
+    let level = saved
+        .as_ref()
+        .map(|s| s.dimension.clone())
+        .filter(|d| world_state.has_level(d))
+        .unwrap_or_else(|| DEFAULT_LEVEL.to_string());
+    let spawn_pos = saved
+        .as_ref()
+        .filter(|s| s.dimension == level)
+        .map(|s| s.position)
+        .unwrap_or_else(world_spawn);
     let player_yaw = saved.as_ref().map(|s| s.yaw).unwrap_or(0.0);
     let player_pitch = saved.as_ref().map(|s| s.pitch).unwrap_or(0.0);
     let player_game_mode = saved.as_ref().map(|s| s.game_mode).unwrap_or(GameMode::Survival);
@@ -1205,31 +2908,50 @@ fn handle_new_player(
         inv
     }).unwrap_or_else(Inventory::new);
     let player_spawn_point = saved.as_ref().and_then(|s| s.spawn_point);
+    let player_homes = saved.as_ref().map(|s| s.homes.clone()).unwrap_or_default();
+    let player_mod_data = saved.as_ref().map(|s| s.mod_data.clone()).unwrap_or_default();
 
     // Send Join Game
     let _ = sender.send(InternalPacket::JoinGame {
         entity_id,
         is_hardcore: false,
-        dimension_names: vec!["minecraft:overworld".into()],
+        dimension_names: world_state.level_names(),
         max_players: config.max_players as i32,
-        view_distance,
-        simulation_distance: view_distance,
-        reduced_debug_info: false,
-        enable_respawn_screen: true,
+        view_distance: config.view_distance as i32,
+        simulation_distance,
+        reduced_debug_info: world_state.game_rules.get_bool("reducedDebugInfo"),
+        enable_respawn_screen: !world_state.game_rules.get_bool("doImmediateRespawn"),
         do_limited_crafting: false,
-        dimension_type: 0,
-        dimension_name: "minecraft:overworld".into(),
+        dimension_type: world_state.get_level(&level).map_or(0, |l| l.dimension_type),
+        dimension_name: level.clone(),
         hashed_seed: 0,
         game_mode: player_game_mode,
         previous_game_mode: -1,
         is_debug: false,
         is_flat: true,
         portal_cooldown: 0,
-        enforces_secure_chat: false,
+        enforces_secure_chat: world_state.secure_chat.enabled,
+    });
+    send_player_abilities(&sender, player_game_mode);
+    let _ = sender.send(InternalPacket::ChangeDifficulty {
+        difficulty: world_state.difficulty as u8,
+        locked: false,
     });
+    let _ = sender.send(ticking_state_packet(&world_state.tick_rate));
+    if world_state.tick_rate.steps() > 0 {
+        let _ = sender.send(InternalPacket::StepTick {
+            steps: world_state.tick_rate.steps() as i32,
+        });
+    }
 
-    // Declare commands for tab completion (includes Lua-registered commands)
-    let _ = sender.send(build_command_tree(lua_commands));
+    // Op level and the commands this player may use (includes Lua-registered commands)
+    crate::access::claim_entries(&profile.name, profile.uuid);
+    let op_level = crate::access::permission_level(&profile.name, Some(profile.uuid));
+    let _ = sender.send(InternalPacket::EntityEvent {
+        entity_id,
+        event_id: 24 + op_level as i8,
+    });
+    let _ = sender.send(build_command_tree(lua_commands, op_level));
 
     // Send current world time
     let _ = sender.send(InternalPacket::UpdateTime {
@@ -1246,8 +2968,9 @@ fn handle_new_player(
         chunk_z: center_cz,
     });
 
-    // Send chunks using batch protocol
-    send_chunks_around(&sender, world_state, center_cx, center_cz, view_distance);
+    // Chunks go out from the tick loop, paced by the client
+    let mut chunk_sender = ChunkSender::default();
+    chunk_sender.queue_view(View::new(center_cx, center_cz, view_distance));
 
     // Teleport player to spawn
     let _ = sender.send(InternalPacket::SynchronizePlayerPosition {
@@ -1275,26 +2998,16 @@ fn handle_new_player(
         angle: spawn_angle,
     });
 
-    // Send current weather state to new player
-    if world_state.raining {
-        let _ = sender.send(InternalPacket::GameEvent {
-            event: 1, // START_RAINING
-            value: 0.0,
-        });
-        let _ = sender.send(InternalPacket::GameEvent {
-            event: 7, // RAIN_LEVEL_CHANGE
-            value: world_state.rain_level,
-        });
-        let _ = sender.send(InternalPacket::GameEvent {
-            event: 8, // THUNDER_LEVEL_CHANGE
-            value: world_state.thunder_level,
-        });
-    }
+    // Send current weather state to new player (including a rain fade in progress)
+    send_weather_state(&sender, world_state);
 
     // Send tab list: add this player to all existing players, and all existing to this player
     // First, send all existing players to the new player
     let mut existing_entries: Vec<PlayerInfoEntry> = Vec::new();
-    for (_eid, (p, gm)) in world.query::<(&Profile, &PlayerGameMode)>().iter() {
+    for (_eid, (p, gm, signer, activity)) in
+        world.query::<(&Profile, &PlayerGameMode, Option<&ChatSigner>, Option<&Activity>)>().iter()
+    {
+        let afk = activity.is_some_and(|a| a.is_afk());
         existing_entries.push(PlayerInfoEntry {
             uuid: p.0.uuid,
             name: Some(p.0.name.clone()),
@@ -1307,7 +3020,8 @@ fn handle_new_player(
             game_mode: Some(gm.0.id() as i32),
             listed: Some(true),
             ping: Some(0),
-            display_name: None,
+            display_name: afk_display_name(&config.afk, &p.0.name, afk),
+            chat_session: signer.map(|signer| signer.session.clone()),
         });
     }
 
@@ -1324,6 +3038,7 @@ fn handle_new_player(
         listed: Some(true),
         ping: Some(0),
         display_name: None,
+        chat_session: None,
     };
 
     // Send all existing + self to new player
@@ -1331,9 +3046,11 @@ fn handle_new_player(
     all_entries.push(new_entry.clone());
 
     let actions = player_info_actions::ADD_PLAYER
+        | player_info_actions::INITIALIZE_CHAT
         | player_info_actions::UPDATE_GAME_MODE
         | player_info_actions::UPDATE_LISTED
-        | player_info_actions::UPDATE_LATENCY;
+        | player_info_actions::UPDATE_LATENCY
+        | player_info_actions::UPDATE_DISPLAY_NAME;
 
     let _ = sender.send(InternalPacket::PlayerInfoUpdate {
         actions,
@@ -1349,11 +3066,10 @@ fn handle_new_player(
         },
     );
 
-    // Send tab list header/footer
-    let _ = sender.send(InternalPacket::SetTabListHeaderAndFooter {
-        header: TextComponent::plain("\u{00a7}6Pickaxe Server\u{00a7}r"),
-        footer: TextComponent::plain("\u{00a7}7Minecraft 1.21.1 \u{00a7}8| \u{00a7}7Powered by Rust"),
-    });
+    // Send scoreboard objectives, scores, and display slots
+    for packet in world_state.scoreboard.sync_packets() {
+        let _ = sender.send(packet);
+    }
 
     // Send inventory (loaded or empty)
     let _ = sender.send(InternalPacket::SetContainerContent {
@@ -1414,13 +3130,49 @@ fn handle_new_player(
         player_xp,
         AirSupply::default(),
         ActiveEffects::new(),
+        PeerAddress(new_player.address),
+        Homes(player_homes),
+        PlayerModData(player_mod_data),
+        new_player.channels,
+        LastSeen::default(),
+        SimulationDistance(simulation_distance),
+        settings,
+        chunk_sender,
     ));
+    let _ = world.insert(player_entity, (
+        NetworkStats(new_player.net_stats),
+        Activity::new(Instant::now()),
+        Statistics::load(std::path::Path::new(&config.world_dir), &profile.uuid),
+        AdvancementProgress::load(std::path::Path::new(&config.world_dir), &profile.uuid),
+    ));
+    if level != DEFAULT_LEVEL {
+        let _ = world.insert_one(player_entity, Dimension(level));
+    }
     if let Some((pos, yaw)) = player_spawn_point {
         let _ = world.insert_one(player_entity, SpawnPoint { position: pos, yaw });
     }
+    if new_player.transferred {
+        let _ = world.insert_one(player_entity, Transferred);
+    }
 
     inbound_receivers.insert(entity_id, new_player.packet_rx);
 
+    // Tell the client which channels mods listen on
+    let channels: Vec<String> = world_state.channels.lock().map(|c| c.keys().cloned().collect()).unwrap_or_default();
+    if !channels.is_empty() {
+        if let Ok(sender) = world.get::<&ConnectionSender>(player_entity) {
+            let _ = sender.0.send(InternalPacket::PluginMessage {
+                channel: crate::channels::REGISTER.into(),
+                data: crate::channels::channel_list_payload(&channels),
+            });
+        }
+    }
+
+    send_advancements(world, world_state, player_entity);
+
+    // Tab list header/footer for everyone, now that the player counts as online
+    send_tab_list_header(world, world_state, scripting, config);
+
     // Fire Lua event
     scripting.fire_event_in_context(
         "player_join",
@@ -1444,12 +3196,14 @@ fn handle_disconnect(
     let mut to_despawn = None;
     let mut player_uuid = None;
     let mut player_name = String::new();
+    let mut timed_out = None;
 
     for (e, (eid, profile)) in world.query::<(&EntityId, &Profile)>().iter() {
         if eid.0 == entity_id {
             to_despawn = Some(e);
             player_uuid = Some(profile.0.uuid);
             player_name = profile.0.name.clone();
+            timed_out = world.get::<&TimedOut>(e).ok().map(|reason| *reason);
             break;
         }
     }
@@ -1474,6 +3228,9 @@ fn handle_disconnect(
         }
 
         // Clean up open container (crafting grid items are lost on disconnect)
+        if let Some((gui_id, _, gui)) = take_custom_gui(world, world_state, entity) {
+            fire_gui_close(world, world_state, scripting, gui_id, gui, &player_name);
+        }
         let _ = world.remove_one::<OpenContainer>(entity);
 
         // Despawn any active fishing bobber
@@ -1500,14 +3257,19 @@ fn handle_disconnect(
         // Save player data BEFORE despawn (ECS components are needed)
         if let Some(uuid) = player_uuid {
             if let Some(data) = serialize_player_data(world, entity) {
-                let _ = world_state.save_tx.send(SaveOp::Player(uuid, data));
+                world_state.player_data.queue_save(uuid, &player_name, data, &world_state.save_tx);
+            }
+            if let Ok(mut stats) = world.get::<&mut Statistics>(entity) {
+                stats.custom("leave_game", 1);
             }
+            save_player_progress(world, world_state, entity, uuid);
         }
         let _ = world.despawn(entity);
     }
 
     if let Some(uuid) = player_uuid {
         info!("{} disconnected", player_name);
+        world_state.tpa.remove_player(&player_name);
 
         // Broadcast tab list removal
         broadcast_to_all(
@@ -1529,6 +3291,16 @@ fn handle_disconnect(
             }
         }
 
+        // Timeouts fire their own event ahead of the usual leave
+        if let Some(reason) = timed_out {
+            scripting.fire_event_in_context(
+                "player_timeout",
+                &[("name", &player_name), ("reason", reason.name())],
+                world as *mut _ as *mut (),
+                world_state as *mut _ as *mut (),
+            );
+        }
+
         // Fire Lua event
         scripting.fire_event_in_context(
             "player_leave",
@@ -1539,8 +3311,54 @@ fn handle_disconnect(
     }
 }
 
+/// Handle a player's packet with their level switched in. Entities other
+/// than players are only simulated in the default level, so any the packet
+/// spawns elsewhere (drops, arrows, timers) are removed again.
 fn process_packet(
-    _config: &ServerConfig,
+    config: &ServerConfig,
+    adapter: &V1_21Adapter,
+    world: &mut World,
+    world_state: &mut WorldState,
+    pkt: InboundPacket,
+    scripting: &ScriptRuntime,
+    lua_commands: &crate::bridge::LuaCommands,
+    block_overrides: &crate::bridge::BlockOverrides,
+    next_eid: &Arc<AtomicI32>,
+) {
+    let level = world
+        .query::<(&EntityId, &Dimension)>()
+        .iter()
+        .find(|(_, (eid, _))| eid.0 == pkt.entity_id)
+        .map(|(_, (_, dimension))| dimension.0.clone());
+    let Some(level) = level else {
+        process_player_packet(config, adapter, world, world_state, pkt, scripting, lua_commands, block_overrides, next_eid);
+        return;
+    };
+
+    let existing: HashSet<hecs::Entity> = world.iter().map(|e| e.entity()).collect();
+    world_state.switch_level(&level);
+    process_player_packet(config, adapter, world, world_state, pkt, scripting, lua_commands, block_overrides, next_eid);
+    world_state.switch_level(DEFAULT_LEVEL);
+
+    let spawned: Vec<hecs::Entity> = world
+        .iter()
+        .map(|e| e.entity())
+        .filter(|e| !existing.contains(e) && world.get::<&Profile>(*e).is_err())
+        .collect();
+    let mut removed_ids = Vec::new();
+    for entity in spawned {
+        if let Ok(eid) = world.get::<&EntityId>(entity) {
+            removed_ids.push(eid.0);
+        }
+        let _ = world.despawn(entity);
+    }
+    if !removed_ids.is_empty() {
+        broadcast_to_all(world, &InternalPacket::RemoveEntities { entity_ids: removed_ids });
+    }
+}
+
+fn process_player_packet(
+    config: &ServerConfig,
     _adapter: &V1_21Adapter,
     world: &mut World,
     world_state: &mut WorldState,
@@ -1567,9 +3385,24 @@ fn process_packet(
         }
     };
 
+    // Any packet shows the connection is alive
+    if let Ok(mut ka) = world.get::<&mut KeepAlive>(entity) {
+        ka.last_packet = Instant::now();
+    }
+    // but only the player's own actions show they're still playing
+    if is_player_input(world, entity, &pkt.packet) {
+        let returned = world.get::<&mut Activity>(entity).is_ok_and(|mut a| a.input(Instant::now()));
+        if returned {
+            set_afk(world, world_state, scripting, &config.afk, entity, false);
+        }
+    }
+
     match pkt.packet {
         InternalPacket::ConfirmTeleportation { teleport_id } => {
             debug!("Teleport confirmed: {}", teleport_id);
+            if world.get::<&PendingTeleport>(entity).is_ok_and(|p| p.id == teleport_id) {
+                let _ = world.remove_one::<PendingTeleport>(entity);
+            }
         }
 
         InternalPacket::PlayerPosition {
@@ -1626,8 +3459,7 @@ fn process_packet(
         InternalPacket::KeepAliveServerbound { id: ka_id } => {
             if let Ok(mut ka) = world.get::<&mut KeepAlive>(entity) {
                 if ka.pending == Some(ka_id) {
-                    ka.pending = None;
-                    ka.last_response = Instant::now();
+                    ka.record_response(Instant::now());
                 }
             }
         }
@@ -1917,17 +3749,39 @@ fn process_packet(
         }
 
         InternalPacket::BlockPlace {
+            hand,
             position,
             face,
+            cursor_y,
             sequence,
             ..
         } => {
             // Check if the target block is a container — open it instead of placing
             let target_block = world_state.get_block(&position);
             let target_name = pickaxe_data::block_state_to_name(target_block).unwrap_or("");
-            let is_container = matches!(target_name, "chest" | "furnace" | "lit_furnace" | "crafting_table" | "brewing_stand" | "anvil" | "chipped_anvil" | "damaged_anvil");
+            let is_container = matches!(target_name, "chest" | "furnace" | "lit_furnace" | "crafting_table" | "brewing_stand" | "anvil" | "chipped_anvil" | "damaged_anvil")
+                || (target_name == "lectern" && pickaxe_data::block_property(target_block, "has_book") == Some("true"));
             let sneaking = world.get::<&MovementState>(entity).map(|m| m.sneaking).unwrap_or(false);
 
+            // Lua block handlers see the click first; "cancel" skips the vanilla interaction and placement
+            if !sneaking {
+                let name = world.get::<&Profile>(entity).map(|p| p.0.name.clone()).unwrap_or_default();
+                let response = fire_block_handler(
+                    world, world_state, scripting, block_overrides, BlockHandler::Interact, &position, target_block,
+                    |event| {
+                        event.set("player", name)?;
+                        event.set("hand", if hand == 1 { "off_hand" } else { "main_hand" })?;
+                        event.set("face", face)
+                    },
+                );
+                if response.cancelled {
+                    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+                        let _ = sender.0.send(InternalPacket::AcknowledgeBlockChange { sequence });
+                    }
+                    return;
+                }
+            }
+
             if is_container && !sneaking {
                 let name = world.get::<&Profile>(entity).map(|p| p.0.name.clone()).unwrap_or_default();
                 let cancelled = scripting.fire_event_in_context(
@@ -1944,6 +3798,7 @@ fn process_packet(
                 );
 
                 if !cancelled {
+                    world_state.audit.record(&name, AuditAction::Container, Some(position), target_name);
                     open_container(world, world_state, entity, &position, target_name);
                 }
 
@@ -1953,6 +3808,22 @@ fn process_packet(
                 return;
             }
 
+            // Bells ring, lecterns take books, and campfires take raw food
+            let used = !sneaking && match target_name {
+                "bell" => try_ring_bell(world, world_state, entity, &position, target_block, face, cursor_y, scripting),
+                "lectern" => try_place_lectern_book(world, world_state, entity, &position, target_block, scripting),
+                "campfire" | "soul_campfire" => {
+                    try_add_campfire_food(world, world_state, entity, &position, target_name, scripting)
+                }
+                _ => false,
+            };
+            if used {
+                if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+                    let _ = sender.0.send(InternalPacket::AcknowledgeBlockChange { sequence });
+                }
+                return;
+            }
+
             // Check if the target block is a sign — open editor on right-click
             if pickaxe_data::is_sign_state(target_block) && !sneaking {
                 // Check if sign is waxed
@@ -1992,7 +3863,7 @@ fn process_packet(
                     if !cancelled {
                         // Toggle the block state
                         world_state.set_block(&position, new_state);
-                        broadcast_to_all(world, &InternalPacket::BlockUpdate {
+                        broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
                             position,
                             block_id: new_state,
                         });
@@ -2007,7 +3878,7 @@ fn process_packet(
                             let other_state = world_state.get_block(&other_pos);
                             if let Some(other_new) = pickaxe_data::toggle_interactive_block(other_state) {
                                 world_state.set_block(&other_pos, other_new);
-                                broadcast_to_all(world, &InternalPacket::BlockUpdate {
+                                broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
                                     position: other_pos,
                                     block_id: other_new,
                                 });
@@ -2066,6 +3937,16 @@ fn process_packet(
                 return;
             }
 
+            // Check if the target block is a cauldron — fill, empty, or wash with the held item
+            if target_name.ends_with("cauldron") && !sneaking
+                && try_cauldron_interaction(world, world_state, entity, &position, target_block, &next_eid, scripting)
+            {
+                if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+                    let _ = sender.0.send(InternalPacket::AcknowledgeBlockChange { sequence });
+                }
+                return;
+            }
+
             // Check for flint_and_steel on TNT block — ignite it
             if target_name == "tnt" {
                 let held_slot = world.get::<&HeldSlot>(entity).map(|h| h.0).unwrap_or(0);
@@ -2077,7 +3958,7 @@ fn process_packet(
                 if held_name == "flint_and_steel" {
                     // Remove TNT block
                     world_state.set_block(&position, 0);
-                    broadcast_to_all(world, &InternalPacket::BlockUpdate {
+                    broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
                         position,
                         block_id: 0,
                     });
@@ -2099,7 +3980,9 @@ fn process_packet(
                         let slot_index = 36 + held_slot as usize;
                         if let Ok(mut inv) = world.get::<&mut Inventory>(entity) {
                             if let Some(ref mut tool) = inv.slots[slot_index] {
-                                tool.damage += 1;
+                                if !tool.unbreakable {
+                                    tool.damage += 1;
+                                }
                                 if tool.max_damage > 0 && tool.damage >= tool.max_damage {
                                     inv.slots[slot_index] = None;
                                 }
@@ -2173,8 +4056,9 @@ fn process_packet(
                                 world_state as *mut _ as *mut (),
                             );
                             if !cancelled {
+                                world_state.audit.record(&player_name, AuditAction::Place, Some(fire_pos), "fire");
                                 world_state.set_block(&fire_pos, fire_state);
-                                broadcast_to_all(world, &InternalPacket::BlockUpdate {
+                                broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
                                     position: fire_pos,
                                     block_id: fire_state,
                                 });
@@ -2187,7 +4071,9 @@ fn process_packet(
                                 let slot_index = 36 + held_slot as usize;
                                 if let Ok(mut inv) = world.get::<&mut Inventory>(entity) {
                                     if let Some(ref mut tool) = inv.slots[slot_index] {
-                                        tool.damage += 1;
+                                        if !tool.unbreakable {
+                                            tool.damage += 1;
+                                        }
                                         if tool.max_damage > 0 && tool.damage >= tool.max_damage {
                                             inv.slots[slot_index] = None;
                                         }
@@ -2222,21 +4108,30 @@ fn process_packet(
 
                 match held_name {
                     "water_bucket" | "lava_bucket" => {
-                        // Place water/lava source at target face
-                        let place_pos = offset_by_face(&position, face);
+                        // Water buckets fill waterloggable blocks (slabs, stairs, fences) in place
+                        let waterlogged_state = if held_name == "water_bucket" && !pickaxe_data::is_waterlogged(target_block) {
+                            pickaxe_data::set_waterlogged(target_block, true)
+                        } else {
+                            None
+                        };
+
+                        // Otherwise place water/lava source at target face
+                        let place_pos = if waterlogged_state.is_some() { position } else { offset_by_face(&position, face) };
                         let place_block = world_state.get_block(&place_pos);
                         let place_name = pickaxe_data::block_state_to_name(place_block).unwrap_or("");
 
-                        if place_block == 0 || pickaxe_data::is_fluid_destructible(place_name)
+                        if waterlogged_state.is_some() || place_block == 0 || pickaxe_data::is_fluid_destructible(place_name)
                             || pickaxe_data::is_fluid(place_block) {
-                            let source_state = if held_name == "water_bucket" {
+                            let source_state = if let Some(state) = waterlogged_state {
+                                state
+                            } else if held_name == "water_bucket" {
                                 pickaxe_data::WATER_SOURCE
                             } else {
                                 pickaxe_data::LAVA_SOURCE
                             };
 
                             world_state.set_block(&place_pos, source_state);
-                            broadcast_to_all(world, &InternalPacket::BlockUpdate {
+                            broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
                                 position: place_pos,
                                 block_id: source_state,
                             });
@@ -2272,24 +4167,31 @@ fn process_packet(
                         }
                     }
                     "bucket" => {
-                        // Pick up water/lava source with empty bucket
-                        // Check the block at cursor position (not offset)
-                        let pickup_pos = offset_by_face(&position, face);
+                        // Drain a waterlogged block at the cursor, otherwise pick up the
+                        // water/lava source in front of the clicked face
+                        let drained_state = if pickaxe_data::is_waterlogged(target_block) {
+                            pickaxe_data::set_waterlogged(target_block, false)
+                        } else {
+                            None
+                        };
+                        let pickup_pos = if drained_state.is_some() { position } else { offset_by_face(&position, face) };
                         let pickup_block = world_state.get_block(&pickup_pos);
 
-                        if pickaxe_data::is_fluid_source(pickup_block) {
-                            let filled_id = if pickaxe_data::is_water(pickup_block) { 909 } else { 910 };
-                            let sound = if pickaxe_data::is_water(pickup_block) {
+                        if drained_state.is_some() || pickaxe_data::is_fluid_source(pickup_block) {
+                            let from_water = drained_state.is_some() || pickaxe_data::is_water(pickup_block);
+                            let filled_id = if from_water { 909 } else { 910 };
+                            let sound = if from_water {
                                 "item.bucket.fill"
                             } else {
                                 "item.bucket.fill_lava"
                             };
 
-                            // Remove the source block
-                            world_state.set_block(&pickup_pos, 0);
-                            broadcast_to_all(world, &InternalPacket::BlockUpdate {
+                            // Remove the source block (or just the water from a waterlogged block)
+                            let remaining = drained_state.unwrap_or(0);
+                            world_state.set_block(&pickup_pos, remaining);
+                            broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
                                 position: pickup_pos,
-                                block_id: 0,
+                                block_id: remaining,
                             });
 
                             play_sound_at_block(world, &pickup_pos, sound, SOUND_PLAYERS, 1.0, 1.0);
@@ -2348,7 +4250,7 @@ fn process_packet(
                                 // Convert to farmland (moisture=0)
                                 let farmland = pickaxe_data::farmland_state(0);
                                 world_state.set_block(&position, farmland);
-                                broadcast_to_all(world, &InternalPacket::BlockUpdate {
+                                broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
                                     position,
                                     block_id: farmland,
                                 });
@@ -2361,7 +4263,9 @@ fn process_packet(
                                     let slot_index = 36 + held_slot as usize;
                                     if let Ok(mut inv) = world.get::<&mut Inventory>(entity) {
                                         if let Some(ref mut hoe_item) = inv.slots[slot_index] {
-                                            hoe_item.damage += 1;
+                                            if !hoe_item.unbreakable {
+                                                hoe_item.damage += 1;
+                                            }
                                             if hoe_item.max_damage > 0 && hoe_item.damage >= hoe_item.max_damage {
                                                 inv.slots[slot_index] = None;
                                             }
@@ -2392,7 +4296,7 @@ fn process_packet(
                             let above_block = world_state.get_block(&plant_pos);
                             if above_block == 0 {
                                 world_state.set_block(&plant_pos, crop_state);
-                                broadcast_to_all(world, &InternalPacket::BlockUpdate {
+                                broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
                                     position: plant_pos,
                                     block_id: crop_state,
                                 });
@@ -2441,14 +4345,14 @@ fn process_packet(
                             let stages = rng.gen_range(2..=5);
                             if let Some(new_state) = pickaxe_data::crop_grow(target_block, stages) {
                                 world_state.set_block(&position, new_state);
-                                broadcast_to_all(world, &InternalPacket::BlockUpdate {
+                                broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
                                     position,
                                     block_id: new_state,
                                 });
 
                                 // Green particle effect (block event level 15 = bone meal)
                                 // Send WorldEvent 1505 for bone meal particles
-                                broadcast_to_all(world, &InternalPacket::WorldEvent {
+                                broadcast_to_level(world, world_state, &InternalPacket::WorldEvent {
                                     event: 1505,
                                     position,
                                     data: 0,
@@ -2541,7 +4445,7 @@ fn process_packet(
 
                 // Check if head position is clear
                 let head_block = world_state.get_block(&head_pos);
-                if head_block != 0 {
+                if head_block != 0 || is_placement_obstructed(world, &target) || is_placement_obstructed(world, &head_pos) {
                     // Can't place bed — head position blocked
                     if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
                         let _ = sender.0.send(InternalPacket::AcknowledgeBlockChange { sequence });
@@ -2557,8 +4461,8 @@ fn process_packet(
                 world_state.set_block(&target, foot_state);
                 world_state.set_block(&head_pos, head_state);
 
-                broadcast_to_all(world, &InternalPacket::BlockUpdate { position: target, block_id: foot_state });
-                broadcast_to_all(world, &InternalPacket::BlockUpdate { position: head_pos, block_id: head_state });
+                broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate { position: target, block_id: foot_state });
+                broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate { position: head_pos, block_id: head_state });
 
                 if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
                     let _ = sender.0.send(InternalPacket::AcknowledgeBlockChange { sequence });
@@ -2617,7 +4521,13 @@ fn process_packet(
                             let yaw = world.get::<&Rotation>(entity).map(|r| r.yaw).unwrap_or(0.0);
                             (pickaxe_data::standing_sign_state(standing_min, yaw), false)
                         };
-                        let _ = is_wall;
+                        // Standing signs need a block below, wall signs need the clicked block
+                        let support_pos = if is_wall { position } else { BlockPos::new(target.x, target.y - 1, target.z) };
+                        let sign_name = pickaxe_data::block_state_to_name(sign_state).unwrap_or("");
+                        if face == 0 || !pickaxe_data::can_support(world_state.get_block(&support_pos), sign_name) {
+                            reject_block_place(world, world_state, entity, &target, sequence);
+                            return;
+                        }
 
                         let player_name = world.get::<&Profile>(entity).map(|p| p.0.name.clone()).unwrap_or_default();
                         let cancelled = scripting.fire_event_in_context(
@@ -2639,6 +4549,7 @@ fn process_packet(
                             return;
                         }
 
+                        world_state.audit.record(&player_name, AuditAction::Place, Some(target), sign_name);
                         world_state.set_block(&target, sign_state);
                         world_state.set_block_entity(target, BlockEntity::Sign {
                             front_text: [String::new(), String::new(), String::new(), String::new()],
@@ -2648,7 +4559,7 @@ fn process_packet(
                             is_waxed: false,
                         });
 
-                        broadcast_to_all(world, &InternalPacket::BlockUpdate {
+                        broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
                             position: target,
                             block_id: sign_state,
                         });
@@ -2724,6 +4635,9 @@ fn process_packet(
                 return;
             }
 
+            let placed_name = pickaxe_data::block_state_to_name(block_id).unwrap_or("unknown");
+            world_state.audit.record(&name, AuditAction::Place, Some(target), placed_name);
+
             // Special handling for directional redstone components
             let block_id = {
                 let block_name = pickaxe_data::block_state_to_name(block_id).unwrap_or("");
@@ -2764,11 +4678,69 @@ fn process_packet(
                     let pitch = world.get::<&Rotation>(entity).map(|r| r.pitch).unwrap_or(0.0);
                     let facing6 = pickaxe_data::yaw_pitch_to_facing6(yaw, pitch);
                     pickaxe_data::piston_state(facing6, false, block_name == "sticky_piston")
+                } else if matches!(block_name, "torch" | "soul_torch" | "ladder") && (2..=5).contains(&face) {
+                    // Wall-mounted variant facing away from the clicked block
+                    let wall_name = match block_name {
+                        "torch" => "wall_torch",
+                        "soul_torch" => "soul_wall_torch",
+                        _ => "ladder",
+                    };
+                    let facing = match face {
+                        2 => "north",
+                        3 => "south",
+                        4 => "west",
+                        _ => "east",
+                    };
+                    pickaxe_data::block_name_with_properties_to_state(wall_name, &[("facing", facing)])
+                        .unwrap_or(block_id)
+                } else if matches!(block_name, "lectern" | "campfire" | "soul_campfire" | "bell") {
+                    const FACINGS: [&str; 4] = ["north", "south", "west", "east"];
+                    let yaw = world.get::<&Rotation>(entity).map(|r| r.yaw).unwrap_or(0.0);
+                    let look = pickaxe_data::yaw_to_facing(yaw);
+                    if block_name != "bell" {
+                        // Lecterns and campfires face the player
+                        let facing = FACINGS[pickaxe_data::opposite_facing(look) as usize];
+                        pickaxe_data::set_block_property(block_id, "facing", facing).unwrap_or(block_id)
+                    } else if face < 2 {
+                        let attachment = if face == 0 { "ceiling" } else { "floor" };
+                        pickaxe_data::block_name_with_properties_to_state("bell", &[
+                            ("attachment", attachment),
+                            ("facing", FACINGS[look as usize]),
+                        ]).unwrap_or(block_id)
+                    } else {
+                        // Wall bells hang off the clicked block, and span a gap between two blocks
+                        let (dx, dz) = match face { 2 => (0, -1), 3 => (0, 1), 4 => (-1, 0), _ => (1, 0) };
+                        let far = BlockPos::new(target.x + dx, target.y, target.z + dz);
+                        let double = pickaxe_data::is_solid_block(world_state.get_block(&far));
+                        let facing = match face { 2 => "south", 3 => "north", 4 => "east", _ => "west" };
+                        pickaxe_data::block_name_with_properties_to_state("bell", &[
+                            ("attachment", if double { "double_wall" } else { "single_wall" }),
+                            ("facing", facing),
+                        ]).unwrap_or(block_id)
+                    }
                 } else {
                     block_id
                 }
             };
 
+            // Attachments need something to hang on to, and solid blocks can't go inside entities
+            let placed_name = pickaxe_data::block_state_to_name(block_id).unwrap_or("");
+            let unsupported = pickaxe_data::support_offset(block_id).is_some_and(|(sx, sy, sz)| {
+                let support_pos = BlockPos::new(target.x + sx, target.y + sy, target.z + sz);
+                !pickaxe_data::can_support(world_state.get_block(&support_pos), placed_name)
+            }) || (placed_name == "ladder" && face < 2);
+            if unsupported || (pickaxe_data::has_collision(placed_name) && is_placement_obstructed(world, &target)) {
+                reject_block_place(world, world_state, entity, &target, sequence);
+                return;
+            }
+
+            // Placing a waterloggable block into a water source keeps the water
+            let block_id = if world_state.get_block(&target) == pickaxe_data::WATER_SOURCE {
+                pickaxe_data::set_waterlogged(block_id, true).unwrap_or(block_id)
+            } else {
+                block_id
+            };
+
             world_state.set_block(&target, block_id);
 
             // Create block entity for container blocks
@@ -2794,6 +4766,19 @@ fn process_packet(
                         fuel_uses: 0,
                     });
                 }
+                "bell" => {
+                    world_state.set_block_entity(target, BlockEntity::Bell { ringing_ticks: 0 });
+                }
+                "lectern" => {
+                    world_state.set_block_entity(target, BlockEntity::Lectern { book: None, page: 0 });
+                }
+                "campfire" | "soul_campfire" => {
+                    world_state.set_block_entity(target, BlockEntity::Campfire {
+                        items: std::array::from_fn(|_| None),
+                        cook_times: [0; 4],
+                        cook_totals: [0; 4],
+                    });
+                }
                 _ => {}
             }
 
@@ -2824,8 +4809,9 @@ fn process_packet(
                     .send(InternalPacket::AcknowledgeBlockChange { sequence });
             }
             // Broadcast to others
-            broadcast_except(
+            broadcast_to_level_except(
                 world,
+                world_state,
                 entity_id,
                 &InternalPacket::BlockUpdate {
                     position: target,
@@ -2845,39 +4831,106 @@ fn process_packet(
             debug!("{} placed block at {:?}", name, target);
         }
 
-        InternalPacket::ChatMessage { message, .. } => {
-            let name = world
-                .get::<&Profile>(entity)
-                .map(|p| p.0.name.clone())
-                .unwrap_or_default();
+        InternalPacket::ChatMessage { message, timestamp, salt, signature, offset, acknowledged, .. } => {
+            let (name, uuid) = match world.get::<&Profile>(entity) {
+                Ok(p) => (p.0.name.clone(), p.0.uuid),
+                Err(_) => return,
+            };
+            // Nothing sees the message until its signature checks out
+            let signed = match verify_chat(world, world_state, entity, &message, timestamp, salt, signature, offset, acknowledged) {
+                Ok(signed) => signed,
+                Err(ChatRejection::Refuse(reason)) => {
+                    send_message(world, entity, reason);
+                    return;
+                }
+                Err(ChatRejection::Kick(reason)) => {
+                    warn!("Disconnecting {}: {}", name, reason);
+                    disconnect(world, entity, TextComponent::plain(reason));
+                    return;
+                }
+            };
             info!("<{}> {}", name, message);
 
-            // Fire Lua event
-            let cancelled = scripting.fire_event_in_context(
+            // Fire Lua event; handlers may rewrite the message or the format,
+            // where {name} and {message} are filled in
+            let mut fields = [
+                ("name", name.clone()),
+                ("message", message.clone()),
+                ("format", DEFAULT_CHAT_FORMAT.to_string()),
+            ];
+            let cancelled = scripting.fire_event_mut_in_context(
                 "player_chat",
-                &[("name", &name), ("message", &message)],
+                &mut fields,
                 world as *mut _ as *mut (),
                 world_state as *mut _ as *mut (),
             );
+            let [_, (_, new_message), (_, format)] = fields;
 
             if !cancelled {
-                let chat_text = format!("<{}> {}", name, message);
-                broadcast_to_all(
-                    world,
-                    &InternalPacket::SystemChatMessage {
-                        content: TextComponent::plain(&chat_text),
-                        overlay: false,
-                    },
-                );
+                // Unchanged chat is rendered by the client; otherwise the
+                // formatted text is shown in place of the signed message
+                let formatted = if new_message == message && format == DEFAULT_CHAT_FORMAT {
+                    None
+                } else {
+                    // Placeholders are resolved in the format only, so players
+                    // can't expand them by typing them
+                    let format = format_text(world, world_state, scripting, &format, Some(&name));
+                    let chat_text = format.replace("{name}", &name).replace("{message}", &new_message);
+                    Some(TextComponent::plain(&chat_text))
+                };
+                broadcast_player_chat(world, uuid, &name, &message, timestamp, salt, signed, formatted);
+            }
+        }
+
+        InternalPacket::ChatAck { offset } => {
+            if world_state.secure_chat.enabled {
+                let valid = world
+                    .get::<&mut LastSeen>(entity)
+                    .map(|mut last_seen| last_seen.0.apply_offset(offset).is_ok())
+                    .unwrap_or(true);
+                if !valid {
+                    disconnect(world, entity, TextComponent::plain(CHAT_VALIDATION_FAILED));
+                }
+            }
+        }
+
+        InternalPacket::PlayerSession { session } => {
+            handle_player_session(world, world_state, entity, session);
+        }
+
+        InternalPacket::ChunkBatchReceived { chunks_per_tick } => {
+            if let Ok(mut chunk_sender) = world.get::<&mut ChunkSender>(entity) {
+                chunk_sender.acknowledge(chunks_per_tick);
             }
         }
 
+        InternalPacket::ClientInformation { locale, view_distance, .. } => {
+            handle_client_information(config, world, entity, locale, view_distance as i32);
+        }
+
         InternalPacket::ChatCommand { command } => {
             let name = world
                 .get::<&Profile>(entity)
                 .map(|p| p.0.name.clone())
                 .unwrap_or_default();
+            // command_preprocess handlers may rewrite the command line or cancel it
+            let mut fields = [("name", name.clone()), ("command", command)];
+            let cancelled = scripting.fire_event_mut_in_context(
+                "command_preprocess",
+                &mut fields,
+                world as *mut _ as *mut (),
+                world_state as *mut _ as *mut (),
+            );
+            let [_, (_, command)] = fields;
+            if cancelled {
+                return;
+            }
             info!("{} issued command: /{}", name, command);
+            let block_pos = world
+                .get::<&Position>(entity)
+                .map(|p| BlockPos::new(p.0.x.floor() as i32, p.0.y.floor() as i32, p.0.z.floor() as i32))
+                .ok();
+            world_state.audit.record(&name, AuditAction::Command, block_pos, &format!("/{}", command));
 
             scripting.fire_event_in_context(
                 "player_command",
@@ -2886,63 +4939,12 @@ fn process_packet(
                 world_state as *mut _ as *mut (),
             );
 
-            let parts: Vec<&str> = command.splitn(2, ' ').collect();
-            let cmd_name = parts[0];
-            let args = if parts.len() > 1 { parts[1] } else { "" };
-
-            match cmd_name {
-                "gamemode" | "gm" => cmd_gamemode(world, entity, args),
-                "tp" | "teleport" => cmd_tp(world, entity, args),
-                "give" => cmd_give(world, entity, args),
-                "kill" => cmd_kill(world, world_state, entity, entity_id, scripting),
-                "say" => cmd_say(world, args, &name),
-                "help" => cmd_help(world, entity, lua_commands),
-                "time" => cmd_time(world, entity, args, world_state),
-                "effect" => cmd_effect(world, entity, args),
-                "potion" => cmd_potion(world, entity, args),
-                "enchant" => cmd_enchant(world, entity, args),
-                _ => {
-                    // Check Lua-registered commands
-                    let handled = if let Ok(cmds) = lua_commands.lock() {
-                        if let Some(lua_cmd) = cmds.iter().find(|c| c.name == cmd_name) {
-                            let lua = scripting.lua();
-                            // Set game context so bridge APIs work inside command handlers
-                            lua.set_app_data(pickaxe_scripting::bridge::LuaGameContext {
-                                world_ptr: world as *mut _ as *mut (),
-                                world_state_ptr: world_state as *mut _ as *mut (),
-                            });
-                            let func: mlua::Result<mlua::Function> =
-                                lua.registry_value(&lua_cmd.handler_key);
-                            let result = if let Ok(func) = func {
-                                if let Err(e) = func.call::<()>((name.clone(), args.to_string())) {
-                                    warn!("Lua command /{} error: {}", cmd_name, e);
-                                    send_message(
-                                        world,
-                                        entity,
-                                        &format!("Command error: {}", e),
-                                    );
-                                }
-                                true
-                            } else {
-                                false
-                            };
-                            lua.remove_app_data::<pickaxe_scripting::bridge::LuaGameContext>();
-                            result
-                        } else {
-                            false
-                        }
-                    } else {
-                        false
-                    };
-                    if !handled {
-                        send_message(
-                            world,
-                            entity,
-                            &format!("Unknown command: /{}", cmd_name),
-                        );
-                    }
-                }
-            }
+            let ctx = execution_context(world, entity);
+            run_command(config, world, world_state, &ctx, &command, scripting, lua_commands);
+        }
+
+        InternalPacket::CommandSuggestionsRequest { transaction_id, text } => {
+            handle_command_suggestions(world, world_state, entity, transaction_id, &text, scripting, lua_commands);
         }
 
         InternalPacket::HeldItemChange { slot } => {
@@ -3015,26 +5017,65 @@ fn process_packet(
             }
         }
 
-        InternalPacket::ClientCommand { action } => {
-            if action == 0 {
-                respawn_player(world, world_state, entity, entity_id, scripting);
+        InternalPacket::ClientCommand { action } => match action {
+            0 => respawn_player(world, world_state, entity, entity_id, scripting),
+            1 => {
+                // Request stats: the client's statistics screen
+                let stats = world.get::<&Statistics>(entity).map(|s| s.packet_entries()).unwrap_or_default();
+                if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+                    let _ = sender.0.send(InternalPacket::AwardStatistics { stats });
+                }
             }
-        }
+            _ => {}
+        },
 
         InternalPacket::ClientCloseContainer { container_id } => {
             close_container(world, world_state, entity, container_id, next_eid, scripting);
         }
 
         InternalPacket::ContainerClick { window_id, state_id, slot, button, mode, ref changed_slots, ref carried_item } => {
-            handle_container_click(world, world_state, entity, window_id, state_id, slot, button, mode, changed_slots, carried_item);
+            handle_container_click(world, world_state, entity, window_id, state_id, slot, button, mode, changed_slots, carried_item, scripting);
             // Broadcast equipment if armor/held slots may have changed
             send_equipment_update(world, entity, entity_id);
         }
 
+        InternalPacket::ContainerButtonClick { window_id, button_id } => {
+            handle_container_button(world, world_state, entity, window_id, button_id, next_eid, scripting);
+        }
+
         InternalPacket::RenameItem { ref name } => {
             handle_anvil_rename(world, entity, name);
         }
 
+        InternalPacket::PluginMessage { ref channel, ref data } => {
+            let recorded = world
+                .get::<&mut ClientChannels>(entity)
+                .map(|mut channels| channels.record(channel, data))
+                .unwrap_or(false);
+            if !recorded {
+                handle_plugin_message(world, world_state, entity, channel, data, scripting);
+            }
+        }
+
+        InternalPacket::CookieResponse { ref key, ref payload } => {
+            handle_cookie_response(world, world_state, entity, key, payload.as_deref(), scripting);
+        }
+
+        InternalPacket::ResourcePackResponse { uuid, result } => {
+            let name = match world.get::<&Profile>(entity) {
+                Ok(p) => p.0.name.clone(),
+                Err(_) => return,
+            };
+            let status = crate::resource_pack::status_name(result);
+            debug!("{} resource pack {}: {}", name, uuid, status);
+            scripting.fire_event_in_context(
+                "resource_pack_status",
+                &[("name", &name), ("id", &uuid.to_string()), ("status", status)],
+                world as *mut _ as *mut (),
+                world_state as *mut _ as *mut (),
+            );
+        }
+
         InternalPacket::SignUpdate { position, is_front_text, ref lines } => {
             // Update the sign block entity with the text from the client
             if let Some(be) = world_state.get_block_entity_mut(&position) {
@@ -3055,7 +5096,7 @@ fn process_packet(
             if let Some(be) = world_state.get_block_entity(&position) {
                 if matches!(be, BlockEntity::Sign { .. }) {
                     let nbt = build_sign_update_nbt(be);
-                    broadcast_to_all(world, &InternalPacket::BlockEntityData {
+                    broadcast_to_level(world, world_state, &InternalPacket::BlockEntityData {
                         position,
                         block_entity_type: 7, // sign
                         nbt,
@@ -3065,7 +5106,7 @@ fn process_packet(
 
             // Save the chunk containing this sign
             let chunk_pos = position.chunk_pos();
-            world_state.queue_chunk_save(chunk_pos);
+            world_state.mark_chunk_dirty(chunk_pos);
 
             let player_name = world.get::<&Profile>(entity).map(|p| p.0.name.clone()).unwrap_or_default();
             debug!("{} updated sign at {:?}", player_name, position);
@@ -3078,7 +5119,7 @@ fn process_packet(
             }
 
             // Get the item in the used hand
-            let item_id = {
+            let (item_id, custom_id) = {
                 let held_slot = world.get::<&HeldSlot>(entity).map(|h| h.0).unwrap_or(0);
                 let inv = match world.get::<&Inventory>(entity) {
                     Ok(inv) => inv,
@@ -3086,11 +5127,25 @@ fn process_packet(
                 };
                 let slot_idx = if hand == 1 { 45 } else { 36 + held_slot as usize };
                 match &inv.slots[slot_idx] {
-                    Some(item) => item.item_id,
+                    Some(item) => (item.item_id, item.custom_id.clone()),
                     None => return,
                 }
             };
 
+            // Lua custom items run their on_use callback, which may cancel the base item's behaviour
+            if let Some(custom_id) = custom_id {
+                let lua = scripting.lua();
+                if let Some(func) = crate::bridge::custom_item_callback(lua, &world_state.custom_items, &custom_id, false) {
+                    let player = world.get::<&Profile>(entity).map(|p| p.0.name.clone()).unwrap_or_default();
+                    let cancelled = with_lua_context(world, world_state, scripting, |lua| {
+                        crate::bridge::call_custom_item_use(lua, func, &player, &custom_id, hand)
+                    });
+                    if cancelled {
+                        return;
+                    }
+                }
+            }
+
             // Check if item is a shield
             let shield_id = pickaxe_data::item_name_to_id("shield").unwrap_or(1162);
             if item_id == shield_id {
@@ -3199,7 +5254,9 @@ fn process_packet(
                         };
                         if let Ok(mut inv) = world.get::<&mut Inventory>(entity) {
                             if let Some(ref mut rod_item) = inv.slots[held_slot_idx] {
-                                rod_item.damage += rod_damage;
+                                if !rod_item.unbreakable {
+                                    rod_item.damage += rod_damage;
+                                }
                                 if rod_item.max_damage > 0 && rod_item.damage >= rod_item.max_damage {
                                     inv.slots[held_slot_idx] = None;
                                     // Play break sound
@@ -3276,6 +5333,22 @@ fn process_packet(
                 return;
             }
 
+            // Glass bottle: fill with water from the source block the player is looking at
+            let glass_bottle_id = pickaxe_data::item_name_to_id("glass_bottle").unwrap_or(0);
+            if item_id == glass_bottle_id {
+                if let Some(water_pos) = raycast_water_source(world, world_state, entity) {
+                    let slot_idx = {
+                        let held_slot = world.get::<&HeldSlot>(entity).map(|h| h.0).unwrap_or(0);
+                        if hand == 1 { 45 } else { 36 + held_slot as usize }
+                    };
+                    // Potion damage 0 = water bottle
+                    let potion_id = pickaxe_data::item_name_to_id("potion").unwrap_or(0);
+                    exchange_held_item(world, world_state, entity, slot_idx, ItemStack::new(potion_id, 1), next_eid, scripting);
+                    play_sound_at_block(world, &water_pos, "item.bottle.fill", SOUND_NEUTRAL, 1.0, 1.0);
+                }
+                return;
+            }
+
             // Check if the item is a drinkable potion
             if pickaxe_data::is_potion(item_id) {
                 // Potions always drinkable, use 32-tick drink time
@@ -3319,8 +5392,10 @@ fn process_packet(
 
         InternalPacket::InteractEntity { entity_id: target_eid, action_type, sneaking, .. } => {
             if action_type == 1 {
-                // ATTACK action
-                handle_attack(world, world_state, entity, entity_id, target_eid, scripting, next_eid);
+                // ATTACK action; a Lua custom item in the main hand may cancel it
+                if !fire_custom_item_attack(world, world_state, scripting, entity, target_eid) {
+                    handle_attack(world, world_state, entity, entity_id, target_eid, scripting, next_eid);
+                }
             }
             let _ = sneaking; // used for future interact mechanics
         }
@@ -3364,15 +5439,11 @@ fn open_container(
             rename: None,
             repair_cost: 0,
         }),
+        "lectern" => (17, "Lectern", Menu::Lectern { pos: *pos }),
         _ => return,
     };
 
-    // Assign container ID (1-255, never 0)
-    let container_id = {
-        let old = world.get::<&OpenContainer>(entity).map(|c| c.container_id).unwrap_or(0);
-        old.wrapping_add(1).max(1)
-    };
-
+    let container_id = next_container_id(world, entity);
     let slots = build_container_slots(world_state, world, entity, &menu);
 
     if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
@@ -3397,6 +5468,12 @@ fn open_container(
                 let _ = sender.0.send(InternalPacket::SetContainerData { container_id, property: 3, value: *cook_total });
             }
         }
+        // For lecterns, send the open page
+        if block_name == "lectern" {
+            if let Some(BlockEntity::Lectern { page, .. }) = world_state.get_block_entity(pos) {
+                let _ = sender.0.send(InternalPacket::SetContainerData { container_id, property: 0, value: *page as i16 });
+            }
+        }
         // For brewing stands, send current brew time and fuel
         if block_name == "brewing_stand" {
             if let Some(BlockEntity::BrewingStand { brew_time, fuel_uses, .. }) = world_state.get_block_entity(pos) {
@@ -3410,40 +5487,459 @@ fn open_container(
         container_id,
         menu,
         state_id: 1,
+        carried: None,
     });
 }
 
-fn build_container_slots(
-    world_state: &WorldState,
-    world: &World,
+/// Assign a container ID (1-255, never 0) for the next screen a player opens.
+fn next_container_id(world: &World, entity: hecs::Entity) -> u8 {
+    let old = world.get::<&OpenContainer>(entity).map(|c| c.container_id).unwrap_or(0);
+    old.wrapping_add(1).max(1)
+}
+
+/// Open a `pickaxe.gui` menu of `rows` rows of 9 empty slots and return its id.
+pub(crate) fn open_custom_gui(
+    world: &mut World,
+    world_state: &mut WorldState,
     entity: hecs::Entity,
-    menu: &Menu,
-) -> Vec<Option<ItemStack>> {
-    let player_inv = world.get::<&Inventory>(entity).ok();
+    rows: u8,
+    title: TextComponent,
+) -> u64 {
+    world_state.next_gui_id += 1;
+    let gui_id = world_state.next_gui_id;
+    let container_id = next_container_id(world, entity);
+    let menu = Menu::Custom { gui_id, slots: vec![None; rows as usize * 9] };
+    let slots = build_container_slots(world_state, world, entity, &menu);
+    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+        // generic_9x1 through generic_9x6 are menu types 0-5
+        let _ = sender.0.send(InternalPacket::OpenScreen {
+            container_id: container_id as i32,
+            menu_type: rows as i32 - 1,
+            title,
+        });
+        let _ = sender.0.send(InternalPacket::SetContainerContent {
+            window_id: container_id,
+            state_id: 1,
+            slots,
+            carried_item: None,
+        });
+    }
+    let _ = world.insert_one(entity, OpenContainer { container_id, menu, state_id: 1, carried: None });
+    world_state.guis.insert(gui_id, crate::bridge::LuaGui { player: entity, on_click: None, on_close: None });
+    gui_id
+}
 
-    match menu {
-        Menu::Chest { pos } => {
-            let mut slots = Vec::with_capacity(63);
-            if let Some(BlockEntity::Chest { inventory }) = world_state.get_block_entity(pos) {
-                slots.extend_from_slice(inventory);
-            } else {
-                slots.resize(27, None);
-            }
-            if let Some(inv) = &player_inv {
-                for i in 9..36 { slots.push(inv.slots[i].clone()); }
-                for i in 36..45 { slots.push(inv.slots[i].clone()); }
-            } else {
-                slots.resize(63, None);
-            }
-            slots
-        }
-        Menu::Furnace { pos } => {
-            let mut slots = Vec::with_capacity(39);
-            if let Some(BlockEntity::Furnace { input, fuel, output, .. }) = world_state.get_block_entity(pos) {
-                slots.push(input.clone());
-                slots.push(fuel.clone());
-                slots.push(output.clone());
-            } else {
+/// Close the player's `pickaxe.gui` menu on the server side, if one is open,
+/// returning its id and callbacks. The caller tells the client and runs `on_close`.
+pub(crate) fn take_custom_gui(
+    world: &mut World,
+    world_state: &mut WorldState,
+    entity: hecs::Entity,
+) -> Option<(u64, u8, crate::bridge::LuaGui)> {
+    let (gui_id, container_id) = match world.get::<&OpenContainer>(entity) {
+        Ok(open) => match open.menu {
+            Menu::Custom { gui_id, .. } => (gui_id, open.container_id),
+            _ => return None,
+        },
+        Err(_) => return None,
+    };
+    let _ = world.remove_one::<OpenContainer>(entity);
+    Some((gui_id, container_id, world_state.guis.remove(&gui_id)?))
+}
+
+/// Run a `pickaxe.gui` close callback with the game context set.
+fn fire_gui_close(
+    world: &mut World,
+    world_state: &mut WorldState,
+    scripting: &ScriptRuntime,
+    gui_id: u64,
+    gui: crate::bridge::LuaGui,
+    player: &str,
+) {
+    let lua = scripting.lua();
+    let Some(func) = gui.on_close.and_then(|key| lua.registry_value::<mlua::Function>(&key).ok()) else {
+        return;
+    };
+    with_lua_context(world, world_state, scripting, |lua| crate::bridge::call_gui_close(lua, func, gui_id, player));
+}
+
+/// Run `f` with the game context set, so Lua callbacks can use the game APIs.
+fn with_lua_context<R>(
+    world: &mut World,
+    world_state: &mut WorldState,
+    scripting: &ScriptRuntime,
+    f: impl FnOnce(&mlua::Lua) -> R,
+) -> R {
+    let lua = scripting.lua();
+    lua.set_app_data(pickaxe_scripting::bridge::LuaGameContext {
+        world_ptr: world as *mut _ as *mut (),
+        world_state_ptr: world_state as *mut _ as *mut (),
+    });
+    let result = f(lua);
+    lua.remove_app_data::<pickaxe_scripting::bridge::LuaGameContext>();
+    result
+}
+
+/// Pass a plugin message on a mod's channel to its Lua handler.
+fn handle_plugin_message(
+    world: &mut World,
+    world_state: &mut WorldState,
+    entity: hecs::Entity,
+    channel: &str,
+    data: &[u8],
+    scripting: &ScriptRuntime,
+) {
+    let name = match world.get::<&Profile>(entity) {
+        Ok(p) => p.0.name.clone(),
+        Err(_) => return,
+    };
+    let channels = world_state.channels.clone();
+    with_lua_context(world, world_state, scripting, |lua| {
+        let Some(handler) = channels
+            .lock()
+            .ok()
+            .and_then(|c| c.get(channel).and_then(|key| lua.registry_value::<mlua::Function>(key).ok()))
+        else {
+            debug!("Ignoring plugin message from {} on {}", name, channel);
+            return;
+        };
+        let result = lua
+            .create_string(data)
+            .and_then(|data| sandbox::call::<()>(lua, &handler, (name.as_str(), data)));
+        if let Err(e) = result {
+            warn!("Lua handler for channel {} failed: {}", channel, e);
+        }
+    });
+}
+
+/// How chat is shown when no `player_chat` handler changes it.
+const DEFAULT_CHAT_FORMAT: &str = "<{name}> {message}";
+
+const CHAT_VALIDATION_FAILED: &str = "Chat message validation failure";
+
+/// A chat message whose signature checked out, ready to relay.
+struct SignedChat {
+    index: i32,
+    signature: Vec<u8>,
+    /// Signatures of the messages the sender acknowledged, which theirs covers.
+    last_seen: Vec<Vec<u8>>,
+}
+
+enum ChatRejection {
+    /// Tell the player their message wasn't sent.
+    Refuse(&'static str),
+    /// The client's chat state is broken or forged; disconnect them.
+    Kick(&'static str),
+}
+
+/// Check a chat message against the sender's session when chat is signed.
+/// None when chat is unsigned.
+fn verify_chat(
+    world: &World,
+    world_state: &WorldState,
+    entity: hecs::Entity,
+    message: &str,
+    timestamp: i64,
+    salt: i64,
+    signature: Option<Vec<u8>>,
+    offset: i32,
+    acknowledged: [u8; 3],
+) -> Result<Option<SignedChat>, ChatRejection> {
+    if !world_state.secure_chat.enabled {
+        return Ok(None);
+    }
+    let uuid = world.get::<&Profile>(entity).map(|p| p.0.uuid).map_err(|_| ChatRejection::Kick(CHAT_VALIDATION_FAILED))?;
+    let last_seen = world
+        .get::<&mut LastSeen>(entity)
+        .map_err(|_| ChatRejection::Kick(CHAT_VALIDATION_FAILED))?
+        .0
+        .apply_update(offset, acknowledged)
+        .map_err(|_| ChatRejection::Kick(CHAT_VALIDATION_FAILED))?;
+    let Ok(mut signer) = world.get::<&mut ChatSigner>(entity) else {
+        return Err(ChatRejection::Refuse("Chat disabled due to missing profile public key. Please try reconnecting."));
+    };
+    let Some(signature) = signature else {
+        return Err(ChatRejection::Kick("Received chat packet with missing or invalid signature."));
+    };
+    if timestamp < signer.last_timestamp {
+        return Err(ChatRejection::Kick("Out-of-order chat packet received. Did your system time change?"));
+    }
+    let body = crate::chat::SignedBody {
+        sender: uuid,
+        session_id: signer.session.session_id,
+        index: signer.next_index,
+        salt,
+        timestamp,
+        message,
+        last_seen: &last_seen,
+    };
+    if !body.verify(&signer.key, &signature) {
+        return Err(ChatRejection::Kick("Received chat packet with missing or invalid signature."));
+    }
+    let index = signer.next_index;
+    signer.next_index += 1;
+    signer.last_timestamp = timestamp;
+    Ok(Some(SignedChat {
+        index,
+        signature,
+        last_seen,
+    }))
+}
+
+/// Relay a player's chat to everyone with the Player Chat packet, signed if
+/// `signed` is set. `formatted` is shown instead of `<name> message`.
+fn broadcast_player_chat(
+    world: &mut World,
+    sender: Uuid,
+    name: &str,
+    message: &str,
+    timestamp: i64,
+    salt: i64,
+    signed: Option<SignedChat>,
+    formatted: Option<TextComponent>,
+) {
+    let chat_type = if formatted.is_some() {
+        pickaxe_protocol_v1_21::CHAT_TYPE_RAW
+    } else {
+        pickaxe_protocol_v1_21::CHAT_TYPE_CHAT
+    };
+    let (index, signature, previous_messages) = match signed {
+        Some(signed) => (signed.index, Some(signed.signature), signed.last_seen),
+        None => (0, None, Vec::new()),
+    };
+    let packet = InternalPacket::PlayerChat {
+        sender,
+        index,
+        signature: signature.clone(),
+        message: message.to_string(),
+        timestamp,
+        salt,
+        previous_messages,
+        unsigned_content: formatted,
+        chat_type,
+        sender_name: TextComponent::plain(name),
+        target_name: None,
+    }
+    .shared();
+    let mut behind = Vec::new();
+    for (entity, (conn, last_seen)) in world.query_mut::<(&ConnectionSender, Option<&mut LastSeen>)>() {
+        let _ = conn.0.send(packet.clone());
+        // Receivers may acknowledge it in their own messages
+        if let (Some(signature), Some(last_seen)) = (&signature, last_seen) {
+            if last_seen.0.add_pending(signature.clone()).is_err() {
+                behind.push(entity);
+            }
+        }
+    }
+    for entity in behind {
+        disconnect(world, entity, TextComponent::translatable("multiplayer.disconnect.too_many_pending_chats", Vec::new()));
+    }
+}
+
+/// Check and record the chat session a client sends after joining, and
+/// share it so other clients can verify the player's messages.
+fn handle_player_session(world: &mut World, world_state: &WorldState, entity: hecs::Entity, session: ChatSession) {
+    if !world_state.secure_chat.enabled {
+        return;
+    }
+    let (name, uuid) = match world.get::<&Profile>(entity) {
+        Ok(p) => (p.0.name.clone(), p.0.uuid),
+        Err(_) => return,
+    };
+    let key = match world_state.secure_chat.validate_session(uuid, &session, crate::chat::now_millis()) {
+        Ok(key) => key,
+        Err(reason) => {
+            warn!("Disconnecting {}: {}", name, reason);
+            disconnect(world, entity, TextComponent::plain(reason));
+            return;
+        }
+    };
+    debug!("{} started chat session {}", name, session.session_id);
+    broadcast_to_all(
+        world,
+        &InternalPacket::PlayerInfoUpdate {
+            actions: player_info_actions::INITIALIZE_CHAT,
+            players: vec![PlayerInfoEntry {
+                uuid,
+                name: None,
+                properties: Vec::new(),
+                game_mode: None,
+                listed: None,
+                ping: None,
+                display_name: None,
+                chat_session: Some(session.clone()),
+            }],
+        },
+    );
+    // A new session starts a new chain
+    let _ = world.insert_one(
+        entity,
+        ChatSigner {
+            session,
+            key,
+            next_index: 0,
+            last_timestamp: i64::MIN,
+        },
+    );
+}
+
+/// Answer the oldest `pickaxe.players.request_cookie` for this player and key.
+fn handle_cookie_response(
+    world: &mut World,
+    world_state: &mut WorldState,
+    entity: hecs::Entity,
+    key: &str,
+    payload: Option<&[u8]>,
+    scripting: &ScriptRuntime,
+) {
+    // Requests of players who left are never answered
+    world_state.cookie_requests.retain(|request| world.contains(request.player));
+    let Some(index) = world_state
+        .cookie_requests
+        .iter()
+        .position(|request| request.player == entity && request.key == key)
+    else {
+        debug!("Ignoring unrequested cookie {}", key);
+        return;
+    };
+    let request = world_state.cookie_requests.remove(index);
+    let uuid = world.get::<&Profile>(entity).map(|p| p.0.uuid).unwrap_or_default();
+    let now = crate::chat::now_millis() / 1000;
+    let data = payload.and_then(|payload| {
+        let data = crate::cookies::verify(&world_state.cookie_secret, key, uuid, now, payload);
+        if data.is_none() {
+            warn!("Cookie {} has a bad signature or has expired", key);
+        }
+        data
+    });
+    with_lua_context(world, world_state, scripting, |lua| {
+        let Ok(callback) = lua.registry_value::<mlua::Function>(&request.callback) else {
+            return;
+        };
+        let result = match data {
+            Some(data) => lua.create_string(&data).and_then(|data| sandbox::call::<()>(lua, &callback, data)),
+            None => sandbox::call::<()>(lua, &callback, mlua::Value::Nil),
+        };
+        if let Err(e) = result {
+            warn!("Lua cookie callback for {} failed: {}", key, e);
+        }
+    });
+    let _ = scripting.lua().remove_registry_value(request.callback);
+}
+
+/// Run the Lua handler registered for the block state at `pos`, if any, with
+/// the game context set.
+pub(crate) fn fire_block_handler(
+    world: &mut World,
+    world_state: &mut WorldState,
+    scripting: &ScriptRuntime,
+    block_overrides: &crate::bridge::BlockOverrides,
+    handler: BlockHandler,
+    pos: &BlockPos,
+    state: i32,
+    fill: impl FnOnce(&mlua::Table) -> mlua::Result<()>,
+) -> BlockResponse {
+    let Some(func) = crate::bridge::block_handler(scripting.lua(), block_overrides, state, handler) else {
+        return BlockResponse::default();
+    };
+    with_lua_context(world, world_state, scripting, |lua| {
+        crate::bridge::call_block_handler(lua, func, pos, state, fill)
+    })
+}
+
+/// Run the `on_attack` callback of the Lua custom item in the attacker's
+/// main hand, if any. Returns true if it cancelled the attack.
+fn fire_custom_item_attack(
+    world: &mut World,
+    world_state: &mut WorldState,
+    scripting: &ScriptRuntime,
+    entity: hecs::Entity,
+    target_eid: i32,
+) -> bool {
+    let custom_id = {
+        let held_slot = world.get::<&HeldSlot>(entity).map(|h| h.0).unwrap_or(0);
+        let Ok(inv) = world.get::<&Inventory>(entity) else {
+            return false;
+        };
+        match inv.slots[36 + held_slot as usize].as_ref().and_then(|item| item.custom_id.clone()) {
+            Some(id) => id,
+            None => return false,
+        }
+    };
+    let lua = scripting.lua();
+    let Some(func) = crate::bridge::custom_item_callback(lua, &world_state.custom_items, &custom_id, true) else {
+        return false;
+    };
+    let player = world.get::<&Profile>(entity).map(|p| p.0.name.clone()).unwrap_or_default();
+    let target_type = world
+        .query::<&EntityId>()
+        .iter()
+        .find(|(_, eid)| eid.0 == target_eid)
+        .and_then(|(target, _)| selector::entity_type_name(world, target));
+    with_lua_context(world, world_state, scripting, |lua| {
+        crate::bridge::call_custom_item_attack(lua, func, &player, &custom_id, target_eid, target_type)
+    })
+}
+
+/// Run a `pickaxe.gui` click callback with the game context set. Returns
+/// whether the callback allowed the click.
+fn fire_gui_click(
+    world: &mut World,
+    world_state: &mut WorldState,
+    scripting: &ScriptRuntime,
+    entity: hecs::Entity,
+    gui_id: u64,
+    slot: i16,
+    button: i8,
+    mode: i32,
+    item: Option<ItemStack>,
+) -> bool {
+    let lua = scripting.lua();
+    let Some(func) = world_state
+        .guis
+        .get(&gui_id)
+        .and_then(|gui| gui.on_click.as_ref())
+        .and_then(|key| lua.registry_value::<mlua::Function>(key).ok())
+    else {
+        return false;
+    };
+    let player = world.get::<&Profile>(entity).map(|p| p.0.name.clone()).unwrap_or_default();
+    with_lua_context(world, world_state, scripting, |lua| {
+        crate::bridge::call_gui_click(lua, func, gui_id, &player, slot, button, mode, item.as_ref())
+    })
+}
+
+fn build_container_slots(
+    world_state: &WorldState,
+    world: &World,
+    entity: hecs::Entity,
+    menu: &Menu,
+) -> Vec<Option<ItemStack>> {
+    let player_inv = world.get::<&Inventory>(entity).ok();
+
+    match menu {
+        Menu::Chest { pos } => {
+            let mut slots = Vec::with_capacity(63);
+            if let Some(BlockEntity::Chest { inventory }) = world_state.get_block_entity(pos) {
+                slots.extend_from_slice(inventory);
+            } else {
+                slots.resize(27, None);
+            }
+            if let Some(inv) = &player_inv {
+                for i in 9..36 { slots.push(inv.slots[i].clone()); }
+                for i in 36..45 { slots.push(inv.slots[i].clone()); }
+            } else {
+                slots.resize(63, None);
+            }
+            slots
+        }
+        Menu::Furnace { pos } => {
+            let mut slots = Vec::with_capacity(39);
+            if let Some(BlockEntity::Furnace { input, fuel, output, .. }) = world_state.get_block_entity(pos) {
+                slots.push(input.clone());
+                slots.push(fuel.clone());
+                slots.push(output.clone());
+            } else {
                 slots.resize(3, None);
             }
             if let Some(inv) = &player_inv {
@@ -3484,6 +5980,25 @@ fn build_container_slots(
             }
             slots
         }
+        Menu::Lectern { pos } => {
+            // The book is the only slot; lecterns don't show the player inventory
+            match world_state.get_block_entity(pos) {
+                Some(BlockEntity::Lectern { book, .. }) => vec![book.clone()],
+                _ => vec![None],
+            }
+        }
+        Menu::Custom { slots: gui_slots, .. } => {
+            let size = gui_slots.len() + 36;
+            let mut slots = Vec::with_capacity(size);
+            slots.extend_from_slice(gui_slots);
+            if let Some(inv) = &player_inv {
+                for i in 9..36 { slots.push(inv.slots[i].clone()); }
+                for i in 36..45 { slots.push(inv.slots[i].clone()); }
+            } else {
+                slots.resize(size, None);
+            }
+            slots
+        }
         Menu::Anvil { input, sacrifice, result, .. } => {
             // Slots: 0=input, 1=sacrifice, 2=result, 3-29=player inv, 30-38=hotbar
             let mut slots = Vec::with_capacity(39);
@@ -3526,6 +6041,15 @@ fn close_container(
         Menu::CraftingTable { .. } => "crafting_table",
         Menu::BrewingStand { .. } => "brewing_stand",
         Menu::Anvil { .. } => "anvil",
+        Menu::Lectern { .. } => "lectern",
+        // pickaxe.gui menus only notify the mod that opened them
+        Menu::Custom { gui_id, .. } => {
+            if let Some(gui) = world_state.guis.remove(gui_id) {
+                let name = world.get::<&Profile>(entity).map(|p| p.0.name.clone()).unwrap_or_default();
+                fire_gui_close(world, world_state, scripting, *gui_id, gui, &name);
+            }
+            return;
+        }
     };
 
     // Drop crafting grid items back to the player
@@ -3556,7 +6080,7 @@ fn close_container(
     // Save chunk for block entity containers (chest/furnace)
     match &open.menu {
         Menu::Chest { pos } | Menu::Furnace { pos } => {
-            world_state.queue_chunk_save(pos.chunk_pos());
+            world_state.mark_chunk_dirty(pos.chunk_pos());
         }
         _ => {}
     }
@@ -3615,6 +6139,15 @@ fn map_slot(menu: &Menu, window_slot: i16) -> Option<SlotTarget> {
             else if s < 39 { Some(SlotTarget::PlayerInventory(s - 30 + 36)) }
             else { None }
         }
+        // The book can only be taken with the "take book" button
+        Menu::Lectern { .. } => None,
+        Menu::Custom { slots, .. } => {
+            let n = slots.len();
+            if s < n { Some(SlotTarget::Container(s)) }
+            else if s < n + 27 { Some(SlotTarget::PlayerInventory(s - n + 9)) }
+            else if s < n + 36 { Some(SlotTarget::PlayerInventory(s - n - 27 + 36)) }
+            else { None }
+        }
     }
 }
 
@@ -3656,6 +6189,7 @@ fn set_container_slot(
                         _ => {}
                     }
                 }
+                Menu::Custom { ref mut slots, .. } => slots[*idx] = item,
                 _ => {}
             }
         }
@@ -3738,10 +6272,11 @@ fn handle_container_click(
     window_id: u8,
     client_state_id: i32,
     slot: i16,
-    _button: i8,
+    button: i8,
     mode: i32,
     changed_slots: &[(i16, Option<ItemStack>)],
     carried_item: &Option<ItemStack>,
+    scripting: &ScriptRuntime,
 ) {
     // Window 0 = player inventory (always open, no OpenContainer component)
     if window_id == 0 {
@@ -3759,6 +6294,72 @@ fn handle_container_click(
         return;
     }
 
+    // pickaxe.gui menus: clicks are cancelled unless the mod's callback allows them
+    if let Menu::Custom { gui_id, ref slots, .. } = open.menu {
+        let item = match map_slot(&open.menu, slot) {
+            Some(SlotTarget::Container(i)) => slots[i].clone(),
+            Some(SlotTarget::PlayerInventory(i)) => world.get::<&Inventory>(entity).ok().and_then(|inv| inv.slots[i].clone()),
+            _ => None,
+        };
+        let _ = world.insert_one(entity, open);
+        let allowed = fire_gui_click(world, world_state, scripting, entity, gui_id, slot, button, mode, item);
+        // The callback may have closed the menu or opened another
+        open = match world.remove_one::<OpenContainer>(entity) {
+            Ok(oc) if oc.container_id == window_id => oc,
+            other => {
+                if let Ok(oc) = other {
+                    let _ = world.insert_one(entity, oc);
+                }
+                if let (Ok(inv), Ok(sender)) = (world.get::<&Inventory>(entity), world.get::<&ConnectionSender>(entity)) {
+                    let _ = sender.0.send(InternalPacket::SetContainerContent {
+                        window_id: 0,
+                        state_id: inv.state_id,
+                        slots: inv.slots.to_vec(),
+                        carried_item: None,
+                    });
+                }
+                return;
+            }
+        };
+        if !allowed {
+            reject_container_click(world, world_state, entity, open, client_state_id);
+            return;
+        }
+    } else {
+        // container_click handlers can cancel clicks in any other menu
+        let name = world.get::<&Profile>(entity).map(|p| p.0.name.clone()).unwrap_or_default();
+        let item = usize::try_from(slot)
+            .ok()
+            .and_then(|i| build_container_slots(world_state, world, entity, &open.menu).get(i).cloned().flatten());
+        let _ = world.insert_one(entity, open);
+        let cancelled = scripting.fire_event_in_context(
+            "container_click",
+            &[
+                ("name", &name),
+                ("slot", &slot.to_string()),
+                ("button", &button.to_string()),
+                ("mode", &mode.to_string()),
+                ("item_name", item.as_ref().and_then(|i| pickaxe_data::item_id_to_name(i.item_id)).unwrap_or("")),
+                ("item_count", &item.as_ref().map_or(0, |i| i.count).to_string()),
+            ],
+            world as *mut _ as *mut (),
+            world_state as *mut _ as *mut (),
+        );
+        open = match world.remove_one::<OpenContainer>(entity) {
+            Ok(oc) if oc.container_id == window_id => oc,
+            other => {
+                if let Ok(oc) = other {
+                    let _ = world.insert_one(entity, oc);
+                }
+                return;
+            }
+        };
+        if cancelled {
+            reject_container_click(world, world_state, entity, open, client_state_id);
+            return;
+        }
+    }
+
     // Apply the client's proposed slot changes (trust-based with stack size validation)
     match mode {
         0 | 1 | 2 | 3 | 4 | 5 | 6 => {
@@ -3798,7 +6399,9 @@ fn handle_container_click(
             // Handle crafting/anvil result take
             if slot >= 0 {
                 if let Some(SlotTarget::CraftResult) = map_slot(&open.menu, slot) {
+                    let mut crafted = None;
                     if let Menu::CraftingTable { ref mut grid, ref mut result } = open.menu {
+                        crafted = result.as_ref().and_then(|r| pickaxe_data::item_id_to_name(r.item_id));
                         for grid_slot in grid.iter_mut() {
                             if let Some(ref mut item) = grid_slot {
                                 item.count -= 1;
@@ -3807,6 +6410,11 @@ fn handle_container_click(
                         }
                         *result = lookup_crafting_recipe(grid);
                     }
+                    if let Some(crafted) = crafted {
+                        trigger_advancements(world, world_state, entity, |trigger| {
+                            matches!(trigger, Trigger::RecipeCrafted(recipe) if recipe == crafted)
+                        });
+                    }
                     handle_anvil_result_take(world, world_state, entity, &mut open.menu);
                 }
             }
@@ -3835,6 +6443,8 @@ fn handle_container_click(
         _ => {} // Unknown modes — resync below
     }
 
+    open.carried = carried_item.clone();
+
     let new_state_id = client_state_id.wrapping_add(1);
     open.state_id = new_state_id;
 
@@ -3852,6 +6462,28 @@ fn handle_container_click(
     let _ = world.insert_one(entity, open);
 }
 
+/// Undo a click the server refused: resend the container and the cursor item
+/// from before the click.
+fn reject_container_click(
+    world: &mut World,
+    world_state: &WorldState,
+    entity: hecs::Entity,
+    mut open: OpenContainer,
+    client_state_id: i32,
+) {
+    open.state_id = client_state_id.wrapping_add(1);
+    let slots = build_container_slots(world_state, world, entity, &open.menu);
+    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+        let _ = sender.0.send(InternalPacket::SetContainerContent {
+            window_id: open.container_id,
+            state_id: open.state_id,
+            slots,
+            carried_item: open.carried.clone(),
+        });
+    }
+    let _ = world.insert_one(entity, open);
+}
+
 /// Look up a crafting recipe from a 3x3 grid. Returns the result item if a recipe matches.
 fn lookup_crafting_recipe(grid: &[Option<ItemStack>; 9]) -> Option<ItemStack> {
     let grid_ids: [i32; 9] = std::array::from_fn(|i| {
@@ -4162,7 +6794,7 @@ fn handle_anvil_result_take(
         };
         if let Some(new_state) = new_block {
             world_state.set_block(&pos, new_state);
-            broadcast_to_all(world, &InternalPacket::BlockUpdate {
+            broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
                 position: pos,
                 block_id: new_state,
             });
@@ -4210,6 +6842,38 @@ fn handle_anvil_rename(world: &mut World, entity: hecs::Entity, name: &str) {
 }
 
 /// Handle player position update: fall distance, sprint exhaustion, jump exhaustion.
+/// Distance statistics for one movement, in centimetres like vanilla.
+fn award_movement_stats(world: &World, entity: hecs::Entity, horiz_dist: f32, in_water: bool, on_ground: bool, fell: f32) {
+    let Ok(mut stats) = world.get::<&mut Statistics>(entity) else {
+        return;
+    };
+    let (sprinting, sneaking) = world
+        .get::<&MovementState>(entity)
+        .map(|m| (m.sprinting, m.sneaking))
+        .unwrap_or((false, false));
+    let cm = (horiz_dist * 100.0).round() as i32;
+    let stat = if in_water {
+        Some(if sprinting { "swim_one_cm" } else { "walk_on_water_one_cm" })
+    } else if on_ground {
+        Some(if sprinting {
+            "sprint_one_cm"
+        } else if sneaking {
+            "crouch_one_cm"
+        } else {
+            "walk_one_cm"
+        })
+    } else {
+        // Short hops in the air aren't flying
+        (cm > 25).then_some("fly_one_cm")
+    };
+    if let Some(stat) = stat.filter(|_| cm > 0) {
+        stats.custom(stat, cm);
+    }
+    if fell >= 2.0 {
+        stats.custom("fall_one_cm", (fell * 100.0).round() as i32);
+    }
+}
+
 fn handle_player_movement(
     world: &mut World,
     world_state: &mut WorldState,
@@ -4222,8 +6886,9 @@ fn handle_player_movement(
     on_ground: bool,
     scripting: &ScriptRuntime,
 ) {
-    // Ignore movement while sleeping
-    if world.get::<&SleepingState>(entity).is_ok() {
+    // Ignore movement while sleeping, and movement sent before the client
+    // saw a teleport
+    if world.get::<&SleepingState>(entity).is_ok() || world.get::<&PendingTeleport>(entity).is_ok() {
         return;
     }
 
@@ -4259,8 +6924,12 @@ fn handle_player_movement(
         let feet_block = world_state.get_block(&BlockPos::new(x.floor() as i32, y.floor() as i32, z.floor() as i32));
         pickaxe_data::is_fluid(feet_block)
     };
+    let mut landed_fall_distance = 0.0;
     let fall_damage = {
         if let Ok(mut fd) = world.get::<&mut FallDistance>(entity) {
+            if on_ground && !in_water {
+                landed_fall_distance = fd.0;
+            }
             if on_ground || in_water {
                 let damage = if on_ground && fd.0 > 3.0 && !in_water {
                     Some((fd.0 - 3.0).ceil())
@@ -4300,10 +6969,27 @@ fn handle_player_movement(
         }
     }
 
+    // Landing on farmland can trample it back to dirt (vanilla FarmBlock.fallOn);
+    // the crop on top then pops off through its neighbor update
+    if landed_fall_distance > 0.0 {
+        let on_pos = BlockPos::new(x.floor() as i32, (y - 0.2).floor() as i32, z.floor() as i32);
+        if pickaxe_data::is_farmland(world_state.get_block(&on_pos))
+            && rand::random::<f32>() < landed_fall_distance - 0.5
+        {
+            let dirt = pickaxe_data::block_name_to_default_state("dirt").unwrap_or(10);
+            world_state.set_block(&on_pos, dirt);
+            broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
+                position: on_pos,
+                block_id: dirt,
+            });
+        }
+    }
+
     // Sprint exhaustion (MC: 0.1 per meter while sprinting)
     let dx = x - old_pos.x;
     let dz = z - old_pos.z;
     let horiz_dist = ((dx * dx + dz * dz) as f32).sqrt();
+    award_movement_stats(world, entity, horiz_dist, in_water, on_ground, landed_fall_distance);
     if horiz_dist > 0.01 {
         let sprinting = world.get::<&MovementState>(entity).map(|m| m.sprinting).unwrap_or(false);
         if sprinting {
@@ -4320,9 +7006,12 @@ fn handle_player_movement(
         if let Ok(mut food) = world.get::<&mut FoodData>(entity) {
             food.exhaustion = (food.exhaustion + if sprinting { 0.2 } else { 0.05 }).min(40.0);
         }
+        if let Ok(mut stats) = world.get::<&mut Statistics>(entity) {
+            stats.custom("jump", 1);
+        }
     }
 
-    handle_chunk_updates(world, world_state, entity);
+    handle_chunk_updates(world, entity);
     fire_move_event(world, world_state, entity, x, y, z, scripting);
 }
 
@@ -4470,6 +7159,10 @@ fn handle_attack(
         return;
     }
 
+    if is_player && friendly_fire_blocked(world, world_state, attacker, target) {
+        return;
+    }
+
     if is_mob {
         attack_mob(world, world_state, attacker, _attacker_eid, target, target_eid_val, damage, is_critical, scripting, next_eid);
     } else {
@@ -4487,7 +7180,25 @@ fn handle_attack(
 
         // PvP: Apply damage to target player (with attacker position for directional shield)
         let attacker_pos = world.get::<&Position>(attacker).map(|p| p.0).unwrap_or(Vec3d::new(0.0, 0.0, 0.0));
+        let was_alive = world.get::<&Health>(target).map(|h| h.current > 0.0).unwrap_or(false);
         apply_damage_from(world, world_state, target, target_eid_val, damage, "player", Some(attacker_pos), scripting);
+        let now_dead = world.get::<&Health>(target).map(|h| h.current <= 0.0).unwrap_or(false);
+        if was_alive && now_dead {
+            let attacker_name = world.get::<&Profile>(attacker).map(|p| p.0.name.clone()).unwrap_or_default();
+            for packet in world_state.scoreboard.increment_criteria(&attacker_name, Criteria::PlayerKillCount) {
+                broadcast_to_all(world, &packet);
+            }
+            if let Ok(mut stats) = world.get::<&mut Statistics>(attacker) {
+                stats.custom("player_kills", 1);
+                stats.add(StatType::Killed, "player", 1);
+            }
+            if let Ok(mut stats) = world.get::<&mut Statistics>(target) {
+                stats.add(StatType::KilledBy, "player", 1);
+            }
+            trigger_advancements(world, world_state, attacker, |trigger| {
+                matches!(trigger, Trigger::KilledEntity(types) if types.as_ref().map_or(true, |t| t.iter().any(|t| t == "player")))
+            });
+        }
 
         // If target was blocking and attacker used axe, disable their shield
         if attacker_has_axe && target_is_blocking {
@@ -4696,7 +7407,17 @@ fn apply_damage_from(
         return;
     }
 
-    // Check invulnerability — MC checks invulnerableTime > 10 (half the 20-tick cooldown)
+    let damage_rule = match source {
+        "fall" => Some("fallDamage"),
+        "fire" | "lava" => Some("fireDamage"),
+        "drowning" => Some("drowningDamage"),
+        _ => None,
+    };
+    if damage_rule.is_some_and(|rule| !world_state.game_rules.get_bool(rule)) {
+        return;
+    }
+
+    // Check invulnerability — MC checks invulnerableTime > 10 (half the 20-tick cooldown)
     let invuln = world.get::<&Health>(entity).map(|h| h.invulnerable_ticks > 10).unwrap_or(false);
     if invuln {
         return;
@@ -4756,7 +7477,7 @@ fn apply_damage_from(
         let mut shield_broke = false;
         if let Ok(mut inv) = world.get::<&mut Inventory>(entity) {
             if let Some(ref mut shield_item) = inv.slots[shield_slot] {
-                let new_damage = shield_item.damage + dur_damage;
+                let new_damage = if shield_item.unbreakable { shield_item.damage } else { shield_item.damage + dur_damage };
                 if shield_item.max_damage > 0 && new_damage >= shield_item.max_damage {
                     inv.slots[shield_slot] = None;
                     shield_broke = true;
@@ -4795,19 +7516,21 @@ fn apply_damage_from(
         return;
     }
 
-    // Fire cancellable Lua event
+    // Fire cancellable Lua event; handlers may change the amount
     let name = world.get::<&Profile>(entity).map(|p| p.0.name.clone()).unwrap_or_default();
-    let cancelled = scripting.fire_event_in_context(
+    let mut fields = [
+        ("name", name),
+        ("amount", format!("{:.1}", damage)),
+        ("source", source.to_string()),
+    ];
+    let cancelled = scripting.fire_event_mut_in_context(
         "player_damage",
-        &[
-            ("name", &name),
-            ("amount", &format!("{:.1}", damage)),
-            ("source", source),
-        ],
+        &mut fields,
         world as *mut _ as *mut (),
         world_state as *mut _ as *mut (),
     );
-    if cancelled {
+    let damage = fields[1].1.parse::<f32>().unwrap_or(damage);
+    if cancelled || damage <= 0.0 {
         return;
     }
 
@@ -4905,7 +7628,7 @@ fn apply_damage_from(
                 let mut broken_slots = Vec::new();
                 for slot_idx in 5..=8 {
                     if let Some(ref mut item) = inv.slots[slot_idx] {
-                        if item.max_damage > 0 {
+                        if item.is_damageable() {
                             // Unbreaking enchantment: chance to not consume durability
                             let unbreaking = item.enchantment_level(22);
                             if unbreaking > 0 {
@@ -4986,6 +7709,20 @@ fn apply_damage_from(
     }
 }
 
+/// True if `attacker` and `target` are players on the same team with friendly fire off.
+fn friendly_fire_blocked(world: &World, world_state: &WorldState, attacker: hecs::Entity, target: hecs::Entity) -> bool {
+    if attacker == target {
+        return false;
+    }
+    let (Ok(a), Ok(t)) = (world.get::<&Profile>(attacker), world.get::<&Profile>(target)) else {
+        return false;
+    };
+    match (world_state.scoreboard.team_of(&a.0.name), world_state.scoreboard.team_of(&t.0.name)) {
+        (Some(team_a), Some(team_t)) => team_a.name == team_t.name && !team_a.friendly_fire,
+        _ => false,
+    }
+}
+
 /// Handle player death: send death screen, broadcast death message.
 fn handle_player_death(
     world: &mut World,
@@ -4997,6 +7734,20 @@ fn handle_player_death(
 ) {
     let name = world.get::<&Profile>(entity).map(|p| p.0.name.clone()).unwrap_or_default();
 
+    for packet in world_state.scoreboard.increment_criteria(&name, Criteria::DeathCount) {
+        broadcast_to_all(world, &packet);
+    }
+    if let Ok(mut stats) = world.get::<&mut Statistics>(entity) {
+        stats.custom("deaths", 1);
+        stats.set(StatType::Custom, "time_since_death", 0);
+    }
+    // Sources that are entities: "player" or the attacking mob's name
+    if pickaxe_data::entity_name_to_type(source).is_some() {
+        trigger_advancements(world, world_state, entity, |trigger| {
+            matches!(trigger, Trigger::KilledByEntity(types) if types.as_ref().map_or(true, |t| t.iter().any(|t| t == source)))
+        });
+    }
+
     // Fire Lua event
     scripting.fire_event_in_context(
         "player_death",
@@ -5005,7 +7756,7 @@ fn handle_player_death(
         world_state as *mut _ as *mut (),
     );
 
-    // Death message
+    // Death message, which entity_death handlers may replace
     let death_msg = match source {
         "fall" => format!("{} hit the ground too hard", name),
         "void" => format!("{} fell out of the world", name),
@@ -5013,6 +7764,20 @@ fn handle_player_death(
         "kill" => format!("{} was killed", name),
         _ => format!("{} died", name),
     };
+    let mut fields = [
+        ("type", "player".to_string()),
+        ("entity_id", entity_id.to_string()),
+        ("name", name.clone()),
+        ("source", source.to_string()),
+        ("message", death_msg),
+    ];
+    scripting.fire_event_mut_in_context(
+        "entity_death",
+        &mut fields,
+        world as *mut _ as *mut (),
+        world_state as *mut _ as *mut (),
+    );
+    let [.., (_, death_msg)] = fields;
 
     // Send combat kill to the dead player (shows death screen)
     if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
@@ -5028,14 +7793,30 @@ fn handle_player_death(
         event_id: 3,
     });
 
-    // Broadcast death message to all players
-    broadcast_to_all(world, &InternalPacket::SystemChatMessage {
+    // Broadcast death message, honoring showDeathMessages and the team's deathMessageVisibility
+    let show_death_messages = world_state.game_rules.get_bool("showDeathMessages");
+    let death_team = world_state.scoreboard.team_of(&name);
+    let visibility = death_team.map(|t| t.death_message_visibility.as_str()).unwrap_or("always");
+    let death_packet = InternalPacket::SystemChatMessage {
         content: TextComponent::plain(&death_msg),
         overlay: false,
-    });
+    }
+    .shared();
+    for (_, (profile, sender)) in world.query::<(&Profile, &ConnectionSender)>().iter() {
+        let teammate = death_team.is_some_and(|t| t.members.contains(&profile.0.name));
+        let visible = show_death_messages && match visibility {
+            "never" => false,
+            "hideForOtherTeams" => teammate,
+            "hideForOwnTeam" => !teammate,
+            _ => true,
+        };
+        if visible {
+            let _ = sender.0.send(death_packet.clone());
+        }
+    }
 
     // Drop inventory on death if keepInventory is false
-    if !world_state.keep_inventory {
+    if !world_state.game_rules.get_bool("keepInventory") {
         let pos = world.get::<&Position>(entity).map(|p| p.0).unwrap_or(Vec3d::new(0.0, 0.0, 0.0));
         // Collect items first, then spawn entities (avoids borrow conflict)
         let mut drop_items = Vec::new();
@@ -5149,7 +7930,7 @@ fn try_sleep_in_bed(
     // Set bed block occupied
     let new_head_state = pickaxe_data::bed_set_occupied(head_block, true);
     world_state.set_block(&head_pos, new_head_state);
-    broadcast_to_all(world, &InternalPacket::BlockUpdate {
+    broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
         position: head_pos,
         block_id: new_head_state,
     });
@@ -5162,7 +7943,7 @@ fn try_sleep_in_bed(
     if pickaxe_data::is_bed(foot_block) {
         let new_foot_state = pickaxe_data::bed_set_occupied(foot_block, true);
         world_state.set_block(&foot_pos, new_foot_state);
-        broadcast_to_all(world, &InternalPacket::BlockUpdate {
+        broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
             position: foot_pos,
             block_id: new_foot_state,
         });
@@ -5188,6 +7969,10 @@ fn try_sleep_in_bed(
         bed_pos: head_pos,
         sleep_timer: 0,
     });
+    if let Ok(mut stats) = world.get::<&mut Statistics>(entity) {
+        stats.custom("sleep_in_bed", 1);
+        stats.set(StatType::Custom, "time_since_rest", 0);
+    }
 
     // Fire Lua event
     scripting.fire_event_in_context(
@@ -5235,7 +8020,7 @@ fn wake_player(
     if pickaxe_data::is_bed(head_block) {
         let new_state = pickaxe_data::bed_set_occupied(head_block, false);
         world_state.set_block(&sleeping, new_state);
-        broadcast_to_all(world, &InternalPacket::BlockUpdate {
+        broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
             position: sleeping,
             block_id: new_state,
         });
@@ -5248,7 +8033,7 @@ fn wake_player(
         if pickaxe_data::is_bed(foot_block) {
             let new_foot = pickaxe_data::bed_set_occupied(foot_block, false);
             world_state.set_block(&foot_pos, new_foot);
-            broadcast_to_all(world, &InternalPacket::BlockUpdate {
+            broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
                 position: foot_pos,
                 block_id: new_foot,
             });
@@ -5281,6 +8066,492 @@ fn wake_player(
     let _ = world.remove_one::<SleepingState>(entity);
 }
 
+/// What a cauldron interaction does to the item in the player's hand.
+enum CauldronHandResult {
+    /// Use up one held item and hand back this one (buckets, bottles).
+    Exchange(ItemStack),
+    /// Swap the held stack for this one in place (washed items).
+    Replace(ItemStack),
+}
+
+/// Handle right-clicking a cauldron with the held item, following vanilla
+/// `CauldronInteraction`: fluid buckets fill it, an empty bucket takes a full
+/// cauldron, bottles move one level at a time, and water washes dye out of
+/// leather armor and shulker boxes.
+/// Returns false if the held item doesn't interact, so normal placement continues.
+fn try_cauldron_interaction(
+    world: &mut World,
+    world_state: &mut WorldState,
+    entity: hecs::Entity,
+    position: &BlockPos,
+    cauldron_block: i32,
+    next_eid: &Arc<AtomicI32>,
+    scripting: &ScriptRuntime,
+) -> bool {
+    use pickaxe_data::{cauldron_state, CauldronContents, CauldronWash};
+
+    let Some((contents, level)) = pickaxe_data::cauldron_contents(cauldron_block) else {
+        return false;
+    };
+    let held_slot = world.get::<&HeldSlot>(entity).map(|h| h.0).unwrap_or(0);
+    let slot_idx = 36 + held_slot as usize;
+    let Some(held) = world.get::<&Inventory>(entity).ok().and_then(|inv| inv.slots[slot_idx].clone()) else {
+        return false;
+    };
+    let held_name = pickaxe_data::item_id_to_name(held.item_id).unwrap_or("");
+    let item = |name: &str| ItemStack::new(pickaxe_data::item_name_to_id(name).unwrap_or(0), 1);
+    let wash = pickaxe_data::cauldron_washed_item(held_name);
+
+    let (new_state, hand_result, sound) = match (held_name, contents) {
+        // Fluid buckets replace whatever the cauldron holds
+        ("water_bucket", _) => (
+            cauldron_state(CauldronContents::Water, 3),
+            CauldronHandResult::Exchange(item("bucket")),
+            Some("item.bucket.empty"),
+        ),
+        ("lava_bucket", _) => (
+            cauldron_state(CauldronContents::Lava, 3),
+            CauldronHandResult::Exchange(item("bucket")),
+            Some("item.bucket.empty_lava"),
+        ),
+        ("powder_snow_bucket", _) => (
+            cauldron_state(CauldronContents::PowderSnow, 3),
+            CauldronHandResult::Exchange(item("bucket")),
+            Some("item.bucket.empty_powder_snow"),
+        ),
+        // An empty bucket only takes a full cauldron
+        ("bucket", CauldronContents::Water) if level == 3 => (
+            cauldron_state(CauldronContents::Empty, 0),
+            CauldronHandResult::Exchange(item("water_bucket")),
+            Some("item.bucket.fill"),
+        ),
+        ("bucket", CauldronContents::Lava) => (
+            cauldron_state(CauldronContents::Empty, 0),
+            CauldronHandResult::Exchange(item("lava_bucket")),
+            Some("item.bucket.fill_lava"),
+        ),
+        ("bucket", CauldronContents::PowderSnow) if level == 3 => (
+            cauldron_state(CauldronContents::Empty, 0),
+            CauldronHandResult::Exchange(item("powder_snow_bucket")),
+            Some("item.bucket.fill_powder_snow"),
+        ),
+        // Bottles move one level at a time (potion damage 0 = water bottle)
+        ("glass_bottle", CauldronContents::Water) => (
+            cauldron_state(CauldronContents::Water, level - 1),
+            CauldronHandResult::Exchange(item("potion")),
+            Some("item.bottle.fill"),
+        ),
+        ("potion", CauldronContents::Empty | CauldronContents::Water) if held.damage == 0 && level < 3 => (
+            cauldron_state(CauldronContents::Water, level + 1),
+            CauldronHandResult::Exchange(item("glass_bottle")),
+            Some("item.bottle.empty"),
+        ),
+        // Washing costs one level of water
+        (name, CauldronContents::Water) if pickaxe_data::is_dyeable_item(name) && held.dyed_color.is_some() => {
+            let mut washed = held.clone();
+            washed.dyed_color = None;
+            (
+                cauldron_state(CauldronContents::Water, level - 1),
+                CauldronHandResult::Replace(washed),
+                None,
+            )
+        }
+        (_, CauldronContents::Water) if matches!(wash, Some(CauldronWash::Into(_))) => {
+            let Some(CauldronWash::Into(plain)) = wash else {
+                return false;
+            };
+            let mut washed = held.clone();
+            washed.item_id = pickaxe_data::item_name_to_id(plain).unwrap_or(held.item_id);
+            (
+                cauldron_state(CauldronContents::Water, level - 1),
+                CauldronHandResult::Replace(washed),
+                None,
+            )
+        }
+        // Banners stack, so one is washed and handed back like a filled bottle
+        (_, CauldronContents::Water) if wash == Some(CauldronWash::BannerLayer) && !held.banner_patterns.is_empty() => {
+            let mut washed = ItemStack { count: 1, ..held.clone() };
+            washed.banner_patterns.pop();
+            (
+                cauldron_state(CauldronContents::Water, level - 1),
+                CauldronHandResult::Exchange(washed),
+                None,
+            )
+        }
+        _ => return false,
+    };
+
+    let name = world.get::<&Profile>(entity).map(|p| p.0.name.clone()).unwrap_or_default();
+    let block_name = pickaxe_data::block_state_to_name(cauldron_block).unwrap_or("cauldron");
+    let cancelled = scripting.fire_event_in_context(
+        "block_interact",
+        &[
+            ("name", &name),
+            ("block_type", block_name),
+            ("x", &position.x.to_string()),
+            ("y", &position.y.to_string()),
+            ("z", &position.z.to_string()),
+        ],
+        world as *mut _ as *mut (),
+        world_state as *mut _ as *mut (),
+    );
+    if cancelled {
+        return true;
+    }
+
+    if new_state != cauldron_block {
+        world_state.set_block(position, new_state);
+        broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
+            position: *position,
+            block_id: new_state,
+        });
+    }
+    match hand_result {
+        CauldronHandResult::Exchange(result) => {
+            exchange_held_item(world, world_state, entity, slot_idx, result, next_eid, scripting);
+        }
+        CauldronHandResult::Replace(washed) => {
+            if let Ok(mut inv) = world.get::<&mut Inventory>(entity) {
+                inv.set_slot(slot_idx, Some(washed));
+                let state_id = inv.state_id;
+                let slot_item = inv.slots[slot_idx].clone();
+                drop(inv);
+                if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+                    let _ = sender.0.send(InternalPacket::SetContainerSlot {
+                        window_id: 0, state_id, slot: slot_idx as i16, item: slot_item,
+                    });
+                }
+            }
+        }
+    }
+    if let Some(sound) = sound {
+        play_sound_at_block(world, position, sound, SOUND_BLOCKS, 1.0, 1.0);
+    }
+    debug!("{} used {} on {} at {:?}", name, held_name, block_name, position);
+    true
+}
+
+/// Trade one held item for `result` (vanilla `ItemUtils.createFilledResult`).
+/// In survival the result replaces the held stack when it was the last item,
+/// otherwise it goes into the inventory and drops at the player's feet if full.
+/// Creative players keep the held item and only get the result if they lack one.
+fn exchange_held_item(
+    world: &mut World,
+    world_state: &mut WorldState,
+    entity: hecs::Entity,
+    slot_idx: usize,
+    result: ItemStack,
+    next_eid: &Arc<AtomicI32>,
+    scripting: &ScriptRuntime,
+) {
+    let game_mode = world.get::<&PlayerGameMode>(entity).map(|g| g.0).unwrap_or(GameMode::Survival);
+    if game_mode == GameMode::Creative {
+        let has_result = world.get::<&Inventory>(entity).map(|inv| {
+            inv.slots.iter().flatten().any(|i| i.stacks_with(&result))
+        }).unwrap_or(true);
+        if !has_result {
+            insert_into_inventory(world, entity, &result);
+        }
+        return;
+    }
+
+    let replaced = if let Ok(mut inv) = world.get::<&mut Inventory>(entity) {
+        match inv.slots[slot_idx].clone() {
+            Some(mut held) if held.count > 1 => {
+                held.count -= 1;
+                inv.set_slot(slot_idx, Some(held));
+                false
+            }
+            _ => {
+                inv.set_slot(slot_idx, Some(result.clone()));
+                true
+            }
+        }
+    } else {
+        return;
+    };
+    if let Ok(inv) = world.get::<&Inventory>(entity) {
+        if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+            let _ = sender.0.send(InternalPacket::SetContainerSlot {
+                window_id: 0,
+                state_id: inv.state_id,
+                slot: slot_idx as i16,
+                item: inv.slots[slot_idx].clone(),
+            });
+        }
+    }
+    if !replaced && insert_into_inventory(world, entity, &result) == 0 {
+        if let Ok(pos) = world.get::<&Position>(entity).map(|p| p.0) {
+            spawn_item_entity(world, world_state, next_eid, pos.x, pos.y + 0.5, pos.z, result, 40, scripting);
+        }
+    }
+}
+
+/// Use up one of the held item in survival.
+fn consume_held_item(world: &mut World, entity: hecs::Entity, slot_idx: usize) {
+    let game_mode = world.get::<&PlayerGameMode>(entity).map(|g| g.0).unwrap_or(GameMode::Survival);
+    if game_mode == GameMode::Creative {
+        return;
+    }
+    let Ok(mut inv) = world.get::<&mut Inventory>(entity) else { return };
+    let remaining = inv.slots[slot_idx].clone().and_then(|mut held| {
+        held.count -= 1;
+        (held.count > 0).then_some(held)
+    });
+    inv.set_slot(slot_idx, remaining);
+    let state_id = inv.state_id;
+    let slot_item = inv.slots[slot_idx].clone();
+    drop(inv);
+    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+        let _ = sender.0.send(InternalPacket::SetContainerSlot {
+            window_id: 0, state_id, slot: slot_idx as i16, item: slot_item,
+        });
+    }
+}
+
+/// Fire `block_interact` for a player using a block. Returns true if a script cancelled it.
+fn block_interact_cancelled(
+    world: &mut World,
+    world_state: &mut WorldState,
+    entity: hecs::Entity,
+    position: &BlockPos,
+    block_name: &str,
+    scripting: &ScriptRuntime,
+) -> bool {
+    let name = world.get::<&Profile>(entity).map(|p| p.0.name.clone()).unwrap_or_default();
+    scripting.fire_event_in_context(
+        "block_interact",
+        &[
+            ("name", &name),
+            ("block_type", block_name),
+            ("x", &position.x.to_string()),
+            ("y", &position.y.to_string()),
+            ("z", &position.z.to_string()),
+        ],
+        world as *mut _ as *mut (),
+        world_state as *mut _ as *mut (),
+    )
+}
+
+/// How long a bell swings after being rung.
+const BELL_RING_TICKS: i32 = 50;
+
+/// Ring a bell: play the sound and send the swing animation, struck from
+/// `direction` (a face index, 0=down..5=east). Bells are rung from their facing
+/// side when powered by redstone.
+pub(crate) fn ring_bell(world: &World, world_state: &mut WorldState, pos: &BlockPos, direction: u8) {
+    let Some(block_type) = pickaxe_data::block_name_to_id("bell") else { return };
+    if let Some(BlockEntity::Bell { ringing_ticks }) = world_state.get_block_entity_mut(pos) {
+        *ringing_ticks = BELL_RING_TICKS;
+    }
+    broadcast_to_level(world, world_state, &InternalPacket::BlockAction {
+        position: *pos,
+        action_id: 1,
+        action_param: direction,
+        block_type,
+    });
+    play_sound_at_block(world, pos, "block.bell.use", SOUND_BLOCKS, 2.0, 1.0);
+}
+
+/// Handle right-clicking a bell. Only hits on the bell itself count (vanilla
+/// `BellBlock.isProperHit`): not the top or bottom, not the mount above y=0.8124,
+/// and on the sides the bell swings toward.
+fn try_ring_bell(
+    world: &mut World,
+    world_state: &mut WorldState,
+    entity: hecs::Entity,
+    position: &BlockPos,
+    bell_block: i32,
+    face: u8,
+    cursor_y: f32,
+    scripting: &ScriptRuntime,
+) -> bool {
+    if face < 2 || cursor_y > 0.8124 {
+        return false;
+    }
+    let facing_on_z = matches!(pickaxe_data::block_property(bell_block, "facing"), Some("north" | "south"));
+    let hit_on_z = face == 2 || face == 3;
+    let proper = match pickaxe_data::block_property(bell_block, "attachment") {
+        Some("floor") => facing_on_z == hit_on_z,
+        Some("single_wall" | "double_wall") => facing_on_z != hit_on_z,
+        _ => true,
+    };
+    if !proper {
+        return false;
+    }
+    if !block_interact_cancelled(world, world_state, entity, position, "bell", scripting) {
+        ring_bell(world, world_state, position, face);
+    }
+    true
+}
+
+/// Put a book and quill or written book on an empty lectern.
+/// Returns false if the lectern already has a book or the player isn't holding one.
+fn try_place_lectern_book(
+    world: &mut World,
+    world_state: &mut WorldState,
+    entity: hecs::Entity,
+    position: &BlockPos,
+    lectern_block: i32,
+    scripting: &ScriptRuntime,
+) -> bool {
+    if pickaxe_data::block_property(lectern_block, "has_book") != Some("false") {
+        return false;
+    }
+    let held_slot = world.get::<&HeldSlot>(entity).map(|h| h.0).unwrap_or(0);
+    let slot_idx = 36 + held_slot as usize;
+    let Some(held) = world.get::<&Inventory>(entity).ok().and_then(|inv| inv.slots[slot_idx].clone()) else {
+        return false;
+    };
+    if !matches!(pickaxe_data::item_id_to_name(held.item_id), Some("writable_book" | "written_book")) {
+        return false;
+    }
+    if block_interact_cancelled(world, world_state, entity, position, "lectern", scripting) {
+        return true;
+    }
+
+    let mut book = held;
+    book.count = 1;
+    world_state.set_block_entity(*position, BlockEntity::Lectern { book: Some(book), page: 0 });
+    let new_state = pickaxe_data::set_block_property(lectern_block, "has_book", "true").unwrap_or(lectern_block);
+    world_state.set_block(position, new_state);
+    broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate { position: *position, block_id: new_state });
+    consume_held_item(world, entity, slot_idx);
+    play_sound_at_block(world, position, "item.book.put", SOUND_BLOCKS, 1.0, 1.0);
+    world_state.mark_chunk_dirty(position.chunk_pos());
+    true
+}
+
+/// Handle a Click Container Button packet. Only lecterns use buttons so far:
+/// 1 = previous page, 2 = next page, 3 = take the book, 100+n = jump to page n.
+fn handle_container_button(
+    world: &mut World,
+    world_state: &mut WorldState,
+    entity: hecs::Entity,
+    window_id: u8,
+    button_id: u8,
+    next_eid: &Arc<AtomicI32>,
+    scripting: &ScriptRuntime,
+) {
+    let pos = match world.get::<&OpenContainer>(entity) {
+        Ok(open) if open.container_id == window_id => match open.menu {
+            Menu::Lectern { pos } => pos,
+            _ => return,
+        },
+        _ => return,
+    };
+    let Some(BlockEntity::Lectern { book, page }) = world_state.get_block_entity(&pos) else { return };
+    let (book, page) = (book.clone(), *page);
+    let Some(book) = book else { return };
+
+    if button_id == 3 {
+        // Take the book: give it back and close the lectern for everyone reading it
+        world_state.set_block_entity(pos, BlockEntity::Lectern { book: None, page: 0 });
+        let state = world_state.get_block(&pos);
+        if let Some(new_state) = pickaxe_data::set_block_property(state, "has_book", "false") {
+            world_state.set_block(&pos, new_state);
+            broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate { position: pos, block_id: new_state });
+        }
+        let plain = book.damage == 0 && book.enchantments.is_empty();
+        if !(plain && give_item_to_player(world, entity, book.item_id, 1)) {
+            spawn_item_entity(
+                world, world_state, next_eid,
+                pos.x as f64 + 0.5, pos.y as f64 + 1.0, pos.z as f64 + 0.5,
+                book, 10, scripting,
+            );
+        }
+        let readers: Vec<(hecs::Entity, u8)> = world.query::<&OpenContainer>().iter()
+            .filter(|(_, open)| matches!(open.menu, Menu::Lectern { pos: p } if p == pos))
+            .map(|(e, open)| (e, open.container_id))
+            .collect();
+        for (reader, container_id) in readers {
+            if let Ok(sender) = world.get::<&ConnectionSender>(reader) {
+                let _ = sender.0.send(InternalPacket::ContainerClose { container_id: container_id as i32 });
+            }
+            close_container(world, world_state, reader, container_id, next_eid, scripting);
+        }
+        world_state.mark_chunk_dirty(pos.chunk_pos());
+        return;
+    }
+
+    // Page counts live in the book's components, which we don't track; a book holds at most 100 pages
+    let new_page = match button_id {
+        1 => page - 1,
+        2 => page + 1,
+        n if n >= 100 => (n - 100) as i32,
+        _ => return,
+    }
+    .clamp(0, 99);
+    if new_page == page {
+        return;
+    }
+    if let Some(BlockEntity::Lectern { page, .. }) = world_state.get_block_entity_mut(&pos) {
+        *page = new_page;
+    }
+    for (_e, (sender, open)) in world.query::<(&ConnectionSender, &OpenContainer)>().iter() {
+        if matches!(open.menu, Menu::Lectern { pos: p } if p == pos) {
+            let _ = sender.0.send(InternalPacket::SetContainerData {
+                container_id: open.container_id,
+                property: 0,
+                value: new_page as i16,
+            });
+        }
+    }
+    pulse_lectern(world, world_state, &pos);
+    world_state.mark_chunk_dirty(pos.chunk_pos());
+}
+
+/// Turning a lectern's page gives off a 2-tick redstone pulse.
+fn pulse_lectern(world: &World, world_state: &mut WorldState, pos: &BlockPos) {
+    let state = world_state.get_block(pos);
+    if let Some(powered) = pickaxe_data::set_block_property(state, "powered", "true") {
+        if powered != state {
+            world_state.set_block(pos, powered);
+            broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate { position: *pos, block_id: powered });
+            update_redstone_neighbors(world, world_state, pos);
+        }
+    }
+    world_state.schedule_block_tick(pos, 2);
+}
+
+/// Put a raw food item on a campfire's first free spot.
+/// Returns false if the held item can't be cooked there or the campfire is full.
+fn try_add_campfire_food(
+    world: &mut World,
+    world_state: &mut WorldState,
+    entity: hecs::Entity,
+    position: &BlockPos,
+    campfire_name: &str,
+    scripting: &ScriptRuntime,
+) -> bool {
+    let held_slot = world.get::<&HeldSlot>(entity).map(|h| h.0).unwrap_or(0);
+    let slot_idx = 36 + held_slot as usize;
+    let Some(held) = world.get::<&Inventory>(entity).ok().and_then(|inv| inv.slots[slot_idx].clone()) else {
+        return false;
+    };
+    let Some((_, cook_total)) = pickaxe_data::campfire_cooking_result(held.item_id) else { return false };
+    let free_slot = match world_state.get_block_entity(position) {
+        Some(BlockEntity::Campfire { items, .. }) => items.iter().position(|i| i.is_none()),
+        _ => None,
+    };
+    let Some(free_slot) = free_slot else { return false };
+    if block_interact_cancelled(world, world_state, entity, position, campfire_name, scripting) {
+        return true;
+    }
+
+    if let Some(BlockEntity::Campfire { items, cook_times, cook_totals }) = world_state.get_block_entity_mut(position) {
+        items[free_slot] = Some(ItemStack::new(held.item_id, 1));
+        cook_times[free_slot] = 0;
+        cook_totals[free_slot] = cook_total;
+    }
+    consume_held_item(world, entity, slot_idx);
+    if let Some(packet) = world_state.get_block_entity(position).and_then(|be| block_entity_update_packet(position, be)) {
+        broadcast_to_level(world, world_state, &packet);
+    }
+    world_state.mark_chunk_dirty(position.chunk_pos());
+    true
+}
+
 /// Tick sleeping: increment timers, check for night skip when all players are sleeping.
 fn tick_sleeping(
     world: &mut World,
@@ -5306,8 +8577,10 @@ fn tick_sleeping(
         total_players += 1;
     }
 
-    // Check for night skip: all players must be sleeping long enough
-    if total_players > 0 && sleeping_long_enough == total_players {
+    // Check for night skip: playersSleepingPercentage of players must be sleeping long enough
+    let percentage = world_state.game_rules.get_int("playersSleepingPercentage").max(0) as u32;
+    let required = (total_players * percentage).div_ceil(100).max(1);
+    if total_players > 0 && percentage <= 100 && sleeping_long_enough >= required {
         // Advance time to dawn (time_of_day = 0 of next day cycle)
         let time = world_state.time_of_day;
         let skip_ticks = if time >= 12542 {
@@ -5378,6 +8651,7 @@ fn spawn_mob(
             no_damage_ticks: 0,
             fuse_timer: -1,
             attack_cooldown: 0,
+            no_ai: false,
         },
     ))
 }
@@ -5394,6 +8668,22 @@ fn attack_mob(
     is_critical: bool,
     scripting: &ScriptRuntime,
     next_eid: &Arc<AtomicI32>,
+) {
+    damage_mob(world, world_state, target, target_eid, damage, Some(attacker), scripting, next_eid);
+    let _ = is_critical; // used by caller for particles
+}
+
+/// Damage a mob, firing `mob_damage`, respecting its hurt cooldown and
+/// killing it at zero health. Hostile mobs start chasing a player attacker.
+fn damage_mob(
+    world: &mut World,
+    world_state: &mut WorldState,
+    target: hecs::Entity,
+    target_eid: i32,
+    damage: f32,
+    attacker: Option<hecs::Entity>,
+    scripting: &ScriptRuntime,
+    next_eid: &Arc<AtomicI32>,
 ) {
     // Check invulnerability
     let no_dmg = world.get::<&MobEntity>(target).map(|m| m.no_damage_ticks > 0).unwrap_or(false);
@@ -5403,7 +8693,9 @@ fn attack_mob(
 
     let mob_type = world.get::<&MobEntity>(target).map(|m| m.mob_type).unwrap_or(0);
     let mob_name = pickaxe_data::mob_type_name(mob_type).unwrap_or("unknown");
-    let attacker_name = world.get::<&Profile>(attacker).map(|p| p.0.name.clone()).unwrap_or_default();
+    let attacker_name = attacker
+        .and_then(|a| world.get::<&Profile>(a).ok().map(|p| p.0.name.clone()))
+        .unwrap_or_default();
 
     // Fire Lua event
     let cancelled = scripting.fire_event_in_context(
@@ -5421,13 +8713,27 @@ fn attack_mob(
         return;
     }
 
+    // Lua-controlled mobs decide for themselves how to react
+    let lua_ai = world_state.mob_ai.contains_key(&target);
+    if lua_ai {
+        let attacker_id = attacker.and_then(|a| world.get::<&EntityId>(a).ok().map(|e| e.0));
+        let cancelled = fire_mob_ai_handler(world, world_state, scripting, target, MobAiHandler::Hurt, |event| {
+            event.set("amount", damage)?;
+            event.set("attacker", attacker_name.as_str())?;
+            event.set("attacker_id", attacker_id)
+        });
+        if cancelled {
+            return;
+        }
+    }
+
     // Apply damage
     let died = {
         let mut mob = world.get::<&mut MobEntity>(target).unwrap();
         mob.health -= damage;
         mob.no_damage_ticks = 10; // 0.5s invulnerability
         // Hostile mobs target the attacker
-        if pickaxe_data::mob_is_hostile(mob.mob_type) {
+        if let Some(attacker) = attacker.filter(|_| !lua_ai && pickaxe_data::mob_is_hostile(mob.mob_type)) {
             mob.target = Some(attacker);
             mob.ai_state = MobAiState::Chasing;
         }
@@ -5437,62 +8743,16 @@ fn attack_mob(
     let mob_pos = world.get::<&Position>(target).map(|p| p.0).unwrap_or(Vec3d::new(0.0, 0.0, 0.0));
 
     // Play hurt sound
-    let (_, hurt_sound, death_sound) = pickaxe_data::mob_sounds(mob_type);
+    let (_, hurt_sound, _) = pickaxe_data::mob_sounds(mob_type);
 
     if died {
-        // Play death sound
-        play_sound_at_entity(world, mob_pos.x, mob_pos.y, mob_pos.z, death_sound, SOUND_HOSTILE, 1.0, 1.0);
-
-        // Broadcast entity event (death animation = status 3)
-        broadcast_to_all(world, &InternalPacket::EntityEvent {
-            entity_id: target_eid,
-            event_id: 3, // death
-        });
-
-        // Drop items
-        let drops = pickaxe_data::mob_drops(mob_type);
-        for (item_name, min, max) in drops {
-            let count = if min == max {
-                *min
-            } else {
-                let range = (max - min + 1) as u32;
-                *min + (rand::random::<u32>() % range) as i32
-            };
-            if count > 0 {
-                if let Some(item_id) = pickaxe_data::item_name_to_id(item_name) {
-                    let item = ItemStack::new(item_id, count as i8);
-                    spawn_item_entity(world, world_state, next_eid, mob_pos.x, mob_pos.y + 0.5, mob_pos.z, item, 10, scripting);
-                }
+        kill_mob(world, world_state, target, target_eid, attacker, scripting, next_eid);
+    } else {
+        if let Some(attacker) = attacker.filter(|_| lua_ai && !attacker_name.is_empty()) {
+            if world.get::<&MobEntity>(target).is_ok_and(|m| m.target != Some(attacker)) {
+                offer_mob_target(world, world_state, scripting, target, attacker, "attacked");
             }
         }
-
-        // Award XP
-        let xp = pickaxe_data::mob_xp_drop(mob_type);
-        if xp > 0 {
-            award_xp(world, attacker, xp);
-        }
-
-        // Despawn mob
-        let _ = world.despawn(target);
-        broadcast_to_all(world, &InternalPacket::RemoveEntities {
-            entity_ids: vec![target_eid],
-        });
-        for (_, tracked) in world.query_mut::<&mut TrackedEntities>() {
-            tracked.visible.remove(&target_eid);
-        }
-
-        // Fire Lua event
-        scripting.fire_event_in_context(
-            "mob_death",
-            &[
-                ("mob_type", mob_name),
-                ("killer", &attacker_name),
-                ("entity_id", &target_eid.to_string()),
-            ],
-            world as *mut _ as *mut (),
-            world_state as *mut _ as *mut (),
-        );
-    } else {
         // Play hurt sound + hurt animation
         play_sound_at_entity(world, mob_pos.x, mob_pos.y, mob_pos.z, hurt_sound, SOUND_HOSTILE, 1.0, 1.0);
         broadcast_to_all(world, &InternalPacket::EntityEvent {
@@ -5500,26 +8760,220 @@ fn attack_mob(
             event_id: 2, // hurt
         });
     }
+}
 
-    let _ = is_critical; // used by caller for particles
+/// Kill a mob: death sound and animation, loot, XP to the killer, despawn,
+/// and the `mob_death` event. `killer` is None for command kills.
+fn kill_mob(
+    world: &mut World,
+    world_state: &mut WorldState,
+    target: hecs::Entity,
+    target_eid: i32,
+    killer: Option<hecs::Entity>,
+    scripting: &ScriptRuntime,
+    next_eid: &Arc<AtomicI32>,
+) {
+    let mob_type = world.get::<&MobEntity>(target).map(|m| m.mob_type).unwrap_or(0);
+    let mob_name = pickaxe_data::mob_type_name(mob_type).unwrap_or("unknown");
+    let killer_name = killer
+        .and_then(|k| world.get::<&Profile>(k).ok().map(|p| p.0.name.clone()))
+        .unwrap_or_default();
+    let mob_pos = world.get::<&Position>(target).map(|p| p.0).unwrap_or(Vec3d::new(0.0, 0.0, 0.0));
+    let (_, _, death_sound) = pickaxe_data::mob_sounds(mob_type);
+
+    // Play death sound
+    play_sound_at_entity(world, mob_pos.x, mob_pos.y, mob_pos.z, death_sound, SOUND_HOSTILE, 1.0, 1.0);
+
+    // Broadcast entity event (death animation = status 3)
+    broadcast_to_all(world, &InternalPacket::EntityEvent {
+        entity_id: target_eid,
+        event_id: 3, // death
+    });
+
+    // entity_death handlers may change the XP dropped or turn off loot
+    let mut fields = [
+        ("type", mob_name.to_string()),
+        ("entity_id", target_eid.to_string()),
+        ("killer", killer_name.clone()),
+        ("x", format!("{:.1}", mob_pos.x)),
+        ("y", format!("{:.1}", mob_pos.y)),
+        ("z", format!("{:.1}", mob_pos.z)),
+        ("xp", pickaxe_data::mob_xp_drop(mob_type).to_string()),
+        ("drop_loot", world_state.game_rules.get_bool("doMobLoot").to_string()),
+    ];
+    scripting.fire_event_mut_in_context(
+        "entity_death",
+        &mut fields,
+        world as *mut _ as *mut (),
+        world_state as *mut _ as *mut (),
+    );
+    let xp = fields[6].1.parse::<i32>().unwrap_or(0).max(0);
+    let drop_loot = fields[7].1 == "true";
+
+    // Drop items
+    let drops = if drop_loot { pickaxe_data::mob_drops(mob_type) } else { &[] };
+    for (item_name, min, max) in drops {
+        let count = if min == max {
+            *min
+        } else {
+            let range = (max - min + 1) as u32;
+            *min + (rand::random::<u32>() % range) as i32
+        };
+        if count > 0 {
+            if let Some(item_id) = pickaxe_data::item_name_to_id(item_name) {
+                let item = ItemStack::new(item_id, count as i8);
+                spawn_item_entity(world, world_state, next_eid, mob_pos.x, mob_pos.y + 0.5, mob_pos.z, item, 10, scripting);
+            }
+        }
+    }
+
+    // Award XP
+    if let Some(killer) = killer.filter(|_| xp > 0) {
+        award_xp(world, killer, xp);
+    }
+    if let Some(mut stats) = killer.and_then(|k| world.get::<&mut Statistics>(k).ok()) {
+        stats.custom("mob_kills", 1);
+        stats.add(StatType::Killed, mob_name, 1);
+    }
+    if let Some(killer) = killer {
+        trigger_advancements(world, world_state, killer, |trigger| {
+            matches!(trigger, Trigger::KilledEntity(types) if types.as_ref().map_or(true, |t| t.iter().any(|t| t == mob_name)))
+        });
+    }
+
+    // Despawn mob
+    let _ = world.despawn(target);
+    broadcast_to_all(world, &InternalPacket::RemoveEntities {
+        entity_ids: vec![target_eid],
+    });
+    for (_, tracked) in world.query_mut::<&mut TrackedEntities>() {
+        tracked.visible.remove(&target_eid);
+    }
+
+    // Fire Lua event
+    scripting.fire_event_in_context(
+        "mob_death",
+        &[
+            ("mob_type", mob_name),
+            ("killer", &killer_name),
+            ("entity_id", &target_eid.to_string()),
+        ],
+        world as *mut _ as *mut (),
+        world_state as *mut _ as *mut (),
+    );
+}
+
+/// How close a mob must be to land a melee hit.
+pub(crate) const MOB_MELEE_REACH: f64 = 1.8;
+
+/// Run a `pickaxe.entities.set_ai` callback for `mob`, if it has one.
+/// Returns true if it returned "cancel".
+fn fire_mob_ai_handler(
+    world: &mut World,
+    world_state: &mut WorldState,
+    scripting: &ScriptRuntime,
+    mob: hecs::Entity,
+    handler: MobAiHandler,
+    fill: impl FnOnce(&mlua::Table) -> mlua::Result<()>,
+) -> bool {
+    let lua = scripting.lua();
+    let Some(func) = world_state
+        .mob_ai
+        .get(&mob)
+        .and_then(|ai| ai.handler(handler))
+        .and_then(|key| lua.registry_value::<mlua::Function>(key).ok())
+    else {
+        return false;
+    };
+    with_lua_context(world, world_state, scripting, |lua| crate::bridge::call_mob_ai_handler(lua, func, mob, fill))
+}
+
+/// Offer `target` to a Lua-controlled mob through `on_target`; unless that
+/// cancels, it becomes the mob's target.
+fn offer_mob_target(
+    world: &mut World,
+    world_state: &mut WorldState,
+    scripting: &ScriptRuntime,
+    mob: hecs::Entity,
+    target: hecs::Entity,
+    reason: &str,
+) {
+    let target_id = world.get::<&EntityId>(target).map(|e| e.0).unwrap_or(0);
+    let target_name = world.get::<&Profile>(target).map(|p| p.0.name.clone()).unwrap_or_default();
+    let cancelled = fire_mob_ai_handler(world, world_state, scripting, mob, MobAiHandler::Target, |event| {
+        event.set("new_target", target_id)?;
+        event.set("new_target_name", target_name)?;
+        event.set("reason", reason)
+    });
+    if !cancelled {
+        if let Ok(mut m) = world.get::<&mut MobEntity>(mob) {
+            m.target = Some(target);
+        }
+    }
+}
+
+/// Drive mobs given to Lua with `pickaxe.entities.set_ai`: forget despawned
+/// mobs and dead targets, offer the nearest player within 16 blocks as a
+/// target once a second, and run `on_tick`.
+fn tick_lua_mob_ai(
+    world: &mut World,
+    world_state: &mut WorldState,
+    scripting: &ScriptRuntime,
+    player_positions: &[(hecs::Entity, i32, Vec3d)],
+    simulated: &[(i32, i32, i32)],
+) {
+    world_state.mob_ai.retain(|&mob, _| world.contains(mob));
+    let mobs: Vec<hecs::Entity> = world_state.mob_ai.keys().copied().collect();
+    for mob in mobs {
+        let Ok((target, no_ai)) = world.get::<&MobEntity>(mob).map(|m| (m.target, m.no_ai)) else { continue };
+        let Ok(pos) = world.get::<&Position>(mob).map(|p| p.0) else { continue };
+        if !is_simulated(simulated, &pos) {
+            continue;
+        }
+        let target_alive = target.is_some_and(|t| {
+            player_positions.iter().any(|&(p, _, _)| p == t) || world.get::<&MobEntity>(t).is_ok()
+        });
+        if target.is_some() && !target_alive {
+            if let Ok(mut m) = world.get::<&mut MobEntity>(mob) {
+                m.target = None;
+            }
+        }
+        if !target_alive && !no_ai && world_state.tick_count % 20 == 0 {
+            let nearest = player_positions
+                .iter()
+                .map(|&(p, _, ppos)| (p, ((ppos.x - pos.x).powi(2) + (ppos.y - pos.y).powi(2) + (ppos.z - pos.z).powi(2)).sqrt()))
+                .filter(|&(_, dist)| dist < 16.0)
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((player, _)) = nearest {
+                offer_mob_target(world, world_state, scripting, mob, player, "nearby");
+            }
+        }
+        fire_mob_ai_handler(world, world_state, scripting, mob, MobAiHandler::Tick, |_| Ok(()));
+    }
 }
 
 /// Tick mob AI: wandering, chasing, ambient sounds, gravity.
 fn tick_mob_ai(
     world: &mut World,
     world_state: &mut WorldState,
-    _scripting: &ScriptRuntime,
+    scripting: &ScriptRuntime,
     next_eid: &Arc<AtomicI32>,
 ) {
     // Collect player positions for targeting
     let mut player_positions: Vec<(hecs::Entity, i32, Vec3d)> = Vec::new();
-    for (e, (eid, pos, _profile)) in world.query::<(&EntityId, &Position, &Profile)>().iter() {
+    for (e, (eid, pos, _profile, dimension)) in world.query::<(&EntityId, &Position, &Profile, Option<&Dimension>)>().iter() {
+        if !in_level(world_state, dimension) {
+            continue;
+        }
         let health = world.get::<&Health>(e).map(|h| h.current).unwrap_or(0.0);
         if health > 0.0 {
             player_positions.push((e, eid.0, pos.0));
         }
     }
 
+    let simulated = simulation_areas(world);
+    tick_lua_mob_ai(world, world_state, scripting, &player_positions, &simulated);
+
     // Collect mob data for AI updates
     #[allow(dead_code)]
     struct MobUpdate {
@@ -5537,19 +8991,53 @@ fn tick_mob_ai(
     let mut updates: Vec<MobUpdate> = Vec::new();
 
     for (entity, (eid, pos, rot, mob)) in world.query::<(&EntityId, &Position, &Rotation, &mut MobEntity)>().iter() {
+        // Mobs away from every player are frozen
+        if !is_simulated(&simulated, &pos.0) {
+            continue;
+        }
         // Decrement timers
         if mob.no_damage_ticks > 0 {
             mob.no_damage_ticks -= 1;
         }
+        if mob.no_ai {
+            continue;
+        }
 
         let mut ambient_sound = false;
         if mob.ambient_sound_timer > 0 {
             mob.ambient_sound_timer -= 1;
         } else {
-            ambient_sound = true;
+            // Ambient sounds are cosmetic, so they're skipped under load
+            ambient_sound = world_state.load.level().sends_cosmetics();
             mob.ambient_sound_timer = rand::random::<u32>() % 300 + 200;
         }
 
+        // Lua-controlled mobs only walk where `move_to` sends them
+        if let Some(ai) = world_state.mob_ai.get_mut(&entity) {
+            let (mx, mz) = match ai.move_target {
+                Some((dest, speed_mult)) => {
+                    let (dx, dz) = (dest.x - pos.0.x, dest.z - pos.0.z);
+                    let dist = (dx * dx + dz * dz).sqrt();
+                    let speed = pickaxe_data::mob_speed(mob.mob_type) * speed_mult;
+                    if dist <= speed.max(0.1) {
+                        ai.move_target = None;
+                        (dx, dz)
+                    } else {
+                        (dx / dist * speed, dz / dist * speed)
+                    }
+                }
+                None => (0.0, 0.0),
+            };
+            let new_yaw = if mx != 0.0 || mz != 0.0 { (-mx.atan2(mz)).to_degrees() as f32 } else { rot.yaw };
+            updates.push(MobUpdate {
+                entity, eid: eid.0, mob_type: mob.mob_type,
+                pos: pos.0, new_state: mob.ai_state,
+                move_x: mx, move_z: mz, new_yaw,
+                ambient_sound,
+            });
+            continue;
+        }
+
         if mob.ai_timer > 0 {
             mob.ai_timer -= 1;
             // Continue current behavior
@@ -5653,14 +9141,15 @@ fn tick_mob_ai(
         if is_hostile && !is_bat {
             // Find nearest player within 16 blocks
             let mut nearest: Option<(hecs::Entity, f64)> = None;
-            for &(pe, _peid, ppos) in &player_positions {
+            for (pe, ppos) in entities_in_radius(world, world_state, pos.0, 16.0) {
+                if !player_positions.iter().any(|&(p, _, _)| p == pe) {
+                    continue;
+                }
                 let dx = ppos.x - pos.0.x;
                 let dz = ppos.z - pos.0.z;
                 let dist = (dx * dx + dz * dz).sqrt();
-                if dist < 16.0 {
-                    if nearest.is_none() || dist < nearest.unwrap().1 {
-                        nearest = Some((pe, dist));
-                    }
+                if nearest.is_none_or(|(_, best)| dist < best) {
+                    nearest = Some((pe, dist));
                 }
             }
 
@@ -5849,12 +9338,12 @@ fn tick_mob_ai(
                     let (_, _, death_sound) = pickaxe_data::mob_sounds(mob_type);
                     play_sound_at_entity(world, pos.x, pos.y, pos.z, death_sound, SOUND_HOSTILE, 1.0, 1.0);
                     broadcast_to_all(world, &InternalPacket::EntityEvent { entity_id: eid, event_id: 3 });
-                    let drops = pickaxe_data::mob_drops(mob_type);
+                    let drops = if world_state.game_rules.get_bool("doMobLoot") { pickaxe_data::mob_drops(mob_type) } else { &[] };
                     for (item_name, min, max) in drops {
                         let count = if min == max { *min } else { *min + (rand::random::<u32>() % (max - min + 1) as u32) as i32 };
                         if count > 0 {
                             if let Some(item_id) = pickaxe_data::item_name_to_id(item_name) {
-                                spawn_item_entity(world, world_state, next_eid, pos.x, pos.y + 0.5, pos.z, ItemStack::new(item_id, count as i8), 10, _scripting);
+                                spawn_item_entity(world, world_state, next_eid, pos.x, pos.y + 0.5, pos.z, ItemStack::new(item_id, count as i8), 10, scripting);
                             }
                         }
                     }
@@ -5875,6 +9364,7 @@ fn tick_mob_ai(
         target: hecs::Entity,
         mob_type: i32,
         mob_pos: Vec3d,
+        damage: f32,
     }
     let mut melee_attacks: Vec<MeleeAttack> = Vec::new();
 
@@ -5896,6 +9386,9 @@ fn tick_mob_ai(
     let mut creeper_fuses: Vec<CreeperFuse> = Vec::new();
 
     for (entity, (eid, pos, mob)) in world.query::<(&EntityId, &Position, &MobEntity)>().iter() {
+        if world_state.mob_ai.contains_key(&entity) {
+            continue;
+        }
         let Some(target) = mob.target else { continue };
         let Ok(tp) = world.get::<&Position>(target) else { continue };
 
@@ -5941,17 +9434,37 @@ fn tick_mob_ai(
                 if !pickaxe_data::mob_is_hostile(mob.mob_type) { continue; }
                 if mob.no_damage_ticks > 0 { continue; }
                 if mob.attack_cooldown > 0 { continue; }
-                if dist < 1.8 {
+                if dist < MOB_MELEE_REACH {
                     melee_attacks.push(MeleeAttack {
                         target,
                         mob_type: mob.mob_type,
                         mob_pos: pos.0,
+                        damage: pickaxe_data::mob_attack_damage(mob.mob_type),
                     });
                 }
             }
         }
     }
 
+    // Melee hits queued by Lua-controlled mobs with `pickaxe.entities.attack`
+    let queued: Vec<(hecs::Entity, hecs::Entity, f32)> = world_state
+        .mob_ai
+        .iter_mut()
+        .filter_map(|(&mob, ai)| ai.pending_attack.take().map(|(target, damage)| (mob, target, damage)))
+        .collect();
+    for (mob_entity, target, damage) in queued {
+        let Ok(mob_type) = world.get::<&MobEntity>(mob_entity).map(|m| m.mob_type) else { continue };
+        let mob_pos = world.get::<&Position>(mob_entity).map(|p| p.0).unwrap_or(Vec3d::new(0.0, 0.0, 0.0));
+        if world.get::<&Profile>(target).is_ok() {
+            melee_attacks.push(MeleeAttack { target, mob_type, mob_pos, damage });
+        } else if let Ok(target_eid) = world.get::<&EntityId>(target).map(|e| e.0) {
+            damage_mob(world, world_state, target, target_eid, damage, Some(mob_entity), scripting, next_eid);
+            if let Ok(mut mob) = world.get::<&mut MobEntity>(mob_entity) {
+                mob.attack_cooldown = 20;
+            }
+        }
+    }
+
     // Decrement attack cooldowns
     for (_e, mob) in world.query::<&mut MobEntity>().iter() {
         if mob.attack_cooldown > 0 {
@@ -5961,10 +9474,10 @@ fn tick_mob_ai(
 
     // Process melee attacks
     for attack in melee_attacks {
-        let damage = pickaxe_data::mob_attack_damage(attack.mob_type);
+        let damage = scale_damage_for_difficulty(world_state.difficulty, attack.damage);
         let mob_name = pickaxe_data::mob_type_name(attack.mob_type).unwrap_or("mob");
         let target_eid = world.get::<&EntityId>(attack.target).map(|e| e.0).unwrap_or(0);
-        apply_damage_from(world, world_state, attack.target, target_eid, damage, mob_name, Some(attack.mob_pos), _scripting);
+        apply_damage_from(world, world_state, attack.target, target_eid, damage, mob_name, Some(attack.mob_pos), scripting);
 
         // Apply knockback to target player (vanilla: 0.4 strength)
         if let Ok(target_sender) = world.get::<&ConnectionSender>(attack.target) {
@@ -6077,12 +9590,13 @@ fn tick_mob_ai(
             tracked.visible.remove(&creeper_eid);
         }
 
-        // Creeper explosion: radius 3.0, destroys blocks
+        // Creeper explosion: radius 3.0, destroys blocks unless mobGriefing is off
+        let mob_griefing = world_state.game_rules.get_bool("mobGriefing");
         do_explosion(
-            world, world_state, next_eid, _scripting,
+            world, world_state, next_eid, scripting,
             creeper_pos.x, creeper_pos.y + 1.0, creeper_pos.z,
             3.0,
-            true,
+            mob_griefing,
         );
     }
 }
@@ -6095,13 +9609,17 @@ fn tick_mob_spawning(
     tick_count: u64,
 ) {
     // Only attempt spawning every 2 seconds (40 ticks)
-    if tick_count % 40 != 0 {
+    if tick_count % 40 != 0 || !world_state.game_rules.get_bool("doMobSpawning") {
         return;
     }
 
     // Count existing mobs
     let mob_count = world.query::<&MobEntity>().iter().count();
-    let player_count = world.query::<&Profile>().iter().count();
+    let player_count = world
+        .query::<(&Profile, Option<&Dimension>)>()
+        .iter()
+        .filter(|(_, (_, dimension))| in_level(world_state, *dimension))
+        .count();
 
     if player_count == 0 {
         return;
@@ -6114,8 +9632,9 @@ fn tick_mob_spawning(
     }
 
     // Collect player positions
-    let player_positions: Vec<Vec3d> = world.query::<(&Position, &Profile)>().iter()
-        .map(|(_, (p, _))| p.0)
+    let player_positions: Vec<Vec3d> = world.query::<(&Position, &Profile, Option<&Dimension>)>().iter()
+        .filter(|(_, (_, _, dimension))| in_level(world_state, *dimension))
+        .map(|(_, (p, _, _))| p.0)
         .collect();
 
     // Try to spawn near a random player
@@ -6154,8 +9673,14 @@ fn tick_mob_spawning(
         time >= 13000 && time < 23000
     };
 
-    let mob_type = if is_night && rand::random::<f32>() < 0.5 {
-        // 50% chance of hostile mob at night
+    // Chance of a hostile mob at night scales with difficulty (none on peaceful)
+    let hostile_chance = match world_state.difficulty {
+        0 => 0.0,
+        1 => 0.35,
+        2 => 0.5,
+        _ => 0.65,
+    };
+    let mob_type = if is_night && rand::random::<f32>() < hostile_chance {
         let hostile_types = [
             pickaxe_data::MOB_ZOMBIE,
             pickaxe_data::MOB_SKELETON,
@@ -6178,10 +9703,22 @@ fn tick_mob_spawning(
     spawn_mob(world, next_eid, mob_type, spawn_x, spawn_y, spawn_z);
 }
 
-/// Despawn mobs that are too far from any player (>128 blocks).
-fn tick_mob_despawn(world: &mut World) {
-    let player_positions: Vec<Vec3d> = world.query::<(&Position, &Profile)>().iter()
-        .map(|(_, (p, _))| p.0)
+/// Scale damage dealt by mobs to players (MC: Player.hurt for difficulty-scaled sources).
+fn scale_damage_for_difficulty(difficulty: i32, damage: f32) -> f32 {
+    match difficulty {
+        0 => 0.0,
+        1 => (damage / 2.0 + 1.0).min(damage),
+        2 => damage,
+        _ => damage * 1.5,
+    }
+}
+
+/// Despawn mobs that are too far from any player (>128 blocks), and all
+/// hostile mobs on peaceful.
+fn tick_mob_despawn(world: &mut World, world_state: &WorldState) {
+    let player_positions: Vec<Vec3d> = world.query::<(&Position, &Profile, Option<&Dimension>)>().iter()
+        .filter(|(_, (_, _, dimension))| in_level(world_state, *dimension))
+        .map(|(_, (p, _, _))| p.0)
         .collect();
 
     if player_positions.is_empty() {
@@ -6189,7 +9726,14 @@ fn tick_mob_despawn(world: &mut World) {
     }
 
     let mut to_despawn: Vec<(hecs::Entity, i32)> = Vec::new();
-    for (entity, (eid, pos, _mob)) in world.query::<(&EntityId, &Position, &MobEntity)>().iter() {
+    for (entity, (eid, pos, mob)) in world.query::<(&EntityId, &Position, &MobEntity)>().iter() {
+        if world_state.difficulty == 0 && pickaxe_data::mob_is_hostile(mob.mob_type) {
+            to_despawn.push((entity, eid.0));
+            continue;
+        }
+        if world.get::<&CustomName>(entity).is_ok() {
+            continue;
+        }
         let min_dist = player_positions.iter()
             .map(|pp| {
                 let dx = pp.x - pos.0.x;
@@ -6225,6 +9769,10 @@ fn respawn_player(
     let name = world.get::<&Profile>(entity).map(|p| p.0.name.clone()).unwrap_or_default();
     let game_mode = world.get::<&PlayerGameMode>(entity).map(|g| g.0).unwrap_or(GameMode::Survival);
 
+    // Players respawn in the default level, wherever they died
+    world_state.switch_level(DEFAULT_LEVEL);
+    let _ = world.remove_one::<Dimension>(entity);
+
     // Send Respawn packet
     if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
         let _ = sender.0.send(InternalPacket::Respawn {
@@ -6242,6 +9790,7 @@ fn respawn_player(
             last_death_dimension: None,
             portal_cooldown: 0,
         });
+        send_player_abilities(&sender.0, game_mode);
     }
 
     // Reset health and food
@@ -6269,15 +9818,17 @@ fn respawn_player(
             (Vec3d::new(x, y, z), sp.yaw)
         } else {
             // Bed destroyed — fall back to world spawn
-            (Vec3d::new(0.5, -49.0, 0.5), 0.0)
+            (world_spawn(), 0.0)
         }
     } else {
-        (Vec3d::new(0.5, -49.0, 0.5), 0.0)
+        (world_spawn(), 0.0)
     };
 
     if let Ok(mut pos) = world.get::<&mut Position>(entity) {
         pos.0 = spawn;
     }
+    // The respawn replaces any teleport still waiting for confirmation
+    let _ = world.remove_one::<PendingTeleport>(entity);
 
     let spawn_cx = (spawn.x.floor() as i32) >> 4;
     let spawn_cz = (spawn.z.floor() as i32) >> 4;
@@ -6295,8 +9846,12 @@ fn respawn_player(
             chunk_z: spawn_cz,
         });
 
+        // The client discarded its chunks when it respawned
         let view_distance = world.get::<&ViewDistance>(entity).map(|vd| vd.0).unwrap_or(10);
-        send_chunks_around(&sender.0, world_state, spawn_cx, spawn_cz, view_distance);
+        if let Ok(mut chunk_sender) = world.get::<&mut ChunkSender>(entity) {
+            chunk_sender.clear();
+            chunk_sender.queue_view(View::new(spawn_cx, spawn_cz, view_distance));
+        }
 
         let _ = sender.0.send(InternalPacket::SynchronizePlayerPosition {
             position: spawn,
@@ -6311,6 +9866,13 @@ fn respawn_player(
             saturation: 5.0,
         });
 
+        // Respawn resets the client's level info, so resync time and weather
+        let _ = sender.0.send(InternalPacket::UpdateTime {
+            world_age: world_state.world_age,
+            time_of_day: world_state.time_of_day,
+        });
+        send_weather_state(&sender.0, world_state);
+
         // Clear all active effects on respawn
         if let Ok(effects) = world.get::<&ActiveEffects>(entity) {
             let effect_ids: Vec<i32> = effects.effects.keys().copied().collect();
@@ -6335,6 +9897,108 @@ fn respawn_player(
     );
 }
 
+/// Move a player to a level's spawn point. The client sees a dimension
+/// change: a Respawn into the level, then its chunks, with the player's
+/// health, experience and inventory kept.
+pub(crate) fn move_to_level(world: &mut World, world_state: &mut WorldState, entity: hecs::Entity, name: &str) -> Result<(), String> {
+    let (dimension_type, spawn) = match world_state.get_level(name) {
+        Some(level) => (level.dimension_type, level.spawn),
+        None => return Err(format!("Unknown world {}", name)),
+    };
+    if world.get::<&ConnectionSender>(entity).is_err() {
+        return Err("Only players can change worlds".into());
+    }
+    if name == DEFAULT_LEVEL {
+        let _ = world.remove_one::<Dimension>(entity);
+    } else {
+        let _ = world.insert_one(entity, Dimension(name.to_string()));
+    }
+    let game_mode = world.get::<&PlayerGameMode>(entity).map(|g| g.0).unwrap_or(GameMode::Survival);
+    let (spawn_cx, spawn_cz) = ((spawn.x.floor() as i32) >> 4, (spawn.z.floor() as i32) >> 4);
+    if let Ok(mut cp) = world.get::<&mut ChunkPosition>(entity) {
+        cp.chunk_x = spawn_cx;
+        cp.chunk_z = spawn_cz;
+    }
+
+    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+        let sender = &sender.0;
+        let _ = sender.send(InternalPacket::Respawn {
+            dimension_type,
+            dimension_name: name.to_string(),
+            hashed_seed: 0,
+            game_mode: game_mode.id(),
+            previous_game_mode: -1,
+            is_debug: false,
+            is_flat: true,
+            // Keep attributes and entity metadata
+            data_to_keep: 0x03,
+            last_death_x: None,
+            last_death_y: None,
+            last_death_z: None,
+            last_death_dimension: None,
+            portal_cooldown: 0,
+        });
+        send_player_abilities(sender, game_mode);
+        let _ = sender.send(InternalPacket::ChangeDifficulty {
+            difficulty: world_state.difficulty as u8,
+            locked: false,
+        });
+        let _ = sender.send(InternalPacket::SetCenterChunk {
+            chunk_x: spawn_cx,
+            chunk_z: spawn_cz,
+        });
+        let _ = sender.send(InternalPacket::GameEvent { event: 13, value: 0.0 });
+        let _ = sender.send(InternalPacket::UpdateTime {
+            world_age: world_state.world_age,
+            time_of_day: world_state.time_of_day,
+        });
+        send_weather_state(sender, world_state);
+
+        // The new client-side player starts from scratch
+        if let (Ok(health), Ok(food)) = (world.get::<&Health>(entity), world.get::<&FoodData>(entity)) {
+            let _ = sender.send(InternalPacket::SetHealth {
+                health: health.current,
+                food: food.food_level,
+                saturation: food.saturation,
+            });
+        }
+        if let Ok(xp) = world.get::<&ExperienceData>(entity) {
+            let _ = sender.send(InternalPacket::SetExperience {
+                progress: xp.progress,
+                level: xp.level,
+                total_xp: xp.total_xp,
+            });
+        }
+        if let Ok(inventory) = world.get::<&Inventory>(entity) {
+            let _ = sender.send(InternalPacket::SetContainerContent {
+                window_id: 0,
+                state_id: inventory.state_id,
+                slots: inventory.to_slot_vec(),
+                carried_item: None,
+            });
+        }
+        if let Ok(held) = world.get::<&HeldSlot>(entity) {
+            let _ = sender.send(InternalPacket::SetHeldItem { slot: held.0 as i8 });
+        }
+    }
+
+    // The client dropped its chunks and entities with the old level
+    let view_distance = world.get::<&ViewDistance>(entity).map(|vd| vd.0).unwrap_or(10);
+    if let Ok(mut chunk_sender) = world.get::<&mut ChunkSender>(entity) {
+        chunk_sender.clear();
+        chunk_sender.queue_view(View::new(spawn_cx, spawn_cz, view_distance));
+    }
+    if let Ok(mut tracked) = world.get::<&mut TrackedEntities>(entity) {
+        tracked.visible.clear();
+    }
+    if let Ok(mut fall) = world.get::<&mut FallDistance>(entity) {
+        fall.0 = 0.0;
+    }
+    let rotation = world.get::<&Rotation>(entity).map(|r| (r.yaw, r.pitch)).ok();
+    teleport_entity(world, world_state, entity, spawn, rotation);
+    Ok(())
+}
+
 /// Tick void damage for players below Y=-128.
 /// Increment attack cooldown ticks for all players.
 fn tick_attack_cooldown(world: &mut World) {
@@ -6383,6 +10047,7 @@ fn tick_health_hunger(
     let mut starvation_damage: Vec<(hecs::Entity, i32)> = Vec::new();
     let mut health_updates: Vec<(hecs::Entity, f32, i32, f32)> = Vec::new();
     let mut sprint_stop: Vec<hecs::Entity> = Vec::new();
+    let natural_regeneration = world_state.game_rules.get_bool("naturalRegeneration");
 
     for (entity, (eid, health, food, gm)) in
         world.query::<(&EntityId, &mut Health, &mut FoodData, &PlayerGameMode)>().iter()
@@ -6423,7 +10088,7 @@ fn tick_health_hunger(
 
         // Saturated regen: food=20 and saturation>0 and hurt → heal every 10 ticks
         // Only if naturalRegeneration gamerule is true
-        if world_state.natural_regeneration && food.food_level >= 20 && food.saturation > 0.0 && is_hurt {
+        if natural_regeneration && food.food_level >= 20 && food.saturation > 0.0 && is_hurt {
             food.tick_timer += 1;
             if food.tick_timer >= 10 {
                 let heal_amount = food.saturation.min(6.0) / 6.0;
@@ -6433,7 +10098,7 @@ fn tick_health_hunger(
             }
         }
         // Normal regen: food>=18, hurt → heal every 80 ticks
-        else if world_state.natural_regeneration && food.food_level >= 18 && is_hurt {
+        else if natural_regeneration && food.food_level >= 18 && is_hurt {
             food.tick_timer += 1;
             if food.tick_timer >= 80 {
                 health.current = (health.current + 1.0).min(health.max);
@@ -6651,47 +10316,212 @@ fn tick_effects(
     }
 }
 
-/// Tick eating progress: decrement timer, consume food when done.
-fn tick_eating(world: &mut World) {
-    let mut finished: Vec<(hecs::Entity, i32, i32, f32, i32)> = Vec::new();
-
-    for (entity, eating) in world.query::<&mut EatingState>().iter() {
-        eating.remaining_ticks -= 1;
-        if eating.remaining_ticks <= 0 {
-            finished.push((
-                entity,
-                eating.hand,
-                eating.nutrition,
-                eating.saturation_modifier,
-                eating.item_id,
-            ));
+/// Count the per-tick statistics: play time and the time since death and rest.
+fn tick_statistics(world: &mut World) {
+    for (_, (stats, movement)) in world.query_mut::<(&mut Statistics, &MovementState)>() {
+        stats.custom("play_time", 1);
+        stats.custom("total_world_time", 1);
+        stats.custom("time_since_death", 1);
+        stats.custom("time_since_rest", 1);
+        if movement.sneaking {
+            stats.custom("sneak_time", 1);
         }
     }
+}
 
-    for (entity, hand, nutrition, sat_mod, item_id) in finished {
-        // Remove the EatingState component
-        let _ = world.remove_one::<EatingState>(entity);
+/// Once a second, grant `inventory_changed` criteria the players' inventories
+/// now satisfy, and `tick` criteria.
+fn tick_advancements(world: &mut World, world_state: &mut WorldState, tick_count: u64) {
+    if tick_count % 20 != 0 || world_state.advancements.is_empty() {
+        return;
+    }
+    let players: Vec<(hecs::Entity, Vec<ItemStack>)> = world
+        .query::<(&Inventory, &AdvancementProgress)>()
+        .iter()
+        .map(|(entity, (inv, _))| (entity, inv.slots[5..].iter().flatten().cloned().collect()))
+        .collect();
+    for (entity, items) in players {
+        trigger_advancements(world, world_state, entity, |trigger| match trigger {
+            Trigger::Tick => true,
+            Trigger::InventoryChanged(predicates) => predicates.iter().all(|p| items.iter().any(|item| p.matches(item))),
+            _ => false,
+        });
+    }
+}
 
-        let is_potion = sat_mod < 0.0;
+/// Grant a player every criterion they don't have yet whose trigger `fired` accepts.
+fn trigger_advancements(
+    world: &mut World,
+    world_state: &WorldState,
+    entity: hecs::Entity,
+    fired: impl Fn(&Trigger) -> bool,
+) {
+    let tree = world_state.advancements.clone();
+    let granted: Vec<(&str, &str)> = {
+        let Ok(progress) = world.get::<&AdvancementProgress>(entity) else {
+            return;
+        };
+        tree.iter()
+            .flat_map(|a| a.criteria.iter().map(move |(name, trigger)| (a, name, trigger)))
+            .filter(|(a, name, trigger)| !progress.has(&a.id, name) && fired(trigger))
+            .map(|(a, name, _)| (a.id.as_str(), name.as_str()))
+            .collect()
+    };
+    for (id, criterion) in granted {
+        grant_criterion(world, world_state, entity, id, criterion);
+    }
+}
 
-        if is_potion {
-            // Potion drinking completion — nutrition stores potion type index
-            let potion_index = nutrition;
-            let effects = pickaxe_data::potion_effects(potion_index);
-            let eid = world.get::<&EntityId>(entity).map(|e| e.0).unwrap_or(0);
+/// Send a joining player the advancement tree and their progress.
+fn send_advancements(world: &World, world_state: &WorldState, entity: hecs::Entity) {
+    let tree = &world_state.advancements;
+    if tree.is_empty() {
+        return;
+    }
+    let (Ok(progress), Ok(sender)) = (world.get::<&AdvancementProgress>(entity), world.get::<&ConnectionSender>(entity)) else {
+        return;
+    };
+    let _ = sender.0.send(InternalPacket::UpdateAdvancements {
+        reset: true,
+        added: tree.packet_entries(),
+        removed: Vec::new(),
+        progress: tree.iter().map(|a| progress.packet_progress(a)).collect(),
+    });
+}
 
-            for eff in &effects {
-                // Handle instant effects directly
-                match eff.effect_id {
-                    5 => { // instant_health
-                        let heal = 4.0 * (1 << eff.amplifier.min(30)) as f32;
-                        let max = world.get::<&Health>(entity).map(|h| h.max).unwrap_or(20.0);
-                        if let Ok(mut h) = world.get::<&mut Health>(entity) {
-                            h.current = (h.current + heal).min(max);
-                        }
-                    }
-                    6 => { // instant_damage
-                        let damage = 6.0 * (1 << eff.amplifier.min(30)) as f32;
+/// Tell a player's client the new progress of one advancement.
+fn send_advancement_progress(world: &World, entity: hecs::Entity, progress: (String, Vec<(String, Option<i64>)>)) {
+    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+        let _ = sender.0.send(InternalPacket::UpdateAdvancements {
+            reset: false,
+            added: Vec::new(),
+            removed: Vec::new(),
+            progress: vec![progress],
+        });
+    }
+}
+
+/// Grant one criterion of an advancement. Returns false if the advancement
+/// or criterion is unknown or it was already obtained. Completing the
+/// advancement awards its experience and, with `announceAdvancements`,
+/// announces it in chat.
+pub(crate) fn grant_criterion(
+    world: &mut World,
+    world_state: &WorldState,
+    entity: hecs::Entity,
+    id: &str,
+    criterion: &str,
+) -> bool {
+    let tree = world_state.advancements.clone();
+    let Some(advancement) = tree.get(id).filter(|a| a.criteria.iter().any(|(name, _)| name == criterion)) else {
+        return false;
+    };
+    let (completed, update) = {
+        let Ok(mut progress) = world.get::<&mut AdvancementProgress>(entity) else {
+            return false;
+        };
+        let was_done = progress.is_done(advancement);
+        if !progress.grant(id, criterion, crate::advancements::now_ms()) {
+            return false;
+        }
+        (!was_done && progress.is_done(advancement), progress.packet_progress(advancement))
+    };
+    send_advancement_progress(world, entity, update);
+    if !completed {
+        return true;
+    }
+
+    if advancement.experience > 0 {
+        award_xp(world, entity, advancement.experience);
+    }
+    let display = advancement.display.as_ref().filter(|d| d.announce_to_chat);
+    if let (Some(display), true) = (display, world_state.game_rules.get_bool("announceAdvancements")) {
+        let name = world.get::<&Profile>(entity).map(|p| p.0.name.clone()).unwrap_or_default();
+        let (key, color) = match display.frame {
+            crate::advancements::Frame::Task => ("chat.type.advancement.task", "green"),
+            crate::advancements::Frame::Goal => ("chat.type.advancement.goal", "green"),
+            crate::advancements::Frame::Challenge => ("chat.type.advancement.challenge", "dark_purple"),
+        };
+        let hover = display.title.clone().color(color).append(TextComponent::plain("\n")).append(display.description.clone());
+        let title = TextComponent::plain("[")
+            .append(display.title.clone())
+            .append(TextComponent::plain("]"))
+            .color(color)
+            .hover_text(hover);
+        broadcast_to_all(world, &InternalPacket::SystemChatMessage {
+            content: TextComponent::translatable(key, vec![TextComponent::plain(name), title]),
+            overlay: false,
+        });
+    }
+    true
+}
+
+/// Revoke one criterion, or all of an advancement's when None. Returns
+/// whether the player had any of them.
+pub(crate) fn revoke_criteria(
+    world: &mut World,
+    world_state: &WorldState,
+    entity: hecs::Entity,
+    id: &str,
+    criterion: Option<&str>,
+) -> bool {
+    let Some(advancement) = world_state.advancements.get(id) else {
+        return false;
+    };
+    let update = {
+        let Ok(mut progress) = world.get::<&mut AdvancementProgress>(entity) else {
+            return false;
+        };
+        if !progress.revoke(id, criterion) {
+            return false;
+        }
+        progress.packet_progress(advancement)
+    };
+    send_advancement_progress(world, entity, update);
+    true
+}
+
+/// Tick eating progress: decrement timer, consume food when done.
+fn tick_eating(world: &mut World) {
+    let mut finished: Vec<(hecs::Entity, i32, i32, f32, i32)> = Vec::new();
+
+    for (entity, eating) in world.query::<&mut EatingState>().iter() {
+        eating.remaining_ticks -= 1;
+        if eating.remaining_ticks <= 0 {
+            finished.push((
+                entity,
+                eating.hand,
+                eating.nutrition,
+                eating.saturation_modifier,
+                eating.item_id,
+            ));
+        }
+    }
+
+    for (entity, hand, nutrition, sat_mod, item_id) in finished {
+        // Remove the EatingState component
+        let _ = world.remove_one::<EatingState>(entity);
+
+        let is_potion = sat_mod < 0.0;
+
+        if is_potion {
+            // Potion drinking completion — nutrition stores potion type index
+            let potion_index = nutrition;
+            let effects = pickaxe_data::potion_effects(potion_index);
+            let eid = world.get::<&EntityId>(entity).map(|e| e.0).unwrap_or(0);
+
+            for eff in &effects {
+                // Handle instant effects directly
+                match eff.effect_id {
+                    5 => { // instant_health
+                        let heal = 4.0 * (1 << eff.amplifier.min(30)) as f32;
+                        let max = world.get::<&Health>(entity).map(|h| h.max).unwrap_or(20.0);
+                        if let Ok(mut h) = world.get::<&mut Health>(entity) {
+                            h.current = (h.current + heal).min(max);
+                        }
+                    }
+                    6 => { // instant_damage
+                        let damage = 6.0 * (1 << eff.amplifier.min(30)) as f32;
                         if let Ok(mut h) = world.get::<&mut Health>(entity) {
                             h.current = (h.current - damage).max(0.0);
                         }
@@ -6817,24 +10647,12 @@ fn tick_eating(world: &mut World) {
                 if item.item_id == item_id {
                     if item.count <= 1 {
                         // Replace directly with glass bottle
-                        inv.slots[slot_idx] = Some(ItemStack {
-                            item_id: glass_bottle_id,
-                            count: 1,
-                            damage: 0,
-                            max_damage: 0,
-                            enchantments: Vec::new(),
-                        });
+                        inv.slots[slot_idx] = Some(ItemStack::new(glass_bottle_id, 1));
                     } else {
                         // Decrement potion stack, put glass bottle elsewhere
                         item.count -= 1;
                         // Try to add glass bottle to inventory
-                        let bottle = ItemStack {
-                            item_id: glass_bottle_id,
-                            count: 1,
-                            damage: 0,
-                            max_damage: 0,
-                            enchantments: Vec::new(),
-                        };
+                        let bottle = ItemStack::new(glass_bottle_id, 1);
                         if let Some(target) = inv.find_slot_for_item(glass_bottle_id, 64) {
                             if let Some(ref mut existing) = inv.slots[target] {
                                 existing.count += 1;
@@ -6913,7 +10731,7 @@ fn tick_buttons(world: &mut World, world_state: &mut WorldState) {
         let current_state = world_state.get_block(&position);
         if let Some(new_state) = pickaxe_data::toggle_interactive_block(current_state) {
             world_state.set_block(&position, new_state);
-            broadcast_to_all(world, &InternalPacket::BlockUpdate {
+            broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
                 position,
                 block_id: new_state,
             });
@@ -6947,10 +10765,11 @@ fn tick_drowning_and_lava(
         eid: i32,
         pos: Vec3d,
         game_mode: GameMode,
+        level: Option<String>,
     }
     let mut checks: Vec<FluidCheck> = Vec::new();
-    for (entity, (eid, pos, gm, _profile)) in world
-        .query::<(&EntityId, &Position, &PlayerGameMode, &Profile)>()
+    for (entity, (eid, pos, gm, _profile, dimension)) in world
+        .query::<(&EntityId, &Position, &PlayerGameMode, &Profile, Option<&Dimension>)>()
         .iter()
     {
         checks.push(FluidCheck {
@@ -6958,6 +10777,7 @@ fn tick_drowning_and_lava(
             eid: eid.0,
             pos: pos.0,
             game_mode: gm.0,
+            level: dimension.map(|d| d.0.clone()),
         });
     }
 
@@ -6970,6 +10790,7 @@ fn tick_drowning_and_lava(
         if check.game_mode == GameMode::Creative || check.game_mode == GameMode::Spectator {
             continue;
         }
+        world_state.switch_level(check.level.as_deref().unwrap_or(DEFAULT_LEVEL));
 
         // Check if player's eye is in water (eye at Y + 1.62)
         let eye_y = check.pos.y + 1.62;
@@ -7036,6 +10857,7 @@ fn tick_drowning_and_lava(
             }
         }
     }
+    world_state.switch_level(DEFAULT_LEVEL);
 
     // Apply drown damage (2 HP)
     for (entity, eid) in drown_damage {
@@ -7103,45 +10925,264 @@ fn fire_move_event(
     );
 }
 
-fn tick_keep_alive(_adapter: &V1_21Adapter, world: &mut World, tick_count: u64) {
-    // Send keep-alive every 300 ticks (15 seconds)
-    if tick_count % 300 != 0 {
+fn tick_keep_alive(world: &mut World, config: &KeepAliveConfig, tick_count: u64) {
+    // Checked once a second
+    if tick_count % 20 != 0 {
         return;
     }
 
     let now = Instant::now();
-    let mut to_kick: Vec<i32> = Vec::new();
+    let interval = Duration::from_secs(config.interval_seconds);
+    let timeout = Duration::from_secs(config.timeout_seconds);
+    let mut timed_out = Vec::new();
+
+    for (e, (ka, sender)) in world.query::<(&mut KeepAlive, &ConnectionSender)>().iter() {
+        if let Some(reason) = ka.timed_out(now, timeout, config.idle_timeout()) {
+            timed_out.push((e, reason));
+            continue;
+        }
+
+        // Send a new keep-alive once the last one is answered
+        if ka.pending.is_none() && now.duration_since(ka.last_sent) >= interval {
+            let ka_id = now.elapsed().as_millis() as i64;
+            let _ = sender.0.send(InternalPacket::KeepAliveClientbound { id: ka_id });
+            ka.pending = Some(ka_id);
+            ka.last_sent = now;
+        }
+    }
+
+    // The connection closes once the kick is sent, or the network task gives
+    // up on a dead socket; the player leaves on the next tick either way
+    for (entity, reason) in timed_out {
+        if world.get::<&TimedOut>(entity).is_ok() {
+            continue;
+        }
+        let name = world.get::<&Profile>(entity).map(|p| p.0.name.clone()).unwrap_or_default();
+        info!("{} timed out ({})", name, reason.name());
+        disconnect(world, entity, reason.message());
+        let _ = world.insert_one(entity, reason);
+    }
+}
+
+/// Whether a packet is something the player did, rather than one their
+/// client sends by itself. Movement only counts if the player moved or turned.
+fn is_player_input(world: &World, entity: hecs::Entity, packet: &InternalPacket) -> bool {
+    let moved = |x: f64, y: f64, z: f64| world.get::<&Position>(entity).is_ok_and(|p| p.0 != Vec3d::new(x, y, z));
+    let turned = |yaw: f32, pitch: f32| world.get::<&Rotation>(entity).is_ok_and(|r| r.yaw != yaw || r.pitch != pitch);
+    match *packet {
+        InternalPacket::PlayerPosition { x, y, z, .. } => moved(x, y, z),
+        InternalPacket::PlayerPositionAndRotation { x, y, z, yaw, pitch, .. } => moved(x, y, z) || turned(yaw, pitch),
+        InternalPacket::PlayerRotation { yaw, pitch, .. } => turned(yaw, pitch),
+        InternalPacket::ChatMessage { .. }
+        | InternalPacket::ChatCommand { .. }
+        | InternalPacket::HeldItemChange { .. }
+        | InternalPacket::CreativeInventoryAction { .. }
+        | InternalPacket::PlayerCommand { .. }
+        | InternalPacket::ClientCommand { .. }
+        | InternalPacket::ContainerClick { .. }
+        | InternalPacket::ContainerButtonClick { .. }
+        | InternalPacket::RenameItem { .. }
+        | InternalPacket::SignUpdate { .. }
+        | InternalPacket::BlockDig { .. }
+        | InternalPacket::BlockPlace { .. }
+        | InternalPacket::UseItem { .. }
+        | InternalPacket::InteractEntity { .. }
+        | InternalPacket::Swing { .. } => true,
+        _ => false,
+    }
+}
+
+/// Tab list name for a player: their name and the AFK suffix while AFK,
+/// otherwise None for the plain name.
+fn afk_display_name(config: &AfkConfig, name: &str, afk: bool) -> Option<TextComponent> {
+    (afk && !config.suffix.is_empty())
+        .then(|| TextComponent::plain(name).append(TextComponent::from_legacy(&config.suffix, '&')))
+}
+
+/// Mark a player AFK or back: update their tab list name and fire
+/// `player_afk`.
+fn set_afk(
+    world: &mut World,
+    world_state: &mut WorldState,
+    scripting: &ScriptRuntime,
+    config: &AfkConfig,
+    entity: hecs::Entity,
+    afk: bool,
+) {
+    let Ok((name, uuid)) = world.get::<&Profile>(entity).map(|p| (p.0.name.clone(), p.0.uuid)) else {
+        return;
+    };
+    info!("{} is {}", name, if afk { "now AFK" } else { "no longer AFK" });
+    if !config.suffix.is_empty() {
+        broadcast_to_all(
+            world,
+            &InternalPacket::PlayerInfoUpdate {
+                actions: player_info_actions::UPDATE_DISPLAY_NAME,
+                players: vec![PlayerInfoEntry {
+                    uuid,
+                    name: None,
+                    properties: Vec::new(),
+                    game_mode: None,
+                    listed: None,
+                    ping: None,
+                    display_name: afk_display_name(config, &name, afk),
+                    chat_session: None,
+                }],
+            },
+        );
+    }
+    scripting.fire_event_in_context(
+        "player_afk",
+        &[("name", &name), ("afk", if afk { "true" } else { "false" })],
+        world as *mut _ as *mut (),
+        world_state as *mut _ as *mut (),
+    );
+}
 
-    for (_e, (eid, ka, sender)) in world
-        .query::<(&EntityId, &mut KeepAlive, &ConnectionSender)>()
+/// Mark idle players AFK and disconnect those idle past `kick_after_minutes`.
+fn tick_afk(world: &mut World, world_state: &mut WorldState, scripting: &ScriptRuntime, config: &AfkConfig, tick_count: u64) {
+    // Checked once a second
+    if tick_count % 20 != 0 {
+        return;
+    }
+    let now = Instant::now();
+    let (afk_after, kick_after) = (config.afk_after(), config.kick_after());
+    let changes: Vec<(hecs::Entity, AfkChange)> = world
+        .query::<&mut Activity>()
         .iter()
-    {
-        // Kick if no response for 30 seconds
-        if ka.pending.is_some() && now.duration_since(ka.last_response).as_secs() >= 30 {
-            let _ = sender.0.send(InternalPacket::Disconnect {
-                reason: TextComponent::plain("Timed out"),
+        .filter_map(|(e, activity)| activity.check(now, afk_after, kick_after).map(|change| (e, change)))
+        .collect();
+    for (entity, change) in changes {
+        match change {
+            AfkChange::Away => set_afk(world, world_state, scripting, config, entity, true),
+            AfkChange::Kick => {
+                let name = world.get::<&Profile>(entity).map(|p| p.0.name.clone()).unwrap_or_default();
+                info!("Disconnecting {} for being idle", name);
+                disconnect(world, entity, TextComponent::translatable("multiplayer.disconnect.idling", Vec::new()));
+            }
+        }
+    }
+}
+
+/// Resolve `%placeholders%` in server text as seen by `player`, or for
+/// server-wide text when None.
+pub(crate) fn format_text(
+    world: &mut World,
+    world_state: &mut WorldState,
+    scripting: &ScriptRuntime,
+    template: &str,
+    player: Option<&str>,
+) -> String {
+    if crate::placeholders::names(template).is_empty() {
+        return template.to_string();
+    }
+    let placeholders = world_state.placeholders.clone();
+    with_lua_context(world, world_state, scripting, |lua| {
+        crate::bridge::format_placeholders(lua, &placeholders, template, player)
+    })
+}
+
+/// Send each player the header/footer rendered from the `[tab_list]`
+/// templates, with placeholders resolved for them.
+fn send_tab_list_header(world: &mut World, world_state: &mut WorldState, scripting: &ScriptRuntime, config: &ServerConfig) {
+    let players: Vec<(hecs::Entity, String)> =
+        world.query::<&Profile>().iter().map(|(e, p)| (e, p.0.name.clone())).collect();
+    let online = players.len();
+    let tps = world_state.tick_stats.tps();
+    for (entity, name) in players {
+        let header = format_text(world, world_state, scripting, &config.tab_list.header, Some(&name));
+        let footer = format_text(world, world_state, scripting, &config.tab_list.footer, Some(&name));
+        if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+            let _ = sender.0.send(InternalPacket::SetTabListHeaderAndFooter {
+                header: crate::tablist::render(&header, online, config.max_players, tps),
+                footer: crate::tablist::render(&footer, online, config.max_players, tps),
             });
-            to_kick.push(eid.0);
-            continue;
         }
+    }
+}
 
-        // Send new keep-alive
-        let ka_id = now.elapsed().as_millis() as i64;
-        let _ = sender
-            .0
-            .send(InternalPacket::KeepAliveClientbound { id: ka_id });
-        ka.pending = Some(ka_id);
-        ka.last_sent = now;
+/// Re-render the server list MOTD, resolving its placeholders, and update
+/// the player sample.
+fn refresh_server_list(
+    world: &mut World,
+    world_state: &mut WorldState,
+    scripting: &ScriptRuntime,
+    config: &ServerConfig,
+    server_list: &crate::status::SharedServerList,
+) {
+    let text = format_text(world, world_state, scripting, &config.motd, None);
+    let sample: Vec<(String, Uuid)> = world
+        .query::<&Profile>()
+        .iter()
+        .take(config.status.player_sample)
+        .map(|(_, p)| (p.0.name.clone(), p.0.uuid))
+        .collect();
+    if let Ok(mut info) = server_list.lock() {
+        info.motd = text;
+        info.sample = sample;
+    }
+}
+
+/// Refresh every player's tab list header/footer and the ping bars.
+fn tick_tab_list(world: &mut World, world_state: &mut WorldState, scripting: &ScriptRuntime, config: &ServerConfig) {
+    send_tab_list_header(world, world_state, scripting, config);
+
+    let players: Vec<PlayerInfoEntry> = world
+        .query::<(&Profile, &KeepAlive)>()
+        .iter()
+        .map(|(_, (p, ka))| PlayerInfoEntry {
+            uuid: p.0.uuid,
+            name: None,
+            properties: Vec::new(),
+            game_mode: None,
+            listed: None,
+            ping: Some(ka.latency_ms),
+            display_name: None,
+            chat_session: None,
+        })
+        .collect();
+    if !players.is_empty() {
+        broadcast_to_all(
+            world,
+            &InternalPacket::PlayerInfoUpdate {
+                actions: player_info_actions::UPDATE_LATENCY,
+                players,
+            },
+        );
+    }
+}
+
+/// The tracking kind of a non-player entity, from its components.
+fn tracked_kind(world: &World, entity: hecs::Entity) -> Option<TrackedKind> {
+    let e = world.entity(entity).ok()?;
+    if e.has::<ItemEntity>() {
+        Some(TrackedKind::Item)
+    } else if e.has::<MobEntity>() {
+        Some(TrackedKind::Mob)
+    } else if e.has::<ArrowEntity>() {
+        Some(TrackedKind::Arrow)
+    } else if e.has::<FishingBobber>() {
+        Some(TrackedKind::Bobber)
+    } else if e.has::<TntEntity>() {
+        Some(TrackedKind::Tnt)
+    } else if e.has::<ArmorStand>() {
+        Some(TrackedKind::ArmorStand)
+    } else if e.has::<DisplayEntity>() {
+        Some(TrackedKind::Display)
+    } else {
+        None
     }
 }
 
-fn tick_entity_tracking(world: &mut World) {
+fn tick_entity_tracking(world: &mut World, index: &mut ChunkIndex) {
     use std::collections::HashSet;
 
     // Collect all player data (observers)
     let mut player_data: Vec<(hecs::Entity, i32, Vec3d, f32, f32, bool, Uuid, i32, i32)> =
         Vec::new();
-    for (e, (eid, pos, rot, og, profile, cp, _vd)) in world
+    // Players outside the default level; everything else is in it
+    let mut levels: HashMap<i32, String> = HashMap::new();
+    for (e, (eid, pos, rot, og, profile, cp, _vd, dimension)) in world
         .query::<(
             &EntityId,
             &Position,
@@ -7150,9 +11191,13 @@ fn tick_entity_tracking(world: &mut World) {
             &Profile,
             &ChunkPosition,
             &ViewDistance,
+            Option<&Dimension>,
         )>()
         .iter()
     {
+        if let Some(dimension) = dimension {
+            levels.insert(eid.0, dimension.0.clone());
+        }
         player_data.push((
             e,
             eid.0,
@@ -7174,12 +11219,12 @@ fn tick_entity_tracking(world: &mut World) {
         vel: Vec3d,
         item: ItemStack,
     }
-    let mut item_data: Vec<ItemData> = Vec::new();
+    let mut item_data: HashMap<i32, ItemData> = HashMap::new();
     for (_e, (eid, euuid, pos, vel, item_ent)) in world
         .query::<(&EntityId, &EntityUuid, &Position, &Velocity, &ItemEntity)>()
         .iter()
     {
-        item_data.push(ItemData {
+        item_data.insert(eid.0, ItemData {
             eid: eid.0,
             uuid: euuid.0,
             pos: pos.0,
@@ -7196,19 +11241,21 @@ fn tick_entity_tracking(world: &mut World) {
         yaw: f32,
         pitch: f32,
         mob_type: i32,
+        custom_name: Option<(TextComponent, bool)>,
     }
-    let mut mob_data: Vec<MobData> = Vec::new();
-    for (_e, (eid, euuid, pos, rot, mob)) in world
+    let mut mob_data: HashMap<i32, MobData> = HashMap::new();
+    for (e, (eid, euuid, pos, rot, mob)) in world
         .query::<(&EntityId, &EntityUuid, &Position, &Rotation, &MobEntity)>()
         .iter()
     {
-        mob_data.push(MobData {
+        mob_data.insert(eid.0, MobData {
             eid: eid.0,
             uuid: euuid.0,
             pos: pos.0,
             yaw: rot.yaw,
             pitch: rot.pitch,
             mob_type: mob.mob_type,
+            custom_name: world.get::<&CustomName>(e).ok().map(|n| (n.text.clone(), n.visible)),
         });
     }
 
@@ -7223,7 +11270,7 @@ fn tick_entity_tracking(world: &mut World) {
         owner_eid: i32, // 0 if no owner
         is_critical: bool,
     }
-    let mut arrow_data: Vec<ArrowData> = Vec::new();
+    let mut arrow_data: HashMap<i32, ArrowData> = HashMap::new();
     for (_e, (eid, euuid, pos, vel, rot, arrow)) in world
         .query::<(&EntityId, &EntityUuid, &Position, &Velocity, &Rotation, &ArrowEntity)>()
         .iter()
@@ -7231,7 +11278,7 @@ fn tick_entity_tracking(world: &mut World) {
         let owner_eid = arrow.owner
             .and_then(|o| world.get::<&EntityId>(o).ok().map(|e| e.0))
             .unwrap_or(0);
-        arrow_data.push(ArrowData {
+        arrow_data.insert(eid.0, ArrowData {
             eid: eid.0,
             uuid: euuid.0,
             pos: pos.0,
@@ -7251,14 +11298,14 @@ fn tick_entity_tracking(world: &mut World) {
         vel: Vec3d,
         owner_eid: i32,
     }
-    let mut bobber_data: Vec<BobberData> = Vec::new();
+    let mut bobber_data: HashMap<i32, BobberData> = HashMap::new();
     for (_e, (eid, euuid, pos, vel, bobber)) in world
         .query::<(&EntityId, &EntityUuid, &Position, &Velocity, &FishingBobber)>()
         .iter()
     {
         let owner_eid = world.get::<&EntityId>(bobber.owner)
             .ok().map(|e| e.0).unwrap_or(0);
-        bobber_data.push(BobberData {
+        bobber_data.insert(eid.0, BobberData {
             eid: eid.0,
             uuid: euuid.0,
             pos: pos.0,
@@ -7267,6 +11314,59 @@ fn tick_entity_tracking(world: &mut World) {
         });
     }
 
+    // Collect all armor stands
+    struct ArmorStandData {
+        eid: i32,
+        uuid: Uuid,
+        pos: Vec3d,
+        yaw: f32,
+        custom_name: Option<(TextComponent, bool)>,
+    }
+    let mut armor_stand_data: HashMap<i32, ArmorStandData> = HashMap::new();
+    for (e, (eid, euuid, pos, rot, _stand)) in world
+        .query::<(&EntityId, &EntityUuid, &Position, &Rotation, &ArmorStand)>()
+        .iter()
+    {
+        armor_stand_data.insert(eid.0, ArmorStandData {
+            eid: eid.0,
+            uuid: euuid.0,
+            pos: pos.0,
+            yaw: rot.yaw,
+            custom_name: world.get::<&CustomName>(e).ok().map(|n| (n.text.clone(), n.visible)),
+        });
+    }
+
+    // Collect all display entities
+    struct DisplayData {
+        entity: hecs::Entity,
+        eid: i32,
+        uuid: Uuid,
+        pos: Vec3d,
+        yaw: f32,
+        pitch: f32,
+    }
+    let mut display_data: HashMap<i32, DisplayData> = HashMap::new();
+    for (e, (eid, euuid, pos, rot, _display)) in world
+        .query::<(&EntityId, &EntityUuid, &Position, &Rotation, &DisplayEntity)>()
+        .iter()
+    {
+        display_data.insert(eid.0, DisplayData {
+            entity: e,
+            eid: eid.0,
+            uuid: euuid.0,
+            pos: pos.0,
+            yaw: rot.yaw,
+            pitch: rot.pitch,
+        });
+    }
+
+    // Entities drawn with the glowing outline
+    let glowing: HashSet<i32> = world
+        .query::<(&EntityId, &Glowing)>()
+        .iter()
+        .map(|(_e, (eid, _))| eid.0)
+        .collect();
+
     // Collect all primed TNT entities
     struct TntData {
         eid: i32,
@@ -7275,12 +11375,12 @@ fn tick_entity_tracking(world: &mut World) {
         vel: Vec3d,
         fuse: i32,
     }
-    let mut tnt_data: Vec<TntData> = Vec::new();
+    let mut tnt_data: HashMap<i32, TntData> = HashMap::new();
     for (_e, (eid, euuid, pos, vel, tnt)) in world
         .query::<(&EntityId, &EntityUuid, &Position, &Velocity, &TntEntity)>()
         .iter()
     {
-        tnt_data.push(TntData {
+        tnt_data.insert(eid.0, TntData {
             eid: eid.0,
             uuid: euuid.0,
             pos: pos.0,
@@ -7289,71 +11389,30 @@ fn tick_entity_tracking(world: &mut World) {
         });
     }
 
-    for i in 0..player_data.len() {
-        let (observer_entity, _observer_eid, _, _, _, _, _, obs_cx, obs_cz) = player_data[i];
+    // Bucket everything by chunk so each player only checks what's near them.
+    // The index is kept for the next tick's proximity checks
+    index.clear();
+    for &(entity, eid, _, _, _, _, _, cx, cz) in &player_data {
+        index.insert(cx, cz, entity, eid, TrackedKind::Player);
+    }
+    for (entity, (eid, pos)) in world.query::<(&EntityId, &Position)>().without::<&Profile>().iter() {
+        if let Some(kind) = tracked_kind(world, entity) {
+            index.insert((pos.0.x.floor() as i32) >> 4, (pos.0.z.floor() as i32) >> 4, entity, eid.0, kind);
+        }
+    }
+
+    for &(observer_entity, observer_eid, _, _, _, _, _, obs_cx, obs_cz) in &player_data {
 
         let obs_vd = match world.get::<&ViewDistance>(observer_entity) {
             Ok(vd) => vd.0,
             Err(_) => continue,
         };
 
-        let mut should_see: HashSet<i32> = HashSet::new();
-
-        // Other players in view distance
-        for j in 0..player_data.len() {
-            if i == j {
-                continue;
-            }
-            let (_, target_eid, _, _, _, _, _, tgt_cx, tgt_cz) = player_data[j];
-            if (tgt_cx - obs_cx).abs() <= obs_vd && (tgt_cz - obs_cz).abs() <= obs_vd {
-                should_see.insert(target_eid);
-            }
-        }
-
-        // Item entities in view distance
-        for item in &item_data {
-            let item_cx = (item.pos.x.floor() as i32) >> 4;
-            let item_cz = (item.pos.z.floor() as i32) >> 4;
-            if (item_cx - obs_cx).abs() <= obs_vd && (item_cz - obs_cz).abs() <= obs_vd {
-                should_see.insert(item.eid);
-            }
-        }
-
-        // Mob entities in view distance
-        for mob in &mob_data {
-            let mob_cx = (mob.pos.x.floor() as i32) >> 4;
-            let mob_cz = (mob.pos.z.floor() as i32) >> 4;
-            if (mob_cx - obs_cx).abs() <= obs_vd && (mob_cz - obs_cz).abs() <= obs_vd {
-                should_see.insert(mob.eid);
-            }
-        }
-
-        // Arrow entities in view distance
-        for arrow in &arrow_data {
-            let arrow_cx = (arrow.pos.x.floor() as i32) >> 4;
-            let arrow_cz = (arrow.pos.z.floor() as i32) >> 4;
-            if (arrow_cx - obs_cx).abs() <= obs_vd && (arrow_cz - obs_cz).abs() <= obs_vd {
-                should_see.insert(arrow.eid);
-            }
-        }
-
-        // Fishing bobber entities in view distance
-        for bobber in &bobber_data {
-            let bobber_cx = (bobber.pos.x.floor() as i32) >> 4;
-            let bobber_cz = (bobber.pos.z.floor() as i32) >> 4;
-            if (bobber_cx - obs_cx).abs() <= obs_vd && (bobber_cz - obs_cz).abs() <= obs_vd {
-                should_see.insert(bobber.eid);
-            }
-        }
-
-        // TNT entities in view distance
-        for tnt in &tnt_data {
-            let tnt_cx = (tnt.pos.x.floor() as i32) >> 4;
-            let tnt_cz = (tnt.pos.z.floor() as i32) >> 4;
-            if (tnt_cx - obs_cx).abs() <= obs_vd && (tnt_cz - obs_cz).abs() <= obs_vd {
-                should_see.insert(tnt.eid);
-            }
-        }
+        let should_see: HashSet<i32> = index
+            .visible_from(obs_cx, obs_cz, obs_vd)
+            .into_iter()
+            .filter(|&eid| eid != observer_eid && levels.get(&eid) == levels.get(&observer_eid))
+            .collect();
 
         let currently_tracked: HashSet<i32> = match world.get::<&TrackedEntities>(observer_entity) {
             Ok(te) => te.visible.clone(),
@@ -7402,7 +11461,7 @@ fn tick_entity_tracking(world: &mut World) {
                         });
                     }
                 }
-            } else if let Some(item) = item_data.iter().find(|d| d.eid == eid) {
+            } else if let Some(item) = item_data.get(&eid) {
                 // Item entity
                 let vx = (item.vel.x * 8000.0) as i16;
                 let vy = (item.vel.y * 8000.0) as i16;
@@ -7428,7 +11487,7 @@ fn tick_entity_tracking(world: &mut World) {
                     entity_id: eid,
                     metadata,
                 });
-            } else if let Some(mob) = mob_data.iter().find(|d| d.eid == eid) {
+            } else if let Some(mob) = mob_data.get(&eid) {
                 // Mob entity
                 let _ = observer_sender.send(InternalPacket::SpawnEntity {
                     entity_id: eid,
@@ -7449,7 +11508,35 @@ fn tick_entity_tracking(world: &mut World) {
                     entity_id: eid,
                     head_yaw: degrees_to_angle(mob.yaw),
                 });
-            } else if let Some(arrow) = arrow_data.iter().find(|d| d.eid == eid) {
+                if let Some((name, visible)) = &mob.custom_name {
+                    let _ = observer_sender.send(InternalPacket::SetEntityMetadata {
+                        entity_id: eid,
+                        metadata: build_custom_name_metadata(name, *visible),
+                    });
+                }
+            } else if let Some(stand) = armor_stand_data.get(&eid) {
+                let _ = observer_sender.send(InternalPacket::SpawnEntity {
+                    entity_id: eid,
+                    entity_uuid: stand.uuid,
+                    entity_type: pickaxe_data::ENTITY_ARMOR_STAND,
+                    x: stand.pos.x,
+                    y: stand.pos.y,
+                    z: stand.pos.z,
+                    pitch: 0,
+                    yaw: degrees_to_angle(stand.yaw),
+                    head_yaw: degrees_to_angle(stand.yaw),
+                    data: 0,
+                    velocity_x: 0,
+                    velocity_y: 0,
+                    velocity_z: 0,
+                });
+                if let Some((name, visible)) = &stand.custom_name {
+                    let _ = observer_sender.send(InternalPacket::SetEntityMetadata {
+                        entity_id: eid,
+                        metadata: build_custom_name_metadata(name, *visible),
+                    });
+                }
+            } else if let Some(arrow) = arrow_data.get(&eid) {
                 // Arrow entity (type 4)
                 let vx = (arrow.vel.x * 8000.0) as i16;
                 let vy = (arrow.vel.y * 8000.0) as i16;
@@ -7482,7 +11569,7 @@ fn tick_entity_tracking(world: &mut World) {
                         }],
                     });
                 }
-            } else if let Some(bobber) = bobber_data.iter().find(|d| d.eid == eid) {
+            } else if let Some(bobber) = bobber_data.get(&eid) {
                 // Fishing bobber entity (type 129)
                 let vx = (bobber.vel.x * 8000.0) as i16;
                 let vy = (bobber.vel.y * 8000.0) as i16;
@@ -7503,7 +11590,7 @@ fn tick_entity_tracking(world: &mut World) {
                     velocity_y: vy,
                     velocity_z: vz,
                 });
-            } else if let Some(tnt) = tnt_data.iter().find(|d| d.eid == eid) {
+            } else if let Some(tnt) = tnt_data.get(&eid) {
                 // Primed TNT entity (type 106)
                 let vx = (tnt.vel.x * 8000.0) as i16;
                 let vy = (tnt.vel.y * 8000.0) as i16;
@@ -7529,10 +11616,47 @@ fn tick_entity_tracking(world: &mut World) {
                     entity_id: eid,
                     metadata,
                 });
+            } else if let Some(display) = display_data.get(&eid) {
+                let Ok(display_entity) = world.get::<&DisplayEntity>(display.entity) else {
+                    continue;
+                };
+                let _ = observer_sender.send(InternalPacket::SpawnEntity {
+                    entity_id: eid,
+                    entity_uuid: display.uuid,
+                    entity_type: display_entity.content.entity_type(),
+                    x: display.pos.x,
+                    y: display.pos.y,
+                    z: display.pos.z,
+                    pitch: degrees_to_angle(display.pitch),
+                    yaw: degrees_to_angle(display.yaw),
+                    head_yaw: degrees_to_angle(display.yaw),
+                    data: 0,
+                    velocity_x: 0,
+                    velocity_y: 0,
+                    velocity_z: 0,
+                });
+                let _ = observer_sender.send(InternalPacket::SetEntityMetadata {
+                    entity_id: eid,
+                    metadata: display_metadata(&display_entity),
+                });
+                if let Ok(name) = world.get::<&CustomName>(display.entity) {
+                    let _ = observer_sender.send(InternalPacket::SetEntityMetadata {
+                        entity_id: eid,
+                        metadata: build_custom_name_metadata(&name.text, name.visible),
+                    });
+                }
             }
-        }
 
-        // Despawn removed entities
+            // Glowing outline (players' flags byte is sent with their own state)
+            if glowing.contains(&eid) && !player_data.iter().any(|d| d.1 == eid) {
+                let _ = observer_sender.send(InternalPacket::SetEntityMetadata {
+                    entity_id: eid,
+                    metadata: build_entity_flags_metadata(0x40),
+                });
+            }
+        }
+
+        // Despawn removed entities
         let to_remove: Vec<i32> = currently_tracked.difference(&should_see).copied().collect();
         if !to_remove.is_empty() {
             let _ = observer_sender.send(InternalPacket::RemoveEntities {
@@ -7547,7 +11671,22 @@ fn tick_entity_tracking(world: &mut World) {
     }
 }
 
-fn tick_entity_movement_broadcast(world: &mut World) {
+/// Full metadata for a display entity: shared display settings plus its text, item or block.
+pub(crate) fn display_metadata(display: &DisplayEntity) -> Vec<pickaxe_protocol_core::EntityMetadataEntry> {
+    let mut metadata = build_display_metadata(display.billboard.id(), display.scale, display.glow_color);
+    metadata.extend(match &display.content {
+        DisplayContent::Text { text, line_width, background, shadow, see_through } => {
+            build_text_display_metadata(text, *line_width, *background, *shadow, *see_through)
+        }
+        DisplayContent::Item(item) => build_item_display_metadata(item),
+        DisplayContent::Block(state) => build_block_display_metadata(*state),
+    });
+    metadata
+}
+
+/// Send tracked entities' movement to the players who see them. Each kind of
+/// entity is synced at its own interval; Previous* hold what was last sent.
+fn tick_entity_movement_broadcast(world: &mut World, tick_count: u64) {
     // Collect player entities that moved or rotated (have PreviousRotation)
     let mut player_movers: Vec<(i32, Vec3d, Vec3d, f32, f32, f32, f32, bool)> = Vec::new();
 
@@ -7555,14 +11694,17 @@ fn tick_entity_movement_broadcast(world: &mut World) {
         .query::<(
             &EntityId,
             &Position,
-            &PreviousPosition,
+            &mut PreviousPosition,
             &Rotation,
-            &PreviousRotation,
+            &mut PreviousRotation,
             &OnGround,
             &Profile,
         )>()
         .iter()
     {
+        if !TrackedKind::Player.update_due(eid.0, tick_count) {
+            continue;
+        }
         let pos_changed =
             pos.0.x != prev_pos.0.x || pos.0.y != prev_pos.0.y || pos.0.z != prev_pos.0.z;
         let rot_changed = rot.yaw != prev_rot.yaw || rot.pitch != prev_rot.pitch;
@@ -7578,19 +11720,26 @@ fn tick_entity_movement_broadcast(world: &mut World) {
                 og.0,
             ));
         }
+        prev_pos.0 = pos.0;
+        prev_rot.yaw = rot.yaw;
+        prev_rot.pitch = rot.pitch;
     }
 
     // Collect item entities that moved (no rotation tracking needed)
     let mut item_movers: Vec<(i32, Vec3d, Vec3d, bool)> = Vec::new();
     for (_e, (eid, pos, prev_pos, og, _item)) in world
-        .query::<(&EntityId, &Position, &PreviousPosition, &OnGround, &ItemEntity)>()
+        .query::<(&EntityId, &Position, &mut PreviousPosition, &OnGround, &ItemEntity)>()
         .iter()
     {
+        if !TrackedKind::Item.update_due(eid.0, tick_count) {
+            continue;
+        }
         let pos_changed =
             pos.0.x != prev_pos.0.x || pos.0.y != prev_pos.0.y || pos.0.z != prev_pos.0.z;
         if pos_changed {
             item_movers.push((eid.0, pos.0, prev_pos.0, og.0));
         }
+        prev_pos.0 = pos.0;
     }
 
     // Collect mob entities that moved or rotated
@@ -7599,14 +11748,17 @@ fn tick_entity_movement_broadcast(world: &mut World) {
         .query::<(
             &EntityId,
             &Position,
-            &PreviousPosition,
+            &mut PreviousPosition,
             &Rotation,
-            &PreviousRotation,
+            &mut PreviousRotation,
             &OnGround,
             &MobEntity,
         )>()
         .iter()
     {
+        if !TrackedKind::Mob.update_due(eid.0, tick_count) {
+            continue;
+        }
         let pos_changed =
             pos.0.x != prev_pos.0.x || pos.0.y != prev_pos.0.y || pos.0.z != prev_pos.0.z;
         let rot_changed = rot.yaw != prev_rot.yaw || rot.pitch != prev_rot.pitch;
@@ -7622,45 +11774,60 @@ fn tick_entity_movement_broadcast(world: &mut World) {
                 og.0,
             ));
         }
+        prev_pos.0 = pos.0;
+        prev_rot.yaw = rot.yaw;
+        prev_rot.pitch = rot.pitch;
     }
 
     // Collect arrow entities that moved or rotated
     let mut arrow_movers: Vec<(i32, Vec3d, Vec3d, f32, f32, bool)> = Vec::new();
     for (_e, (eid, pos, prev_pos, rot, og, _arrow)) in world
-        .query::<(&EntityId, &Position, &PreviousPosition, &Rotation, &OnGround, &ArrowEntity)>()
+        .query::<(&EntityId, &Position, &mut PreviousPosition, &Rotation, &OnGround, &ArrowEntity)>()
         .iter()
     {
+        if !TrackedKind::Arrow.update_due(eid.0, tick_count) {
+            continue;
+        }
         let pos_changed =
             pos.0.x != prev_pos.0.x || pos.0.y != prev_pos.0.y || pos.0.z != prev_pos.0.z;
         if pos_changed {
             arrow_movers.push((eid.0, pos.0, prev_pos.0, rot.yaw, rot.pitch, og.0));
         }
+        prev_pos.0 = pos.0;
     }
 
     // Collect fishing bobber entities that moved
     let mut bobber_movers: Vec<(i32, Vec3d, Vec3d, bool)> = Vec::new();
     for (_e, (eid, pos, prev_pos, og, _bobber)) in world
-        .query::<(&EntityId, &Position, &PreviousPosition, &OnGround, &FishingBobber)>()
+        .query::<(&EntityId, &Position, &mut PreviousPosition, &OnGround, &FishingBobber)>()
         .iter()
     {
+        if !TrackedKind::Bobber.update_due(eid.0, tick_count) {
+            continue;
+        }
         let pos_changed =
             pos.0.x != prev_pos.0.x || pos.0.y != prev_pos.0.y || pos.0.z != prev_pos.0.z;
         if pos_changed {
             bobber_movers.push((eid.0, pos.0, prev_pos.0, og.0));
         }
+        prev_pos.0 = pos.0;
     }
 
     // Collect TNT entities that moved
     let mut tnt_movers: Vec<(i32, Vec3d, Vec3d, bool)> = Vec::new();
     for (_e, (eid, pos, prev_pos, og, _tnt)) in world
-        .query::<(&EntityId, &Position, &PreviousPosition, &OnGround, &TntEntity)>()
+        .query::<(&EntityId, &Position, &mut PreviousPosition, &OnGround, &TntEntity)>()
         .iter()
     {
+        if !TrackedKind::Tnt.update_due(eid.0, tick_count) {
+            continue;
+        }
         let pos_changed =
             pos.0.x != prev_pos.0.x || pos.0.y != prev_pos.0.y || pos.0.z != prev_pos.0.z;
         if pos_changed {
             tnt_movers.push((eid.0, pos.0, prev_pos.0, og.0));
         }
+        prev_pos.0 = pos.0;
     }
 
     // For each player mover, send packets to all observers tracking them
@@ -7675,78 +11842,56 @@ fn tick_entity_movement_broadcast(world: &mut World) {
             || (new_pos.y - old_pos.y).abs() > 8.0
             || (new_pos.z - old_pos.z).abs() > 8.0;
 
-        for (_e, (eid, tracked, sender)) in world
-            .query::<(&EntityId, &TrackedEntities, &ConnectionSender)>()
-            .iter()
-        {
-            if eid.0 == mover_eid {
-                continue;
+        let movement = if needs_teleport {
+            InternalPacket::TeleportEntity {
+                entity_id: mover_eid,
+                x: new_pos.x,
+                y: new_pos.y,
+                z: new_pos.z,
+                yaw: degrees_to_angle(yaw),
+                pitch: degrees_to_angle(pitch),
+                on_ground,
             }
-            if !tracked.visible.contains(&mover_eid) {
-                continue;
+        } else if pos_changed {
+            InternalPacket::UpdateEntityPositionAndRotation {
+                entity_id: mover_eid,
+                delta_x: dx,
+                delta_y: dy,
+                delta_z: dz,
+                yaw: degrees_to_angle(yaw),
+                pitch: degrees_to_angle(pitch),
+                on_ground,
             }
-
-            if needs_teleport {
-                let _ = sender.0.send(InternalPacket::TeleportEntity {
-                    entity_id: mover_eid,
-                    x: new_pos.x,
-                    y: new_pos.y,
-                    z: new_pos.z,
-                    yaw: degrees_to_angle(yaw),
-                    pitch: degrees_to_angle(pitch),
-                    on_ground,
-                });
-            } else if pos_changed {
-                let _ = sender.0.send(InternalPacket::UpdateEntityPositionAndRotation {
-                    entity_id: mover_eid,
-                    delta_x: dx,
-                    delta_y: dy,
-                    delta_z: dz,
-                    yaw: degrees_to_angle(yaw),
-                    pitch: degrees_to_angle(pitch),
-                    on_ground,
-                });
-            } else {
-                let _ = sender.0.send(InternalPacket::UpdateEntityRotation {
-                    entity_id: mover_eid,
-                    yaw: degrees_to_angle(yaw),
-                    pitch: degrees_to_angle(pitch),
-                    on_ground,
-                });
+        } else {
+            InternalPacket::UpdateEntityRotation {
+                entity_id: mover_eid,
+                yaw: degrees_to_angle(yaw),
+                pitch: degrees_to_angle(pitch),
+                on_ground,
             }
+        };
 
-            // Always send head rotation
-            let _ = sender.0.send(InternalPacket::SetHeadRotation {
-                entity_id: mover_eid,
-                head_yaw: degrees_to_angle(yaw),
-            });
-        }
+        // Always send head rotation
+        let head = InternalPacket::SetHeadRotation {
+            entity_id: mover_eid,
+            head_yaw: degrees_to_angle(yaw),
+        };
+        broadcast_to_trackers(world, mover_eid, vec![movement, head]);
     }
 
     // For each item mover, always use teleport for exact positioning
     // (delta updates accumulate rounding errors, and client-side prediction diverges)
     for &(mover_eid, new_pos, _old_pos, on_ground) in &item_movers {
-        for (_e, (eid, tracked, sender)) in world
-            .query::<(&EntityId, &TrackedEntities, &ConnectionSender)>()
-            .iter()
-        {
-            if eid.0 == mover_eid {
-                continue;
-            }
-            if !tracked.visible.contains(&mover_eid) {
-                continue;
-            }
-
-            let _ = sender.0.send(InternalPacket::TeleportEntity {
-                entity_id: mover_eid,
-                x: new_pos.x,
-                y: new_pos.y,
-                z: new_pos.z,
-                yaw: 0,
-                pitch: 0,
-                on_ground,
-            });
-        }
+        let teleport = InternalPacket::TeleportEntity {
+            entity_id: mover_eid,
+            x: new_pos.x,
+            y: new_pos.y,
+            z: new_pos.z,
+            yaw: 0,
+            pitch: 0,
+            on_ground,
+        };
+        broadcast_to_trackers(world, mover_eid, vec![teleport]);
     }
 
     // For each mob mover, send position+rotation updates
@@ -7761,51 +11906,40 @@ fn tick_entity_movement_broadcast(world: &mut World) {
             || (new_pos.y - old_pos.y).abs() > 8.0
             || (new_pos.z - old_pos.z).abs() > 8.0;
 
-        for (_e, (eid, tracked, sender)) in world
-            .query::<(&EntityId, &TrackedEntities, &ConnectionSender)>()
-            .iter()
-        {
-            if eid.0 == mover_eid {
-                continue;
+        let movement = if needs_teleport {
+            InternalPacket::TeleportEntity {
+                entity_id: mover_eid,
+                x: new_pos.x,
+                y: new_pos.y,
+                z: new_pos.z,
+                yaw: degrees_to_angle(yaw),
+                pitch: degrees_to_angle(pitch),
+                on_ground,
             }
-            if !tracked.visible.contains(&mover_eid) {
-                continue;
+        } else if pos_changed {
+            InternalPacket::UpdateEntityPositionAndRotation {
+                entity_id: mover_eid,
+                delta_x: dx,
+                delta_y: dy,
+                delta_z: dz,
+                yaw: degrees_to_angle(yaw),
+                pitch: degrees_to_angle(pitch),
+                on_ground,
             }
-
-            if needs_teleport {
-                let _ = sender.0.send(InternalPacket::TeleportEntity {
-                    entity_id: mover_eid,
-                    x: new_pos.x,
-                    y: new_pos.y,
-                    z: new_pos.z,
-                    yaw: degrees_to_angle(yaw),
-                    pitch: degrees_to_angle(pitch),
-                    on_ground,
-                });
-            } else if pos_changed {
-                let _ = sender.0.send(InternalPacket::UpdateEntityPositionAndRotation {
-                    entity_id: mover_eid,
-                    delta_x: dx,
-                    delta_y: dy,
-                    delta_z: dz,
-                    yaw: degrees_to_angle(yaw),
-                    pitch: degrees_to_angle(pitch),
-                    on_ground,
-                });
-            } else {
-                let _ = sender.0.send(InternalPacket::UpdateEntityRotation {
-                    entity_id: mover_eid,
-                    yaw: degrees_to_angle(yaw),
-                    pitch: degrees_to_angle(pitch),
-                    on_ground,
-                });
+        } else {
+            InternalPacket::UpdateEntityRotation {
+                entity_id: mover_eid,
+                yaw: degrees_to_angle(yaw),
+                pitch: degrees_to_angle(pitch),
+                on_ground,
             }
+        };
 
-            let _ = sender.0.send(InternalPacket::SetHeadRotation {
-                entity_id: mover_eid,
-                head_yaw: degrees_to_angle(yaw),
-            });
-        }
+        let head = InternalPacket::SetHeadRotation {
+            entity_id: mover_eid,
+            head_yaw: degrees_to_angle(yaw),
+        };
+        broadcast_to_trackers(world, mover_eid, vec![movement, head]);
     }
 
     // For each arrow mover, send position+rotation updates
@@ -7818,43 +11952,32 @@ fn tick_entity_movement_broadcast(world: &mut World) {
             || (new_pos.y - old_pos.y).abs() > 8.0
             || (new_pos.z - old_pos.z).abs() > 8.0;
 
-        for (_e, (eid, tracked, sender)) in world
-            .query::<(&EntityId, &TrackedEntities, &ConnectionSender)>()
-            .iter()
-        {
-            if eid.0 == mover_eid {
-                continue;
-            }
-            if !tracked.visible.contains(&mover_eid) {
-                continue;
+        let movement = if needs_teleport {
+            InternalPacket::TeleportEntity {
+                entity_id: mover_eid,
+                x: new_pos.x,
+                y: new_pos.y,
+                z: new_pos.z,
+                yaw: degrees_to_angle(yaw),
+                pitch: degrees_to_angle(pitch),
+                on_ground,
             }
-
-            if needs_teleport {
-                let _ = sender.0.send(InternalPacket::TeleportEntity {
-                    entity_id: mover_eid,
-                    x: new_pos.x,
-                    y: new_pos.y,
-                    z: new_pos.z,
-                    yaw: degrees_to_angle(yaw),
-                    pitch: degrees_to_angle(pitch),
-                    on_ground,
-                });
-            } else {
-                let _ = sender.0.send(InternalPacket::UpdateEntityPositionAndRotation {
-                    entity_id: mover_eid,
-                    delta_x: dx,
-                    delta_y: dy,
-                    delta_z: dz,
-                    yaw: degrees_to_angle(yaw),
-                    pitch: degrees_to_angle(pitch),
-                    on_ground,
-                });
+        } else {
+            InternalPacket::UpdateEntityPositionAndRotation {
+                entity_id: mover_eid,
+                delta_x: dx,
+                delta_y: dy,
+                delta_z: dz,
+                yaw: degrees_to_angle(yaw),
+                pitch: degrees_to_angle(pitch),
+                on_ground,
             }
-        }
+        };
+        broadcast_to_trackers(world, mover_eid, vec![movement]);
     }
 
-    // For each bobber mover, send position-only updates (like items)
-    for &(mover_eid, new_pos, old_pos, on_ground) in &bobber_movers {
+    // Bobbers and TNT send position-only updates (like items)
+    for &(mover_eid, new_pos, old_pos, on_ground) in bobber_movers.iter().chain(&tnt_movers) {
         let dx = ((new_pos.x - old_pos.x) * 4096.0) as i16;
         let dy = ((new_pos.y - old_pos.y) * 4096.0) as i16;
         let dz = ((new_pos.z - old_pos.z) * 4096.0) as i16;
@@ -7863,106 +11986,56 @@ fn tick_entity_movement_broadcast(world: &mut World) {
             || (new_pos.y - old_pos.y).abs() > 8.0
             || (new_pos.z - old_pos.z).abs() > 8.0;
 
-        for (_e, (eid, tracked, sender)) in world
-            .query::<(&EntityId, &TrackedEntities, &ConnectionSender)>()
-            .iter()
-        {
-            if eid.0 == mover_eid {
-                continue;
-            }
-            if !tracked.visible.contains(&mover_eid) {
-                continue;
+        let movement = if needs_teleport {
+            InternalPacket::TeleportEntity {
+                entity_id: mover_eid,
+                x: new_pos.x,
+                y: new_pos.y,
+                z: new_pos.z,
+                yaw: 0,
+                pitch: 0,
+                on_ground,
             }
-
-            if needs_teleport {
-                let _ = sender.0.send(InternalPacket::TeleportEntity {
-                    entity_id: mover_eid,
-                    x: new_pos.x,
-                    y: new_pos.y,
-                    z: new_pos.z,
-                    yaw: 0,
-                    pitch: 0,
-                    on_ground,
-                });
-            } else {
-                let _ = sender.0.send(InternalPacket::UpdateEntityPosition {
-                    entity_id: mover_eid,
-                    delta_x: dx,
-                    delta_y: dy,
-                    delta_z: dz,
-                    on_ground,
-                });
+        } else {
+            InternalPacket::UpdateEntityPosition {
+                entity_id: mover_eid,
+                delta_x: dx,
+                delta_y: dy,
+                delta_z: dz,
+                on_ground,
             }
-        }
+        };
+        broadcast_to_trackers(world, mover_eid, vec![movement]);
     }
+}
 
-    // For each TNT mover, send position-only updates (like items/bobbers)
-    for &(mover_eid, new_pos, old_pos, on_ground) in &tnt_movers {
-        let dx = ((new_pos.x - old_pos.x) * 4096.0) as i16;
-        let dy = ((new_pos.y - old_pos.y) * 4096.0) as i16;
-        let dz = ((new_pos.z - old_pos.z) * 4096.0) as i16;
-
-        let needs_teleport = (new_pos.x - old_pos.x).abs() > 8.0
-            || (new_pos.y - old_pos.y).abs() > 8.0
-            || (new_pos.z - old_pos.z).abs() > 8.0;
-
-        for (_e, (eid, tracked, sender)) in world
-            .query::<(&EntityId, &TrackedEntities, &ConnectionSender)>()
-            .iter()
-        {
-            if eid.0 == mover_eid {
-                continue;
-            }
-            if !tracked.visible.contains(&mover_eid) {
-                continue;
-            }
-
-            if needs_teleport {
-                let _ = sender.0.send(InternalPacket::TeleportEntity {
-                    entity_id: mover_eid,
-                    x: new_pos.x,
-                    y: new_pos.y,
-                    z: new_pos.z,
-                    yaw: 0,
-                    pitch: 0,
-                    on_ground,
-                });
-            } else {
-                let _ = sender.0.send(InternalPacket::UpdateEntityPosition {
-                    entity_id: mover_eid,
-                    delta_x: dx,
-                    delta_y: dy,
-                    delta_z: dz,
-                    on_ground,
-                });
-            }
+/// Send packets about an entity to every other player tracking it. Each
+/// packet is encoded once, however many players see it.
+fn broadcast_to_trackers(world: &World, mover_eid: i32, packets: Vec<InternalPacket>) {
+    let packets: Vec<InternalPacket> = packets.into_iter().map(InternalPacket::shared).collect();
+    for (_e, (eid, tracked, sender)) in world.query::<(&EntityId, &TrackedEntities, &ConnectionSender)>().iter() {
+        if eid.0 == mover_eid || !tracked.visible.contains(&mover_eid) {
+            continue;
+        }
+        for packet in &packets {
+            let _ = sender.0.send(packet.clone());
         }
-    }
-
-    // Update previous positions and rotations for all entities that have them
-    for (_e, (pos, prev_pos)) in world
-        .query::<(&Position, &mut PreviousPosition)>()
-        .iter()
-    {
-        prev_pos.0 = pos.0;
-    }
-
-    for (_e, (rot, prev_rot)) in world
-        .query::<(&Rotation, &mut PreviousRotation)>()
-        .iter()
-    {
-        prev_rot.yaw = rot.yaw;
-        prev_rot.pitch = rot.pitch;
     }
 }
 
-/// Advance world time each tick. Broadcast UpdateTime every 20 ticks (1 second).
+/// How often UpdateTime is broadcast so clients don't drift from server time (1 second).
+const TIME_SYNC_INTERVAL: u64 = 20;
+
+/// Advance world time each tick. Broadcast UpdateTime every TIME_SYNC_INTERVAL ticks,
+/// or immediately after time was changed by a command or script.
 fn tick_world_time(world: &World, world_state: &mut WorldState, tick_count: u64) {
     world_state.world_age += 1;
-    world_state.time_of_day = (world_state.time_of_day + 1) % 24000;
+    if world_state.game_rules.get_bool("doDaylightCycle") {
+        world_state.time_of_day = (world_state.time_of_day + 1) % 24000;
+    }
 
-    // Broadcast time update every 20 ticks (once per second)
-    if tick_count % 20 == 0 {
+    if tick_count % TIME_SYNC_INTERVAL == 0 || world_state.time_dirty {
+        world_state.time_dirty = false;
         broadcast_to_all(world, &InternalPacket::UpdateTime {
             world_age: world_state.world_age,
             time_of_day: world_state.time_of_day,
@@ -7970,14 +12043,35 @@ fn tick_world_time(world: &World, world_state: &mut WorldState, tick_count: u64)
     }
 }
 
+/// Send the full weather state (rain on/off plus current rain/thunder levels) to one player.
+/// Used for late joiners and respawns, where the client starts from clear skies.
+fn send_weather_state(sender: &mpsc::UnboundedSender<InternalPacket>, world_state: &WorldState) {
+    if world_state.raining || world_state.rain_level > 0.0 {
+        let _ = sender.send(InternalPacket::GameEvent {
+            event: 1, // START_RAINING
+            value: 0.0,
+        });
+        let _ = sender.send(InternalPacket::GameEvent {
+            event: 7, // RAIN_LEVEL_CHANGE
+            value: world_state.rain_level,
+        });
+        let _ = sender.send(InternalPacket::GameEvent {
+            event: 8, // THUNDER_LEVEL_CHANGE
+            value: world_state.thunder_level,
+        });
+    }
+}
+
 /// Advance the weather cycle. Matches vanilla MC logic:
 /// - Rain/thunder timers count down, toggling state when they reach 0
 /// - Rain/thunder levels transition gradually at ±0.01 per tick
 /// - GameEvent packets are broadcast when levels change
 fn tick_weather_cycle(world: &World, world_state: &mut WorldState, scripting: &ScriptRuntime) {
-    let was_raining = world_state.raining;
+    let was_raining = world_state.synced_raining;
 
-    if world_state.clear_weather_time > 0 {
+    if !world_state.game_rules.get_bool("doWeatherCycle") {
+        // Weather is frozen: timers stand still, levels still settle below
+    } else if world_state.clear_weather_time > 0 {
         world_state.clear_weather_time -= 1;
         world_state.thunder_time = if world_state.thundering { 0 } else { 1 };
         world_state.rain_time = if world_state.raining { 0 } else { 1 };
@@ -8013,6 +12107,28 @@ fn tick_weather_cycle(world: &World, world_state: &mut WorldState, scripting: &S
         }
     }
 
+    // Rain starting or stopping (naturally or by /weather) fires weather_change;
+    // cancelling keeps the current weather and the timers pick a new duration
+    if was_raining != world_state.raining {
+        let weather = if !world_state.raining {
+            "clear"
+        } else if world_state.thundering {
+            "thunder"
+        } else {
+            "rain"
+        };
+        let cancelled = scripting.fire_event_in_context(
+            "weather_change",
+            &[("weather", weather)],
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        );
+        if cancelled {
+            world_state.raining = was_raining;
+            world_state.thundering &= was_raining;
+        }
+    }
+
     // Gradual level transitions (±0.01 per tick, clamped to 0.0-1.0)
     let old_rain_level = world_state.rain_level;
     let old_thunder_level = world_state.thunder_level;
@@ -8046,6 +12162,7 @@ fn tick_weather_cycle(world: &World, world_state: &mut WorldState, scripting: &S
 
     // Start/stop rain events
     if was_raining != world_state.raining {
+        world_state.synced_raining = world_state.raining;
         if world_state.raining {
             broadcast_to_all(world, &InternalPacket::GameEvent {
                 event: 1, // START_RAINING
@@ -8067,21 +12184,6 @@ fn tick_weather_cycle(world: &World, world_state: &mut WorldState, scripting: &S
             event: 8,
             value: world_state.thunder_level,
         });
-
-        // Fire Lua event
-        let weather = if world_state.thundering {
-            "thunder"
-        } else if world_state.raining {
-            "rain"
-        } else {
-            "clear"
-        };
-        scripting.fire_event_in_context(
-            "weather_change",
-            &[("weather", weather)],
-            std::ptr::null_mut(),
-            std::ptr::null_mut(),
-        );
     }
 }
 
@@ -8124,7 +12226,12 @@ fn strike_lightning(
 
     // Damage players
     let mut player_hits: Vec<(hecs::Entity, i32)> = Vec::new();
-    for (entity, (entity_id, pos, _profile)) in world.query::<(&EntityId, &Position, &Profile)>().iter() {
+    for (entity, (entity_id, pos, _profile, dimension)) in
+        world.query::<(&EntityId, &Position, &Profile, Option<&Dimension>)>().iter()
+    {
+        if !in_level(world_state, dimension) {
+            continue;
+        }
         let dx = pos.0.x - x;
         let dy = pos.0.y - y;
         let dz = pos.0.z - z;
@@ -8201,6 +12308,11 @@ fn tick_lightning(
     next_eid: &Arc<AtomicI32>,
     scripting: &ScriptRuntime,
 ) {
+    // Strikes requested by mods happen in any weather
+    for pos in std::mem::take(&mut world_state.pending_lightning) {
+        strike_lightning(world, world_state, next_eid, pos.x, pos.y, pos.z, scripting);
+    }
+
     // Only during thunderstorms
     if world_state.thunder_level <= 0.0 {
         return;
@@ -8213,8 +12325,9 @@ fn tick_lightning(
     }
 
     // Pick a random player to strike near
-    let player_positions: Vec<Vec3d> = world.query::<(&Position, &Profile)>().iter()
-        .map(|(_, (p, _))| p.0)
+    let player_positions: Vec<Vec3d> = world.query::<(&Position, &Profile, Option<&Dimension>)>().iter()
+        .filter(|(_, (_, _, dimension))| in_level(world_state, *dimension))
+        .map(|(_, (p, _, _))| p.0)
         .collect();
 
     if player_positions.is_empty() {
@@ -8243,6 +12356,19 @@ fn tick_lightning(
         }
     }
 
+    // A lightning rod under open sky within 128 blocks draws the strike
+    let target = BlockPos::new(bx, strike_y as i32, bz);
+    let rods: Vec<BlockPos> = world_state
+        .find_poi(&target, 128, Some("minecraft:lightning_rod"))
+        .iter()
+        .map(|r| r.pos)
+        .collect();
+    let rod = rods.into_iter().find(|rod| world_state.height_at(rod.x, rod.z) == rod.y + 1);
+    let (strike_x, strike_y, strike_z) = match rod {
+        Some(rod) => (rod.x as f64 + 0.5, rod.y as f64 + 1.0, rod.z as f64 + 0.5),
+        None => (strike_x, strike_y, strike_z),
+    };
+
     strike_lightning(world, world_state, next_eid, strike_x, strike_y, strike_z, scripting);
 }
 
@@ -8391,8 +12517,18 @@ fn complete_block_break(
         world as *mut _ as *mut (),
         world_state as *mut _ as *mut (),
     );
+    // Then the block's own Lua handler, which may also pick the drops
+    let response = if cancelled {
+        BlockResponse::default()
+    } else {
+        let player = name.clone();
+        fire_block_handler(
+            world, world_state, scripting, block_overrides, BlockHandler::Break, position, old_block,
+            |event| event.set("player", player),
+        )
+    };
 
-    if cancelled {
+    if cancelled || response.cancelled {
         // Send block correction (restore original) + ack to prevent desync
         if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
             let _ = sender.0.send(InternalPacket::BlockUpdate {
@@ -8416,7 +12552,15 @@ fn complete_block_break(
     }
 
     // Proceed with the break
+    let old_name = pickaxe_data::block_state_to_name(old_block).unwrap_or("unknown");
+    world_state.audit.record(&name, AuditAction::Break, Some(*position), old_name);
     world_state.set_block(position, 0);
+    let creative = world.get::<&PlayerGameMode>(entity).is_ok_and(|gm| gm.0 == GameMode::Creative);
+    if !creative {
+        if let Ok(mut stats) = world.get::<&mut Statistics>(entity) {
+            stats.add(StatType::Mined, old_name, 1);
+        }
+    }
 
     // Special handling for beds: break other half and wake sleeping players
     if pickaxe_data::is_bed(old_block) {
@@ -8432,7 +12576,7 @@ fn complete_block_break(
         let other_block = world_state.get_block(&other_pos);
         if pickaxe_data::is_bed(other_block) {
             world_state.set_block(&other_pos, 0);
-            broadcast_to_all(world, &InternalPacket::BlockUpdate {
+            broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
                 position: other_pos,
                 block_id: 0,
             });
@@ -8462,7 +12606,7 @@ fn complete_block_break(
             let head_block = world_state.get_block(&head_pos);
             if pickaxe_data::is_piston_head(head_block) {
                 world_state.set_block(&head_pos, 0);
-                broadcast_to_all(world, &InternalPacket::BlockUpdate {
+                broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
                     position: head_pos,
                     block_id: 0,
                 });
@@ -8477,7 +12621,7 @@ fn complete_block_break(
             if pickaxe_data::is_any_piston(base_block) && pickaxe_data::piston_is_extended(base_block) {
                 let retracted = pickaxe_data::piston_state(facing, false, is_sticky);
                 world_state.set_block(&base_pos, retracted);
-                broadcast_to_all(world, &InternalPacket::BlockUpdate {
+                broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
                     position: base_pos,
                     block_id: retracted,
                 });
@@ -8508,8 +12652,9 @@ fn complete_block_break(
     }
 
     // Broadcast block update to other players
-    broadcast_except(
+    broadcast_to_level_except(
         world,
+        world_state,
         entity_id,
         &InternalPacket::BlockUpdate {
             position: *position,
@@ -8551,34 +12696,20 @@ fn complete_block_break(
         .map(|gm| gm.0)
         .unwrap_or(GameMode::Survival);
 
-    if game_mode == GameMode::Survival {
-        // Handle crop drops specially
-        if let Some((drop_name, drop_min, drop_max, seed_name, seed_min, seed_max)) = pickaxe_data::crop_drops(old_block) {
-            let mut rng = rand::thread_rng();
-            // Drop main item
-            let count = rng.gen_range(drop_min..=drop_max);
-            if count > 0 {
-                if let Some(drop_id) = pickaxe_data::item_name_to_id(drop_name) {
-                    spawn_item_entity(
-                        world, world_state, next_eid,
-                        position.x as f64 + 0.5, position.y as f64 + 0.25, position.z as f64 + 0.5,
-                        ItemStack::new(drop_id, count as i8), 10, scripting,
-                    );
-                }
-            }
-            // Drop seeds (if applicable)
-            if !seed_name.is_empty() && seed_max > 0 {
-                let seed_count = rng.gen_range(seed_min..=seed_max);
-                if seed_count > 0 {
-                    if let Some(seed_id) = pickaxe_data::item_name_to_id(seed_name) {
-                        spawn_item_entity(
-                            world, world_state, next_eid,
-                            position.x as f64 + 0.5, position.y as f64 + 0.25, position.z as f64 + 0.5,
-                            ItemStack::new(seed_id, seed_count as i8), 10, scripting,
-                        );
-                    }
-                }
+    if let Some(drops) = response.drops.filter(|_| game_mode == GameMode::Survival) {
+        // Drops chosen by the block's on_break handler replace the normal ones
+        if world_state.game_rules.get_bool("doTileDrops") {
+            for stack in drops {
+                spawn_item_entity(
+                    world, world_state, next_eid,
+                    position.x as f64 + 0.5, position.y as f64 + 0.25, position.z as f64 + 0.5,
+                    stack, 10, scripting,
+                );
             }
+        }
+    } else if game_mode == GameMode::Survival {
+        // Handle crop drops specially
+        if spawn_crop_drops(world, world_state, next_eid, position, old_block, scripting) {
             // Apply exhaustion for mining
             if let Ok(mut food) = world.get::<&mut FoodData>(entity) {
                 food.exhaustion += 0.005;
@@ -8631,7 +12762,7 @@ fn complete_block_break(
             }
         };
 
-        if has_correct_tool {
+        if has_correct_tool && world_state.game_rules.get_bool("doTileDrops") {
             // Get fortune and silk touch levels from held item
             let (fortune_level, silk_touch) = {
                 let slot = world.get::<&HeldSlot>(entity).map(|h| h.0).unwrap_or(0);
@@ -8715,7 +12846,9 @@ fn complete_block_break(
                 v.extend(fuel.into_iter());
                 v
             }
-            BlockEntity::Sign { .. } => Vec::new(), // Signs have no items to drop
+            BlockEntity::Sign { .. } | BlockEntity::Bell { .. } => Vec::new(),
+            BlockEntity::Lectern { book, .. } => book.into_iter().collect(),
+            BlockEntity::Campfire { items, .. } => items.into_iter().flatten().collect(),
         };
         for item in items {
             spawn_item_entity(
@@ -8757,17 +12890,33 @@ fn drop_held_item(
     let drop_count = if drop_stack { item.count } else { 1 };
     let remaining = item.count - drop_count;
 
+    // Fire cancellable Lua event; cancelling puts the item back
+    let name = world.get::<&Profile>(entity).map(|p| p.0.name.clone()).unwrap_or_default();
+    let cancelled = scripting.fire_event_in_context(
+        "item_drop",
+        &[
+            ("name", &name),
+            ("item_id", &item.item_id.to_string()),
+            ("item_name", pickaxe_data::item_id_to_name(item.item_id).unwrap_or("unknown")),
+            ("item_count", &drop_count.to_string()),
+        ],
+        world as *mut _ as *mut (),
+        world_state as *mut _ as *mut (),
+    );
+    // Dropping keeps the stack's components (name, enchantments, custom id)
+    let slot_item = if cancelled {
+        Some(item.clone())
+    } else {
+        (remaining > 0).then(|| ItemStack { count: remaining, ..item.clone() })
+    };
+
     // Update inventory
     {
         let mut inv = match world.get::<&mut Inventory>(entity) {
             Ok(inv) => inv,
             Err(_) => return,
         };
-        if remaining > 0 {
-            inv.set_slot(slot_index, Some(ItemStack::new(item.item_id, remaining)));
-        } else {
-            inv.set_slot(slot_index, None);
-        }
+        inv.set_slot(slot_index, slot_item.clone());
     }
 
     // Send slot update to client
@@ -8776,11 +12925,6 @@ fn drop_held_item(
         .map(|inv| inv.state_id)
         .unwrap_or(1);
     if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
-        let slot_item = if remaining > 0 {
-            Some(ItemStack::new(item.item_id, remaining))
-        } else {
-            None
-        };
         let _ = sender.0.send(InternalPacket::SetContainerSlot {
             window_id: 0,
             state_id,
@@ -8788,6 +12932,9 @@ fn drop_held_item(
             item: slot_item,
         });
     }
+    if cancelled {
+        return;
+    }
 
     // Get player position and look direction for throw velocity
     let (pos, yaw, pitch) = {
@@ -8812,7 +12959,7 @@ fn drop_held_item(
     let eid = next_eid.fetch_add(1, Ordering::Relaxed);
     let uuid = Uuid::new_v4();
 
-    let drop_item = ItemStack::new(item.item_id, drop_count);
+    let drop_item = ItemStack { count: drop_count, ..item };
     let item_id = drop_item.item_id;
     let item_count = drop_item.count;
 
@@ -9063,6 +13210,145 @@ fn spawn_arrow(
     (entity, eid)
 }
 
+/// Run the Lua callbacks of finished `pickaxe.http` requests.
+fn tick_http_responses(world: &mut World, world_state: &mut WorldState, scripting: &ScriptRuntime) {
+    let completed = match world_state.http.lock() {
+        Ok(mut http) => http.take_completed(),
+        Err(_) => return,
+    };
+    let lua = scripting.lua();
+    for (key, result) in completed {
+        let callback = lua.registry_value::<mlua::Function>(&key);
+        let _ = lua.remove_registry_value(key);
+        if let Ok(callback) = callback {
+            with_lua_context(world, world_state, scripting, |lua| crate::bridge::call_http_callback(lua, callback, result));
+        }
+    }
+}
+
+/// Draw the `pickaxe.particles.emitter` shapes due this tick.
+fn tick_particle_emitters(world: &World, world_state: &mut WorldState) {
+    let tick = world_state.tick_count;
+    // Emitters are cosmetic: while shedding load they still expire but draw nothing
+    let draw = world_state.load.level().sends_cosmetics();
+    for emitter in world_state.particle_emitters.due(tick).filter(|_| draw) {
+        send_particle_points(world, &emitter.effect, &emitter.points, &emitter.players);
+    }
+}
+
+/// Fire `chunk_load` for every chunk loaded from disk or generated since
+/// the last call, with its level switched in so handlers see its blocks.
+fn fire_chunk_load_events(world: &mut World, world_state: &mut WorldState, scripting: &ScriptRuntime) {
+    for level in world_state.level_names() {
+        world_state.switch_level(&level);
+        for (pos, generated) in std::mem::take(&mut world_state.level.loaded_chunks) {
+            scripting.fire_event_in_context(
+                "chunk_load",
+                &[
+                    ("x", &pos.x.to_string()),
+                    ("z", &pos.z.to_string()),
+                    ("generated", if generated { "true" } else { "false" }),
+                    ("world", &level),
+                ],
+                world as *mut _ as *mut (),
+                world_state as *mut _ as *mut (),
+            );
+        }
+    }
+    world_state.switch_level(DEFAULT_LEVEL);
+}
+
+/// Each default-level player's chunk and simulation distance, as
+/// `(chunk_x, chunk_z, distance)`. Other levels aren't simulated.
+fn simulation_areas(world: &World) -> Vec<(i32, i32, i32)> {
+    world
+        .query::<(&ChunkPosition, &SimulationDistance, Option<&Dimension>)>()
+        .iter()
+        .filter(|(_, (_, _, dimension))| dimension.is_none())
+        .map(|(_, (cp, sd, _))| (cp.chunk_x, cp.chunk_z, sd.0))
+        .collect()
+}
+
+/// Whether a player with this `Dimension` is in the current level.
+fn in_level(world_state: &WorldState, dimension: Option<&Dimension>) -> bool {
+    dimension.map_or(DEFAULT_LEVEL, |d| d.0.as_str()) == world_state.level.name
+}
+
+/// Whether blocks in this chunk random-tick: it's within some player's
+/// simulation distance.
+fn chunk_is_simulated(areas: &[(i32, i32, i32)], chunk_x: i32, chunk_z: i32) -> bool {
+    areas
+        .iter()
+        .any(|&(cx, cz, distance)| (chunk_x - cx).abs() <= distance && (chunk_z - cz).abs() <= distance)
+}
+
+/// Whether an entity at `pos` is in a simulated chunk.
+fn is_simulated(areas: &[(i32, i32, i32)], pos: &Vec3d) -> bool {
+    chunk_is_simulated(areas, (pos.x.floor() as i32) >> 4, (pos.z.floor() as i32) >> 4)
+}
+
+/// Entities within `radius` blocks of `center`, with their positions.
+/// Players in other levels are left out. Found through the index entity
+/// tracking built at the end of the last tick, so entities spawned since
+/// (drops, primed TNT, summoned mobs) aren't found until the next tick.
+fn entities_in_radius(world: &World, world_state: &WorldState, center: Vec3d, radius: f64) -> Vec<(hecs::Entity, Vec3d)> {
+    world_state
+        .entity_index
+        .near(center, radius)
+        .filter_map(|e| {
+            let pos = world.get::<&Position>(e).ok()?.0;
+            let dimension = world.get::<&Dimension>(e).ok();
+            let (dx, dy, dz) = (pos.x - center.x, pos.y - center.y, pos.z - center.z);
+            (dx * dx + dy * dy + dz * dz <= radius * radius && in_level(world_state, dimension.as_deref()))
+                .then_some((e, pos))
+        })
+        .collect()
+}
+
+/// Random ticks for blocks with a Lua `on_random_tick` handler, sampled the
+/// same way as `tick_farming`.
+fn tick_lua_random_ticks(
+    world: &mut World,
+    world_state: &mut WorldState,
+    scripting: &ScriptRuntime,
+    block_overrides: &crate::bridge::BlockOverrides,
+) {
+    let names = crate::bridge::random_tick_blocks(block_overrides);
+    if names.is_empty() {
+        return;
+    }
+    let mut rng = rand::thread_rng();
+    let mut ticked: Vec<(BlockPos, i32)> = Vec::new();
+    let simulated = simulation_areas(world);
+    for (chunk_pos, chunk) in &world_state.level.chunks {
+        if !chunk_is_simulated(&simulated, chunk_pos.x, chunk_pos.z) {
+            continue;
+        }
+        for section_y in 0..24 {
+            let world_y = section_y * 16 - 64;
+            for _ in 0..3 {
+                let local_x = rng.gen_range(0..16);
+                let local_z = rng.gen_range(0..16);
+                let by = world_y + rng.gen_range(0..16);
+                let block = chunk.get_block(local_x, by, local_z);
+                if block == 0 {
+                    continue;
+                }
+                if pickaxe_data::block_state_to_name(block).is_some_and(|name| names.contains(name)) {
+                    ticked.push((BlockPos::new(chunk_pos.x * 16 + local_x as i32, by, chunk_pos.z * 16 + local_z as i32), block));
+                }
+            }
+        }
+    }
+    for (pos, state) in ticked {
+        // An earlier handler may have changed this block
+        if world_state.get_block_if_loaded(&pos) != Some(state) {
+            continue;
+        }
+        fire_block_handler(world, world_state, scripting, block_overrides, BlockHandler::RandomTick, &pos, state, |_| Ok(()));
+    }
+}
+
 /// Tick crop growth and farmland moisture. Runs every 68 ticks (~3.4 seconds) to approximate
 /// MC's random tick system. Scans all loaded chunks for crops and farmland.
 fn tick_farming(world: &World, world_state: &mut WorldState) {
@@ -9070,13 +13356,20 @@ fn tick_farming(world: &World, world_state: &mut WorldState) {
     let mut updates: Vec<(BlockPos, i32)> = Vec::new();
     let mut rng = rand::thread_rng();
 
-    // Get all loaded chunk positions
-    let chunk_positions: Vec<pickaxe_types::ChunkPos> = world_state.chunks.keys().cloned().collect();
+    // Loaded chunks within some player's simulation distance
+    let simulated = simulation_areas(world);
+    let chunk_positions: Vec<pickaxe_types::ChunkPos> = world_state
+        .level
+        .chunks
+        .keys()
+        .filter(|pos| chunk_is_simulated(&simulated, pos.x, pos.z))
+        .cloned()
+        .collect();
 
     for chunk_pos in chunk_positions {
         // Simulate random tick: 3 random blocks per chunk section per tick (MC default)
         // Since we run less often, check more blocks
-        let chunk = match world_state.chunks.get(&chunk_pos) {
+        let chunk = match world_state.level.chunks.get(&chunk_pos) {
             Some(c) => c,
             None => continue,
         };
@@ -9123,15 +13416,17 @@ fn tick_farming(world: &World, world_state: &mut WorldState) {
                 // Farmland moisture
                 if pickaxe_data::is_farmland(block) {
                     let moisture = pickaxe_data::farmland_moisture(block).unwrap_or(0);
-                    // Check for water within 4 blocks horizontally, 1 vertically
-                    let has_water = 'water: {
+                    // Check for water (including waterlogged blocks) within 4 blocks
+                    // horizontally, 0-1 above; rain on open sky also keeps it wet
+                    let rained_on = world_state.raining && by + 1 >= chunk.height_at(local_x, local_z);
+                    let has_water = rained_on || 'water: {
                         for wx in (bx - 4)..=(bx + 4) {
                             for wz in (bz - 4)..=(bz + 4) {
                                 for wy in by..=(by + 1) {
                                     let wpos = BlockPos::new(wx, wy, wz);
                                     // Only check loaded chunks
                                     if let Some(wblock) = world_state.get_block_if_loaded(&wpos) {
-                                        if pickaxe_data::is_water(wblock) {
+                                        if pickaxe_data::is_water(wblock) || pickaxe_data::is_waterlogged(wblock) {
                                             break 'water true;
                                         }
                                     }
@@ -9163,13 +13458,97 @@ fn tick_farming(world: &World, world_state: &mut WorldState) {
     // Apply updates and broadcast
     for (pos, new_state) in updates {
         world_state.set_block(&pos, new_state);
-        broadcast_to_all(world, &InternalPacket::BlockUpdate {
+        broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
+            position: pos,
+            block_id: new_state,
+        });
+    }
+}
+
+/// Rain falling into open-air cauldrons (vanilla `ServerLevel.tickPrecipitation`):
+/// each tick, every loaded chunk has a 1 in 16 chance to pick a random column,
+/// and a cauldron on top of it gains a level of water 5% of the time.
+fn tick_precipitation(world: &World, world_state: &mut WorldState) {
+    use pickaxe_data::CauldronContents;
+
+    if !world_state.raining {
+        return;
+    }
+    let mut rng = rand::thread_rng();
+    let mut updates: Vec<(BlockPos, i32)> = Vec::new();
+
+    for (chunk_pos, chunk) in world_state.level.chunks.iter() {
+        if rng.gen_range(0..16) != 0 {
+            continue;
+        }
+        let local_x = rng.gen_range(0..16);
+        let local_z = rng.gen_range(0..16);
+        // Topmost non-air block in the column is the one rain lands on
+        let top_y = chunk.height_at(local_x, local_z) - 1;
+        if top_y < -64 {
+            continue;
+        }
+        let block = chunk.get_block(local_x, top_y, local_z);
+        let Some((contents, level)) = pickaxe_data::cauldron_contents(block) else { continue };
+        if !matches!(contents, CauldronContents::Empty | CauldronContents::Water) || level >= 3 {
+            continue;
+        }
+        if rng.gen::<f32>() < 0.05 {
+            let pos = BlockPos::new(chunk_pos.x * 16 + local_x as i32, top_y, chunk_pos.z * 16 + local_z as i32);
+            updates.push((pos, pickaxe_data::cauldron_state(CauldronContents::Water, level + 1)));
+        }
+    }
+
+    for (pos, new_state) in updates {
+        world_state.set_block(&pos, new_state);
+        broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
             position: pos,
             block_id: new_state,
         });
     }
 }
 
+/// Spawn the drops for a crop block (produce plus seeds when fully grown).
+/// Returns false if the block isn't a crop.
+pub(crate) fn spawn_crop_drops(
+    world: &mut World,
+    world_state: &mut WorldState,
+    next_eid: &Arc<AtomicI32>,
+    position: &BlockPos,
+    state: i32,
+    scripting: &ScriptRuntime,
+) -> bool {
+    let Some((drop_name, drop_min, drop_max, seed_name, seed_min, seed_max)) = pickaxe_data::crop_drops(state) else {
+        return false;
+    };
+    let mut rng = rand::thread_rng();
+    // Drop main item
+    let count = rng.gen_range(drop_min..=drop_max);
+    if count > 0 {
+        if let Some(drop_id) = pickaxe_data::item_name_to_id(drop_name) {
+            spawn_item_entity(
+                world, world_state, next_eid,
+                position.x as f64 + 0.5, position.y as f64 + 0.25, position.z as f64 + 0.5,
+                ItemStack::new(drop_id, count as i8), 10, scripting,
+            );
+        }
+    }
+    // Drop seeds (if applicable)
+    if !seed_name.is_empty() && seed_max > 0 {
+        let seed_count = rng.gen_range(seed_min..=seed_max);
+        if seed_count > 0 {
+            if let Some(seed_id) = pickaxe_data::item_name_to_id(seed_name) {
+                spawn_item_entity(
+                    world, world_state, next_eid,
+                    position.x as f64 + 0.5, position.y as f64 + 0.25, position.z as f64 + 0.5,
+                    ItemStack::new(seed_id, seed_count as i8), 10, scripting,
+                );
+            }
+        }
+    }
+    true
+}
+
 /// Tick fire blocks: age progression, spread, burnout, block destruction.
 /// Runs every 35 ticks (~1.75 seconds), simulating MC's 30-40 tick random delay.
 fn tick_fire(
@@ -9179,14 +13558,17 @@ fn tick_fire(
     scripting: &ScriptRuntime,
 ) {
     use rand::Rng;
+    if !world_state.game_rules.get_bool("doFireTick") {
+        return;
+    }
     let mut rng = rand::thread_rng();
 
     // Phase 1: Collect all fire block positions from chunks (immutable borrow)
     let mut fire_blocks: Vec<(BlockPos, i32)> = Vec::new(); // (pos, state_id)
     {
-        let chunk_positions: Vec<pickaxe_types::ChunkPos> = world_state.chunks.keys().cloned().collect();
+        let chunk_positions: Vec<pickaxe_types::ChunkPos> = world_state.level.chunks.keys().cloned().collect();
         for chunk_pos in chunk_positions {
-            let chunk = match world_state.chunks.get(&chunk_pos) {
+            let chunk = match world_state.level.chunks.get(&chunk_pos) {
                 Some(c) => c,
                 None => continue,
             };
@@ -9213,42 +13595,39 @@ fn tick_fire(
     let mut updates: Vec<(BlockPos, i32)> = Vec::new();
     let mut tnt_ignitions: Vec<BlockPos> = Vec::new();
 
+    // How easily fire catches at `pos`: 0 unless it's air, else the best ignite odds of its neighbors
+    let ignite_odds = |world_state: &mut WorldState, pos: &BlockPos| -> i32 {
+        if world_state.get_block(pos) != 0 {
+            return 0;
+        }
+        let offsets: [(i32,i32,i32); 6] = [(1,0,0),(-1,0,0),(0,1,0),(0,-1,0),(0,0,1),(0,0,-1)];
+        offsets.iter().map(|(dx, dy, dz)| {
+            let adj = world_state.get_block(&BlockPos::new(pos.x + dx, pos.y + dy, pos.z + dz));
+            pickaxe_data::block_flammability(pickaxe_data::block_state_to_name(adj).unwrap_or("")).0
+        }).max().unwrap_or(0)
+    };
+    // Rain reaches `pos` or one of its horizontal neighbors
+    let near_rain = |world_state: &mut WorldState, pos: &BlockPos| -> bool {
+        [(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1)].iter()
+            .any(|(dx, dz)| world_state.is_raining_at(&BlockPos::new(pos.x + dx, pos.y, pos.z + dz)))
+    };
+
     for (fire_pos, block) in &fire_blocks {
         let age = pickaxe_data::fire_age(*block);
         let bx = fire_pos.x;
         let by = fire_pos.y;
         let bz = fire_pos.z;
 
-        // Rain extinguishes fire (vanilla: 0.2 + age * 0.03 chance when raining near rain)
-        if world_state.raining {
-            let rain_chance = 0.2 + age as f64 * 0.03;
-            if rng.gen::<f64>() < rain_chance {
-                updates.push((*fire_pos, 0));
-                continue;
-            }
-        }
-
-        // Check if fire can survive: needs solid block below or adjacent flammable
         let below = BlockPos::new(bx, by - 1, bz);
         let below_block = world_state.get_block(&below);
-        let below_solid = below_block != 0 && !pickaxe_data::is_fire(below_block);
-
-        let has_fuel = {
-            let offsets: [(i32,i32,i32); 6] = [(1,0,0),(-1,0,0),(0,1,0),(0,-1,0),(0,0,1),(0,0,-1)];
-            offsets.iter().any(|(dx, dy, dz)| {
-                let adj = BlockPos::new(bx + dx, by + dy, bz + dz);
-                let adj_block = world_state.get_block(&adj);
-                let adj_name = pickaxe_data::block_state_to_name(adj_block).unwrap_or("");
-                pickaxe_data::is_flammable(adj_name)
-            })
-        };
+        let below_name = pickaxe_data::block_state_to_name(below_block).unwrap_or("");
+        // Netherrack and magma blocks burn forever and ignore rain
+        let infiniburn = pickaxe_data::is_infiniburn(below_name);
 
-        // Fire dies if no support and no fuel, or age 15 with no fuel
-        if !below_solid && !has_fuel {
-            updates.push((*fire_pos, 0));
-            continue;
-        }
-        if age >= 15 && !has_fuel && rng.gen_range(0..4) == 0 {
+        // Rain extinguishes exposed fire: 0.2 + age * 0.03 chance
+        if !infiniburn && world_state.raining && near_rain(world_state, fire_pos)
+            && rng.gen::<f32>() < 0.2 + age as f32 * 0.03
+        {
             updates.push((*fire_pos, 0));
             continue;
         }
@@ -9259,6 +13638,28 @@ fn tick_fire(
             updates.push((*fire_pos, pickaxe_data::fire_state_with_age(new_age)));
         }
 
+        if !infiniburn {
+            let has_fuel = {
+                let offsets: [(i32,i32,i32); 6] = [(1,0,0),(-1,0,0),(0,1,0),(0,-1,0),(0,0,1),(0,0,-1)];
+                offsets.iter().any(|(dx, dy, dz)| {
+                    let adj = world_state.get_block(&BlockPos::new(bx + dx, by + dy, bz + dz));
+                    pickaxe_data::is_flammable(pickaxe_data::block_state_to_name(adj).unwrap_or(""))
+                })
+            };
+            // Without fuel, fire only lingers briefly on a solid block
+            if !has_fuel {
+                if !pickaxe_data::is_solid_block(below_block) || age > 3 {
+                    updates.push((*fire_pos, 0));
+                }
+                continue;
+            }
+            // Fully aged fire burns out unless the block below is flammable
+            if age == 15 && rng.gen_range(0..4) == 0 && !pickaxe_data::is_flammable(below_name) {
+                updates.push((*fire_pos, 0));
+                continue;
+            }
+        }
+
         // Burn adjacent flammable blocks (checkBurnOut equivalent)
         let direction_odds: [(i32,i32,i32,i32); 6] = [
             (1, 0, 0, 300),   // east
@@ -9274,23 +13675,23 @@ fn tick_fire(
             let adj_block = world_state.get_block(&adj);
             let adj_name = pickaxe_data::block_state_to_name(adj_block).unwrap_or("");
             let (_, burn_odds) = pickaxe_data::block_flammability(adj_name);
-            if burn_odds > 0 && rng.gen_range(0..*odds) < burn_odds {
-                if adj_name == "tnt" {
-                    tnt_ignitions.push(adj);
-                    updates.push((adj, 0)); // remove the TNT block
-                } else if rng.gen_range(0..(new_age + 10)) < 5 {
+            if rng.gen_range(0..*odds) < burn_odds {
+                if rng.gen_range(0..(age + 10)) < 5 && !world_state.is_raining_at(&adj) {
                     // Replace with fire
-                    let fire_age = (new_age + rng.gen_range(0..5) / 4).min(15);
+                    let fire_age = (age + rng.gen_range(0..5) / 4).min(15);
                     updates.push((adj, pickaxe_data::fire_state_with_age(fire_age)));
                 } else {
                     // Destroy block
                     updates.push((adj, 0));
                 }
+                if adj_name == "tnt" {
+                    tnt_ignitions.push(adj);
+                }
             }
         }
 
         // Fire spread to nearby air blocks adjacent to flammable blocks
-        // Search 3x3x5 cube (x: -1 to 1, z: -1 to 1, y: -1 to 4)
+        // Search 3x3x6 volume (x: -1 to 1, z: -1 to 1, y: -1 to 4)
         for sx in -1..=1i32 {
             for sz in -1..=1i32 {
                 for sy in -1..=4i32 {
@@ -9298,35 +13699,23 @@ fn tick_fire(
                         continue;
                     }
                     let spread_pos = BlockPos::new(bx + sx, by + sy, bz + sz);
-                    let spread_block = world_state.get_block(&spread_pos);
-                    if spread_block != 0 {
-                        continue; // must be air
-                    }
-
-                    // Check if any adjacent block to spread_pos is flammable
-                    let mut max_ignite = 0i32;
-                    let adj_offsets: [(i32,i32,i32); 6] = [(1,0,0),(-1,0,0),(0,1,0),(0,-1,0),(0,0,1),(0,0,-1)];
-                    for (ax, ay, az) in &adj_offsets {
-                        let check_pos = BlockPos::new(spread_pos.x + ax, spread_pos.y + ay, spread_pos.z + az);
-                        let check_block = world_state.get_block(&check_pos);
-                        let check_name = pickaxe_data::block_state_to_name(check_block).unwrap_or("");
-                        let (ignite, _) = pickaxe_data::block_flammability(check_name);
-                        max_ignite = max_ignite.max(ignite);
-                    }
-
-                    if max_ignite > 0 {
-                        // Spread difficulty increases with height above fire
-                        let mut difficulty = 100;
-                        if sy > 1 {
-                            difficulty += (sy - 1) * 100;
-                        }
+                    let max_ignite = ignite_odds(world_state, &spread_pos);
+                    if max_ignite <= 0 {
+                        continue;
+                    }
 
-                        let spread_chance = (max_ignite + 40 + world_state.difficulty * 7) / (new_age + 30);
-                        if spread_chance > 0 && rng.gen_range(0..difficulty) <= spread_chance
-                            && (!world_state.raining || rng.gen::<f64>() > 0.2 + new_age as f64 * 0.03) {
-                            let fire_age = (new_age + rng.gen_range(0..5) / 4).min(15);
-                            updates.push((spread_pos, pickaxe_data::fire_state_with_age(fire_age)));
-                        }
+                    // Spread difficulty increases with height above fire
+                    let mut difficulty = 100;
+                    if sy > 1 {
+                        difficulty += (sy - 1) * 100;
+                    }
+
+                    let spread_chance = (max_ignite + 40 + world_state.difficulty * 7) / (age + 30);
+                    if spread_chance > 0 && rng.gen_range(0..difficulty) <= spread_chance
+                        && !(world_state.raining && near_rain(world_state, &spread_pos))
+                    {
+                        let fire_age = (age + rng.gen_range(0..5) / 4).min(15);
+                        updates.push((spread_pos, pickaxe_data::fire_state_with_age(fire_age)));
                     }
                 }
             }
@@ -9336,7 +13725,7 @@ fn tick_fire(
     // Phase 3: Apply all updates and broadcast
     for (pos, new_state) in updates {
         world_state.set_block(&pos, new_state);
-        broadcast_to_all(world, &InternalPacket::BlockUpdate {
+        broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
             position: pos,
             block_id: new_state,
         });
@@ -9362,13 +13751,20 @@ fn tick_fire(
 
 /// Tick fluid blocks: water and lava flow, source creation, water-lava interactions.
 /// Water ticks every 5 game ticks, lava every 30 game ticks.
-fn tick_fluids(world: &World, world_state: &mut WorldState, do_water: bool, do_lava: bool) {
+fn tick_fluids(
+    world: &mut World,
+    world_state: &mut WorldState,
+    do_water: bool,
+    do_lava: bool,
+    next_eid: &Arc<AtomicI32>,
+    scripting: &ScriptRuntime,
+) {
     // Phase 1: Collect all fluid block positions
     let mut fluid_blocks: Vec<(BlockPos, i32, bool)> = Vec::new(); // (pos, state, is_water)
     {
-        let chunk_positions: Vec<pickaxe_types::ChunkPos> = world_state.chunks.keys().cloned().collect();
+        let chunk_positions: Vec<pickaxe_types::ChunkPos> = world_state.level.chunks.keys().cloned().collect();
         for chunk_pos in chunk_positions {
-            let chunk = match world_state.chunks.get(&chunk_pos) {
+            let chunk = match world_state.level.chunks.get(&chunk_pos) {
                 Some(c) => c,
                 None => continue,
             };
@@ -9387,6 +13783,11 @@ fn tick_fluids(world: &World, world_state: &mut WorldState, do_water: bool, do_l
                                 let bx = chunk_pos.x * 16 + local_x as i32;
                                 let bz = chunk_pos.z * 16 + local_z as i32;
                                 fluid_blocks.push((BlockPos::new(bx, by, bz), block, false));
+                            } else if do_water && block != 0 && pickaxe_data::is_waterlogged(block) {
+                                // Waterlogged blocks spread like a water source but never change themselves
+                                let bx = chunk_pos.x * 16 + local_x as i32;
+                                let bz = chunk_pos.z * 16 + local_z as i32;
+                                fluid_blocks.push((BlockPos::new(bx, by, bz), block, true));
                             }
                         }
                     }
@@ -9574,8 +13975,7 @@ fn tick_fluids(world: &World, world_state: &mut WorldState, do_water: bool, do_l
             continue;
         }
 
-        // Destroy blocks that water can break (flowers, torches, etc. just disappear)
-        // In vanilla, some blocks drop items but we skip drops for simplicity
+        // Blocks that water can break (flowers, torches, etc.) are washed away in Phase 5
 
         // Only place if stronger than existing flow
         if pickaxe_data::is_fluid(existing) && !pickaxe_data::is_fluid_source(existing) {
@@ -9605,10 +14005,22 @@ fn tick_fluids(world: &World, world_state: &mut WorldState, do_water: bool, do_l
             continue;
         }
         world_state.set_block(&pos, new_state);
-        broadcast_to_all(world, &InternalPacket::BlockUpdate {
+        broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
             position: pos,
             block_id: new_state,
         });
+
+        // Water washes away plants, torches, rails etc. and drops them; lava just burns them
+        let old_name = pickaxe_data::block_state_to_name(old).unwrap_or("");
+        if pickaxe_data::is_water(new_state) && pickaxe_data::is_fluid_destructible(old_name) {
+            for &drop_id in pickaxe_data::block_state_to_drops(old) {
+                spawn_item_entity(
+                    world, world_state, next_eid,
+                    pos.x as f64 + 0.5, pos.y as f64 + 0.25, pos.z as f64 + 0.5,
+                    ItemStack::new(drop_id, 1), 10, scripting,
+                );
+            }
+        }
     }
 }
 
@@ -9632,23 +14044,6 @@ fn compute_new_fluid_state(
         return current;
     }
 
-    // Check above: if fluid above, this should be falling (level 8)
-    let above = BlockPos::new(pos.x, pos.y + 1, pos.z);
-    let above_block = world_state.get_block_if_loaded(&above).unwrap_or(0);
-    let same_fluid_above = if is_water {
-        pickaxe_data::is_water(above_block)
-    } else {
-        pickaxe_data::is_lava(above_block)
-    };
-
-    if same_fluid_above {
-        return if is_water {
-            pickaxe_data::water_state_with_level(8)
-        } else {
-            pickaxe_data::lava_state_with_level(8)
-        };
-    }
-
     // Check horizontal neighbors for sources / higher-level fluid
     let mut max_neighbor_amount = 0i32;
     let mut source_count = 0i32;
@@ -9657,6 +14052,12 @@ fn compute_new_fluid_state(
     for (dx, dz) in &directions {
         let adj = BlockPos::new(pos.x + dx, pos.y, pos.z + dz);
         let adj_block = world_state.get_block_if_loaded(&adj).unwrap_or(0);
+        if is_water && pickaxe_data::is_waterlogged(adj_block) {
+            // Waterlogged neighbors (slabs, kelp, ...) count as full water sources
+            source_count += 1;
+            max_neighbor_amount = 8;
+            continue;
+        }
         let is_same = if is_water { pickaxe_data::is_water(adj_block) } else { pickaxe_data::is_lava(adj_block) };
         if is_same {
             let adj_amount = pickaxe_data::fluid_amount(adj_block);
@@ -9667,16 +14068,37 @@ fn compute_new_fluid_state(
         }
     }
 
-    // Infinite source creation (water only): 2+ source neighbors + solid below
+    // Infinite source creation (water only): 2+ source neighbors and a solid block
+    // or water source below. Checked before falling so a waterfall between two
+    // sources still fills in, matching vanilla's order.
     if is_water && source_count >= 2 {
         let below = BlockPos::new(pos.x, pos.y - 1, pos.z);
         let below_block = world_state.get_block_if_loaded(&below).unwrap_or(0);
         let below_name = pickaxe_data::block_state_to_name(below_block).unwrap_or("");
-        if pickaxe_data::is_solid_for_fluid(below_name) || pickaxe_data::is_fluid_source(below_block) {
+        if pickaxe_data::is_fluid_source(below_block) || pickaxe_data::is_waterlogged(below_block)
+            || (!pickaxe_data::is_fluid(below_block) && below_block != 0 && pickaxe_data::is_solid_for_fluid(below_name))
+        {
             return pickaxe_data::WATER_SOURCE;
         }
     }
 
+    // Check above: if fluid above, this should be falling (level 8)
+    let above = BlockPos::new(pos.x, pos.y + 1, pos.z);
+    let above_block = world_state.get_block_if_loaded(&above).unwrap_or(0);
+    let same_fluid_above = if is_water {
+        pickaxe_data::is_water(above_block) || pickaxe_data::is_waterlogged(above_block)
+    } else {
+        pickaxe_data::is_lava(above_block)
+    };
+
+    if same_fluid_above {
+        return if is_water {
+            pickaxe_data::water_state_with_level(8)
+        } else {
+            pickaxe_data::lava_state_with_level(8)
+        };
+    }
+
     // Compute new level from neighbors
     let new_amount = max_neighbor_amount - drop_off;
     if new_amount <= 0 {
@@ -10020,14 +14442,18 @@ fn tick_arrow_physics(world: &mut World, world_state: &mut WorldState, next_eid:
         is_critical: bool,
     }
     let mut entity_hits: Vec<ArrowHit> = Vec::new();
+    // Arrows that stuck in a block this tick: (arrow eid, owner, block position)
+    let mut block_hits: Vec<(i32, Option<hecs::Entity>, BlockPos)> = Vec::new();
 
     // Collect all player positions for hit detection
     let mut player_positions: Vec<(hecs::Entity, i32, Vec3d, Option<hecs::Entity>)> = Vec::new();
-    for (e, (eid, pos, _profile)) in world
-        .query::<(&EntityId, &Position, &Profile)>()
+    for (e, (eid, pos, _profile, dimension)) in world
+        .query::<(&EntityId, &Position, &Profile, Option<&Dimension>)>()
         .iter()
     {
-        player_positions.push((e, eid.0, pos.0, None));
+        if in_level(world_state, dimension) {
+            player_positions.push((e, eid.0, pos.0, None));
+        }
     }
 
     // Collect all mob positions for hit detection
@@ -10086,6 +14512,12 @@ fn tick_arrow_physics(world: &mut World, world_state: &mut WorldState, next_eid:
                 } else {
                     arrow.damage
                 };
+                // Arrows shot by mobs scale with difficulty like melee attacks
+                let damage = if arrow.from_player {
+                    damage
+                } else {
+                    scale_damage_for_difficulty(world_state.difficulty, damage)
+                };
                 entity_hits.push(ArrowHit {
                     arrow_entity: e, arrow_eid: eid.0,
                     target_entity: target_e, target_eid,
@@ -10137,6 +14569,7 @@ fn tick_arrow_physics(world: &mut World, world_state: &mut WorldState, next_eid:
             arrow.in_ground = true;
             vel.0 = Vec3d::new(0.0, 0.0, 0.0);
             og.0 = true;
+            block_hits.push((eid.0, arrow.owner, block_pos));
 
             // Play arrow hit sound
             play_sound_at_entity(world, pos.0.x, pos.0.y, pos.0.z, "entity.arrow.hit_block", SOUND_NEUTRAL, 1.0, 1.0);
@@ -10163,24 +14596,72 @@ fn tick_arrow_physics(world: &mut World, world_state: &mut WorldState, next_eid:
         let _ = old_pos; // suppress unused warning
     }
 
+    // Arrows stuck in blocks can't be stopped any more; the event is informational
+    for (arrow_eid, owner, block_pos) in block_hits {
+        let shooter = owner.and_then(|o| world.get::<&Profile>(o).ok().map(|p| p.0.name.clone())).unwrap_or_default();
+        let block = world_state.get_block(&block_pos);
+        scripting.fire_event_in_context(
+            "projectile_hit",
+            &[
+                ("projectile", "arrow"),
+                ("entity_id", &arrow_eid.to_string()),
+                ("shooter", &shooter),
+                ("block", pickaxe_data::block_state_to_name(block).unwrap_or("unknown")),
+                ("x", &block_pos.x.to_string()),
+                ("y", &block_pos.y.to_string()),
+                ("z", &block_pos.z.to_string()),
+            ],
+            world as *mut _ as *mut (),
+            world_state as *mut _ as *mut (),
+        );
+    }
+
     // Process entity hits
     for hit in &entity_hits {
+        // projectile_hit handlers may change the damage, or cancel so the arrow flies on
+        let shooter = hit.owner.and_then(|o| world.get::<&Profile>(o).ok().map(|p| p.0.name.clone())).unwrap_or_default();
+        let target_type = selector::entity_type_name(world, hit.target_entity).unwrap_or("unknown");
+        let mut fields = [
+            ("projectile", "arrow".to_string()),
+            ("entity_id", hit.arrow_eid.to_string()),
+            ("shooter", shooter),
+            ("target", hit.target_eid.to_string()),
+            ("target_type", target_type.to_string()),
+            ("x", format!("{:.1}", hit.hit_pos.x)),
+            ("y", format!("{:.1}", hit.hit_pos.y)),
+            ("z", format!("{:.1}", hit.hit_pos.z)),
+            ("damage", format!("{:.1}", hit.damage)),
+        ];
+        let cancelled = scripting.fire_event_mut_in_context(
+            "projectile_hit",
+            &mut fields,
+            world as *mut _ as *mut (),
+            world_state as *mut _ as *mut (),
+        );
+        if cancelled {
+            continue;
+        }
+        let damage = fields[8].1.parse::<f32>().unwrap_or(hit.damage);
+
         if hit.is_mob_target {
             // Arrow hit a mob — use attack_mob
             if let Some(owner) = hit.owner {
                 let owner_eid = world.get::<&EntityId>(owner).map(|e| e.0).unwrap_or(0);
                 attack_mob(world, world_state, owner, owner_eid, hit.target_entity, hit.target_eid,
-                    hit.damage, hit.is_critical, scripting, next_eid);
+                    damage, hit.is_critical, scripting, next_eid);
             } else {
                 // No owner (shouldn't happen but handle gracefully) — direct mob damage
                 if let Ok(mut mob) = world.get::<&mut MobEntity>(hit.target_entity) {
-                    mob.health -= hit.damage;
+                    mob.health -= damage;
                     mob.no_damage_ticks = 10;
                 }
             }
         } else {
             // Arrow hit a player — use apply_damage with arrow position for directional shield
-            apply_damage_from(world, world_state, hit.target_entity, hit.target_eid, hit.damage, "arrow", Some(hit.hit_pos), scripting);
+            let blocked = hit.owner.is_some_and(|owner| friendly_fire_blocked(world, world_state, owner, hit.target_entity));
+            if !blocked {
+                apply_damage_from(world, world_state, hit.target_entity, hit.target_eid, damage, "arrow", Some(hit.hit_pos), scripting);
+            }
         }
 
         // Play hit sound
@@ -10472,7 +14953,7 @@ fn do_explosion(
             chain_tnt.push((bx as f64 + 0.5, by as f64, bz as f64 + 0.5));
         } else {
             // Spawn item drops (1/radius chance per block in explosions, MC uses 1/radius)
-            if rng.gen::<f64>() < (1.0 / radius as f64) {
+            if world_state.game_rules.get_bool("doTileDrops") && rng.gen::<f64>() < (1.0 / radius as f64) {
                 let drops = pickaxe_data::block_state_to_drops(block);
                 for &drop_id in drops {
                     let drop_item = ItemStack::new(drop_id, 1);
@@ -10496,7 +14977,7 @@ fn do_explosion(
 
         // Set to air
         world_state.set_block(&pos, 0);
-        broadcast_to_all(world, &InternalPacket::BlockUpdate {
+        broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
             position: pos,
             block_id: 0,
         });
@@ -10521,23 +15002,33 @@ fn do_explosion(
         knockback_z: f32,
     }
     let mut player_infos: Vec<PlayerExplosionInfo> = Vec::new();
+    let mut mob_damage: Vec<(hecs::Entity, i32, f32, Vec3d)> = Vec::new();
 
-    for (pe, (peid, ppos, _profile)) in world.query::<(&EntityId, &Position, &Profile)>().iter() {
-        let dx = ppos.0.x - center_x;
-        let dy = ppos.0.y - center_y;
-        let dz = ppos.0.z - center_z;
+    let explosion_center = Vec3d::new(center_x, center_y, center_z);
+    for (e, epos) in entities_in_radius(world, world_state, explosion_center, damage_radius) {
+        let Ok(eid) = world.get::<&EntityId>(e).map(|eid| eid.0) else {
+            continue;
+        };
+        let dx = epos.x - center_x;
+        let dy = epos.y - center_y;
+        let dz = epos.z - center_z;
         let dist = (dx * dx + dy * dy + dz * dz).sqrt();
-        if dist < damage_radius && dist > 0.0 {
-            let d0 = dist / damage_radius;
-            let d1 = 1.0 - d0; // simplified LOS (assume full exposure)
-            let damage = ((d1 * d1 + d1) / 2.0 * 7.0 * damage_radius + 1.0) as f32;
+        if dist >= damage_radius || dist <= 0.0 {
+            continue;
+        }
+        let d0 = dist / damage_radius;
+        let d1 = 1.0 - d0; // simplified LOS (assume full exposure)
+        let damage = ((d1 * d1 + d1) / 2.0 * 7.0 * damage_radius + 1.0) as f32;
+        if world.get::<&MobEntity>(e).is_ok() {
+            mob_damage.push((e, eid, damage, epos));
+        } else if world.get::<&Profile>(e).is_ok() {
             let knockback = d1;
             let nx = dx / dist;
             let ny = dy / dist;
             let nz = dz / dist;
             player_infos.push(PlayerExplosionInfo {
-                entity: pe,
-                eid: peid.0,
+                entity: e,
+                eid,
                 damage,
                 knockback_x: (nx * knockback) as f32,
                 knockback_y: (ny * knockback) as f32,
@@ -10547,26 +15038,11 @@ fn do_explosion(
     }
 
     // Apply damage to players (explosions use center position for shield directionality)
-    let explosion_center = Vec3d::new(center_x, center_y, center_z);
     for info in &player_infos {
         apply_damage_from(world, world_state, info.entity, info.eid, info.damage, "explosion", Some(explosion_center), scripting);
     }
 
     // Damage mobs
-    let mut mob_damage: Vec<(hecs::Entity, i32, f32, Vec3d)> = Vec::new();
-    for (me, (meid, mpos, _mob)) in world.query::<(&EntityId, &Position, &MobEntity)>().iter() {
-        let dx = mpos.0.x - center_x;
-        let dy = mpos.0.y - center_y;
-        let dz = mpos.0.z - center_z;
-        let dist = (dx * dx + dy * dy + dz * dz).sqrt();
-        if dist < damage_radius && dist > 0.0 {
-            let d0 = dist / damage_radius;
-            let d1 = 1.0 - d0;
-            let damage = ((d1 * d1 + d1) / 2.0 * 7.0 * damage_radius + 1.0) as f32;
-            mob_damage.push((me, meid.0, damage, mpos.0));
-        }
-    }
-
     for (me, meid, damage, mpos) in &mob_damage {
         if let Ok(mut mob) = world.get::<&mut MobEntity>(*me) {
             if mob.no_damage_ticks <= 0 {
@@ -10610,71 +15086,87 @@ fn do_explosion(
 }
 
 /// Check for item pickup by nearby players. Runs every 4 ticks.
+/// Players take as much of a stack as fits in their inventory; any remainder
+/// stays on the ground with its count updated.
 fn tick_item_pickup(world: &mut World, world_state: &mut WorldState, scripting: &ScriptRuntime) {
-    // Collect all pickable items
-    let mut items: Vec<(hecs::Entity, i32, Vec3d, i32, i8)> = Vec::new();
-    for (e, (eid, pos, item_ent)) in world
-        .query::<(&EntityId, &Position, &ItemEntity)>()
+    // Collect all players
+    let mut players: Vec<(hecs::Entity, i32, Vec3d, String)> = Vec::new();
+    for (e, (eid, pos, profile, dimension)) in world
+        .query::<(&EntityId, &Position, &Profile, Option<&Dimension>)>()
         .iter()
     {
-        if item_ent.pickup_delay == 0 {
-            items.push((e, eid.0, pos.0, item_ent.item.item_id, item_ent.item.count));
+        if in_level(world_state, dimension) {
+            players.push((e, eid.0, pos.0, profile.0.name.clone()));
         }
     }
 
-    // Collect all players
-    let mut players: Vec<(hecs::Entity, i32, Vec3d, String)> = Vec::new();
-    for (e, (eid, pos, profile)) in world
-        .query::<(&EntityId, &Position, &Profile)>()
-        .iter()
-    {
-        players.push((e, eid.0, pos.0, profile.0.name.clone()));
-    }
-
     let mut picked_up: Vec<(hecs::Entity, i32, i32, i8)> = Vec::new(); // (entity, item_eid, collector_eid, count)
+    // Stacks players took from this round: entity -> (item_eid, original count, what's left)
+    let mut taken_from: HashMap<hecs::Entity, (i32, i8, ItemStack)> = HashMap::new();
+
+    for (player_entity, player_eid, player_pos, name) in players {
+        // Pickable items near this player, as left by earlier players
+        let nearby: Vec<(hecs::Entity, i32, ItemStack)> = entities_in_radius(world, world_state, player_pos, 1.5)
+            .into_iter()
+            .filter_map(|(e, _)| {
+                if let Some((eid, _, stack)) = taken_from.get(&e) {
+                    return (stack.count > 0).then(|| (e, *eid, stack.clone()));
+                }
+                let eid = world.get::<&EntityId>(e).ok()?.0;
+                let item_ent = world.get::<&ItemEntity>(e).ok()?;
+                (item_ent.pickup_delay == 0).then(|| (e, eid, item_ent.item.clone()))
+            })
+            .collect();
 
-    for &(item_entity, item_eid, item_pos, item_id, item_count) in &items {
-        for &(player_entity, player_eid, player_pos, ref name) in &players {
-            let dx = item_pos.x - player_pos.x;
-            let dy = item_pos.y - player_pos.y;
-            let dz = item_pos.z - player_pos.z;
-            let dist_sq = dx * dx + dy * dy + dz * dz;
-
-            if dist_sq < 1.5 * 1.5 {
-                let item_name = pickaxe_data::item_id_to_name(item_id)
-                    .unwrap_or("unknown")
-                    .to_string();
+        for (item_entity, item_eid, mut stack) in nearby {
+            let item_name = pickaxe_data::item_id_to_name(stack.item_id)
+                .unwrap_or("unknown")
+                .to_string();
 
-                // Fire cancellable event
-                let cancelled = scripting.fire_event_in_context(
-                    "item_pickup",
-                    &[
-                        ("name", name),
-                        ("item_id", &item_id.to_string()),
-                        ("item_name", &item_name),
-                        ("item_count", &item_count.to_string()),
-                        ("entity_id", &item_eid.to_string()),
-                    ],
-                    world as *mut _ as *mut (),
-                    world_state as *mut _ as *mut (),
-                );
+            // Fire cancellable event
+            let cancelled = scripting.fire_event_in_context(
+                "item_pickup",
+                &[
+                    ("name", &name),
+                    ("item_id", &stack.item_id.to_string()),
+                    ("item_name", &item_name),
+                    ("item_count", &stack.count.to_string()),
+                    ("entity_id", &item_eid.to_string()),
+                ],
+                world as *mut _ as *mut (),
+                world_state as *mut _ as *mut (),
+            );
 
-                if cancelled {
-                    continue;
-                }
+            if cancelled {
+                continue;
+            }
 
-                // Try to give item to player
-                if give_item_to_player(world, player_entity, item_id, item_count) {
-                    picked_up.push((item_entity, item_eid, player_eid, item_count));
-                    break; // Item is picked up, move to next item
-                }
+            // Take as much as fits into the player's inventory
+            let original_count = stack.count;
+            let taken = insert_into_inventory(world, player_entity, &stack);
+            if taken > 0 {
+                picked_up.push((item_entity, item_eid, player_eid, taken));
+                stack.count -= taken;
+                taken_from
+                    .entry(item_entity)
+                    .and_modify(|(_, _, left)| left.count = stack.count)
+                    .or_insert((item_eid, original_count, stack));
             }
         }
     }
 
-    // Despawn picked up items
+    let mut remainders: Vec<(hecs::Entity, i32, ItemStack)> = Vec::new(); // (entity, item_eid, what's left)
+    let mut collected: Vec<(hecs::Entity, i32)> = Vec::new(); // fully picked up (entity, item_eid)
+    for (entity, (eid, original_count, stack)) in taken_from {
+        if stack.count <= 0 {
+            collected.push((entity, eid));
+        } else if stack.count < original_count {
+            remainders.push((entity, eid, stack));
+        }
+    }
+
+    // Pickup animation and sound for everything collected
     for &(entity, eid, collector_eid, count) in &picked_up {
-        // Send pickup animation
         broadcast_to_all(world, &InternalPacket::TakeItemEntity {
             collected_entity_id: eid,
             collector_entity_id: collector_eid,
@@ -10685,7 +15177,22 @@ fn tick_item_pickup(world: &mut World, world_state: &mut WorldState, scripting:
         if let Ok(pos) = world.get::<&Position>(entity) {
             play_sound_at_entity(world, pos.0.x, pos.0.y, pos.0.z, "entity.item.pickup", SOUND_PLAYERS, 0.2, (rand::random::<f32>() - 0.5) * 1.4 + 1.0);
         }
+    }
+
+    // Partially collected stacks stay in the world with the smaller count
+    for (entity, eid, stack) in remainders {
+        let metadata = build_item_metadata(&stack);
+        if let Ok(mut item_ent) = world.get::<&mut ItemEntity>(entity) {
+            item_ent.item = stack;
+        }
+        broadcast_to_all(world, &InternalPacket::SetEntityMetadata {
+            entity_id: eid,
+            metadata,
+        });
+    }
 
+    // Despawn fully collected items
+    for (entity, eid) in collected {
         broadcast_to_all(world, &InternalPacket::RemoveEntities {
             entity_ids: vec![eid],
         });
@@ -10708,6 +15215,35 @@ fn tick_item_pickup(world: &mut World, world_state: &mut WorldState, scripting:
     }
 }
 
+/// Insert a full item stack (keeping damage, enchantments and dye) into a
+/// player's inventory, syncing every changed slot. Returns how many items fit.
+pub(crate) fn insert_into_inventory(world: &mut World, entity: hecs::Entity, stack: &ItemStack) -> i8 {
+    let max_stack = pickaxe_data::item_id_to_stack_size(stack.item_id).unwrap_or(64);
+    let (inserted, updates, state_id) = {
+        let mut inv = match world.get::<&mut Inventory>(entity) {
+            Ok(inv) => inv,
+            Err(_) => return 0,
+        };
+        let (inserted, changed) = inv.insert_stack(stack, max_stack);
+        let updates: Vec<(usize, Option<ItemStack>)> = changed.into_iter()
+            .map(|i| (i, inv.slots[i].clone()))
+            .collect();
+        (inserted, updates, inv.state_id)
+    };
+
+    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+        for (slot, item) in updates {
+            let _ = sender.0.send(InternalPacket::SetContainerSlot {
+                window_id: 0,
+                state_id,
+                slot: slot as i16,
+                item,
+            });
+        }
+    }
+    inserted
+}
+
 /// Give an item to a player entity, returning true on success.
 fn give_item_to_player(world: &mut World, entity: hecs::Entity, item_id: i32, count: i8) -> bool {
     let max_stack = pickaxe_data::item_id_to_stack_size(item_id).unwrap_or(64);
@@ -10753,7 +15289,7 @@ fn give_item_to_player(world: &mut World, entity: hecs::Entity, item_id: i32, co
 
 /// Update redstone components in response to a block change at `origin`.
 /// Propagates power changes to adjacent redstone wire, torches, repeaters, and lamps.
-fn update_redstone_neighbors(
+pub(crate) fn update_redstone_neighbors(
     world: &World,
     world_state: &mut WorldState,
     origin: &BlockPos,
@@ -10779,6 +15315,7 @@ fn update_redstone_neighbors(
                 || pickaxe_data::is_any_piston(s)
                 || pickaxe_data::is_lever_powered(s)
                 || pickaxe_data::is_button_powered(s)
+                || pickaxe_data::is_lectern_powered(s)
                 || pickaxe_data::block_state_to_name(s) == Some("redstone_block")
             {
                 has_redstone = true;
@@ -10926,7 +15463,7 @@ fn update_redstone_neighbors(
     // Apply all wire updates
     for (pos, _old, new_state) in &wire_updates {
         world_state.set_block(pos, *new_state);
-        broadcast_to_all(world, &InternalPacket::BlockUpdate {
+        broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
             position: *pos,
             block_id: *new_state,
         });
@@ -10935,7 +15472,7 @@ fn update_redstone_neighbors(
     // Apply all other block updates
     for (pos, _old, new_state) in &block_updates {
         world_state.set_block(pos, *new_state);
-        broadcast_to_all(world, &InternalPacket::BlockUpdate {
+        broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
             position: *pos,
             block_id: *new_state,
         });
@@ -11052,7 +15589,7 @@ fn update_redstone_cascade(
 
     for (pos, new_state) in &changes {
         world_state.set_block(pos, *new_state);
-        broadcast_to_all(world, &InternalPacket::BlockUpdate {
+        broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
             position: *pos,
             block_id: *new_state,
         });
@@ -11102,8 +15639,10 @@ fn calculate_wire_power(world_state: &WorldState, pos: &BlockPos) -> i32 {
             continue;
         }
 
-        // Lever/button: powers adjacent wire
-        if pickaxe_data::is_lever_powered(nstate) || pickaxe_data::is_button_powered(nstate) {
+        // Lever/button/lectern: powers adjacent wire
+        if pickaxe_data::is_lever_powered(nstate) || pickaxe_data::is_button_powered(nstate)
+            || pickaxe_data::is_lectern_powered(nstate)
+        {
             max_power = 15;
             continue;
         }
@@ -11251,7 +15790,7 @@ fn is_torch_receiving_power(world_state: &WorldState, pos: &BlockPos, state: i32
 
 /// Check if a block at `pos` is receiving any redstone power.
 /// Used for lamps, torches checking their attachment block, etc.
-fn block_receives_power(world_state: &WorldState, pos: &BlockPos) -> bool {
+pub(crate) fn block_receives_power(world_state: &WorldState, pos: &BlockPos) -> bool {
     let offsets: [(i32, i32, i32); 6] = [
         (1, 0, 0), (-1, 0, 0),
         (0, 1, 0), (0, -1, 0),
@@ -11266,7 +15805,9 @@ fn block_receives_power(world_state: &WorldState, pos: &BlockPos) -> bool {
         };
 
         // Direct power sources
-        if pickaxe_data::is_lever_powered(nstate) || pickaxe_data::is_button_powered(nstate) {
+        if pickaxe_data::is_lever_powered(nstate) || pickaxe_data::is_button_powered(nstate)
+            || pickaxe_data::is_lectern_powered(nstate)
+        {
             return true;
         }
 
@@ -11394,7 +15935,7 @@ fn try_extend_piston(
     // Destroy breakable blocks first
     for dpos in &to_destroy {
         world_state.set_block(dpos, 0);
-        broadcast_to_all(world, &InternalPacket::BlockUpdate {
+        broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
             position: *dpos,
             block_id: 0,
         });
@@ -11407,7 +15948,7 @@ fn try_extend_piston(
         let dest = BlockPos::new(bpos.x + dx, bpos.y + dy, bpos.z + dz);
         world_state.set_block(&dest, block);
         world_state.set_block(bpos, 0);
-        broadcast_to_all(world, &InternalPacket::BlockUpdate {
+        broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
             position: dest,
             block_id: block,
         });
@@ -11420,7 +15961,7 @@ fn try_extend_piston(
         if current != 0 {
             // Position was overwritten by another push — already correct
         } else {
-            broadcast_to_all(world, &InternalPacket::BlockUpdate {
+            broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
                 position: *bpos,
                 block_id: 0,
             });
@@ -11430,7 +15971,7 @@ fn try_extend_piston(
     // Place piston head at the head position
     let head_state = pickaxe_data::piston_head_state(facing, false, is_sticky);
     world_state.set_block(&head_pos, head_state);
-    broadcast_to_all(world, &InternalPacket::BlockUpdate {
+    broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
         position: head_pos,
         block_id: head_state,
     });
@@ -11438,7 +15979,7 @@ fn try_extend_piston(
     // Set piston base to extended
     let extended_state = pickaxe_data::piston_state(facing, true, is_sticky);
     world_state.set_block(pos, extended_state);
-    broadcast_to_all(world, &InternalPacket::BlockUpdate {
+    broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
         position: *pos,
         block_id: extended_state,
     });
@@ -11470,7 +16011,7 @@ fn try_retract_piston(
     let head_block = world_state.get_block(&head_pos);
     if pickaxe_data::is_piston_head(head_block) {
         world_state.set_block(&head_pos, 0);
-        broadcast_to_all(world, &InternalPacket::BlockUpdate {
+        broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
             position: head_pos,
             block_id: 0,
         });
@@ -11479,7 +16020,7 @@ fn try_retract_piston(
     // Set piston base to retracted
     let retracted_state = pickaxe_data::piston_state(facing, false, is_sticky);
     world_state.set_block(pos, retracted_state);
-    broadcast_to_all(world, &InternalPacket::BlockUpdate {
+    broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
         position: *pos,
         block_id: retracted_state,
     });
@@ -11492,11 +16033,11 @@ fn try_retract_piston(
             // Pull block to head position
             world_state.set_block(&head_pos, pull_block);
             world_state.set_block(&pull_pos, 0);
-            broadcast_to_all(world, &InternalPacket::BlockUpdate {
+            broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
                 position: head_pos,
                 block_id: pull_block,
             });
-            broadcast_to_all(world, &InternalPacket::BlockUpdate {
+            broadcast_to_level(world, world_state, &InternalPacket::BlockUpdate {
                 position: pull_pos,
                 block_id: 0,
             });
@@ -11563,7 +16104,7 @@ fn resolve_push_structure(
 fn tick_furnaces(world: &World, world_state: &mut WorldState) {
     let mut updates: Vec<(BlockPos, i16, i16, i16, i16)> = Vec::new();
 
-    for (pos, block_entity) in world_state.block_entities.iter_mut() {
+    for (pos, block_entity) in world_state.level.block_entities.iter_mut() {
         let BlockEntity::Furnace {
             ref mut input, ref mut fuel, ref mut output,
             ref mut burn_time, ref mut burn_duration,
@@ -11644,11 +16185,73 @@ fn tick_furnaces(world: &World, world_state: &mut WorldState) {
     }
 }
 
+/// Tick all campfires: lit campfires cook their food and drop the result,
+/// unlit ones let it cool back down.
+fn tick_campfires(
+    world: &mut World,
+    world_state: &mut WorldState,
+    next_eid: &Arc<AtomicI32>,
+    scripting: &ScriptRuntime,
+) {
+    let campfires: Vec<BlockPos> = world_state.level.block_entities.iter()
+        .filter(|(_, be)| matches!(be, BlockEntity::Campfire { items, .. } if items.iter().any(|i| i.is_some())))
+        .map(|(pos, _)| *pos)
+        .collect();
+
+    for pos in campfires {
+        let Some(state) = world_state.get_block_if_loaded(&pos) else { continue };
+        let lit = pickaxe_data::block_property(state, "lit") == Some("true");
+        let mut cooked: Vec<ItemStack> = Vec::new();
+        if let Some(BlockEntity::Campfire { items, cook_times, cook_totals }) = world_state.get_block_entity_mut(&pos) {
+            for i in 0..4 {
+                let Some(item) = &items[i] else { continue };
+                if !lit {
+                    cook_times[i] = (cook_times[i] - 2).max(0);
+                    continue;
+                }
+                cook_times[i] += 1;
+                if cook_times[i] >= cook_totals[i] {
+                    if let Some((result_id, _)) = pickaxe_data::campfire_cooking_result(item.item_id) {
+                        cooked.push(ItemStack::new(result_id, 1));
+                    }
+                    items[i] = None;
+                    cook_times[i] = 0;
+                }
+            }
+        }
+        if cooked.is_empty() {
+            continue;
+        }
+        for item in cooked {
+            spawn_item_entity(
+                world, world_state, next_eid,
+                pos.x as f64 + 0.5, pos.y as f64 + 0.5, pos.z as f64 + 0.5,
+                item, 10, scripting,
+            );
+        }
+        if let Some(packet) = world_state.get_block_entity(&pos).and_then(|be| block_entity_update_packet(&pos, be)) {
+            broadcast_to_level(world, world_state, &packet);
+        }
+        world_state.mark_chunk_dirty(pos.chunk_pos());
+    }
+}
+
+/// Count down bell swing animations.
+fn tick_bells(world_state: &mut WorldState) {
+    for block_entity in world_state.level.block_entities.values_mut() {
+        if let BlockEntity::Bell { ringing_ticks } = block_entity {
+            if *ringing_ticks > 0 {
+                *ringing_ticks -= 1;
+            }
+        }
+    }
+}
+
 /// Tick all brewing stands: consume fuel, progress brew, transform potions.
 fn tick_brewing_stands(world: &World, world_state: &mut WorldState) {
     let mut updates: Vec<(BlockPos, i16, i16)> = Vec::new();
 
-    for (pos, block_entity) in world_state.block_entities.iter_mut() {
+    for (pos, block_entity) in world_state.level.block_entities.iter_mut() {
         let BlockEntity::BrewingStand {
             ref mut bottles, ref mut ingredient, ref mut fuel,
             ref mut brew_time, ref mut fuel_uses,
@@ -11728,6 +16331,140 @@ fn tick_brewing_stands(world: &World, world_state: &mut WorldState) {
 }
 
 /// Update destroy stage animation for all players currently breaking blocks.
+/// Mirror player health into `health` criteria objectives.
+/// Report finished /save-all runs.
+fn tick_pending_saves(world: &World, world_state: &mut WorldState) {
+    world_state.pending_saves.retain_mut(|(done_rx, entity)| match done_rx.try_recv() {
+        Ok(()) => {
+            send_message(world, *entity, "Saved the game");
+            false
+        }
+        Err(tokio::sync::oneshot::error::TryRecvError::Empty) => true,
+        Err(tokio::sync::oneshot::error::TryRecvError::Closed) => false,
+    });
+}
+
+/// Start scheduled and scripted backups and report finished ones.
+fn tick_backups(world: &World, world_state: &mut WorldState, config: &ServerConfig, tick_count: u64) {
+    let interval = world_state.backup.interval_minutes * 1200;
+    let scheduled = interval > 0 && tick_count > 0 && tick_count % interval == 0;
+    if scheduled || std::mem::take(&mut world_state.backup_requested) {
+        if let Err(e) = start_backup(world, world_state, config, None) {
+            tracing::warn!("Backup skipped: {}", e);
+        }
+    }
+
+    let Some((done_rx, requester)) = &mut world_state.pending_backup else {
+        return;
+    };
+    let result = match done_rx.try_recv() {
+        Ok(result) => result,
+        Err(tokio::sync::oneshot::error::TryRecvError::Empty) => return,
+        Err(tokio::sync::oneshot::error::TryRecvError::Closed) => Err("the saver task stopped".into()),
+    };
+    let msg = match result {
+        Ok(path) => {
+            tracing::info!("Backup written to {}", path.display());
+            format!("Backup written to {}", path.display())
+        }
+        Err(e) => {
+            tracing::error!("Backup failed: {}", e);
+            format!("Backup failed: {}", e)
+        }
+    };
+    if let Some(entity) = *requester {
+        send_message(world, entity, &msg);
+    }
+    world_state.pending_backup = None;
+}
+
+/// Flush pending saves and have the saver task snapshot the world. The
+/// snapshot is taken between saver ops, so it holds everything saved up to
+/// now and nothing written after.
+pub(crate) fn start_backup(
+    world: &World,
+    world_state: &mut WorldState,
+    config: &ServerConfig,
+    requester: Option<hecs::Entity>,
+) -> Result<(), &'static str> {
+    if !world_state.backup.enabled {
+        return Err("Backups are disabled");
+    }
+    if world_state.pending_backup.is_some() {
+        return Err("A backup is already running");
+    }
+    // With saving paused the files on disk already are the snapshot
+    if world_state.saving_enabled {
+        save_world(world, world_state, config);
+        world_state.flush_dirty_chunks();
+    }
+    let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+    world_state
+        .save_tx
+        .send(SaveOp::Backup(world_state.backup.clone(), done_tx))
+        .map_err(|_| "The saver task has stopped")?;
+    world_state.pending_backup = Some((done_rx, requester));
+    tracing::info!("Starting backup");
+    Ok(())
+}
+
+/// Report finished /audit lookups, newest entry first.
+fn tick_pending_lookups(world: &World, world_state: &mut WorldState) {
+    world_state.pending_lookups.retain_mut(|(reply_rx, entity)| match reply_rx.try_recv() {
+        Ok((entries, total)) => {
+            if total == 0 {
+                send_message(world, *entity, "No audit entries found");
+                return false;
+            }
+            send_message(world, *entity, &format!("Audit entries: showing {} of {}", entries.len(), total));
+            let now = audit::now();
+            for entry in entries {
+                let position = entry
+                    .position
+                    .map(|p| format!(" at {} {} {}", p.x, p.y, p.z))
+                    .unwrap_or_default();
+                send_message(world, *entity, &format!(
+                    "{} ago {} {} {}{}",
+                    audit::format_age(now.saturating_sub(entry.time)),
+                    entry.player,
+                    entry.action.as_str(),
+                    entry.detail,
+                    position,
+                ));
+            }
+            false
+        }
+        Err(tokio::sync::oneshot::error::TryRecvError::Empty) => true,
+        Err(tokio::sync::oneshot::error::TryRecvError::Closed) => false,
+    });
+}
+
+/// Drop lapsed /tpa requests and tell both players.
+fn tick_tpa_requests(world: &World, world_state: &mut WorldState) {
+    for request in world_state.tpa.expire(world_state.tick_count) {
+        if let Some(requester) = find_online_player(world, &request.from) {
+            send_message(world, requester, &format!("Your teleport request to {} has expired", request.to));
+        }
+        if let Some(target) = find_online_player(world, &request.to) {
+            send_message(world, target, &format!("The teleport request from {} has expired", request.from));
+        }
+    }
+}
+
+fn tick_scoreboard_health(world: &World, world_state: &mut WorldState) {
+    if !world_state.scoreboard.objectives().iter().any(|o| o.criteria == Criteria::Health) {
+        return;
+    }
+    let mut packets = Vec::new();
+    for (_, (profile, health)) in world.query::<(&Profile, &Health)>().iter() {
+        let value = (health.current + health.absorption).ceil() as i32;
+        packets.extend(world_state.scoreboard.sync_criteria(&profile.0.name, Criteria::Health, value));
+    }
+    for packet in packets {
+        broadcast_to_all(world, &packet);
+    }
+}
+
 fn tick_block_breaking(world: &mut World, tick_count: u64) {
     let mut updates: Vec<(i32, BlockPos, i8)> = Vec::new();
     for (_entity, (eid, breaking)) in world.query::<(&EntityId, &BreakingBlock)>().iter() {
@@ -11759,36 +16496,68 @@ fn tick_block_breaking(world: &mut World, tick_count: u64) {
 
 // ── Command handlers ──────────────────────────────────────────────────
 
-fn cmd_gamemode(world: &mut World, entity: hecs::Entity, args: &str) {
-    if !is_op(world, entity) {
-        send_message(world, entity, "You don't have permission to use this command.");
-        return;
+/// Player Abilities flags for a game mode (vanilla `GameType.updatePlayerAbilities`).
+fn game_mode_ability_flags(mode: GameMode) -> u8 {
+    match mode {
+        // invulnerable | allow flying | instant break
+        GameMode::Creative => 0x01 | 0x04 | 0x08,
+        // invulnerable | flying | allow flying
+        GameMode::Spectator => 0x01 | 0x02 | 0x04,
+        GameMode::Survival | GameMode::Adventure => 0,
     }
+}
 
-    let mode = match args.trim() {
-        "survival" | "s" | "0" => GameMode::Survival,
-        "creative" | "c" | "1" => GameMode::Creative,
-        "adventure" | "a" | "2" => GameMode::Adventure,
-        "spectator" | "sp" | "3" => GameMode::Spectator,
-        _ => {
-            send_message(
-                world,
-                entity,
-                "Usage: /gamemode <survival|creative|adventure|spectator>",
-            );
-            return;
-        }
-    };
+/// Send the abilities (flight, invulnerability, speeds) that go with a game mode.
+/// The client keeps its previous abilities across Login/Respawn, so this must
+/// follow any game mode change.
+pub(crate) fn send_player_abilities(sender: &mpsc::UnboundedSender<InternalPacket>, mode: GameMode) {
+    let _ = sender.send(InternalPacket::PlayerAbilities {
+        flags: game_mode_ability_flags(mode),
+        flying_speed: 0.05,
+        fov_modifier: 0.1,
+    });
+}
 
+/// Switch a player's game mode and push every dependent state change:
+/// game-mode event, abilities, tab-list entry, and inventory/breaking state
+/// that the creative client handles differently.
+pub(crate) fn apply_game_mode(world: &mut World, entity: hecs::Entity, mode: GameMode) {
     if let Ok(mut gm) = world.get::<&mut PlayerGameMode>(entity) {
         gm.0 = mode;
     }
 
+    // Any survival mining in progress no longer applies (creative breaks instantly,
+    // spectators can't break at all)
+    if let Ok(breaking) = world.remove_one::<BreakingBlock>(entity) {
+        let entity_id = world.get::<&EntityId>(entity).map(|e| e.0).unwrap_or(0);
+        broadcast_to_all(
+            world,
+            &InternalPacket::SetBlockDestroyStage {
+                entity_id,
+                position: breaking.position,
+                destroy_stage: -1,
+            },
+        );
+    }
+
     if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
         let _ = sender.0.send(InternalPacket::GameEvent {
-            event: 3,
+            event: 3, // CHANGE_GAME_MODE
             value: mode.id() as f32,
         });
+        send_player_abilities(&sender.0, mode);
+
+        // The creative client edits its own inventory; resync so both sides
+        // agree once the server is authoritative again (and vice versa)
+        if let Ok(mut inv) = world.get::<&mut Inventory>(entity) {
+            inv.state_id = inv.state_id.wrapping_add(1);
+            let _ = sender.0.send(InternalPacket::SetContainerContent {
+                window_id: 0,
+                state_id: inv.state_id,
+                slots: inv.to_slot_vec(),
+                carried_item: None,
+            });
+        }
     }
 
     let uuid = world
@@ -11807,9 +16576,34 @@ fn cmd_gamemode(world: &mut World, entity: hecs::Entity, args: &str) {
                 listed: None,
                 ping: None,
                 display_name: None,
+                chat_session: None,
             }],
         },
     );
+}
+
+fn cmd_gamemode(world: &mut World, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+
+    let mode = match args.trim() {
+        "survival" | "s" | "0" => GameMode::Survival,
+        "creative" | "c" | "1" => GameMode::Creative,
+        "adventure" | "a" | "2" => GameMode::Adventure,
+        "spectator" | "sp" | "3" => GameMode::Spectator,
+        _ => {
+            send_message(
+                world,
+                entity,
+                "Usage: /gamemode <survival|creative|adventure|spectator>",
+            );
+            return;
+        }
+    };
+
+    apply_game_mode(world, entity, mode);
 
     let name = world
         .get::<&Profile>(entity)
@@ -11819,204 +16613,4147 @@ fn cmd_gamemode(world: &mut World, entity: hecs::Entity, args: &str) {
     info!("{} changed game mode to {:?}", name, mode);
 }
 
-fn cmd_tp(world: &mut World, entity: hecs::Entity, args: &str) {
+/// Find an online player's entity by name (case-insensitive).
+fn find_online_player(world: &World, name: &str) -> Option<hecs::Entity> {
+    world
+        .query::<&Profile>()
+        .iter()
+        .find(|(_, p)| p.0.name.eq_ignore_ascii_case(name))
+        .map(|(e, _)| e)
+}
+
+/// Resolve a target argument (selector or player name) from the sender's
+/// point of view. Reports parse errors and empty matches to the sender.
+fn resolve_targets(world: &World, entity: hecs::Entity, arg: &str) -> Option<Vec<hecs::Entity>> {
+    let sel = match selector::parse(arg) {
+        Ok(sel) => sel,
+        Err(e) => {
+            send_error(world, entity, &e);
+            return None;
+        }
+    };
+    let (origin, _) = command_source(world, entity);
+    let targets = selector::resolve(world, Some(entity), origin, &sel);
+    if targets.is_empty() {
+        let msg = if sel.players_only() { "No player was found" } else { "No entity was found" };
+        send_error(world, entity, msg);
+        return None;
+    }
+    Some(targets)
+}
+
+/// Player name, custom name, or entity type name for other entities.
+fn entity_display_name(world: &World, entity: hecs::Entity) -> String {
+    if let Ok(profile) = world.get::<&Profile>(entity) {
+        return profile.0.name.clone();
+    }
+    if world.get::<&ConsoleSource>(entity).is_ok() {
+        return "Server".to_string();
+    }
+    if let Ok(name) = world.get::<&CustomName>(entity) {
+        return name.text.text.clone();
+    }
+    selector::entity_type_name(world, entity).unwrap_or("entity").to_string()
+}
+
+/// Format an inventory listing, one line per occupied slot.
+fn format_inventory(slots: &[Option<ItemStack>]) -> Vec<String> {
+    slots
+        .iter()
+        .enumerate()
+        .filter_map(|(i, slot)| {
+            let stack = slot.as_ref()?;
+            let name = pickaxe_data::item_id_to_name(stack.item_id).unwrap_or("unknown");
+            Some(format!("  [{}] {} x{}", i, name, stack.count))
+        })
+        .collect()
+}
+
+/// /invsee <player> — list a player's inventory, online or offline.
+fn cmd_invsee(world: &World, world_state: &WorldState, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let target = args.trim();
+    if target.is_empty() {
+        send_message(world, entity, "Usage: /invsee <player>");
+        return;
+    }
+
+    let (name, lines) = if let Some(target_entity) = find_online_player(world, target) {
+        let name = world.get::<&Profile>(target_entity).map(|p| p.0.name.clone()).unwrap_or_default();
+        let lines = world
+            .get::<&Inventory>(target_entity)
+            .map(|inv| format_inventory(&inv.slots))
+            .unwrap_or_default();
+        (name, lines)
+    } else if let Some(offline) = world_state.player_data.load_offline(target) {
+        (format!("{} (offline)", offline.name), format_inventory(&offline.inventory()))
+    } else {
+        send_message(world, entity, &format!("No player data for {}", target));
+        return;
+    };
+
+    send_message(world, entity, &format!("Inventory of {}:", name));
+    if lines.is_empty() {
+        send_message(world, entity, "  (empty)");
+    }
+    for line in lines {
+        send_message(world, entity, &line);
+    }
+}
+
+/// /playerdata <player> [pos <x> <y> <z> | xp <level> | clear [slot]]
+/// Inspect a saved player, or edit one who is offline.
+fn cmd_playerdata(world: &World, world_state: &WorldState, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
     let parts: Vec<&str> = args.split_whitespace().collect();
+    let Some(&target) = parts.first() else {
+        send_message(world, entity, "Usage: /playerdata <player> [pos <x> <y> <z> | xp <level> | clear [slot]]");
+        return;
+    };
 
-    let (x, y, z) = match parts.len() {
-        3 => {
-            let x: f64 = match parts[0].parse() {
-                Ok(v) => v,
-                Err(_) => {
-                    send_message(world, entity, "Invalid x coordinate");
-                    return;
-                }
+    let Some(mut player) = world_state.player_data.load_offline(target) else {
+        send_message(world, entity, &format!("No player data for {}", target));
+        return;
+    };
+    // By UUID, so a player who has since changed name is still found
+    if parts.len() > 1 && world.query::<&Profile>().iter().any(|(_, p)| p.0.uuid == player.uuid) {
+        send_message(world, entity, &format!("{} is online; only offline players can be edited", player.name));
+        return;
+    }
+
+    match parts.get(1).copied() {
+        None => {
+            let pos = player.position();
+            let (level, progress, total) = player.xp();
+            send_message(world, entity, &format!("Player data for {} ({}):", player.name, player.uuid));
+            send_message(world, entity, &format!("  Position: {:.1}, {:.1}, {:.1}", pos.x, pos.y, pos.z));
+            send_message(world, entity, &format!("  XP: level {} ({:.0}%), {} total", level, progress * 100.0, total));
+            send_message(world, entity, &format!("  Health: {:.1}", player.health()));
+            send_message(world, entity, &format!("  Game mode: {:?}", player.game_mode()));
+            let homes = player.homes();
+            if !homes.is_empty() {
+                let names: Vec<&str> = homes.keys().map(String::as_str).collect();
+                send_message(world, entity, &format!("  Homes: {}", names.join(", ")));
+            }
+            return;
+        }
+        Some("pos") => {
+            let coords: Vec<f64> = parts[2..].iter().filter_map(|p| p.parse().ok()).collect();
+            if coords.len() != 3 || parts.len() != 5 {
+                send_message(world, entity, "Usage: /playerdata <player> pos <x> <y> <z>");
+                return;
+            }
+            player.set_position(Vec3d::new(coords[0], coords[1], coords[2]));
+            send_message(world, entity, &format!("Moved {} to {:.1}, {:.1}, {:.1}", player.name, coords[0], coords[1], coords[2]));
+        }
+        Some("xp") => {
+            let Some(level) = parts.get(2).and_then(|p| p.parse::<i32>().ok()) else {
+                send_message(world, entity, "Usage: /playerdata <player> xp <level>");
+                return;
             };
-            let y: f64 = match parts[1].parse() {
-                Ok(v) => v,
-                Err(_) => {
-                    send_message(world, entity, "Invalid y coordinate");
+            player.set_xp_level(level);
+            send_message(world, entity, &format!("Set {}'s XP level to {}", player.name, level.max(0)));
+        }
+        Some("clear") => {
+            if let Some(slot) = parts.get(2) {
+                let Some(slot) = slot.parse::<usize>().ok().filter(|s| *s < 46) else {
+                    send_message(world, entity, "Slot must be 0-45");
                     return;
-                }
-            };
-            let z: f64 = match parts[2].parse() {
-                Ok(v) => v,
-                Err(_) => {
-                    send_message(world, entity, "Invalid z coordinate");
+                };
+                if !player.set_slot(slot, None) {
+                    send_message(world, entity, &format!("Slot {} is not saved", slot));
                     return;
                 }
-            };
-            (x, y, z)
+                send_message(world, entity, &format!("Cleared slot {} of {}", slot, player.name));
+            } else {
+                for slot in 0..46 {
+                    player.set_slot(slot, None);
+                }
+                send_message(world, entity, &format!("Cleared {}'s inventory", player.name));
+            }
         }
-        1 => {
-            let target_name = parts[0];
-            let mut found = None;
-            for (_e, (profile, pos)) in world.query::<(&Profile, &Position)>().iter() {
-                if profile.0.name.eq_ignore_ascii_case(target_name) {
-                    found = Some(pos.0);
+        Some(other) => {
+            send_message(world, entity, &format!("Unknown action: {}", other));
+            return;
+        }
+    }
+
+    if !world_state.player_data.save_offline(&mut player, &world_state.save_tx) {
+        send_message(world, entity, &format!("{}'s data changed while editing; try again", player.name));
+        return;
+    }
+    info!("Edited offline player data for {}", player.name);
+}
+
+/// Entity counts by kind: (players, items, mobs, other).
+fn count_entities(world: &World, filter: impl Fn(&Vec3d) -> bool) -> (usize, usize, usize, usize) {
+    let mut counts = (0, 0, 0, 0);
+    for (e, pos) in world.query::<&Position>().iter() {
+        if !filter(&pos.0) {
+            continue;
+        }
+        if world.get::<&Profile>(e).is_ok() {
+            counts.0 += 1;
+        } else if world.get::<&ItemEntity>(e).is_ok() {
+            counts.1 += 1;
+        } else if world.get::<&MobEntity>(e).is_ok() {
+            counts.2 += 1;
+        } else {
+            counts.3 += 1;
+        }
+    }
+    counts
+}
+
+/// /chunkinfo [chunk_x chunk_z] — debug info for a chunk (defaults to the player's).
+fn cmd_chunkinfo(world: &World, world_state: &WorldState, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let parts: Vec<&str> = args.split_whitespace().collect();
+    let chunk_pos = match parts.as_slice() {
+        [] => match world.get::<&ChunkPosition>(entity) {
+            Ok(cp) => ChunkPos::new(cp.chunk_x, cp.chunk_z),
+            Err(_) => return,
+        },
+        [x, z] => match (x.parse(), z.parse()) {
+            (Ok(x), Ok(z)) => ChunkPos::new(x, z),
+            _ => {
+                send_message(world, entity, "Usage: /chunkinfo [chunk_x chunk_z]");
+                return;
+            }
+        },
+        _ => {
+            send_message(world, entity, "Usage: /chunkinfo [chunk_x chunk_z]");
+            return;
+        }
+    };
+
+    let in_chunk = |x: i32, z: i32| x.div_euclid(16) == chunk_pos.x && z.div_euclid(16) == chunk_pos.z;
+    let loaded = world_state.is_chunk_loaded(&chunk_pos);
+    let block_entities = world_state.level.block_entities.keys().filter(|p| in_chunk(p.x, p.z)).count();
+    let pending = world_state.level.pending_neighbor_updates.iter().filter(|(p, _)| in_chunk(p.x, p.z)).count();
+    let scheduled = world_state.level.scheduled_block_ticks.keys().filter(|p| in_chunk(p.x, p.z)).count();
+    let (players, items, mobs, other) =
+        count_entities(world, |p| in_chunk(p.x.floor() as i32, p.z.floor() as i32));
+    let mut last_save = match world_state.chunk_last_saved(&chunk_pos) {
+        Some(at) => format!("{:.1}s ago", at.elapsed().as_secs_f64()),
+        None => "not since load".into(),
+    };
+    if world_state.is_chunk_dirty(&chunk_pos) {
+        last_save.push_str(" (unsaved changes)");
+    }
+
+    send_message(world, entity, &format!("Chunk ({}, {}):", chunk_pos.x, chunk_pos.z));
+    send_message(world, entity, &format!("  Loaded: {}", if loaded { "yes" } else { "no" }));
+    send_message(world, entity, &format!("  Block entities: {}", block_entities));
+    send_message(world, entity, &format!(
+        "  Entities: {} ({} players, {} items, {} mobs, {} other)",
+        players + items + mobs + other, players, items, mobs, other
+    ));
+    send_message(world, entity, &format!("  Pending block updates: {}", pending));
+    send_message(world, entity, &format!("  Scheduled block ticks: {}", scheduled));
+    send_message(world, entity, &format!("  Last save: {}", last_save));
+}
+
+/// /worldstats — server-wide world and performance summary.
+fn cmd_worldstats(world: &World, world_state: &WorldState, entity: hecs::Entity) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let (players, items, mobs, other) = count_entities(world, |_| true);
+    let stats = &world_state.tick_stats;
+
+    send_message(world, entity, "World statistics:");
+    send_message(world, entity, &format!("  Loaded chunks: {}", world_state.loaded_chunk_count()));
+    send_message(world, entity, &format!("  Block entities: {}", world_state.level.block_entities.len()));
+    send_message(world, entity, &format!(
+        "  Entities: {} ({} players, {} items, {} mobs, {} other)",
+        players + items + mobs + other, players, items, mobs, other
+    ));
+    send_message(world, entity, &format!("  Pending block updates: {}", world_state.level.pending_neighbor_updates.len()));
+    send_message(world, entity, &format!("  Scheduled block ticks: {}", world_state.level.scheduled_block_ticks.len()));
+    send_message(world, entity, &format!(
+        "  TPS: {:.1} (avg {:.2} ms/tick, max {:.2} ms)",
+        stats.tps(), stats.average_mspt(), stats.max_mspt()
+    ));
+    send_message(world, entity, &format!("  World age: {} ticks", world_state.world_age));
+}
+
+/// /world list | /world tp <name> [targets] — the server's worlds, or move
+/// players to a world's spawn.
+fn cmd_world(world: &mut World, world_state: &mut WorldState, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    match args.split_whitespace().collect::<Vec<_>>().as_slice() {
+        [] | ["list"] => {
+            let names = world_state.level_names();
+            send_message(world, entity, &format!("Worlds ({}):", names.len()));
+            for name in names {
+                let Some(level) = world_state.get_level(&name) else {
+                    continue;
+                };
+                let players = world
+                    .query::<(&Profile, Option<&Dimension>)>()
+                    .iter()
+                    .filter(|(_, (_, dim))| dim.map_or(DEFAULT_LEVEL, |d| d.0.as_str()) == name)
+                    .count();
+                send_message(world, entity, &format!(
+                    "  {} ({}): {} players, {} chunks loaded",
+                    name, level.generator.name(), players, level.loaded_chunk_count()
+                ));
+            }
+        }
+        ["tp", name, rest @ ..] => {
+            let name = crate::datapacks::qualify(name);
+            if !world_state.has_level(&name) {
+                send_error(world, entity, &format!("Unknown world {}", name));
+                return;
+            }
+            let targets = match rest {
+                [] => vec![entity],
+                [selector] => match resolve_targets(world, entity, selector) {
+                    Some(t) => t,
+                    None => return,
+                },
+                _ => {
+                    send_error(world, entity, "Usage: /world tp <name> [targets]");
+                    return;
+                }
+            };
+            let mut moved = 0;
+            for target in targets {
+                match move_to_level(world, world_state, target, &name) {
+                    Ok(()) => moved += 1,
+                    Err(e) => send_error(world, entity, &format!("{}: {}", entity_display_name(world, target), e)),
+                }
+            }
+            if moved > 0 {
+                send_message(world, entity, &format!("Moved {} player(s) to {}", moved, name));
+            }
+        }
+        _ => send_error(world, entity, "Usage: /world list | /world tp <name> [targets]"),
+    }
+}
+
+/// /mods [timings [reset]] — loaded mods, or time spent in each mod's event
+/// handlers since startup or the last reset.
+fn cmd_mods(world: &World, entity: hecs::Entity, args: &str, scripting: &ScriptRuntime) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    match args.split_whitespace().collect::<Vec<_>>().as_slice() {
+        [] => {
+            let mods = scripting.loaded_mods();
+            send_message(world, entity, &format!("Mods ({}): {}", mods.len(), mods.join(", ")));
+        }
+        ["timings"] => {
+            let timings = scripting.timings();
+            let report = timings.report();
+            if report.is_empty() {
+                send_message(world, entity, "No event handlers have run yet");
+                return;
+            }
+            send_message(world, entity, &format!(
+                "Event handler time over the last {} ticks ({:.1}s):",
+                timings.ticks(), timings.ticks() as f64 / 20.0
+            ));
+            for m in &report {
+                send_message(world, entity, &format!("  {}: {:.3} ms/tick, {} calls", m.mod_id, m.ms_per_tick, m.calls));
+                for (event, ms_per_tick, max_ms) in m.events.iter().take(5) {
+                    send_message(world, entity, &format!("    {}: {:.3} ms/tick, max {:.2} ms", event, ms_per_tick, max_ms));
+                }
+            }
+        }
+        ["timings", "reset"] => {
+            scripting.timings().reset();
+            send_message(world, entity, "Reset mod timings");
+        }
+        _ => send_message(world, entity, "Usage: /mods [timings [reset]]"),
+    }
+}
+
+/// /debug net [player] — packets and bytes sent and received since startup,
+/// or over one player's connection. /debug save — how far the saver task is
+/// behind. /debug timings — time spent in each tick system while profiling.
+fn cmd_debug(world: &World, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 3) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    match args.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["net"] => {
+            let stats = crate::net_stats::SERVER.lock().map(|s| s.clone()).unwrap_or_default();
+            send_message(world, entity, "Network traffic since startup:");
+            send_net_stats(world, entity, &stats);
+            let mut talkers: Vec<(String, u64)> = world
+                .query::<(&Profile, &NetworkStats)>()
+                .iter()
+                .map(|(_, (profile, net))| {
+                    let bytes = net.0.lock().map(|s| s.total_out().bytes).unwrap_or(0);
+                    (profile.0.name.clone(), bytes)
+                })
+                .collect();
+            talkers.sort_by(|a, b| b.1.cmp(&a.1));
+            talkers.truncate(5);
+            if !talkers.is_empty() {
+                let talkers: Vec<String> = talkers
+                    .iter()
+                    .map(|(name, bytes)| format!("{} ({})", name, crate::net_stats::format_bytes(*bytes)))
+                    .collect();
+                send_message(world, entity, &format!("  Most sent to: {}", talkers.join(", ")));
+            }
+        }
+        ["net", target_name] => {
+            let Some(target) = find_online_player(world, target_name) else {
+                send_message(world, entity, "No player was found");
+                return;
+            };
+            let Some(stats) = world
+                .get::<&NetworkStats>(target)
+                .ok()
+                .and_then(|net| net.0.lock().ok().map(|s| s.clone()))
+            else {
+                return;
+            };
+            send_message(world, entity, &format!("Network traffic for {}:", entity_display_name(world, target)));
+            send_net_stats(world, entity, &stats);
+            send_message(world, entity, &format!("  Queued: {} packets", stats.queued));
+        }
+        ["save"] => {
+            let stats = crate::save_stats::SAVER.lock().map(|s| s.clone()).unwrap_or_default();
+            send_message(world, entity, "Saving since startup:");
+            send_message(world, entity, &format!(
+                "  Waiting: {} saver ops, {} chunks lined up", stats.queue_depth, stats.pending_chunks
+            ));
+            send_message(world, entity, &format!(
+                "  Written: {} chunks in {} batches, {:.1} ms average, {:.1} ms slowest",
+                stats.chunks,
+                stats.flushes,
+                stats.mean_flush_time().as_secs_f64() * 1000.0,
+                stats.max_flush_time.as_secs_f64() * 1000.0
+            ));
+        }
+        ["timings", "start"] => {
+            if let Ok(mut profiler) = crate::profiler::PROFILER.lock() {
+                profiler.start(Instant::now());
+            }
+            send_message(world, entity, "Started profiling tick systems; see /debug timings report");
+        }
+        ["timings", "stop"] => {
+            let Ok(mut profiler) = crate::profiler::PROFILER.lock() else {
+                return;
+            };
+            if !profiler.is_running() {
+                send_error(world, entity, "Tick profiling isn't running");
+                return;
+            }
+            profiler.stop(Instant::now());
+            send_message(world, entity, &format!("Stopped profiling after {} ticks", profiler.ticks()));
+        }
+        ["timings"] | ["timings", "report"] => {
+            let Ok(profiler) = crate::profiler::PROFILER.lock() else {
+                return;
+            };
+            if profiler.ticks() == 0 {
+                send_message(world, entity, "No ticks profiled; start with /debug timings start");
+                return;
+            }
+            let tick = profiler.tick_report();
+            send_message(world, entity, &format!(
+                "Tick systems over {} ticks ({:.1}s){}:",
+                profiler.ticks(),
+                profiler.duration(Instant::now()).as_secs_f64(),
+                if profiler.is_running() { ", still running" } else { "" }
+            ));
+            send_message(world, entity, &format!(
+                "  Whole tick: {:.2} ms average, {:.2} ms recently, {:.2} ms slowest",
+                tick.ms_per_tick, tick.rolling_ms, tick.max_ms
+            ));
+            for system in profiler.report().iter().take(10) {
+                send_message(world, entity, &format!(
+                    "  {}: {:.3} ms/tick, {:.3} ms recently, max {:.2} ms, {} calls",
+                    system.name, system.ms_per_tick, system.rolling_ms, system.max_ms, system.calls
+                ));
+            }
+        }
+        ["timings", "dump"] => {
+            let Ok(profiler) = crate::profiler::PROFILER.lock() else {
+                return;
+            };
+            match profiler.dump(std::path::Path::new(crate::profiler::DUMP_DIR), Instant::now()) {
+                Ok(path) => send_message(world, entity, &format!("Wrote tick timings to {}", path.display())),
+                Err(e) => send_error(world, entity, &format!("Couldn't write tick timings: {}", e)),
+            }
+        }
+        _ => send_message(world, entity, "Usage: /debug net [player] | /debug save | /debug timings <start|stop|report|dump>"),
+    }
+}
+
+fn send_net_stats(world: &World, entity: hecs::Entity, stats: &crate::net_stats::NetStats) {
+    use crate::net_stats::format_bytes;
+    let (received, sent) = (stats.total_in(), stats.total_out());
+    send_message(world, entity, &format!("  In: {} packets, {}", received.packets, format_bytes(received.bytes)));
+    send_message(world, entity, &format!("  Out: {} packets, {}", sent.packets, format_bytes(sent.bytes)));
+    let top = stats.top_outbound(5);
+    if !top.is_empty() {
+        send_message(world, entity, &format!("  Most sent: {}", crate::net_stats::describe(&top)));
+    }
+}
+
+fn cmd_scoreboard(world: &World, world_state: &mut WorldState, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let parts: Vec<&str> = args.split_whitespace().collect();
+    let result = match parts.as_slice() {
+        ["objectives", "list"] => {
+            let objectives = world_state.scoreboard.objectives();
+            if objectives.is_empty() {
+                Ok("There are no objectives".to_string())
+            } else {
+                let list: Vec<String> = objectives
+                    .iter()
+                    .map(|o| format!("{} ({}, \"{}\")", o.name, o.criteria.name(), o.display_name))
+                    .collect();
+                Ok(format!("There are {} objective(s): {}", list.len(), list.join(", ")))
+            }
+        }
+        ["objectives", "add", name, criteria, display @ ..] => match Criteria::parse(criteria) {
+            Some(criteria) => {
+                let display_name = display.join(" ");
+                let display_name = (!display_name.is_empty()).then_some(display_name.as_str());
+                world_state
+                    .scoreboard
+                    .add_objective(name, criteria, display_name)
+                    .map(|packet| {
+                        broadcast_to_all(world, &packet);
+                        format!("Created new objective '{}'", name)
+                    })
+            }
+            None => Err(format!("Unknown criteria '{}' (dummy, deathCount, playerKillCount, health)", criteria)),
+        },
+        ["objectives", "remove", name] => world_state.scoreboard.remove_objective(name).map(|packet| {
+            broadcast_to_all(world, &packet);
+            format!("Removed objective '{}'", name)
+        }),
+        ["objectives", "modify", name, "displayname", display @ ..] if !display.is_empty() => {
+            let display_name = display.join(" ");
+            world_state.scoreboard.set_display_name(name, &display_name).map(|packet| {
+                broadcast_to_all(world, &packet);
+                format!("Changed the display name of '{}' to \"{}\"", name, display_name)
+            })
+        }
+        ["objectives", "setdisplay", slot] | ["objectives", "setdisplay", slot, _] => {
+            let objective = parts.get(3).copied();
+            world_state.scoreboard.set_display(slot, objective).map(|packet| {
+                broadcast_to_all(world, &packet);
+                match objective {
+                    Some(name) => format!("Set display slot {} to show objective '{}'", slot, name),
+                    None => format!("Cleared objective display slot {}", slot),
+                }
+            })
+        }
+        ["players", "list"] => {
+            let holders = world_state.scoreboard.holders();
+            if holders.is_empty() {
+                Ok("There are no tracked entities".to_string())
+            } else {
+                Ok(format!("There are {} tracked entities: {}", holders.len(), holders.join(", ")))
+            }
+        }
+        ["players", "list", target] => match resolve_score_holders(world, entity, target) {
+            Some(holders) => {
+                let lines: Vec<String> = holders
+                    .iter()
+                    .map(|holder| {
+                        let scores = world_state.scoreboard.scores_of(holder);
+                        if scores.is_empty() {
+                            format!("{} has no scores", holder)
+                        } else {
+                            let list: Vec<String> = scores.iter().map(|(o, v)| format!("{}: {}", o, v)).collect();
+                            format!("{} has {} score(s): {}", holder, scores.len(), list.join(", "))
+                        }
+                    })
+                    .collect();
+                Ok(lines.join("\n"))
+            }
+            None => return,
+        },
+        ["players", "get", target, objective] => match resolve_score_holders(world, entity, target) {
+            Some(holders) if holders.len() == 1 => match world_state.scoreboard.get_score(&holders[0], objective) {
+                Some(value) => Ok(format!("{} has {} [{}]", holders[0], value, objective)),
+                None => Err(format!("Can't get value of {} for {}; none is set", objective, holders[0])),
+            },
+            Some(_) => Err("Only one entity is allowed, but the provided selector allows more than one".to_string()),
+            None => return,
+        },
+        ["players", op @ ("set" | "add" | "remove"), targets, objective, value] => {
+            let value: i32 = match value.parse() {
+                Ok(v) => v,
+                Err(_) => {
+                    send_message(world, entity, &format!("Invalid score '{}'", value));
+                    return;
+                }
+            };
+            let holders = match resolve_score_holders(world, entity, targets) {
+                Some(h) => h,
+                None => return,
+            };
+            let mut last = 0;
+            let mut outcome = Ok(());
+            for holder in &holders {
+                let change = match *op {
+                    "set" => world_state.scoreboard.set_score(holder, objective, value).map(|p| (value, p)),
+                    "add" => world_state.scoreboard.add_score(holder, objective, value),
+                    _ => world_state.scoreboard.add_score(holder, objective, value.wrapping_neg()),
+                };
+                match change {
+                    Ok((new_value, packet)) => {
+                        broadcast_to_all(world, &packet);
+                        last = new_value;
+                    }
+                    Err(e) => {
+                        outcome = Err(e);
+                        break;
+                    }
+                }
+            }
+            outcome.map(|_| {
+                if holders.len() == 1 {
+                    format!("Set [{}] for {} to {}", objective, holders[0], last)
+                } else {
+                    format!("Updated [{}] for {} entities", objective, holders.len())
+                }
+            })
+        }
+        ["players", op @ ("set" | "add" | "remove"), ..] => {
+            Err(format!("Usage: /scoreboard players {} <targets> <objective> <score>", op))
+        }
+        ["players", "reset", targets] | ["players", "reset", targets, _] => {
+            let objective = parts.get(3).copied();
+            let holders = match resolve_score_holders(world, entity, targets) {
+                Some(h) => h,
+                None => return,
+            };
+            let mut outcome = Ok(());
+            for holder in &holders {
+                match world_state.scoreboard.reset_score(holder, objective) {
+                    Ok(packet) => broadcast_to_all(world, &packet),
+                    Err(e) => {
+                        outcome = Err(e);
+                        break;
+                    }
+                }
+            }
+            outcome.map(|_| match objective {
+                Some(name) => format!("Reset [{}] for {} entities", name, holders.len()),
+                None => format!("Reset all scores for {} entities", holders.len()),
+            })
+        }
+        _ => Err("Usage: /scoreboard objectives <list|add|remove|modify|setdisplay> ... or /scoreboard players <list|get|set|add|remove|reset> ...".to_string()),
+    };
+    match result {
+        Ok(msg) => {
+            for line in msg.lines() {
+                send_message(world, entity, line);
+            }
+        }
+        Err(e) => send_message(world, entity, &e),
+    }
+}
+
+fn cmd_team(world: &World, world_state: &mut WorldState, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let parts: Vec<&str> = args.split_whitespace().collect();
+    let result = match parts.as_slice() {
+        ["list"] => {
+            let teams = world_state.scoreboard.teams();
+            if teams.is_empty() {
+                Ok("There are no teams".to_string())
+            } else {
+                let names: Vec<&str> = teams.iter().map(|t| t.name.as_str()).collect();
+                Ok(format!("There are {} team(s): {}", names.len(), names.join(", ")))
+            }
+        }
+        ["list", name] => match world_state.scoreboard.team(name) {
+            Some(team) if team.members.is_empty() => Ok(format!("There are no members on team {}", team.name)),
+            Some(team) => Ok(format!(
+                "Team {} has {} member(s): {}",
+                team.name,
+                team.members.len(),
+                team.members.join(", ")
+            )),
+            None => Err(format!("Unknown team '{}'", name)),
+        },
+        ["add", name, display @ ..] => {
+            let display_name = display.join(" ");
+            let display_name = (!display_name.is_empty()).then_some(display_name.as_str());
+            world_state.scoreboard.add_team(name, display_name).map(|packet| {
+                broadcast_to_all(world, &packet);
+                format!("Created team {}", name)
+            })
+        }
+        ["remove", name] => world_state.scoreboard.remove_team(name).map(|packet| {
+            broadcast_to_all(world, &packet);
+            format!("Removed team {}", name)
+        }),
+        ["empty", name] => world_state.scoreboard.empty_team(name).map(|(count, packet)| {
+            broadcast_to_all(world, &packet);
+            format!("Removed {} member(s) from team {}", count, name)
+        }),
+        ["join", name, targets @ ..] => {
+            let members = if targets.is_empty() {
+                resolve_score_holders(world, entity, "@s")
+            } else {
+                targets.iter().try_fold(Vec::new(), |mut acc, t| {
+                    acc.extend(resolve_score_holders(world, entity, t)?);
+                    Some(acc)
+                })
+            };
+            let Some(members) = members else {
+                return;
+            };
+            world_state.scoreboard.join_team(name, &members).map(|packets| {
+                for packet in &packets {
+                    broadcast_to_all(world, packet);
+                }
+                format!("Added {} member(s) to team {}", members.len(), name)
+            })
+        }
+        ["leave", targets @ ..] if !targets.is_empty() => {
+            let members = targets.iter().try_fold(Vec::new(), |mut acc, t| {
+                acc.extend(resolve_score_holders(world, entity, t)?);
+                Some(acc)
+            });
+            let Some(members) = members else {
+                return;
+            };
+            for packet in world_state.scoreboard.leave_team(&members) {
+                broadcast_to_all(world, &packet);
+            }
+            Ok(format!("Removed {} member(s) from any team", members.len()))
+        }
+        ["modify", name, option, value @ ..] if !value.is_empty() || matches!(*option, "prefix" | "suffix") => {
+            let value = value.join(" ");
+            world_state.scoreboard.modify_team(name, option, &value).map(|packet| {
+                broadcast_to_all(world, &packet);
+                format!("Updated {} of team {}", option, name)
+            })
+        }
+        _ => Err("Usage: /team <list|add|remove|empty|join|leave|modify> ... (modify options: displayName, color, prefix, suffix, friendlyFire, seeFriendlyInvisibles, nametagVisibility, deathMessageVisibility, collisionRule)".to_string()),
+    };
+    match result {
+        Ok(msg) => send_message(world, entity, &msg),
+        Err(e) => send_message(world, entity, &e),
+    }
+}
+
+/// Set the weather the way vanilla's setWeatherParameters does. Without a
+/// duration, picks vanilla's random length for that weather type.
+pub(crate) fn set_weather(world_state: &mut WorldState, kind: &str, duration: Option<i32>) -> bool {
+    let mut rng = rand::thread_rng();
+    match kind {
+        "clear" => {
+            world_state.clear_weather_time = duration.unwrap_or_else(|| rng.gen_range(12000..=180000));
+            world_state.rain_time = 0;
+            world_state.thunder_time = 0;
+            world_state.raining = false;
+            world_state.thundering = false;
+        }
+        "rain" => {
+            let duration = duration.unwrap_or_else(|| rng.gen_range(12000..=24000));
+            world_state.clear_weather_time = 0;
+            world_state.rain_time = duration;
+            world_state.thunder_time = duration;
+            world_state.raining = true;
+            world_state.thundering = false;
+        }
+        "thunder" => {
+            let duration = duration.unwrap_or_else(|| rng.gen_range(3600..=15600));
+            world_state.clear_weather_time = 0;
+            world_state.rain_time = duration;
+            world_state.thunder_time = duration;
+            world_state.raining = true;
+            world_state.thundering = true;
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// Parse a vanilla time argument: plain ticks, or a number suffixed with
+/// `t` (ticks), `s` (seconds) or `d` (in-game days).
+fn parse_time_arg(raw: &str) -> Option<i32> {
+    let (number, scale) = match raw.chars().last()? {
+        't' => (&raw[..raw.len() - 1], 1.0),
+        's' => (&raw[..raw.len() - 1], 20.0),
+        'd' => (&raw[..raw.len() - 1], 24000.0),
+        _ => (raw, 1.0),
+    };
+    let ticks = number.parse::<f64>().ok()? * scale;
+    (0.0..=i32::MAX as f64).contains(&ticks).then_some(ticks.round() as i32)
+}
+
+/// /weather [clear|rain|thunder] [duration]
+fn cmd_weather(world: &World, world_state: &mut WorldState, entity: hecs::Entity, args: &str) {
+    let parts: Vec<&str> = args.split_whitespace().collect();
+    if parts.is_empty() {
+        let current = if world_state.thundering {
+            "thunder"
+        } else if world_state.raining {
+            "rain"
+        } else {
+            "clear"
+        };
+        send_message(world, entity, &format!("The weather is currently {}", current));
+        return;
+    }
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let duration = match parts.get(1) {
+        Some(raw) => match parse_time_arg(raw) {
+            Some(ticks) => Some(ticks),
+            None => {
+                send_message(world, entity, &format!("Invalid duration: {}", raw));
+                return;
+            }
+        },
+        None => None,
+    };
+    if !set_weather(world_state, parts[0], duration) {
+        send_message(world, entity, "Usage: /weather <clear|rain|thunder> [duration]");
+        return;
+    }
+    let msg = match parts[0] {
+        "clear" => "Set the weather to clear",
+        "rain" => "Set the weather to rain",
+        _ => "Set the weather to rain & thunder",
+    };
+    send_message(world, entity, msg);
+}
+
+const DIFFICULTY_NAMES: [&str; 4] = ["peaceful", "easy", "normal", "hard"];
+
+/// /difficulty [peaceful|easy|normal|hard]
+fn cmd_difficulty(world: &World, world_state: &mut WorldState, entity: hecs::Entity, args: &str) {
+    let arg = args.trim();
+    if arg.is_empty() {
+        let name = DIFFICULTY_NAMES[world_state.difficulty.clamp(0, 3) as usize];
+        send_message(world, entity, &format!("The difficulty is {}", name));
+        return;
+    }
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let Some(difficulty) = DIFFICULTY_NAMES.iter().position(|d| *d == arg) else {
+        send_message(world, entity, "Usage: /difficulty <peaceful|easy|normal|hard>");
+        return;
+    };
+    if world_state.difficulty == difficulty as i32 {
+        send_message(world, entity, &format!("The difficulty did not change; it is already set to {}", arg));
+        return;
+    }
+    world_state.difficulty = difficulty as i32;
+    broadcast_to_all(world, &InternalPacket::ChangeDifficulty {
+        difficulty: difficulty as u8,
+        locked: false,
+    });
+    send_message(world, entity, &format!("The difficulty has been set to {}", arg));
+}
+
+fn ticking_state_packet(tick_rate: &TickRate) -> InternalPacket {
+    InternalPacket::TickingState {
+        tick_rate: tick_rate.rate(),
+        is_frozen: tick_rate.frozen(),
+    }
+}
+
+/// /tick query|rate <n>|freeze|unfreeze|step [n|stop]|sprint <n>|sprint stop —
+/// inspect or change how fast the world ticks.
+fn cmd_tick(world: &World, world_state: &mut WorldState, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 3) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let mut parts = args.split_whitespace();
+    let sub = parts.next().unwrap_or("query");
+    let arg = parts.next();
+    let ticks = &mut world_state.tick_rate;
+    match (sub, arg) {
+        ("query", _) => {
+            let state = if ticks.frozen() { "frozen" } else if ticks.sprinting() { "sprinting" } else { "running normally" };
+            send_message(world, entity, &format!(
+                "The game is {}. Target tick rate: {} per second ({:.1} ms per tick), average: {:.1} ms per tick",
+                state,
+                ticks.rate(),
+                1000.0 / ticks.rate(),
+                world_state.tick_stats.average_mspt(),
+            ));
+        }
+        ("rate", Some(rate)) => {
+            let Some(rate) = rate.parse::<f32>().ok().filter(|r| (crate::tick_rate::MIN_RATE..=crate::tick_rate::MAX_RATE).contains(r)) else {
+                send_message(world, entity, &format!(
+                    "Tick rate must be between {} and {}",
+                    crate::tick_rate::MIN_RATE,
+                    crate::tick_rate::MAX_RATE,
+                ));
+                return;
+            };
+            ticks.set_rate(rate);
+            broadcast_to_all(world, &ticking_state_packet(ticks));
+            send_message(world, entity, &format!("Set the target tick rate to {} per second", rate));
+        }
+        ("freeze", None) | ("unfreeze", None) => {
+            ticks.set_frozen(sub == "freeze");
+            broadcast_to_all(world, &ticking_state_packet(ticks));
+            send_message(world, entity, if ticks.frozen() { "The game is frozen" } else { "The game is running normally" });
+        }
+        ("step", arg) => {
+            let steps = match arg {
+                None => 1,
+                Some("stop") => 0,
+                Some(n) => match n.parse::<u32>() {
+                    Ok(n) if n > 0 => n,
+                    _ => {
+                        send_message(world, entity, "Usage: /tick step [ticks|stop]");
+                        return;
+                    }
+                },
+            };
+            if !ticks.step(steps) {
+                send_message(world, entity, "The game must be frozen first to step");
+                return;
+            }
+            broadcast_to_all(world, &InternalPacket::StepTick { steps: steps as i32 });
+            if steps == 0 {
+                send_message(world, entity, "Stopped stepping");
+            } else {
+                send_message(world, entity, &format!("Stepping {} tick(s)", steps));
+            }
+        }
+        ("sprint", Some("stop")) => {
+            ticks.sprint(0);
+            send_message(world, entity, "Stopped sprinting");
+        }
+        ("sprint", Some(n)) => {
+            let Ok(n) = n.parse::<u64>() else {
+                send_message(world, entity, "Usage: /tick sprint <ticks|stop>");
+                return;
+            };
+            ticks.sprint(n);
+            send_message(world, entity, &format!("Sprinting {} ticks", n));
+        }
+        _ => send_message(world, entity, "Usage: /tick <query|rate|freeze|unfreeze|step|sprint>"),
+    }
+}
+
+/// /seed — show the world seed.
+fn cmd_seed(world: &World, world_state: &WorldState, entity: hecs::Entity) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    send_message(world, entity, &format!("Seed: [{}]", world_state.seed));
+}
+
+/// How far /locate poi searches, as in vanilla.
+const LOCATE_POI_RADIUS: i32 = 256;
+
+/// /locate poi <type> — the nearest loaded point of interest of a type,
+/// like `minecraft:home` or `minecraft:lightning_rod`.
+fn cmd_locate(world: &World, world_state: &WorldState, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let poi_type = match args.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["poi", poi_type] => crate::datapacks::qualify(poi_type),
+        ["structure" | "biome", ..] => {
+            send_error(world, entity, "Only points of interest can be located");
+            return;
+        }
+        _ => {
+            send_error(world, entity, "Usage: /locate poi <type>");
+            return;
+        }
+    };
+    let (origin, _) = command_source(world, entity);
+    let center = BlockPos::new(origin.x.floor() as i32, origin.y.floor() as i32, origin.z.floor() as i32);
+    match world_state.find_poi(&center, LOCATE_POI_RADIUS, Some(&poi_type)).first() {
+        Some(record) => {
+            let (dx, dz) = ((record.pos.x - center.x) as f64, (record.pos.z - center.z) as f64);
+            send_message(world, entity, &format!(
+                "The nearest {} is at [{}, {}, {}] ({} blocks away)",
+                poi_type, record.pos.x, record.pos.y, record.pos.z, (dx * dx + dz * dz).sqrt().floor()
+            ));
+        }
+        None => send_error(world, entity, &format!("Could not find a point of interest of type \"{}\" within reasonable distance", poi_type)),
+    }
+}
+
+/// Disconnect a player, showing `reason` on their disconnect screen. The
+/// writer closes the connection once it's sent; the player leaves next tick.
+pub(crate) fn disconnect(world: &World, target: hecs::Entity, reason: TextComponent) {
+    if let Ok(sender) = world.get::<&ConnectionSender>(target) {
+        let _ = sender.0.send(InternalPacket::Disconnect { reason });
+    }
+}
+
+/// Name and UUID for a player who may be offline: online players first,
+/// then saved player data, then the offline-mode UUID for the name.
+fn resolve_profile(world: &World, world_state: &WorldState, name: &str) -> (String, Uuid) {
+    if let Some(target) = find_online_player(world, name) {
+        if let Ok(profile) = world.get::<&Profile>(target) {
+            return (profile.0.name.clone(), profile.0.uuid);
+        }
+    }
+    world_state
+        .player_data
+        .lookup(name)
+        .unwrap_or_else(|| (name.to_string(), crate::network::offline_uuid(name)))
+}
+
+fn sender_name(world: &World, entity: hecs::Entity) -> String {
+    world
+        .get::<&Profile>(entity)
+        .map(|p| p.0.name.clone())
+        .unwrap_or_else(|_| "Server".to_string())
+}
+
+/// Split `args` into the first word and an optional trailing reason.
+fn split_target_reason(args: &str) -> (&str, Option<&str>) {
+    let args = args.trim();
+    match args.split_once(char::is_whitespace) {
+        Some((target, reason)) if !reason.trim().is_empty() => (target, Some(reason.trim())),
+        _ => (args, None),
+    }
+}
+
+/// /kick <targets> [reason]
+fn cmd_kick(world: &World, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 3) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let (target_arg, reason) = split_target_reason(args);
+    if target_arg.is_empty() {
+        send_message(world, entity, "Usage: /kick <targets> [reason]");
+        return;
+    }
+    let Some(targets) = resolve_targets(world, entity, target_arg) else {
+        return;
+    };
+    let message = match reason {
+        Some(reason) => TextComponent::plain(reason),
+        None => TextComponent::translatable("multiplayer.disconnect.kicked", Vec::new()),
+    };
+    let reason = reason.unwrap_or("Kicked by an operator");
+    for target in targets {
+        if world.get::<&ConnectionSender>(target).is_err() {
+            continue;
+        }
+        let name = entity_display_name(world, target);
+        disconnect(world, target, message.clone());
+        send_message(world, entity, &format!("Kicked {}: {}", name, reason));
+        info!("{} kicked {}: {}", sender_name(world, entity), name, reason);
+    }
+}
+
+/// /ban <player> [reason]
+fn cmd_ban(world: &World, world_state: &WorldState, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 3) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let (target, reason) = split_target_reason(args);
+    if target.is_empty() {
+        send_message(world, entity, "Usage: /ban <player> [reason]");
+        return;
+    }
+    let (name, uuid) = resolve_profile(world, world_state, target);
+    let mut bans = crate::access::load_player_bans();
+    if bans.iter().any(|b| b.uuid == uuid || b.name.eq_ignore_ascii_case(&name)) {
+        send_message(world, entity, "Nothing changed. The player is already banned");
+        return;
+    }
+    let ban = crate::access::PlayerBan::new(uuid, &name, &sender_name(world, entity), reason);
+    let kick_message = ban.kick_message();
+    let reason = ban.reason.clone();
+    bans.push(ban);
+    if let Err(e) = crate::access::save_player_bans(&bans) {
+        send_message(world, entity, &e);
+        return;
+    }
+    if let Some(online) = find_online_player(world, &name) {
+        disconnect(world, online, kick_message);
+    }
+    send_message(world, entity, &format!("Banned {}: {}", name, reason));
+    info!("{} banned {}: {}", sender_name(world, entity), name, reason);
+}
+
+/// /ban-ip <address|player> [reason]
+fn cmd_ban_ip(world: &World, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 3) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let (target, reason) = split_target_reason(args);
+    if target.is_empty() {
+        send_message(world, entity, "Usage: /ban-ip <address|player> [reason]");
+        return;
+    }
+    let ip = match target.parse::<std::net::IpAddr>() {
+        Ok(ip) => ip,
+        Err(_) => match find_online_player(world, target)
+            .and_then(|e| world.get::<&PeerAddress>(e).ok().map(|a| a.0))
+        {
+            Some(ip) => ip,
+            None => {
+                send_message(world, entity, "Invalid IP address or unknown player");
+                return;
+            }
+        },
+    };
+    let mut bans = crate::access::load_ip_bans();
+    if bans.iter().any(|b| b.ip == ip.to_string()) {
+        send_message(world, entity, "Nothing changed. That IP is already banned");
+        return;
+    }
+    let ban = crate::access::IpBan::new(ip, &sender_name(world, entity), reason);
+    let kick_message = ban.kick_message();
+    let reason = ban.reason.clone();
+    bans.push(ban);
+    if let Err(e) = crate::access::save_ip_bans(&bans) {
+        send_message(world, entity, &e);
+        return;
+    }
+    let affected: Vec<hecs::Entity> = world
+        .query::<&PeerAddress>()
+        .iter()
+        .filter(|(_, addr)| addr.0 == ip)
+        .map(|(e, _)| e)
+        .collect();
+    for target in &affected {
+        disconnect(world, *target, kick_message.clone());
+    }
+    send_message(
+        world,
+        entity,
+        &format!("Banned IP {}: {} ({} player(s) online)", ip, reason, affected.len()),
+    );
+    info!("{} banned IP {}: {}", sender_name(world, entity), ip, reason);
+}
+
+/// /pardon <player>
+fn cmd_pardon(world: &World, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 3) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let target = args.trim();
+    if target.is_empty() {
+        send_message(world, entity, "Usage: /pardon <player>");
+        return;
+    }
+    let mut bans = crate::access::load_player_bans();
+    let before = bans.len();
+    bans.retain(|b| !b.name.eq_ignore_ascii_case(target) && b.uuid.to_string() != target);
+    if bans.len() == before {
+        send_message(world, entity, "Nothing changed. The player isn't banned");
+        return;
+    }
+    match crate::access::save_player_bans(&bans) {
+        Ok(()) => send_message(world, entity, &format!("Unbanned {}", target)),
+        Err(e) => send_message(world, entity, &e),
+    }
+}
+
+/// /pardon-ip <address>
+fn cmd_pardon_ip(world: &World, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 3) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let Ok(ip) = args.trim().parse::<std::net::IpAddr>() else {
+        send_message(world, entity, "Usage: /pardon-ip <address>");
+        return;
+    };
+    let mut bans = crate::access::load_ip_bans();
+    let before = bans.len();
+    bans.retain(|b| b.ip != ip.to_string());
+    if bans.len() == before {
+        send_message(world, entity, "Nothing changed. That IP isn't banned");
+        return;
+    }
+    match crate::access::save_ip_bans(&bans) {
+        Ok(()) => send_message(world, entity, &format!("Unbanned IP {}", ip)),
+        Err(e) => send_message(world, entity, &e),
+    }
+}
+
+/// /banlist [players|ips]
+fn cmd_banlist(world: &World, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 3) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let mut lines = Vec::new();
+    if args.trim() != "ips" {
+        for ban in crate::access::load_player_bans() {
+            lines.push(format!("{} was banned by {}: {}", ban.name, ban.source, ban.reason));
+        }
+    }
+    if args.trim() != "players" {
+        for ban in crate::access::load_ip_bans() {
+            lines.push(format!("{} was banned by {}: {}", ban.ip, ban.source, ban.reason));
+        }
+    }
+    if lines.is_empty() {
+        send_message(world, entity, "There are no bans");
+        return;
+    }
+    send_message(world, entity, &format!("There are {} ban(s):", lines.len()));
+    for line in lines {
+        send_message(world, entity, &line);
+    }
+}
+
+/// /op <player> [level]
+fn cmd_op(
+    world: &World,
+    world_state: &WorldState,
+    entity: hecs::Entity,
+    args: &str,
+    lua_commands: &crate::bridge::LuaCommands,
+) {
+    if !has_permission(world, entity, 3) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let parts: Vec<&str> = args.split_whitespace().collect();
+    let (target, level) = match parts.as_slice() {
+        [target] => (*target, 4),
+        [target, level] => match level.parse::<u8>() {
+            Ok(level @ 1..=4) => (*target, level),
+            _ => {
+                send_message(world, entity, "Permission level must be between 1 and 4");
+                return;
+            }
+        },
+        _ => {
+            send_message(world, entity, "Usage: /op <player> [level]");
+            return;
+        }
+    };
+    // Can't grant more than you have
+    if level > permission_level(world, entity) {
+        send_message(world, entity, "You can't grant a higher permission level than your own");
+        return;
+    }
+    let (name, uuid) = resolve_profile(world, world_state, target);
+    let mut ops = crate::access::load_ops();
+    match ops.iter_mut().find(|op| op.matches(&name, Some(uuid))) {
+        Some(op) if op.level == level => {
+            send_message(world, entity, "Nothing changed. The player already is an operator");
+            return;
+        }
+        Some(op) => op.level = level,
+        None => ops.push(crate::access::OpEntry {
+            uuid,
+            name: name.clone(),
+            level,
+            bypasses_player_limit: false,
+        }),
+    }
+    if let Err(e) = crate::access::save_ops(&ops) {
+        send_message(world, entity, &e);
+        return;
+    }
+    if let Some(online) = find_online_player(world, &name) {
+        send_permissions(world, online, lua_commands);
+    }
+    send_message(world, entity, &format!("Made {} a server operator (level {})", name, level));
+    info!("{} opped {} at level {}", sender_name(world, entity), name, level);
+}
+
+/// /deop <player>
+fn cmd_deop(world: &World, world_state: &WorldState, entity: hecs::Entity, args: &str, lua_commands: &crate::bridge::LuaCommands) {
+    if !has_permission(world, entity, 3) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let target = args.trim();
+    if target.is_empty() {
+        send_message(world, entity, "Usage: /deop <player>");
+        return;
+    }
+    let online = find_online_player(world, target);
+    let (_, uuid) = resolve_profile(world, world_state, target);
+    let mut ops = crate::access::load_ops();
+    let before = ops.len();
+    ops.retain(|op| !op.matches(target, Some(uuid)));
+    if ops.len() == before {
+        send_message(world, entity, "Nothing changed. The player is not an operator");
+        return;
+    }
+    if let Err(e) = crate::access::save_ops(&ops) {
+        send_message(world, entity, &e);
+        return;
+    }
+    if let Some(online) = online {
+        send_permissions(world, online, lua_commands);
+    }
+    if crate::access::permission_level(target, Some(uuid)) > 0 {
+        send_message(world, entity, &format!("Removed {} from ops.json, but they are still listed in config/ops.toml", target));
+    } else {
+        send_message(world, entity, &format!("Made {} no longer a server operator", target));
+    }
+    info!("{} deopped {}", sender_name(world, entity), target);
+}
+
+/// /ops reload — re-read ops.json and config/ops.toml after editing them by hand.
+fn cmd_ops(world: &World, entity: hecs::Entity, args: &str, lua_commands: &crate::bridge::LuaCommands) {
+    if !has_permission(world, entity, 3) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    if args.trim() != "reload" {
+        send_message(world, entity, "Usage: /ops reload");
+        return;
+    }
+    let count = crate::access::reload_ops();
+    let players: Vec<hecs::Entity> = world.query::<&Profile>().iter().map(|(e, _)| e).collect();
+    for player in players {
+        send_permissions(world, player, lua_commands);
+    }
+    send_message(world, entity, &format!("Reloaded the operator list ({} entries)", count));
+}
+
+/// /whitelist <on|off|list|add|remove|reload> [player]
+fn cmd_whitelist(world: &World, world_state: &WorldState, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 3) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let parts: Vec<&str> = args.split_whitespace().collect();
+    match parts.as_slice() {
+        ["on"] => {
+            if crate::access::whitelist_enabled() {
+                send_message(world, entity, "Whitelist is already turned on");
+            } else {
+                crate::access::set_whitelist_enabled(true);
+                send_message(world, entity, "Whitelist is now turned on");
+            }
+        }
+        ["off"] => {
+            if !crate::access::whitelist_enabled() {
+                send_message(world, entity, "Whitelist is already turned off");
+            } else {
+                crate::access::set_whitelist_enabled(false);
+                send_message(world, entity, "Whitelist is now turned off");
+            }
+        }
+        ["list"] => {
+            let names: Vec<String> = crate::access::load_whitelist().into_iter().map(|w| w.name).collect();
+            if names.is_empty() {
+                send_message(world, entity, "There are no whitelisted players");
+            } else {
+                send_message(
+                    world,
+                    entity,
+                    &format!("There are {} whitelisted player(s): {}", names.len(), names.join(", ")),
+                );
+            }
+        }
+        ["reload"] => {
+            let count = crate::access::load_whitelist().len();
+            send_message(world, entity, &format!("Reloaded the whitelist ({} entries)", count));
+        }
+        ["add", target] => {
+            let (name, uuid) = resolve_profile(world, world_state, target);
+            let mut entries = crate::access::load_whitelist();
+            if entries.iter().any(|w| w.matches(&name, Some(uuid))) {
+                send_message(world, entity, "Player is already whitelisted");
+                return;
+            }
+            entries.push(crate::access::WhitelistEntry { uuid, name: name.clone() });
+            match crate::access::save_whitelist(&entries) {
+                Ok(()) => send_message(world, entity, &format!("Added {} to the whitelist", name)),
+                Err(e) => send_message(world, entity, &e),
+            }
+        }
+        ["remove", target] => {
+            let mut entries = crate::access::load_whitelist();
+            let before = entries.len();
+            entries.retain(|w| !w.name.eq_ignore_ascii_case(target));
+            if entries.len() == before {
+                send_message(world, entity, "Player is not whitelisted");
+                return;
+            }
+            match crate::access::save_whitelist(&entries) {
+                Ok(()) => send_message(world, entity, &format!("Removed {} from the whitelist", target)),
+                Err(e) => send_message(world, entity, &e),
+            }
+        }
+        _ => send_message(world, entity, "Usage: /whitelist <on|off|list|add|remove|reload> [player]"),
+    }
+}
+
+/// /advancement (grant|revoke) <targets> (everything | only <advancement> [criterion]
+/// | from|through|until <advancement>) — edit players' advancement progress.
+fn cmd_advancement(world: &mut World, world_state: &WorldState, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    const USAGE: &str = "Usage: /advancement (grant|revoke) <targets> (everything|only <advancement> [criterion]|from|through|until <advancement>)";
+    let parts: Vec<&str> = args.split_whitespace().collect();
+    let (grant, target_arg, mode, rest) = match parts.as_slice() {
+        [action @ ("grant" | "revoke"), targets, mode, rest @ ..] => (*action == "grant", *targets, *mode, rest),
+        _ => {
+            send_error(world, entity, USAGE);
+            return;
+        }
+    };
+    let Some(targets) = resolve_targets(world, entity, target_arg) else {
+        return;
+    };
+    let tree = world_state.advancements.clone();
+    let named = rest.first().map(|id| if id.contains(':') { id.to_string() } else { format!("minecraft:{}", id) });
+    let selected: Vec<&crate::advancements::Advancement> = match (mode, named.as_deref().map(|id| tree.get(id))) {
+        ("everything", None) => tree.iter().collect(),
+        ("only" | "from" | "through" | "until", Some(None)) => {
+            send_error(world, entity, &format!("No advancement was found by the name '{}'", rest[0]));
+            return;
+        }
+        ("only", Some(Some(advancement))) if rest.len() <= 2 => vec![advancement],
+        ("from" | "through" | "until", Some(Some(advancement))) if rest.len() == 1 => {
+            let mut ids = HashSet::new();
+            if mode != "from" {
+                // The advancement and its ancestors
+                let mut next = Some(advancement);
+                while let Some(a) = next {
+                    ids.insert(a.id.as_str());
+                    next = a.parent.as_deref().and_then(|p| tree.get(p));
+                }
+            }
+            if mode != "until" {
+                // The advancement and its descendants; parents come first
+                let mut below = HashSet::from([advancement.id.as_str()]);
+                for a in tree.iter() {
+                    if a.parent.as_deref().is_some_and(|p| below.contains(p)) {
+                        below.insert(a.id.as_str());
+                    }
+                }
+                ids.extend(below);
+            }
+            tree.iter().filter(|a| ids.contains(a.id.as_str())).collect()
+        }
+        _ => {
+            send_error(world, entity, USAGE);
+            return;
+        }
+    };
+
+    let criterion = rest.get(1).copied();
+    if let (Some(criterion), [advancement]) = (criterion, selected.as_slice()) {
+        if !advancement.criteria.iter().any(|(name, _)| name == criterion) {
+            send_error(world, entity, &format!("Advancement '{}' does not contain the criterion '{}'", advancement.id, criterion));
+            return;
+        }
+    }
+
+    let mut changed = 0;
+    for &target in &targets {
+        for advancement in &selected {
+            let criteria: Vec<&str> = match criterion {
+                Some(c) => vec![c],
+                None => advancement.criteria.iter().map(|(name, _)| name.as_str()).collect(),
+            };
+            let mut any = false;
+            if grant {
+                for c in criteria {
+                    any |= grant_criterion(world, world_state, target, &advancement.id, c);
+                }
+            } else {
+                any = revoke_criteria(world, world_state, target, &advancement.id, criterion);
+            }
+            changed += any as usize;
+        }
+    }
+
+    let (verb, preposition) = if grant { ("Granted", "to") } else { ("Revoked", "from") };
+    let who = match targets.as_slice() {
+        [target] => entity_display_name(world, *target),
+        _ => format!("{} players", targets.len()),
+    };
+    if changed == 0 {
+        let what = if grant { "already have" } else { "don't have" };
+        send_error(world, entity, &format!("Nothing changed: the targets {} those advancements", what));
+    } else if let (Some(criterion), [advancement]) = (criterion, selected.as_slice()) {
+        send_message(world, entity, &format!("{} criterion '{}' of advancement {} {} {}", verb, criterion, advancement.id, preposition, who));
+    } else {
+        send_message(world, entity, &format!("{} {} advancement(s) {} {}", verb, changed, preposition, who));
+    }
+}
+
+/// /gamerule [rule] [value] — list rules, query one, or set it.
+fn cmd_gamerule(world: &World, world_state: &mut WorldState, entity: hecs::Entity, args: &str) {
+    let parts: Vec<&str> = args.split_whitespace().collect();
+    match parts.as_slice() {
+        [] => {
+            let names: Vec<&str> = world_state.game_rules.names().collect();
+            send_message(world, entity, &format!("Gamerules: {}", names.join(", ")));
+        }
+        [rule] => match world_state.game_rules.get(rule) {
+            Some(value) => send_message(world, entity, &format!("Gamerule {} is currently set to: {}", rule, value)),
+            None => send_message(world, entity, &format!("Unknown gamerule: {}", rule)),
+        },
+        [rule, value] => {
+            if !has_permission(world, entity, 2) {
+                send_message(world, entity, "You don't have permission to use this command.");
+                return;
+            }
+            match world_state.game_rules.set(rule, value) {
+                Ok(value) => {
+                    apply_gamerule_change(world, world_state, rule);
+                    send_message(world, entity, &format!("Gamerule {} is now set to: {}", rule, value));
+                }
+                Err(e) => send_message(world, entity, &e),
+            }
+        }
+        _ => send_message(world, entity, "Usage: /gamerule [rule] [value]"),
+    }
+}
+
+/// Push client-visible gamerule changes to everyone online.
+fn apply_gamerule_change(world: &World, world_state: &WorldState, rule: &str) {
+    match rule {
+        "doImmediateRespawn" => {
+            let immediate = world_state.game_rules.get_bool(rule);
+            broadcast_to_all(world, &InternalPacket::GameEvent {
+                event: 11, // ENABLE_RESPAWN_SCREEN (1.0 = respawn immediately)
+                value: if immediate { 1.0 } else { 0.0 },
+            });
+        }
+        "reducedDebugInfo" => {
+            // Entity events 22/23 toggle reduced debug info for the receiving player
+            let event_id = if world_state.game_rules.get_bool(rule) { 22 } else { 23 };
+            for (_e, (eid, sender)) in world.query::<(&EntityId, &ConnectionSender)>().iter() {
+                let _ = sender.0.send(InternalPacket::EntityEvent { entity_id: eid.0, event_id });
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Send a title (and optionally subtitle and fade times) to one player.
+/// The subtitle is only displayed alongside a title, so it must be sent first.
+pub(crate) fn send_title(
+    sender: &ConnectionSender,
+    title: TextComponent,
+    subtitle: Option<TextComponent>,
+    times: Option<(i32, i32, i32)>,
+) {
+    if let Some((fade_in, stay, fade_out)) = times {
+        let _ = sender.0.send(InternalPacket::SetTitlesAnimation { fade_in, stay, fade_out });
+    }
+    if let Some(text) = subtitle {
+        let _ = sender.0.send(InternalPacket::SetSubtitleText { text });
+    }
+    let _ = sender.0.send(InternalPacket::SetTitleText { text: title });
+}
+
+/// Parse a text argument: JSON text components (`{"text":..}` or `"..."`)
+/// are decoded, anything else is taken as plain text.
+pub(crate) fn parse_text_arg(raw: &str) -> TextComponent {
+    let raw = raw.trim();
+    if raw.starts_with('{') {
+        if let Ok(component) = serde_json::from_str::<TextComponent>(raw) {
+            return component;
+        }
+    } else if raw.starts_with('"') {
+        if let Ok(text) = serde_json::from_str::<String>(raw) {
+            return TextComponent::plain(text);
+        }
+    }
+    TextComponent::plain(raw)
+}
+
+/// /title <targets> <title|subtitle|actionbar> <text>
+/// /title <targets> times <fadeIn> <stay> <fadeOut>
+/// /title <targets> <clear|reset>
+fn cmd_title(world: &World, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let usage = "Usage: /title <targets> <title|subtitle|actionbar|times|clear|reset> ...";
+    let mut split = args.trim().splitn(3, ' ');
+    let (Some(target_arg), Some(action)) = (split.next().filter(|s| !s.is_empty()), split.next()) else {
+        send_message(world, entity, usage);
+        return;
+    };
+    let rest = split.next().unwrap_or("").trim();
+
+    let packets = match action {
+        "title" | "subtitle" | "actionbar" if !rest.is_empty() => {
+            let text = parse_text_arg(rest);
+            vec![match action {
+                "title" => InternalPacket::SetTitleText { text },
+                "subtitle" => InternalPacket::SetSubtitleText { text },
+                _ => InternalPacket::SetActionBarText { text },
+            }]
+        }
+        "times" => {
+            let times: Vec<i32> = rest.split_whitespace().filter_map(|s| s.parse().ok()).collect();
+            match times.as_slice() {
+                &[fade_in, stay, fade_out] if times.iter().all(|t| *t >= 0) => {
+                    vec![InternalPacket::SetTitlesAnimation { fade_in, stay, fade_out }]
+                }
+                _ => {
+                    send_message(world, entity, "Usage: /title <targets> times <fadeIn> <stay> <fadeOut>");
+                    return;
+                }
+            }
+        }
+        "clear" => vec![InternalPacket::ClearTitles { reset: false }],
+        "reset" => vec![InternalPacket::ClearTitles { reset: true }],
+        _ => {
+            send_message(world, entity, usage);
+            return;
+        }
+    };
+
+    let Some(targets) = resolve_targets(world, entity, target_arg) else {
+        return;
+    };
+    let players: Vec<hecs::Entity> =
+        targets.into_iter().filter(|t| world.get::<&ConnectionSender>(*t).is_ok()).collect();
+    if players.is_empty() {
+        send_message(world, entity, "No player was found");
+        return;
+    }
+    for player in &players {
+        if let Ok(sender) = world.get::<&ConnectionSender>(*player) {
+            for packet in &packets {
+                let _ = sender.0.send(packet.clone());
+            }
+        }
+    }
+    let what = match action {
+        "times" => "Changed title display times",
+        "clear" => "Cleared titles",
+        "reset" => "Reset title options",
+        _ => "Showing new title",
+    };
+    let msg = if players.len() == 1 {
+        format!("{} for {}", what, entity_display_name(world, players[0]))
+    } else {
+        format!("{} for {} players", what, players.len())
+    };
+    send_message(world, entity, &msg);
+}
+
+/// /tellraw <targets> <json> - send a raw JSON text component as a chat message.
+fn cmd_tellraw(world: &World, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let Some((target_arg, raw)) = args.trim().split_once(' ').filter(|(_, raw)| !raw.trim().is_empty()) else {
+        send_message(world, entity, "Usage: /tellraw <targets> <json>");
+        return;
+    };
+    let Some(targets) = resolve_targets(world, entity, target_arg) else {
+        return;
+    };
+    let content = parse_text_arg(raw);
+    let mut sent = false;
+    for target in targets {
+        if let Ok(sender) = world.get::<&ConnectionSender>(target) {
+            let _ = sender.0.send(InternalPacket::SystemChatMessage { content: content.clone(), overlay: false });
+            sent = true;
+        }
+    }
+    if !sent {
+        send_message(world, entity, "No player was found");
+    }
+}
+
+/// Resolve score holders: selectors resolve to player names (or entity UUIDs),
+/// while a plain word is used as-is so offline and fake players can hold scores.
+fn resolve_score_holders(world: &World, entity: hecs::Entity, arg: &str) -> Option<Vec<String>> {
+    if !selector::is_selector(arg) {
+        return Some(vec![arg.to_string()]);
+    }
+    let targets = resolve_targets(world, entity, arg)?;
+    Some(
+        targets
+            .into_iter()
+            .filter_map(|e| {
+                if let Ok(profile) = world.get::<&Profile>(e) {
+                    return Some(profile.0.name.clone());
+                }
+                world.get::<&EntityUuid>(e).ok().map(|u| u.0.to_string())
+            })
+            .collect(),
+    )
+}
+
+/// /tp [targets] <x y z> [yaw pitch | facing <x y z> | facing entity <target> [eyes|feet]]
+/// /tp [targets] <destination>
+///
+/// Coordinates may be absolute, relative (`~`) or local (`^`) to the sender.
+/// Teleporting players to an entity in another world moves them to that world;
+/// other entities can't change worlds and are left where they are.
+fn cmd_tp(world: &mut World, world_state: &mut WorldState, entity: hecs::Entity, args: &str) {
+    let parts: Vec<&str> = args.split_whitespace().collect();
+    if parts.is_empty() {
+        send_message(world, entity, "Usage: /tp [targets] <x> <y> <z> [yaw pitch] or /tp [targets] <destination>");
+        return;
+    }
+
+    // A leading coordinate means the sender is the target.
+    let (targets, dest_parts) = if is_coordinate(parts[0]) || parts.len() == 1 {
+        (vec![entity], &parts[..])
+    } else {
+        if !has_permission(world, entity, 2) {
+            send_message(world, entity, "You don't have permission to teleport other entities.");
+            return;
+        }
+        match resolve_targets(world, entity, parts[0]) {
+            Some(t) => (t, &parts[1..]),
+            None => return,
+        }
+    };
+
+    let (origin, source_rot) = command_source(world, entity);
+    let mut dest_level = None;
+    let (position, rotation) = match dest_parts.len() {
+        1 => {
+            let dest = match resolve_targets(world, entity, dest_parts[0]) {
+                Some(d) => d,
+                None => return,
+            };
+            if dest.len() > 1 {
+                send_message(world, entity, "Only one entity is allowed, but the provided selector allows more than one");
+                return;
+            }
+            let position = match world.get::<&Position>(dest[0]) {
+                Ok(pos) => pos.0,
+                Err(_) => return,
+            };
+            let rotation = world.get::<&Rotation>(dest[0]).map(|r| (r.yaw, r.pitch)).ok();
+            dest_level = Some(player_level(world, dest[0]));
+            (position, rotation)
+        }
+        n if n >= 3 => {
+            let position = match parse_coords(&dest_parts[..3], origin, source_rot) {
+                Ok(p) => p,
+                Err(msg) => {
+                    send_message(world, entity, &msg);
+                    return;
+                }
+            };
+            let rotation = match parse_tp_rotation(world, entity, &dest_parts[3..], position, source_rot) {
+                Ok(r) => r,
+                Err(msg) => {
+                    send_message(world, entity, &msg);
+                    return;
+                }
+            };
+            (position, rotation)
+        }
+        _ => {
+            send_message(world, entity, "Usage: /tp [targets] <x> <y> <z> [yaw pitch] or /tp [targets] <destination>");
+            return;
+        }
+    };
+
+    for &target in &targets {
+        if let Some(level) = &dest_level {
+            if *level != player_level(world, target) && move_to_level(world, world_state, target, level).is_err() {
+                continue;
+            }
+        }
+        teleport_entity(world, world_state, target, position, rotation);
+    }
+
+    let (x, y, z) = (position.x, position.y, position.z);
+    let msg = if targets.len() == 1 && targets[0] == entity {
+        format!("Teleported to {:.1}, {:.1}, {:.1}", x, y, z)
+    } else if targets.len() == 1 {
+        format!("Teleported {} to {:.1}, {:.1}, {:.1}", entity_display_name(world, targets[0]), x, y, z)
+    } else {
+        format!("Teleported {} entities to {:.1}, {:.1}, {:.1}", targets.len(), x, y, z)
+    };
+    send_message(world, entity, &msg);
+}
+
+/// Parse the optional rotation tail of /tp: `<yaw> <pitch>`, `facing <x y z>`
+/// or `facing entity <target> [eyes|feet]`. `position` is the destination.
+fn parse_tp_rotation(
+    world: &World,
+    entity: hecs::Entity,
+    parts: &[&str],
+    position: Vec3d,
+    source_rot: (f32, f32),
+) -> Result<Option<(f32, f32)>, String> {
+    match parts {
+        [] => Ok(None),
+        ["facing", "entity", target, rest @ ..] => {
+            let eyes = match rest {
+                [] | ["eyes"] => true,
+                ["feet"] => false,
+                _ => return Err("Expected 'eyes' or 'feet'".to_string()),
+            };
+            let dest = resolve_targets(world, entity, target).ok_or_else(|| "No entity was found".to_string())?;
+            let mut look_at = world
+                .get::<&Position>(dest[0])
+                .map(|p| p.0)
+                .map_err(|_| "No entity was found".to_string())?;
+            if eyes && world.get::<&Profile>(dest[0]).is_ok() {
+                look_at.y += 1.62;
+            }
+            Ok(Some(facing_rotation(position, look_at)))
+        }
+        ["facing", x, y, z] => {
+            let (origin, _) = command_source(world, entity);
+            let look_at = parse_coords(&[*x, *y, *z], origin, source_rot)?;
+            Ok(Some(facing_rotation(position, look_at)))
+        }
+        [yaw, pitch] => {
+            let yaw = parse_relative_f64(yaw, source_rot.0 as f64).ok_or("Invalid yaw")? as f32;
+            let pitch = parse_relative_f64(pitch, source_rot.1 as f64).ok_or("Invalid pitch")? as f32;
+            Ok(Some((yaw, pitch.clamp(-90.0, 90.0))))
+        }
+        _ => Err("Expected <yaw> <pitch> or facing <x y z> | facing entity <target>".to_string()),
+    }
+}
+
+/// Yaw/pitch for a player standing at `from` to look at `target`.
+fn facing_rotation(from: Vec3d, target: Vec3d) -> (f32, f32) {
+    let dx = target.x - from.x;
+    let dy = target.y - (from.y + 1.62);
+    let dz = target.z - from.z;
+    let horizontal = (dx * dx + dz * dz).sqrt();
+    let yaw = (dz.atan2(dx).to_degrees() - 90.0) as f32;
+    let pitch = (-dy.atan2(horizontal).to_degrees()) as f32;
+    (yaw, pitch)
+}
+
+/// Position and rotation a command is run from: the running command's
+/// execution context, or the entity's own.
+fn command_source(world: &World, entity: hecs::Entity) -> (Vec3d, (f32, f32)) {
+    if let Ok(ctx) = world.get::<&ExecutionContext>(entity) {
+        return (ctx.position, ctx.rotation);
+    }
+    entity_location(world, entity)
+}
+
+/// An entity's own position and rotation (the origin, for the console).
+fn entity_location(world: &World, entity: hecs::Entity) -> (Vec3d, (f32, f32)) {
+    let position = world.get::<&Position>(entity).map(|p| p.0).unwrap_or(Vec3d::new(0.0, 0.0, 0.0));
+    let rotation = world.get::<&Rotation>(entity).map(|r| (r.yaw, r.pitch)).unwrap_or((0.0, 0.0));
+    (position, rotation)
+}
+
+/// Context for a command issued directly by a player or the console.
+fn execution_context(world: &World, entity: hecs::Entity) -> ExecutionContext {
+    let (position, rotation) = entity_location(world, entity);
+    ExecutionContext {
+        entity,
+        position,
+        rotation,
+        dimension: player_level(world, entity),
+        output: entity,
+        permission_level: permission_level(world, entity),
+    }
+}
+
+/// Whether an argument looks like a coordinate rather than a target.
+fn is_coordinate(arg: &str) -> bool {
+    arg.starts_with('~') || arg.starts_with('^') || arg.parse::<f64>().is_ok()
+}
+
+/// Parse an absolute value or a `~`-relative offset from `base`.
+fn parse_relative_f64(arg: &str, base: f64) -> Option<f64> {
+    match arg.strip_prefix('~') {
+        Some("") => Some(base),
+        Some(offset) => offset.parse::<f64>().ok().map(|o| base + o),
+        None => arg.parse::<f64>().ok(),
+    }
+}
+
+/// Parse three coordinates relative to a command source at `origin` facing
+/// `rotation`. Each axis may be absolute or `~`-relative; alternatively all
+/// three may be local `^left ^up ^forwards` offsets.
+fn parse_coords(parts: &[&str], origin: Vec3d, rotation: (f32, f32)) -> Result<Vec3d, String> {
+    let local_count = parts.iter().filter(|p| p.starts_with('^')).count();
+    if local_count == 3 {
+        let local = |i: usize| -> Result<f64, String> {
+            match &parts[i][1..] {
+                "" => Ok(0.0),
+                offset => offset.parse::<f64>().map_err(|_| "Invalid local coordinate".to_string()),
+            }
+        };
+        return Ok(local_to_world(origin, rotation, local(0)?, local(1)?, local(2)?));
+    }
+    if local_count > 0 {
+        return Err("Cannot mix world & local coordinates (everything must either use ^ or not)".to_string());
+    }
+    let axis = |i: usize, base: f64, name: &str| -> Result<f64, String> {
+        parse_relative_f64(parts[i], base).ok_or_else(|| format!("Invalid {} coordinate", name))
+    };
+    Ok(Vec3d::new(
+        axis(0, origin.x, "x")?,
+        axis(1, origin.y, "y")?,
+        axis(2, origin.z, "z")?,
+    ))
+}
+
+/// Convert local `^left ^up ^forwards` offsets to a world position.
+fn local_to_world(origin: Vec3d, (yaw, pitch): (f32, f32), left: f64, up: f64, forwards: f64) -> Vec3d {
+    let (yaw, pitch) = ((yaw as f64 + 90.0).to_radians(), (-pitch as f64).to_radians());
+    let (sin_pitch_up, cos_pitch_up) = (pitch + std::f64::consts::FRAC_PI_2).sin_cos();
+    let forward = (yaw.cos() * pitch.cos(), pitch.sin(), yaw.sin() * pitch.cos());
+    let upward = (yaw.cos() * cos_pitch_up, sin_pitch_up, yaw.sin() * cos_pitch_up);
+    // left = -(forward x up)
+    let leftward = (
+        -(forward.1 * upward.2 - forward.2 * upward.1),
+        -(forward.2 * upward.0 - forward.0 * upward.2),
+        -(forward.0 * upward.1 - forward.1 * upward.0),
+    );
+    Vec3d::new(
+        origin.x + forward.0 * forwards + upward.0 * up + leftward.0 * left,
+        origin.y + forward.1 * forwards + upward.1 * up + leftward.1 * left,
+        origin.z + forward.2 * forwards + upward.2 * up + leftward.2 * left,
+    )
+}
+
+/// Move an entity, syncing the client for players: chunks around the
+/// destination are sent first, and the player's movement is ignored until
+/// they confirm the teleport. Other entities are picked up by the movement
+/// broadcast on the next tick. Without a rotation the entity keeps facing the
+/// way it was.
+pub(crate) fn teleport_entity(
+    world: &mut World,
+    world_state: &mut WorldState,
+    target: hecs::Entity,
+    position: Vec3d,
+    rotation: Option<(f32, f32)>,
+) {
+    if let Ok(mut pos) = world.get::<&mut Position>(target) {
+        pos.0 = position;
+    }
+    if let Some((yaw, pitch)) = rotation {
+        if let Ok(mut rot) = world.get::<&mut Rotation>(target) {
+            rot.yaw = yaw;
+            rot.pitch = pitch;
+        }
+    }
+    if world.get::<&ConnectionSender>(target).is_err() {
+        return;
+    }
+    handle_chunk_updates(world, target);
+
+    // Flags 0x08 | 0x10: yaw and pitch relative (unchanged)
+    let (yaw, pitch, flags) = match rotation {
+        Some((yaw, pitch)) => (yaw, pitch, 0),
+        None => (0.0, 0.0, 0x18),
+    };
+    world_state.next_teleport_id += 1;
+    let pending = PendingTeleport {
+        id: world_state.next_teleport_id,
+        position,
+        yaw,
+        pitch,
+        flags,
+        sent_tick: world_state.tick_count,
+    };
+    if let Ok(sender) = world.get::<&ConnectionSender>(target) {
+        let _ = sender.0.send(pending.packet());
+    }
+    let _ = world.insert_one(target, pending);
+}
+
+/// Resend teleports a player hasn't confirmed within a second, as vanilla does.
+fn tick_pending_teleports(world: &mut World, world_state: &WorldState) {
+    for (_e, (sender, pending)) in world.query_mut::<(&ConnectionSender, &mut PendingTeleport)>() {
+        if world_state.tick_count.saturating_sub(pending.sent_tick) >= 20 {
+            pending.sent_tick = world_state.tick_count;
+            let _ = sender.0.send(pending.packet());
+        }
+    }
+}
+
+/// Whether a player can stand at `(x, y, z)`: solid ground below, and two
+/// non-solid blocks free of lava and fire.
+fn is_safe_spot(world_state: &mut WorldState, x: i32, y: i32, z: i32) -> bool {
+    let ground = world_state.get_block(&BlockPos::new(x, y - 1, z));
+    if !pickaxe_data::is_solid_block(ground) {
+        return false;
+    }
+    (y..=y + 1).all(|by| {
+        let block = world_state.get_block(&BlockPos::new(x, by, z));
+        !pickaxe_data::is_solid_block(block) && !pickaxe_data::is_lava(block) && !pickaxe_data::is_fire(block)
+    })
+}
+
+/// Teleport a player to the nearest safe spot in the column at `position`:
+/// at or below it first, then above. Falls back to `position` unchanged.
+/// Used by /spawn, /home and /warp.
+fn safe_teleport(
+    world: &mut World,
+    world_state: &mut WorldState,
+    entity: hecs::Entity,
+    position: Vec3d,
+    rotation: Option<(f32, f32)>,
+) {
+    let (x, z) = (position.x.floor() as i32, position.z.floor() as i32);
+    let start = (position.y.floor() as i32).clamp(-63, 318);
+    let safe_y = (-63..=start).rev().chain(start + 1..=318).find(|&y| is_safe_spot(world_state, x, y, z));
+    let destination = match safe_y {
+        Some(y) => Vec3d::new(position.x, y as f64, position.z),
+        None => position,
+    };
+    if let Ok(mut fall) = world.get::<&mut FallDistance>(entity) {
+        fall.0 = 0.0;
+    }
+    teleport_entity(world, world_state, entity, destination, rotation);
+}
+
+/// /give [targets] <item>[components] [count]
+///
+/// Components use the 1.21 syntax, e.g.
+/// `diamond_sword[enchantments={levels:{sharpness:5}},damage=10,custom_name='"Excalibur"']`;
+/// a legacy `{Damage:10,...}` SNBT tag is accepted as well.
+fn cmd_give(world: &mut World, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+
+    let parts = split_command_args(args);
+    if parts.is_empty() {
+        send_message(world, entity, "Usage: /give [targets] <item>[components] [count]");
+        return;
+    }
+
+    // `/give <item> [count]` targets the sender; `/give <targets> <item> [count]` otherwise.
+    let (targets, rest) = if parts.len() >= 2 && parts[1].parse::<i32>().is_err() {
+        let targets = match resolve_targets(world, entity, parts[0]) {
+            Some(t) => t,
+            None => return,
+        };
+        if targets.iter().any(|&t| world.get::<&Inventory>(t).is_err()) {
+            send_message(world, entity, "Only players may be affected by this command");
+            return;
+        }
+        (targets, &parts[1..])
+    } else {
+        (vec![entity], &parts[..])
+    };
+
+    let stack = match parse_item_spec(rest[0]) {
+        Ok(stack) => stack,
+        Err(e) => {
+            send_message(world, entity, &e);
+            return;
+        }
+    };
+    let count = match rest.get(1).map(|c| c.parse::<i32>()) {
+        None => 1,
+        Some(Ok(n @ 1..=6400)) => n,
+        Some(Ok(n)) if n > 6400 => {
+            send_message(world, entity, &format!("Can't give more than 6400 of {}", rest[0]));
+            return;
+        }
+        Some(_) => {
+            send_message(world, entity, "Count must be a positive integer");
+            return;
+        }
+    };
+
+    let max_stack = pickaxe_data::item_id_to_stack_size(stack.item_id).unwrap_or(64).clamp(1, 64);
+    let mut given = Vec::new();
+    for &target in &targets {
+        let mut remaining = count;
+        while remaining > 0 {
+            let mut part = stack.clone();
+            part.count = remaining.min(max_stack) as i8;
+            let inserted = insert_into_inventory(world, target, &part) as i32;
+            remaining -= inserted;
+            if inserted < part.count as i32 {
+                break;
+            }
+        }
+        if remaining < count {
+            given.push(target);
+        }
+        if remaining > 0 {
+            let msg = if target == entity {
+                "Inventory is full!".to_string()
+            } else {
+                format!("{}'s inventory is full!", entity_display_name(world, target))
+            };
+            send_message(world, entity, &msg);
+        }
+    }
+
+    let display_name = match &stack.custom_name {
+        Some(name) => name.text.clone(),
+        None => pickaxe_data::item_id_to_name(stack.item_id).unwrap_or("unknown").to_string(),
+    };
+    match given.as_slice() {
+        [] => {}
+        [only] if *only == entity => {
+            send_message(world, entity, &format!("Gave {} x{}", display_name, count));
+        }
+        [only] => {
+            let target_name = entity_display_name(world, *only);
+            send_message(world, entity, &format!("Gave {} x{} to {}", display_name, count, target_name));
+        }
+        _ => {
+            send_message(world, entity, &format!("Gave {} x{} to {} players", display_name, count, given.len()));
+        }
+    }
+}
+
+/// Split command arguments on whitespace, keeping `[...]`, `{...}` and quoted
+/// sections (which may contain spaces) inside a single argument.
+pub(crate) fn split_command_args(args: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut quote: Option<char> = None;
+    let mut start: Option<usize> = None;
+    for (i, c) in args.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '[' | '{' => depth += 1,
+                ']' | '}' => depth -= 1,
+                c if c.is_whitespace() && depth <= 0 => {
+                    if let Some(s) = start.take() {
+                        parts.push(&args[s..i]);
+                    }
+                    continue;
+                }
+                _ => {}
+            },
+        }
+        if start.is_none() {
+            start = Some(i);
+        }
+    }
+    if let Some(s) = start {
+        parts.push(&args[s..]);
+    }
+    parts
+}
+
+/// Parse an integer command argument within `min..=max`.
+fn parse_int_arg(raw: &str, min: i32, max: i32) -> Result<i32, String> {
+    let value: i32 = raw.parse().map_err(|_| format!("Invalid integer '{}'", raw))?;
+    if value < min {
+        Err(format!("Integer must not be less than {}, found {}", min, value))
+    } else if value > max {
+        Err(format!("Integer must not be more than {}, found {}", max, value))
+    } else {
+        Ok(value)
+    }
+}
+
+/// Parse `name`, `name[component=value,...]` or `name{SNBT}` into an item stack
+/// of one item.
+pub(crate) fn parse_item_spec(spec: &str) -> Result<ItemStack, String> {
+    let split = spec.find(['[', '{']).unwrap_or(spec.len());
+    let (name, data) = spec.split_at(split);
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    let item_id = pickaxe_data::item_name_to_id(name).ok_or_else(|| format!("Unknown item: {}", name))?;
+    let mut stack = make_crafted_item(item_id, 1);
+
+    let components = if data.starts_with('[') {
+        let inner = data
+            .strip_prefix('[')
+            .and_then(|d| d.strip_suffix(']'))
+            .ok_or("Expected ']' after item components")?;
+        let mut snbt = String::from("{");
+        for (i, component) in split_top_level(inner, ',').into_iter().enumerate() {
+            let (key, value) = split_top_level(component, '=')
+                .split_first()
+                .and_then(|(k, v)| (v.len() == 1).then(|| (k.trim(), v[0])))
+                .ok_or_else(|| format!("Expected <component>=<value>, got '{}'", component))?;
+            if i > 0 {
+                snbt.push(',');
+            }
+            snbt.push_str(&format!("\"{}\":{}", key, value));
+        }
+        snbt.push('}');
+        pickaxe_nbt::parse_snbt(&snbt)?
+    } else if data.starts_with('{') {
+        pickaxe_nbt::parse_snbt(data)?
+    } else {
+        return Ok(stack);
+    };
+
+    let NbtValue::Compound(entries) = components else {
+        return Ok(stack);
+    };
+    for (key, value) in &entries {
+        match key.strip_prefix("minecraft:").unwrap_or(key) {
+            "damage" | "Damage" => {
+                stack.damage = value.as_number().ok_or("damage must be a number")? as i32;
+            }
+            "enchantments" | "Enchantments" => apply_enchantments_component(&mut stack, value)?,
+            "custom_name" | "CustomName" => {
+                let raw = value.as_str().ok_or("custom_name must be a string")?;
+                stack.custom_name = Some(parse_text_arg(raw));
+            }
+            "display" => {
+                if let Some(raw) = value.get("Name").and_then(|v| v.as_str()) {
+                    stack.custom_name = Some(parse_text_arg(raw));
+                }
+            }
+            "dyed_color" => {
+                let rgb = value.get("rgb").unwrap_or(value);
+                stack.dyed_color = Some(rgb.as_number().ok_or("dyed_color must be a number")? as i32);
+            }
+            "lore" => {
+                let lines = value.as_list().ok_or("lore must be a list")?;
+                stack.lore = lines.iter().map(|line| line.as_str().map(parse_text_arg).ok_or("lore lines must be strings")).collect::<Result<_, _>>()?;
+            }
+            "unbreakable" | "Unbreakable" => {
+                stack.unbreakable = value.as_number().is_none_or(|n| n != 0.0);
+            }
+            "attribute_modifiers" => {
+                stack.attribute_modifiers = attribute_modifiers_from_nbt(value);
+            }
+            "potion_contents" => {
+                stack.potion_contents = Some(potion_contents_from_nbt(value));
+            }
+            "banner_patterns" => {
+                stack.banner_patterns = banner_patterns_from_nbt(value);
+            }
+            "custom_data" => {
+                (stack.custom_id, stack.custom_data) = pickaxe_protocol_core::split_custom_data(value);
+            }
+            other => return Err(format!("Unknown item component: {}", other)),
+        }
+    }
+    Ok(stack)
+}
+
+/// Apply `{levels:{sharpness:5}}`, `{sharpness:5}` or the legacy
+/// `[{id:"sharpness",lvl:5}]` enchantment forms.
+fn apply_enchantments_component(stack: &mut ItemStack, value: &NbtValue) -> Result<(), String> {
+    let mut pairs: Vec<(String, f64)> = Vec::new();
+    match value.get("levels").unwrap_or(value) {
+        NbtValue::Compound(levels) => {
+            for (name, level) in levels {
+                if name == "show_in_tooltip" {
+                    continue;
+                }
+                pairs.push((name.clone(), level.as_number().ok_or("Enchantment level must be a number")?));
+            }
+        }
+        NbtValue::List(list) => {
+            for ench in list {
+                let name = ench.get("id").and_then(|v| v.as_str()).ok_or("Enchantment is missing an id")?;
+                let level = ench.get("lvl").and_then(|v| v.as_number()).unwrap_or(1.0);
+                pairs.push((name.to_string(), level));
+            }
+        }
+        _ => return Err("Invalid enchantments component".to_string()),
+    }
+    for (name, level) in pairs {
+        let short = name.strip_prefix("minecraft:").unwrap_or(&name);
+        let id = pickaxe_data::enchantment_name_to_id(short).ok_or_else(|| format!("Unknown enchantment: {}", name))?;
+        let level = level as i32;
+        if !(1..=255).contains(&level) {
+            return Err(format!("Invalid enchantment level {} for {}", level, short));
+        }
+        stack.enchantments.retain(|(existing, _)| *existing != id);
+        stack.enchantments.push((id, level));
+    }
+    Ok(())
+}
+
+/// Split on `sep` where it is not nested in brackets/braces or quotes.
+fn split_top_level(s: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut quote: Option<char> = None;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '[' | '{' => depth += 1,
+                ']' | '}' => depth -= 1,
+                c if c == sep && depth == 0 => {
+                    parts.push(&s[start..i]);
+                    start = i + c.len_utf8();
+                }
+                _ => {}
+            },
+        }
+    }
+    if start < s.len() || !parts.is_empty() {
+        parts.push(&s[start..]);
+    }
+    parts
+}
+
+/// /clear [targets] [item] [maxCount]
+///
+/// Removes matching items (all items, or `*`) from the players' inventories.
+/// A maxCount of 0 only counts the matching items.
+fn cmd_clear(world: &mut World, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let parts: Vec<&str> = args.split_whitespace().collect();
+    let targets = match parts.first() {
+        Some(arg) => match resolve_targets(world, entity, arg) {
+            Some(t) => t,
+            None => return,
+        },
+        None => vec![entity],
+    };
+    let item_filter = match parts.get(1).copied() {
+        None | Some("*") => None,
+        Some(raw) => {
+            let name = raw.strip_prefix("minecraft:").unwrap_or(raw);
+            match pickaxe_data::item_name_to_id(name) {
+                Some(id) => Some(id),
+                None => {
+                    send_message(world, entity, &format!("Unknown item: {}", raw));
+                    return;
+                }
+            }
+        }
+    };
+    let max_count = match parts.get(2).map(|c| c.parse::<i32>()) {
+        None => -1,
+        Some(Ok(n)) if n >= 0 => n,
+        Some(_) => {
+            send_message(world, entity, "maxCount must be a non-negative integer");
+            return;
+        }
+    };
+
+    let mut total = 0;
+    let mut affected = 0;
+    for &target in &targets {
+        let (removed, updates, state_id) = {
+            let Ok(mut inv) = world.get::<&mut Inventory>(target) else {
+                continue;
+            };
+            let mut removed = 0;
+            let mut changed = Vec::new();
+            // Slot 0 is the crafting output, which is derived from the grid
+            for slot in 1..inv.slots.len() {
+                let Some(stack) = inv.slots[slot].clone() else {
+                    continue;
+                };
+                if item_filter.is_some_and(|id| id != stack.item_id) {
+                    continue;
+                }
+                let take = if max_count < 0 {
+                    stack.count as i32
+                } else if max_count == 0 {
+                    removed += stack.count as i32;
+                    continue;
+                } else {
+                    (max_count - removed).min(stack.count as i32)
+                };
+                if take <= 0 {
                     break;
                 }
+                removed += take;
+                let remaining = stack.count as i32 - take;
+                let new_slot = (remaining > 0).then(|| ItemStack { count: remaining as i8, ..stack });
+                inv.set_slot(slot, new_slot.clone());
+                changed.push((slot, new_slot));
+            }
+            (removed, changed, inv.state_id)
+        };
+        if let Ok(sender) = world.get::<&ConnectionSender>(target) {
+            for (slot, item) in updates {
+                let _ = sender.0.send(InternalPacket::SetContainerSlot {
+                    window_id: 0,
+                    state_id,
+                    slot: slot as i16,
+                    item,
+                });
+            }
+        }
+        if removed > 0 {
+            affected += 1;
+            total += removed;
+        }
+    }
+
+    let msg = if total == 0 {
+        if targets.len() == 1 {
+            format!("No items were found on {}", entity_display_name(world, targets[0]))
+        } else {
+            format!("No items were found on {} players", targets.len())
+        }
+    } else {
+        let who = if targets.len() == 1 {
+            entity_display_name(world, targets[0])
+        } else {
+            format!("{} players", affected)
+        };
+        if max_count == 0 {
+            format!("Found {} matching item(s) on {}", total, who)
+        } else {
+            format!("Removed {} item(s) from {}", total, who)
+        }
+    };
+    send_message(world, entity, &msg);
+}
+
+fn cmd_kill(
+    world: &mut World,
+    world_state: &mut WorldState,
+    entity: hecs::Entity,
+    entity_id: i32,
+    args: &str,
+    scripting: &ScriptRuntime,
+) {
+    let args = args.trim();
+    if args.is_empty() {
+        if world.get::<&Profile>(entity).is_err() {
+            send_message(world, entity, "A player is required to run this command here");
+            return;
+        }
+        kill_player(world, world_state, entity, entity_id, scripting);
+        return;
+    }
+
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to kill other entities.");
+        return;
+    }
+    let targets = match resolve_targets(world, entity, args) {
+        Some(t) => t,
+        None => return,
+    };
+    let summary = if targets.len() == 1 {
+        format!("Killed {}", entity_display_name(world, targets[0]))
+    } else {
+        format!("Killed {} entities", targets.len())
+    };
+
+    let next_eid = world_state.next_eid.clone();
+    for target in targets {
+        let target_eid = match world.get::<&EntityId>(target) {
+            Ok(id) => id.0,
+            Err(_) => continue,
+        };
+        if world.get::<&Profile>(target).is_ok() {
+            kill_player(world, world_state, target, target_eid, scripting);
+        } else if world.get::<&MobEntity>(target).is_ok() {
+            kill_mob(world, world_state, target, target_eid, None, scripting, &next_eid);
+        } else {
+            let _ = world.despawn(target);
+            broadcast_to_all(world, &InternalPacket::RemoveEntities {
+                entity_ids: vec![target_eid],
+            });
+            for (_, tracked) in world.query_mut::<&mut TrackedEntities>() {
+                tracked.visible.remove(&target_eid);
+            }
+        }
+    }
+
+    if world.contains(entity) {
+        send_message(world, entity, &summary);
+    }
+}
+
+/// Vanilla damage type names accepted by /damage, mapped to the internal
+/// damage sources used by the damage pipeline.
+const DAMAGE_TYPES: &[(&str, &str)] = &[
+    ("generic", "generic"),
+    ("player_attack", "player"),
+    ("mob_attack", "mob"),
+    ("arrow", "arrow"),
+    ("fall", "fall"),
+    ("in_fire", "fire"),
+    ("on_fire", "fire"),
+    ("lava", "lava"),
+    ("drown", "drowning"),
+    ("starve", "starve"),
+    ("out_of_world", "void"),
+    ("lightning_bolt", "lightning"),
+    ("explosion", "explosion"),
+    ("wither", "wither"),
+    ("magic", "magic"),
+];
+
+/// /damage <targets> <amount> [damageType] [by <entity>]
+fn cmd_damage(world: &mut World, world_state: &mut WorldState, entity: hecs::Entity, args: &str, scripting: &ScriptRuntime) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let parts: Vec<&str> = args.split_whitespace().collect();
+    if parts.len() < 2 {
+        send_message(world, entity, "Usage: /damage <targets> <amount> [damageType] [by <entity>]");
+        return;
+    }
+    let Some(targets) = resolve_targets(world, entity, parts[0]) else {
+        return;
+    };
+    let amount = match parts[1].parse::<f32>() {
+        Ok(a) if a >= 0.0 && a.is_finite() => a,
+        _ => {
+            send_message(world, entity, "Amount must be a non-negative number");
+            return;
+        }
+    };
+    let source = match parts.get(2) {
+        None => "generic",
+        Some(raw) => {
+            let name = raw.strip_prefix("minecraft:").unwrap_or(raw);
+            match DAMAGE_TYPES.iter().find(|(n, _)| *n == name) {
+                Some((_, source)) => *source,
+                None => {
+                    send_message(world, entity, &format!("Unknown damage type: {}", raw));
+                    return;
+                }
+            }
+        }
+    };
+    let attacker = match parts.get(3..) {
+        None | Some([]) => None,
+        Some(["by", who]) => match resolve_targets(world, entity, who) {
+            Some(found) if found.len() == 1 => Some(found[0]),
+            Some(_) => {
+                send_message(world, entity, "Only one entity is allowed, but the provided selector allows more than one");
+                return;
+            }
+            None => return,
+        },
+        Some(_) => {
+            send_message(world, entity, "Usage: /damage <targets> <amount> [damageType] [by <entity>]");
+            return;
+        }
+    };
+    let attacker_pos = attacker.and_then(|a| world.get::<&Position>(a).ok().map(|p| p.0));
+    let player_attacker = attacker.filter(|a| world.get::<&Profile>(*a).is_ok());
+
+    let next_eid = world_state.next_eid.clone();
+    let mut damaged = 0;
+    for &target in &targets {
+        let Ok(target_eid) = world.get::<&EntityId>(target).map(|e| e.0) else {
+            continue;
+        };
+        if world.get::<&Profile>(target).is_ok() {
+            apply_damage_from(world, world_state, target, target_eid, amount, source, attacker_pos, scripting);
+            damaged += 1;
+        } else if world.get::<&MobEntity>(target).is_ok() {
+            damage_mob(world, world_state, target, target_eid, amount, player_attacker, scripting, &next_eid);
+            damaged += 1;
+        }
+    }
+
+    if damaged == 0 {
+        send_message(world, entity, "No damageable entity was found");
+    } else if targets.len() == 1 {
+        let name = entity_display_name(world, targets[0]);
+        send_message(world, entity, &format!("Applied {} damage to {}", amount, name));
+    } else {
+        send_message(world, entity, &format!("Applied {} damage to {} entities", amount, damaged));
+    }
+}
+
+/// /summon <entity> [x y z] [nbt]
+fn cmd_summon(world: &mut World, world_state: &mut WorldState, entity: hecs::Entity, args: &str, scripting: &ScriptRuntime) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    // NBT is everything from the first '{' so it may contain spaces.
+    let (head, nbt_str) = match args.find('{') {
+        Some(i) => (&args[..i], Some(&args[i..])),
+        None => (args, None),
+    };
+    let parts: Vec<&str> = head.split_whitespace().collect();
+    let position = match parts.len() {
+        1 => world.get::<&Position>(entity).map(|p| p.0).unwrap_or(Vec3d::new(0.0, 0.0, 0.0)),
+        4 => {
+            let (origin, rotation) = command_source(world, entity);
+            match parse_coords(&parts[1..], origin, rotation) {
+                Ok(position) => position,
+                Err(e) => {
+                    send_message(world, entity, &e);
+                    return;
+                }
+            }
+        }
+        _ => {
+            send_message(world, entity, "Usage: /summon <entity> [x y z] [nbt]");
+            return;
+        }
+    };
+    let nbt = match nbt_str.map(pickaxe_nbt::parse_snbt).transpose() {
+        Ok(nbt) => nbt,
+        Err(e) => {
+            send_message(world, entity, &format!("Invalid NBT: {}", e));
+            return;
+        }
+    };
+
+    match summon_entity(world, world_state, parts[0], position, nbt.as_ref()) {
+        Ok((type_name, eid)) => {
+            scripting.fire_event_in_context(
+                "entity_spawn",
+                &[
+                    ("entity_id", &eid.to_string()),
+                    ("entity_type", type_name),
+                    ("x", &format!("{:.2}", position.x)),
+                    ("y", &format!("{:.2}", position.y)),
+                    ("z", &format!("{:.2}", position.z)),
+                ],
+                world as *mut _ as *mut (),
+                world_state as *mut _ as *mut (),
+            );
+            send_message(world, entity, &format!("Summoned new {}", type_name));
+        }
+        Err(e) => send_message(world, entity, &e),
+    }
+}
+
+/// Spawn any supported entity by registry name, applying NBT overrides
+/// (Health, CustomName, CustomNameVisible, NoAI, Fuse for TNT, Item for items).
+/// Returns the registry name and the new entity ID.
+pub(crate) fn summon_entity(
+    world: &mut World,
+    world_state: &mut WorldState,
+    name: &str,
+    pos: Vec3d,
+    nbt: Option<&NbtValue>,
+) -> Result<(&'static str, i32), String> {
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    let type_id = pickaxe_data::entity_name_to_type(name).ok_or_else(|| format!("Unknown entity: {}", name))?;
+    let type_name = pickaxe_data::entity_type_name(type_id).unwrap_or("unknown");
+    let tag = |key: &str| nbt.and_then(|n| n.get(key));
+    let custom_name = tag("CustomName").and_then(|v| v.as_str()).map(|raw| CustomName {
+        text: parse_text_arg(raw),
+        visible: tag("CustomNameVisible").and_then(|v| v.as_number()).unwrap_or(0.0) != 0.0,
+    });
+
+    let spawned = if let Some(mob_type) = pickaxe_data::mob_name_to_type(type_name) {
+        let e = spawn_mob(world, &world_state.next_eid, mob_type, pos.x, pos.y, pos.z);
+        if let Ok(mut mob) = world.get::<&mut MobEntity>(e) {
+            if let Some(health) = tag("Health").and_then(|v| v.as_number()) {
+                mob.health = (health as f32).clamp(0.5, mob.max_health);
+            }
+            mob.no_ai = tag("NoAI").and_then(|v| v.as_number()).unwrap_or(0.0) != 0.0;
+        }
+        e
+    } else {
+        let eid = world_state.next_eid.fetch_add(1, Ordering::Relaxed);
+        let base = (
+            EntityId(eid),
+            EntityUuid(Uuid::new_v4()),
+            Position(pos),
+            PreviousPosition(pos),
+            Velocity(Vec3d::new(0.0, 0.0, 0.0)),
+            Rotation { yaw: 0.0, pitch: 0.0 },
+        );
+        match type_id {
+            pickaxe_data::ENTITY_ITEM => {
+                let item_tag = tag("Item").ok_or("Item entities need an Item tag, e.g. {Item:{id:\"stone\",count:1}}")?;
+                let item_name = item_tag.get("id").and_then(|v| v.as_str()).unwrap_or("");
+                let item_name = item_name.strip_prefix("minecraft:").unwrap_or(item_name);
+                let item_id = pickaxe_data::item_name_to_id(item_name)
+                    .ok_or_else(|| format!("Unknown item: {}", item_name))?;
+                let count = item_tag
+                    .get("count")
+                    .or_else(|| item_tag.get("Count"))
+                    .and_then(|v| v.as_number())
+                    .unwrap_or(1.0)
+                    .clamp(1.0, 127.0) as i8;
+                let e = world.spawn(base);
+                let _ = world.insert(
+                    e,
+                    (OnGround(false), ItemEntity { item: ItemStack::new(item_id, count), pickup_delay: 10, age: 0 }),
+                );
+                e
+            }
+            pickaxe_data::ENTITY_TNT => {
+                let fuse = tag("fuse").or_else(|| tag("Fuse")).and_then(|v| v.as_number()).unwrap_or(80.0) as i32;
+                let e = world.spawn(base);
+                let _ = world.insert(e, (OnGround(false), TntEntity { fuse, owner: None }));
+                play_sound_at_entity(world, pos.x, pos.y, pos.z, "entity.tnt.primed", SOUND_BLOCKS, 1.0, 1.0);
+                e
+            }
+            pickaxe_data::ENTITY_ARMOR_STAND => {
+                let e = world.spawn(base);
+                let _ = world.insert_one(e, ArmorStand);
+                e
+            }
+            _ => return Err(format!("Summoning {} is not supported yet", type_name)),
+        }
+    };
+
+    if let Some(custom_name) = custom_name {
+        let _ = world.insert_one(spawned, custom_name);
+    }
+    let eid = world.get::<&EntityId>(spawned).map(|id| id.0).unwrap_or(0);
+    Ok((type_name, eid))
+}
+
+fn kill_player(world: &mut World, world_state: &mut WorldState, entity: hecs::Entity, entity_id: i32, scripting: &ScriptRuntime) {
+    // Set health to 0 and trigger death
+    if let Ok(mut h) = world.get::<&mut Health>(entity) {
+        h.current = 0.0;
+    }
+    // Send health update
+    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+        let _ = sender.0.send(InternalPacket::SetHealth {
+            health: 0.0,
+            food: 20,
+            saturation: 5.0,
+        });
+    }
+    handle_player_death(world, world_state, entity, entity_id, "kill", scripting);
+}
+
+fn cmd_say(world: &World, message: &str, sender_name: &str) {
+    if message.is_empty() {
+        return;
+    }
+    let chat_text = format!("[{}] {}", sender_name, message);
+    broadcast_to_all(
+        world,
+        &InternalPacket::SystemChatMessage {
+            content: TextComponent::plain(&chat_text),
+            overlay: false,
+        },
+    );
+}
+
+/// Run a command line (without the leading `/`) from a player or the console.
+fn run_command(
+    config: &ServerConfig,
+    world: &mut World,
+    world_state: &mut WorldState,
+    ctx: &ExecutionContext,
+    command: &str,
+    scripting: &ScriptRuntime,
+    lua_commands: &crate::bridge::LuaCommands,
+) {
+    let entity = ctx.entity;
+    let name = entity_display_name(world, entity);
+    let entity_id = world.get::<&EntityId>(entity).map(|id| id.0).unwrap_or(0);
+    let parts: Vec<&str> = command.splitn(2, ' ').collect();
+    let cmd_name = parts[0];
+    let args = if parts.len() > 1 { parts[1] } else { "" };
+
+    // Attach the context while the command runs, so command_source,
+    // permission_level and send_message see it
+    let outer = world.remove_one::<ExecutionContext>(entity).ok();
+    let _ = world.insert_one(entity, ctx.clone());
+
+    match cmd_name {
+        "execute" => cmd_execute(config, world, world_state, ctx, args, scripting, lua_commands),
+        "function" => cmd_function(config, world, world_state, ctx, args, scripting, lua_commands),
+        "gamemode" | "gm" => cmd_gamemode(world, entity, args),
+        "tp" | "teleport" => cmd_tp(world, world_state, entity, args),
+        "give" => cmd_give(world, entity, args),
+        "clear" => cmd_clear(world, entity, args),
+        "damage" => cmd_damage(world, world_state, entity, args, scripting),
+        "kill" => cmd_kill(world, world_state, entity, entity_id, args, scripting),
+        "say" => cmd_say(world, args, &name),
+        "help" => cmd_help(world, entity, lua_commands),
+        "time" => cmd_time(world, entity, args, world_state),
+        "effect" => cmd_effect(world, entity, args),
+        "potion" => cmd_potion(world, entity, args),
+        "enchant" => cmd_enchant(world, entity, args),
+        "invsee" => cmd_invsee(world, world_state, entity, args),
+        "playerdata" => cmd_playerdata(world, world_state, entity, args),
+        "chunkinfo" => cmd_chunkinfo(world, world_state, entity, args),
+        "worldstats" => cmd_worldstats(world, world_state, entity),
+        "mods" => cmd_mods(world, entity, args, scripting),
+        "debug" => cmd_debug(world, entity, args),
+        "scoreboard" => cmd_scoreboard(world, world_state, entity, args),
+        "team" => cmd_team(world, world_state, entity, args),
+        "summon" => cmd_summon(world, world_state, entity, args, scripting),
+        "msg" | "tell" | "w" => cmd_msg(world, world_state, entity, args, scripting),
+        "reply" | "r" => cmd_reply(world, world_state, entity, args, scripting),
+        "socialspy" => cmd_socialspy(world, entity),
+        "audit" => cmd_audit(world, world_state, entity, args),
+        "list" => cmd_list(world, entity, config),
+        "ping" => cmd_ping(world, entity, args),
+        "tps" => cmd_tps(world, world_state, entity),
+        // Disabled built-ins fall through, so a Lua mod may provide its own
+        _ if !teleports::command_enabled(cmd_name) => run_lua_command(world, world_state, ctx, &name, cmd_name, args, scripting, lua_commands),
+        "spawn" => cmd_spawn(world, world_state, entity),
+        "sethome" => cmd_sethome(world, entity, args),
+        "home" => cmd_home(world, world_state, entity, args),
+        "delhome" => cmd_delhome(world, entity, args),
+        "homes" => cmd_homes(world, entity),
+        "warp" => cmd_warp(world, world_state, entity, args),
+        "setwarp" => cmd_setwarp(world, world_state, entity, args),
+        "delwarp" => cmd_delwarp(world, world_state, entity, args),
+        "stop" => cmd_stop(world, world_state, entity),
+        "save-all" => cmd_save_all(world, world_state, entity, args, config),
+        "save-off" => cmd_save_toggle(world, world_state, entity, false),
+        "save-on" => cmd_save_toggle(world, world_state, entity, true),
+        "backup" => cmd_backup(world, world_state, entity, args, config),
+        "schem" => cmd_schem(world, world_state, entity, args, config),
+        "world" => cmd_world(world, world_state, entity, args),
+        "place" => cmd_place(world, world_state, entity, args, config),
+        "structure" => cmd_structure(world, world_state, entity, args, config),
+        "tpa" if tpa::enabled() => cmd_tpa(world, world_state, entity, args),
+        "tpaccept" if tpa::enabled() => cmd_tpaccept(world, world_state, entity, args),
+        "tpdeny" if tpa::enabled() => cmd_tpdeny(world, world_state, entity, args),
+        "title" => cmd_title(world, entity, args),
+        "tellraw" => cmd_tellraw(world, entity, args),
+        "gamerule" => cmd_gamerule(world, world_state, entity, args),
+        "advancement" => cmd_advancement(world, world_state, entity, args),
+        "weather" => cmd_weather(world, world_state, entity, args),
+        "difficulty" => cmd_difficulty(world, world_state, entity, args),
+        "seed" => cmd_seed(world, world_state, entity),
+        "locate" => cmd_locate(world, world_state, entity, args),
+        "tick" => cmd_tick(world, world_state, entity, args),
+        "kick" => cmd_kick(world, entity, args),
+        "ban" => cmd_ban(world, world_state, entity, args),
+        "ban-ip" => cmd_ban_ip(world, entity, args),
+        "pardon" => cmd_pardon(world, entity, args),
+        "pardon-ip" => cmd_pardon_ip(world, entity, args),
+        "banlist" => cmd_banlist(world, entity, args),
+        "whitelist" => cmd_whitelist(world, world_state, entity, args),
+        "op" => cmd_op(world, world_state, entity, args, lua_commands),
+        "deop" => cmd_deop(world, world_state, entity, args, lua_commands),
+        "ops" => cmd_ops(world, entity, args, lua_commands),
+        _ => run_lua_command(world, world_state, ctx, &name, cmd_name, args, scripting, lua_commands),
+    }
+
+    // The entity may not exist any more (e.g. `/execute as @e run kill @s`)
+    let _ = world.remove_one::<ExecutionContext>(entity);
+    if let Some(outer) = outer {
+        let _ = world.insert_one(entity, outer);
+    }
+}
+
+/// /execute <subcommand>... [run <command>] - run a command in a modified
+/// execution context. `as` and `at` fork the context once per matched entity;
+/// conditions drop contexts. Without `run`, reports whether the test passed.
+fn cmd_execute(
+    config: &ServerConfig,
+    world: &mut World,
+    world_state: &mut WorldState,
+    ctx: &ExecutionContext,
+    args: &str,
+    scripting: &ScriptRuntime,
+    lua_commands: &crate::bridge::LuaCommands,
+) {
+    let entity = ctx.entity;
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let chain = match execute::parse(args) {
+        Ok(chain) => chain,
+        Err(e) => {
+            send_error(world, entity, &e);
+            return;
+        }
+    };
+
+    let mut contexts = vec![ctx.clone()];
+    for step in &chain.steps {
+        let mut next = Vec::new();
+        for ctx in contexts {
+            match step {
+                Step::As(sel) => {
+                    for target in selector::resolve(world, Some(ctx.entity), ctx.position, sel) {
+                        next.push(ExecutionContext { entity: target, ..ctx.clone() });
+                    }
+                }
+                Step::At(sel) => {
+                    for target in selector::resolve(world, Some(ctx.entity), ctx.position, sel) {
+                        let (position, rotation) = entity_location(world, target);
+                        next.push(ExecutionContext { position, rotation, ..ctx.clone() });
+                    }
+                }
+                Step::PositionedAs(sel) => {
+                    for target in selector::resolve(world, Some(ctx.entity), ctx.position, sel) {
+                        let (position, _) = entity_location(world, target);
+                        next.push(ExecutionContext { position, ..ctx.clone() });
+                    }
+                }
+                Step::RotatedAs(sel) => {
+                    for target in selector::resolve(world, Some(ctx.entity), ctx.position, sel) {
+                        let (_, rotation) = entity_location(world, target);
+                        next.push(ExecutionContext { rotation, ..ctx.clone() });
+                    }
+                }
+                Step::Positioned(coords) => match parse_coords(coords, ctx.position, ctx.rotation) {
+                    Ok(position) => next.push(ExecutionContext { position, ..ctx }),
+                    Err(e) => {
+                        send_error(world, entity, &e);
+                        return;
+                    }
+                },
+                Step::Rotated([yaw, pitch]) => {
+                    let rotation = parse_relative_f64(yaw, ctx.rotation.0 as f64)
+                        .zip(parse_relative_f64(pitch, ctx.rotation.1 as f64));
+                    let Some((yaw, pitch)) = rotation else {
+                        send_error(world, entity, &format!("Invalid rotation: {} {}", yaw, pitch));
+                        return;
+                    };
+                    let rotation = (yaw as f32, (pitch as f32).clamp(-90.0, 90.0));
+                    next.push(ExecutionContext { rotation, ..ctx });
+                }
+                Step::IfBlock { negate, pos, block } => {
+                    let position = match parse_coords(pos, ctx.position, ctx.rotation) {
+                        Ok(position) => position,
+                        Err(e) => {
+                            send_error(world, entity, &e);
+                            return;
+                        }
+                    };
+                    let block_pos = BlockPos::new(
+                        position.x.floor() as i32,
+                        position.y.floor() as i32,
+                        position.z.floor() as i32,
+                    );
+                    let state = world_state.get_block(&block_pos);
+                    if (pickaxe_data::block_state_to_name(state) == Some(*block)) != *negate {
+                        next.push(ctx);
+                    }
+                }
+                Step::IfEntity { negate, selector } => {
+                    let found = !selector::resolve(world, Some(ctx.entity), ctx.position, selector).is_empty();
+                    if found != *negate {
+                        next.push(ctx);
+                    }
+                }
+            }
+        }
+        contexts = next;
+    }
+
+    match chain.run {
+        Some(command) => {
+            for ctx in &contexts {
+                run_command(config, world, world_state, ctx, command, scripting, lua_commands);
+            }
+        }
+        None => match contexts.len() {
+            0 => send_error(world, entity, "Test failed"),
+            1 => send_message(world, entity, "Test passed"),
+            n => send_message(world, entity, &format!("Test passed, count: {}", n)),
+        },
+    }
+}
+
+/// /function <id>|#<tag> - run a datapack function, or each function in a
+/// function tag, as the sender.
+fn cmd_function(
+    config: &ServerConfig,
+    world: &mut World,
+    world_state: &mut WorldState,
+    ctx: &ExecutionContext,
+    args: &str,
+    scripting: &ScriptRuntime,
+    lua_commands: &crate::bridge::LuaCommands,
+) {
+    let entity = ctx.entity;
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let parts: Vec<&str> = args.split_whitespace().collect();
+    let [id] = parts.as_slice() else {
+        send_error(world, entity, "Usage: /function <id>|#<tag>");
+        return;
+    };
+    let ids = match id.strip_prefix('#') {
+        Some(tag) => match world_state.functions.tag(tag) {
+            Some(ids) => ids.to_vec(),
+            None => {
+                send_error(world, entity, &format!("Unknown function tag #{}", crate::datapacks::qualify(tag)));
+                return;
+            }
+        },
+        None => vec![crate::datapacks::qualify(id)],
+    };
+    let mut count = 0;
+    for function in &ids {
+        match run_function(config, world, world_state, ctx, function, scripting, lua_commands) {
+            Ok(n) => count += n,
+            Err(e) => {
+                send_error(world, entity, &e);
+                return;
+            }
+        }
+    }
+    match ids.as_slice() {
+        [function] => send_message(world, entity, &format!("Executed {} commands from function {}", count, function)),
+        _ => send_message(world, entity, &format!("Executed {} commands from {} functions", count, ids.len())),
+    }
+}
+
+/// Run a datapack function's commands in order as `ctx`, with the output
+/// suppressed and at most permission level 2 as vanilla does. Stops at
+/// `maxCommandChainLength` commands, counting nested functions. Returns how
+/// many commands ran.
+fn run_function(
+    config: &ServerConfig,
+    world: &mut World,
+    world_state: &mut WorldState,
+    ctx: &ExecutionContext,
+    id: &str,
+    scripting: &ScriptRuntime,
+    lua_commands: &crate::bridge::LuaCommands,
+) -> Result<usize, String> {
+    let commands = world_state.functions.get(id).ok_or_else(|| format!("Unknown function {}", id))?;
+    if world_state.function_depth >= crate::datapacks::MAX_FUNCTION_DEPTH {
+        return Err(format!("Function {} nests more than {} functions deep", id, crate::datapacks::MAX_FUNCTION_DEPTH));
+    }
+    if world_state.function_depth == 0 {
+        world_state.function_commands = 0;
+    }
+    let limit = world_state.game_rules.get_int("maxCommandChainLength").max(0) as usize;
+    let start = world_state.function_commands;
+    // Feedback to a dangling entity goes nowhere
+    let ctx = ExecutionContext {
+        output: hecs::Entity::DANGLING,
+        permission_level: ctx.permission_level.min(2),
+        ..ctx.clone()
+    };
+    world_state.function_depth += 1;
+    for command in commands.iter() {
+        if world_state.function_commands >= limit {
+            warn!("Function {} stopped after maxCommandChainLength ({}) commands", id, limit);
+            break;
+        }
+        world_state.function_commands += 1;
+        run_command(config, world, world_state, &ctx, command, scripting, lua_commands);
+    }
+    world_state.function_depth -= 1;
+    Ok(world_state.function_commands - start)
+}
+
+/// Run every function in a tag as the server, as for `#minecraft:load` at
+/// startup and `#minecraft:tick` each tick.
+fn run_function_tag(
+    config: &ServerConfig,
+    world: &mut World,
+    world_state: &mut WorldState,
+    console: hecs::Entity,
+    tag: &str,
+    scripting: &ScriptRuntime,
+    lua_commands: &crate::bridge::LuaCommands,
+) {
+    let functions = world_state.functions.clone();
+    let Some(ids) = functions.tag(tag) else {
+        return;
+    };
+    let ctx = execution_context(world, console);
+    for id in ids {
+        if let Err(e) = run_function(config, world, world_state, &ctx, id, scripting, lua_commands) {
+            warn!("#{}: {}", tag, e);
+        }
+    }
+}
+
+/// Run a Lua-registered command, or report an unknown command.
+fn run_lua_command(
+    world: &mut World,
+    world_state: &mut WorldState,
+    ctx: &ExecutionContext,
+    name: &str,
+    cmd_name: &str,
+    args: &str,
+    scripting: &ScriptRuntime,
+    lua_commands: &crate::bridge::LuaCommands,
+) {
+    let entity = ctx.entity;
+    // Check Lua-registered commands
+    let handled = if let Ok(cmds) = lua_commands.lock() {
+        if let Some(lua_cmd) = cmds.iter().find(|c| c.name == cmd_name) {
+            if !has_permission(world, entity, lua_cmd.permission_level) {
+                send_message(world, entity, "You don't have permission to use this command.");
+                true
+            } else {
+                let lua = scripting.lua();
+                // Set game context so bridge APIs work inside command handlers
+                lua.set_app_data(pickaxe_scripting::bridge::LuaGameContext {
+                    world_ptr: world as *mut _ as *mut (),
+                    world_state_ptr: world_state as *mut _ as *mut (),
+                });
+                let func: mlua::Result<mlua::Function> =
+                    lua.registry_value(&lua_cmd.handler_key);
+                let result = if let Ok(func) = func {
+                    let call = lua_execution_context(lua, name, ctx)
+                        .and_then(|context| sandbox::call::<()>(lua, &func, (name.to_string(), args.to_string(), context)));
+                    if let Err(e) = call {
+                        warn!("Lua command /{} error: {}", cmd_name, e);
+                        send_message(
+                            world,
+                            entity,
+                            &format!("Command error: {}", e),
+                        );
+                    }
+                    true
+                } else {
+                    false
+                };
+                lua.remove_app_data::<pickaxe_scripting::bridge::LuaGameContext>();
+                result
+            }
+        } else {
+            false
+        }
+    } else {
+        false
+    };
+    if !handled {
+        send_message(
+            world,
+            entity,
+            &format!("Unknown command: /{}", cmd_name),
+        );
+    }
+}
+
+/// Answer a completion request for a Lua command argument that has a
+/// `suggest` callback. Everything else is completed by the client from the
+/// command tree.
+fn handle_command_suggestions(
+    world: &mut World,
+    world_state: &mut WorldState,
+    entity: hecs::Entity,
+    transaction_id: i32,
+    text: &str,
+    scripting: &ScriptRuntime,
+    lua_commands: &crate::bridge::LuaCommands,
+) {
+    let Some((cmd_name, input)) = text.strip_prefix('/').unwrap_or(text).split_once(' ') else {
+        return;
+    };
+    let (suggest, offset) = {
+        let Ok(cmds) = lua_commands.lock() else {
+            return;
+        };
+        let Some(cmd) = cmds.iter().find(|c| c.name == cmd_name) else {
+            return;
+        };
+        if !has_permission(world, entity, cmd.permission_level) {
+            return;
+        }
+        let types: Vec<ArgType> = cmd.args.iter().map(|a| a.ty).collect();
+        let Some((arg, offset)) = command_args::completion_target(&types, input) else {
+            return;
+        };
+        let Some(key) = &cmd.args[arg].suggest else {
+            return;
+        };
+        let Ok(suggest) = scripting.lua().registry_value::<mlua::Function>(key) else {
+            return;
+        };
+        (suggest, offset)
+    };
+    let partial = &input[offset..];
+    let name = world.get::<&Profile>(entity).map(|p| p.0.name.clone()).unwrap_or_default();
+    let result = with_lua_context(world, world_state, scripting, |lua| sandbox::call::<Vec<String>>(lua, &suggest, (name, partial)));
+    let matches = match result {
+        Ok(matches) => matches,
+        Err(e) => {
+            warn!("Lua command /{} suggest error: {}", cmd_name, e);
+            return;
+        }
+    };
+    let lower = partial.to_lowercase();
+    let matches: Vec<String> = matches.into_iter().filter(|m| m.to_lowercase().starts_with(&lower)).collect();
+    // The client counts in UTF-16 units
+    let start = text[..text.len() - partial.len()].encode_utf16().count();
+    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+        let _ = sender.0.send(InternalPacket::CommandSuggestionsResponse {
+            transaction_id,
+            start: start as i32,
+            length: partial.encode_utf16().count() as i32,
+            matches,
+        });
+    }
+}
+
+/// The execution context as passed to Lua command handlers: a table of
+/// `executor`, `x`, `y`, `z`, `yaw`, `pitch` and `dimension`.
+fn lua_execution_context(lua: &mlua::Lua, executor: &str, ctx: &ExecutionContext) -> mlua::Result<mlua::Table> {
+    let table = lua.create_table()?;
+    table.set("executor", executor)?;
+    table.set("x", ctx.position.x)?;
+    table.set("y", ctx.position.y)?;
+    table.set("z", ctx.position.z)?;
+    table.set("yaw", ctx.rotation.0)?;
+    table.set("pitch", ctx.rotation.1)?;
+    table.set("dimension", ctx.dimension.as_str())?;
+    Ok(table)
+}
+
+/// /spawn - teleport to the world spawn.
+fn cmd_spawn(world: &mut World, world_state: &mut WorldState, entity: hecs::Entity) {
+    if world.get::<&Profile>(entity).is_err() {
+        send_message(world, entity, "A player is required to run this command here");
+        return;
+    }
+    safe_teleport(world, world_state, entity, world_spawn(), None);
+    send_message(world, entity, "Teleported to spawn");
+}
+
+/// Current location of a player, for /sethome and /setwarp.
+fn player_location(world: &World, entity: hecs::Entity) -> Option<Location> {
+    let position = world.get::<&Position>(entity).ok()?.0;
+    let rotation = world.get::<&Rotation>(entity).ok()?;
+    Some(Location { position, yaw: rotation.yaw, pitch: rotation.pitch })
+}
+
+/// Parse the optional home name argument, defaulting to "home".
+fn home_name(world: &World, entity: hecs::Entity, args: &str) -> Option<String> {
+    let name = Some(args.trim()).filter(|a| !a.is_empty()).unwrap_or(teleports::DEFAULT_HOME);
+    if !teleports::valid_name(name) {
+        send_message(world, entity, "Home names may only contain letters, digits, _ and -");
+        return None;
+    }
+    Some(name.to_lowercase())
+}
+
+/// /sethome [name]
+fn cmd_sethome(world: &mut World, entity: hecs::Entity, args: &str) {
+    let Some(location) = player_location(world, entity) else {
+        send_message(world, entity, "A player is required to run this command here");
+        return;
+    };
+    let Some(name) = home_name(world, entity, args) else {
+        return;
+    };
+    let msg = match world.get::<&mut Homes>(entity) {
+        Ok(mut homes) => {
+            let max = teleports::max_homes();
+            if !homes.0.contains_key(&name) && homes.0.len() >= max {
+                format!("You can only have {} home(s); remove one with /delhome", max)
+            } else {
+                homes.0.insert(name.clone(), location);
+                format!("Home '{}' set", name)
+            }
+        }
+        Err(_) => return,
+    };
+    send_message(world, entity, &msg);
+}
+
+/// /home [name]
+fn cmd_home(world: &mut World, world_state: &mut WorldState, entity: hecs::Entity, args: &str) {
+    let Some(name) = home_name(world, entity, args) else {
+        return;
+    };
+    let Some(location) = world.get::<&Homes>(entity).ok().and_then(|h| h.0.get(&name).copied()) else {
+        send_message(world, entity, &format!("You have no home named '{}'", name));
+        return;
+    };
+    safe_teleport(world, world_state, entity, location.position, Some((location.yaw, location.pitch)));
+    send_message(world, entity, &format!("Teleported to home '{}'", name));
+}
+
+/// /delhome [name]
+fn cmd_delhome(world: &World, entity: hecs::Entity, args: &str) {
+    let Some(name) = home_name(world, entity, args) else {
+        return;
+    };
+    let removed = world.get::<&mut Homes>(entity).is_ok_and(|mut h| h.0.remove(&name).is_some());
+    let msg = if removed {
+        format!("Home '{}' removed", name)
+    } else {
+        format!("You have no home named '{}'", name)
+    };
+    send_message(world, entity, &msg);
+}
+
+/// /homes - list your homes.
+fn cmd_homes(world: &World, entity: hecs::Entity) {
+    let names: Vec<String> = world.get::<&Homes>(entity).map(|h| h.0.keys().cloned().collect()).unwrap_or_default();
+    if names.is_empty() {
+        send_message(world, entity, "You have no homes. Set one with /sethome [name]");
+    } else {
+        send_message(world, entity, &format!("Homes ({}/{}): {}", names.len(), teleports::max_homes(), names.join(", ")));
+    }
+}
+
+/// /warp [name] - teleport to a warp, or list warps.
+fn cmd_warp(world: &mut World, world_state: &mut WorldState, entity: hecs::Entity, args: &str) {
+    let name = args.trim();
+    if name.is_empty() {
+        let names: Vec<&str> = world_state.warps.warps.keys().map(String::as_str).collect();
+        if names.is_empty() {
+            send_message(world, entity, "There are no warps");
+        } else {
+            send_message(world, entity, &format!("Warps: {}", names.join(", ")));
+        }
+        return;
+    }
+    let Some(location) = world_state.warps.get(name) else {
+        send_message(world, entity, &format!("Unknown warp '{}'", name));
+        return;
+    };
+    if world.get::<&Profile>(entity).is_err() {
+        send_message(world, entity, "A player is required to run this command here");
+        return;
+    }
+    safe_teleport(world, world_state, entity, location.position, Some((location.yaw, location.pitch)));
+    send_message(world, entity, &format!("Warped to {}", name.to_lowercase()));
+}
+
+/// /setwarp <name> - create or move a warp at your location.
+fn cmd_setwarp(world: &World, world_state: &mut WorldState, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let name = args.trim();
+    if !teleports::valid_name(name) {
+        send_message(world, entity, "Usage: /setwarp <name> (letters, digits, _ and -)");
+        return;
+    }
+    let Some(location) = player_location(world, entity) else {
+        send_message(world, entity, "A player is required to run this command here");
+        return;
+    };
+    world_state.warps.set(name, location);
+    send_message(world, entity, &format!("Warp '{}' set", name.to_lowercase()));
+}
+
+/// /delwarp <name>
+fn cmd_delwarp(world: &World, world_state: &mut WorldState, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let name = args.trim();
+    if name.is_empty() {
+        send_message(world, entity, "Usage: /delwarp <name>");
+    } else if world_state.warps.remove(name) {
+        send_message(world, entity, &format!("Warp '{}' removed", name.to_lowercase()));
+    } else {
+        send_message(world, entity, &format!("Unknown warp '{}'", name));
+    }
+}
+
+/// /list - online players.
+fn cmd_list(world: &World, entity: hecs::Entity, config: &ServerConfig) {
+    let mut players: Vec<(String, i32)> = world
+        .query::<(&Profile, &KeepAlive)>()
+        .iter()
+        .map(|(_, (p, ka))| (p.0.name.clone(), ka.latency_ms))
+        .collect();
+    players.sort_by_key(|(n, _)| n.to_lowercase());
+    let names: Vec<String> = players.iter().map(|(n, ping)| format!("{} ({}ms)", n, ping)).collect();
+    send_message(
+        world,
+        entity,
+        &format!(
+            "There are {} of a max of {} players online: {}",
+            players.len(),
+            config.max_players,
+            names.join(", ")
+        ),
+    );
+}
+
+/// /tps - ticks per second over the last 5 seconds, 1 minute and 5 minutes,
+/// tick times and the load level.
+fn cmd_tps(world: &World, world_state: &WorldState, entity: hecs::Entity) {
+    let stats = &world_state.tick_stats;
+    send_message(world, entity, &format!(
+        "TPS (5s, 1m, 5m): {:.1}, {:.1}, {:.1}",
+        stats.tps(), stats.tps_over(1200), stats.tps_over(6000)
+    ));
+    send_message(world, entity, &format!(
+        "MSPT: avg {:.2} ms, max {:.2} ms (budget {:.1} ms)",
+        stats.average_mspt(), stats.max_mspt(), stats.target_mspt()
+    ));
+    send_message(world, entity, &format!("Load: {}", world_state.load.level().name()));
+}
+
+/// /ping [player] - keep-alive round trip time.
+fn cmd_ping(world: &World, entity: hecs::Entity, args: &str) {
+    let target_name = args.trim();
+    let target = if target_name.is_empty() {
+        if world.get::<&KeepAlive>(entity).is_err() {
+            send_message(world, entity, "Usage: /ping <player>");
+            return;
+        }
+        entity
+    } else {
+        match find_online_player(world, target_name) {
+            Some(e) => e,
+            None => {
+                send_message(world, entity, &format!("Player not found: {}", target_name));
+                return;
+            }
+        }
+    };
+    let ping = world.get::<&KeepAlive>(target).map(|ka| ka.latency_ms).unwrap_or(0);
+    if target == entity {
+        send_message(world, entity, &format!("Your ping is {}ms", ping));
+    } else {
+        let name = entity_display_name(world, target);
+        send_message(world, entity, &format!("{}'s ping is {}ms", name, ping));
+    }
+}
+
+/// /stop - save everything and shut down after this tick.
+fn cmd_stop(world: &World, world_state: &mut WorldState, entity: hecs::Entity) {
+    if !has_permission(world, entity, 4) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    send_message(world, entity, "Stopping the server");
+    world_state.stop_requested = true;
+}
+
+/// /save-all [compact] - save players, chunks and level data. "Saved the
+/// game" is reported once the saver task has written everything; `compact`
+/// also squeezes the unused sectors out of the region files afterwards.
+fn cmd_save_all(world: &World, world_state: &mut WorldState, entity: hecs::Entity, args: &str, config: &ServerConfig) {
+    if !has_permission(world, entity, 4) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let compact = match args.trim() {
+        "" => false,
+        "compact" => true,
+        _ => {
+            send_message(world, entity, "Usage: /save-all [compact]");
+            return;
+        }
+    };
+    if compact && !world_state.saving_enabled {
+        // /save-off promises the files stay put, e.g. during a backup
+        send_message(world, entity, "Saving is turned off; use /save-on before compacting");
+        return;
+    }
+    send_message(world, entity, "Saving the game (this may take a moment!)");
+    save_world(world, world_state, config);
+    world_state.flush_dirty_chunks();
+    if compact {
+        let _ = world_state.save_tx.send(SaveOp::Compact(world_state.level_names()));
+    }
+    let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+    let _ = world_state.save_tx.send(SaveOp::Flush(done_tx));
+    world_state.pending_saves.push((done_rx, entity));
+}
+
+/// /save-off, /save-on - pause or resume automatic saving (e.g. for backups).
+fn cmd_save_toggle(world: &World, world_state: &mut WorldState, entity: hecs::Entity, enable: bool) {
+    if !has_permission(world, entity, 4) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let msg = match (enable, world_state.saving_enabled) {
+        (true, true) => "Saving is already turned on",
+        (false, false) => "Saving is already turned off",
+        (true, false) => "Automatic saving is now enabled",
+        (false, true) => "Automatic saving is now disabled",
+    };
+    world_state.saving_enabled = enable;
+    send_message(world, entity, msg);
+}
+
+/// /backup now - back up the world as the `[backup]` schedule does.
+fn cmd_backup(world: &World, world_state: &mut WorldState, entity: hecs::Entity, args: &str, config: &ServerConfig) {
+    if !has_permission(world, entity, 4) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    if args.trim() != "now" {
+        send_message(world, entity, "Usage: /backup now");
+        return;
+    }
+    match start_backup(world, world_state, config, Some(entity)) {
+        Ok(()) => send_message(world, entity, "Backing up the world..."),
+        Err(e) => send_message(world, entity, e),
+    }
+}
+
+/// /schem pos1|pos2 [x y z] | save <name> | load <name> | list - mark a box,
+/// save it as a Sponge schematic or paste one where the sender stands.
+fn cmd_schem(world: &mut World, world_state: &mut WorldState, entity: hecs::Entity, args: &str, config: &ServerConfig) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let dir = &config.schematics.dir;
+    let parts: Vec<&str> = args.split_whitespace().collect();
+    if parts.first() == Some(&"list") {
+        let names = crate::schematics::list(dir);
+        if names.is_empty() {
+            send_message(world, entity, "There are no schematics");
+        } else {
+            send_message(world, entity, &format!("Schematics: {}", names.join(", ")));
+        }
+        return;
+    }
+
+    let Some(here) = world.get::<&Position>(entity).ok().map(|p| p.0) else {
+        send_error(world, entity, "Only players can use /schem");
+        return;
+    };
+    let feet = BlockPos::new(here.x.floor() as i32, here.y.floor() as i32, here.z.floor() as i32);
+    match parts.as_slice() {
+        [corner @ ("pos1" | "pos2"), coords @ ..] if coords.is_empty() || coords.len() == 3 => {
+            let pos = if coords.is_empty() {
+                feet
+            } else {
+                let rotation = world.get::<&Rotation>(entity).map(|r| (r.yaw, r.pitch)).unwrap_or((0.0, 0.0));
+                match parse_coords(coords, here, rotation) {
+                    Ok(p) => BlockPos::new(p.x.floor() as i32, p.y.floor() as i32, p.z.floor() as i32),
+                    Err(e) => {
+                        send_error(world, entity, &e);
+                        return;
+                    }
+                }
+            };
+            if world.get::<&SchematicSelection>(entity).is_err() {
+                let _ = world.insert_one(entity, SchematicSelection::default());
+            }
+            if let Ok(mut selection) = world.get::<&mut SchematicSelection>(entity) {
+                match *corner {
+                    "pos1" => selection.pos1 = Some(pos),
+                    _ => selection.pos2 = Some(pos),
+                }
+            }
+            send_message(world, entity, &format!("Set {} to {} {} {}", corner, pos.x, pos.y, pos.z));
+        }
+        ["save", name] => {
+            if !crate::schematics::valid_name(name) {
+                send_error(world, entity, "Schematic names may only use letters, digits, _ and -");
+                return;
+            }
+            let corners = world.get::<&SchematicSelection>(entity).ok().and_then(|s| s.pos1.zip(s.pos2));
+            let Some((pos1, pos2)) = corners else {
+                send_error(world, entity, "Set both corners with /schem pos1 and /schem pos2 first");
+                return;
+            };
+            let max_y = pickaxe_world::MIN_Y + pickaxe_world::SECTION_COUNT as i32 * 16 - 1;
+            let clamp_y = |p: BlockPos| BlockPos::new(p.x, p.y.clamp(pickaxe_world::MIN_Y, max_y), p.z);
+            let (pos1, pos2) = (clamp_y(pos1), clamp_y(pos2));
+            let size = [pos1.x.abs_diff(pos2.x), pos1.y.abs_diff(pos2.y), pos1.z.abs_diff(pos2.z)].map(|d| d as usize + 1);
+            let volume = size.iter().fold(1usize, |v, d| v.saturating_mul(*d));
+            if volume > config.schematics.max_blocks || size.iter().any(|&d| d > u16::MAX as usize) {
+                send_error(world, entity, &format!("Too many blocks in the selection ({}, at most {})", volume, config.schematics.max_blocks));
+                return;
+            }
+            let schematic = copy_schematic(world_state, pos1, pos2, feet);
+            match crate::schematics::save(dir, name, &schematic) {
+                Ok(_) => send_message(
+                    world,
+                    entity,
+                    &format!("Saved {} ({}x{}x{}, {} block entities)", name, size[0], size[1], size[2], schematic.block_entities.len()),
+                ),
+                Err(e) => send_error(world, entity, &format!("Can't save schematic {}: {}", name, e)),
+            }
+        }
+        ["load" | "paste", name] => {
+            if !crate::schematics::valid_name(name) {
+                send_error(world, entity, "Schematic names may only use letters, digits, _ and -");
+                return;
+            }
+            let (schematic, unknown) = match crate::schematics::load(dir, name) {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    send_error(world, entity, &e);
+                    return;
+                }
+            };
+            let volume = schematic.width as usize * schematic.height as usize * schematic.length as usize;
+            if volume > config.schematics.max_blocks {
+                send_error(world, entity, &format!("Schematic {} has too many blocks ({}, at most {})", name, volume, config.schematics.max_blocks));
+                return;
+            }
+            let placed = paste_schematic(world, world_state, &schematic, feet);
+            let mut message = format!("Pasted {} ({} blocks)", name, placed);
+            if unknown > 0 {
+                message.push_str(&format!("; {} unknown block states became air", unknown));
+            }
+            send_message(world, entity, &message);
+        }
+        _ => send_error(world, entity, "Usage: /schem pos1|pos2 [x y z] | save <name> | load <name> | list"),
+    }
+}
+
+/// Copy the box between two corners. The schematic's offset is its minimum
+/// corner relative to `origin`, so pasting at `origin` puts it back in place.
+fn copy_schematic(world_state: &mut WorldState, a: BlockPos, b: BlockPos, origin: BlockPos) -> Schematic {
+    let min = BlockPos::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+    let max = BlockPos::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+    let mut schematic = Schematic::new(
+        (max.x - min.x + 1) as u16,
+        (max.y - min.y + 1) as u16,
+        (max.z - min.z + 1) as u16,
+    );
+    schematic.offset = [min.x - origin.x, min.y - origin.y, min.z - origin.z];
+    for y in min.y..=max.y {
+        for z in min.z..=max.z {
+            for x in min.x..=max.x {
+                let state = world_state.get_block(&BlockPos::new(x, y, z));
+                schematic.set_block((x - min.x) as usize, (y - min.y) as usize, (z - min.z) as usize, state);
+            }
+        }
+    }
+    for (pos, be) in &world_state.level.block_entities {
+        let inside = (min.x..=max.x).contains(&pos.x) && (min.y..=max.y).contains(&pos.y) && (min.z..=max.z).contains(&pos.z);
+        if !inside {
+            continue;
+        }
+        let mut data = serialize_block_entity(pos, be);
+        let id = data.remove("id").and_then(|id| id.as_str().map(String::from)).unwrap_or_default();
+        for key in ["x", "y", "z"] {
+            data.remove(key);
+        }
+        schematic.block_entities.push(SchematicBlockEntity {
+            pos: [pos.x - min.x, pos.y - min.y, pos.z - min.z],
+            id,
+            data,
+        });
+    }
+    schematic
+}
+
+/// Paste a schematic at `origin` plus its offset, without block updates as
+/// WorldEdit does, and resend the changed chunks to players who can see
+/// them. Block entities the server doesn't model are left out. Returns the
+/// number of blocks placed.
+fn paste_schematic(world: &mut World, world_state: &mut WorldState, schematic: &Schematic, origin: BlockPos) -> usize {
+    let [ox, oy, oz] = schematic.offset;
+    let base = BlockPos::new(origin.x + ox, origin.y + oy, origin.z + oz);
+    let world_y = pickaxe_world::MIN_Y..pickaxe_world::MIN_Y + pickaxe_world::SECTION_COUNT as i32 * 16;
+    let mut chunks = HashSet::new();
+    let mut placed = 0;
+    for ([x, y, z], state) in schematic.blocks() {
+        let pos = BlockPos::new(base.x + x, base.y + y, base.z + z);
+        if !world_y.contains(&pos.y) {
+            continue;
+        }
+        world_state.remove_block_entity(&pos);
+        world_state.set_block_without_updates(&pos, state);
+        chunks.insert(pos.chunk_pos());
+        placed += 1;
+    }
+    for be in &schematic.block_entities {
+        let mut nbt = be.data.clone();
+        nbt.set("id", NbtValue::String(be.id.clone()));
+        nbt.set("x", NbtValue::Int(base.x + be.pos[0]));
+        nbt.set("y", NbtValue::Int(base.y + be.pos[1]));
+        nbt.set("z", NbtValue::Int(base.z + be.pos[2]));
+        if let Some((pos, entity)) = deserialize_block_entity(&nbt) {
+            world_state.set_block_entity(pos, entity);
+        }
+    }
+
+    for (_, (cp, vd, chunk_sender)) in world.query_mut::<(&ChunkPosition, &ViewDistance, &mut ChunkSender)>() {
+        let view = View::new(cp.chunk_x, cp.chunk_z, vd.0);
+        for chunk in chunks.iter().filter(|c| view.contains(c.x, c.z)) {
+            chunk_sender.queue(chunk.x, chunk.z);
+        }
+    }
+    placed
+}
+
+/// /place template <id> [x y z] [rotation] [mirror] - place a vanilla
+/// structure template with its origin at the position.
+fn cmd_place(world: &mut World, world_state: &mut WorldState, entity: hecs::Entity, args: &str, config: &ServerConfig) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let parts: Vec<&str> = args.split_whitespace().collect();
+    let ["template", id, rest @ ..] = parts.as_slice() else {
+        send_error(world, entity, "Usage: /place template <id> [x y z] [rotation] [mirror]");
+        return;
+    };
+    let here = world.get::<&Position>(entity).map(|p| p.0).unwrap_or(Vec3d::new(0.0, 0.0, 0.0));
+    let origin = if (3..=5).contains(&rest.len()) {
+        let rotation = world.get::<&Rotation>(entity).map(|r| (r.yaw, r.pitch)).unwrap_or((0.0, 0.0));
+        match parse_coords(&rest[..3], here, rotation) {
+            Ok(p) => BlockPos::new(p.x.floor() as i32, p.y.floor() as i32, p.z.floor() as i32),
+            Err(e) => {
+                send_error(world, entity, &e);
+                return;
+            }
+        }
+    } else if rest.is_empty() {
+        BlockPos::new(here.x.floor() as i32, here.y.floor() as i32, here.z.floor() as i32)
+    } else {
+        send_error(world, entity, "Usage: /place template <id> [x y z] [rotation] [mirror]");
+        return;
+    };
+    let rotation = match rest.get(3) {
+        Some(name) => match pickaxe_world::Rotation::from_name(name) {
+            Some(rotation) => rotation,
+            None => {
+                send_error(world, entity, &format!("Unknown rotation {} (none, clockwise_90, 180, counterclockwise_90)", name));
+                return;
+            }
+        },
+        None => pickaxe_world::Rotation::None,
+    };
+    let mirror = match rest.get(4) {
+        Some(name) => match Mirror::from_name(name) {
+            Some(mirror) => mirror,
+            None => {
+                send_error(world, entity, &format!("Unknown mirror {} (none, left_right, front_back)", name));
+                return;
+            }
+        },
+        None => Mirror::None,
+    };
+
+    let (template, unknown) = match crate::structures::load(std::path::Path::new(&config.world_dir), &config.structures.data_dir, id) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            send_error(world, entity, &e);
+            return;
+        }
+    };
+    if template.blocks.len() > config.structures.max_blocks {
+        send_error(world, entity, &format!("Template {} has too many blocks ({}, at most {})", id, template.blocks.len(), config.structures.max_blocks));
+        return;
+    }
+    let placed = place_template(world, world_state, &template, origin, Placement { rotation, mirror });
+    let mut message = format!("Placed template {} at {}, {}, {} ({} blocks)", id, origin.x, origin.y, origin.z, placed);
+    if unknown > 0 {
+        message.push_str(&format!("; {} unknown block states became air", unknown));
+    }
+    send_message(world, entity, &message);
+}
+
+/// /structure save <id> <x1 y1 z1> <x2 y2 z2> - save the box between two
+/// corners to the world's generated/ templates, as a structure block would.
+fn cmd_structure(world: &mut World, world_state: &mut WorldState, entity: hecs::Entity, args: &str, config: &ServerConfig) {
+    if !has_permission(world, entity, 2) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let parts: Vec<&str> = args.split_whitespace().collect();
+    let ["save", id, coords @ ..] = parts.as_slice() else {
+        send_error(world, entity, "Usage: /structure save <id> <x1 y1 z1> <x2 y2 z2>");
+        return;
+    };
+    if coords.len() != 6 {
+        send_error(world, entity, "Usage: /structure save <id> <x1 y1 z1> <x2 y2 z2>");
+        return;
+    }
+    if crate::structures::parse_id(id).is_none() {
+        send_error(world, entity, "Template IDs may only use lowercase letters, digits, _, -, . and /");
+        return;
+    }
+    let here = world.get::<&Position>(entity).map(|p| p.0).unwrap_or(Vec3d::new(0.0, 0.0, 0.0));
+    let rotation = world.get::<&Rotation>(entity).map(|r| (r.yaw, r.pitch)).unwrap_or((0.0, 0.0));
+    let max_y = pickaxe_world::MIN_Y + pickaxe_world::SECTION_COUNT as i32 * 16 - 1;
+    let mut corners = Vec::new();
+    for corner in coords.chunks(3) {
+        match parse_coords(corner, here, rotation) {
+            Ok(p) => corners.push(BlockPos::new(p.x.floor() as i32, (p.y.floor() as i32).clamp(pickaxe_world::MIN_Y, max_y), p.z.floor() as i32)),
+            Err(e) => {
+                send_error(world, entity, &e);
+                return;
             }
-            match found {
-                Some(pos) => (pos.x, pos.y, pos.z),
-                None => {
-                    send_message(
-                        world,
-                        entity,
-                        &format!("Player '{}' not found", target_name),
-                    );
-                    return;
+        }
+    }
+    let (a, b) = (corners[0], corners[1]);
+    let size = [a.x.abs_diff(b.x), a.y.abs_diff(b.y), a.z.abs_diff(b.z)].map(|d| d as usize + 1);
+    let volume = size.iter().fold(1usize, |v, d| v.saturating_mul(*d));
+    if volume > config.structures.max_blocks {
+        send_error(world, entity, &format!("Too many blocks in the box ({}, at most {})", volume, config.structures.max_blocks));
+        return;
+    }
+    let template = copy_template(world_state, a, b);
+    match crate::structures::save(std::path::Path::new(&config.world_dir), id, &template) {
+        Ok(_) => send_message(world, entity, &format!("Saved template {} ({}x{}x{})", id, size[0], size[1], size[2])),
+        Err(e) => send_error(world, entity, &format!("Can't save template {}: {}", id, e)),
+    }
+}
+
+/// Copy the box between two corners into a template whose origin is the
+/// minimum corner. Structure voids are left out, so placing the template
+/// keeps whatever is there.
+fn copy_template(world_state: &mut WorldState, a: BlockPos, b: BlockPos) -> StructureTemplate {
+    let min = BlockPos::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+    let max = BlockPos::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+    let void = pickaxe_data::block_name_to_default_state("structure_void");
+    let mut template = StructureTemplate {
+        size: [max.x - min.x + 1, max.y - min.y + 1, max.z - min.z + 1],
+        ..Default::default()
+    };
+    for y in min.y..=max.y {
+        for z in min.z..=max.z {
+            for x in min.x..=max.x {
+                let pos = BlockPos::new(x, y, z);
+                let state = world_state.get_block(&pos);
+                if Some(state) == void {
+                    continue;
                 }
+                let nbt = world_state.level.block_entities.get(&pos).map(|be| {
+                    let mut nbt = serialize_block_entity(&pos, be);
+                    for key in ["x", "y", "z"] {
+                        nbt.remove(key);
+                    }
+                    nbt
+                });
+                template.blocks.push(StructureBlock { pos: [x - min.x, y - min.y, z - min.z], state, nbt });
             }
         }
-        _ => {
-            send_message(world, entity, "Usage: /tp <x> <y> <z> or /tp <player>");
-            return;
-        }
-    };
-
-    if let Ok(mut pos) = world.get::<&mut Position>(entity) {
-        pos.0 = Vec3d::new(x, y, z);
     }
+    template
+}
 
-    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
-        let _ = sender.0.send(InternalPacket::SynchronizePlayerPosition {
-            position: Vec3d::new(x, y, z),
-            yaw: 0.0,
-            pitch: 0.0,
-            flags: 0,
-            teleport_id: 2,
-        });
+/// Place a template with its origin at `origin`, mirrored then rotated
+/// about it, without block updates, and resend the changed chunks to
+/// players who can see them. Returns the number of blocks placed.
+fn place_template(world: &mut World, world_state: &mut WorldState, template: &StructureTemplate, origin: BlockPos, placement: Placement) -> usize {
+    let world_y = pickaxe_world::MIN_Y..pickaxe_world::MIN_Y + pickaxe_world::SECTION_COUNT as i32 * 16;
+    let mut chunks = HashSet::new();
+    let mut placed = 0;
+    for block in &template.blocks {
+        let [x, y, z] = placement.transform_pos(block.pos);
+        let pos = BlockPos::new(origin.x + x, origin.y + y, origin.z + z);
+        if !world_y.contains(&pos.y) {
+            continue;
+        }
+        world_state.remove_block_entity(&pos);
+        world_state.set_block_without_updates(&pos, placement.transform_state(block.state));
+        if let Some(mut nbt) = block.nbt.clone() {
+            nbt.set("x", NbtValue::Int(pos.x));
+            nbt.set("y", NbtValue::Int(pos.y));
+            nbt.set("z", NbtValue::Int(pos.z));
+            if let Some((pos, entity)) = deserialize_block_entity(&nbt) {
+                world_state.set_block_entity(pos, entity);
+            }
+        }
+        chunks.insert(pos.chunk_pos());
+        placed += 1;
     }
 
-    send_message(
-        world,
-        entity,
-        &format!("Teleported to {:.1}, {:.1}, {:.1}", x, y, z),
-    );
+    for (_, (cp, vd, chunk_sender)) in world.query_mut::<(&ChunkPosition, &ViewDistance, &mut ChunkSender)>() {
+        let view = View::new(cp.chunk_x, cp.chunk_z, vd.0);
+        for chunk in chunks.iter().filter(|c| view.contains(c.x, c.z)) {
+            chunk_sender.queue(chunk.x, chunk.z);
+        }
+    }
+    placed
 }
 
-fn cmd_give(world: &mut World, entity: hecs::Entity, args: &str) {
-    if !is_op(world, entity) {
-        send_message(world, entity, "You don't have permission to use this command.");
+/// /msg <targets> <message> (also /tell, /w)
+fn cmd_msg(world: &mut World, world_state: &mut WorldState, entity: hecs::Entity, args: &str, scripting: &ScriptRuntime) {
+    let Some((target_arg, message)) = args.trim().split_once(' ').filter(|(_, m)| !m.trim().is_empty()) else {
+        send_message(world, entity, "Usage: /msg <targets> <message>");
+        return;
+    };
+    let Some(targets) = resolve_targets(world, entity, target_arg) else {
+        return;
+    };
+    let players: Vec<hecs::Entity> =
+        targets.into_iter().filter(|t| world.get::<&ConnectionSender>(*t).is_ok()).collect();
+    if players.is_empty() {
+        send_message(world, entity, "No player was found");
         return;
     }
+    for target in players {
+        send_private_message(world, world_state, entity, target, message.trim(), scripting);
+    }
+}
 
-    let parts: Vec<&str> = args.split_whitespace().collect();
-    if parts.is_empty() {
-        send_message(world, entity, "Usage: /give <item_name> [count]");
+/// /reply <message> (also /r) - message the last private message partner.
+fn cmd_reply(world: &mut World, world_state: &mut WorldState, entity: hecs::Entity, args: &str, scripting: &ScriptRuntime) {
+    let message = args.trim();
+    if message.is_empty() {
+        send_message(world, entity, "Usage: /reply <message>");
         return;
     }
+    let Some(partner) = world.get::<&ReplyTarget>(entity).ok().map(|r| r.0.clone()) else {
+        send_message(world, entity, "You have nobody to reply to");
+        return;
+    };
+    let Some(target) = find_online_player(world, &partner) else {
+        send_message(world, entity, &format!("{} is no longer online", partner));
+        return;
+    };
+    send_private_message(world, world_state, entity, target, message, scripting);
+}
 
-    let item_name = parts[0].strip_prefix("minecraft:").unwrap_or(parts[0]);
-    let count = if parts.len() > 1 {
-        parts[1].parse::<i8>().unwrap_or(1).max(1)
+/// /socialspy - toggle seeing every private message on the server.
+fn cmd_socialspy(world: &mut World, entity: hecs::Entity) {
+    if !has_permission(world, entity, 3) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    if world.remove_one::<SocialSpy>(entity).is_ok() {
+        send_message(world, entity, "Social spy disabled");
     } else {
-        1
-    };
+        let _ = world.insert_one(entity, SocialSpy);
+        send_message(world, entity, "Social spy enabled");
+    }
+}
 
-    let item_id = match pickaxe_data::item_name_to_id(item_name) {
-        Some(id) => id,
-        None => {
-            send_message(world, entity, &format!("Unknown item: {}", item_name));
+/// /audit lookup [user:<name>] [action:<action>] [radius:<blocks>] [time:<age>]
+/// - search the audit log. Results are reported once the audit task replies.
+fn cmd_audit(world: &World, world_state: &mut WorldState, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, 3) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let mut parts = args.split_whitespace();
+    if parts.next() != Some("lookup") {
+        send_message(world, entity, "Usage: /audit lookup [user:<name>] [action:<action>] [radius:<blocks>] [time:<age>]");
+        return;
+    }
+    if !world_state.audit.enabled() {
+        send_error(world, entity, "The audit log is disabled (see [audit] in server.toml)");
+        return;
+    }
+    let (origin, _) = command_source(world, entity);
+    let center = BlockPos::new(origin.x.floor() as i32, origin.y.floor() as i32, origin.z.floor() as i32);
+    let filters: Vec<&str> = parts.collect();
+    let query = match AuditQuery::parse(&filters, center, audit::now()) {
+        Ok(query) => query,
+        Err(e) => {
+            send_error(world, entity, &e);
             return;
         }
     };
+    if let Some(reply_rx) = world_state.audit.lookup(query, AUDIT_LOOKUP_LIMIT) {
+        world_state.pending_lookups.push((reply_rx, entity));
+    }
+}
 
-    let max_stack = pickaxe_data::item_id_to_stack_size(item_id).unwrap_or(64);
-    let slot_index = {
-        let inv = match world.get::<&Inventory>(entity) {
-            Ok(inv) => inv,
-            Err(_) => return,
-        };
-        match inv.find_slot_for_item(item_id, max_stack) {
-            Some(i) => i,
-            None => {
-                send_message(world, entity, "Inventory is full!");
-                return;
-            }
-        }
-    };
+/// Most /audit lookup results shown at once.
+const AUDIT_LOOKUP_LIMIT: usize = 10;
 
-    let (item, state_id) = {
-        let mut inv = match world.get::<&mut Inventory>(entity) {
-            Ok(inv) => inv,
-            Err(_) => return,
-        };
-        let new_item = match &inv.slots[slot_index] {
-            Some(existing) => {
-                let space = (max_stack as i8).saturating_sub(existing.count);
-                let to_add = count.min(space);
-                pickaxe_types::ItemStack::new(item_id, existing.count.saturating_add(to_add))
-            }
-            None => pickaxe_types::ItemStack::new(item_id, count.min(max_stack as i8)),
-        };
-        inv.set_slot(slot_index, Some(new_item.clone()));
-        (new_item, inv.state_id)
+/// /tpa <player> - ask to teleport to another player.
+fn cmd_tpa(world: &World, world_state: &mut WorldState, entity: hecs::Entity, args: &str) {
+    if !has_permission(world, entity, tpa::permission_level()) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return;
+    }
+    let target_name = args.trim();
+    if target_name.is_empty() || target_name.contains(' ') {
+        send_message(world, entity, "Usage: /tpa <player>");
+        return;
+    }
+    let Some(target) = find_online_player(world, target_name) else {
+        send_message(world, entity, "No player was found");
+        return;
     };
-
-    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
-        let _ = sender.0.send(InternalPacket::SetContainerSlot {
-            window_id: 0,
-            state_id,
-            slot: slot_index as i16,
-            item: Some(item),
-        });
+    if target == entity {
+        send_message(world, entity, "You can't send a teleport request to yourself");
+        return;
+    }
+    let from = entity_display_name(world, entity);
+    let to = entity_display_name(world, target);
+    let now = world_state.tick_count;
+    if !world_state.tpa.add(&from, &to, now) {
+        send_message(world, entity, &format!("You already have a pending request to {}", to));
+        return;
+    }
+    let seconds = world_state.tpa.timeout_ticks / 20;
+    send_message(world, entity, &format!("Teleport request sent to {}. It expires in {} seconds.", to, seconds));
+    if let Ok(sender) = world.get::<&ConnectionSender>(target) {
+        let button = |label: &str, color: &str, command: String| {
+            TextComponent::colored(label, color)
+                .bold(true)
+                .click(ClickEvent::RunCommand(command.clone()))
+                .hover_text(TextComponent::plain(command))
+        };
+        let content = TextComponent::colored(format!("{} wants to teleport to you. ", from), "gold")
+            .append(button("[Accept]", "green", format!("/tpaccept {}", from)))
+            .append(TextComponent::plain(" "))
+            .append(button("[Deny]", "red", format!("/tpdeny {}", from)));
+        let _ = sender.0.send(InternalPacket::SystemChatMessage { content, overlay: false });
     }
-
-    let display_name = pickaxe_data::item_id_to_name(item_id).unwrap_or("unknown");
-    send_message(
-        world,
-        entity,
-        &format!("Gave {} x{}", display_name, count),
-    );
 }
 
-fn cmd_kill(world: &mut World, world_state: &mut WorldState, entity: hecs::Entity, entity_id: i32, scripting: &ScriptRuntime) {
-    // Set health to 0 and trigger death
-    if let Ok(mut h) = world.get::<&mut Health>(entity) {
-        h.current = 0.0;
+/// Take the request named by `args` (or the most recent one) sent to `entity`.
+fn take_tpa_request(world: &World, world_state: &mut WorldState, entity: hecs::Entity, args: &str) -> Option<tpa::TpaRequest> {
+    if !has_permission(world, entity, tpa::permission_level()) {
+        send_message(world, entity, "You don't have permission to use this command.");
+        return None;
     }
-    // Send health update
-    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
-        let _ = sender.0.send(InternalPacket::SetHealth {
-            health: 0.0,
-            food: 20,
-            saturation: 5.0,
-        });
+    let name = entity_display_name(world, entity);
+    let from = Some(args.trim()).filter(|a| !a.is_empty());
+    let request = world_state.tpa.take(&name, from);
+    if request.is_none() {
+        let msg = match from {
+            Some(from) => format!("You have no pending teleport request from {}", from),
+            None => "You have no pending teleport requests".to_string(),
+        };
+        send_message(world, entity, &msg);
     }
-    handle_player_death(world, world_state, entity, entity_id, "kill", scripting);
+    request
 }
 
-fn cmd_say(world: &World, message: &str, sender_name: &str) {
-    if message.is_empty() {
+/// /tpaccept [player] - teleport the requester to you.
+fn cmd_tpaccept(world: &mut World, world_state: &mut WorldState, entity: hecs::Entity, args: &str) {
+    let Some(request) = take_tpa_request(world, world_state, entity, args) else {
+        return;
+    };
+    let Some(requester) = find_online_player(world, &request.from) else {
+        send_message(world, entity, &format!("{} is no longer online", request.from));
         return;
+    };
+    let Some(destination) = world.get::<&Position>(entity).ok().map(|p| p.0) else {
+        return;
+    };
+    teleport_entity(world, world_state, requester, destination, None);
+    send_message(world, entity, &format!("Accepted teleport request from {}", request.from));
+    send_message(world, requester, &format!("Teleporting to {}", request.to));
+}
+
+/// /tpdeny [player] - refuse a teleport request.
+fn cmd_tpdeny(world: &World, world_state: &mut WorldState, entity: hecs::Entity, args: &str) {
+    let Some(request) = take_tpa_request(world, world_state, entity, args) else {
+        return;
+    };
+    send_message(world, entity, &format!("Denied teleport request from {}", request.from));
+    if let Some(requester) = find_online_player(world, &request.from) {
+        send_message(world, requester, &format!("{} denied your teleport request", request.to));
     }
-    let chat_text = format!("[{}] {}", sender_name, message);
-    broadcast_to_all(
-        world,
-        &InternalPacket::SystemChatMessage {
-            content: TextComponent::plain(&chat_text),
-            overlay: false,
-        },
+}
+
+/// Deliver a private message, firing the cancellable `player_whisper` event
+/// and copying it to social spies. Both players' reply targets are updated.
+fn send_private_message(
+    world: &mut World,
+    world_state: &mut WorldState,
+    from: hecs::Entity,
+    to: hecs::Entity,
+    message: &str,
+    scripting: &ScriptRuntime,
+) {
+    let sender_name = entity_display_name(world, from);
+    let receiver_name = entity_display_name(world, to);
+    let cancelled = scripting.fire_event_in_context(
+        "player_whisper",
+        &[("name", &sender_name), ("target", &receiver_name), ("message", message)],
+        world as *mut _ as *mut (),
+        world_state as *mut _ as *mut (),
     );
+    if cancelled {
+        return;
+    }
+    info!("[{} -> {}] {}", sender_name, receiver_name, message);
+
+    let formats = &world_state.messaging;
+    let packet = |format: &str| InternalPacket::SystemChatMessage {
+        content: crate::messaging::format_message(format, &sender_name, &receiver_name, message),
+        overlay: false,
+    };
+    if let Ok(sender) = world.get::<&ConnectionSender>(to) {
+        let _ = sender.0.send(packet(&formats.incoming));
+    }
+    if let Ok(sender) = world.get::<&ConnectionSender>(from) {
+        let _ = sender.0.send(packet(&formats.outgoing));
+    }
+    let spy_packet = packet(&formats.spy);
+    for (spy, (sender, _)) in world.query::<(&ConnectionSender, &SocialSpy)>().iter() {
+        if spy != from && spy != to {
+            let _ = sender.0.send(spy_packet.clone());
+        }
+    }
+
+    let _ = world.insert_one(to, ReplyTarget(sender_name));
+    let _ = world.insert_one(from, ReplyTarget(receiver_name));
 }
 
 fn cmd_help(world: &World, entity: hecs::Entity, lua_commands: &crate::bridge::LuaCommands) {
     let help_text = [
         "=== Pickaxe Server Commands ===",
         "/gamemode <mode> - Change game mode (survival/creative/adventure/spectator)",
-        "/tp <x> <y> <z> - Teleport to coordinates",
+        "/tp <x> <y> <z> [yaw pitch] - Teleport to coordinates (~ relative, ^ local)",
         "/tp <player> - Teleport to player",
-        "/give <item> [count] - Give item to yourself",
-        "/kill - Respawn at spawn point",
+        "/execute as|at|positioned|rotated|if|unless ... run <command> - Run a command in another context",
+        "/function <id>|#<tag> - Run a datapack function",
+        "/give [targets] <item>[components] [count] - Give items",
+        "/clear [targets] [item] [maxCount] - Remove items from inventories",
+        "/kill [targets] - Kill yourself or the selected entities",
+        "/damage <targets> <amount> [type] [by <entity>] - Deal damage",
         "/say <message> - Broadcast a message",
+        "/tellraw <targets> <json> - Send a JSON text component",
+        "/msg <targets> <message> - Send a private message (aliases: /tell, /w)",
+        "/reply <message> - Reply to your last private message (alias: /r)",
+        "/socialspy - Toggle seeing other players' private messages",
+        "/audit lookup [user:<name>] [action:<action>] [radius:<blocks>] [time:<age>] - Search the audit log",
+        "/tpa <player> - Ask to teleport to a player",
+        "/tpaccept [player] - Accept a teleport request",
+        "/tpdeny [player] - Deny a teleport request",
+        "/list - List online players and their ping",
+        "/ping [player] - Show connection latency",
+        "/tps - Ticks per second, tick times and server load",
+        "/spawn - Teleport to the world spawn",
+        "/sethome [name], /home [name], /delhome [name], /homes - Personal homes",
+        "/warp [name] - Teleport to a warp (no name lists them)",
+        "/setwarp <name>, /delwarp <name> - Manage warps",
+        "/save-all [compact] - Save the world and player data",
+        "/save-off, /save-on - Pause or resume automatic saving",
+        "/backup now - Back up the world",
+        "/schem pos1|pos2 [x y z], /schem save|load <name>, /schem list - Copy and paste .schem schematics",
+        "/place template <id> [x y z] [rotation] [mirror] - Place a structure template",
+        "/structure save <id> <x1 y1 z1> <x2 y2 z2> - Save a box as a structure template",
+        "/stop - Save and stop the server",
         "/time set <day|night|noon|midnight|value> - Set time of day",
         "/time add <value> - Add to time of day",
         "/time query [daytime|gametime|day] - Query current time",
-        "/effect give <effect> [duration] [amplifier] - Apply status effect",
-        "/effect clear [effect] - Remove status effects",
+        "/effect give [targets] <effect> [seconds|infinite] [amplifier] [hideParticles] - Apply status effect",
+        "/effect clear [targets] [effect] - Remove status effects",
         "/potion <player> <potion_name> - Give a potion to a player",
-        "/enchant <enchantment> [level] - Enchant held item",
+        "/enchant [targets] <enchantment> [level] - Enchant held item",
+        "/invsee <player> - List a player's inventory (online or offline)",
+        "/playerdata <player> [pos|xp|clear ...] - Inspect or edit offline player data",
+        "/advancement grant|revoke <targets> everything|only <advancement> [criterion] - Edit advancement progress",
+        "/chunkinfo [chunk_x chunk_z] - Debug info for the current (or given) chunk",
+        "/worldstats - Loaded chunks, entity counts and tick timings",
+        "/world list | tp <name> [targets] - List worlds or move players to one",
+        "/locate poi <type> - Find the nearest bed, workstation, portal or lightning rod",
+        "/tick query|rate <n>|freeze|unfreeze|step [n]|sprint <n> - Change how fast the world ticks",
+        "/mods [timings [reset]] - Loaded mods, and time their event handlers take",
+        "/debug net [player] - Packets and bytes sent and received",
+        "/debug save - How far world saving is behind",
+        "/debug timings <start|stop|report|dump> - Time spent in each tick system",
         "/help - Show this help",
     ];
     for line in &help_text {
@@ -12041,7 +20778,7 @@ fn cmd_time(world: &World, entity: hecs::Entity, args: &str, world_state: &mut W
 
     match parts[0] {
         "set" => {
-            if !is_op(world, entity) {
+            if !has_permission(world, entity, 2) {
                 send_message(world, entity, "You don't have permission to use this command.");
                 return;
             }
@@ -12070,7 +20807,7 @@ fn cmd_time(world: &World, entity: hecs::Entity, args: &str, world_state: &mut W
             send_message(world, entity, &format!("Set time to {}", time));
         }
         "add" => {
-            if !is_op(world, entity) {
+            if !has_permission(world, entity, 2) {
                 send_message(world, entity, "You don't have permission to use this command.");
                 return;
             }
@@ -12110,187 +20847,222 @@ fn cmd_time(world: &World, entity: hecs::Entity, args: &str, world_state: &mut W
     }
 }
 
-/// /effect give <effect> [duration_seconds] [amplifier] — apply a status effect
-/// /effect clear [effect] — remove one or all effects
+/// /effect give [targets] <effect> [seconds|infinite] [amplifier] [hideParticles] — apply a status effect
+/// /effect clear [targets] [effect] — remove one or all effects
+/// As with /give, `give` affects the sender when the targets are left out.
 fn cmd_effect(world: &mut World, entity: hecs::Entity, args: &str) {
-    if !is_op(world, entity) {
+    if !has_permission(world, entity, 2) {
         send_message(world, entity, "You don't have permission to use this command.");
         return;
     }
-    let parts: Vec<&str> = args.split_whitespace().collect();
+    let parts = split_command_args(args);
+    match parts.first().copied() {
+        Some("give") => cmd_effect_give(world, entity, &parts[1..]),
+        Some("clear") => cmd_effect_clear(world, entity, &parts[1..]),
+        _ => send_error(world, entity, "Usage: /effect <give|clear> ..."),
+    }
+}
+
+fn cmd_effect_give(world: &mut World, entity: hecs::Entity, parts: &[&str]) {
     if parts.is_empty() {
-        send_message(world, entity, "Usage: /effect <give|clear> ...");
+        send_error(world, entity, "Usage: /effect give <targets> <effect> [seconds|infinite] [amplifier] [hideParticles]");
         return;
     }
+    let (targets, rest) = if parts.len() >= 2 && parts[1].parse::<i32>().is_err() && parts[1] != "infinite" {
+        match resolve_targets(world, entity, parts[0]) {
+            Some(t) => (t, &parts[1..]),
+            None => return,
+        }
+    } else {
+        (vec![entity], parts)
+    };
+
+    let effect_name = rest[0].strip_prefix("minecraft:").unwrap_or(rest[0]);
+    let Some(effect_id) = pickaxe_data::effect_name_to_id(effect_name) else {
+        send_error(world, entity, &format!("Unknown effect: {}", rest[0]));
+        return;
+    };
+    let duration = match rest.get(1).copied() {
+        None => 30 * 20,
+        Some("infinite") => -1,
+        Some(raw) => match parse_int_arg(raw, 1, 1_000_000) {
+            Ok(seconds) => seconds * 20,
+            Err(e) => {
+                send_error(world, entity, &e);
+                return;
+            }
+        },
+    };
+    let amplifier = match rest.get(2).map(|raw| parse_int_arg(raw, 0, 255)) {
+        None => 0,
+        Some(Ok(amplifier)) => amplifier,
+        Some(Err(e)) => {
+            send_error(world, entity, &e);
+            return;
+        }
+    };
+    let hide_particles = match rest.get(3).copied() {
+        None | Some("false") => false,
+        Some("true") => true,
+        Some(raw) => {
+            send_error(world, entity, &format!("Invalid boolean '{}', expected true or false", raw));
+            return;
+        }
+    };
 
-    let eid = world.get::<&EntityId>(entity).map(|e| e.0).unwrap_or(0);
+    let applied: Vec<hecs::Entity> = targets
+        .into_iter()
+        .filter(|&target| apply_effect(world, target, effect_id, amplifier, duration, !hide_particles))
+        .collect();
+    match applied.as_slice() {
+        [] => send_error(world, entity, "Unable to apply this effect (target is either immune to effects, or has something stronger)"),
+        [only] => {
+            let name = entity_display_name(world, *only);
+            send_message(world, entity, &format!("Applied effect {} to {}", effect_name, name));
+        }
+        _ => send_message(world, entity, &format!("Applied effect {} to {} targets", effect_name, applied.len())),
+    }
+}
 
-    match parts[0] {
-        "give" => {
-            if parts.len() < 2 {
-                send_message(world, entity, "Usage: /effect give <effect> [duration_seconds] [amplifier]");
+fn cmd_effect_clear(world: &mut World, entity: hecs::Entity, parts: &[&str]) {
+    let targets = match parts.first() {
+        Some(arg) => match resolve_targets(world, entity, arg) {
+            Some(t) => t,
+            None => return,
+        },
+        None => vec![entity],
+    };
+    let effect = match parts.get(1) {
+        None => None,
+        Some(&raw) => match pickaxe_data::effect_name_to_id(raw.strip_prefix("minecraft:").unwrap_or(raw)) {
+            Some(id) => Some((id, raw)),
+            None => {
+                send_error(world, entity, &format!("Unknown effect: {}", raw));
                 return;
             }
-            let effect_name = parts[1];
-            let effect_id = match pickaxe_data::effect_name_to_id(effect_name) {
-                Some(id) => id,
-                None => {
-                    send_message(world, entity, &format!("Unknown effect: {}", effect_name));
-                    return;
-                }
-            };
-            let duration_secs: i32 = if parts.len() > 2 {
-                parts[2].parse().unwrap_or(30)
-            } else {
-                30
-            };
-            let duration_ticks = if duration_secs < 0 { -1 } else { duration_secs * 20 };
-            let amplifier: i32 = if parts.len() > 3 {
-                parts[3].parse::<i32>().unwrap_or(0).clamp(0, 255)
-            } else {
-                0
-            };
+        },
+    };
 
-            let inst = EffectInstance {
-                effect_id,
-                amplifier,
-                duration: duration_ticks,
-                ambient: false,
-                show_particles: true,
-                show_icon: true,
+    let mut cleared = Vec::new();
+    for &target in &targets {
+        let removed: Vec<i32> = {
+            let Ok(mut effects) = world.get::<&mut ActiveEffects>(target) else {
+                continue;
             };
-            let flags: u8 = 0x02 | 0x04; // visible + show_icon
-
-            // Handle instant effects
-            match effect_id {
-                5 => { // instant_health
-                    let heal = 4.0 * (1 << amplifier.min(30)) as f32;
-                    let max = world.get::<&Health>(entity).map(|h| h.max).unwrap_or(20.0);
-                    if let Ok(mut h) = world.get::<&mut Health>(entity) {
-                        h.current = (h.current + heal).min(max);
-                    }
-                    let (health, food, sat) = {
-                        let h = world.get::<&Health>(entity).map(|h| h.current).unwrap_or(20.0);
-                        let (f, s) = world.get::<&FoodData>(entity).map(|f| (f.food_level, f.saturation)).unwrap_or((20, 5.0));
-                        (h, f, s)
-                    };
-                    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
-                        let _ = sender.0.send(InternalPacket::SetHealth { health, food, saturation: sat });
-                    }
-                    send_message(world, entity, &format!("Applied Instant Health (level {})", amplifier + 1));
-                    return;
-                }
-                6 => { // instant_damage
-                    let damage = 6.0 * (1 << amplifier.min(30)) as f32;
-                    if let Ok(mut h) = world.get::<&mut Health>(entity) {
-                        h.current = (h.current - damage).max(0.0);
-                    }
-                    let (health, food, sat) = {
-                        let h = world.get::<&Health>(entity).map(|h| h.current).unwrap_or(0.0);
-                        let (f, s) = world.get::<&FoodData>(entity).map(|f| (f.food_level, f.saturation)).unwrap_or((20, 5.0));
-                        (h, f, s)
-                    };
-                    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
-                        let _ = sender.0.send(InternalPacket::SetHealth { health, food, saturation: sat });
-                    }
-                    send_message(world, entity, &format!("Applied Instant Damage (level {})", amplifier + 1));
-                    return;
-                }
-                22 => { // saturation
-                    if let Ok(mut food) = world.get::<&mut FoodData>(entity) {
-                        food.food_level = (food.food_level + amplifier + 1).min(20);
-                        food.saturation = (food.saturation + (amplifier + 1) as f32).min(food.food_level as f32);
-                    }
-                    let (health, food, sat) = {
-                        let h = world.get::<&Health>(entity).map(|h| h.current).unwrap_or(20.0);
-                        let (f, s) = world.get::<&FoodData>(entity).map(|f| (f.food_level, f.saturation)).unwrap_or((20, 5.0));
-                        (h, f, s)
-                    };
-                    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
-                        let _ = sender.0.send(InternalPacket::SetHealth { health, food, saturation: sat });
-                    }
-                    send_message(world, entity, &format!("Applied Saturation (level {})", amplifier + 1));
-                    return;
-                }
-                _ => {}
+            match effect {
+                Some((id, _)) => effects.effects.remove(&id).map(|_| vec![id]).unwrap_or_default(),
+                None => effects.effects.drain().map(|(id, _)| id).collect(),
+            }
+        };
+        if removed.is_empty() {
+            continue;
+        }
+        let eid = world.get::<&EntityId>(target).map(|e| e.0).unwrap_or(0);
+        if let Ok(sender) = world.get::<&ConnectionSender>(target) {
+            for effect_id in removed {
+                let _ = sender.0.send(InternalPacket::RemoveMobEffect { entity_id: eid, effect_id });
             }
+        }
+        cleared.push(target);
+    }
+
+    let what = match effect {
+        Some((_, name)) => format!("effect {}", name.strip_prefix("minecraft:").unwrap_or(name)),
+        None => "every effect".to_string(),
+    };
+    match cleared.as_slice() {
+        [] if effect.is_some() => send_error(world, entity, "Target doesn't have the requested effect"),
+        [] => send_error(world, entity, "Target has no effects to remove"),
+        [only] => {
+            let name = entity_display_name(world, *only);
+            send_message(world, entity, &format!("Removed {} from {}", what, name));
+        }
+        _ => send_message(world, entity, &format!("Removed {} from {} targets", what, cleared.len())),
+    }
+}
+
+/// Apply a status effect as /effect does; instant effects take hold at once.
+/// Returns false if the entity can't have effects or already has a stronger
+/// (or equal and no shorter) instance.
+fn apply_effect(
+    world: &mut World,
+    target: hecs::Entity,
+    effect_id: i32,
+    amplifier: i32,
+    duration: i32,
+    show_particles: bool,
+) -> bool {
+    let stronger = match world.get::<&ActiveEffects>(target) {
+        Ok(effects) => effects.effects.get(&effect_id).is_some_and(|current| {
+            current.amplifier > amplifier
+                || (current.amplifier == amplifier
+                    && (current.duration < 0 || (duration >= 0 && current.duration >= duration)))
+        }),
+        Err(_) => return false,
+    };
 
-            if let Ok(mut effects) = world.get::<&mut ActiveEffects>(entity) {
-                effects.effects.insert(effect_id, inst);
+    match effect_id {
+        5 => {
+            // instant_health
+            let heal = 4.0 * (1 << amplifier.min(30)) as f32;
+            if let Ok(mut h) = world.get::<&mut Health>(target) {
+                h.current = (h.current + heal).min(h.max);
             }
-            if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+        }
+        6 => {
+            // instant_damage
+            let damage = 6.0 * (1 << amplifier.min(30)) as f32;
+            if let Ok(mut h) = world.get::<&mut Health>(target) {
+                h.current = (h.current - damage).max(0.0);
+            }
+        }
+        22 => {
+            // saturation
+            if let Ok(mut food) = world.get::<&mut FoodData>(target) {
+                food.food_level = (food.food_level + amplifier + 1).min(20);
+                food.saturation = (food.saturation + (amplifier + 1) as f32).min(food.food_level as f32);
+            }
+        }
+        _ => {
+            if stronger {
+                return false;
+            }
+            if let Ok(mut effects) = world.get::<&mut ActiveEffects>(target) {
+                effects.effects.insert(effect_id, EffectInstance {
+                    effect_id,
+                    amplifier,
+                    duration,
+                    ambient: false,
+                    show_particles,
+                    show_icon: true,
+                });
+            }
+            let eid = world.get::<&EntityId>(target).map(|e| e.0).unwrap_or(0);
+            let flags: u8 = if show_particles { 0x02 | 0x04 } else { 0x04 }; // particles + show_icon
+            if let Ok(sender) = world.get::<&ConnectionSender>(target) {
                 let _ = sender.0.send(InternalPacket::UpdateMobEffect {
                     entity_id: eid,
                     effect_id,
                     amplifier,
-                    duration: duration_ticks,
+                    duration,
                     flags,
                 });
             }
-            let dur_str = if duration_ticks < 0 { "infinite".to_string() } else { format!("{}s", duration_secs) };
-            send_message(world, entity, &format!("Applied {} (level {}) for {}", effect_name, amplifier + 1, dur_str));
-        }
-        "clear" => {
-            if parts.len() > 1 {
-                // Clear specific effect
-                let effect_name = parts[1];
-                let effect_id = match pickaxe_data::effect_name_to_id(effect_name) {
-                    Some(id) => id,
-                    None => {
-                        send_message(world, entity, &format!("Unknown effect: {}", effect_name));
-                        return;
-                    }
-                };
-                let removed = if let Ok(mut effects) = world.get::<&mut ActiveEffects>(entity) {
-                    effects.effects.remove(&effect_id).is_some()
-                } else {
-                    false
-                };
-                if removed {
-                    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
-                        let _ = sender.0.send(InternalPacket::RemoveMobEffect {
-                            entity_id: eid,
-                            effect_id,
-                        });
-                    }
-                    send_message(world, entity, &format!("Removed {}", effect_name));
-                } else {
-                    send_message(world, entity, &format!("You don't have {}", effect_name));
-                }
-            } else {
-                // Clear all effects
-                let effect_ids: Vec<i32> = if let Ok(effects) = world.get::<&ActiveEffects>(entity) {
-                    effects.effects.keys().copied().collect()
-                } else {
-                    Vec::new()
-                };
-                if effect_ids.is_empty() {
-                    send_message(world, entity, "No active effects to clear.");
-                    return;
-                }
-                if let Ok(mut effects) = world.get::<&mut ActiveEffects>(entity) {
-                    effects.effects.clear();
-                }
-                if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
-                    for eff_id in &effect_ids {
-                        let _ = sender.0.send(InternalPacket::RemoveMobEffect {
-                            entity_id: eid,
-                            effect_id: *eff_id,
-                        });
-                    }
-                }
-                send_message(world, entity, &format!("Cleared {} effects", effect_ids.len()));
-            }
-        }
-        _ => {
-            send_message(world, entity, "Usage: /effect <give|clear> ...");
+            return true;
         }
     }
+
+    let health = world.get::<&Health>(target).map(|h| h.current).unwrap_or(20.0);
+    let (food, saturation) = world.get::<&FoodData>(target).map(|f| (f.food_level, f.saturation)).unwrap_or((20, 5.0));
+    if let Ok(sender) = world.get::<&ConnectionSender>(target) {
+        let _ = sender.0.send(InternalPacket::SetHealth { health, food, saturation });
+    }
+    true
 }
 
 /// /potion <player> <potion_name> — give a potion to a player
 fn cmd_potion(world: &mut World, entity: hecs::Entity, args: &str) {
-    if !is_op(world, entity) {
+    if !has_permission(world, entity, 2) {
         send_message(world, entity, "You don't have permission to use this command.");
         return;
     }
@@ -12338,13 +21110,7 @@ fn cmd_potion(world: &mut World, entity: hecs::Entity, args: &str) {
     };
 
     // Give the potion item (using damage field to store potion type index)
-    let item = ItemStack {
-        item_id: potion_id,
-        count: 1,
-        damage: potion_index,
-        max_damage: 0,
-        enchantments: Vec::new(),
-    };
+    let item = ItemStack { damage: potion_index, ..ItemStack::new(potion_id, 1) };
     let slot_update = {
         let mut inv = match world.get::<&mut Inventory>(target) {
             Ok(inv) => inv,
@@ -12389,77 +21155,167 @@ fn cmd_potion(world: &mut World, entity: hecs::Entity, args: &str) {
     }
 }
 
+/// /enchant [targets] <enchantment> [level] - enchant the item in each
+/// target's main hand. The targets may be left out to enchant the sender's.
 fn cmd_enchant(world: &mut World, entity: hecs::Entity, args: &str) {
-    if !is_op(world, entity) {
+    if !has_permission(world, entity, 2) {
         send_message(world, entity, "You don't have permission to use this command.");
         return;
     }
 
-    let parts: Vec<&str> = args.split_whitespace().collect();
+    let parts = split_command_args(args);
     if parts.is_empty() {
-        send_message(world, entity, "Usage: /enchant <enchantment> [level]");
+        send_error(world, entity, "Usage: /enchant [targets] <enchantment> [level]");
         return;
     }
-
-    let ench_name = parts[0].strip_prefix("minecraft:").unwrap_or(parts[0]);
-    let level = if parts.len() > 1 {
-        parts[1].parse::<i32>().unwrap_or(1).max(1)
+    let (targets, rest) = if parts.len() >= 2 && parts[1].parse::<i32>().is_err() {
+        match resolve_targets(world, entity, parts[0]) {
+            Some(t) => (t, &parts[1..]),
+            None => return,
+        }
     } else {
-        1
+        (vec![entity], &parts[..])
     };
 
-    let ench_id = match pickaxe_data::enchantment_name_to_id(ench_name) {
-        Some(id) => id,
-        None => {
-            send_message(world, entity, &format!("Unknown enchantment: {}", ench_name));
+    let ench_name = rest[0].strip_prefix("minecraft:").unwrap_or(rest[0]);
+    let Some(ench_id) = pickaxe_data::enchantment_name_to_id(ench_name) else {
+        send_error(world, entity, &format!("Unknown enchantment: {}", rest[0]));
+        return;
+    };
+    let level = match rest.get(1).map(|raw| parse_int_arg(raw, 1, i32::MAX)) {
+        None => 1,
+        Some(Ok(level)) => level,
+        Some(Err(e)) => {
+            send_error(world, entity, &e);
             return;
         }
     };
+    let max_level = pickaxe_data::enchantment_max_level(ench_id);
+    if level > max_level {
+        send_error(
+            world,
+            entity,
+            &format!("{} is higher than the maximum level of {} supported by that enchantment", level, max_level),
+        );
+        return;
+    }
 
-    let held_slot = world.get::<&HeldSlot>(entity).map(|h| h.0).unwrap_or(0);
-    let slot_idx = 36 + held_slot as usize;
-
-    let slot_update = {
-        let mut inv = match world.get::<&mut Inventory>(entity) {
-            Ok(inv) => inv,
-            Err(_) => {
-                send_message(world, entity, "No inventory found.");
-                return;
+    let mut enchanted = Vec::new();
+    let mut last_error = None;
+    for &target in &targets {
+        let held_slot = world.get::<&HeldSlot>(target).map(|h| h.0).unwrap_or(0);
+        let slot = 36 + held_slot as usize;
+        let result = {
+            let Ok(mut inv) = world.get::<&mut Inventory>(target) else {
+                last_error = Some(format!("{} is not a valid entity for this command", entity_display_name(world, target)));
+                continue;
+            };
+            match inv.slots[slot].clone() {
+                None => Err(format!("{} is not holding any item", entity_display_name(world, target))),
+                Some(mut item) => {
+                    let item_name = pickaxe_data::item_id_to_name(item.item_id).unwrap_or("unknown");
+                    let conflicts = item
+                        .enchantments
+                        .iter()
+                        .any(|(id, _)| pickaxe_data::enchantments_incompatible(*id, ench_id));
+                    if conflicts || !pickaxe_data::enchantment_supports_item(ench_id, item_name) {
+                        Err(format!("{} cannot support that enchantment", item_name))
+                    } else {
+                        match item.enchantments.iter_mut().find(|(id, _)| *id == ench_id) {
+                            Some(entry) => entry.1 = level,
+                            None => item.enchantments.push((ench_id, level)),
+                        }
+                        inv.set_slot(slot, Some(item.clone()));
+                        Ok((item, inv.state_id))
+                    }
+                }
             }
         };
-        if let Some(ref mut item) = inv.slots[slot_idx] {
-            // Add or update enchantment
-            if let Some(entry) = item.enchantments.iter_mut().find(|(id, _)| *id == ench_id) {
-                entry.1 = level;
-            } else {
-                item.enchantments.push((ench_id, level));
+        match result {
+            Ok((item, state_id)) => {
+                if let Ok(sender) = world.get::<&ConnectionSender>(target) {
+                    let _ = sender.0.send(InternalPacket::SetContainerSlot {
+                        window_id: 0,
+                        state_id,
+                        slot: slot as i16,
+                        item: Some(item),
+                    });
+                }
+                enchanted.push(target);
             }
-            inv.state_id = inv.state_id.wrapping_add(1);
-            Some((slot_idx, inv.slots[slot_idx].clone(), inv.state_id))
-        } else {
-            send_message(world, entity, "You must be holding an item to enchant.");
-            None
+            Err(e) => last_error = Some(e),
         }
-    };
+    }
 
-    if let Some((slot, slot_item, state_id)) = slot_update {
-        if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
-            let _ = sender.0.send(InternalPacket::SetContainerSlot {
-                window_id: 0,
-                state_id,
-                slot: slot as i16,
-                item: slot_item,
-            });
+    let applied = format!("{} {}", ench_name, level);
+    match enchanted.as_slice() {
+        [] => {
+            let msg = match last_error {
+                Some(e) if targets.len() == 1 => e,
+                _ => "Nothing changed. Targets either have no item in their hands or the enchantment could not be applied".to_string(),
+            };
+            send_error(world, entity, &msg);
         }
-        send_message(world, entity, &format!("Enchanted held item with {} {}", ench_name, level));
+        [only] => {
+            let name = entity_display_name(world, *only);
+            send_message(world, entity, &format!("Applied enchantment {} to {}'s item", applied, name));
+        }
+        _ => send_message(world, entity, &format!("Applied enchantment {} to {} entities", applied, enchanted.len())),
     }
 }
 
-fn handle_chunk_updates(
+/// Apply options the player changed mid-game. A new render distance queues
+/// the chunks that came into view, or unloads the ones that left it.
+fn handle_client_information(
+    config: &ServerConfig,
     world: &mut World,
-    world_state: &mut WorldState,
     entity: hecs::Entity,
+    locale: String,
+    requested: i32,
 ) {
+    let (view_distance, simulation_distance) = negotiate_distances(config, requested);
+    let _ = world.insert(
+        entity,
+        (
+            ClientSettings {
+                locale,
+                view_distance: requested,
+            },
+            SimulationDistance(simulation_distance),
+        ),
+    );
+
+    let old_vd = match world.get::<&mut ViewDistance>(entity) {
+        Ok(mut vd) => std::mem::replace(&mut vd.0, view_distance),
+        Err(_) => return,
+    };
+    if old_vd == view_distance {
+        return;
+    }
+    let (cx, cz) = match world.get::<&ChunkPosition>(entity) {
+        Ok(cp) => (cp.chunk_x, cp.chunk_z),
+        Err(_) => return,
+    };
+    let Ok(sender) = world.get::<&ConnectionSender>(entity) else {
+        return;
+    };
+    let sender = &sender.0;
+
+    let Ok(mut chunk_sender) = world.get::<&mut ChunkSender>(entity) else {
+        return;
+    };
+
+    let _ = sender.send(InternalPacket::SetSimulationDistance { simulation_distance });
+    let _ = sender.send(InternalPacket::SetCenterChunk { chunk_x: cx, chunk_z: cz });
+    for (chunk_x, chunk_z) in chunk_sender.move_view(View::new(cx, cz, old_vd), View::new(cx, cz, view_distance)) {
+        let _ = sender.send(InternalPacket::UnloadChunk { chunk_x, chunk_z });
+    }
+}
+
+/// Follow a player across chunk borders (by walking or teleporting): move
+/// the client's center chunk, queue the ring that came into view and unload
+/// what fell out of it.
+fn handle_chunk_updates(world: &mut World, entity: hecs::Entity) {
     // Get current position and chunk state
     let (pos, old_cx, old_cz, vd) = {
         let Ok(pos) = world.get::<&Position>(entity) else {
@@ -12491,6 +21347,9 @@ fn handle_chunk_updates(
         return;
     };
     let sender = &sender.0;
+    let Ok(mut chunk_sender) = world.get::<&mut ChunkSender>(entity) else {
+        return;
+    };
 
     // Send Set Center Chunk
     let _ = sender.send(InternalPacket::SetCenterChunk {
@@ -12498,100 +21357,46 @@ fn handle_chunk_updates(
         chunk_z: new_cz,
     });
 
-    // Unload old chunks
-    for cx in (old_cx - vd)..=(old_cx + vd) {
-        for cz in (old_cz - vd)..=(old_cz + vd) {
-            if (cx - new_cx).abs() > vd || (cz - new_cz).abs() > vd {
-                let _ = sender.send(InternalPacket::UnloadChunk {
-                    chunk_x: cx,
-                    chunk_z: cz,
-                });
-            }
-        }
-    }
-
-    // Send new chunks in batch
-    send_new_chunks(sender, world_state, old_cx, old_cz, new_cx, new_cz, vd);
-}
-
-fn send_chunks_around(
-    sender: &mpsc::UnboundedSender<InternalPacket>,
-    world_state: &mut WorldState,
-    center_cx: i32,
-    center_cz: i32,
-    view_distance: i32,
-) {
-    // We need to send chunk batch start/finished via raw packets.
-    // Since we're going through the packet channel now, we use a special mechanism.
-    // The writer task will handle the batch framing when it sees the ChunkBatchStart/Finished markers.
-    let _ = sender.send(InternalPacket::ChunkBatchStart);
-
-    let mut count = 0i32;
-    for cx in (center_cx - view_distance)..=(center_cx + view_distance) {
-        for cz in (center_cz - view_distance)..=(center_cz + view_distance) {
-            let chunk_packet = world_state.get_chunk_packet(cx, cz);
-            let _ = sender.send(chunk_packet);
-            count += 1;
-        }
+    // Queue the ring that came into view and unload the one that left it
+    let unload = chunk_sender.move_view(View::new(old_cx, old_cz, vd), View::new(new_cx, new_cz, vd));
+    for (chunk_x, chunk_z) in unload {
+        let _ = sender.send(InternalPacket::UnloadChunk { chunk_x, chunk_z });
     }
-
-    let _ = sender.send(InternalPacket::ChunkBatchFinished { batch_size: count });
-
-    // Send BlockEntityData for signs in loaded chunks
-    send_sign_block_entities(sender, world_state, center_cx, center_cz, view_distance);
 }
 
-fn send_new_chunks(
-    sender: &mpsc::UnboundedSender<InternalPacket>,
-    world_state: &mut WorldState,
-    old_cx: i32,
-    old_cz: i32,
-    new_cx: i32,
-    new_cz: i32,
-    vd: i32,
-) {
-    let _ = sender.send(InternalPacket::ChunkBatchStart);
-
-    let mut count = 0i32;
-    for cx in (new_cx - vd)..=(new_cx + vd) {
-        for cz in (new_cz - vd)..=(new_cz + vd) {
-            if (cx - old_cx).abs() > vd || (cz - old_cz).abs() > vd {
-                let chunk_packet = world_state.get_chunk_packet(cx, cz);
-                let _ = sender.send(chunk_packet);
-                count += 1;
-            }
-        }
-    }
-
-    let _ = sender.send(InternalPacket::ChunkBatchFinished { batch_size: count });
-
-    // Send BlockEntityData for signs in newly loaded chunks
-    for cx in (new_cx - vd)..=(new_cx + vd) {
-        for cz in (new_cz - vd)..=(new_cz + vd) {
-            if (cx - old_cx).abs() > vd || (cz - old_cz).abs() > vd {
-                send_sign_block_entities_for_chunk(sender, world_state, cx, cz);
-            }
+/// Send each player their next batch of queued chunks, nearest first, at the
+/// rate their client says it can load them.
+fn tick_chunk_sending(world: &mut World, world_state: &mut WorldState) {
+    let mut batches: Vec<(hecs::Entity, Vec<(i32, i32)>)> = Vec::new();
+    for (entity, (cp, chunk_sender)) in world.query_mut::<(&ChunkPosition, &mut ChunkSender)>() {
+        let batch = chunk_sender.next_batch(cp.chunk_x, cp.chunk_z);
+        if !batch.is_empty() {
+            batches.push((entity, batch));
         }
     }
-}
-
-/// Send BlockEntityData packets for all signs in chunks within the given range.
-fn send_sign_block_entities(
-    sender: &mpsc::UnboundedSender<InternalPacket>,
-    world_state: &WorldState,
-    center_cx: i32,
-    center_cz: i32,
-    view_distance: i32,
-) {
-    for cx in (center_cx - view_distance)..=(center_cx + view_distance) {
-        for cz in (center_cz - view_distance)..=(center_cz + view_distance) {
-            send_sign_block_entities_for_chunk(sender, world_state, cx, cz);
+    for (entity, batch) in batches {
+        let Ok(sender) = world.get::<&ConnectionSender>(entity) else {
+            continue;
+        };
+        // Chunks come from the player's own level
+        world_state.switch_level(&player_level(world, entity));
+        let _ = sender.0.send(InternalPacket::ChunkBatchStart);
+        for &(cx, cz) in &batch {
+            let _ = sender.0.send(world_state.get_chunk_packet(cx, cz));
+        }
+        let _ = sender.0.send(InternalPacket::ChunkBatchFinished {
+            batch_size: batch.len() as i32,
+        });
+        // BlockEntityData for signs and campfires in the new chunks
+        for &(cx, cz) in &batch {
+            send_block_entities_for_chunk(&sender.0, world_state, cx, cz);
         }
     }
+    world_state.switch_level(DEFAULT_LEVEL);
 }
 
-/// Send BlockEntityData packets for all signs in a specific chunk.
-fn send_sign_block_entities_for_chunk(
+/// Send BlockEntityData packets for all client-rendered block entities in a specific chunk.
+fn send_block_entities_for_chunk(
     sender: &mpsc::UnboundedSender<InternalPacket>,
     world_state: &WorldState,
     chunk_x: i32,
@@ -12599,31 +21404,106 @@ fn send_sign_block_entities_for_chunk(
 ) {
     let min_x = chunk_x * 16;
     let min_z = chunk_z * 16;
-    for (pos, be) in &world_state.block_entities {
+    for (pos, be) in &world_state.level.block_entities {
         if pos.x >= min_x && pos.x < min_x + 16
             && pos.z >= min_z && pos.z < min_z + 16
         {
-            if let BlockEntity::Sign { .. } = be {
-                let nbt = build_sign_update_nbt(be);
-                let _ = sender.send(InternalPacket::BlockEntityData {
-                    position: *pos,
-                    block_entity_type: 7, // sign
-                    nbt,
-                });
+            if let Some(packet) = block_entity_update_packet(pos, be) {
+                let _ = sender.send(packet);
             }
         }
     }
 }
 
-/// Send a packet to all players.
-fn broadcast_to_all(world: &World, packet: &InternalPacket) {
+/// Build the BlockEntityData packet for block entities the client renders
+/// (sign text, food on campfires). Returns None for the rest.
+fn block_entity_update_packet(pos: &BlockPos, be: &BlockEntity) -> Option<InternalPacket> {
+    match be {
+        BlockEntity::Sign { .. } => Some(InternalPacket::BlockEntityData {
+            position: *pos,
+            block_entity_type: 7, // sign
+            nbt: build_sign_update_nbt(be),
+        }),
+        BlockEntity::Campfire { items, .. } => {
+            let items: Vec<NbtValue> = items.iter().enumerate()
+                .filter_map(|(i, slot)| slot.as_ref().map(|item| item_stack_to_nbt(i as i8, item)))
+                .collect();
+            Some(InternalPacket::BlockEntityData {
+                position: *pos,
+                block_entity_type: 32, // campfire
+                nbt: nbt_compound! { "Items" => NbtValue::List(items) },
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Show a block entity changed by a mod to players (sign text, campfire food,
+/// open container menus) and queue its chunk for saving.
+pub(crate) fn block_entity_changed(world: &World, world_state: &mut WorldState, pos: &BlockPos) {
+    if let Some(packet) = world_state.get_block_entity(pos).and_then(|be| block_entity_update_packet(pos, be)) {
+        broadcast_to_level(world, world_state, &packet);
+    }
+    for (entity, (sender, open)) in world.query::<(&ConnectionSender, &OpenContainer)>().iter() {
+        let viewing = matches!(&open.menu,
+            Menu::Chest { pos: p } | Menu::Furnace { pos: p } | Menu::BrewingStand { pos: p } if p == pos);
+        if viewing {
+            let _ = sender.0.send(InternalPacket::SetContainerContent {
+                window_id: open.container_id,
+                state_id: open.state_id,
+                slots: build_container_slots(world_state, world, entity, &open.menu),
+                carried_item: open.carried.clone(),
+            });
+        }
+    }
+    world_state.mark_chunk_dirty(pos.chunk_pos());
+}
+
+/// Send a packet to all players. The broadcast helpers share one encoding
+/// of the packet between every receiving connection.
+pub(crate) fn broadcast_to_all(world: &World, packet: &InternalPacket) {
+    let packet = packet.clone().shared();
     for (_e, sender) in world.query::<&ConnectionSender>().iter() {
         let _ = sender.0.send(packet.clone());
     }
 }
 
+/// Send a block change to the players in the current level.
+pub(crate) fn broadcast_to_level(world: &World, world_state: &WorldState, packet: &InternalPacket) {
+    let packet = packet.clone().shared();
+    for (_e, (sender, dimension)) in world.query::<(&ConnectionSender, Option<&Dimension>)>().iter() {
+        if in_level(world_state, dimension) {
+            let _ = sender.0.send(packet.clone());
+        }
+    }
+}
+
+/// `broadcast_to_level`, skipping the player who made the change.
+fn broadcast_to_level_except(world: &World, world_state: &WorldState, except_eid: i32, packet: &InternalPacket) {
+    let packet = packet.clone().shared();
+    for (_e, (eid, sender, dimension)) in world.query::<(&EntityId, &ConnectionSender, Option<&Dimension>)>().iter() {
+        if eid.0 != except_eid && in_level(world_state, dimension) {
+            let _ = sender.0.send(packet.clone());
+        }
+    }
+}
+
+/// Send particles at each point to the named players, or to everyone when
+/// `players` is empty.
+pub(crate) fn send_particle_points(world: &World, effect: &ParticleEffect, points: &[Point], players: &[String]) {
+    for (_e, (profile, sender)) in world.query::<(&Profile, &ConnectionSender)>().iter() {
+        if !players.is_empty() && !players.iter().any(|name| name.eq_ignore_ascii_case(&profile.0.name)) {
+            continue;
+        }
+        for &point in points {
+            let _ = sender.0.send(effect.packet(point));
+        }
+    }
+}
+
 /// Send a packet to all players except the one with the given entity ID.
 fn broadcast_except(world: &World, except_eid: i32, packet: &InternalPacket) {
+    let packet = packet.clone().shared();
     for (_e, (eid, sender)) in world.query::<(&EntityId, &ConnectionSender)>().iter() {
         if eid.0 != except_eid {
             let _ = sender.0.send(packet.clone());
@@ -12673,7 +21553,7 @@ fn damage_held_item(world: &mut World, entity: hecs::Entity, entity_id: i32, amo
             Err(_) => return,
         };
         if let Some(ref mut item) = inv.slots[inv_slot] {
-            if item.max_damage > 0 {
+            if item.is_damageable() {
                 // Unbreaking enchantment: 1/(level+1) chance to consume durability
                 let unbreaking = item.enchantment_level(22);
                 if unbreaking > 0 && rand::random::<f32>() > 1.0 / (unbreaking as f32 + 1.0) {
@@ -12719,13 +21599,13 @@ fn damage_held_item(world: &mut World, entity: hecs::Entity, entity_id: i32, amo
 
 /// SoundSource enum ordinal values matching MC SoundSource.
 const SOUND_WEATHER: u8 = 3;
-const SOUND_BLOCKS: u8 = 4;
+pub(crate) const SOUND_BLOCKS: u8 = 4;
 const SOUND_HOSTILE: u8 = 5;
 const SOUND_NEUTRAL: u8 = 6;
 const SOUND_PLAYERS: u8 = 7;
 
 /// Play a sound at a block position, broadcast to all players.
-fn play_sound_at_block(world: &World, pos: &BlockPos, sound: &str, source: u8, volume: f32, pitch: f32) {
+pub(crate) fn play_sound_at_block(world: &World, pos: &BlockPos, sound: &str, source: u8, volume: f32, pitch: f32) {
     let packet = InternalPacket::SoundEffect {
         sound_name: format!("minecraft:{}", sound),
         source,
@@ -12755,7 +21635,7 @@ fn play_sound_at_entity(world: &World, x: f64, y: f64, z: f64, sound: &str, sour
 }
 
 /// XP needed to advance from the given level (MC formula).
-fn xp_needed_for_level(level: i32) -> i32 {
+pub(crate) fn xp_needed_for_level(level: i32) -> i32 {
     if level < 15 {
         7 + level * 2
     } else if level < 30 {
@@ -12877,8 +21757,83 @@ fn offset_by_face(pos: &BlockPos, face: u8) -> BlockPos {
     }
 }
 
+/// Step along the player's line of sight (up to 5 blocks) looking for a water source
+/// or waterlogged block. Flowing water is passed through; other solid blocks stop the ray.
+fn raycast_water_source(world: &World, world_state: &mut WorldState, entity: hecs::Entity) -> Option<BlockPos> {
+    let pos = world.get::<&Position>(entity).ok()?.0;
+    let (yaw, pitch) = world.get::<&Rotation>(entity).map(|r| (r.yaw, r.pitch)).unwrap_or((0.0, 0.0));
+    let yaw_rad = (yaw as f64).to_radians();
+    let pitch_rad = (pitch as f64).to_radians();
+    let dir = (-yaw_rad.sin() * pitch_rad.cos(), -pitch_rad.sin(), yaw_rad.cos() * pitch_rad.cos());
+    let eye_y = pos.y + 1.62;
+
+    let mut last = None;
+    for step in 0..=100 {
+        let t = step as f64 * 0.05;
+        let block_pos = BlockPos::new(
+            (pos.x + dir.0 * t).floor() as i32,
+            (eye_y + dir.1 * t).floor() as i32,
+            (pos.z + dir.2 * t).floor() as i32,
+        );
+        if last == Some(block_pos) {
+            continue;
+        }
+        last = Some(block_pos);
+        let state = world_state.get_block(&block_pos);
+        if state == pickaxe_data::WATER_SOURCE || pickaxe_data::is_waterlogged(state) {
+            return Some(block_pos);
+        }
+        let name = pickaxe_data::block_state_to_name(state).unwrap_or("");
+        if state != 0 && !pickaxe_data::is_fluid(state) && pickaxe_data::is_solid_for_fluid(name) {
+            return None;
+        }
+    }
+    None
+}
+
+/// Undo a client-predicted placement: resend the real block at `pos` and ack the sequence.
+fn reject_block_place(world: &World, world_state: &mut WorldState, entity: hecs::Entity, pos: &BlockPos, sequence: i32) {
+    let actual = world_state.get_block(pos);
+    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+        let _ = sender.0.send(InternalPacket::BlockUpdate {
+            position: *pos,
+            block_id: actual,
+        });
+        let _ = sender.0.send(InternalPacket::AcknowledgeBlockChange { sequence });
+    }
+}
+
+/// Check whether a full block at `pos` would overlap a player or mob hitbox.
+/// Spectators don't collide with blocks, so they never obstruct placement.
+fn is_placement_obstructed(world: &World, pos: &BlockPos) -> bool {
+    let (bx, by, bz) = (pos.x as f64, pos.y as f64, pos.z as f64);
+    let overlaps = |p: &Vec3d, width: f64, height: f64| {
+        let half = width / 2.0;
+        p.x + half > bx && p.x - half < bx + 1.0
+            && p.y + height > by && p.y < by + 1.0
+            && p.z + half > bz && p.z - half < bz + 1.0
+    };
+
+    for (_, (p, gm, ms)) in world.query::<(&Position, &PlayerGameMode, Option<&MovementState>)>().iter() {
+        if gm.0 == GameMode::Spectator {
+            continue;
+        }
+        let height = if ms.map(|m| m.sneaking).unwrap_or(false) { 1.5 } else { 1.8 };
+        if overlaps(&p.0, 0.6, height) {
+            return true;
+        }
+    }
+    for (_, (p, mob)) in world.query::<(&Position, &MobEntity)>().iter() {
+        let (width, height) = pickaxe_data::mob_hitbox(mob.mob_type);
+        if overlaps(&p.0, width, height) {
+            return true;
+        }
+    }
+    false
+}
+
 /// Build the Declare Commands packet with the full command tree.
-fn build_command_tree(lua_commands: &crate::bridge::LuaCommands) -> InternalPacket {
+fn build_command_tree(lua_commands: &crate::bridge::LuaCommands, level: u8) -> InternalPacket {
     let mut nodes: Vec<CommandNode> = Vec::new();
 
     // Helper: create a literal node (type=1)
@@ -12889,6 +21844,7 @@ fn build_command_tree(lua_commands: &crate::bridge::LuaCommands) -> InternalPack
             name: Some(name.to_string()),
             parser: None,
             parser_properties: None,
+            suggestions_type: None,
         }
     };
 
@@ -12900,12 +21856,18 @@ fn build_command_tree(lua_commands: &crate::bridge::LuaCommands) -> InternalPack
         name: None,
         parser: None,
         parser_properties: None,
+        suggestions_type: None,
     });
 
     // Simple commands: literal + executable, no subcommands
-    let simple_cmds = ["gamemode", "gm", "tp", "teleport", "give", "kill", "say", "help", "effect", "potion", "enchant"];
+    let simple_cmds = ["execute", "function", "gamemode", "gm", "tp", "teleport", "give", "clear", "kill", "damage", "say", "msg", "tell", "w", "reply", "r", "socialspy", "audit", "tpa", "tpaccept", "tpdeny", "list", "ping", "tps", "stop", "save-all", "save-off", "save-on", "backup", "schem", "place", "structure", "spawn", "sethome", "home", "delhome", "homes", "warp", "setwarp", "delwarp", "help", "effect", "potion", "enchant", "invsee", "playerdata", "chunkinfo", "worldstats", "world", "mods", "debug", "scoreboard", "team", "title", "tellraw", "summon", "gamerule", "advancement", "weather", "difficulty", "seed", "locate", "tick", "kick", "ban", "ban-ip", "pardon", "pardon-ip", "banlist", "whitelist", "op", "deop", "ops"];
     let mut root_children: Vec<i32> = Vec::new();
-    for cmd in &simple_cmds {
+    let available = |c: &&&str| {
+        builtin_command_level(c) <= level
+            && (tpa::enabled() || !tpa::COMMANDS.contains(*c))
+            && teleports::command_enabled(c)
+    };
+    for cmd in simple_cmds.iter().filter(available) {
         let idx = nodes.len() as i32;
         root_children.push(idx);
         nodes.push(lit(cmd, true, vec![]));
@@ -12948,10 +21910,27 @@ fn build_command_tree(lua_commands: &crate::bridge::LuaCommands) -> InternalPack
 
     // Add Lua-registered commands
     if let Ok(cmds) = lua_commands.lock() {
-        for cmd in cmds.iter() {
+        for cmd in cmds.iter().filter(|c| c.permission_level <= level) {
+            // Argument nodes chained from the last one back; each is executable
+            // when everything after it is optional
+            let mut children = vec![];
+            for (i, arg) in cmd.args.iter().enumerate().rev() {
+                let executable = cmd.args.get(i + 1).is_none_or(|next| next.optional);
+                let (parser, properties) = arg.ty.parser();
+                let idx = nodes.len() as i32;
+                nodes.push(CommandNode {
+                    flags: 0x02 | if executable { 0x04 } else { 0 } | if arg.suggest.is_some() { 0x10 } else { 0 },
+                    children,
+                    name: Some(arg.name.clone()),
+                    parser: Some(parser.to_string()),
+                    parser_properties: properties,
+                    suggestions_type: arg.suggest.as_ref().map(|_| "minecraft:ask_server".to_string()),
+                });
+                children = vec![idx];
+            }
             let idx = nodes.len() as i32;
             root_children.push(idx);
-            nodes.push(lit(&cmd.name, true, vec![]));
+            nodes.push(lit(&cmd.name, cmd.args.first().is_none_or(|a| a.optional), children));
         }
     }
 
@@ -12966,23 +21945,86 @@ fn build_command_tree(lua_commands: &crate::bridge::LuaCommands) -> InternalPack
 
 /// Send a system chat message to a specific player entity.
 fn send_message(world: &World, entity: hecs::Entity, message: &str) {
+    let entity = feedback_target(world, entity);
     if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
         let _ = sender.0.send(InternalPacket::SystemChatMessage {
             content: TextComponent::plain(message),
             overlay: false,
         });
+    } else if world.get::<&ConsoleSource>(entity).is_ok() {
+        info!("{}", message);
     }
 }
 
-/// Check if a player is an operator.
-/// Re-reads config/ops.toml so changes take effect without a restart.
-fn is_op(world: &World, entity: hecs::Entity) -> bool {
-    let name = match world.get::<&Profile>(entity) {
-        Ok(p) => p.0.name.clone(),
-        Err(_) => return false,
+/// Where messages to `entity` go: while it runs a command for someone else
+/// (`/execute as`), feedback goes to whoever issued the command.
+fn feedback_target(world: &World, entity: hecs::Entity) -> hecs::Entity {
+    world.get::<&ExecutionContext>(entity).map(|ctx| ctx.output).unwrap_or(entity)
+}
+
+/// Send a command failure message (red, as vanilla shows command errors).
+fn send_error(world: &World, entity: hecs::Entity, message: &str) {
+    let entity = feedback_target(world, entity);
+    if let Ok(sender) = world.get::<&ConnectionSender>(entity) {
+        let _ = sender.0.send(InternalPacket::SystemChatMessage {
+            content: TextComponent::colored(message, "red"),
+            overlay: false,
+        });
+    } else if world.get::<&ConsoleSource>(entity).is_ok() {
+        warn!("{}", message);
+    }
+}
+
+/// A player's operator permission level (0-4). The console has level 4.
+/// Re-reads ops.json and config/ops.toml so changes take effect without a restart.
+fn permission_level(world: &World, entity: hecs::Entity) -> u8 {
+    if let Ok(ctx) = world.get::<&ExecutionContext>(entity) {
+        return ctx.permission_level;
+    }
+    if world.get::<&ConsoleSource>(entity).is_ok() {
+        return 4;
+    }
+    match world.get::<&Profile>(entity) {
+        Ok(p) => crate::access::permission_level(&p.0.name, Some(p.0.uuid)),
+        Err(_) => 0,
+    }
+}
+
+/// Check if a player has at least the given permission level.
+fn has_permission(world: &World, entity: hecs::Entity, level: u8) -> bool {
+    permission_level(world, entity) >= level
+}
+
+/// Minimum permission level at which a built-in command is usable at all
+/// (commands with public subcommands check the rest themselves).
+fn builtin_command_level(name: &str) -> u8 {
+    match name {
+        "tpa" | "tpaccept" | "tpdeny" => tpa::permission_level(),
+        "spawn" | "sethome" | "home" | "delhome" | "homes" | "warp" => 0,
+        "help" | "list" | "ping" | "tps" | "say" | "msg" | "tell" | "w" | "reply" | "r" | "tp" | "teleport" | "kill" | "time" | "weather" | "difficulty" | "gamerule" => 0,
+        "kick" | "ban" | "ban-ip" | "pardon" | "pardon-ip" | "banlist" | "whitelist" | "op" | "deop" | "ops"
+        | "socialspy" | "audit" | "tick" | "debug" => 3,
+        "stop" | "save-all" | "save-off" | "save-on" | "backup" => 4,
+        _ => 2,
+    }
+}
+
+/// Send a player their op level (Entity Event 24-28) and a command tree
+/// containing only the commands they may use.
+fn send_permissions(
+    world: &World,
+    entity: hecs::Entity,
+    lua_commands: &crate::bridge::LuaCommands,
+) {
+    let level = permission_level(world, entity);
+    let (Ok(sender), Ok(eid)) = (world.get::<&ConnectionSender>(entity), world.get::<&EntityId>(entity)) else {
+        return;
     };
-    let ops = crate::config::load_ops();
-    ops.iter().any(|op| op.eq_ignore_ascii_case(&name))
+    let _ = sender.0.send(InternalPacket::EntityEvent {
+        entity_id: eid.0,
+        event_id: 24 + level as i8,
+    });
+    let _ = sender.0.send(build_command_tree(lua_commands, level));
 }
 
 /// Get the player count.
@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+/// Slowest and fastest rates `/tick rate` accepts, as vanilla.
+pub const MIN_RATE: f32 = 1.0;
+pub const MAX_RATE: f32 = 10000.0;
+
+/// How fast the world ticks, changed at runtime with `/tick`. While frozen
+/// the server keeps handling players and connections but the world stands
+/// still, except for ticks requested with `/tick step`.
+pub struct TickRate {
+    rate: f32,
+    frozen: bool,
+    /// Ticks left to run while frozen.
+    steps: u32,
+    /// Ticks left to run back to back, without waiting between them.
+    sprint: u64,
+}
+
+impl TickRate {
+    pub fn new(rate: f32) -> Self {
+        Self {
+            rate: rate.clamp(MIN_RATE, MAX_RATE),
+            frozen: false,
+            steps: 0,
+            sprint: 0,
+        }
+    }
+
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+
+    pub fn set_rate(&mut self, rate: f32) {
+        self.rate = rate.clamp(MIN_RATE, MAX_RATE);
+    }
+
+    pub fn frozen(&self) -> bool {
+        self.frozen
+    }
+
+    pub fn set_frozen(&mut self, frozen: bool) {
+        self.frozen = frozen;
+        self.steps = 0;
+    }
+
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    /// Run `steps` ticks of a frozen world. False if it isn't frozen.
+    pub fn step(&mut self, steps: u32) -> bool {
+        if !self.frozen {
+            return false;
+        }
+        self.steps = steps;
+        true
+    }
+
+    pub fn sprinting(&self) -> bool {
+        self.sprint > 0
+    }
+
+    /// Run the next `ticks` ticks as fast as possible; 0 stops sprinting.
+    pub fn sprint(&mut self, ticks: u64) {
+        self.sprint = ticks;
+    }
+
+    /// Time one tick should take: none while sprinting.
+    pub fn interval(&self) -> Duration {
+        if self.sprinting() {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(1.0 / self.rate as f64)
+        }
+    }
+
+    /// Start a tick. Returns whether the world advances this tick.
+    pub fn advance(&mut self) -> bool {
+        self.sprint = self.sprint.saturating_sub(1);
+        if !self.frozen {
+            return true;
+        }
+        if self.steps == 0 {
+            return false;
+        }
+        self.steps -= 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_freeze_and_step() {
+        let mut ticks = TickRate::new(20.0);
+        assert_eq!(ticks.interval(), Duration::from_millis(50));
+        assert!(!ticks.step(5));
+        assert!(ticks.advance());
+
+        ticks.set_frozen(true);
+        assert!(!ticks.advance());
+        assert!(ticks.step(2));
+        assert!(ticks.advance());
+        assert!(ticks.advance());
+        assert!(!ticks.advance());
+
+        ticks.sprint(1);
+        assert_eq!(ticks.interval(), Duration::ZERO);
+        ticks.advance();
+        assert!(!ticks.sprinting());
+
+        ticks.set_rate(0.0);
+        assert_eq!(ticks.rate(), MIN_RATE);
+    }
+}
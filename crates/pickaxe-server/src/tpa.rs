@@ -0,0 +1,111 @@
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+/// Whether the /tpa module is enabled (`[tpa] enabled` in server.toml).
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// Permission level required for /tpa, /tpaccept and /tpdeny.
+static PERMISSION_LEVEL: AtomicU8 = AtomicU8::new(0);
+
+/// Command names handled by this module.
+pub const COMMANDS: &[&str] = &["tpa", "tpaccept", "tpdeny"];
+
+pub fn configure(config: &crate::config::TpaConfig) {
+    ENABLED.store(config.enabled, Ordering::Relaxed);
+    PERMISSION_LEVEL.store(config.permission_level.min(4), Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn permission_level() -> u8 {
+    PERMISSION_LEVEL.load(Ordering::Relaxed)
+}
+
+/// A pending request for `from` to teleport to `to`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TpaRequest {
+    pub from: String,
+    pub to: String,
+    /// World tick at which the request lapses.
+    pub expires_at: u64,
+}
+
+/// Pending teleport requests, oldest first. Names compare case-insensitively.
+#[derive(Default)]
+pub struct TpaRequests {
+    requests: Vec<TpaRequest>,
+    /// Request lifetime in ticks.
+    pub timeout_ticks: u64,
+}
+
+impl TpaRequests {
+    pub fn new(timeout_ticks: u64) -> Self {
+        Self { requests: Vec::new(), timeout_ticks }
+    }
+
+    /// Add a request, replacing any earlier one from `from` to `to`.
+    /// Returns false if an identical request was already pending.
+    pub fn add(&mut self, from: &str, to: &str, now: u64) -> bool {
+        let existed = self.take(to, Some(from)).is_some();
+        self.requests.push(TpaRequest {
+            from: from.to_string(),
+            to: to.to_string(),
+            expires_at: now + self.timeout_ticks,
+        });
+        !existed
+    }
+
+    /// Remove and return a request sent to `to`: the one from `from` if given,
+    /// otherwise the most recent.
+    pub fn take(&mut self, to: &str, from: Option<&str>) -> Option<TpaRequest> {
+        let index = self.requests.iter().rposition(|r| {
+            r.to.eq_ignore_ascii_case(to) && from.is_none_or(|f| r.from.eq_ignore_ascii_case(f))
+        })?;
+        Some(self.requests.remove(index))
+    }
+
+    /// Remove and return every request that has lapsed by `now`.
+    pub fn expire(&mut self, now: u64) -> Vec<TpaRequest> {
+        let (expired, pending) = std::mem::take(&mut self.requests).into_iter().partition(|r| r.expires_at <= now);
+        self.requests = pending;
+        expired
+    }
+
+    /// Drop all requests to or from a player (e.g. when they disconnect).
+    pub fn remove_player(&mut self, name: &str) {
+        self.requests.retain(|r| !r.from.eq_ignore_ascii_case(name) && !r.to.eq_ignore_ascii_case(name));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests() {
+        let mut requests = TpaRequests::new(100);
+        assert!(requests.add("Alice", "Carol", 0));
+        assert!(requests.add("Bob", "Carol", 10));
+        assert!(!requests.add("alice", "carol", 20));
+
+        // Most recent request wins when no requester is named
+        assert_eq!(requests.take("Carol", None).map(|r| r.from), Some("alice".to_string()));
+        assert_eq!(requests.take("carol", Some("BOB")).map(|r| r.expires_at), Some(110));
+        assert_eq!(requests.take("Carol", None), None);
+    }
+
+    #[test]
+    fn test_expiry_and_removal() {
+        let mut requests = TpaRequests::new(100);
+        requests.add("Alice", "Carol", 0);
+        requests.add("Bob", "Carol", 50);
+        requests.add("Dave", "Erin", 60);
+        let expired = requests.expire(100);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].from, "Alice");
+
+        requests.remove_player("carol");
+        assert_eq!(requests.take("Carol", None), None);
+        assert!(requests.take("Erin", None).is_some());
+    }
+}
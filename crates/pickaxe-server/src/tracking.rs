@@ -0,0 +1,155 @@
+use pickaxe_types::Vec3d;
+use std::collections::HashMap;
+
+/// Kinds of entity the server sends to players. Each has its own tracking
+/// range and movement update interval, like vanilla's entity types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackedKind {
+    Player,
+    Mob,
+    Item,
+    Arrow,
+    Bobber,
+    Tnt,
+    ArmorStand,
+    Display,
+}
+
+impl TrackedKind {
+    /// How many chunks away players see this kind of entity, before their
+    /// own view distance caps it.
+    pub fn range(self) -> i32 {
+        match self {
+            TrackedKind::Player => 32,
+            TrackedKind::Mob | TrackedKind::Tnt | TrackedKind::ArmorStand | TrackedKind::Display => 10,
+            TrackedKind::Item => 6,
+            TrackedKind::Arrow | TrackedKind::Bobber => 4,
+        }
+    }
+
+    /// Ticks between movement updates. The client interpolates in between,
+    /// so small, client-simulated entities are synced less often.
+    pub fn update_interval(self) -> u64 {
+        match self {
+            TrackedKind::Player | TrackedKind::Display => 1,
+            TrackedKind::Mob | TrackedKind::Bobber | TrackedKind::ArmorStand => 2,
+            TrackedKind::Item | TrackedKind::Arrow | TrackedKind::Tnt => 4,
+        }
+    }
+
+    /// Whether an entity of this kind sends its movement this tick. Staggered
+    /// by entity ID so updates don't all land on the same tick.
+    pub fn update_due(self, entity_id: i32, tick_count: u64) -> bool {
+        (tick_count + entity_id as u64).is_multiple_of(self.update_interval())
+    }
+}
+
+const MAX_RANGE: i32 = 32;
+
+/// How far an entity may have moved since the index was built. Proximity
+/// lookups search this much further so entities that crossed into another
+/// chunk since are still found.
+pub const STALE_DISTANCE: f64 = 8.0;
+
+/// Entities bucketed by chunk, so each player only looks at the entities
+/// near them and proximity checks only look at a few chunks. Rebuilt by
+/// entity tracking at the end of every tick.
+#[derive(Default)]
+pub struct ChunkIndex {
+    buckets: HashMap<(i32, i32), Vec<Entry>>,
+}
+
+/// (entity, entity ID, kind)
+type Entry = (hecs::Entity, i32, TrackedKind);
+
+impl ChunkIndex {
+    pub fn clear(&mut self) {
+        self.buckets.clear();
+    }
+
+    pub fn insert(&mut self, chunk_x: i32, chunk_z: i32, entity: hecs::Entity, entity_id: i32, kind: TrackedKind) {
+        self.buckets.entry((chunk_x, chunk_z)).or_default().push((entity, entity_id, kind));
+    }
+
+    /// Entities in the chunks within `radius` (plus `STALE_DISTANCE`) of
+    /// `pos` horizontally. Callers check the exact distance against the
+    /// entities' current positions.
+    pub fn near(&self, pos: Vec3d, radius: f64) -> impl Iterator<Item = hecs::Entity> + '_ {
+        let reach = radius + STALE_DISTANCE;
+        let chunk = |v: f64| (v.floor() as i32) >> 4;
+        let (min_x, min_z, max_x, max_z) = (chunk(pos.x - reach), chunk(pos.z - reach), chunk(pos.x + reach), chunk(pos.z + reach));
+        (min_x..=max_x)
+            .flat_map(move |x| (min_z..=max_z).map(move |z| (x, z)))
+            .filter_map(|chunk| self.buckets.get(&chunk))
+            .flatten()
+            .map(|&(entity, _, _)| entity)
+    }
+
+    /// Entity IDs a player in this chunk should see: within the range of
+    /// their kind and the player's view distance.
+    pub fn visible_from(&self, chunk_x: i32, chunk_z: i32, view_distance: i32) -> Vec<i32> {
+        let radius = view_distance.min(MAX_RANGE);
+        let in_range = |(cx, cz): (i32, i32), kind: TrackedKind| {
+            let distance = (cx - chunk_x).abs().max((cz - chunk_z).abs());
+            distance <= kind.range().min(radius)
+        };
+        let mut visible = Vec::new();
+        let mut add_bucket = |pos: (i32, i32), entities: &Vec<Entry>| {
+            visible.extend(entities.iter().filter(|&&(_, _, kind)| in_range(pos, kind)).map(|&(_, eid, _)| eid));
+        };
+        // Scan whichever is smaller: the occupied chunks or the view square
+        let area = ((2 * radius + 1) as usize).pow(2);
+        if self.buckets.len() < area {
+            for (&pos, entities) in &self.buckets {
+                add_bucket(pos, entities);
+            }
+        } else {
+            for cx in (chunk_x - radius)..=(chunk_x + radius) {
+                for cz in (chunk_z - radius)..=(chunk_z + radius) {
+                    if let Some(entities) = self.buckets.get(&(cx, cz)) {
+                        add_bucket((cx, cz), entities);
+                    }
+                }
+            }
+        }
+        visible
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_visible_from() {
+        let mut world = hecs::World::new();
+        let mut index = ChunkIndex::default();
+        index.insert(0, 0, world.spawn(()), 1, TrackedKind::Item);
+        index.insert(5, 0, world.spawn(()), 2, TrackedKind::Mob);
+        index.insert(0, 8, world.spawn(()), 3, TrackedKind::Item);
+        index.insert(-20, 0, world.spawn(()), 4, TrackedKind::Player);
+
+        let mut visible = index.visible_from(0, 0, 32);
+        visible.sort();
+        // The item 8 chunks out is beyond item range
+        assert_eq!(visible, vec![1, 2, 4]);
+        // A short view distance caps every range
+        assert_eq!(index.visible_from(0, 0, 4), vec![1]);
+    }
+
+    #[test]
+    fn test_near() {
+        let mut world = hecs::World::new();
+        let (here, west, far) = (world.spawn(()), world.spawn(()), world.spawn(()));
+        let mut index = ChunkIndex::default();
+        index.insert(0, 0, here, 1, TrackedKind::Item);
+        index.insert(-1, 0, west, 2, TrackedKind::Mob);
+        index.insert(12, 12, far, 3, TrackedKind::Player);
+
+        let near: Vec<hecs::Entity> = index.near(Vec3d::new(0.5, 64.0, 0.5), 4.0).collect();
+        assert!(near.contains(&here) && near.contains(&west) && !near.contains(&far));
+        assert_eq!(index.near(Vec3d::new(200.0, 64.0, 200.0), 4.0).next(), Some(far));
+        index.clear();
+        assert_eq!(index.near(Vec3d::new(0.5, 64.0, 0.5), 4.0).count(), 0);
+    }
+}
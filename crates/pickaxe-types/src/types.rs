@@ -1,3 +1,4 @@
+use pickaxe_nbt::NbtValue;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -134,30 +135,268 @@ pub struct ProfileProperty {
 }
 
 /// Text component for chat messages (simplified JSON text).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TextComponent {
+    #[serde(default)]
     pub text: String,
+    /// Translation key the client renders in its own language, filling `%s`
+    /// placeholders from `with`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translate: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub with: Vec<TextComponent>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub color: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub bold: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub italic: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub underlined: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strikethrough: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub obfuscated: Option<bool>,
+    #[serde(rename = "clickEvent", alias = "click_event", skip_serializing_if = "Option::is_none")]
+    pub click_event: Option<Box<ClickEvent>>,
+    #[serde(rename = "hoverEvent", alias = "hover_event", skip_serializing_if = "Option::is_none")]
+    pub hover_event: Option<Box<HoverEvent>>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub extra: Vec<TextComponent>,
 }
 
+/// Action performed when a text component is clicked.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", content = "value", rename_all = "snake_case")]
+pub enum ClickEvent {
+    RunCommand(String),
+    SuggestCommand(String),
+    OpenUrl(String),
+    CopyToClipboard(String),
+}
+
+impl ClickEvent {
+    pub fn action(&self) -> &'static str {
+        match self {
+            ClickEvent::RunCommand(_) => "run_command",
+            ClickEvent::SuggestCommand(_) => "suggest_command",
+            ClickEvent::OpenUrl(_) => "open_url",
+            ClickEvent::CopyToClipboard(_) => "copy_to_clipboard",
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        match self {
+            ClickEvent::RunCommand(v)
+            | ClickEvent::SuggestCommand(v)
+            | ClickEvent::OpenUrl(v)
+            | ClickEvent::CopyToClipboard(v) => v,
+        }
+    }
+
+    /// Build a click event from its vanilla action name.
+    pub fn from_action(action: &str, value: impl Into<String>) -> Option<Self> {
+        let value = value.into();
+        Some(match action {
+            "run_command" => ClickEvent::RunCommand(value),
+            "suggest_command" => ClickEvent::SuggestCommand(value),
+            "open_url" => ClickEvent::OpenUrl(value),
+            "copy_to_clipboard" => ClickEvent::CopyToClipboard(value),
+            _ => return None,
+        })
+    }
+}
+
+/// Tooltip shown when a text component is hovered.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", content = "contents", rename_all = "snake_case")]
+pub enum HoverEvent {
+    ShowText(TextComponent),
+    ShowItem {
+        /// Namespaced item ID, e.g. `minecraft:diamond_sword`.
+        id: String,
+        #[serde(default = "default_hover_count")]
+        count: i32,
+    },
+}
+
+fn default_hover_count() -> i32 {
+    1
+}
+
+/// Legacy formatting code colors, indexed by `0`-`f`.
+const LEGACY_COLORS: [&str; 16] = [
+    "black",
+    "dark_blue",
+    "dark_green",
+    "dark_aqua",
+    "dark_red",
+    "dark_purple",
+    "gold",
+    "gray",
+    "dark_gray",
+    "blue",
+    "green",
+    "aqua",
+    "red",
+    "light_purple",
+    "yellow",
+    "white",
+];
+
 impl TextComponent {
     pub fn plain(text: impl Into<String>) -> Self {
         Self {
             text: text.into(),
+            translate: None,
+            with: Vec::new(),
             color: None,
             bold: None,
             italic: None,
+            underlined: None,
+            strikethrough: None,
+            obfuscated: None,
+            click_event: None,
+            hover_event: None,
             extra: Vec::new(),
         }
     }
 
+    /// A component the client translates, e.g. `multiplayer.disconnect.kicked`.
+    pub fn translatable(key: impl Into<String>, with: Vec<TextComponent>) -> Self {
+        Self {
+            translate: Some(key.into()),
+            with,
+            ..Self::plain("")
+        }
+    }
+
+    pub fn colored(text: impl Into<String>, color: impl Into<String>) -> Self {
+        Self::plain(text).color(color)
+    }
+
+    pub fn color(mut self, color: impl Into<String>) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+
+    pub fn bold(mut self, bold: bool) -> Self {
+        self.bold = Some(bold);
+        self
+    }
+
+    pub fn italic(mut self, italic: bool) -> Self {
+        self.italic = Some(italic);
+        self
+    }
+
+    pub fn underlined(mut self, underlined: bool) -> Self {
+        self.underlined = Some(underlined);
+        self
+    }
+
+    pub fn strikethrough(mut self, strikethrough: bool) -> Self {
+        self.strikethrough = Some(strikethrough);
+        self
+    }
+
+    pub fn obfuscated(mut self, obfuscated: bool) -> Self {
+        self.obfuscated = Some(obfuscated);
+        self
+    }
+
+    pub fn click(mut self, event: ClickEvent) -> Self {
+        self.click_event = Some(Box::new(event));
+        self
+    }
+
+    pub fn hover(mut self, event: HoverEvent) -> Self {
+        self.hover_event = Some(Box::new(event));
+        self
+    }
+
+    /// Shorthand for a `show_text` hover event.
+    pub fn hover_text(self, text: TextComponent) -> Self {
+        self.hover(HoverEvent::ShowText(text))
+    }
+
+    pub fn append(mut self, child: TextComponent) -> Self {
+        self.extra.push(child);
+        self
+    }
+
+    /// Concatenated text of this component and all its children, without
+    /// formatting. Translated parts show as their key and arguments.
+    pub fn plain_text(&self) -> String {
+        let mut out = self.text.clone();
+        if let Some(key) = &self.translate {
+            out.push_str(key);
+            if !self.with.is_empty() {
+                let args: Vec<String> = self.with.iter().map(TextComponent::plain_text).collect();
+                out.push_str(&format!("[{}]", args.join(", ")));
+            }
+        }
+        for child in &self.extra {
+            out.push_str(&child.plain_text());
+        }
+        out
+    }
+
+    /// Translate legacy formatting codes (`&a`, `&l`, `&r`, ... with `prefix`
+    /// as the code character) into a component. Also accepts `&#RRGGBB` hex
+    /// colors. As in vanilla, a color code resets any active formatting.
+    pub fn from_legacy(input: &str, prefix: char) -> Self {
+        let mut root = TextComponent::plain("");
+        let mut style = TextComponent::plain("");
+        let mut chars = input.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != prefix {
+                style.text.push(c);
+                continue;
+            }
+            let Some(&code) = chars.peek() else {
+                style.text.push(c);
+                break;
+            };
+            let code = code.to_ascii_lowercase();
+            let hex: String = if code == '#' { chars.clone().skip(1).take(6).collect() } else { String::new() };
+            let is_hex = hex.len() == 6 && hex.chars().all(|h| h.is_ascii_hexdigit());
+            if !is_hex && !matches!(code, '0'..='9' | 'a'..='f' | 'k'..='o' | 'r') {
+                style.text.push(c);
+                continue;
+            }
+            chars.next();
+            if !style.text.is_empty() {
+                let next = TextComponent { text: String::new(), ..style.clone() };
+                root.extra.push(std::mem::replace(&mut style, next));
+            }
+            if is_hex {
+                chars.nth(5);
+                style = TextComponent::plain("").color(format!("#{}", hex.to_ascii_uppercase()));
+                continue;
+            }
+            match code {
+                'k' => style.obfuscated = Some(true),
+                'l' => style.bold = Some(true),
+                'm' => style.strikethrough = Some(true),
+                'n' => style.underlined = Some(true),
+                'o' => style.italic = Some(true),
+                'r' => style = TextComponent::plain(""),
+                _ => {
+                    let index = code.to_digit(16).unwrap_or(15) as usize;
+                    style = TextComponent::plain("").color(LEGACY_COLORS[index]);
+                }
+            }
+        }
+        if !style.text.is_empty() {
+            root.extra.push(style);
+        }
+        if root.extra.len() == 1 && root.extra[0].extra.is_empty() {
+            return root.extra.remove(0);
+        }
+        root
+    }
+
     pub fn to_json(&self) -> String {
         serde_json::to_string(self).unwrap_or_else(|_| r#"{"text":""}"#.to_string())
     }
@@ -200,15 +439,212 @@ pub struct ItemStack {
     pub max_damage: i32,
     /// Enchantments: Vec of (enchantment_registry_id, level).
     pub enchantments: Vec<(i32, i32)>,
+    /// Dye color (0xRRGGBB) for dyeable items such as leather armor. None = undyed.
+    pub dyed_color: Option<i32>,
+    /// Custom display name (e.g. from an anvil or /give). None = default name.
+    pub custom_name: Option<TextComponent>,
+    /// Tooltip lines shown below the name.
+    pub lore: Vec<TextComponent>,
+    /// Id of a Lua-registered custom item, kept in the `custom_data` component.
+    pub custom_id: Option<String>,
+    /// Never takes durability damage.
+    pub unbreakable: bool,
+    /// Attribute changes applied while the item is equipped. Empty = the
+    /// item's default modifiers.
+    pub attribute_modifiers: Vec<AttributeModifier>,
+    /// Potion type, color and extra effects for potions and tipped arrows.
+    pub potion_contents: Option<PotionContents>,
+    /// Pattern layers of a banner, bottom first.
+    pub banner_patterns: Vec<BannerLayer>,
+    /// Other `custom_data` entries, kept as-is so plugin and vanilla data
+    /// survives round trips.
+    pub custom_data: Vec<(String, NbtValue)>,
+}
+
+/// How an attribute modifier's amount combines with the attribute's value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AttributeOperation {
+    AddValue = 0,
+    AddMultipliedBase = 1,
+    AddMultipliedTotal = 2,
+}
+
+impl AttributeOperation {
+    const ALL: [Self; 3] = [Self::AddValue, Self::AddMultipliedBase, Self::AddMultipliedTotal];
+
+    pub fn id(self) -> i32 {
+        self as i32
+    }
+
+    pub fn from_id(id: i32) -> Option<Self> {
+        Self::ALL.into_iter().find(|op| op.id() == id)
+    }
+
+    /// Vanilla's serialized name, like `add_value`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::AddValue => "add_value",
+            Self::AddMultipliedBase => "add_multiplied_base",
+            Self::AddMultipliedTotal => "add_multiplied_total",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|op| op.name() == name)
+    }
+}
+
+/// The equipment slots an attribute modifier is active in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum EquipmentSlotGroup {
+    Any = 0,
+    MainHand = 1,
+    OffHand = 2,
+    Hand = 3,
+    Feet = 4,
+    Legs = 5,
+    Chest = 6,
+    Head = 7,
+    Armor = 8,
+    Body = 9,
+}
+
+impl EquipmentSlotGroup {
+    const ALL: [Self; 10] = [
+        Self::Any,
+        Self::MainHand,
+        Self::OffHand,
+        Self::Hand,
+        Self::Feet,
+        Self::Legs,
+        Self::Chest,
+        Self::Head,
+        Self::Armor,
+        Self::Body,
+    ];
+
+    pub fn id(self) -> i32 {
+        self as i32
+    }
+
+    pub fn from_id(id: i32) -> Option<Self> {
+        Self::ALL.into_iter().find(|slot| slot.id() == id)
+    }
+
+    /// Vanilla's serialized name, like `mainhand`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Any => "any",
+            Self::MainHand => "mainhand",
+            Self::OffHand => "offhand",
+            Self::Hand => "hand",
+            Self::Feet => "feet",
+            Self::Legs => "legs",
+            Self::Chest => "chest",
+            Self::Head => "head",
+            Self::Armor => "armor",
+            Self::Body => "body",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|slot| slot.name() == name)
+    }
+}
+
+/// One entry of the `attribute_modifiers` component.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeModifier {
+    /// Attribute registry ID (see `pickaxe_data::attribute_id_to_name`).
+    pub attribute: i32,
+    /// Namespaced modifier ID, unique per attribute, like `minecraft:base_attack_damage`.
+    pub id: String,
+    pub amount: f64,
+    pub operation: AttributeOperation,
+    pub slot: EquipmentSlotGroup,
+}
+
+/// The `potion_contents` component.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PotionContents {
+    /// Potion registry ID (see `pickaxe_data::potion_registry_name`). None
+    /// for items with only custom effects.
+    pub potion: Option<i32>,
+    /// Liquid color (0xRRGGBB). None = mixed from the effects.
+    pub custom_color: Option<i32>,
+    pub custom_effects: Vec<PotionContentsEffect>,
+}
+
+/// A layer of the `banner_patterns` component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BannerLayer {
+    /// Banner pattern registry ID (see `pickaxe_data::registries::banner_pattern_name`).
+    pub pattern: i32,
+    /// Dye color ID (see `pickaxe_data::dye_color_name`).
+    pub color: i32,
+}
+
+/// An effect a potion applies on top of its potion type's.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PotionContentsEffect {
+    /// Effect registry ID (see `pickaxe_data::effect_id_to_name`).
+    pub effect_id: i32,
+    /// 0 = level I, 1 = level II, etc.
+    pub amplifier: i32,
+    /// Ticks, -1 = infinite.
+    pub duration: i32,
+    pub ambient: bool,
+    pub show_particles: bool,
+    pub show_icon: bool,
 }
 
 impl ItemStack {
     pub fn new(item_id: i32, count: i8) -> Self {
-        Self { item_id, count, damage: 0, max_damage: 0, enchantments: Vec::new() }
+        Self::with_durability(item_id, count, 0)
     }
 
     pub fn with_durability(item_id: i32, count: i8, max_damage: i32) -> Self {
-        Self { item_id, count, damage: 0, max_damage, enchantments: Vec::new() }
+        Self {
+            item_id,
+            count,
+            damage: 0,
+            max_damage,
+            enchantments: Vec::new(),
+            dyed_color: None,
+            custom_name: None,
+            lore: Vec::new(),
+            custom_id: None,
+            unbreakable: false,
+            attribute_modifiers: Vec::new(),
+            potion_contents: None,
+            banner_patterns: Vec::new(),
+            custom_data: Vec::new(),
+        }
+    }
+
+    /// Returns true if `other` is the same item with the same data, so the two
+    /// can share a slot (counts are ignored).
+    pub fn stacks_with(&self, other: &ItemStack) -> bool {
+        self.item_id == other.item_id
+            && self.damage == other.damage
+            && self.max_damage == other.max_damage
+            && self.enchantments == other.enchantments
+            && self.dyed_color == other.dyed_color
+            && self.custom_name == other.custom_name
+            && self.lore == other.lore
+            && self.custom_id == other.custom_id
+            && self.unbreakable == other.unbreakable
+            && self.attribute_modifiers == other.attribute_modifiers
+            && self.potion_contents == other.potion_contents
+            && self.banner_patterns == other.banner_patterns
+            && self.custom_data == other.custom_data
+    }
+
+    /// Returns true if this item can lose durability.
+    pub fn is_damageable(&self) -> bool {
+        self.max_damage > 0 && !self.unbreakable
     }
 
     /// Returns true if this item is damageable and has taken some damage.
@@ -239,3 +675,48 @@ impl ItemStack {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_legacy() {
+        let text = TextComponent::from_legacy("&aHello &l&nworld&r!", '&');
+        assert_eq!(text.plain_text(), "Hello world!");
+        assert_eq!(text.extra[0], TextComponent::colored("Hello ", "green"));
+        assert_eq!(
+            text.extra[1],
+            TextComponent::colored("world", "green").bold(true).underlined(true)
+        );
+        assert_eq!(text.extra[2], TextComponent::plain("!"));
+
+        let hex = TextComponent::from_legacy("&#ff8800Orange", '&');
+        assert_eq!(hex, TextComponent::colored("Orange", "#FF8800"));
+        assert_eq!(TextComponent::from_legacy("50&z & 1&", '&'), TextComponent::plain("50&z & 1&"));
+    }
+
+    #[test]
+    fn test_click_hover_json() {
+        let text = TextComponent::plain("Docs")
+            .click(ClickEvent::OpenUrl("https://example.com".into()))
+            .hover_text(TextComponent::plain("Open"));
+        let json = text.to_json();
+        assert!(json.contains(r#""clickEvent":{"action":"open_url","value":"https://example.com"}"#));
+        assert!(json.contains(r#""hoverEvent":{"action":"show_text","contents":{"text":"Open"}}"#));
+        assert_eq!(serde_json::from_str::<TextComponent>(&json).unwrap(), text);
+    }
+
+    #[test]
+    fn test_translatable_json() {
+        let text = TextComponent::translatable(
+            "multiplayer.disconnect.banned.reason",
+            vec![TextComponent::plain("Griefing")],
+        );
+        assert_eq!(
+            text.to_json(),
+            r#"{"text":"","translate":"multiplayer.disconnect.banned.reason","with":[{"text":"Griefing"}]}"#
+        );
+        assert_eq!(text.plain_text(), "multiplayer.disconnect.banned.reason[Griefing]");
+    }
+}
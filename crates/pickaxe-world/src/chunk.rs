@@ -1,4 +1,5 @@
 use bytes::{BufMut, BytesMut};
+use pickaxe_data::registries::Registries;
 use pickaxe_nbt::{nbt_compound, NbtValue};
 use pickaxe_protocol_core::{write_varint, ChunkLightData, InternalPacket};
 use std::collections::HashMap;
@@ -8,6 +9,131 @@ pub const SECTION_COUNT: usize = 24;
 /// Minimum Y coordinate.
 pub const MIN_Y: i32 = -64;
 
+/// A section's biomes, one per 4x4x4 cell. They are kept by name so a
+/// chunk from a vanilla world keeps its biomes when saved, even where the
+/// server doesn't register them.
+#[derive(Clone, Default)]
+pub struct SectionBiomes {
+    /// Biome names; empty means plains.
+    pub palette: Vec<String>,
+    /// Palette index of each cell ([y][z][x], 64 entries); empty when the
+    /// palette has a single entry.
+    pub cells: Vec<u8>,
+}
+
+impl SectionBiomes {
+    /// Read the `biomes` compound of an Anvil section.
+    fn from_nbt(nbt: &NbtValue) -> Self {
+        let palette: Vec<String> = nbt
+            .get("palette")
+            .and_then(|v| v.as_list())
+            .map(|list| list.iter().filter_map(|v| v.as_str().map(str::to_string)).collect())
+            .unwrap_or_default();
+        let cells = match nbt.get("data").and_then(|v| v.as_long_array()) {
+            Some(data) if palette.len() > 1 => unpack(data, ceil_log2(palette.len()), 64)
+                .into_iter()
+                .map(|index| if (index as usize) < palette.len() { index as u8 } else { 0 })
+                .collect(),
+            _ => Vec::new(),
+        };
+        Self { palette, cells }
+    }
+
+    fn to_nbt(&self) -> NbtValue {
+        if self.palette.is_empty() {
+            return nbt_compound! {
+                "palette" => NbtValue::List(vec![NbtValue::String("minecraft:plains".into())])
+            };
+        }
+        let mut entries = vec![(
+            "palette".into(),
+            NbtValue::List(self.palette.iter().map(|name| NbtValue::String(name.clone())).collect()),
+        )];
+        if self.palette.len() > 1 && !self.cells.is_empty() {
+            let indices: Vec<u32> = self.cells.iter().map(|&c| c as u32).collect();
+            entries.push(("data".into(), NbtValue::LongArray(pack(&indices, ceil_log2(self.palette.len())))));
+        }
+        NbtValue::Compound(entries)
+    }
+
+    /// Write the biomes paletted container of the chunk data packet. Biomes
+    /// the registries don't have are sent as the first one.
+    fn write_to(&self, buf: &mut BytesMut, registries: &Registries) {
+        let resolve = |name: &String| registries.biome_id(name).unwrap_or(0);
+        let mut ids: Vec<i32> = Vec::new();
+        let mut cell_ids = [0u32; 64];
+        if self.cells.is_empty() {
+            ids.push(self.palette.first().map(resolve).unwrap_or(0));
+        } else {
+            let resolved: Vec<i32> = self.palette.iter().map(resolve).collect();
+            for (cell, &index) in cell_ids.iter_mut().zip(&self.cells) {
+                let id = resolved.get(index as usize).copied().unwrap_or(0);
+                *cell = match ids.iter().position(|&known| known == id) {
+                    Some(position) => position as u32,
+                    None => {
+                        ids.push(id);
+                        ids.len() as u32 - 1
+                    }
+                };
+            }
+        }
+
+        if ids.len() == 1 {
+            buf.put_u8(0);
+            write_varint(buf, ids[0]);
+            write_varint(buf, 0);
+            return;
+        }
+        let bits = ceil_log2(ids.len());
+        let data = if bits <= 3 {
+            // Indirect: a palette of biome IDs
+            buf.put_u8(bits as u8);
+            write_varint(buf, ids.len() as i32);
+            for &id in &ids {
+                write_varint(buf, id);
+            }
+            pack(&cell_ids, bits)
+        } else {
+            // Direct: registry IDs, sized to the whole registry
+            let bits = ceil_log2(registries.biomes.len());
+            buf.put_u8(bits as u8);
+            let direct: Vec<u32> = cell_ids.iter().map(|&i| ids[i as usize] as u32).collect();
+            pack(&direct, bits)
+        };
+        write_varint(buf, data.len() as i32);
+        for long in data {
+            buf.put_i64(long);
+        }
+    }
+}
+
+/// Bits needed to index `n` values (0 for a single value).
+fn ceil_log2(n: usize) -> u32 {
+    usize::BITS - n.saturating_sub(1).leading_zeros()
+}
+
+/// Pack values into longs without spanning long boundaries, as Anvil and
+/// the chunk data packet do.
+fn pack(values: &[u32], bits: u32) -> Vec<i64> {
+    let per_long = (64 / bits) as usize;
+    let mut data = vec![0i64; values.len().div_ceil(per_long)];
+    for (i, &value) in values.iter().enumerate() {
+        let shift = (i % per_long) as u32 * bits;
+        data[i / per_long] |= ((value as u64 & ((1u64 << bits) - 1)) << shift) as i64;
+    }
+    data
+}
+
+fn unpack(data: &[i64], bits: u32, count: usize) -> Vec<u32> {
+    let per_long = (64 / bits) as usize;
+    (0..count)
+        .map(|i| {
+            let long = data.get(i / per_long).copied().unwrap_or(0) as u64;
+            ((long >> ((i % per_long) as u32 * bits)) & ((1u64 << bits) - 1)) as u32
+        })
+        .collect()
+}
+
 /// A 16x16x16 chunk section.
 #[derive(Clone)]
 pub struct ChunkSection {
@@ -20,6 +146,7 @@ pub struct ChunkSection {
     pub block_data: Option<Vec<i64>>,
     /// Bits per entry for the block data.
     pub bits_per_entry: u8,
+    pub biomes: SectionBiomes,
     /// Flat block state array for mutation. Populated on first set_block call.
     /// Layout: [y * 256 + z * 16 + x] = state_id
     blocks: Option<Box<[i32; 4096]>>,
@@ -34,6 +161,7 @@ impl ChunkSection {
             block_data: None,
             bits_per_entry: 0,
             blocks: None,
+            biomes: SectionBiomes::default(),
         }
     }
 
@@ -45,6 +173,7 @@ impl ChunkSection {
             block_data: None,
             bits_per_entry: 0,
             blocks: None,
+            biomes: SectionBiomes::default(),
         }
     }
 
@@ -89,6 +218,7 @@ impl ChunkSection {
             block_data: Some(data),
             bits_per_entry,
             blocks: None,
+            biomes: SectionBiomes::default(),
         }
     }
 
@@ -198,8 +328,9 @@ impl ChunkSection {
         self.bits_per_entry = bits_per_entry;
     }
 
-    /// Serialize this section for the chunk data packet.
-    pub fn write_to(&self, buf: &mut BytesMut) {
+    /// Serialize this section for the chunk data packet, with biome IDs
+    /// from `registries`.
+    pub fn write_to(&self, buf: &mut BytesMut, registries: &Registries) {
         buf.put_i16(self.block_count);
 
         // Block states — paletted container
@@ -223,14 +354,12 @@ impl ChunkSection {
             }
         }
 
-        // Biomes — single-valued (plains = 0)
-        buf.put_u8(0); // bits per entry = 0 (single value)
-        write_varint(buf, 0); // palette entry: biome ID 0 (plains)
-        write_varint(buf, 0); // data array length = 0
+        self.biomes.write_to(buf, registries);
     }
 }
 
 /// A full chunk column (24 sections).
+#[derive(Clone)]
 pub struct Chunk {
     pub sections: Vec<ChunkSection>,
 }
@@ -243,10 +372,10 @@ impl Chunk {
     }
 
     /// Serialize all sections into the chunk data byte array.
-    pub fn serialize_sections(&self) -> Vec<u8> {
+    pub fn serialize_sections(&self, registries: &Registries) -> Vec<u8> {
         let mut buf = BytesMut::new();
         for section in &self.sections {
-            section.write_to(&mut buf);
+            section.write_to(&mut buf, registries);
         }
         buf.to_vec()
     }
@@ -273,25 +402,29 @@ impl Chunk {
         self.sections[section_idx].set_block(local_x, local_y, local_z, state_id)
     }
 
+    /// World Y of the first air block above the topmost non-air block in a column
+    /// (MIN_Y if the column is empty). Blocks at or above this Y see the sky.
+    pub fn height_at(&self, local_x: usize, local_z: usize) -> i32 {
+        for section_idx in (0..SECTION_COUNT).rev() {
+            if self.sections[section_idx].block_count == 0 {
+                continue;
+            }
+            for local_y in (0..16).rev() {
+                if self.sections[section_idx].get_block(local_x, local_y, local_z) != 0 {
+                    return MIN_Y + (section_idx as i32) * 16 + local_y as i32 + 1;
+                }
+            }
+        }
+        MIN_Y
+    }
+
     /// Build a heightmap for MOTION_BLOCKING.
     /// Returns packed long array (256 entries, 9 bits each for 384 height range).
     pub fn compute_heightmap(&self) -> Vec<i64> {
         let mut heights = [0u16; 256]; // 16x16
-
-        // Scan from top to bottom for each column
         for x in 0..16 {
             for z in 0..16 {
-                let col_idx = z * 16 + x;
-                'scan: for section_idx in (0..SECTION_COUNT).rev() {
-                    for local_y in (0..16).rev() {
-                        let block_state = self.sections[section_idx].get_block(x, local_y, z);
-                        if block_state != 0 {
-                            let world_y = MIN_Y + (section_idx as i32) * 16 + local_y as i32;
-                            heights[col_idx] = (world_y - MIN_Y + 1) as u16;
-                            break 'scan;
-                        }
-                    }
-                }
+                heights[z * 16 + x] = (self.height_at(x, z) - MIN_Y) as u16;
             }
         }
 
@@ -317,7 +450,7 @@ impl Chunk {
 
         let mut sections_list = Vec::new();
 
-        for (i, _section) in self.sections.iter().enumerate() {
+        for (i, section) in self.sections.iter().enumerate() {
             let section_y = (i as i32) + (MIN_Y / 16); // -4 to 19
 
             // Build palette NBT from actual block data
@@ -384,6 +517,7 @@ impl Chunk {
             sections_list.push(NbtValue::Compound(vec![
                 ("Y".into(), NbtValue::Byte(section_y as i8)),
                 ("block_states".into(), NbtValue::Compound(block_states_entries)),
+                ("biomes".into(), section.biomes.to_nbt()),
             ]));
         }
 
@@ -405,6 +539,14 @@ impl Chunk {
 
     /// Deserialize a chunk from Anvil NBT.
     pub fn from_nbt(nbt: &NbtValue) -> Option<Self> {
+        // Vanilla saves chunks around the generated area before they have
+        // terrain; treat those as missing so they are generated instead
+        if let Some(status) = nbt.get("Status").and_then(|v| v.as_str()) {
+            let status = status.strip_prefix("minecraft:").unwrap_or(status);
+            if matches!(status, "empty" | "structure_starts" | "structure_references" | "biomes") {
+                return None;
+            }
+        }
         let sections_nbt = nbt.get("sections")?.as_list()?;
         let mut chunk = Chunk::new();
 
@@ -418,6 +560,7 @@ impl Chunk {
             if section_idx >= SECTION_COUNT {
                 continue;
             }
+            let biomes = section_nbt.get("biomes").map(SectionBiomes::from_nbt).unwrap_or_default();
 
             let block_states = match section_nbt.get("block_states") {
                 Some(bs) => bs,
@@ -474,6 +617,7 @@ impl Chunk {
                 }
                 chunk.sections[section_idx] = ChunkSection::from_blocks(&blocks);
             }
+            chunk.sections[section_idx].biomes = biomes;
         }
 
         Some(chunk)
@@ -494,8 +638,8 @@ impl Chunk {
     }
 
     /// Build the full chunk data + light packet.
-    pub fn to_packet(&self, chunk_x: i32, chunk_z: i32) -> InternalPacket {
-        let data = self.serialize_sections();
+    pub fn to_packet(&self, chunk_x: i32, chunk_z: i32, registries: &Registries) -> InternalPacket {
+        let data = self.serialize_sections(registries);
         let heightmap_data = self.compute_heightmap();
 
         let heightmaps = nbt_compound! {
@@ -535,7 +679,7 @@ mod tests {
     fn test_empty_section_serialize() {
         let section = ChunkSection::empty();
         let mut buf = BytesMut::new();
-        section.write_to(&mut buf);
+        section.write_to(&mut buf, &Registries::default());
         // Should have block_count(2) + bits_per_entry(1) + palette_varint + data_len_varint + biome data
         assert!(buf.len() > 0);
     }
@@ -558,7 +702,7 @@ mod tests {
         assert!(section.block_count > 1024); // more blocks than the old 4-layer layout
 
         let mut buf = BytesMut::new();
-        section.write_to(&mut buf);
+        section.write_to(&mut buf, &Registries::default());
         let block_count_val = i16::from_be_bytes([buf[0], buf[1]]);
         assert!(block_count_val > 1024);
     }
@@ -574,6 +718,16 @@ mod tests {
         assert_eq!(heightmap.len(), 37); // ceil(256/7) = 37
     }
 
+    #[test]
+    fn test_height_at() {
+        let mut chunk = Chunk::new();
+        assert_eq!(chunk.height_at(3, 4), MIN_Y);
+        chunk.set_block(3, 10, 4, 1);
+        chunk.set_block(3, -20, 4, 1);
+        assert_eq!(chunk.height_at(3, 4), 11);
+        assert_eq!(chunk.height_at(4, 4), MIN_Y);
+    }
+
     #[test]
     fn test_section_get_block() {
         let section = ChunkSection::single_value(1); // all stone
@@ -620,7 +774,7 @@ mod tests {
         let mut chunk = generate_flat_chunk();
         // Break a block and verify serialization still works
         chunk.set_block(8, -51, 8, AIR);
-        let data = chunk.serialize_sections();
+        let data = chunk.serialize_sections(&Registries::default());
         assert!(!data.is_empty());
     }
 
@@ -653,6 +807,47 @@ mod tests {
         assert_eq!(restored.get_block(0, -51, 0), GRASS_BLOCK);
     }
 
+    #[test]
+    fn test_vanilla_chunk_nbt() {
+        use crate::generator::STONE;
+        let cells: Vec<u32> = (0..64).map(|i| (i >= 32) as u32).collect();
+        let section = nbt_compound! {
+            "Y" => NbtValue::Byte(0),
+            "block_states" => nbt_compound! {
+                "palette" => NbtValue::List(vec![nbt_compound! { "Name" => NbtValue::String("minecraft:stone".into()) }])
+            },
+            "biomes" => nbt_compound! {
+                "palette" => NbtValue::List(vec![
+                    NbtValue::String("minecraft:plains".into()),
+                    NbtValue::String("minecraft:desert".into()),
+                ]),
+                "data" => NbtValue::LongArray(pack(&cells, 1))
+            }
+        };
+        let mut nbt = nbt_compound! {
+            "Status" => NbtValue::String("minecraft:full".into()),
+            "sections" => NbtValue::List(vec![section])
+        };
+        let chunk = Chunk::from_nbt(&nbt).unwrap();
+        assert_eq!(chunk.get_block(0, 0, 0), STONE);
+        assert_eq!(chunk.sections[4].biomes.cells[40], 1);
+
+        // Saved and loaded again, the biomes are kept
+        let restored = Chunk::from_nbt(&chunk.to_nbt(0, 0, 0)).unwrap();
+        assert_eq!(restored.sections[4].biomes.palette[1], "minecraft:desert");
+        assert_eq!(restored.sections[4].biomes.cells, chunk.sections[4].biomes.cells);
+
+        // Unregistered biomes fall back to plains, leaving a single value
+        let mut buf = BytesMut::new();
+        chunk.sections[4].biomes.write_to(&mut buf, &Registries::default());
+        assert_eq!(&buf[..], &[0, 0, 0]);
+
+        if let NbtValue::Compound(entries) = &mut nbt {
+            entries[0].1 = NbtValue::String("minecraft:biomes".into());
+        }
+        assert!(Chunk::from_nbt(&nbt).is_none());
+    }
+
     #[test]
     fn test_chunk_nbt_empty_sections() {
         use crate::generator::AIR;
@@ -166,7 +166,7 @@ mod tests {
     #[test]
     fn test_flat_chunk_serializes() {
         let chunk = generate_flat_chunk();
-        let data = chunk.serialize_sections();
+        let data = chunk.serialize_sections(&pickaxe_data::registries::Registries::default());
         assert!(!data.is_empty());
     }
 
@@ -1,5 +1,11 @@
 mod chunk;
 mod generator;
+mod poi;
+mod schematic;
+mod structure;
 
 pub use chunk::*;
 pub use generator::*;
+pub use poi::*;
+pub use schematic::*;
+pub use structure::*;
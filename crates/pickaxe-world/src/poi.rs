@@ -0,0 +1,225 @@
+use crate::{Chunk, MIN_Y, SECTION_COUNT};
+use pickaxe_nbt::{nbt_compound, NbtValue};
+use pickaxe_types::BlockPos;
+use std::collections::{BTreeMap, HashMap};
+
+const DATA_VERSION: i32 = 3955; // MC 1.21.1
+
+/// Vanilla's point-of-interest types: (type, blocks, tickets). Tickets are how
+/// many villagers can claim the POI at once.
+const POI_TYPES: &[(&str, &[&str], i32)] = &[
+    ("minecraft:armorer", &["blast_furnace"], 1),
+    ("minecraft:butcher", &["smoker"], 1),
+    ("minecraft:cartographer", &["cartography_table"], 1),
+    ("minecraft:cleric", &["brewing_stand"], 1),
+    ("minecraft:farmer", &["composter"], 1),
+    ("minecraft:fisherman", &["barrel"], 1),
+    ("minecraft:fletcher", &["fletching_table"], 1),
+    ("minecraft:leatherworker", &["cauldron", "water_cauldron", "lava_cauldron", "powder_snow_cauldron"], 1),
+    ("minecraft:librarian", &["lectern"], 1),
+    ("minecraft:mason", &["stonecutter"], 1),
+    ("minecraft:shepherd", &["loom"], 1),
+    ("minecraft:toolsmith", &["smithing_table"], 1),
+    ("minecraft:weaponsmith", &["grindstone"], 1),
+    ("minecraft:home", &[], 1),
+    ("minecraft:meeting", &["bell"], 32),
+    ("minecraft:beehive", &["beehive"], 0),
+    ("minecraft:bee_nest", &["bee_nest"], 0),
+    ("minecraft:nether_portal", &["nether_portal"], 0),
+    ("minecraft:lodestone", &["lodestone"], 0),
+    ("minecraft:lightning_rod", &["lightning_rod"], 0),
+];
+
+/// The POI type a block state is, if any. Beds count only at their head.
+pub fn poi_type(state_id: i32) -> Option<&'static str> {
+    if pickaxe_data::is_bed(state_id) {
+        return pickaxe_data::bed_is_head(state_id).then_some("minecraft:home");
+    }
+    let name = pickaxe_data::block_state_to_name(state_id)?;
+    POI_TYPES.iter().find(|(_, blocks, _)| blocks.contains(&name)).map(|(poi, _, _)| *poi)
+}
+
+/// How many villagers can claim a POI of this type.
+pub fn poi_max_tickets(poi_type: &str) -> i32 {
+    POI_TYPES.iter().find(|(poi, _, _)| *poi == poi_type).map_or(0, |(_, _, tickets)| *tickets)
+}
+
+/// A point of interest: a block villagers, portals or lightning look for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoiRecord {
+    pub pos: BlockPos,
+    /// Namespaced type, like `minecraft:home`.
+    pub poi_type: String,
+    /// Tickets not claimed by a villager.
+    pub free_tickets: i32,
+}
+
+/// The points of interest in one chunk column, stored in vanilla's `poi/`
+/// region files next to the chunk's own.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChunkPoi {
+    records: Vec<PoiRecord>,
+}
+
+impl ChunkPoi {
+    /// Find the POIs in a chunk's blocks, for chunks without saved POI data.
+    /// Sections whose palette has no POI block are skipped.
+    pub fn scan(chunk: &Chunk, chunk_x: i32, chunk_z: i32) -> Self {
+        let mut poi = Self::default();
+        for (section_index, section) in chunk.sections.iter().enumerate() {
+            let types: HashMap<i32, &str> = section
+                .palette
+                .iter()
+                .filter_map(|&state| poi_type(state).map(|poi| (state, poi)))
+                .collect();
+            if types.is_empty() {
+                continue;
+            }
+            for y in 0..16 {
+                for z in 0..16 {
+                    for x in 0..16 {
+                        if let Some(poi_type) = types.get(&section.get_block(x, y, z)) {
+                            let pos = BlockPos::new(
+                                chunk_x * 16 + x as i32,
+                                MIN_Y + section_index as i32 * 16 + y as i32,
+                                chunk_z * 16 + z as i32,
+                            );
+                            poi.set(pos, Some(poi_type));
+                        }
+                    }
+                }
+            }
+        }
+        poi
+    }
+
+    /// Read a `poi/` chunk. `None` if a section is marked invalid, meaning
+    /// the chunk should be scanned again.
+    pub fn from_nbt(nbt: &NbtValue) -> Option<Self> {
+        let mut poi = Self::default();
+        let Some(NbtValue::Compound(sections)) = nbt.get("Sections") else {
+            return Some(poi);
+        };
+        for (_, section) in sections {
+            if section.get("Valid").and_then(|v| v.as_byte()) != Some(1) {
+                return None;
+            }
+            for record in section.get("Records").and_then(|v| v.as_list()).unwrap_or(&[]) {
+                let Some(&[x, y, z]) = record.get("pos").and_then(|v| v.as_int_array()) else {
+                    continue;
+                };
+                let Some(poi_type) = record.get("type").and_then(|v| v.as_str()) else {
+                    continue;
+                };
+                poi.records.push(PoiRecord {
+                    pos: BlockPos::new(x, y, z),
+                    poi_type: poi_type.to_string(),
+                    free_tickets: record.get("free_tickets").and_then(|v| v.as_int()).unwrap_or(0),
+                });
+            }
+        }
+        Some(poi)
+    }
+
+    /// The root compound of a `poi/` chunk, written unnamed.
+    pub fn to_nbt(&self) -> NbtValue {
+        let mut sections: BTreeMap<i32, Vec<NbtValue>> = BTreeMap::new();
+        // Every section is written so stale records on disk are replaced
+        for section_y in 0..SECTION_COUNT as i32 {
+            sections.insert((MIN_Y >> 4) + section_y, Vec::new());
+        }
+        for record in &self.records {
+            sections.entry(record.pos.y >> 4).or_default().push(nbt_compound! {
+                "pos" => NbtValue::IntArray(vec![record.pos.x, record.pos.y, record.pos.z]),
+                "type" => NbtValue::String(record.poi_type.clone()),
+                "free_tickets" => NbtValue::Int(record.free_tickets)
+            });
+        }
+        let sections = sections
+            .into_iter()
+            .map(|(y, records)| {
+                let section = nbt_compound! {
+                    "Valid" => NbtValue::Byte(1),
+                    "Records" => NbtValue::List(records)
+                };
+                (y.to_string(), section)
+            })
+            .collect();
+        nbt_compound! {
+            "DataVersion" => NbtValue::Int(DATA_VERSION),
+            "Sections" => NbtValue::Compound(sections)
+        }
+    }
+
+    /// Record the POI type of the block now at `pos`, or remove its record
+    /// for `None`. A POI replaced by the same type keeps its free tickets.
+    /// Returns whether anything changed.
+    pub fn set(&mut self, pos: BlockPos, poi_type: Option<&str>) -> bool {
+        let existing = self.records.iter().position(|r| r.pos == pos);
+        match (existing, poi_type) {
+            (Some(i), Some(poi_type)) if self.records[i].poi_type == poi_type => false,
+            (Some(i), Some(poi_type)) => {
+                self.records[i] = PoiRecord { pos, poi_type: poi_type.to_string(), free_tickets: poi_max_tickets(poi_type) };
+                true
+            }
+            (Some(i), None) => {
+                self.records.swap_remove(i);
+                true
+            }
+            (None, Some(poi_type)) => {
+                self.records.push(PoiRecord { pos, poi_type: poi_type.to_string(), free_tickets: poi_max_tickets(poi_type) });
+                true
+            }
+            (None, None) => false,
+        }
+    }
+
+    pub fn get(&self, pos: BlockPos) -> Option<&PoiRecord> {
+        self.records.iter().find(|r| r.pos == pos)
+    }
+
+    pub fn get_mut(&mut self, pos: BlockPos) -> Option<&mut PoiRecord> {
+        self.records.iter_mut().find(|r| r.pos == pos)
+    }
+
+    pub fn records(&self) -> &[PoiRecord] {
+        &self.records
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_and_round_trip() {
+        let lectern = pickaxe_data::block_name_to_default_state("lectern").unwrap();
+        let rod = pickaxe_data::block_name_to_default_state("lightning_rod").unwrap();
+        assert_eq!(poi_type(lectern), Some("minecraft:librarian"));
+        assert_eq!(poi_type(pickaxe_data::block_name_to_default_state("stone").unwrap()), None);
+        assert_eq!(poi_type(pickaxe_data::bed_state(1688, 0, false, true)), Some("minecraft:home"));
+        assert_eq!(poi_type(pickaxe_data::bed_state(1688, 0, false, false)), None);
+
+        let mut chunk = Chunk::new();
+        chunk.set_block(3, -60, 4, lectern);
+        chunk.set_block(15, 100, 0, rod);
+        let mut poi = ChunkPoi::scan(&chunk, -1, 2);
+        assert_eq!(poi.records().len(), 2);
+        assert_eq!(poi.get(BlockPos::new(-13, -60, 36)).unwrap().free_tickets, 1);
+        assert_eq!(poi.get(BlockPos::new(-1, 100, 32)).unwrap().poi_type, "minecraft:lightning_rod");
+
+        poi.get_mut(BlockPos::new(-13, -60, 36)).unwrap().free_tickets = 0;
+        assert!(!poi.set(BlockPos::new(-13, -60, 36), Some("minecraft:librarian")));
+        assert!(poi.set(BlockPos::new(-1, 100, 32), None));
+        assert_eq!(ChunkPoi::from_nbt(&poi.to_nbt()), Some(poi));
+
+        let invalid = nbt_compound! {
+            "Sections" => nbt_compound! { "0" => nbt_compound! { "Valid" => NbtValue::Byte(0) } }
+        };
+        assert_eq!(ChunkPoi::from_nbt(&invalid), None);
+    }
+}
@@ -0,0 +1,260 @@
+use pickaxe_nbt::{nbt_compound, NbtValue};
+use pickaxe_protocol_core::write_varint_vec;
+use std::collections::HashMap;
+
+/// Sponge schematic version written by [`Schematic::to_nbt`].
+const SPONGE_VERSION: i32 = 3;
+const DATA_VERSION: i32 = 3955; // MC 1.21.1
+
+/// A box of blocks in the Sponge schematic format (`.schem`, versions 2 and
+/// 3), as written by WorldEdit and most map tools.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schematic {
+    pub width: u16,
+    pub height: u16,
+    pub length: u16,
+    /// Where the minimum corner goes relative to the paste position.
+    pub offset: [i32; 3],
+    /// Block states, indexed `x + z * width + y * width * length`.
+    blocks: Vec<i32>,
+    pub block_entities: Vec<SchematicBlockEntity>,
+}
+
+/// A block entity in a schematic: its position in the schematic, its type
+/// and its chunk-format fields other than `id` and the position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchematicBlockEntity {
+    pub pos: [i32; 3],
+    pub id: String,
+    pub data: NbtValue,
+}
+
+impl Schematic {
+    /// An all-air schematic.
+    pub fn new(width: u16, height: u16, length: u16) -> Self {
+        Self {
+            width,
+            height,
+            length,
+            offset: [0, 0, 0],
+            blocks: vec![0; width as usize * height as usize * length as usize],
+            block_entities: Vec::new(),
+        }
+    }
+
+    fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        x + z * self.width as usize + y * self.width as usize * self.length as usize
+    }
+
+    pub fn get_block(&self, x: usize, y: usize, z: usize) -> i32 {
+        self.blocks[self.index(x, y, z)]
+    }
+
+    pub fn set_block(&mut self, x: usize, y: usize, z: usize, state_id: i32) {
+        let i = self.index(x, y, z);
+        self.blocks[i] = state_id;
+    }
+
+    /// Every block with its position in the schematic, bottom layer first.
+    pub fn blocks(&self) -> impl Iterator<Item = ([i32; 3], i32)> + '_ {
+        let (w, l) = (self.width as usize, self.length as usize);
+        self.blocks
+            .iter()
+            .enumerate()
+            .map(move |(i, &state)| ([(i % w) as i32, (i / (w * l)) as i32, (i / w % l) as i32], state))
+    }
+
+    /// Read a schematic from its root compound. Block states this server
+    /// doesn't know become air; the second value counts them.
+    pub fn from_nbt(root: &NbtValue) -> Result<(Self, usize), String> {
+        // Version 3 nests everything in a "Schematic" compound
+        let nbt = root.get("Schematic").unwrap_or(root);
+        let version = nbt.get("Version").and_then(|v| v.as_int()).ok_or("Not a Sponge schematic: no Version")?;
+        let dimension = |key: &str| {
+            nbt.get(key)
+                .and_then(|v| v.as_short())
+                .map(|v| v as u16)
+                .ok_or_else(|| format!("Missing {}", key))
+        };
+        let mut schematic = Self::new(dimension("Width")?, dimension("Height")?, dimension("Length")?);
+        if let Some([x, y, z]) = nbt.get("Offset").and_then(|v| v.as_int_array()).and_then(|o| <[i32; 3]>::try_from(o).ok()) {
+            schematic.offset = [x, y, z];
+        }
+
+        let (blocks, entities_key) = match version {
+            2 => (nbt, "BlockEntities"),
+            3 => (nbt.get("Blocks").ok_or("Missing Blocks")?, "BlockEntities"),
+            1 => (nbt, "TileEntities"),
+            v => return Err(format!("Unsupported schematic version {}", v)),
+        };
+        let palette = blocks.get("Palette").ok_or("Missing Palette")?;
+        let data = blocks
+            .get(if version == 3 { "Data" } else { "BlockData" })
+            .and_then(|v| v.as_byte_array())
+            .ok_or("Missing block data")?;
+
+        let mut unknown = 0;
+        let mut states = HashMap::new();
+        if let NbtValue::Compound(entries) = palette {
+            for (state, index) in entries {
+                let id = parse_block_state(state).unwrap_or_else(|| {
+                    unknown += 1;
+                    0
+                });
+                states.insert(index.as_int().ok_or("Bad palette index")?, id);
+            }
+        }
+
+        let mut bytes = data.iter().map(|&b| b as u8);
+        for block in schematic.blocks.iter_mut() {
+            let index = read_varint(&mut bytes).ok_or("Block data ends early")?;
+            *block = *states.get(&index).ok_or_else(|| format!("Palette index {} out of range", index))?;
+        }
+
+        for entity in blocks.get(entities_key).and_then(|v| v.as_list()).unwrap_or(&[]) {
+            let Some(pos) = entity.get("Pos").and_then(|v| v.as_int_array()).and_then(|p| <[i32; 3]>::try_from(p).ok()) else {
+                continue;
+            };
+            let id = entity.get("Id").and_then(|v| v.as_str()).unwrap_or("").to_string();
+            // Version 3 keeps the fields under Data; earlier ones inline them
+            let data = match entity.get("Data") {
+                Some(data) if version == 3 => data.clone(),
+                _ => {
+                    let mut data = entity.clone();
+                    for key in ["Pos", "Id", "Extra"] {
+                        data.remove(key);
+                    }
+                    data
+                }
+            };
+            schematic.block_entities.push(SchematicBlockEntity { pos, id, data });
+        }
+        Ok((schematic, unknown))
+    }
+
+    /// The root compound of a version 3 schematic, written unnamed.
+    pub fn to_nbt(&self) -> NbtValue {
+        let mut palette: Vec<(String, NbtValue)> = Vec::new();
+        let mut indices = HashMap::new();
+        let mut data = Vec::new();
+        for &state in &self.blocks {
+            let index = *indices.entry(state).or_insert_with(|| {
+                palette.push((block_state_string(state), NbtValue::Int(palette.len() as i32)));
+                palette.len() as i32 - 1
+            });
+            write_varint_vec(&mut data, index);
+        }
+        let block_entities = self
+            .block_entities
+            .iter()
+            .map(|be| {
+                nbt_compound! {
+                    "Pos" => NbtValue::IntArray(be.pos.to_vec()),
+                    "Id" => NbtValue::String(be.id.clone()),
+                    "Data" => be.data.clone()
+                }
+            })
+            .collect();
+
+        nbt_compound! {
+            "Schematic" => nbt_compound! {
+                "Version" => NbtValue::Int(SPONGE_VERSION),
+                "DataVersion" => NbtValue::Int(DATA_VERSION),
+                "Width" => NbtValue::Short(self.width as i16),
+                "Height" => NbtValue::Short(self.height as i16),
+                "Length" => NbtValue::Short(self.length as i16),
+                "Offset" => NbtValue::IntArray(self.offset.to_vec()),
+                "Blocks" => nbt_compound! {
+                    "Palette" => NbtValue::Compound(palette),
+                    "Data" => NbtValue::ByteArray(data.into_iter().map(|b| b as i8).collect()),
+                    "BlockEntities" => NbtValue::List(block_entities)
+                }
+            }
+        }
+    }
+}
+
+/// A state as `minecraft:name[key=value,...]`, the form schematic palettes use.
+pub fn block_state_string(state_id: i32) -> String {
+    match pickaxe_data::block_state_to_properties(state_id) {
+        Some((name, props)) if props.is_empty() => format!("minecraft:{}", name),
+        Some((name, props)) => {
+            let props: Vec<String> = props.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+            format!("minecraft:{}[{}]", name, props.join(","))
+        }
+        None => "minecraft:air".into(),
+    }
+}
+
+/// Parse a `minecraft:name[key=value,...]` block state. Properties left out
+/// take their first value.
+pub fn parse_block_state(state: &str) -> Option<i32> {
+    let (name, props) = match state.split_once('[') {
+        Some((name, rest)) => (name, rest.strip_suffix(']')?),
+        None => (state, ""),
+    };
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    let props: Vec<(&str, &str)> = props.split(',').filter_map(|p| p.split_once('=')).collect();
+    pickaxe_data::block_name_with_properties_to_state(name, &props)
+}
+
+fn read_varint(bytes: &mut impl Iterator<Item = u8>) -> Option<i32> {
+    let mut value = 0i32;
+    for shift in (0..35).step_by(7) {
+        let byte = bytes.next()?;
+        value |= ((byte & 0x7F) as i32) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schematic_round_trip() {
+        let stairs = parse_block_state("minecraft:oak_stairs[facing=east,half=top,shape=straight,waterlogged=false]").unwrap();
+        assert_eq!(pickaxe_data::block_property(stairs, "facing"), Some("east"));
+        assert_eq!(parse_block_state(&block_state_string(stairs)), Some(stairs));
+
+        // 300 distinct states need two-byte varints
+        let mut schematic = Schematic::new(20, 2, 15);
+        for (i, (x, y, z)) in (0..2).flat_map(|y| (0..15).flat_map(move |z| (0..20).map(move |x| (x, y, z)))).enumerate() {
+            schematic.set_block(x, y, z, i as i32 % 300);
+        }
+        schematic.offset = [-1, 0, -2];
+        schematic.block_entities.push(SchematicBlockEntity {
+            pos: [1, 0, 2],
+            id: "minecraft:chest".into(),
+            data: nbt_compound! { "Items" => NbtValue::List(Vec::new()) },
+        });
+
+        let (read, unknown) = Schematic::from_nbt(&schematic.to_nbt()).unwrap();
+        assert_eq!(unknown, 0);
+        assert_eq!(read, schematic);
+        assert_eq!(read.blocks().nth(21), Some(([1, 0, 1], 21)));
+
+        // Version 2 keeps the palette at the top and block entity fields inline
+        let v2 = nbt_compound! {
+            "Version" => NbtValue::Int(2),
+            "Width" => NbtValue::Short(1),
+            "Height" => NbtValue::Short(1),
+            "Length" => NbtValue::Short(2),
+            "Palette" => nbt_compound! { "minecraft:stone" => NbtValue::Int(0), "minecraft:made_up" => NbtValue::Int(1) },
+            "BlockData" => NbtValue::ByteArray(vec![0, 1]),
+            "BlockEntities" => NbtValue::List(vec![nbt_compound! {
+                "Pos" => NbtValue::IntArray(vec![0, 0, 0]),
+                "Id" => NbtValue::String("minecraft:sign".into()),
+                "is_waxed" => NbtValue::Byte(1)
+            }])
+        };
+        let (read, unknown) = Schematic::from_nbt(&v2).unwrap();
+        assert_eq!(unknown, 1);
+        assert_eq!(read.get_block(0, 0, 0), pickaxe_data::block_name_to_default_state("stone").unwrap());
+        assert_eq!(read.get_block(0, 0, 1), 0);
+        assert_eq!(read.block_entities[0].data, nbt_compound! { "is_waxed" => NbtValue::Byte(1) });
+    }
+}
@@ -0,0 +1,311 @@
+use pickaxe_nbt::{nbt_compound, NbtValue};
+use std::collections::HashMap;
+
+const DATA_VERSION: i32 = 3955; // MC 1.21.1
+
+/// A vanilla structure template (`structures/*.nbt`), as saved by structure
+/// blocks and used by jigsaw structures.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StructureTemplate {
+    pub size: [i32; 3],
+    /// Every block but structure voids, which leave the world as it is.
+    pub blocks: Vec<StructureBlock>,
+    /// Entities, kept so templates survive a load and save; not placed.
+    pub entities: Vec<NbtValue>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructureBlock {
+    pub pos: [i32; 3],
+    pub state: i32,
+    /// Block entity fields with `id` and without the position.
+    pub nbt: Option<NbtValue>,
+}
+
+/// How a template is turned when placed, clockwise seen from above.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rotation {
+    #[default]
+    None,
+    Clockwise90,
+    Clockwise180,
+    Counterclockwise90,
+}
+
+impl Rotation {
+    /// Vanilla's names, as `/place template` takes them.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(Self::None),
+            "clockwise_90" => Some(Self::Clockwise90),
+            "180" => Some(Self::Clockwise180),
+            "counterclockwise_90" => Some(Self::Counterclockwise90),
+            _ => None,
+        }
+    }
+}
+
+/// How a template is flipped when placed, before it is rotated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mirror {
+    #[default]
+    None,
+    /// Flips along Z (north and south swap).
+    LeftRight,
+    /// Flips along X (east and west swap).
+    FrontBack,
+}
+
+impl Mirror {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(Self::None),
+            "left_right" => Some(Self::LeftRight),
+            "front_back" => Some(Self::FrontBack),
+            _ => None,
+        }
+    }
+}
+
+/// A mirror followed by a rotation about the template's origin.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Placement {
+    pub rotation: Rotation,
+    pub mirror: Mirror,
+}
+
+const HORIZONTAL: [&str; 4] = ["north", "east", "south", "west"];
+
+impl Placement {
+    /// Where a position in the template ends up, relative to the origin.
+    pub fn transform_pos(&self, [x, y, z]: [i32; 3]) -> [i32; 3] {
+        let (x, z) = match self.mirror {
+            Mirror::None => (x, z),
+            Mirror::LeftRight => (x, -z),
+            Mirror::FrontBack => (-x, z),
+        };
+        match self.rotation {
+            Rotation::None => [x, y, z],
+            Rotation::Clockwise90 => [-z, y, x],
+            Rotation::Clockwise180 => [-x, y, -z],
+            Rotation::Counterclockwise90 => [z, y, -x],
+        }
+    }
+
+    fn transform_direction(&self, direction: &str) -> Option<&'static str> {
+        let mut i = HORIZONTAL.iter().position(|d| *d == direction)?;
+        i = match (self.mirror, i % 2) {
+            (Mirror::LeftRight, 0) | (Mirror::FrontBack, 1) => (i + 2) % 4,
+            _ => i,
+        };
+        let turns = match self.rotation {
+            Rotation::None => 0,
+            Rotation::Clockwise90 => 1,
+            Rotation::Clockwise180 => 2,
+            Rotation::Counterclockwise90 => 3,
+        };
+        Some(HORIZONTAL[(i + turns) % 4])
+    }
+
+    /// A property value with its horizontal directions turned, e.g. a
+    /// `facing`, a rail `shape` like `south_east` or an `orientation` like
+    /// `north_up`.
+    fn transform_value(&self, value: &str) -> String {
+        let mut words: Vec<&str> = value
+            .split('_')
+            .map(|w| self.transform_direction(w).unwrap_or(w))
+            .collect();
+        // Two-direction values name north or south first
+        if let [a, b] = words[..] {
+            match (HORIZONTAL.iter().position(|d| *d == a), HORIZONTAL.iter().position(|d| *d == b)) {
+                (Some(i), Some(j)) if i % 2 == j % 2 => words = if i % 2 == 0 { vec!["north", "south"] } else { vec!["east", "west"] },
+                (Some(i), Some(_)) if i % 2 == 1 => words.swap(0, 1),
+                _ => {}
+            }
+        }
+        words.join("_")
+    }
+
+    /// A block state as it looks after the mirror and rotation: facings,
+    /// axes, sign rotations, connection sides and left/right shapes turn
+    /// with the template.
+    pub fn transform_state(&self, state_id: i32) -> i32 {
+        if *self == Self::default() {
+            return state_id;
+        }
+        let Some((name, props)) = pickaxe_data::block_state_to_properties(state_id) else {
+            return state_id;
+        };
+        let quarter_turn = matches!(self.rotation, Rotation::Clockwise90 | Rotation::Counterclockwise90);
+        let mut changed: Vec<(String, String)> = Vec::new();
+        for (key, value) in &props {
+            let key = self.transform_direction(key).unwrap_or(key);
+            let value = match (key, *value) {
+                ("axis", "x") if quarter_turn => "z".to_string(),
+                ("axis", "z") if quarter_turn => "x".to_string(),
+                ("rotation", r) => {
+                    let mut r: i32 = r.parse().unwrap_or(0);
+                    r = match self.mirror {
+                        Mirror::None => r,
+                        Mirror::LeftRight => 8 - r,
+                        Mirror::FrontBack => 16 - r,
+                    };
+                    r += match self.rotation {
+                        Rotation::None => 0,
+                        Rotation::Clockwise90 => 4,
+                        Rotation::Clockwise180 => 8,
+                        Rotation::Counterclockwise90 => 12,
+                    };
+                    r.rem_euclid(16).to_string()
+                }
+                // Mirroring swaps door hinges, double chest halves and stair corners
+                ("hinge" | "type" | "shape", v) if self.mirror != Mirror::None && (v.contains("left") || v.contains("right")) => {
+                    v.replace("left", "\0").replace("right", "left").replace('\0', "right")
+                }
+                (_, v) => self.transform_value(v),
+            };
+            changed.push((key.to_string(), value));
+        }
+        let props: Vec<(&str, &str)> = changed.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        pickaxe_data::block_name_with_properties_to_state(name, &props).unwrap_or(state_id)
+    }
+}
+
+impl StructureTemplate {
+    /// Read a template from its root compound. Block states this server
+    /// doesn't know become air; the second value counts them.
+    pub fn from_nbt(nbt: &NbtValue) -> Result<(Self, usize), String> {
+        let ints = |value: Option<&NbtValue>| -> Option<[i32; 3]> {
+            let list = value?.as_list()?;
+            let v: Vec<i32> = list.iter().filter_map(|i| i.as_int()).collect();
+            <[i32; 3]>::try_from(v).ok()
+        };
+        let size = ints(nbt.get("size")).ok_or("Missing size")?;
+        // Templates with several palettes pick one at random; use the first
+        let palette = match nbt.get("palette") {
+            Some(palette) => palette.as_list(),
+            None => nbt.get("palettes").and_then(|p| p.as_list()).and_then(|p| p.first()).and_then(|p| p.as_list()),
+        }
+        .ok_or("Missing palette")?;
+
+        let mut unknown = 0;
+        let states: Vec<i32> = palette
+            .iter()
+            .map(|entry| {
+                let name = entry.get("Name").and_then(|n| n.as_str()).unwrap_or("minecraft:air");
+                let name = name.strip_prefix("minecraft:").unwrap_or(name);
+                let props: Vec<(&str, &str)> = match entry.get("Properties") {
+                    Some(NbtValue::Compound(props)) => props.iter().filter_map(|(k, v)| Some((k.as_str(), v.as_str()?))).collect(),
+                    _ => Vec::new(),
+                };
+                pickaxe_data::block_name_with_properties_to_state(name, &props).unwrap_or_else(|| {
+                    unknown += 1;
+                    0
+                })
+            })
+            .collect();
+
+        let void = pickaxe_data::block_name_to_default_state("structure_void");
+        let mut template = Self { size, ..Default::default() };
+        for block in nbt.get("blocks").and_then(|b| b.as_list()).unwrap_or(&[]) {
+            let pos = ints(block.get("pos")).ok_or("Block without a pos")?;
+            let index = block.get("state").and_then(|s| s.as_int()).ok_or("Block without a state")?;
+            let state = *states.get(index as usize).ok_or_else(|| format!("Palette index {} out of range", index))?;
+            if Some(state) == void {
+                continue;
+            }
+            template.blocks.push(StructureBlock { pos, state, nbt: block.get("nbt").cloned() });
+        }
+        template.entities = nbt.get("entities").and_then(|e| e.as_list()).map(<[_]>::to_vec).unwrap_or_default();
+        Ok((template, unknown))
+    }
+
+    /// The template's root compound, written unnamed and gzipped.
+    pub fn to_nbt(&self) -> NbtValue {
+        let int_list = |v: [i32; 3]| NbtValue::List(v.iter().map(|&i| NbtValue::Int(i)).collect());
+        let mut palette = Vec::new();
+        let mut indices = HashMap::new();
+        let mut blocks = Vec::new();
+        for block in &self.blocks {
+            let index = *indices.entry(block.state).or_insert_with(|| {
+                palette.push(palette_entry(block.state));
+                palette.len() as i32 - 1
+            });
+            let mut entry = nbt_compound! {
+                "pos" => int_list(block.pos),
+                "state" => NbtValue::Int(index)
+            };
+            if let Some(nbt) = &block.nbt {
+                entry.set("nbt", nbt.clone());
+            }
+            blocks.push(entry);
+        }
+        nbt_compound! {
+            "DataVersion" => NbtValue::Int(DATA_VERSION),
+            "size" => int_list(self.size),
+            "palette" => NbtValue::List(palette),
+            "blocks" => NbtValue::List(blocks),
+            "entities" => NbtValue::List(self.entities.clone())
+        }
+    }
+}
+
+fn palette_entry(state_id: i32) -> NbtValue {
+    let Some((name, props)) = pickaxe_data::block_state_to_properties(state_id) else {
+        return nbt_compound! { "Name" => NbtValue::String("minecraft:air".into()) };
+    };
+    let mut entry = nbt_compound! { "Name" => NbtValue::String(format!("minecraft:{}", name)) };
+    if !props.is_empty() {
+        let props = props.iter().map(|(k, v)| (k.to_string(), NbtValue::String(v.to_string()))).collect();
+        entry.set("Properties", NbtValue::Compound(props));
+    }
+    entry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(name: &str, props: &[(&str, &str)]) -> i32 {
+        pickaxe_data::block_name_with_properties_to_state(name, props).unwrap()
+    }
+
+    #[test]
+    fn test_placement_transforms() {
+        let turn = Placement { rotation: Rotation::Clockwise90, mirror: Mirror::None };
+        assert_eq!(turn.transform_pos([2, 1, 0]), [0, 1, 2]);
+        let stairs = state("oak_stairs", &[("facing", "north"), ("half", "bottom"), ("shape", "inner_left"), ("waterlogged", "false")]);
+        assert_eq!(pickaxe_data::block_property(turn.transform_state(stairs), "facing"), Some("east"));
+        let log = state("oak_log", &[("axis", "x")]);
+        assert_eq!(pickaxe_data::block_property(turn.transform_state(log), "axis"), Some("z"));
+        let rail = state("rail", &[("shape", "south_east"), ("waterlogged", "false")]);
+        assert_eq!(pickaxe_data::block_property(turn.transform_state(rail), "shape"), Some("south_west"));
+
+        let flip = Placement { rotation: Rotation::None, mirror: Mirror::LeftRight };
+        assert_eq!(flip.transform_pos([1, 0, 3]), [1, 0, -3]);
+        let flipped = flip.transform_state(stairs);
+        assert_eq!(pickaxe_data::block_property(flipped, "facing"), Some("south"));
+        assert_eq!(pickaxe_data::block_property(flipped, "shape"), Some("inner_right"));
+        let fence = state("oak_fence", &[("north", "true"), ("east", "true"), ("south", "false"), ("west", "false"), ("waterlogged", "false")]);
+        let fence = flip.transform_state(fence);
+        assert_eq!(pickaxe_data::block_property(fence, "south"), Some("true"));
+        assert_eq!(pickaxe_data::block_property(fence, "north"), Some("false"));
+    }
+
+    #[test]
+    fn test_template_round_trip() {
+        let template = StructureTemplate {
+            size: [2, 1, 1],
+            blocks: vec![
+                StructureBlock { pos: [0, 0, 0], state: state("stone", &[]), nbt: None },
+                StructureBlock {
+                    pos: [1, 0, 0],
+                    state: state("chest", &[("facing", "west"), ("type", "single"), ("waterlogged", "false")]),
+                    nbt: Some(nbt_compound! { "id" => NbtValue::String("minecraft:chest".into()) }),
+                },
+            ],
+            entities: Vec::new(),
+        };
+        assert_eq!(StructureTemplate::from_nbt(&template.to_nbt()).unwrap(), (template, 0));
+    }
+}
@@ -4,7 +4,7 @@
 pickaxe.events.on("player_join", function(event)
     local name = event.name or "unknown"
     pickaxe.log("Player joined: " .. name)
-    pickaxe.players.broadcast(name .. " joined the game")
+    pickaxe.players.broadcast(pickaxe.placeholders.format("%player_name% joined the game", name))
     pickaxe.players.send_message(name, "Welcome to Pickaxe! Type /help for commands.")
 end, { priority = "NORMAL", mod_id = "pickaxe-vanilla" })
 
@@ -12,15 +12,8 @@ end, { priority = "NORMAL", mod_id = "pickaxe-vanilla" })
 pickaxe.events.on("player_leave", function(event)
     local name = event.name or "unknown"
     pickaxe.log("Player left: " .. name)
-    pickaxe.players.broadcast(name .. " left the game")
-end, { priority = "NORMAL", mod_id = "pickaxe-vanilla" })
-
--- Player chat: format and broadcast (cancels default Rust broadcast)
-pickaxe.events.on("player_chat", function(event)
-    local name = event.name or "?"
-    local message = event.message or ""
-    pickaxe.players.broadcast("<" .. name .. "> " .. message)
-    return "cancel"
+    -- The player is already gone, so only server-wide placeholders resolve
+    pickaxe.players.broadcast(pickaxe.placeholders.format(name .. " left the game"))
 end, { priority = "NORMAL", mod_id = "pickaxe-vanilla" })
 
 -- Player command event (logging only)
@@ -38,7 +31,7 @@ pickaxe.events.on("player_respawn", function(event)
     pickaxe.log(event.name .. " respawned")
 end, { priority = "NORMAL", mod_id = "pickaxe-vanilla" })
 
--- Player damage: log (can return "cancel" to prevent damage)
+-- Player damage: log (can set event.amount, or return "cancel" to prevent damage)
 pickaxe.events.on("player_damage", function(event)
     pickaxe.log(event.name .. " took " .. event.amount .. " damage (" .. (event.source or "unknown") .. ")")
 end, { priority = "NORMAL", mod_id = "pickaxe-vanilla" })